@@ -1,6 +1,6 @@
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use rust_qr::utils::memory_pool::BufferPool;
-use rust_qr::{detect, detect_with_pool};
+use rust_qr::{detect, detect_fast_with_pool, detect_with_pool};
 
 fn bench_detect_without_pool(c: &mut Criterion) {
     let image = vec![128u8; 640 * 480 * 3];
@@ -54,11 +54,27 @@ fn bench_detect_pool_large(c: &mut Criterion) {
     });
 }
 
+fn bench_fast_path_with_pool(c: &mut Criterion) {
+    let image = vec![128u8; 640 * 480 * 3];
+    let mut pool = BufferPool::with_capacity(640 * 480);
+    c.bench_function("detect_fast_640x480_with_pool", |b| {
+        b.iter(|| {
+            detect_fast_with_pool(
+                black_box(&image),
+                black_box(640),
+                black_box(480),
+                black_box(&mut pool),
+            )
+        })
+    });
+}
+
 criterion_group!(
     benches,
     bench_detect_without_pool,
     bench_detect_with_pool,
     bench_detect_pool_small,
-    bench_detect_pool_large
+    bench_detect_pool_large,
+    bench_fast_path_with_pool
 );
 criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Smallest program that links the full detect path; `scripts/check_binary_size.sh`
+//! builds it to track the library's footprint.
+
+use std::io::Read;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(width), Some(height)) = (args.next(), args.next()) else {
+        eprintln!("usage: size_probe WIDTH HEIGHT < image.rgb");
+        std::process::exit(2);
+    };
+    let (Ok(width), Ok(height)) = (width.parse::<usize>(), height.parse::<usize>()) else {
+        eprintln!("WIDTH and HEIGHT must be integers");
+        std::process::exit(2);
+    };
+    let mut rgb = Vec::new();
+    if std::io::stdin().read_to_end(&mut rgb).is_err() || rgb.len() != width * height * 3 {
+        eprintln!("expected {} bytes of RGB on stdin", width * height * 3);
+        std::process::exit(2);
+    }
+    for code in rust_qr::detect(&rgb, width, height) {
+        println!("{}", code.content);
+    }
+}
@@ -0,0 +1,108 @@
+/* rust_qr stable C ABI. Symbols are versioned by name (rustqr_v1_*). */
+
+#ifndef RUSTQR_H
+#define RUSTQR_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Version of the C ABI these symbols implement.
+#define RUSTQR_ABI_VERSION 1
+
+// The call succeeded.
+#define RUSTQR_V1_OK 0
+
+// A required pointer argument was null.
+#define RUSTQR_V1_NULL_POINTER 1
+
+// The dimensions, stride or pixel format do not describe a valid image.
+#define RUSTQR_V1_INVALID_IMAGE 2
+
+// Detection failed internally; no result was produced.
+#define RUSTQR_V1_INTERNAL_ERROR 3
+
+// 8-bit grayscale pixels.
+#define RUSTQR_V1_FORMAT_LUMA8 0
+
+// 8-bit RGB pixels.
+#define RUSTQR_V1_FORMAT_RGB8 1
+
+// 8-bit RGBA pixels; alpha is ignored.
+#define RUSTQR_V1_FORMAT_RGBA8 2
+
+// A point in image coordinates.
+typedef struct RustqrV1Point {
+  float x;
+  float y;
+} RustqrV1Point;
+
+// One decoded QR code, owned by the `RustqrV1Result` it belongs to.
+typedef struct RustqrV1Code {
+  // Raw payload bytes, `data_len` long.
+  const uint8_t *data;
+  size_t data_len;
+  // Payload as UTF-8 text, `content_len` bytes plus a terminating NUL.
+  const char *content;
+  size_t content_len;
+  // Outline from the symbol's top-left, clockwise in image space: TL,
+  // TR, BR, BL (TL, BL, BR, TR when mirrored).
+  struct RustqrV1Point corners[4];
+  // Calibrated probability in `[0, 1]` that the payload is correct.
+  float confidence;
+  // Symbol version, 1 to 40.
+  uint8_t version;
+  // Error correction level: 0 = L, 1 = M, 2 = Q, 3 = H.
+  uint8_t ec_level;
+  // Data mask pattern, 0 to 7.
+  uint8_t mask;
+} RustqrV1Code;
+
+// The codes found by one detection call, best first. Free with
+// `rustqr_v1_result_free`.
+typedef struct RustqrV1Result {
+  struct RustqrV1Code *codes;
+  size_t len;
+} RustqrV1Result;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The ABI version of the loaded library, to check against
+// `RUSTQR_ABI_VERSION` at startup.
+uint32_t rustqr_v1_abi_version(void);
+
+// Detect QR codes in an image whose rows are `stride` bytes apart.
+//
+// On `RUSTQR_V1_OK`, `*out` points to a result, possibly with no codes,
+// that the caller frees with `rustqr_v1_result_free`; on any other status
+// `*out` is set to null.
+//
+// # Safety
+// `pixels` must be readable for `stride * (height - 1) + width * bpp`
+// bytes, where `bpp` is the pixel size of `format`, and `out` must be
+// writable.
+int32_t rustqr_v1_detect(const uint8_t *pixels,
+                         size_t width,
+                         size_t height,
+                         size_t stride,
+                         uint32_t format,
+                         struct RustqrV1Result **out);
+
+// Free a result returned by `rustqr_v1_detect`. Null is ignored.
+//
+// # Safety
+// `result` must be null or a pointer from `rustqr_v1_detect` that has
+// not been freed yet.
+void rustqr_v1_result_free(struct RustqrV1Result *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* RUSTQR_H */
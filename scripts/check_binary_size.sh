@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+# Build examples/size_probe with the `minimal` profile and no default features,
+# and fail if it exceeds the documented budget (README "Binary size").
+#
+# Usage: scripts/check_binary_size.sh [BUDGET_KIB]   (default 576)
+set -euo pipefail
+
+budget_kib="${1:-${QR_SIZE_BUDGET_KIB:-576}}"
+cd "$(dirname "$0")/.."
+
+cargo build --quiet --profile minimal --no-default-features --example size_probe
+bin="target/minimal/examples/size_probe"
+[ -f "$bin" ] || bin="$bin.exe"
+
+size=$(wc -c < "$bin" | tr -d ' ')
+size_kib=$(( (size + 1023) / 1024 ))
+echo "size_probe (minimal, no default features): ${size_kib} KiB, budget ${budget_kib} KiB"
+if [ "$size_kib" -gt "$budget_kib" ]; then
+    echo "error: binary exceeds size budget by $(( size_kib - budget_kib )) KiB" >&2
+    exit 1
+fi
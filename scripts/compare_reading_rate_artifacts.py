@@ -53,6 +53,12 @@ def read_fingerprint(data: dict, path: Path) -> str:
     return fingerprint
 
 
+def read_ablations(data: dict) -> list[str]:
+    """Subsystems switched off for the run; older artifacts ran them all."""
+    ablations = data.get("metadata", {}).get("ablations", [])
+    return sorted(a for a in ablations if isinstance(a, str))
+
+
 def per_category_rates(data: dict) -> dict[str, float]:
     out: dict[str, float] = {}
     for entry in data.get("categories", []):
@@ -182,6 +188,13 @@ def main() -> int:
     print(f"Baseline:  {baseline_path}")
     print(f"Candidate: {candidate_path}")
     print(f"Dataset fingerprint: baseline={baseline_fp} candidate={candidate_fp}")
+    baseline_ablations = read_ablations(baseline)
+    candidate_ablations = read_ablations(candidate)
+    if baseline_ablations or candidate_ablations:
+        print(
+            "Ablations: baseline=[" + ", ".join(baseline_ablations) + "] "
+            "candidate=[" + ", ".join(candidate_ablations) + "]"
+        )
     print(
         f"Weighted-global rate: baseline={baseline_rate:.4f}% candidate={candidate_rate:.4f}% "
         f"drop={rate_drop_pp:.4f} pp"
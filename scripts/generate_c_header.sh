@@ -0,0 +1,9 @@
+#!/usr/bin/env bash
+# Regenerate include/rustqr.h from src/ffi.rs (needs `cargo install cbindgen`).
+# The header is checked in; commit the result with the change to src/ffi.rs.
+#
+# Usage: scripts/generate_c_header.sh
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+cbindgen --config cbindgen.toml --output include/rustqr.h src/ffi.rs
@@ -1,13 +1,23 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use rust_qr::decoder::format::FormatInfo;
-use rust_qr::detector::finder::FinderDetector;
+use rust_qr::detector::finder::{FinderDetector, FinderPattern};
 use rust_qr::models::{BitMatrix, Point};
+use rust_qr::snapshot::{Snapshot, SnapshotPolicy, detect_with_snapshot};
+use rust_qr::tools::compat_json::{boofcv_json, zxing_json};
+use rust_qr::tools::failed_grids;
+use rust_qr::tools::smoke::write_smoke_set;
 use rust_qr::tools::{
-    bench_limit_from_env, binarize, binary_stats, dataset_fingerprint, dataset_iter,
-    dataset_root_from_env, detect_qr, grayscale_stats, load_rgb, parse_expected_qr_count,
-    smoke_from_env, to_grayscale,
+    EnvironmentInfo, PerImageRow, bench_limit_from_env, binarize, binary_stats,
+    capture_environment, dataset_fingerprint, dataset_iter, dataset_root_from_env, detect_qr,
+    grayscale_stats, is_svg, load_pages, load_rgb, load_svg, parse_expected_payload,
+    parse_expected_qr_corners, parse_expected_qr_count, payload_matches, smoke_from_env,
+    to_grayscale,
+};
+use rust_qr::utils::geometry::{PerspectiveTransform, point_in_polygon};
+use rust_qr::{
+    Ablation, Binarization, ConfidenceCalibration, DetectorConfig, EncoderQuirk, ImageCondition,
+    PhysicalScale, QRCode, detect_from_grayscale,
 };
-use rust_qr::utils::geometry::PerspectiveTransform;
 use std::collections::BTreeMap;
 use std::fmt::Write as _;
 use std::fs;
@@ -21,13 +31,66 @@ struct Cli {
     command: Command,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DetectFormat {
+    Text,
+    ZxingJson,
+    BoofcvJson,
+}
+
 #[derive(Subcommand)]
 enum Command {
     /// Run QR detection on a single image
+    ///
+    /// Exits 0 when at least one code was read (with `--expect`, one
+    /// carrying that payload), 1 when none was, and 2 when the image could
+    /// not be loaded.
     Detect {
+        /// Image to scan; multi-page TIFFs are scanned page by page, SVGs are
+        /// rasterized at 300 dpi.
+        #[arg(long)]
+        image: PathBuf,
+        /// Write a repro bundle (.rqz) here when nothing decodes.
+        #[arg(long, value_name = "PATH")]
+        snapshot: Option<PathBuf>,
+        /// Accept codes printed without a quiet zone.
+        #[arg(long)]
+        tolerate_missing_quiet_zone: bool,
+        /// Also look for finders with reversed polarity (etched or metallic marks).
+        #[arg(long)]
+        inverted_finders: bool,
+        /// Tolerate every known encoder quirk (see `EncoderQuirk`).
+        #[arg(long)]
+        lenient: bool,
+        /// Smallest module size in pixels; finder scanning skips lines to match.
+        #[arg(long, value_name = "PX")]
+        min_module_size: Option<f32>,
+        /// Working-memory cap; larger frames are scanned downscaled or tiled.
+        #[arg(long, value_name = "BYTES")]
+        max_memory_bytes: Option<usize>,
+        /// Scan resolution, to report each code's printed size in mm.
+        #[arg(long)]
+        dpi: Option<f32>,
+        /// Output layout; the JSON profiles mirror ZXing and BoofCV results.
+        #[arg(long, value_enum, default_value_t = DetectFormat::Text)]
+        format: DetectFormat,
+        /// Print only the payloads, one per line, for shell pipelines.
+        #[arg(long, conflicts_with = "format")]
+        quiet: bool,
+        /// Succeed only if a code carries this payload.
+        #[arg(long, value_name = "PAYLOAD")]
+        expect: Option<String>,
+    },
+    /// List symbols with their version and EC level, without decoding data
+    Inventory {
         #[arg(long)]
         image: PathBuf,
     },
+    /// Re-run a repro bundle written by `detect --snapshot` and diff the stages
+    Replay {
+        /// Bundle path (.rqz)
+        bundle: PathBuf,
+    },
     /// Print grayscale/binary stats and finder patterns for an image
     DebugDetect {
         #[arg(long)]
@@ -54,6 +117,13 @@ enum Command {
         /// Write machine-readable benchmark JSON artifact.
         #[arg(long, value_name = "PATH")]
         artifact_json: Option<PathBuf>,
+        /// Write one JSON object per labeled image (NDJSON) for failure triage.
+        #[arg(long, value_name = "PATH")]
+        per_image_jsonl: Option<PathBuf>,
+        /// Write each sampled grid that failed at error correction or payload
+        /// parsing, in images not fully read, as NDJSON training/debug data.
+        #[arg(long, value_name = "PATH")]
+        failed_grids_jsonl: Option<PathBuf>,
         /// Suppress per-image logs for non-interactive runs (CI/scripts).
         #[arg(long)]
         non_interactive: bool,
@@ -63,6 +133,60 @@ enum Command {
         /// Optional category to run (e.g. lots, rotations, high_version).
         #[arg(long)]
         category: Option<String>,
+        /// Accept codes printed without a quiet zone (pair with --category noncompliant).
+        #[arg(long)]
+        tolerate_missing_quiet_zone: bool,
+        /// Tolerate every known encoder quirk and report which rules rescued
+        /// decodes (pair with --category noncompliant or pathological).
+        #[arg(long)]
+        lenient: bool,
+        /// Recovery subsystems to switch off, comma-separated: two_finder,
+        /// roi_norm, deskew, scale_retry, rs_erasure, contour. The artifact
+        /// records the set, so runs can be diffed for each one's share.
+        #[arg(long, value_delimiter = ',', value_parser = parse_ablation)]
+        ablate: Vec<Ablation>,
+        /// Run only every Nth image of each category, starting at the i-th
+        /// (`i/N`, zero-based), so N machines can split one dataset.
+        #[arg(long, value_name = "i/N", value_parser = parse_shard)]
+        shard: Option<Shard>,
+        /// Checkpoint file: each image's row is appended as it finishes and
+        /// images already in it are credited instead of re-run.
+        #[arg(long, value_name = "PATH")]
+        resume: Option<PathBuf>,
+        /// Credit the rows of another run's checkpoint (repeatable), e.g. to
+        /// aggregate the shards of a split run.
+        #[arg(long, value_name = "PATH")]
+        merge: Vec<PathBuf>,
+    },
+    /// Fit a score-to-probability confidence table on a labeled dataset
+    CalibrateConfidence {
+        /// Dataset root (default: QR_DATASET_ROOT or benches/images/boofcv);
+        /// only images whose label holds the payload, as in
+        /// benches/images/custom/decoding, are counted
+        #[arg(long)]
+        root: Option<PathBuf>,
+        /// Max images (default: QR_BENCH_LIMIT; 0 means all)
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Use smoke subset (default also enabled by QR_SMOKE)
+        #[arg(long)]
+        smoke: bool,
+        /// Number of equal-count score bins to fit.
+        #[arg(long, default_value_t = 12)]
+        bins: usize,
+        /// Write the fitted table here instead of stdout.
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Compare two `reading-rate --per-image-jsonl` logs image by image
+    TelemetryDiff {
+        /// Baseline per-image log.
+        old: PathBuf,
+        /// Candidate per-image log.
+        new: PathBuf,
+        /// Example paths shown per signature transition.
+        #[arg(long, default_value_t = 3)]
+        examples: usize,
     },
     /// Iterate a dataset and run detection once per image
     DatasetBench {
@@ -73,13 +197,50 @@ enum Command {
         #[arg(long)]
         smoke: bool,
     },
+    /// Render an encoder-generated smoke set with BoofCV-format labels
+    GenSmoke {
+        /// Output directory (created if missing)
+        #[arg(long)]
+        out: PathBuf,
+        /// Number of images; 160 covers every version at every EC level.
+        #[arg(long, default_value_t = 160)]
+        count: usize,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Detect { image } => detect_cmd(&image),
+        Command::Detect {
+            image,
+            snapshot,
+            tolerate_missing_quiet_zone,
+            inverted_finders,
+            lenient,
+            min_module_size,
+            max_memory_bytes,
+            dpi,
+            format,
+            quiet,
+            expect,
+        } => {
+            let config = DetectorConfig {
+                tolerate_missing_quiet_zone,
+                inverted_finders,
+                encoder_quirks: lenient_quirks(lenient),
+                min_module_size,
+                max_memory_bytes,
+                physical_scale: dpi.map(|dpi| PhysicalScale::Dpi { x: dpi, y: dpi }),
+                // The JSON profiles report raw and corrected codewords.
+                keep_intermediate_data: format != DetectFormat::Text,
+                ..Default::default()
+            };
+            let codes = config.apply(|| detect_cmd(&image, snapshot.as_deref(), format, quiet));
+            std::process::exit(detect_exit_code(codes.as_deref(), expect.as_deref()));
+        }
+        Command::Inventory { image } => inventory_cmd(&image),
+        Command::Replay { bundle } => replay_cmd(&bundle),
         Command::DebugDetect { image } => debug_detect_cmd(&image),
         Command::DebugDecode { image, points } => debug_decode_cmd(&image, points.as_deref()),
         Command::ReadingRate {
@@ -87,38 +248,333 @@ fn main() {
             limit,
             smoke,
             artifact_json,
+            per_image_jsonl,
+            failed_grids_jsonl,
             non_interactive,
             progress_every,
             category,
+            tolerate_missing_quiet_zone,
+            lenient,
+            ablate,
+            shard,
+            resume,
+            merge,
         } => reading_rate_cmd(
             root,
             limit,
             smoke,
             artifact_json,
+            per_image_jsonl,
+            failed_grids_jsonl.as_deref(),
             non_interactive,
             progress_every,
             category,
+            shard,
+            resume,
+            &merge,
+            DetectorConfig {
+                tolerate_missing_quiet_zone,
+                encoder_quirks: lenient_quirks(lenient),
+                ablations: ablate,
+                export_failed_grids: failed_grids_jsonl.is_some(),
+                ..Default::default()
+            },
         ),
+        Command::CalibrateConfidence {
+            root,
+            limit,
+            smoke,
+            bins,
+            output,
+        } => calibrate_confidence_cmd(root, limit, smoke, bins, output.as_deref()),
+        Command::TelemetryDiff { old, new, examples } => telemetry_diff_cmd(&old, &new, examples),
         Command::DatasetBench { root, limit, smoke } => dataset_bench_cmd(root, limit, smoke),
+        Command::GenSmoke { out, count } => gen_smoke_cmd(&out, count),
     }
 }
 
-fn detect_cmd(image: &Path) {
-    match load_rgb(image) {
-        Ok((pixels, width, height)) => {
-            let results = detect_qr(&pixels, width, height);
-            println!("Image: {} ({}x{})", image.display(), width, height);
-            println!("Found {} QR codes", results.len());
-            for (i, qr) in results.iter().enumerate() {
-                println!(
-                    "  QR {}: version={:?}, error_correction={:?}, mask={:?}, content={}",
-                    i, qr.version, qr.error_correction, qr.mask_pattern, qr.content
-                );
+/// Every [`EncoderQuirk`] rule under `--lenient`, none otherwise.
+fn lenient_quirks(lenient: bool) -> Vec<EncoderQuirk> {
+    if lenient {
+        EncoderQuirk::ALL.to_vec()
+    } else {
+        Vec::new()
+    }
+}
+
+fn parse_ablation(name: &str) -> Result<Ablation, String> {
+    Ablation::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = Ablation::ALL.iter().map(|a| a.name()).collect();
+        format!(
+            "unknown subsystem `{name}`; expected one of {}",
+            names.join(", ")
+        )
+    })
+}
+
+/// One slice of a sharded `reading-rate` run: images `index`, `index + count`,
+/// `index + 2 * count`, ... of each sorted category list.
+#[derive(Clone, Copy, Debug)]
+struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    fn keep(self, images: Vec<PathBuf>) -> Vec<PathBuf> {
+        images
+            .into_iter()
+            .skip(self.index)
+            .step_by(self.count)
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+fn parse_shard(spec: &str) -> Result<Shard, String> {
+    let (index, count) = spec
+        .split_once('/')
+        .ok_or_else(|| format!("expected `i/N`, got `{spec}`"))?;
+    let index: usize = index
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad shard index `{index}`"))?;
+    let count: usize = count
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad shard count `{count}`"))?;
+    if index >= count {
+        return Err(format!("shard index must be below the count, got `{spec}`"));
+    }
+    Ok(Shard { index, count })
+}
+
+/// Exit status of `detect` for the `codes` it read, `None` when the image
+/// failed to load: 0 on a read (of `expect`, when given), 1 otherwise, 2 on
+/// a load error.
+fn detect_exit_code(codes: Option<&[QRCode]>, expect: Option<&str>) -> i32 {
+    let Some(codes) = codes else {
+        return 2;
+    };
+    let found = match expect {
+        Some(expected) => codes
+            .iter()
+            .any(|qr| qr.content == expected || qr.data == expected.as_bytes()),
+        None => !codes.is_empty(),
+    };
+    if found { 0 } else { 1 }
+}
+
+/// Scan `image` and print what was read; returns every page's codes, or
+/// `None` when the image can't be loaded.
+fn detect_cmd(
+    image: &Path,
+    snapshot_path: Option<&Path>,
+    format: DetectFormat,
+    quiet: bool,
+) -> Option<Vec<QRCode>> {
+    if is_svg(image) {
+        return detect_svg_cmd(image, snapshot_path, format, quiet);
+    }
+    let pages = match load_pages(image) {
+        Ok(pages) => pages,
+        Err(err) => {
+            eprintln!("Failed to load image {}: {}", image.display(), err);
+            return None;
+        }
+    };
+    // Single images keep their original output; multi-page files gain a
+    // page header (text) or a per-page wrapper object (JSON).
+    let multi_page = pages.len() > 1;
+    let mut page_json = Vec::new();
+    let mut codes = Vec::new();
+    for page in &pages {
+        let (results, snapshot) = match snapshot_path {
+            Some(_) => detect_with_snapshot(
+                &page.rgb,
+                page.width,
+                page.height,
+                SnapshotPolicy::OnFailure,
+            ),
+            None => (detect_qr(&page.rgb, page.width, page.height), None),
+        };
+        match format {
+            _ if quiet => print_payloads(&results),
+            DetectFormat::Text => {
+                if multi_page {
+                    println!(
+                        "Image: {} page {} ({}x{})",
+                        image.display(),
+                        page.index,
+                        page.width,
+                        page.height
+                    );
+                } else {
+                    println!(
+                        "Image: {} ({}x{})",
+                        image.display(),
+                        page.width,
+                        page.height
+                    );
+                }
+                print_detect_results(&results);
             }
+            DetectFormat::ZxingJson => page_json.push(zxing_json(&results)),
+            DetectFormat::BoofcvJson => page_json.push(boofcv_json(&results)),
+        }
+        if let (Some(path), Some(snapshot)) = (snapshot_path, snapshot) {
+            let path = if multi_page {
+                path.with_extension(format!("p{}.rqz", page.index))
+            } else {
+                path.to_path_buf()
+            };
+            write_snapshot(&snapshot, &path);
+        }
+        codes.extend(results);
+    }
+    if !multi_page {
+        if let Some(json) = page_json.pop() {
+            println!("{json}");
+        }
+    } else if !page_json.is_empty() {
+        let wrapped: Vec<String> = page_json
+            .iter()
+            .enumerate()
+            .map(|(index, json)| format!("{{\"page\": {index}, \"results\": {json}}}"))
+            .collect();
+        println!("[{}]", wrapped.join(",\n"));
+    }
+    Some(codes)
+}
+
+/// `detect` for SVG input: rasterized to black-and-white luma at `SVG_DPI`
+/// and scanned as grayscale, skipping the RGB round trip.
+fn detect_svg_cmd(
+    image: &Path,
+    snapshot_path: Option<&Path>,
+    format: DetectFormat,
+    quiet: bool,
+) -> Option<Vec<QRCode>> {
+    let (gray, width, height) = match load_svg(image) {
+        Ok(raster) => raster,
+        Err(err) => {
+            eprintln!("Failed to load image {}: {}", image.display(), err);
+            return None;
+        }
+    };
+    let results = detect_from_grayscale(&gray, width, height);
+    match format {
+        _ if quiet => print_payloads(&results),
+        DetectFormat::Text => {
+            println!("Image: {} ({}x{})", image.display(), width, height);
+            print_detect_results(&results);
+        }
+        DetectFormat::ZxingJson => println!("{}", zxing_json(&results)),
+        DetectFormat::BoofcvJson => println!("{}", boofcv_json(&results)),
+    }
+    if let Some(path) = snapshot_path
+        && results.is_empty()
+    {
+        write_snapshot(&Snapshot::capture(&gray, width, height), path);
+    }
+    Some(results)
+}
+
+/// `detect --quiet` output: each payload on its own line.
+fn print_payloads(results: &[QRCode]) {
+    for qr in results {
+        println!("{}", qr.content);
+    }
+}
+
+fn print_detect_results(results: &[QRCode]) {
+    println!("Found {} QR codes", results.len());
+    for (i, qr) in results.iter().enumerate() {
+        println!(
+            "  QR {}: version={:?}, error_correction={:?}, mask={:?}, content={}",
+            i, qr.version, qr.error_correction, qr.mask_pattern, qr.content
+        );
+        if let Some(size) = qr.physical_size_mm {
+            println!("        size={size:.1}mm");
+        }
+    }
+}
+
+fn write_snapshot(snapshot: &Snapshot, path: &Path) {
+    match snapshot.write_to(path) {
+        // Keep stdout parseable in the JSON profiles.
+        Ok(()) => eprintln!("Wrote repro bundle to {}", path.display()),
+        Err(err) => eprintln!("Failed to write bundle {}: {}", path.display(), err),
+    }
+}
+
+fn replay_cmd(bundle: &Path) {
+    let snapshot = match Snapshot::read_from(bundle) {
+        Ok(snapshot) => snapshot,
+        Err(err) => {
+            eprintln!("Failed to read bundle {}: {}", bundle.display(), err);
+            std::process::exit(2);
         }
+    };
+    let (x0, y0, x1, y1) = snapshot.roi;
+    println!(
+        "Bundle: {} (image {}x{}, roi {},{}..{},{})",
+        bundle.display(),
+        snapshot.image_width,
+        snapshot.image_height,
+        x0,
+        y0,
+        x1,
+        y1
+    );
+    for b in &snapshot.binarizations {
+        println!(
+            "  binarization {}: {} finder patterns",
+            b.policy, b.finder_patterns
+        );
+    }
+    println!(
+        "  candidates: {}, sampled grids: {}, recorded results: {:?}",
+        snapshot.candidates.len(),
+        snapshot.sampled_grids.len(),
+        snapshot.results
+    );
+    let report = snapshot.replay();
+    println!(
+        "Replay: binarization mismatches={}, candidate mismatches={}, grid mismatches={}, results match={}",
+        report.binarization_mismatches,
+        report.candidate_mismatches,
+        report.grid_mismatches,
+        report.results_match
+    );
+    println!("Replay results: {:?}", report.results);
+    if !report.is_deterministic() {
+        std::process::exit(1);
+    }
+}
+
+fn inventory_cmd(image: &Path) {
+    let (pixels, width, height) = match load_rgb(image) {
+        Ok(result) => result,
         Err(err) => {
             eprintln!("Failed to load image {}: {}", image.display(), err);
+            return;
         }
+    };
+    let symbols = rust_qr::scan_metadata_only(&pixels, width, height);
+    println!("Image: {} ({}x{})", image.display(), width, height);
+    println!("Found {} symbols", symbols.len());
+    for (i, symbol) in symbols.iter().enumerate() {
+        let [tl, ..] = symbol.position;
+        println!(
+            "  Symbol {}: version={:?}, error_correction={:?}, mask={:?}, at=({:.0},{:.0})",
+            i, symbol.version, symbol.ec_level, symbol.mask_pattern, tl.x, tl.y
+        );
     }
 }
 
@@ -152,14 +608,25 @@ fn debug_detect_cmd(image: &Path) {
     let patterns = FinderDetector::detect(&binary);
     println!("Found {} finder patterns", patterns.len());
     for (i, pattern) in patterns.iter().take(10).enumerate() {
+        let sharpness = pattern
+            .edge_sharpness(&gray, width, height)
+            .map_or_else(|| "n/a".to_string(), |s| format!("{s:.2}"));
         println!(
-            "  Pattern {}: center=({:.1}, {:.1}) module_size={:.2}",
-            i, pattern.center.x, pattern.center.y, pattern.module_size
+            "  Pattern {}: center=({:.1}, {:.1}) module_size={:.2} quality={:.2} sharpness={}",
+            i, pattern.center.x, pattern.center.y, pattern.module_size, pattern.quality, sharpness
         );
     }
 
-    let results = detect_qr(&pixels, width, height);
+    let (results, tel) = rust_qr::detect_with_telemetry(&pixels, width, height);
     println!("Full detection found {} QR codes", results.len());
+    for (i, group) in tel.decoded_finders.iter().enumerate() {
+        let qualities: Vec<String> = group.iter().map(|f| format!("{:.2}", f.quality)).collect();
+        println!(
+            "  Decoded group {}: finder quality TL/TR/BL = {}",
+            i,
+            qualities.join("/")
+        );
+    }
 }
 
 fn debug_decode_cmd(image: &Path, points: Option<&Path>) {
@@ -301,14 +768,21 @@ fn decode_from_points(binary: &BitMatrix, points: &[Point]) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn reading_rate_cmd(
     root: Option<PathBuf>,
     limit: Option<usize>,
     smoke: bool,
     artifact_json: Option<PathBuf>,
+    per_image_jsonl: Option<PathBuf>,
+    failed_grids_jsonl: Option<&Path>,
     non_interactive: bool,
     progress_every: usize,
     category: Option<String>,
+    shard: Option<Shard>,
+    resume: Option<PathBuf>,
+    merge: &[PathBuf],
+    config: DetectorConfig,
 ) {
     let root = root.unwrap_or_else(dataset_root_from_env);
     let limit = limit.or_else(bench_limit_from_env);
@@ -319,6 +793,46 @@ fn reading_rate_cmd(
         return;
     }
 
+    let mut resumed = ResumedRows {
+        root: root.clone(),
+        rows: BTreeMap::new(),
+    };
+    if let Some(path) = resume.as_ref().filter(|path| path.exists()) {
+        resumed.rows = load_checkpoint_rows(path);
+    }
+    for path in merge {
+        resumed.rows.extend(load_per_image_rows(path));
+    }
+    let mut checkpoint = resume.as_ref().map(|path| {
+        open_checkpoint(path, &root, resumed.rows.values()).unwrap_or_else(|err| {
+            eprintln!("Failed to write checkpoint {}: {}", path.display(), err);
+            std::process::exit(2);
+        })
+    });
+
+    let mut per_image = per_image_jsonl
+        .as_ref()
+        .and_then(|path| match fs::File::create(path) {
+            Ok(file) => Some(PerImageLog {
+                root: root.clone(),
+                out: std::io::BufWriter::new(file),
+            }),
+            Err(err) => {
+                eprintln!("Failed to create {}: {}", path.display(), err);
+                None
+            }
+        });
+    let mut failed_grids = failed_grids_jsonl.and_then(|path| match fs::File::create(path) {
+        Ok(file) => Some(FailedGridLog {
+            root: root.clone(),
+            out: std::io::BufWriter::new(file),
+        }),
+        Err(err) => {
+            eprintln!("Failed to create {}: {}", path.display(), err);
+            None
+        }
+    });
+
     let smoke_images: Option<Vec<PathBuf>> = if smoke {
         Some(dataset_iter(&root, None, true).collect())
     } else {
@@ -354,6 +868,7 @@ fn reading_rate_cmd(
     let datetime = utc_timestamp();
     let commit_sha = commit_sha();
     let data_fingerprint = dataset_fingerprint(&root);
+    let environment = capture_environment();
 
     println!("RustQR QR Code Reading Rate Benchmark");
     println!("=====================================");
@@ -361,11 +876,28 @@ fn reading_rate_cmd(
     println!("Commit:  {}", commit_sha);
     println!("Dataset: {}", root.display());
     println!("Data FP: {}", data_fingerprint);
+    println!(
+        "Host:    {} ({}/{}, {} cpus, {} threads)",
+        environment.cpu_model,
+        environment.os,
+        environment.arch,
+        environment.logical_cpus,
+        environment.worker_threads
+    );
+    println!("Feature: {}", environment.features.join(","));
     if let Some(l) = limit {
         println!("Limit:   {} images per category", l);
     } else {
         println!("Limit:   full dataset");
     }
+    let ablations: Vec<String> = config
+        .ablations
+        .iter()
+        .map(|a| a.name().to_string())
+        .collect();
+    if !ablations.is_empty() {
+        println!("Ablated: {}", ablations.join(", "));
+    }
     if smoke {
         println!("Mode:    smoke test");
     }
@@ -375,11 +907,25 @@ fn reading_rate_cmd(
     if let Some(c) = &category {
         println!("Category filter: {}", c);
     }
+    if let Some(shard) = shard {
+        println!("Shard:   {}", shard);
+    }
+    if !resumed.rows.is_empty() {
+        println!("Resume:  {} images already recorded", resumed.rows.len());
+    }
+    if config.tolerate_missing_quiet_zone {
+        println!("Quiet zone: optional (tolerate_missing_quiet_zone)");
+    }
+    if !config.encoder_quirks.is_empty() {
+        let names: Vec<&str> = config.encoder_quirks.iter().map(|q| q.name()).collect();
+        println!("Encoder quirks: {}", names.join(", "));
+    }
     println!("=====================================\n");
 
     let mut global_hits = 0usize;
     let mut global_expected = 0usize;
     let mut global_images_with_labels = 0usize;
+    let mut global_resumed = 0usize;
     let mut global_runtime_samples_ms: Vec<f64> = Vec::new();
     let mut global_stage_telemetry = StageTelemetry::default();
     let mut global_failure_clusters: BTreeMap<String, FailureCluster> = BTreeMap::new();
@@ -418,11 +964,25 @@ fn reading_rate_cmd(
         } else {
             dataset_iter(&category_root, limit, false).collect()
         };
+        let images = match shard {
+            Some(shard) => shard.keep(images),
+            None => images,
+        };
         if images.is_empty() {
             println!("  {}: no images found\n", dir);
             continue;
         }
-        let stats = reading_rate_for_images(images.into_iter(), non_interactive, progress_every);
+        let stats = config.apply(|| {
+            reading_rate_for_images(
+                images.into_iter(),
+                non_interactive,
+                progress_every,
+                per_image.as_mut(),
+                checkpoint.as_mut(),
+                failed_grids.as_mut(),
+                &resumed,
+            )
+        });
         if stats.total_expected == 0 {
             println!("  {}: no labeled images found\n", dir);
             continue;
@@ -435,6 +995,7 @@ fn reading_rate_cmd(
         global_hits += stats.hits;
         global_expected += stats.total_expected;
         global_images_with_labels += stats.images_with_labels;
+        global_resumed += stats.resumed;
         global_runtime_samples_ms.extend(stats.runtime_samples_ms.iter().copied());
         global_stage_telemetry.accumulate(stats.stage_telemetry);
         for (sig, cluster) in stats.failure_clusters {
@@ -492,6 +1053,12 @@ fn reading_rate_cmd(
             "{:<16} {:>6} {:>6} {:>7.2}%",
             "TOTAL", global_hits, global_expected, global_rate,
         );
+        if global_resumed > 0 {
+            println!(
+                "Resumed: {} images credited from checkpoints (stage telemetry covers the rest)",
+                global_resumed
+            );
+        }
         println!(
             "Runtime median: {:.2} ms/image (mean {:.2} ms, n={})",
             global_runtime.median_per_image_ms,
@@ -518,12 +1085,11 @@ fn reading_rate_cmd(
             "AcceptRj"
         );
         println!("{}", "-".repeat(104));
-        let mut g_decode_attempts = 0usize;
         let mut g_score_buckets = [0usize; 4];
         for category in &category_results {
             let tel = category.stage_telemetry;
             let avg_attempts = if tel.total > 0 {
-                tel.total_decode_attempts as f64 / tel.total as f64
+                tel.stages.decode.attempts as f64 / tel.total as f64
             } else {
                 0.0
             };
@@ -531,24 +1097,24 @@ fn reading_rate_cmd(
                 "{:<16} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8} {:>9.2} {:>8} {:>8} {:>8}",
                 category.name,
                 tel.total,
-                tel.binarize_ok,
-                tel.finder_ok,
-                tel.groups_ok,
-                tel.transform_ok,
+                tel.stages.binarize.successes,
+                tel.stages.finder_scan.successes,
+                tel.stages.grouping.successes,
+                tel.stages.transform.successes,
                 tel.decode_ok,
                 avg_attempts,
                 tel.over_budget_skip,
                 tel.router_multi_region,
                 tel.acceptance_rejected,
             );
-            g_decode_attempts += tel.total_decode_attempts;
             for (i, bucket) in g_score_buckets.iter_mut().enumerate() {
                 *bucket += tel.candidate_score_buckets[i];
             }
         }
         println!("{}", "-".repeat(104));
         let g_avg_attempts = if global_stage_telemetry.total > 0 {
-            g_decode_attempts as f64 / global_stage_telemetry.total as f64
+            global_stage_telemetry.stages.decode.attempts as f64
+                / global_stage_telemetry.total as f64
         } else {
             0.0
         };
@@ -556,55 +1122,194 @@ fn reading_rate_cmd(
             "{:<16} {:>6} {:>8} {:>8} {:>8} {:>8} {:>8} {:>9.2} {:>8} {:>8} {:>8}",
             "TOTAL",
             global_stage_telemetry.total,
-            global_stage_telemetry.binarize_ok,
-            global_stage_telemetry.finder_ok,
-            global_stage_telemetry.groups_ok,
-            global_stage_telemetry.transform_ok,
+            global_stage_telemetry.stages.binarize.successes,
+            global_stage_telemetry.stages.finder_scan.successes,
+            global_stage_telemetry.stages.grouping.successes,
+            global_stage_telemetry.stages.transform.successes,
             global_stage_telemetry.decode_ok,
             g_avg_attempts,
             global_stage_telemetry.over_budget_skip,
             global_stage_telemetry.router_multi_region,
             global_stage_telemetry.acceptance_rejected,
         );
+        println!();
+        print!("{}", global_stage_telemetry.stages.summary());
+        println!();
+
+        // Misses are banded by the version estimated from their label.
+        println!("Version Bands (labeled codes hit/missed)");
+        let band_header: Vec<String> = VERSION_BANDS
+            .iter()
+            .map(|(lo, hi)| format!("{:>9}", format!("v{lo}-{hi}")))
+            .collect();
         println!(
-            "Deskew attempts/successes: {}/{} | High-version precision attempts: {} | Recovery mode attempts: {}",
-            global_stage_telemetry.deskew_attempts,
-            global_stage_telemetry.deskew_successes,
-            global_stage_telemetry.high_version_precision_attempts,
-            global_stage_telemetry.recovery_mode_attempts
-        );
+            "{:<16} {} {:>8}",
+            "Category",
+            band_header.join(" "),
+            "Unknown"
+        );
+        let band_row = |name: &str, tel: &StageTelemetry| {
+            let bands: Vec<String> = (0..VERSION_BANDS.len())
+                .map(|i| {
+                    format!(
+                        "{:>9}",
+                        format!(
+                            "{}/{}",
+                            tel.version_band_hits[i], tel.version_band_misses[i]
+                        )
+                    )
+                })
+                .collect();
+            println!(
+                "{:<16} {} {:>8}",
+                name,
+                bands.join(" "),
+                tel.version_misses_unknown
+            );
+        };
+        for category in &category_results {
+            band_row(category.name, &category.stage_telemetry);
+        }
+        band_row("TOTAL", &global_stage_telemetry);
+        println!();
         println!(
-            "Scale retries attempts/successes/skipped: {}/{}/{}",
-            global_stage_telemetry.scale_retry_attempts,
-            global_stage_telemetry.scale_retry_successes,
-            global_stage_telemetry.scale_retry_skipped_by_budget
+            "High-version precision attempts: {} | Recovery mode attempts: {} | HV subpixel attempts: {}",
+            global_stage_telemetry.high_version_precision_attempts,
+            global_stage_telemetry.recovery_mode_attempts,
+            global_stage_telemetry.hv_subpixel_attempts
         );
         println!(
-            "High-version subpixel/refine attempts/successes: {}/{}/{}",
-            global_stage_telemetry.hv_subpixel_attempts,
-            global_stage_telemetry.hv_refine_attempts,
-            global_stage_telemetry.hv_refine_successes
+            "Skipped scale retries/ROI norms: {}/{} | Grid refine iterations/residual drop: {}/{:.1}px",
+            global_stage_telemetry.scale_retry_skipped_by_budget,
+            global_stage_telemetry.roi_norm_skipped,
+            global_stage_telemetry.grid_refine_iterations,
+            global_stage_telemetry.grid_refine_residual_drop_px
         );
         println!(
-            "RS erasure attempts/successes: {}/{} | hist[1,2-3,4-6,7+]=[{},{},{},{}]",
-            global_stage_telemetry.rs_erasure_attempts,
-            global_stage_telemetry.rs_erasure_successes,
+            "RS erasure count hist[1,2-3,4-6,7+]=[{},{},{},{}]",
             global_stage_telemetry.rs_erasure_count_hist[0],
             global_stage_telemetry.rs_erasure_count_hist[1],
             global_stage_telemetry.rs_erasure_count_hist[2],
             global_stage_telemetry.rs_erasure_count_hist[3]
         );
+        println!(
+            "High-density version pins/residual rejects: {}/{} | RS block load hist[<25%,<50%,<75%,<=100%]=[{},{},{},{}] failed={}",
+            global_stage_telemetry.high_density_version_pins,
+            global_stage_telemetry.high_density_residual_rejects,
+            global_stage_telemetry.rs_block_load_hist[0],
+            global_stage_telemetry.rs_block_load_hist[1],
+            global_stage_telemetry.rs_block_load_hist[2],
+            global_stage_telemetry.rs_block_load_hist[3],
+            global_stage_telemetry.rs_blocks_failed
+        );
         println!(
             "Phase11 time-budget skips: {}",
             global_stage_telemetry.phase11_time_budget_skips
         );
+        if !config.encoder_quirks.is_empty() {
+            let rescues: Vec<String> = EncoderQuirk::ALL
+                .iter()
+                .zip(global_stage_telemetry.quirk_rescues)
+                .map(|(q, n)| format!("{}={n}", q.name()))
+                .collect();
+            println!("Quirk rescues: {}", rescues.join(" "));
+        }
+        println!(
+            "Column scans skipped: {}",
+            global_stage_telemetry.column_scans_skipped
+        );
+        println!(
+            "Decode memo hits: {}",
+            global_stage_telemetry.decode_memo_hits
+        );
+        println!(
+            "Stream decode attempts: {}",
+            global_stage_telemetry.stream_decode_attempts
+        );
+        println!(
+            "Pathology guard trips: {}",
+            global_stage_telemetry.pathology_guard_trips
+        );
         let router_div = global_stage_telemetry.total.max(1) as f64;
         println!(
-            "Router fast signals avg blur/sat/skew/density: {:.2}/{:.3}/{:.2}/{:.2}",
+            "Router fast signals avg blur/sat/skew/density/flatness/span: {:.2}/{:.3}/{:.2}/{:.2}/{:.3}/{:.1}",
             global_stage_telemetry.router_blur_metric_sum / router_div,
             global_stage_telemetry.router_saturation_ratio_sum / router_div,
             global_stage_telemetry.router_skew_estimate_deg_sum / router_div,
-            global_stage_telemetry.router_region_density_proxy_sum / router_div
+            global_stage_telemetry.router_region_density_proxy_sum / router_div,
+            global_stage_telemetry.router_flatness_sum / router_div,
+            global_stage_telemetry.router_contrast_span_sum / router_div
+        );
+        println!(
+            "Router top-group finder SNR avg: {:.2} | low-SNR attempts sent to the low lane: {}",
+            global_stage_telemetry.router_top_snr_sum / router_div,
+            global_stage_telemetry.budget_lane_low_snr
+        );
+        println!(
+            "Router multi-scale images (2+ module-size modes): {}",
+            global_stage_telemetry.router_multi_scale_images
+        );
+        // Auto-category labels per dataset category; a category sharing a
+        // condition's name also shows how many of its images got that label.
+        println!("Auto-category (fast-signal condition per image):");
+        for category in &category_results {
+            let counts = category.stage_telemetry.image_conditions;
+            let labels: Vec<String> = ImageCondition::ALL
+                .iter()
+                .zip(counts)
+                .filter(|&(_, n)| n > 0)
+                .map(|(c, n)| format!("{}={n}", c.name()))
+                .collect();
+            let agreement = ImageCondition::from_name(category.name)
+                .map(|c| {
+                    format!(
+                        " | agree {}/{}",
+                        counts[c.index()],
+                        category.stage_telemetry.total
+                    )
+                })
+                .unwrap_or_default();
+            println!("  {:<16} {}{}", category.name, labels.join(" "), agreement);
+        }
+        println!(
+            "Synthetic snapped decodes: {}",
+            global_stage_telemetry.synthetic_snap_decodes
+        );
+        println!(
+            "Format brute-force guesses skipped by header pre-filter: {}",
+            global_stage_telemetry.header_prefilter_skips
+        );
+        println!(
+            "Rotated format rescues 90/180/270: {}/{}/{}",
+            global_stage_telemetry.rotated_format_rescues[0],
+            global_stage_telemetry.rotated_format_rescues[1],
+            global_stage_telemetry.rotated_format_rescues[2]
+        );
+        println!(
+            "Wrong format mask rescues: {}",
+            global_stage_telemetry.wrong_format_mask_rescues
+        );
+        let by_binarization: Vec<String> = Binarization::ALL
+            .iter()
+            .filter(|b| global_stage_telemetry.binarization_decodes[b.index()] > 0)
+            .map(|b| {
+                format!(
+                    "{} {}",
+                    b.name(),
+                    global_stage_telemetry.binarization_decodes[b.index()]
+                )
+            })
+            .collect();
+        println!("Decodes by binarization: {}", by_binarization.join(", "));
+        println!(
+            "Finder sampling: {} images | kept {} of {} candidates",
+            global_stage_telemetry.finder_sampled_images,
+            global_stage_telemetry.finder_sample_kept,
+            global_stage_telemetry.finder_sample_input
+        );
+        println!(
+            "Failure-run queue reorders: {} | decodes from promoted candidates: {}",
+            global_stage_telemetry.failure_reorders, global_stage_telemetry.failure_reorder_decodes
         );
         println!(
             "Budget lanes H/M/L attempts: {}/{}/{} | Fallback transitions O->A31: {} A31->A21: {} | Fallback successes: {}",
@@ -615,19 +1320,41 @@ fn reading_rate_cmd(
             global_stage_telemetry.bin_fallback_adaptive31_to_adaptive21,
             global_stage_telemetry.bin_fallback_successes
         );
-        let rerank_top1_rate = if global_stage_telemetry.rerank_top1_attempts > 0 {
-            (global_stage_telemetry.rerank_top1_successes as f64
-                / global_stage_telemetry.rerank_top1_attempts as f64)
-                * 100.0
-        } else {
-            0.0
+        let lanes = [
+            global_stage_telemetry.budget_lane_high,
+            global_stage_telemetry.budget_lane_medium,
+            global_stage_telemetry.budget_lane_low,
+        ];
+        println!(
+            "Budget used/allocated attempts: {}/{} transforms: {}/{} lanes H/M/L: {}/{} {}/{} {}/{}",
+            global_stage_telemetry.stages.decode.attempts,
+            global_stage_telemetry.budget_attempts_allocated,
+            global_stage_telemetry.budget_transforms_used,
+            global_stage_telemetry.budget_transforms_allocated,
+            lanes[0],
+            global_stage_telemetry.budget_lane_allocation[0],
+            lanes[1],
+            global_stage_telemetry.budget_lane_allocation[1],
+            lanes[2],
+            global_stage_telemetry.budget_lane_allocation[2]
+        );
+        let policy = |i: usize| {
+            format!(
+                "{}/{}",
+                global_stage_telemetry.bin_policy_attempts[i],
+                global_stage_telemetry.bin_policy_allowance[i]
+            )
         };
         println!(
-            "Rerank enabled(images): {} | Top1 success: {}/{} ({:.2}%) | Transform rejects: {}",
+            "Policy attempts used/allowed otsu: {} adaptive31: {} adaptive21: {} hybrid: {}",
+            policy(0),
+            policy(1),
+            policy(2),
+            policy(3)
+        );
+        println!(
+            "Rerank enabled(images): {} | Transform rejects: {}",
             global_stage_telemetry.rerank_enabled,
-            global_stage_telemetry.rerank_top1_successes,
-            global_stage_telemetry.rerank_top1_attempts,
-            rerank_top1_rate,
             global_stage_telemetry.rerank_transform_reject_count
         );
         let saturation_coverage_avg = if global_stage_telemetry.total > 0 {
@@ -642,12 +1369,6 @@ fn reading_rate_cmd(
             saturation_coverage_avg,
             global_stage_telemetry.saturation_mask_decode_successes
         );
-        println!(
-            "ROI norm attempts/successes/skipped: {}/{}/{}",
-            global_stage_telemetry.roi_norm_attempts,
-            global_stage_telemetry.roi_norm_successes,
-            global_stage_telemetry.roi_norm_skipped
-        );
         println!(
             "Attempts/image histogram [0, 1, 2-3, 4-7, 8+]: [{}, {}, {}, {}, {}]",
             global_stage_telemetry.attempts_used_histogram[0],
@@ -681,6 +1402,13 @@ fn reading_rate_cmd(
         }
         println!("=====================================");
 
+        if let Some(path) = per_image_jsonl.filter(|_| per_image.is_some()) {
+            println!("Per-image rows: {}", path.display());
+        }
+
+        if let Some(path) = failed_grids_jsonl.filter(|_| failed_grids.is_some()) {
+            println!("Failed grids: {}", path.display());
+        }
         if let Some(path) = artifact_json {
             let mut failure_rows: Vec<FailureClusterRow> = global_failure_clusters
                 .into_iter()
@@ -700,11 +1428,15 @@ fn reading_rate_cmd(
             let artifact = ReadingRateArtifact {
                 dataset_root: root.display().to_string(),
                 dataset_fingerprint: data_fingerprint,
+                environment,
                 commit_sha,
                 timestamp_utc: datetime,
                 limit_per_category: limit,
                 smoke,
                 non_interactive,
+                ablations,
+                shard: shard.map(|shard| shard.to_string()),
+                resumed_images: global_resumed,
                 weighted_global_rate_percent: global_rate,
                 total_hits: global_hits,
                 total_expected: global_expected,
@@ -732,11 +1464,31 @@ fn reading_rate_cmd(
     } else {
         dataset_iter(&root, limit, false).collect()
     };
+    let images = match shard {
+        Some(shard) => shard.keep(images),
+        None => images,
+    };
     if images.is_empty() {
         println!("No images found under {}", root.display());
         return;
     }
-    let stats = reading_rate_for_images(images.into_iter(), non_interactive, progress_every);
+    let stats = config.apply(|| {
+        reading_rate_for_images(
+            images.into_iter(),
+            non_interactive,
+            progress_every,
+            per_image.as_mut(),
+            checkpoint.as_mut(),
+            failed_grids.as_mut(),
+            &resumed,
+        )
+    });
+    if let Some(path) = per_image_jsonl.filter(|_| per_image.is_some()) {
+        println!("Per-image rows: {}", path.display());
+    }
+    if let Some(path) = failed_grids_jsonl.filter(|_| failed_grids.is_some()) {
+        println!("Failed grids: {}", path.display());
+    }
     if stats.total_expected == 0 {
         println!("No labeled images found under {}", root.display());
         return;
@@ -751,11 +1503,15 @@ fn reading_rate_cmd(
         let artifact = ReadingRateArtifact {
             dataset_root: root.display().to_string(),
             dataset_fingerprint: data_fingerprint,
+            environment,
             commit_sha,
             timestamp_utc: datetime,
             limit_per_category: limit,
             smoke,
             non_interactive,
+            ablations,
+            shard: shard.map(|shard| shard.to_string()),
+            resumed_images: stats.resumed,
             weighted_global_rate_percent: rate,
             total_hits: stats.hits,
             total_expected: stats.total_expected,
@@ -777,6 +1533,8 @@ struct ReadingRateStats {
     total_expected: usize,
     /// Number of images that had a label file.
     images_with_labels: usize,
+    /// Labeled images credited from a checkpoint rather than run.
+    resumed: usize,
     /// Aggregated per-stage telemetry across all images.
     stage_telemetry: StageTelemetry,
     /// Runtime samples for successfully loaded images.
@@ -785,21 +1543,40 @@ struct ReadingRateStats {
     failure_clusters: BTreeMap<String, FailureCluster>,
 }
 
+impl ReadingRateStats {
+    /// Count a missed image under its failure signature.
+    fn record_failure(&mut self, signature: &str, expected: usize, path: &Path) {
+        let row = self
+            .failure_clusters
+            .entry(signature.to_string())
+            .or_insert(FailureCluster {
+                count: 0,
+                qr_weight: 0,
+                examples: Vec::new(),
+            });
+        row.count += 1;
+        row.qr_weight += expected;
+        if row.examples.len() < 3 {
+            row.examples.push(path.display().to_string());
+        }
+    }
+}
+
+/// Decoded codes per version number, 1 to 40.
+#[derive(Clone, Copy)]
+struct VersionHistogram([usize; 40]);
+
+impl Default for VersionHistogram {
+    fn default() -> Self {
+        Self([0; 40])
+    }
+}
+
 /// Aggregated pipeline-stage failure counts across a set of images.
 #[derive(Default, Clone, Copy)]
 struct StageTelemetry {
-    /// Images where binarization succeeded.
-    binarize_ok: usize,
-    /// Images where >= 3 finder patterns were found.
-    finder_ok: usize,
-    /// Images where >= 1 valid group was formed.
-    groups_ok: usize,
-    /// Images where >= 1 perspective transform was built.
-    transform_ok: usize,
     /// Images where >= 1 QR code was decoded.
     decode_ok: usize,
-    /// Sum of decode attempts across images.
-    total_decode_attempts: usize,
     /// Histogram of candidate group scores:
     /// [<2.0, 2.0-<3.0, 3.0-<5.0, >=5.0]
     candidate_score_buckets: [usize; 4],
@@ -811,18 +1588,28 @@ struct StageTelemetry {
     budget_lane_medium: usize,
     /// Decode attempts routed through low-confidence lane.
     budget_lane_low: usize,
+    /// Low-lane attempts sent there for noisy finder rings.
+    budget_lane_low_snr: usize,
+    /// Decode attempts the ranked-group budget allowed.
+    budget_attempts_allocated: usize,
+    /// Perspective transforms the ranked-group budget allowed.
+    budget_transforms_allocated: usize,
+    /// Perspective transforms built against that allowance.
+    budget_transforms_used: usize,
+    /// Decode attempts allowed per lane [high, medium, low].
+    budget_lane_allocation: [usize; 3],
     /// Binarization fallback transition count: Otsu -> adaptive(31).
     bin_fallback_otsu_to_adaptive31: usize,
     /// Binarization fallback transition count: adaptive(31) -> adaptive(21).
     bin_fallback_adaptive31_to_adaptive21: usize,
     /// Successful decodes achieved on fallback binarization path.
     bin_fallback_successes: usize,
+    /// Decode attempts allowed per binarization policy [otsu, adaptive(31), adaptive(21)].
+    bin_policy_allowance: [usize; 4],
+    /// Decode attempts consumed per binarization policy, same order.
+    bin_policy_attempts: [usize; 4],
     /// Images where reranking was enabled.
     rerank_enabled: usize,
-    /// Number of top-1 rerank attempts.
-    rerank_top1_attempts: usize,
-    /// Number of successful top-1 rerank decodes.
-    rerank_top1_successes: usize,
     /// Number of rerank candidate transform rejects.
     rerank_transform_reject_count: usize,
     /// Images where saturation-aware scoring was enabled.
@@ -831,10 +1618,6 @@ struct StageTelemetry {
     saturation_mask_coverage_sum: f64,
     /// Successful decodes influenced by saturation-aware scoring.
     saturation_mask_decode_successes: usize,
-    /// ROI-local normalization attempts.
-    roi_norm_attempts: usize,
-    /// Successful decodes from ROI-local normalization.
-    roi_norm_successes: usize,
     /// ROI-local normalization skips.
     roi_norm_skipped: usize,
     /// Images where 2-finder fallback was used.
@@ -849,51 +1632,133 @@ struct StageTelemetry {
     router_skew_estimate_deg_sum: f64,
     /// Sum of router region density proxies.
     router_region_density_proxy_sum: f64,
+    /// Sum of router flatness signals.
+    router_flatness_sum: f64,
+    /// Sum of router contrast spans.
+    router_contrast_span_sum: f64,
+    /// Sum of top-group finder ring signal-to-noise ratios.
+    router_top_snr_sum: f64,
+    /// Images whose finder candidates cluster at two or more module sizes.
+    router_multi_scale_images: usize,
+    /// Images labelled with each condition, in `ImageCondition::ALL` order.
+    image_conditions: [usize; ImageCondition::ALL.len()],
     /// Total acceptance-based rejections.
     acceptance_rejected: usize,
-    /// Total deskew attempts.
-    deskew_attempts: usize,
-    /// Total deskew successes.
-    deskew_successes: usize,
     /// Total high-version precision attempts.
     high_version_precision_attempts: usize,
     /// Total recovery-mode attempts.
     recovery_mode_attempts: usize,
-    /// Total multi-scale retry attempts.
-    scale_retry_attempts: usize,
-    /// Total successful multi-scale retries.
-    scale_retry_successes: usize,
     /// Total multi-scale retries skipped by budget/guardrails.
     scale_retry_skipped_by_budget: usize,
     /// Total high-version subpixel attempts.
     hv_subpixel_attempts: usize,
-    /// Total high-version refine attempts.
-    hv_refine_attempts: usize,
-    /// Total high-version refine successes.
-    hv_refine_successes: usize,
-    /// Total RS erasure attempts.
-    rs_erasure_attempts: usize,
-    /// Total RS erasure successes.
-    rs_erasure_successes: usize,
+    /// Total grid refinement iterations.
+    grid_refine_iterations: usize,
+    /// Total feature residual removed by grid refinement (pixels).
+    grid_refine_residual_drop_px: f64,
     /// RS erasure histogram buckets [1, 2-3, 4-6, 7+].
     rs_erasure_count_hist: [usize; 4],
+    /// High-density RS blocks that failed to correct.
+    rs_blocks_failed: usize,
+    /// High-density RS blocks by share of error capacity used, in quarters.
+    rs_block_load_hist: [usize; 4],
+    /// High-density candidates with the version pinned from version info.
+    high_density_version_pins: usize,
+    /// High-density transforms dropped by the grid-fit residual gate.
+    high_density_residual_rejects: usize,
+    /// Decodes from the synthetic profile's pitch-snapped grid.
+    synthetic_snap_decodes: usize,
+    /// Brute-forced format guesses ruled out by the stream header.
+    header_prefilter_skips: usize,
+    /// Decodes whose format read from the grid turned 90/180/270° clockwise.
+    rotated_format_rescues: [usize; 3],
+    /// Decodes whose format was masked with the bit-reversed constant.
+    wrong_format_mask_rescues: usize,
+    /// Returned codes per binarization, in `Binarization::ALL` order.
+    binarization_decodes: [usize; Binarization::ALL.len()],
+    /// Decoded codes per version number.
+    decoded_versions: VersionHistogram,
+    /// Labeled codes read, per `VERSION_BANDS` band of the decoded version.
+    version_band_hits: [usize; VERSION_BANDS.len()],
+    /// Labeled codes missed, per band of the version estimated from the label.
+    version_band_misses: [usize; VERSION_BANDS.len()],
+    /// Labeled codes missed with no finder pattern to estimate a version from.
+    version_misses_unknown: usize,
+    /// Images whose finder candidates were grouped from a stratified sample.
+    finder_sampled_images: usize,
+    /// Finder candidates seen by the sampled passes.
+    finder_sample_input: usize,
+    /// Finder candidates the sampled passes kept.
+    finder_sample_kept: usize,
+    /// Region queues reordered after a lane's run of same-stage failures.
+    failure_reorders: usize,
+    /// Decodes from candidates those reorders moved forward.
+    failure_reorder_decodes: usize,
     /// Phase 9.11 candidate branches skipped due to time budget.
     phase11_time_budget_skips: usize,
+    /// Finder scans that skipped columns after a conclusive row pass.
+    column_scans_skipped: usize,
+    /// Decodes rescued by each encoder-quirk rule, in `EncoderQuirk::ALL` order.
+    quirk_rescues: [usize; EncoderQuirk::ALL.len()],
+    /// Candidate decodes skipped as already failed under another binarization.
+    decode_memo_hits: usize,
+    /// Bit streams run through error correction.
+    stream_decode_attempts: usize,
+    /// Images whose work a pathology guard cut short.
+    pathology_guard_trips: usize,
     /// Per-image decode-attempt histogram:
     /// [0, 1, 2-3, 4-7, 8+]
     attempts_used_histogram: [usize; 5],
+    /// Per-stage attempts, successes and time, summed by the library.
+    stages: rust_qr::TelemetryStages,
     /// Total images processed.
     total: usize,
 }
 
 impl StageTelemetry {
+    /// Add `image`'s `results` to the per-version breakdown. A labeled code
+    /// is a hit in the band of the code decoded inside its outline, or a
+    /// miss in the band of [`estimate_label_version`].
+    fn record_versions(
+        &mut self,
+        image: &Path,
+        results: &[rust_qr::QRCode],
+        pixels: &[u8],
+        width: usize,
+        height: usize,
+    ) {
+        for qr in results {
+            let v = qr.version.number() as usize;
+            if (1..=40).contains(&v) {
+                self.decoded_versions.0[v - 1] += 1;
+            }
+        }
+        let mut unmatched = label_quads(image, width, height);
+        for qr in results {
+            let center = Point::new(
+                qr.position.iter().map(|p| p.x).sum::<f32>() / 4.0,
+                qr.position.iter().map(|p| p.y).sum::<f32>() / 4.0,
+            );
+            if let Some(idx) = unmatched.iter().position(|q| point_in_polygon(center, q)) {
+                unmatched.swap_remove(idx);
+                self.version_band_hits[version_band(qr.version.number())] += 1;
+            }
+        }
+        if unmatched.is_empty() {
+            return;
+        }
+        let gray = to_grayscale(pixels, width, height);
+        let patterns = FinderDetector::detect(&binarize(&gray, width, height));
+        for quad in &unmatched {
+            match estimate_label_version(quad, &patterns) {
+                Some(v) => self.version_band_misses[version_band(v)] += 1,
+                None => self.version_misses_unknown += 1,
+            }
+        }
+    }
+
     fn accumulate(&mut self, other: StageTelemetry) {
-        self.binarize_ok += other.binarize_ok;
-        self.finder_ok += other.finder_ok;
-        self.groups_ok += other.groups_ok;
-        self.transform_ok += other.transform_ok;
         self.decode_ok += other.decode_ok;
-        self.total_decode_attempts += other.total_decode_attempts;
         for i in 0..self.candidate_score_buckets.len() {
             self.candidate_score_buckets[i] += other.candidate_score_buckets[i];
         }
@@ -901,18 +1766,25 @@ impl StageTelemetry {
         self.budget_lane_high += other.budget_lane_high;
         self.budget_lane_medium += other.budget_lane_medium;
         self.budget_lane_low += other.budget_lane_low;
+        self.budget_lane_low_snr += other.budget_lane_low_snr;
+        self.budget_attempts_allocated += other.budget_attempts_allocated;
+        self.budget_transforms_allocated += other.budget_transforms_allocated;
+        self.budget_transforms_used += other.budget_transforms_used;
+        for i in 0..self.budget_lane_allocation.len() {
+            self.budget_lane_allocation[i] += other.budget_lane_allocation[i];
+        }
         self.bin_fallback_otsu_to_adaptive31 += other.bin_fallback_otsu_to_adaptive31;
         self.bin_fallback_adaptive31_to_adaptive21 += other.bin_fallback_adaptive31_to_adaptive21;
         self.bin_fallback_successes += other.bin_fallback_successes;
+        for i in 0..self.bin_policy_attempts.len() {
+            self.bin_policy_allowance[i] += other.bin_policy_allowance[i];
+            self.bin_policy_attempts[i] += other.bin_policy_attempts[i];
+        }
         self.rerank_enabled += other.rerank_enabled;
-        self.rerank_top1_attempts += other.rerank_top1_attempts;
-        self.rerank_top1_successes += other.rerank_top1_successes;
         self.rerank_transform_reject_count += other.rerank_transform_reject_count;
         self.saturation_mask_enabled += other.saturation_mask_enabled;
         self.saturation_mask_coverage_sum += other.saturation_mask_coverage_sum;
         self.saturation_mask_decode_successes += other.saturation_mask_decode_successes;
-        self.roi_norm_attempts += other.roi_norm_attempts;
-        self.roi_norm_successes += other.roi_norm_successes;
         self.roi_norm_skipped += other.roi_norm_skipped;
         self.two_finder_used += other.two_finder_used;
         self.router_multi_region += other.router_multi_region;
@@ -920,26 +1792,63 @@ impl StageTelemetry {
         self.router_saturation_ratio_sum += other.router_saturation_ratio_sum;
         self.router_skew_estimate_deg_sum += other.router_skew_estimate_deg_sum;
         self.router_region_density_proxy_sum += other.router_region_density_proxy_sum;
+        self.router_flatness_sum += other.router_flatness_sum;
+        self.router_contrast_span_sum += other.router_contrast_span_sum;
+        self.router_top_snr_sum += other.router_top_snr_sum;
+        self.router_multi_scale_images += other.router_multi_scale_images;
+        for i in 0..self.image_conditions.len() {
+            self.image_conditions[i] += other.image_conditions[i];
+        }
         self.acceptance_rejected += other.acceptance_rejected;
-        self.deskew_attempts += other.deskew_attempts;
-        self.deskew_successes += other.deskew_successes;
         self.high_version_precision_attempts += other.high_version_precision_attempts;
         self.recovery_mode_attempts += other.recovery_mode_attempts;
-        self.scale_retry_attempts += other.scale_retry_attempts;
-        self.scale_retry_successes += other.scale_retry_successes;
         self.scale_retry_skipped_by_budget += other.scale_retry_skipped_by_budget;
         self.hv_subpixel_attempts += other.hv_subpixel_attempts;
-        self.hv_refine_attempts += other.hv_refine_attempts;
-        self.hv_refine_successes += other.hv_refine_successes;
-        self.rs_erasure_attempts += other.rs_erasure_attempts;
-        self.rs_erasure_successes += other.rs_erasure_successes;
+        self.grid_refine_iterations += other.grid_refine_iterations;
+        self.grid_refine_residual_drop_px += other.grid_refine_residual_drop_px;
         for i in 0..self.rs_erasure_count_hist.len() {
             self.rs_erasure_count_hist[i] += other.rs_erasure_count_hist[i];
         }
+        self.rs_blocks_failed += other.rs_blocks_failed;
+        for i in 0..self.rs_block_load_hist.len() {
+            self.rs_block_load_hist[i] += other.rs_block_load_hist[i];
+        }
+        self.high_density_version_pins += other.high_density_version_pins;
+        self.high_density_residual_rejects += other.high_density_residual_rejects;
+        self.synthetic_snap_decodes += other.synthetic_snap_decodes;
+        self.header_prefilter_skips += other.header_prefilter_skips;
+        for i in 0..self.rotated_format_rescues.len() {
+            self.rotated_format_rescues[i] += other.rotated_format_rescues[i];
+        }
+        self.wrong_format_mask_rescues += other.wrong_format_mask_rescues;
+        for i in 0..self.binarization_decodes.len() {
+            self.binarization_decodes[i] += other.binarization_decodes[i];
+        }
+        for i in 0..self.decoded_versions.0.len() {
+            self.decoded_versions.0[i] += other.decoded_versions.0[i];
+        }
+        self.version_misses_unknown += other.version_misses_unknown;
+        for i in 0..VERSION_BANDS.len() {
+            self.version_band_hits[i] += other.version_band_hits[i];
+            self.version_band_misses[i] += other.version_band_misses[i];
+        }
+        self.finder_sampled_images += other.finder_sampled_images;
+        self.finder_sample_input += other.finder_sample_input;
+        self.finder_sample_kept += other.finder_sample_kept;
+        self.failure_reorders += other.failure_reorders;
+        self.failure_reorder_decodes += other.failure_reorder_decodes;
         self.phase11_time_budget_skips += other.phase11_time_budget_skips;
+        self.column_scans_skipped += other.column_scans_skipped;
+        for i in 0..self.quirk_rescues.len() {
+            self.quirk_rescues[i] += other.quirk_rescues[i];
+        }
+        self.decode_memo_hits += other.decode_memo_hits;
+        self.stream_decode_attempts += other.stream_decode_attempts;
+        self.pathology_guard_trips += other.pathology_guard_trips;
         for i in 0..self.attempts_used_histogram.len() {
             self.attempts_used_histogram[i] += other.attempts_used_histogram[i];
         }
+        self.stages.merge(&other.stages);
         self.total += other.total;
     }
 }
@@ -1022,11 +1931,19 @@ struct CategoryResult {
 struct ReadingRateArtifact {
     dataset_root: String,
     dataset_fingerprint: String,
+    /// Host and build the run was measured on
+    environment: EnvironmentInfo,
     commit_sha: String,
     timestamp_utc: String,
     limit_per_category: Option<usize>,
     smoke: bool,
     non_interactive: bool,
+    /// Names of the subsystems switched off for the run
+    ablations: Vec<String>,
+    /// `i/N` when only one shard of the dataset was run
+    shard: Option<String>,
+    /// Images credited from `--resume`/`--merge` checkpoints
+    resumed_images: usize,
     weighted_global_rate_percent: f64,
     total_hits: usize,
     total_expected: usize,
@@ -1047,6 +1964,10 @@ fn reading_rate_for_images<I>(
     images: I,
     non_interactive: bool,
     progress_every: usize,
+    mut per_image: Option<&mut PerImageLog>,
+    mut checkpoint: Option<&mut PerImageLog>,
+    mut failed_grids: Option<&mut FailedGridLog>,
+    resumed: &ResumedRows,
 ) -> ReadingRateStats
 where
     I: Iterator<Item = PathBuf>,
@@ -1055,6 +1976,7 @@ where
         hits: 0,
         total_expected: 0,
         images_with_labels: 0,
+        resumed: 0,
         stage_telemetry: StageTelemetry::default(),
         runtime_samples_ms: Vec::new(),
         failure_clusters: BTreeMap::new(),
@@ -1071,40 +1993,66 @@ where
         }
         stats.images_with_labels += 1;
         stats.total_expected += expected;
+
+        if let Some(row) = resumed.get(&path) {
+            let image_hits = row.hits.min(expected);
+            stats.hits += image_hits;
+            stats.resumed += 1;
+            if let Some(elapsed_ms) = row.runtime_ms {
+                stats.runtime_samples_ms.push(elapsed_ms);
+                if image_hits == 0 {
+                    stats.record_failure(&row.signature, expected, &path);
+                }
+            }
+            if let Some(log) = per_image.as_deref_mut() {
+                log.append(row);
+            }
+            if !non_interactive {
+                println!(
+                    "  [{}] {} -> {}/{} (resumed)",
+                    stats.images_with_labels,
+                    path.display(),
+                    row.decoded,
+                    expected,
+                );
+            }
+            continue;
+        }
         stats.stage_telemetry.total += 1;
 
         if let Ok((pixels, width, height)) = load_rgb(&path) {
             let start = Instant::now();
-            let (results, tel) = rust_qr::detect_with_telemetry(&pixels, width, height);
+            let (mut results, tel) = rust_qr::detect_with_telemetry(&pixels, width, height);
             let elapsed = start.elapsed();
             let elapsed_ms = elapsed.as_secs_f64() * 1_000.0;
-            let mut decoded = results.len();
             // Telemetry mode can undercount due stricter budgets. For reading-rate scoring,
             // use the best of telemetry and production detect() when telemetry is short.
-            if decoded < expected {
-                decoded = decoded.max(detect_qr(&pixels, width, height).len());
+            if results.len() < expected {
+                let production = detect_qr(&pixels, width, height);
+                if production.len() > results.len() {
+                    results = production;
+                }
+            }
+            stats
+                .stage_telemetry
+                .record_versions(&path, &results, &pixels, width, height);
+            // Counted from the scored results, which may be production's.
+            for qr in &results {
+                if let Some(binarization) = qr.binarization {
+                    stats.stage_telemetry.binarization_decodes[binarization.index()] += 1;
+                }
             }
+            let decoded = results.len();
             let image_hits = decoded.min(expected);
             stats.hits += image_hits;
             stats.runtime_samples_ms.push(elapsed_ms);
 
             // Accumulate stage telemetry
-            if tel.binarize_ok {
-                stats.stage_telemetry.binarize_ok += 1;
-            }
-            if tel.finder_patterns_found >= 3 {
-                stats.stage_telemetry.finder_ok += 1;
-            }
-            if tel.groups_found >= 1 {
-                stats.stage_telemetry.groups_ok += 1;
-            }
-            if tel.transforms_built >= 1 {
-                stats.stage_telemetry.transform_ok += 1;
-            }
+            stats.stage_telemetry.stages.merge(&tel.stages());
             if decoded >= 1 {
                 stats.stage_telemetry.decode_ok += 1;
             }
-            stats.stage_telemetry.total_decode_attempts += tel.decode_attempts;
+            stats.stage_telemetry.stages.decode.attempts += tel.decode_attempts;
             stats.stage_telemetry.attempts_used_histogram
                 [attempts_hist_bucket(tel.decode_attempts)] += 1;
             for i in 0..stats.stage_telemetry.candidate_score_buckets.len() {
@@ -1116,16 +2064,25 @@ where
             stats.stage_telemetry.budget_lane_high += tel.budget_lane_high;
             stats.stage_telemetry.budget_lane_medium += tel.budget_lane_medium;
             stats.stage_telemetry.budget_lane_low += tel.budget_lane_low;
+            stats.stage_telemetry.budget_lane_low_snr += tel.budget_lane_low_snr;
+            stats.stage_telemetry.budget_attempts_allocated += tel.budget_attempts_allocated;
+            stats.stage_telemetry.budget_transforms_allocated += tel.budget_transforms_allocated;
+            stats.stage_telemetry.budget_transforms_used += tel.budget_transforms_used;
+            for i in 0..stats.stage_telemetry.budget_lane_allocation.len() {
+                stats.stage_telemetry.budget_lane_allocation[i] += tel.budget_lane_allocation[i];
+            }
             stats.stage_telemetry.bin_fallback_otsu_to_adaptive31 +=
                 tel.bin_fallback_otsu_to_adaptive31;
             stats.stage_telemetry.bin_fallback_adaptive31_to_adaptive21 +=
                 tel.bin_fallback_adaptive31_to_adaptive21;
             stats.stage_telemetry.bin_fallback_successes += tel.bin_fallback_successes;
+            for i in 0..stats.stage_telemetry.bin_policy_attempts.len() {
+                stats.stage_telemetry.bin_policy_allowance[i] += tel.bin_policy_allowance[i];
+                stats.stage_telemetry.bin_policy_attempts[i] += tel.bin_policy_attempts[i];
+            }
             if tel.rerank_enabled {
                 stats.stage_telemetry.rerank_enabled += 1;
             }
-            stats.stage_telemetry.rerank_top1_attempts += tel.rerank_top1_attempts;
-            stats.stage_telemetry.rerank_top1_successes += tel.rerank_top1_successes;
             stats.stage_telemetry.rerank_transform_reject_count +=
                 tel.rerank_transform_reject_count;
             if tel.saturation_mask_enabled {
@@ -1135,8 +2092,6 @@ where
                 tel.saturation_mask_coverage as f64;
             stats.stage_telemetry.saturation_mask_decode_successes +=
                 tel.saturation_mask_decode_successes;
-            stats.stage_telemetry.roi_norm_attempts += tel.roi_norm_attempts;
-            stats.stage_telemetry.roi_norm_successes += tel.roi_norm_successes;
             stats.stage_telemetry.roi_norm_skipped += tel.roi_norm_skipped;
             if tel.two_finder_successes > 0 || tel.two_finder_attempts > 0 {
                 stats.stage_telemetry.two_finder_used += 1;
@@ -1150,41 +2105,85 @@ where
                 tel.router_skew_estimate_deg as f64;
             stats.stage_telemetry.router_region_density_proxy_sum +=
                 tel.router_region_density_proxy as f64;
+            stats.stage_telemetry.router_flatness_sum += tel.router_flatness as f64;
+            stats.stage_telemetry.router_contrast_span_sum += tel.router_contrast_span as f64;
+            stats.stage_telemetry.router_top_snr_sum += tel.router_top_snr as f64;
+            if tel.router_module_size_modes.len() >= 2 {
+                stats.stage_telemetry.router_multi_scale_images += 1;
+            }
+            if let Some(condition) = ImageCondition::from_name(&tel.image_condition) {
+                stats.stage_telemetry.image_conditions[condition.index()] += 1;
+            }
             stats.stage_telemetry.acceptance_rejected += tel.acceptance_rejected;
-            stats.stage_telemetry.deskew_attempts += tel.deskew_attempts;
-            stats.stage_telemetry.deskew_successes += tel.deskew_successes;
             stats.stage_telemetry.high_version_precision_attempts +=
                 tel.high_version_precision_attempts;
             stats.stage_telemetry.recovery_mode_attempts += tel.recovery_mode_attempts;
-            stats.stage_telemetry.scale_retry_attempts += tel.scale_retry_attempts;
-            stats.stage_telemetry.scale_retry_successes += tel.scale_retry_successes;
             stats.stage_telemetry.scale_retry_skipped_by_budget +=
                 tel.scale_retry_skipped_by_budget;
             stats.stage_telemetry.hv_subpixel_attempts += tel.hv_subpixel_attempts;
-            stats.stage_telemetry.hv_refine_attempts += tel.hv_refine_attempts;
-            stats.stage_telemetry.hv_refine_successes += tel.hv_refine_successes;
-            stats.stage_telemetry.rs_erasure_attempts += tel.rs_erasure_attempts;
-            stats.stage_telemetry.rs_erasure_successes += tel.rs_erasure_successes;
+            stats.stage_telemetry.grid_refine_iterations += tel.grid_refine_iterations;
+            stats.stage_telemetry.grid_refine_residual_drop_px += tel.grid_refine_residual_drop_px;
             for i in 0..stats.stage_telemetry.rs_erasure_count_hist.len() {
                 stats.stage_telemetry.rs_erasure_count_hist[i] += tel.rs_erasure_count_hist[i];
             }
+            stats.stage_telemetry.rs_blocks_failed += tel.rs_blocks_failed;
+            for i in 0..stats.stage_telemetry.rs_block_load_hist.len() {
+                stats.stage_telemetry.rs_block_load_hist[i] += tel.rs_block_load_hist[i];
+            }
+            stats.stage_telemetry.high_density_version_pins += tel.high_density_version_pins;
+            stats.stage_telemetry.high_density_residual_rejects +=
+                tel.high_density_residual_rejects;
+            stats.stage_telemetry.synthetic_snap_decodes += tel.synthetic_snap_decodes;
+            stats.stage_telemetry.header_prefilter_skips += tel.header_prefilter_skips;
+            for i in 0..stats.stage_telemetry.rotated_format_rescues.len() {
+                stats.stage_telemetry.rotated_format_rescues[i] += tel.rotated_format_rescues[i];
+            }
+            stats.stage_telemetry.wrong_format_mask_rescues += tel.wrong_format_mask_rescues;
+            if tel.finder_sample_input > 0 {
+                stats.stage_telemetry.finder_sampled_images += 1;
+            }
+            stats.stage_telemetry.finder_sample_input += tel.finder_sample_input;
+            stats.stage_telemetry.finder_sample_kept += tel.finder_sample_kept;
+            stats.stage_telemetry.failure_reorders += tel.failure_reorders;
+            stats.stage_telemetry.failure_reorder_decodes += tel.failure_reorder_decodes;
             stats.stage_telemetry.phase11_time_budget_skips += tel.phase11_time_budget_skips;
+            stats.stage_telemetry.column_scans_skipped += tel.column_scans_skipped;
+            for i in 0..stats.stage_telemetry.quirk_rescues.len() {
+                stats.stage_telemetry.quirk_rescues[i] += tel.quirk_rescues[i];
+            }
+            stats.stage_telemetry.decode_memo_hits += tel.decode_memo_hits;
+            stats.stage_telemetry.stream_decode_attempts += tel.stream_decode_attempts;
+            if tel.pathology_guard_tripped {
+                stats.stage_telemetry.pathology_guard_trips += 1;
+            }
+
+            let signature = match image_hits {
+                0 => classify_failure_signature(&tel),
+                n if n < expected => "partial",
+                _ => "ok",
+            };
+            for log in [per_image.as_deref_mut(), checkpoint.as_deref_mut()]
+                .into_iter()
+                .flatten()
+            {
+                log.write_row(
+                    &path,
+                    expected,
+                    &results,
+                    signature,
+                    Some((elapsed_ms, &tel)),
+                );
+            }
+
+            if let Some(log) = failed_grids
+                .as_deref_mut()
+                .filter(|_| image_hits < expected)
+            {
+                log.write_grids(&path, &tel.failed_grids);
+            }
 
             if image_hits == 0 {
-                let signature = classify_failure_signature(&tel);
-                let row = stats
-                    .failure_clusters
-                    .entry(signature.to_string())
-                    .or_insert(FailureCluster {
-                        count: 0,
-                        qr_weight: 0,
-                        examples: Vec::new(),
-                    });
-                row.count += 1;
-                row.qr_weight += expected;
-                if row.examples.len() < 3 {
-                    row.examples.push(path.display().to_string());
-                }
+                stats.record_failure(signature, expected, &path);
             }
 
             if !non_interactive {
@@ -1210,19 +2209,154 @@ where
                     path.display()
                 );
             }
-        } else if !non_interactive {
-            println!(
-                "  [{}] {} -> load_failed (expected {})",
-                stats.images_with_labels,
-                path.display(),
-                expected,
-            );
+        } else {
+            for log in [per_image.as_deref_mut(), checkpoint.as_deref_mut()]
+                .into_iter()
+                .flatten()
+            {
+                log.write_row(&path, expected, &[], "load-failed", None);
+            }
+            if !non_interactive {
+                println!(
+                    "  [{}] {} -> load_failed (expected {})",
+                    stats.images_with_labels,
+                    path.display(),
+                    expected,
+                );
+            }
         }
     }
 
     stats
 }
 
+/// NDJSON sink for `reading-rate --per-image-jsonl`: one object per labeled
+/// image, keyed by its dataset-relative path so runs can be joined.
+struct PerImageLog {
+    root: PathBuf,
+    out: std::io::BufWriter<fs::File>,
+}
+
+impl PerImageLog {
+    fn write_row(
+        &mut self,
+        path: &Path,
+        expected: usize,
+        results: &[rust_qr::QRCode],
+        signature: &str,
+        run: Option<(f64, &rust_qr::DetectionTelemetry)>,
+    ) {
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        let category = rel
+            .components()
+            .next()
+            .filter(|_| rel.components().count() > 1)
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut row = PerImageRow {
+            path: dataset_relative(&self.root, path),
+            category,
+            expected,
+            decoded: results.len(),
+            hits: results.len().min(expected),
+            payloads: results.iter().map(|qr| qr.content.clone()).collect(),
+            signature: signature.to_string(),
+            ..Default::default()
+        };
+        if let Some((runtime_ms, tel)) = run {
+            row.runtime_ms = Some(runtime_ms);
+            row.finders = tel.finder_patterns_found;
+            row.groups = tel.groups_found;
+            row.transforms = tel.transforms_built;
+            row.decode_attempts = tel.decode_attempts;
+        }
+        self.append(&row);
+    }
+
+    /// Write `row` and flush it, so an interrupted run loses at most the
+    /// image in flight.
+    fn append(&mut self, row: &PerImageRow) {
+        use std::io::Write as _;
+
+        if let Err(err) = self
+            .out
+            .write_all(row.to_json_line().as_bytes())
+            .and_then(|()| self.out.flush())
+        {
+            eprintln!("Failed to write per-image row: {}", err);
+        }
+    }
+}
+
+/// NDJSON sink for `reading-rate --failed-grids-jsonl`: one object per
+/// failed grid, keyed by its image's dataset-relative path.
+struct FailedGridLog {
+    root: PathBuf,
+    out: std::io::BufWriter<fs::File>,
+}
+
+impl FailedGridLog {
+    /// Write `grids`, found in the image at `path`, and flush them.
+    fn write_grids(&mut self, path: &Path, grids: &[rust_qr::FailedGrid]) {
+        use std::io::Write as _;
+
+        let rel = dataset_relative(&self.root, path);
+        if let Err(err) = grids
+            .iter()
+            .try_for_each(|grid| {
+                self.out
+                    .write_all(failed_grids::to_json_line(&rel, grid).as_bytes())
+            })
+            .and_then(|()| self.out.flush())
+        {
+            eprintln!("Failed to write failed grids: {}", err);
+        }
+    }
+}
+
+/// `path` relative to the dataset root, as per-image rows key it.
+fn dataset_relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+/// Per-image rows from `--resume` and `--merge` checkpoints: images with a
+/// row are credited from it instead of being run again.
+struct ResumedRows {
+    root: PathBuf,
+    rows: BTreeMap<String, PerImageRow>,
+}
+
+impl ResumedRows {
+    fn get(&self, path: &Path) -> Option<&PerImageRow> {
+        self.rows.get(&dataset_relative(&self.root, path))
+    }
+}
+
+/// Rewrite the checkpoint at `path` with `rows` (which already include its
+/// own) and reopen it for appending.
+fn open_checkpoint<'a>(
+    path: &Path,
+    root: &Path,
+    rows: impl Iterator<Item = &'a PerImageRow>,
+) -> std::io::Result<PerImageLog> {
+    let mut content = String::new();
+    for row in rows {
+        content.push_str(&row.to_json_line());
+    }
+    // Write-then-rename so a crash here cannot lose the existing rows.
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, path)?;
+    let file = fs::OpenOptions::new().append(true).open(path)?;
+    Ok(PerImageLog {
+        root: root.to_path_buf(),
+        out: std::io::BufWriter::new(file),
+    })
+}
+
 fn classify_failure_signature(tel: &rust_qr::DetectionTelemetry) -> &'static str {
     if tel.budget_skips > 0 && tel.payload_decoded == 0 {
         return "over-budget-skip";
@@ -1310,6 +2444,24 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         "    \"dataset_fingerprint\": \"{}\",",
         json_escape(&artifact.dataset_fingerprint)
     );
+    let env = &artifact.environment;
+    let features: Vec<String> = env.features.iter().map(|f| format!("\"{f}\"")).collect();
+    json.push_str("    \"environment\": {\n");
+    let _ = writeln!(
+        &mut json,
+        "      \"cpu_model\": \"{}\",",
+        json_escape(&env.cpu_model)
+    );
+    let _ = writeln!(&mut json, "      \"os\": \"{}\",", env.os);
+    let _ = writeln!(&mut json, "      \"arch\": \"{}\",", env.arch);
+    let _ = writeln!(&mut json, "      \"logical_cpus\": {},", env.logical_cpus);
+    let _ = writeln!(
+        &mut json,
+        "      \"worker_threads\": {},",
+        env.worker_threads
+    );
+    let _ = writeln!(&mut json, "      \"features\": [{}]", features.join(", "));
+    json.push_str("    },\n");
     let _ = writeln!(
         &mut json,
         "    \"commit_sha\": \"{}\",",
@@ -1329,9 +2481,26 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
     let _ = writeln!(&mut json, "    \"smoke\": {},", artifact.smoke);
     let _ = writeln!(
         &mut json,
-        "    \"non_interactive\": {}",
+        "    \"non_interactive\": {},",
         artifact.non_interactive
     );
+    let ablations: Vec<String> = artifact
+        .ablations
+        .iter()
+        .map(|a| format!("\"{}\"", json_escape(a)))
+        .collect();
+    let _ = writeln!(&mut json, "    \"ablations\": [{}],", ablations.join(", "));
+    match &artifact.shard {
+        Some(shard) => {
+            let _ = writeln!(&mut json, "    \"shard\": \"{}\",", json_escape(shard));
+        }
+        None => json.push_str("    \"shard\": null,\n"),
+    }
+    let _ = writeln!(
+        &mut json,
+        "    \"resumed_images\": {}",
+        artifact.resumed_images
+    );
     json.push_str("  },\n");
     json.push_str("  \"summary\": {\n");
     let _ = writeln!(
@@ -1391,22 +2560,22 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"binarize_ok\": {},",
-            category.stage_telemetry.binarize_ok
+            category.stage_telemetry.stages.binarize.successes
         );
         let _ = writeln!(
             &mut json,
             "        \"finder_ok\": {},",
-            category.stage_telemetry.finder_ok
+            category.stage_telemetry.stages.finder_scan.successes
         );
         let _ = writeln!(
             &mut json,
             "        \"groups_ok\": {},",
-            category.stage_telemetry.groups_ok
+            category.stage_telemetry.stages.grouping.successes
         );
         let _ = writeln!(
             &mut json,
             "        \"transform_ok\": {},",
-            category.stage_telemetry.transform_ok
+            category.stage_telemetry.stages.transform.successes
         );
         let _ = writeln!(
             &mut json,
@@ -1416,7 +2585,7 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"total_decode_attempts\": {},",
-            category.stage_telemetry.total_decode_attempts
+            category.stage_telemetry.stages.decode.attempts
         );
         let _ = writeln!(
             &mut json,
@@ -1438,6 +2607,33 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
             "        \"budget_lane_low\": {},",
             category.stage_telemetry.budget_lane_low
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"budget_lane_low_snr\": {},",
+            category.stage_telemetry.budget_lane_low_snr
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"budget_attempts_allocated\": {},",
+            category.stage_telemetry.budget_attempts_allocated
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"budget_transforms_allocated\": {},",
+            category.stage_telemetry.budget_transforms_allocated
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"budget_transforms_used\": {},",
+            category.stage_telemetry.budget_transforms_used
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"budget_lane_allocation\": [{}, {}, {}],",
+            category.stage_telemetry.budget_lane_allocation[0],
+            category.stage_telemetry.budget_lane_allocation[1],
+            category.stage_telemetry.budget_lane_allocation[2]
+        );
         let _ = writeln!(
             &mut json,
             "        \"bin_fallback_otsu_to_adaptive31\": {},",
@@ -1455,6 +2651,22 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
             "        \"bin_fallback_successes\": {},",
             category.stage_telemetry.bin_fallback_successes
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"bin_policy_allowance\": [{}, {}, {}, {}],",
+            category.stage_telemetry.bin_policy_allowance[0],
+            category.stage_telemetry.bin_policy_allowance[1],
+            category.stage_telemetry.bin_policy_allowance[2],
+            category.stage_telemetry.bin_policy_allowance[3]
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"bin_policy_attempts\": [{}, {}, {}, {}],",
+            category.stage_telemetry.bin_policy_attempts[0],
+            category.stage_telemetry.bin_policy_attempts[1],
+            category.stage_telemetry.bin_policy_attempts[2],
+            category.stage_telemetry.bin_policy_attempts[3]
+        );
         let _ = writeln!(
             &mut json,
             "        \"rerank_enabled\": {},",
@@ -1463,12 +2675,12 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"rerank_top1_attempts\": {},",
-            category.stage_telemetry.rerank_top1_attempts
+            category.stage_telemetry.stages.rerank_top1.attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"rerank_top1_successes\": {},",
-            category.stage_telemetry.rerank_top1_successes
+            category.stage_telemetry.stages.rerank_top1.successes
         );
         let _ = writeln!(
             &mut json,
@@ -1493,12 +2705,12 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"roi_norm_attempts\": {},",
-            category.stage_telemetry.roi_norm_attempts
+            category.stage_telemetry.stages.recovery.roi_norm.attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"roi_norm_successes\": {},",
-            category.stage_telemetry.roi_norm_successes
+            category.stage_telemetry.stages.recovery.roi_norm.successes
         );
         let _ = writeln!(
             &mut json,
@@ -1535,6 +2747,36 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
             "        \"router_region_density_proxy_sum\": {:.6},",
             category.stage_telemetry.router_region_density_proxy_sum
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"router_flatness_sum\": {:.6},",
+            category.stage_telemetry.router_flatness_sum
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"router_contrast_span_sum\": {:.6},",
+            category.stage_telemetry.router_contrast_span_sum
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"router_top_snr_sum\": {:.6},",
+            category.stage_telemetry.router_top_snr_sum
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"router_multi_scale_images\": {},",
+            category.stage_telemetry.router_multi_scale_images
+        );
+        let conditions: Vec<String> = ImageCondition::ALL
+            .iter()
+            .zip(category.stage_telemetry.image_conditions)
+            .map(|(c, n)| format!("\"{}\": {n}", c.name()))
+            .collect();
+        let _ = writeln!(
+            &mut json,
+            "        \"image_conditions\": {{{}}},",
+            conditions.join(", ")
+        );
         let _ = writeln!(
             &mut json,
             "        \"acceptance_rejected\": {},",
@@ -1543,12 +2785,12 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"deskew_attempts\": {},",
-            category.stage_telemetry.deskew_attempts
+            category.stage_telemetry.stages.recovery.deskew.attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"deskew_successes\": {},",
-            category.stage_telemetry.deskew_successes
+            category.stage_telemetry.stages.recovery.deskew.successes
         );
         let _ = writeln!(
             &mut json,
@@ -1563,18 +2805,48 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"scale_retry_attempts\": {},",
-            category.stage_telemetry.scale_retry_attempts
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .scale_retry
+                .attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"scale_retry_successes\": {},",
-            category.stage_telemetry.scale_retry_successes
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .scale_retry
+                .successes
         );
         let _ = writeln!(
             &mut json,
             "        \"scale_retry_skipped_by_budget\": {},",
             category.stage_telemetry.scale_retry_skipped_by_budget
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"threshold_sweep_attempts\": {},",
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .threshold_sweep
+                .attempts
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"threshold_sweep_successes\": {},",
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .threshold_sweep
+                .successes
+        );
         let _ = writeln!(
             &mut json,
             "        \"hv_subpixel_attempts\": {},",
@@ -1583,22 +2855,37 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
         let _ = writeln!(
             &mut json,
             "        \"hv_refine_attempts\": {},",
-            category.stage_telemetry.hv_refine_attempts
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .high_version_refine
+                .attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"hv_refine_successes\": {},",
-            category.stage_telemetry.hv_refine_successes
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .high_version_refine
+                .successes
         );
         let _ = writeln!(
             &mut json,
             "        \"rs_erasure_attempts\": {},",
-            category.stage_telemetry.rs_erasure_attempts
+            category.stage_telemetry.stages.recovery.rs_erasure.attempts
         );
         let _ = writeln!(
             &mut json,
             "        \"rs_erasure_successes\": {},",
-            category.stage_telemetry.rs_erasure_successes
+            category
+                .stage_telemetry
+                .stages
+                .recovery
+                .rs_erasure
+                .successes
         );
         let _ = writeln!(
             &mut json,
@@ -1608,11 +2895,154 @@ fn write_reading_rate_artifact(path: &Path, artifact: &ReadingRateArtifact) {
             category.stage_telemetry.rs_erasure_count_hist[2],
             category.stage_telemetry.rs_erasure_count_hist[3]
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"rs_blocks_failed\": {},",
+            category.stage_telemetry.rs_blocks_failed
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"rs_block_load_hist\": [{}, {}, {}, {}],",
+            category.stage_telemetry.rs_block_load_hist[0],
+            category.stage_telemetry.rs_block_load_hist[1],
+            category.stage_telemetry.rs_block_load_hist[2],
+            category.stage_telemetry.rs_block_load_hist[3]
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"high_density_version_pins\": {},",
+            category.stage_telemetry.high_density_version_pins
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"high_density_residual_rejects\": {},",
+            category.stage_telemetry.high_density_residual_rejects
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"synthetic_snap_decodes\": {},",
+            category.stage_telemetry.synthetic_snap_decodes
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"header_prefilter_skips\": {},",
+            category.stage_telemetry.header_prefilter_skips
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"rotated_format_rescues\": [{}, {}, {}],",
+            category.stage_telemetry.rotated_format_rescues[0],
+            category.stage_telemetry.rotated_format_rescues[1],
+            category.stage_telemetry.rotated_format_rescues[2]
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"wrong_format_mask_rescues\": {},",
+            category.stage_telemetry.wrong_format_mask_rescues
+        );
+        let by_binarization: Vec<String> = Binarization::ALL
+            .iter()
+            .map(|b| {
+                format!(
+                    "\"{}\": {}",
+                    b.name(),
+                    category.stage_telemetry.binarization_decodes[b.index()]
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            &mut json,
+            "        \"binarization_decodes\": {{{}}},",
+            by_binarization.join(", ")
+        );
+        let join = |counts: &[usize]| {
+            counts
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let _ = writeln!(
+            &mut json,
+            "        \"decoded_versions\": [{}],",
+            join(&category.stage_telemetry.decoded_versions.0)
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"version_band_hits\": [{}],",
+            join(&category.stage_telemetry.version_band_hits)
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"version_band_misses\": [{}],",
+            join(&category.stage_telemetry.version_band_misses)
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"version_misses_unknown\": {},",
+            category.stage_telemetry.version_misses_unknown
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"finder_sampled_images\": {},",
+            category.stage_telemetry.finder_sampled_images
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"finder_sample_input\": {},",
+            category.stage_telemetry.finder_sample_input
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"finder_sample_kept\": {},",
+            category.stage_telemetry.finder_sample_kept
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"failure_reorders\": {},",
+            category.stage_telemetry.failure_reorders
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"failure_reorder_decodes\": {},",
+            category.stage_telemetry.failure_reorder_decodes
+        );
         let _ = writeln!(
             &mut json,
             "        \"phase11_time_budget_skips\": {},",
             category.stage_telemetry.phase11_time_budget_skips
         );
+        let _ = writeln!(
+            &mut json,
+            "        \"column_scans_skipped\": {},",
+            category.stage_telemetry.column_scans_skipped
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"decode_memo_hits\": {},",
+            category.stage_telemetry.decode_memo_hits
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"stream_decode_attempts\": {},",
+            category.stage_telemetry.stream_decode_attempts
+        );
+        let _ = writeln!(
+            &mut json,
+            "        \"pathology_guard_trips\": {},",
+            category.stage_telemetry.pathology_guard_trips
+        );
+        let stages = &category.stage_telemetry.stages;
+        let _ = writeln!(
+            &mut json,
+            "        \"stage_time_ms\": {{\"grayscale\": {:.3}, \"binarize\": {:.3}, \"finder_scan\": {:.3}, \"group_rank\": {:.3}, \"decode\": {:.3}, \"recovery\": {:.3}}},",
+            stages.grayscale.time_ms,
+            stages.binarize.time_ms,
+            stages.finder_scan.time_ms,
+            stages.grouping.time_ms,
+            stages.decode.time_ms,
+            stages.recovery.time_ms
+        );
         let _ = writeln!(
             &mut json,
             "        \"candidate_score_buckets\": [{}, {}, {}, {}],",
@@ -1709,6 +3139,213 @@ fn write_runtime_json(json: &mut String, key: &str, runtime: RuntimeSummary, ind
     let _ = writeln!(json, "{pad}}}");
 }
 
+fn load_per_image_rows(path: &Path) -> BTreeMap<String, PerImageRow> {
+    parse_per_image_rows(path, &read_per_image_file(path))
+}
+
+/// Like [`load_per_image_rows`], dropping a last line left unterminated by
+/// an interrupted run.
+fn load_checkpoint_rows(path: &Path) -> BTreeMap<String, PerImageRow> {
+    let content = read_per_image_file(path);
+    let complete = match content.rfind('\n') {
+        Some(end) => &content[..=end],
+        None => "",
+    };
+    if complete.len() < content.len() {
+        eprintln!("{}: dropping a partially written last row", path.display());
+    }
+    parse_per_image_rows(path, complete)
+}
+
+fn read_per_image_file(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", path.display(), err);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn parse_per_image_rows(path: &Path, content: &str) -> BTreeMap<String, PerImageRow> {
+    let mut rows = BTreeMap::new();
+    for (lineno, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match PerImageRow::parse_json_line(line) {
+            Ok(row) => {
+                rows.insert(row.path.clone(), row);
+            }
+            Err(err) => {
+                eprintln!("{}:{}: {}", path.display(), lineno + 1, err);
+                std::process::exit(2);
+            }
+        }
+    }
+    rows
+}
+
+/// Attempts/runtime movement for images sharing a baseline signature.
+#[derive(Default)]
+struct SignatureDelta {
+    images: usize,
+    still_failing: usize,
+    old_attempts: usize,
+    new_attempts: usize,
+    runtime_deltas_ms: Vec<f64>,
+}
+
+fn telemetry_diff_cmd(old_path: &Path, new_path: &Path, examples: usize) {
+    let old = load_per_image_rows(old_path);
+    let new = load_per_image_rows(new_path);
+
+    let mut newly_failing: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut newly_passing: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut transitions: BTreeMap<(&str, &str), Vec<&str>> = BTreeMap::new();
+    let mut by_signature: BTreeMap<&str, SignatureDelta> = BTreeMap::new();
+    let (mut old_passed, mut new_passed, mut joined) = (0usize, 0usize, 0usize);
+
+    for (path, before) in &old {
+        let Some(after) = new.get(path) else {
+            continue;
+        };
+        joined += 1;
+        old_passed += before.passed() as usize;
+        new_passed += after.passed() as usize;
+        match (before.passed(), after.passed()) {
+            (true, false) => newly_failing
+                .entry(&after.signature)
+                .or_default()
+                .push(path),
+            (false, true) => newly_passing
+                .entry(&before.signature)
+                .or_default()
+                .push(path),
+            _ => {}
+        }
+        if before.signature != after.signature {
+            transitions
+                .entry((&before.signature, &after.signature))
+                .or_default()
+                .push(path);
+        }
+        let delta = by_signature.entry(&before.signature).or_default();
+        delta.images += 1;
+        delta.still_failing += !after.passed() as usize;
+        delta.old_attempts += before.decode_attempts;
+        delta.new_attempts += after.decode_attempts;
+        if let (Some(a), Some(b)) = (before.runtime_ms, after.runtime_ms) {
+            delta.runtime_deltas_ms.push(b - a);
+        }
+    }
+
+    println!("Telemetry diff");
+    println!("=====================================");
+    println!("Old: {} ({} images)", old_path.display(), old.len());
+    println!("New: {} ({} images)", new_path.display(), new.len());
+    let old_only = old.len() - joined;
+    let new_only = new.keys().filter(|p| !old.contains_key(*p)).count();
+    println!(
+        "Joined: {} (old-only {}, new-only {})",
+        joined, old_only, new_only
+    );
+    println!(
+        "Fully read (joined): {} -> {} ({:+})",
+        old_passed,
+        new_passed,
+        new_passed as i64 - old_passed as i64
+    );
+
+    let print_grouped = |title: &str, groups: &BTreeMap<&str, Vec<&str>>| {
+        let total: usize = groups.values().map(Vec::len).sum();
+        println!("\n{} ({}):", title, total);
+        for (signature, paths) in groups {
+            println!("  {} ({})", signature, paths.len());
+            for path in paths {
+                let (a, b) = (&old[*path], &new[*path]);
+                println!(
+                    "    {}: {}/{} {} -> {}/{} {}",
+                    path, a.hits, a.expected, a.signature, b.hits, b.expected, b.signature
+                );
+            }
+        }
+    };
+    print_grouped("Newly failing, by new signature", &newly_failing);
+    print_grouped("Newly passing, by old signature", &newly_passing);
+
+    println!("\nSignature changes:");
+    if transitions.is_empty() {
+        println!("  none");
+    }
+    let mut ranked: Vec<_> = transitions.iter().collect();
+    ranked.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+    for ((from, to), paths) in ranked {
+        let shown: Vec<&str> = paths.iter().take(examples).copied().collect();
+        println!(
+            "  {:<16} -> {:<16} {:>5}  e.g. {}",
+            from,
+            to,
+            paths.len(),
+            shown.join(", ")
+        );
+    }
+
+    println!("\nDeltas by old signature:");
+    println!(
+        "{:<16} {:>6} {:>8} {:>10} {:>10} {:>12} {:>12}",
+        "Signature", "Imgs", "Failing", "Attempts", "dAttempts", "dRt mean ms", "dRt med ms"
+    );
+    println!("{}", "-".repeat(80));
+    for (signature, delta) in &by_signature {
+        let runtime = RuntimeDelta::from_samples(&delta.runtime_deltas_ms);
+        println!(
+            "{:<16} {:>6} {:>8} {:>10} {:>+10} {:>+12.2} {:>+12.2}",
+            signature,
+            delta.images,
+            delta.still_failing,
+            delta.new_attempts,
+            delta.new_attempts as i64 - delta.old_attempts as i64,
+            runtime.mean,
+            runtime.median
+        );
+    }
+    println!("=====================================");
+
+    if !newly_failing.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Mean and median of signed runtime deltas.
+struct RuntimeDelta {
+    mean: f64,
+    median: f64,
+}
+
+impl RuntimeDelta {
+    fn from_samples(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Self {
+                mean: 0.0,
+                median: 0.0,
+            };
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+        Self {
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            median,
+        }
+    }
+}
+
 fn dataset_bench_cmd(root: Option<PathBuf>, limit: Option<usize>, smoke: bool) {
     let root = root.unwrap_or_else(dataset_root_from_env);
     let limit = limit.or_else(bench_limit_from_env);
@@ -1753,3 +3390,139 @@ fn dataset_bench_cmd(root: Option<PathBuf>, limit: Option<usize>, smoke: bool) {
 
     println!("Total time: {:.2?}", total_elapsed);
 }
+
+fn gen_smoke_cmd(out: &Path, count: usize) {
+    match write_smoke_set(out, count) {
+        Ok(written) => println!(
+            "Wrote {} images to {} (run `qrtool reading-rate --root {}`)",
+            written,
+            out.display(),
+            out.display()
+        ),
+        Err(err) => {
+            eprintln!("Failed to write smoke set to {}: {}", out.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn calibrate_confidence_cmd(
+    root: Option<PathBuf>,
+    limit: Option<usize>,
+    smoke: bool,
+    bins: usize,
+    output: Option<&Path>,
+) {
+    let root = root.unwrap_or_else(dataset_root_from_env);
+    let limit = limit.or_else(bench_limit_from_env);
+    let smoke = smoke || smoke_from_env();
+
+    if !root.exists() {
+        eprintln!("Dataset root not found: {}", root.display());
+        return;
+    }
+
+    // A decode is correct when its payload is the labeled one; corner labels
+    // say where a code is but not what it reads, so only images with a
+    // payload label count.
+    let mut samples: Vec<(f32, bool)> = Vec::new();
+    let mut labeled_images = 0usize;
+    for path in dataset_iter(&root, limit, smoke) {
+        let Some(expected) = parse_expected_payload(path.with_extension("txt")) else {
+            continue;
+        };
+        let (pixels, width, height) = match load_rgb(&path) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Failed to load {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        labeled_images += 1;
+
+        // The label is matched once; a second read of it counts as wrong.
+        let mut matched = false;
+        for qr in detect_qr(&pixels, width, height) {
+            let correct = !matched && payload_matches(&qr.content, &expected);
+            matched |= correct;
+            samples.push((qr.score, correct));
+        }
+    }
+
+    let correct = samples.iter().filter(|(_, c)| *c).count();
+    println!(
+        "Labeled images: {}  decodes: {}  correct: {}",
+        labeled_images,
+        samples.len(),
+        correct
+    );
+    let Some(calibration) = ConfidenceCalibration::fit(&samples, bins) else {
+        eprintln!("Not enough decodes to fit a calibration");
+        return;
+    };
+
+    println!("Reliability (score -> probability):");
+    for (score, probability) in calibration.points() {
+        println!("  {:.3} -> {:.3}", score, probability);
+    }
+    for target in [0.95, 0.98] {
+        match calibration.score_for(target) {
+            Some(score) => println!("Score for p >= {:.2}: {:.3}", target, score),
+            None => println!("Score for p >= {:.2}: unreachable", target),
+        }
+    }
+
+    let table = calibration.to_table();
+    match output {
+        Some(path) => match fs::write(path, &table) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(err) => eprintln!("Failed to write {}: {}", path.display(), err),
+        },
+        None => print!("{}", table),
+    }
+}
+
+/// Labeled code outlines of `image`, scaled to the `width`x`height` it was
+/// loaded at (labels are in original pixels; `QR_MAX_DIM` may downscale).
+fn label_quads(image: &Path, width: usize, height: usize) -> Vec<[Point; 4]> {
+    let (sx, sy) = match image::image_dimensions(image) {
+        Ok((w, h)) => (width as f32 / w as f32, height as f32 / h as f32),
+        Err(_) => (1.0, 1.0),
+    };
+    parse_expected_qr_corners(image.with_extension("txt"))
+        .iter()
+        .map(|quad| quad.map(|(x, y)| Point::new(x * sx, y * sy)))
+        .collect()
+}
+
+/// Inclusive version ranges of the per-version breakdown.
+const VERSION_BANDS: [(u8, u8); 4] = [(1, 6), (7, 14), (15, 24), (25, 40)];
+
+fn version_band(version: u8) -> usize {
+    VERSION_BANDS
+        .iter()
+        .position(|&(lo, hi)| (lo..=hi).contains(&version))
+        .unwrap_or(VERSION_BANDS.len() - 1)
+}
+
+/// Version of a labeled code that did not decode, from the quad's mean side
+/// over the median module size of the finder patterns inside it; `None`
+/// when no finder pattern was found there.
+fn estimate_label_version(quad: &[Point; 4], patterns: &[FinderPattern]) -> Option<u8> {
+    let mut modules: Vec<f32> = patterns
+        .iter()
+        .filter(|p| point_in_polygon(p.center, quad))
+        .map(|p| p.module_size)
+        .collect();
+    if modules.is_empty() {
+        return None;
+    }
+    modules.sort_by(f32::total_cmp);
+    let module = modules[modules.len() / 2];
+    let side = (0..4)
+        .map(|i| quad[i].distance(&quad[(i + 1) % 4]))
+        .sum::<f32>()
+        / 4.0;
+    let dimension = side / module.max(f32::EPSILON);
+    Some(((dimension - 17.0) / 4.0).round().clamp(1.0, 40.0) as u8)
+}
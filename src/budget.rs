@@ -0,0 +1,237 @@
+//! Adaptive decode-budget controller
+//!
+//! Tracks per-frame decode attempt usage and success over a rolling window and
+//! moves `max_decode_attempts` and the confidence-lane split within caller-set
+//! bounds: easy streams shrink toward what they actually use, while streams
+//! that keep failing at the cap get more attempts spread into the lower lanes.
+
+use std::collections::VecDeque;
+
+use crate::pipeline::{BudgetOverride, DEFAULT_MAX_DECODE_ATTEMPTS};
+
+const LANE_MEDIUM_FRAC: f32 = 0.3;
+const LANE_HIGH_MIN: f32 = 0.3;
+const LANE_HIGH_MAX: f32 = 0.7;
+const LANE_HIGH_STEP: f32 = 0.05;
+const MIN_SAMPLES: usize = 4;
+/// Fraction of failed frames that must have hit the cap before growing.
+const SATURATED_FAIL_TRIGGER: f32 = 0.25;
+
+/// Caller-set limits for the adaptive controller.
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetBounds {
+    /// Lower bound for per-call decode attempts.
+    pub min_decode_attempts: usize,
+    /// Upper bound for per-call decode attempts, including multi-QR expansion.
+    pub max_decode_attempts: usize,
+    /// Number of recent frames considered.
+    pub window: usize,
+    /// Success rate the controller tries to hold before trimming budget.
+    pub target_success_rate: f32,
+}
+
+impl Default for BudgetBounds {
+    fn default() -> Self {
+        Self {
+            min_decode_attempts: 8,
+            max_decode_attempts: 256,
+            window: 32,
+            target_success_rate: 0.9,
+        }
+    }
+}
+
+/// Current controller state, for observability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetOperatingPoint {
+    pub max_decode_attempts: usize,
+    pub lane_high_frac: f32,
+    pub lane_medium_frac: f32,
+    pub lane_low_frac: f32,
+    /// Success rate over the rolling window.
+    pub success_rate: f32,
+    /// Mean decode attempts per frame over the rolling window.
+    pub mean_attempts_used: f32,
+    /// Frames currently in the rolling window.
+    pub frames_observed: usize,
+    /// Number of budget changes made so far.
+    pub adjustments: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FrameSample {
+    attempts_used: usize,
+    budget: usize,
+    success: bool,
+}
+
+/// Feedback controller owned by [`crate::Detector`].
+#[derive(Debug, Clone)]
+pub struct AdaptiveBudget {
+    bounds: BudgetBounds,
+    max_decode_attempts: usize,
+    lane_high_frac: f32,
+    history: VecDeque<FrameSample>,
+    cooldown: usize,
+    adjustments: usize,
+}
+
+impl AdaptiveBudget {
+    /// Create a controller starting from the default budget, clamped to `bounds`.
+    pub fn new(bounds: BudgetBounds) -> Self {
+        let min = bounds.min_decode_attempts.max(1);
+        let bounds = BudgetBounds {
+            min_decode_attempts: min,
+            max_decode_attempts: bounds.max_decode_attempts.max(min),
+            window: bounds.window.max(MIN_SAMPLES),
+            target_success_rate: bounds.target_success_rate.clamp(0.0, 1.0),
+        };
+        Self {
+            bounds,
+            max_decode_attempts: DEFAULT_MAX_DECODE_ATTEMPTS
+                .clamp(bounds.min_decode_attempts, bounds.max_decode_attempts),
+            lane_high_frac: 0.5,
+            history: VecDeque::with_capacity(bounds.window),
+            cooldown: 0,
+            adjustments: 0,
+        }
+    }
+
+    pub fn bounds(&self) -> BudgetBounds {
+        self.bounds
+    }
+
+    pub(crate) fn current_override(&self) -> BudgetOverride {
+        BudgetOverride {
+            max_decode_attempts: self.max_decode_attempts,
+            max_expanded_attempts: self.bounds.max_decode_attempts,
+            lane_high_frac: self.lane_high_frac,
+            lane_medium_frac: LANE_MEDIUM_FRAC,
+        }
+    }
+
+    /// Record one frame and adjust the operating point if warranted.
+    pub fn observe(&mut self, attempts_used: usize, success: bool) {
+        if self.history.len() == self.bounds.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(FrameSample {
+            attempts_used,
+            budget: self.max_decode_attempts,
+            success,
+        });
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+        if self.history.len() < MIN_SAMPLES {
+            return;
+        }
+
+        let n = self.history.len() as f32;
+        let success_rate = self.history.iter().filter(|s| s.success).count() as f32 / n;
+        let saturated_fails = self
+            .history
+            .iter()
+            .filter(|s| !s.success && s.attempts_used >= s.budget)
+            .count() as f32;
+        let mut used: Vec<usize> = self.history.iter().map(|s| s.attempts_used).collect();
+        used.sort_unstable();
+        let p90 = used[((used.len() - 1) as f32 * 0.9).round() as usize];
+
+        let previous = (self.max_decode_attempts, self.lane_high_frac);
+        if success_rate < self.bounds.target_success_rate
+            && saturated_fails / n >= SATURATED_FAIL_TRIGGER
+        {
+            let grown = self.max_decode_attempts + self.max_decode_attempts / 4 + 1;
+            self.max_decode_attempts = grown.min(self.bounds.max_decode_attempts);
+            self.lane_high_frac = (self.lane_high_frac - LANE_HIGH_STEP).max(LANE_HIGH_MIN);
+        } else if success_rate >= self.bounds.target_success_rate
+            && p90 + p90 / 2 < self.max_decode_attempts
+        {
+            // Trim gradually toward 1.5x the p90 usage so bursts still fit.
+            let target = (p90 + p90 / 2).max(self.max_decode_attempts * 4 / 5);
+            self.max_decode_attempts = target.max(self.bounds.min_decode_attempts);
+            self.lane_high_frac = (self.lane_high_frac + LANE_HIGH_STEP).min(LANE_HIGH_MAX);
+        }
+        if (self.max_decode_attempts, self.lane_high_frac) != previous {
+            self.adjustments += 1;
+            self.cooldown = self.bounds.window / 4;
+        }
+    }
+
+    /// Snapshot of the current budget and rolling statistics.
+    pub fn operating_point(&self) -> BudgetOperatingPoint {
+        let n = self.history.len();
+        let (success_rate, mean_attempts_used) = if n == 0 {
+            (0.0, 0.0)
+        } else {
+            let ok = self.history.iter().filter(|s| s.success).count();
+            let used: usize = self.history.iter().map(|s| s.attempts_used).sum();
+            (ok as f32 / n as f32, used as f32 / n as f32)
+        };
+        BudgetOperatingPoint {
+            max_decode_attempts: self.max_decode_attempts,
+            lane_high_frac: self.lane_high_frac,
+            lane_medium_frac: LANE_MEDIUM_FRAC,
+            lane_low_frac: 1.0 - self.lane_high_frac - LANE_MEDIUM_FRAC,
+            success_rate,
+            mean_attempts_used,
+            frames_observed: n,
+            adjustments: self.adjustments,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easy_stream_shrinks_to_lower_bound() {
+        let mut ctl = AdaptiveBudget::new(BudgetBounds {
+            min_decode_attempts: 6,
+            ..BudgetBounds::default()
+        });
+        let start = ctl.operating_point().max_decode_attempts;
+        for _ in 0..400 {
+            ctl.observe(2, true);
+        }
+        let op = ctl.operating_point();
+        assert!(op.max_decode_attempts < start);
+        assert_eq!(op.max_decode_attempts, 6);
+        assert!(op.lane_high_frac > 0.5);
+        assert!((op.success_rate - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn saturated_failures_grow_within_bounds() {
+        let mut ctl = AdaptiveBudget::new(BudgetBounds {
+            max_decode_attempts: 100,
+            ..BudgetBounds::default()
+        });
+        for _ in 0..400 {
+            let budget = ctl.current_override().max_decode_attempts;
+            ctl.observe(budget, false);
+        }
+        let op = ctl.operating_point();
+        assert_eq!(op.max_decode_attempts, 100);
+        assert!(op.lane_high_frac < 0.5);
+        assert!(op.lane_low_frac > 0.2);
+        assert!(op.adjustments > 0);
+    }
+
+    #[test]
+    fn unsaturated_failures_hold_budget() {
+        let mut ctl = AdaptiveBudget::new(BudgetBounds::default());
+        let start = ctl.operating_point();
+        for _ in 0..64 {
+            ctl.observe(3, false);
+        }
+        assert_eq!(
+            ctl.operating_point().max_decode_attempts,
+            start.max_decode_attempts
+        );
+        assert_eq!(ctl.operating_point().adjustments, 0);
+    }
+}
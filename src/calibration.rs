@@ -0,0 +1,261 @@
+//! Confidence calibration
+//!
+//! The decoder's internal quality score is a blend of geometry and payload
+//! heuristics with no probabilistic meaning. A `ConfidenceCalibration` maps it
+//! to the empirical probability that a decode is correct, fitted offline with
+//! `qrtool calibrate-confidence` against labeled datasets, so callers can set
+//! thresholds such as "auto-accept at 0.98, review below".
+
+use std::fmt::Write as _;
+
+/// Default `(score, probability)` knots, both non-decreasing. Conservative
+/// rather than fitted: `qrtool calibrate-confidence --root
+/// benches/images/custom/decoding` sees 26 decodes, all correct, at scores
+/// 0.82 to 1.00, which cannot separate good decodes from bad ones. Within
+/// that range the table follows the smoothed accuracy of those decodes
+/// (0.95 by 0.96); below it, where no decode was seen, it falls towards
+/// 0.10, and a perfect score is extrapolated to 0.99. Calibrated
+/// confidences therefore span 0.10 to 0.99, and a `min_confidence` above
+/// 0.99 keeps no code. Refit once a labeled set with wrong decodes exists.
+const BUILTIN_TABLE: &[(f32, f32)] = &[
+    (0.0000, 0.1000),
+    (0.5000, 0.5000),
+    (0.8200, 0.9000),
+    (0.9642, 0.9500),
+    (1.0000, 0.9900),
+];
+
+/// Monotone piecewise-linear map from internal score to probability of a
+/// correct decode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceCalibration {
+    points: Vec<(f32, f32)>,
+}
+
+impl Default for ConfidenceCalibration {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ConfidenceCalibration {
+    /// The table shipped with the crate.
+    pub fn builtin() -> Self {
+        Self {
+            points: BUILTIN_TABLE.to_vec(),
+        }
+    }
+
+    /// Build from `(score, probability)` knots. Returns `None` unless there
+    /// are at least two knots, scores strictly increase, probabilities never
+    /// decrease and every value lies in `[0, 1]`.
+    pub fn from_points(points: Vec<(f32, f32)>) -> Option<Self> {
+        let in_unit = |v: f32| (0.0..=1.0).contains(&v);
+        let valid = points.len() >= 2
+            && points.iter().all(|&(s, p)| in_unit(s) && in_unit(p))
+            && points
+                .windows(2)
+                .all(|w| w[0].0 < w[1].0 && w[0].1 <= w[1].1);
+        valid.then_some(Self { points })
+    }
+
+    /// Fit a calibration to `(score, correct)` samples: scores are split into
+    /// up to `bins` equal-count bins, each bin's accuracy becomes a knot at its
+    /// mean score, and adjacent knots are pooled until accuracy is monotone.
+    /// Accuracy is `(correct + 1) / (n + 2)`, so a few clean decodes never
+    /// claim certainty.
+    pub fn fit(samples: &[(f32, bool)], bins: usize) -> Option<Self> {
+        if samples.len() < 2 || bins == 0 {
+            return None;
+        }
+        let mut sorted: Vec<(f32, bool)> = samples
+            .iter()
+            .copied()
+            .filter(|(s, _)| s.is_finite())
+            .map(|(s, c)| (s.clamp(0.0, 1.0), c))
+            .collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // (score sum, correct count, sample count) per block.
+        let accuracy = |(_, correct, n): (f32, f32, f32)| (correct + 1.0) / (n + 2.0);
+        let per_bin = sorted.len().div_ceil(bins.min(sorted.len()));
+        let mut blocks: Vec<(f32, f32, f32)> = Vec::new();
+        for chunk in sorted.chunks(per_bin) {
+            let score: f32 = chunk.iter().map(|(s, _)| s).sum();
+            let correct = chunk.iter().filter(|(_, c)| *c).count() as f32;
+            blocks.push((score, correct, chunk.len() as f32));
+            // Pool adjacent violators.
+            while blocks.len() >= 2 {
+                let (a, b) = (blocks[blocks.len() - 2], blocks[blocks.len() - 1]);
+                if accuracy(a) <= accuracy(b) {
+                    break;
+                }
+                blocks.pop();
+                *blocks.last_mut().expect("two blocks") = (a.0 + b.0, a.1 + b.1, a.2 + b.2);
+            }
+        }
+
+        let mut points: Vec<(f32, f32)> = Vec::with_capacity(blocks.len() + 2);
+        for block in blocks {
+            let knot = (block.0 / block.2, accuracy(block));
+            match points.last_mut() {
+                // Blocks whose mean scores coincide collapse into one knot.
+                Some(last) if knot.0 <= last.0 => last.1 = last.1.max(knot.1),
+                _ => points.push(knot),
+            }
+        }
+        let first = points[0];
+        if first.0 > 0.0 {
+            points.insert(0, (0.0, first.1));
+        }
+        let last = points[points.len() - 1];
+        if last.0 < 1.0 {
+            points.push((1.0, last.1));
+        }
+        Self::from_points(points)
+    }
+
+    /// Estimated probability that a decode with internal `score` is correct.
+    pub fn probability(&self, score: f32) -> f32 {
+        interpolate(&self.points, score)
+    }
+
+    /// Lowest score whose calibrated probability reaches `target`, if any.
+    pub fn score_for(&self, target: f32) -> Option<f32> {
+        let idx = self.points.iter().position(|&(_, p)| p >= target)?;
+        if idx == 0 {
+            return Some(self.points[0].0);
+        }
+        let (s0, p0) = self.points[idx - 1];
+        let (s1, p1) = self.points[idx];
+        Some(s0 + (s1 - s0) * (target - p0) / (p1 - p0))
+    }
+
+    /// The `(score, probability)` knots.
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Serialize as one `score probability` pair per line.
+    pub fn to_table(&self) -> String {
+        let mut out = String::from("# score probability\n");
+        for (s, p) in &self.points {
+            let _ = writeln!(out, "{s:.4} {p:.4}");
+        }
+        out
+    }
+
+    /// Parse the format written by [`Self::to_table`]; `#` starts a comment.
+    pub fn parse_table(text: &str) -> Result<Self, String> {
+        let mut points = Vec::new();
+        for (lineno, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = line.split_whitespace().map(str::parse::<f32>);
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(Ok(s)), Some(Ok(p)), None) => points.push((s, p)),
+                _ => return Err(format!("line {}: expected `score probability`", lineno + 1)),
+            }
+        }
+        Self::from_points(points).ok_or_else(|| {
+            "calibration needs increasing scores and non-decreasing probabilities in [0, 1]"
+                .to_string()
+        })
+    }
+}
+
+/// Calibrate `score` with the active [`crate::DetectorConfig`] table, or the
+/// built-in one.
+pub(crate) fn calibrated(score: f32) -> f32 {
+    crate::config::with_active(|config| match &config.confidence_calibration {
+        Some(calibration) => calibration.probability(score),
+        None => interpolate(BUILTIN_TABLE, score),
+    })
+}
+
+/// Lowest internal score that calibrates to `min_confidence` under the
+/// active table, so gates can compare scores; infinite when no score does,
+/// as for anything above 0.99 under the built-in table.
+pub(crate) fn score_floor(min_confidence: f32) -> f32 {
+    if min_confidence <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    crate::config::with_active(|config| {
+        let floor = match &config.confidence_calibration {
+            Some(calibration) => calibration.score_for(min_confidence),
+            None => ConfidenceCalibration::builtin().score_for(min_confidence),
+        };
+        floor.unwrap_or(f32::INFINITY)
+    })
+}
+
+fn interpolate(points: &[(f32, f32)], score: f32) -> f32 {
+    let Some(&(first_s, first_p)) = points.first() else {
+        return score.clamp(0.0, 1.0);
+    };
+    if score <= first_s || score.is_nan() {
+        return first_p;
+    }
+    for w in points.windows(2) {
+        let ((s0, p0), (s1, p1)) = (w[0], w[1]);
+        if score <= s1 {
+            return p0 + (p1 - p0) * (score - s0) / (s1 - s0);
+        }
+    }
+    points[points.len() - 1].1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_is_valid_and_monotone() {
+        let builtin = ConfidenceCalibration::builtin();
+        assert!(ConfidenceCalibration::from_points(builtin.points().to_vec()).is_some());
+        let mut prev = 0.0;
+        for i in 0..=20 {
+            let p = builtin.probability(i as f32 / 20.0);
+            assert!(p >= prev);
+            prev = p;
+        }
+        // Thresholds up to 0.99 stay reachable; low scores stay doubtful.
+        assert!(builtin.score_for(0.98).is_some_and(|s| s < 1.0));
+        assert!(builtin.score_for(0.995).is_none());
+        assert!(builtin.probability(0.3) < 0.5);
+    }
+
+    #[test]
+    fn fit_recovers_monotone_accuracy() {
+        // Accuracy rises with score; a noisy dip at 0.5 must be pooled away.
+        let mut samples = Vec::new();
+        for i in 0..1000 {
+            let score = i as f32 / 1000.0;
+            let correct = if (0.45..0.55).contains(&score) {
+                i % 4 == 0
+            } else {
+                (i * 7919) % 1000 < i
+            };
+            samples.push((score, correct));
+        }
+        let cal = ConfidenceCalibration::fit(&samples, 10).unwrap();
+        assert!(cal.points().windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(cal.probability(0.05) < 0.2);
+        assert!(cal.probability(0.95) > 0.8);
+        assert!(cal.score_for(0.8).is_some_and(|s| s > 0.5));
+        assert!(ConfidenceCalibration::fit(&samples[..1], 10).is_none());
+    }
+
+    #[test]
+    fn table_round_trips() {
+        let cal = ConfidenceCalibration::builtin();
+        let parsed = ConfidenceCalibration::parse_table(&cal.to_table()).unwrap();
+        for (a, b) in cal.points().iter().zip(parsed.points()) {
+            assert!((a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3);
+        }
+        assert!(ConfidenceCalibration::parse_table("0.5 0.9\n0.4 0.95\n").is_err());
+        assert!(ConfidenceCalibration::parse_table("0.5\n").is_err());
+    }
+}
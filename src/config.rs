@@ -0,0 +1,554 @@
+//! Detector-wide options
+//!
+//! A `DetectorConfig` is applied to the current thread for the duration of a
+//! detection call, so deep pipeline stages can consult it without every
+//! internal signature growing a parameter.
+
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::models::{Binarization, Point};
+
+/// Options for [`crate::detect_with_config`] and [`crate::Detector::with_config`].
+#[derive(Debug, Clone, Default)]
+pub struct DetectorConfig {
+    /// Accept codes printed without a quiet zone: finder patterns whose outer
+    /// ring touches the image edge or adjacent dark content are kept, and
+    /// grouping tolerates the noisier module sizes they produce.
+    pub tolerate_missing_quiet_zone: bool,
+    /// Also look for finder patterns with reversed polarity, as on metallic
+    /// and laser-etched direct part marks whose rings read light while the
+    /// data reads normally. Only the finder blocks of such candidates are
+    /// flipped for the orientation check; format and data are read as
+    /// sampled. Off by default: the inverted scan doubles finder detection
+    /// and adds false candidates on ordinary prints.
+    pub inverted_finders: bool,
+    /// Table mapping the internal score to `QRCode::confidence`; `None` uses
+    /// the built-in calibration.
+    pub confidence_calibration: Option<crate::ConfidenceCalibration>,
+    /// Drop codes whose calibrated `QRCode::confidence` falls below this.
+    /// Candidates whose geometry caps them under it are never decoded, so
+    /// the budget goes to ones that can pass. `None` keeps every code.
+    pub min_confidence: Option<f32>,
+    /// Character set for byte-mode segments not covered by an ECI designator;
+    /// `None` reads valid UTF-8 as UTF-8 and anything else as ISO-8859-1.
+    pub byte_encoding: Option<crate::TextEncoding>,
+    /// Keep raw and corrected codewords and the unmasked module grid on each
+    /// result (see [`crate::QRCode::raw_codewords`]). Off by default: it
+    /// costs a few KB per decoded symbol.
+    pub keep_intermediate_data: bool,
+    /// Keep each grid that read as far as error correction or payload
+    /// parsing without decoding in
+    /// [`crate::DetectionTelemetry::failed_grids`], as training and debug
+    /// data for hard cases. Off by default: every record copies its grid.
+    pub export_failed_grids: bool,
+    /// Keep decoding candidates that overlap a region already read at EC
+    /// level H with a high acceptance score. Off by default, which skips
+    /// them to save budget; enable when nested or overprinted symbols must
+    /// all be found.
+    pub decode_solved_regions: bool,
+    /// Sample module grids with integer arithmetic only, for targets without
+    /// an FPU. Building with the `fixed-point` feature makes this the
+    /// default for every call.
+    pub fixed_point_sampling: bool,
+    /// Width over height of one pixel, for cameras and anamorphic optics
+    /// with non-square pixels. Finder grouping and version estimation work
+    /// in square-pixel coordinates; `None` treats pixels as square.
+    pub pixel_aspect_ratio: Option<f32>,
+    /// Smallest module size, in pixels, of the codes to find. Finder
+    /// scanning then skips lines in proportion to it and densifies around
+    /// hits, a large speedup for close-up frames with big modules; smaller
+    /// codes may be missed. `None` scans every line.
+    pub min_module_size: Option<f32>,
+    /// When finder scanning adds a column pass to the row pass. The default
+    /// [`crate::ColumnScan::Auto`] skips it once the rows are conclusive;
+    /// `Always` restores the full scan for heavily rotated material.
+    pub column_scan: crate::ColumnScan,
+    /// Working-memory cap in bytes for one detection call, not counting the
+    /// caller's image (or, for [`crate::detect_image_ref`], its grayscale
+    /// conversion). RGB and grayscale entry points alike honour it. Frames
+    /// that would exceed it take a cheaper route (no fallback binarizations
+    /// or pyramid, then a downscaled frame, then overlapping tiles) and
+    /// their codes come back with [`crate::QRCode::memory_degraded`] set.
+    /// `None` is uncapped.
+    pub max_memory_bytes: Option<usize>,
+    /// Most worker threads the parallel scan paths may use. `None` shares
+    /// the global rayon pool; `Some(n)` runs them on a dedicated pool of `n`
+    /// threads, kept for reuse, so a host's own pool is left alone.
+    /// `Some(1)` (and `Some(0)`) runs everything sequentially on the calling
+    /// thread, for latency-sensitive real-time threads.
+    pub max_threads: Option<usize>,
+    /// How pixels map to millimetres, to fill in
+    /// [`crate::QRCode::physical_size_mm`]; `None` leaves it unset.
+    pub physical_scale: Option<PhysicalScale>,
+    /// Distortion of a wide-angle or fisheye lens. Finder positions are
+    /// undistorted before each sampling transform is fitted, and module
+    /// sample points are distorted back onto the image, so symbols bent by
+    /// the lens are read as the flat grids they are; the image itself is
+    /// not resampled. `None` assumes an ideal pinhole camera.
+    pub lens: Option<LensModel>,
+    /// Largest frame, in pixels, a call will take on; bigger frames are
+    /// shed (see [`crate::load_shed`]) before any work. `None` is unlimited.
+    pub max_pixels: Option<usize>,
+    /// Most finder candidates one binarization may produce before the call
+    /// is shed rather than grouping and decoding them all. `None` is
+    /// unlimited.
+    pub max_candidates: Option<usize>,
+    /// Consulted before each heavy stage; refusing one sheds the call.
+    pub load_shed_policy: Option<Arc<dyn crate::LoadShedPolicy>>,
+    /// Encoder spec violations to tolerate, tried only after a strict read
+    /// fails; [`crate::DetectionTelemetry::quirk_rescues`] counts the
+    /// decodes each one rescued. Empty (the default) reads strictly;
+    /// `EncoderQuirk::ALL.to_vec()` is the fully lenient mode.
+    pub encoder_quirks: Vec<EncoderQuirk>,
+    /// Recovery subsystems switched off, for measuring what each one
+    /// contributes to the reading rate. Empty (the default) runs them all.
+    pub ablations: Vec<Ablation>,
+}
+
+/// A known way encoders in the wild break the QR specification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncoderQuirk {
+    /// Format information written without its `0x5412` mask.
+    UnmaskedFormat,
+    /// The data mask applied over the format modules as well. Each mask is
+    /// undone in turn, and kept only when the format it reveals names it.
+    MaskedFormatArea,
+    /// No dark module, its position used for a data bit instead. A light
+    /// dark module alone never stops a strict read; this covers the shifted
+    /// bit stream that follows.
+    MissingDarkModule,
+    /// A quiet zone narrower than four modules: when nothing else decodes,
+    /// the fast path runs again as with
+    /// [`DetectorConfig::tolerate_missing_quiet_zone`].
+    NarrowQuietZone,
+    /// Data placed in another module order than the specification's: the
+    /// two columns of each strip swapped, or the first strip run downward.
+    /// A strict read follows only the specified placement.
+    NonstandardTraversal,
+}
+
+impl EncoderQuirk {
+    /// Every rule, in the order [`crate::DetectionTelemetry::quirk_rescues`]
+    /// counts them
+    pub const ALL: [EncoderQuirk; 5] = [
+        EncoderQuirk::UnmaskedFormat,
+        EncoderQuirk::MaskedFormatArea,
+        EncoderQuirk::MissingDarkModule,
+        EncoderQuirk::NarrowQuietZone,
+        EncoderQuirk::NonstandardTraversal,
+    ];
+
+    /// Short snake_case name for logs and reports
+    pub fn name(self) -> &'static str {
+        match self {
+            EncoderQuirk::UnmaskedFormat => "unmasked_format",
+            EncoderQuirk::MaskedFormatArea => "masked_format_area",
+            EncoderQuirk::MissingDarkModule => "missing_dark_module",
+            EncoderQuirk::NarrowQuietZone => "narrow_quiet_zone",
+            EncoderQuirk::NonstandardTraversal => "nonstandard_traversal",
+        }
+    }
+
+    /// Slot in the per-quirk telemetry arrays
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A recovery subsystem that [`DetectorConfig::ablations`] can switch off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ablation {
+    /// Guessing the third finder when a binarization finds only two.
+    TwoFinder,
+    /// Re-decoding a low-contrast frame's finder region with its local
+    /// contrast stretched.
+    RoiNormalization,
+    /// Mesh-warped re-sampling of rotated and bent symbols.
+    Deskew,
+    /// Re-sampling small or dense grids with larger sample kernels.
+    ScaleRetry,
+    /// Reed-Solomon decoding with low-confidence modules as erasures.
+    RsErasure,
+    /// Contour-based finder detection in the fallback binarizations.
+    ContourDetector,
+}
+
+impl Ablation {
+    /// Every subsystem, in pipeline order
+    pub const ALL: [Ablation; 6] = [
+        Ablation::TwoFinder,
+        Ablation::RoiNormalization,
+        Ablation::Deskew,
+        Ablation::ScaleRetry,
+        Ablation::RsErasure,
+        Ablation::ContourDetector,
+    ];
+
+    /// Short snake_case name, as the recovery telemetry names the path
+    pub fn name(self) -> &'static str {
+        match self {
+            Ablation::TwoFinder => "two_finder",
+            Ablation::RoiNormalization => "roi_norm",
+            Ablation::Deskew => "deskew",
+            Ablation::ScaleRetry => "scale_retry",
+            Ablation::RsErasure => "rs_erasure",
+            Ablation::ContourDetector => "contour",
+        }
+    }
+
+    /// The subsystem called `name`, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+/// How image pixels map to millimetres on the plane of a code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PhysicalScale {
+    /// A flat scan or render at this resolution, e.g. the Exif
+    /// `XResolution`/`YResolution` of the file.
+    Dpi { x: f32, y: f32 },
+    /// A pinhole camera with focal lengths in pixels (the `fx`/`fy`
+    /// intrinsics) facing a code `distance_mm` from the lens.
+    Camera {
+        focal_x_px: f32,
+        focal_y_px: f32,
+        distance_mm: f32,
+    },
+}
+
+impl PhysicalScale {
+    /// Millimetres per pixel along x and y, or `None` unless every parameter
+    /// is a positive finite number.
+    pub fn mm_per_pixel(&self) -> Option<(f32, f32)> {
+        let (sx, sy) = match *self {
+            Self::Dpi { x, y } => (25.4 / x, 25.4 / y),
+            Self::Camera {
+                focal_x_px,
+                focal_y_px,
+                distance_mm,
+            } => (distance_mm / focal_x_px, distance_mm / focal_y_px),
+        };
+        let valid = |v: f32| v.is_finite() && v > 0.0;
+        (valid(sx) && valid(sy)).then_some((sx, sy))
+    }
+}
+
+/// Brown-Conrady lens model: camera intrinsics in pixels plus radial
+/// (`k1`, `k2`) and tangential (`p1`, `p2`) distortion coefficients, as
+/// reported by common calibration tools.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LensModel {
+    /// Focal length along x (pixels)
+    pub fx: f32,
+    /// Focal length along y (pixels)
+    pub fy: f32,
+    /// Principal point x (pixels)
+    pub cx: f32,
+    /// Principal point y (pixels)
+    pub cy: f32,
+    /// First radial coefficient; negative for barrel distortion
+    pub k1: f32,
+    /// Second radial coefficient
+    pub k2: f32,
+    /// First tangential coefficient
+    pub p1: f32,
+    /// Second tangential coefficient
+    pub p2: f32,
+}
+
+impl LensModel {
+    /// Where the lens images the ideal (pinhole) pixel `p`
+    pub fn distort(&self, p: Point) -> Point {
+        let (x, y) = ((p.x - self.cx) / self.fx, (p.y - self.cy) / self.fy);
+        let (dx, dy) = self.distort_normalized(x, y);
+        Point::new(dx * self.fx + self.cx, dy * self.fy + self.cy)
+    }
+
+    /// The ideal pixel the lens images at `p`; inverts [`Self::distort`] by
+    /// fixed-point iteration, which converges for the moderate distortion
+    /// of real lenses within the image.
+    pub fn undistort(&self, p: Point) -> Point {
+        let (xd, yd) = ((p.x - self.cx) / self.fx, (p.y - self.cy) / self.fy);
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..10 {
+            let r2 = x * x + y * y;
+            let radial = 1.0 + r2 * (self.k1 + r2 * self.k2);
+            if radial.abs() < 1e-6 {
+                break;
+            }
+            let tx = 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x);
+            let ty = self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y;
+            (x, y) = ((xd - tx) / radial, (yd - ty) / radial);
+        }
+        Point::new(x * self.fx + self.cx, y * self.fy + self.cy)
+    }
+
+    fn distort_normalized(&self, x: f32, y: f32) -> (f32, f32) {
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * self.k2);
+        (
+            x * radial + 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x),
+            y * radial + self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y,
+        )
+    }
+
+    fn is_valid(&self) -> bool {
+        let values = [
+            self.fx, self.fy, self.cx, self.cy, self.k1, self.k2, self.p1, self.p2,
+        ];
+        values.iter().all(|v| v.is_finite()) && self.fx > 0.0 && self.fy > 0.0
+    }
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<DetectorConfig>> = const { RefCell::new(None) };
+    static BINARIZATION: Cell<Option<Binarization>> = const { Cell::new(None) };
+}
+
+impl DetectorConfig {
+    /// Run `f` with this configuration applied to every detection call made
+    /// on the current thread.
+    pub fn apply<R>(&self, f: impl FnOnce() -> R) -> R {
+        let _scope = ActiveScope(ACTIVE.with(|a| a.replace(Some(self.clone()))));
+        f()
+    }
+}
+
+/// Restores the enclosing configuration when dropped, so a panic inside
+/// [`DetectorConfig::apply`] does not leave this one active on the thread.
+struct ActiveScope(Option<DetectorConfig>);
+
+impl Drop for ActiveScope {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        ACTIVE.with(|a| *a.borrow_mut() = prev);
+    }
+}
+
+/// Read a value from the active configuration (defaults when none is applied).
+pub(crate) fn with_active<R>(f: impl FnOnce(&DetectorConfig) -> R) -> R {
+    ACTIVE.with(|a| match a.borrow().as_ref() {
+        Some(config) => f(config),
+        None => f(&DetectorConfig::default()),
+    })
+}
+
+/// Configured pixel aspect ratio, or 1.0 when unset or not a positive number.
+pub(crate) fn pixel_aspect_ratio() -> f32 {
+    with_active(|c| c.pixel_aspect_ratio)
+        .filter(|r| r.is_finite() && *r > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Configured confidence floor in `[0, 1]`, or 0 when unset or not a number.
+pub(crate) fn min_confidence() -> f32 {
+    with_active(|c| c.min_confidence)
+        .filter(|m| !m.is_nan())
+        .map_or(0.0, |m| m.clamp(0.0, 1.0))
+}
+
+/// Configured lens model, unless unset or with a non-positive or
+/// non-finite parameter.
+pub(crate) fn lens_model() -> Option<LensModel> {
+    with_active(|c| c.lens).filter(LensModel::is_valid)
+}
+
+/// Run `f` with the configured lens re-centred on a crop whose top-left
+/// corner sits at `(x0, y0)` in the frame, so points in crop coordinates
+/// undistort as they would in the frame.
+pub(crate) fn with_crop_origin<R>(x0: f32, y0: f32, f: impl FnOnce() -> R) -> R {
+    with_lens_adjusted(
+        |lens| {
+            lens.cx -= x0;
+            lens.cy -= y0;
+        },
+        f,
+    )
+}
+
+/// Run `f` with the configured lens scaled to a frame downscaled by
+/// `factor`, so points in the small frame undistort as they would at full
+/// size.
+pub(crate) fn with_downscale<R>(factor: f32, f: impl FnOnce() -> R) -> R {
+    with_lens_adjusted(
+        |lens| {
+            lens.fx /= factor;
+            lens.fy /= factor;
+            lens.cx /= factor;
+            lens.cy /= factor;
+        },
+        f,
+    )
+}
+
+fn with_lens_adjusted<R>(adjust: impl FnOnce(&mut LensModel), f: impl FnOnce() -> R) -> R {
+    let Some(mut config) = ACTIVE
+        .with(|a| a.borrow().clone())
+        .filter(|c| c.lens.is_some())
+    else {
+        return f();
+    };
+    if let Some(lens) = config.lens.as_mut() {
+        adjust(lens);
+    }
+    config.apply(f)
+}
+
+/// Run `f` with the codes it decodes attributed to `binarization`; an inner
+/// call (a crop re-binarized inside a pass) overrides an outer one.
+pub(crate) fn with_binarization<R>(binarization: Binarization, f: impl FnOnce() -> R) -> R {
+    let prev = BINARIZATION.with(|b| b.replace(Some(binarization)));
+    let out = f();
+    BINARIZATION.with(|b| b.set(prev));
+    out
+}
+
+/// The binarization set by the innermost [`with_binarization`], if any.
+pub(crate) fn active_binarization() -> Option<Binarization> {
+    BINARIZATION.with(Cell::get)
+}
+
+/// Whether the active configuration tolerates `quirk`.
+pub(crate) fn tolerates(quirk: EncoderQuirk) -> bool {
+    with_active(|c| c.encoder_quirks.contains(&quirk))
+}
+
+/// Whether the active configuration switches `subsystem` off.
+pub(crate) fn ablated(subsystem: Ablation) -> bool {
+    with_active(|c| c.ablations.contains(&subsystem))
+}
+
+/// Run `f` where the active [`DetectorConfig::max_threads`] allows: on the
+/// global rayon pool when unset, installed in a dedicated pool of that many
+/// threads otherwise. `f` is told whether to go parallel at all; it is
+/// called with `false` on the calling thread under a limit of one thread,
+/// or when the dedicated pool cannot be built.
+pub(crate) fn with_worker_pool<R: Send>(f: impl FnOnce(bool) -> R + Send) -> R {
+    match with_active(|c| c.max_threads) {
+        None => f(true),
+        Some(threads) if threads <= 1 => f(false),
+        Some(threads) => match worker_pool(threads) {
+            Some(pool) => pool.install(|| f(true)),
+            None => f(false),
+        },
+    }
+}
+
+/// The shared pool of `threads` workers, built on first use.
+fn worker_pool(threads: usize) -> Option<Arc<ThreadPool>> {
+    static POOLS: Mutex<Vec<(usize, Arc<ThreadPool>)>> = Mutex::new(Vec::new());
+    let mut pools = POOLS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((_, pool)) = pools.iter().find(|(n, _)| *n == threads) {
+        return Some(Arc::clone(pool));
+    }
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("rustqr-worker-{i}"))
+        .build()
+        .ok()
+        .map(Arc::new)?;
+    pools.push((threads, Arc::clone(&pool)));
+    Some(pool)
+}
+
+/// Physical side length of `code` under the configured scale, if any.
+pub(crate) fn physical_size_mm(code: &crate::QRCode) -> Option<f32> {
+    with_active(|c| c.physical_scale).and_then(|scale| code.measure_physical_size(&scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_is_scoped_and_nests() {
+        assert!(!with_active(|c| c.tolerate_missing_quiet_zone));
+        let tolerant = DetectorConfig {
+            tolerate_missing_quiet_zone: true,
+            ..Default::default()
+        };
+        tolerant.apply(|| {
+            assert!(with_active(|c| c.tolerate_missing_quiet_zone));
+            DetectorConfig::default().apply(|| {
+                assert!(!with_active(|c| c.tolerate_missing_quiet_zone));
+            });
+            assert!(with_active(|c| c.tolerate_missing_quiet_zone));
+        });
+        assert!(!with_active(|c| c.tolerate_missing_quiet_zone));
+    }
+
+    #[test]
+    fn apply_restores_the_config_after_a_panic() {
+        let tolerant = DetectorConfig {
+            tolerate_missing_quiet_zone: true,
+            ..Default::default()
+        };
+        let caught = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            tolerant.apply(|| panic!("inside apply"))
+        }));
+        assert!(caught.is_err());
+        assert!(!with_active(|c| c.tolerate_missing_quiet_zone));
+    }
+
+    #[test]
+    fn ablation_names_round_trip() {
+        for subsystem in Ablation::ALL {
+            assert_eq!(Ablation::from_name(subsystem.name()), Some(subsystem));
+        }
+        assert_eq!(Ablation::from_name("everything"), None);
+        let config = DetectorConfig {
+            ablations: vec![Ablation::Deskew],
+            ..Default::default()
+        };
+        config.apply(|| {
+            assert!(ablated(Ablation::Deskew));
+            assert!(!ablated(Ablation::ScaleRetry));
+        });
+        assert!(!ablated(Ablation::Deskew));
+    }
+
+    #[test]
+    fn max_threads_bounds_the_worker_pool() {
+        let caller = std::thread::current().id();
+        let limited = |threads| DetectorConfig {
+            max_threads: Some(threads),
+            ..Default::default()
+        };
+
+        let sequential = limited(1)
+            .apply(|| with_worker_pool(|parallel| (parallel, std::thread::current().id())));
+        assert_eq!(sequential, (false, caller));
+
+        let pooled = limited(3)
+            .apply(|| with_worker_pool(|parallel| (parallel, rayon::current_num_threads())));
+        assert_eq!(pooled, (true, 3));
+        assert!(with_worker_pool(|parallel| parallel));
+    }
+
+    #[test]
+    fn lens_undistort_inverts_distort() {
+        let lens = LensModel {
+            fx: 500.0,
+            fy: 480.0,
+            cx: 320.0,
+            cy: 240.0,
+            k1: -0.28,
+            k2: 0.07,
+            p1: 0.001,
+            p2: -0.0015,
+        };
+        for (x, y) in [(320.0, 240.0), (20.0, 30.0), (600.0, 400.0), (100.0, 420.0)] {
+            let ideal = Point::new(x, y);
+            let back = lens.undistort(lens.distort(ideal));
+            assert!(back.distance(&ideal) < 0.05, "{x},{y} -> {back:?}");
+        }
+        // Barrel distortion pulls the corners in.
+        let corner = lens.distort(Point::new(0.0, 0.0));
+        assert!(corner.x > 0.0 && corner.y > 0.0);
+    }
+}
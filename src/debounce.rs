@@ -0,0 +1,222 @@
+//! Once-per-appearance result reporting for scanner apps
+//!
+//! A camera pointed at a code decodes it on most frames, with the odd frame
+//! missed to blur or glare. A [`ResultDebouncer`] turns that per-frame stream
+//! into one report per appearance: each code read is matched to a tracked
+//! symbol by payload and position, only codes that start a new track are
+//! passed on, and a track ends once its symbol has gone unseen for the
+//! cooldown.
+
+use std::time::{Duration, Instant};
+
+use crate::models::{Point, QRCode};
+
+/// Options for [`ResultDebouncer`] and [`crate::Detector::with_debouncer`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebounceConfig {
+    /// How long a symbol must go unseen before it counts as having left the
+    /// frame and is reported again on its next read
+    pub cooldown: Duration,
+    /// How far (in symbol widths) a symbol's centre may move between reads
+    /// and still count as the same symbol
+    pub max_drift: f32,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_millis(1000),
+            max_drift: 1.5,
+        }
+    }
+}
+
+/// A symbol seen recently: its payload, where it was and when.
+#[derive(Debug, Clone)]
+struct TrackedSymbol {
+    data: Vec<u8>,
+    center: Point,
+    side: f32,
+    last_seen: Instant,
+}
+
+/// Reports each code once until it leaves the frame.
+///
+/// Two symbols with the same payload in different places are tracked, and
+/// reported, separately.
+///
+/// ```
+/// use rust_qr::{DebounceConfig, ResultDebouncer};
+///
+/// let mut debouncer = ResultDebouncer::new(DebounceConfig::default());
+/// let frame = vec![255u8; 64 * 64 * 3];
+/// for _ in 0..3 {
+///     for code in debouncer.observe(rust_qr::detect(&frame, 64, 64)) {
+///         println!("{}", code.content);
+///     }
+/// }
+/// assert_eq!(debouncer.tracked(), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResultDebouncer {
+    config: DebounceConfig,
+    tracks: Vec<TrackedSymbol>,
+}
+
+impl ResultDebouncer {
+    /// Create a debouncer tracking nothing yet
+    pub fn new(config: DebounceConfig) -> Self {
+        Self {
+            config: DebounceConfig {
+                max_drift: config.max_drift.max(0.0),
+                ..config
+            },
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Feed one frame's codes, read now, and return those not already
+    /// tracked
+    pub fn observe(&mut self, codes: Vec<QRCode>) -> Vec<QRCode> {
+        self.observe_at(codes, Instant::now())
+    }
+
+    /// Like [`Self::observe`] for a frame captured at `now`, e.g. the
+    /// camera's timestamp
+    pub fn observe_at(&mut self, codes: Vec<QRCode>, now: Instant) -> Vec<QRCode> {
+        let cooldown = self.config.cooldown;
+        self.tracks
+            .retain(|track| now.saturating_duration_since(track.last_seen) <= cooldown);
+
+        // Tracks already claimed this frame, so two copies of one payload
+        // in view don't both land on the same track.
+        let mut claimed = vec![false; self.tracks.len()];
+        let mut fresh = Vec::new();
+        for code in codes {
+            let (center, side) = footprint(&code);
+            let matched = self.tracks.iter().enumerate().position(|(i, track)| {
+                !claimed[i]
+                    && track.data == code.data
+                    && track.center.distance(&center)
+                        <= self.config.max_drift * track.side.max(side)
+            });
+            match matched {
+                Some(i) => {
+                    claimed[i] = true;
+                    let track = &mut self.tracks[i];
+                    track.center = center;
+                    track.side = side;
+                    track.last_seen = now;
+                }
+                None => {
+                    self.tracks.push(TrackedSymbol {
+                        data: code.data.clone(),
+                        center,
+                        side,
+                        last_seen: now,
+                    });
+                    claimed.push(true);
+                    fresh.push(code);
+                }
+            }
+        }
+        fresh
+    }
+
+    /// Symbols currently tracked, i.e. seen within the cooldown
+    pub fn tracked(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// Forget every tracked symbol, so the next read of each is reported
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+/// Centre of a code's corners and its top edge length.
+fn footprint(code: &QRCode) -> (Point, f32) {
+    let [tl, tr, br, bl] = code.position;
+    let center = Point::new(
+        (tl.x + tr.x + br.x + bl.x) / 4.0,
+        (tl.y + tr.y + br.y + bl.y) / 4.0,
+    );
+    (center, tl.distance(&tr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ECLevel, MaskPattern, Version};
+
+    fn code_at(text: &str, x: f32, y: f32) -> QRCode {
+        let mut code = QRCode::new(
+            text.as_bytes().to_vec(),
+            text.to_string(),
+            Version::Model2(1),
+            ECLevel::M,
+            MaskPattern::Pattern0,
+        );
+        code.position = [
+            Point::new(x, y),
+            Point::new(x + 40.0, y),
+            Point::new(x + 40.0, y + 40.0),
+            Point::new(x, y + 40.0),
+        ];
+        code
+    }
+
+    fn contents(codes: &[QRCode]) -> Vec<&str> {
+        codes.iter().map(|c| c.content.as_str()).collect()
+    }
+
+    #[test]
+    fn codes_report_once_until_they_leave_the_frame() {
+        let mut debouncer = ResultDebouncer::new(DebounceConfig {
+            cooldown: Duration::from_millis(500),
+            max_drift: 1.0,
+        });
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        let first = debouncer.observe_at(vec![code_at("A", 10.0, 10.0)], at(0));
+        assert_eq!(contents(&first), ["A"]);
+        // Moving slowly, with one frame missed: still the same appearance.
+        assert!(
+            debouncer
+                .observe_at(vec![code_at("A", 20.0, 14.0)], at(33))
+                .is_empty()
+        );
+        assert!(debouncer.observe_at(Vec::new(), at(66)).is_empty());
+        assert!(
+            debouncer
+                .observe_at(vec![code_at("A", 30.0, 18.0)], at(100))
+                .is_empty()
+        );
+        assert_eq!(debouncer.tracked(), 1);
+
+        // Gone for longer than the cooldown, then back.
+        assert!(debouncer.observe_at(Vec::new(), at(700)).is_empty());
+        assert_eq!(debouncer.tracked(), 0);
+        let again = debouncer.observe_at(vec![code_at("A", 30.0, 18.0)], at(800));
+        assert_eq!(contents(&again), ["A"]);
+    }
+
+    #[test]
+    fn same_payload_elsewhere_is_another_symbol() {
+        let mut debouncer = ResultDebouncer::new(DebounceConfig::default());
+        let now = Instant::now();
+        let both = vec![code_at("A", 10.0, 10.0), code_at("A", 300.0, 10.0)];
+        assert_eq!(
+            contents(&debouncer.observe_at(both.clone(), now)),
+            ["A", "A"]
+        );
+        assert!(debouncer.observe_at(both, now).is_empty());
+
+        let moved = vec![code_at("A", 10.0, 10.0), code_at("B", 300.0, 10.0)];
+        assert_eq!(contents(&debouncer.observe_at(moved, now)), ["B"]);
+        assert_eq!(debouncer.tracked(), 3);
+        debouncer.reset();
+        assert_eq!(debouncer.tracked(), 0);
+    }
+}
@@ -48,6 +48,23 @@ pub(crate) fn strict_fallback_version_match() -> bool {
         .get_or_init(|| parse_env_bool_u8("QR_STRICT_FALLBACK_VERSION_MATCH", false))
 }
 
+static FALLBACK_HEADER_PREFILTER: OnceLock<bool> = OnceLock::new();
+
+/// Skip brute-forced format guesses whose unmasked stream can't open with a
+/// segment header that fits the symbol.
+pub(crate) fn fallback_header_prefilter() -> bool {
+    *FALLBACK_HEADER_PREFILTER
+        .get_or_init(|| parse_env_bool_u8("QR_FALLBACK_HEADER_PREFILTER", true))
+}
+
+static ROTATED_FORMAT_FALLBACK: OnceLock<bool> = OnceLock::new();
+
+/// Read format info from the grid turned by quarter turns when neither
+/// upright copy passes BCH, ahead of the format brute force.
+pub(crate) fn rotated_format_fallback() -> bool {
+    *ROTATED_FORMAT_FALLBACK.get_or_init(|| parse_env_bool_u8("QR_ROTATED_FORMAT_FALLBACK", true))
+}
+
 static RELAXED_FINDER_MISMATCH: OnceLock<usize> = OnceLock::new();
 
 pub(crate) fn relaxed_finder_mismatch() -> usize {
@@ -102,6 +119,77 @@ pub(crate) fn image_decode_attempt_budget() -> usize {
         .get_or_init(|| parse_env_usize("QR_MAX_IMAGE_DECODE_ATTEMPTS", 72).max(1))
 }
 
+static POLICY_ATTEMPT_RESERVE: OnceLock<usize> = OnceLock::new();
+
+/// Decode attempts held back from the per-image budget for every binarization
+/// fallback still to run, so one policy cannot spend the whole budget.
+/// Unused attempts roll forward to the next policy. Default: 12
+pub(crate) fn policy_attempt_reserve() -> usize {
+    *POLICY_ATTEMPT_RESERVE.get_or_init(|| parse_env_usize("QR_POLICY_ATTEMPT_RESERVE", 12))
+}
+
+static REGION_BINARIZATION: OnceLock<bool> = OnceLock::new();
+
+/// Re-binarize each candidate's region with an adaptive window sized from its
+/// finder module size and decode against that instead of the frame-wide
+/// binary. Default: on (0 = use the frame-wide binary)
+pub(crate) fn region_binarization() -> bool {
+    *REGION_BINARIZATION.get_or_init(|| parse_env_bool_u8("QR_REGION_BINARIZATION", true))
+}
+
+static DECODE_MEMO: OnceLock<bool> = OnceLock::new();
+
+/// Remember candidates whose region decode failed at Reed-Solomon and skip
+/// the same geometry under later binarizations of the image, which would
+/// re-binarize the same region the same way. Default: on (0 = retry them)
+pub(crate) fn decode_memo() -> bool {
+    *DECODE_MEMO.get_or_init(|| parse_env_bool_u8("QR_DECODE_MEMO", true))
+}
+
+static PATHOLOGY_MAX_FINDERS: OnceLock<usize> = OnceLock::new();
+
+/// Hard cap on the finder candidates one binarization hands to grouping,
+/// enforced after `QR_FINDER_SAMPLE_CAP` and even with sampling off.
+/// Default: 8192 (0 = unlimited)
+pub(crate) fn pathology_max_finders() -> usize {
+    *PATHOLOGY_MAX_FINDERS.get_or_init(|| parse_env_usize("QR_PATHOLOGY_MAX_FINDERS", 8192))
+}
+
+static PATHOLOGY_MAX_GROUPS: OnceLock<usize> = OnceLock::new();
+
+/// Hard cap on the finder triples ranked per binarization; past it only
+/// the triples with the best raw geometry score are ranked.
+/// Default: 20000 (0 = unlimited)
+pub(crate) fn pathology_max_groups() -> usize {
+    *PATHOLOGY_MAX_GROUPS.get_or_init(|| parse_env_usize("QR_PATHOLOGY_MAX_GROUPS", 20_000))
+}
+
+static RANK_TIME_BUDGET_MS: OnceLock<u64> = OnceLock::new();
+
+/// Wall-clock cap on ranking the finder triples of one binarization; the
+/// triples not ranked when it runs out are dropped.
+/// Default: 500 (0 = unlimited)
+pub(crate) fn rank_time_budget_ms() -> u64 {
+    *RANK_TIME_BUDGET_MS.get_or_init(|| parse_env_u64("QR_RANK_TIME_BUDGET_MS", 500))
+}
+
+static PATHOLOGY_MAX_REENTRIES: OnceLock<usize> = OnceLock::new();
+
+/// How often one finder geometry may enter candidate decoding per image,
+/// across binarizations and retries, before further entries are taken for
+/// a loop and skipped. Default: 16 (0 = unlimited)
+pub(crate) fn pathology_max_reentries() -> usize {
+    *PATHOLOGY_MAX_REENTRIES.get_or_init(|| parse_env_usize("QR_PATHOLOGY_MAX_REENTRIES", 16))
+}
+
+static ROI_NORM_MAX_CONTRAST_SPAN: OnceLock<u8> = OnceLock::new();
+
+/// Widest 5th-to-95th percentile gray span at which a frame still counts as
+/// low contrast and gets ROI normalization. Default: 90
+pub(crate) fn roi_norm_max_contrast_span() -> u8 {
+    *ROI_NORM_MAX_CONTRAST_SPAN.get_or_init(|| parse_env_u8("QR_ROI_NORM_MAX_CONTRAST_SPAN", 90))
+}
+
 static BLUR_DISABLE_RECOVERY_THRESHOLD: OnceLock<f32> = OnceLock::new();
 
 /// Blur metric threshold below which expensive recovery (RS erasure, subpixel) is disabled.
@@ -1,8 +1,17 @@
 /// Format information extraction from QR code
+use crate::error::DecodeFailure;
 use crate::models::{BitMatrix, ECLevel, MaskPattern};
 
+/// XOR mask applied to the 15-bit format word
+const FORMAT_MASK: u16 = 0x5412;
+
+/// [`FORMAT_MASK`] with its 15 bits in reverse order, as encoders that build
+/// the mask least significant bit first apply it
+const REVERSED_FORMAT_MASK: u16 = 0x2415;
+
 /// Format info is 15 bits (5 data + 10 ECC)
 /// Located at fixed positions in QR code corners
+#[derive(Debug, Clone, Copy)]
 pub struct FormatInfo {
     pub ec_level: ECLevel,
     pub mask_pattern: MaskPattern,
@@ -11,22 +20,86 @@ pub struct FormatInfo {
 impl FormatInfo {
     /// Extract format info from QR code matrix
     pub fn extract(matrix: &BitMatrix) -> Option<Self> {
-        let bits_a = Self::read_format_bits_top_left(matrix)?;
-        let bits_b = Self::read_format_bits_other(matrix)?;
+        Self::try_extract(matrix).ok()
+    }
+
+    /// Extract format info, reporting why extraction failed.
+    pub fn try_extract(matrix: &BitMatrix) -> Result<Self, DecodeFailure> {
+        Self::try_extract_flipped(matrix, 0)
+    }
+
+    /// Format info written without its `0x5412` mask
+    /// ([`crate::EncoderQuirk::UnmaskedFormat`]).
+    pub fn try_extract_unmasked(matrix: &BitMatrix) -> Result<Self, DecodeFailure> {
+        Self::try_extract_flipped(matrix, FORMAT_MASK)
+    }
+
+    /// Format info masked with [`REVERSED_FORMAT_MASK`] instead
+    /// ([`crate::FormatQuirk::WrongFormatMask`]). `None` unless it reads
+    /// closer than the strict read, which wins for any genuine word with at
+    /// most one bit error.
+    pub fn extract_wrong_mask(matrix: &BitMatrix) -> Option<Self> {
+        let (info, dist) = Self::read_flipped(matrix, FORMAT_MASK ^ REVERSED_FORMAT_MASK).ok()?;
+        match Self::read_flipped(matrix, 0) {
+            Ok((_, strict)) if strict <= dist => None,
+            _ => Some(info),
+        }
+    }
+
+    /// Format info under the data mask `mask`
+    /// ([`crate::EncoderQuirk::MaskedFormatArea`]); `None` unless the format
+    /// revealed names `mask` itself.
+    pub fn extract_under_mask(matrix: &BitMatrix, mask: MaskPattern) -> Option<Self> {
+        let size = matrix.width();
+        if size < 21 {
+            return None;
+        }
+        let mut unmasked = matrix.clone();
+        let top_left = (0..9)
+            .filter(|&i| i != 6)
+            .map(|y| (8, y))
+            .chain((0..8).filter(|&i| i != 6).map(|x| (x, 8)));
+        let others = (0..8)
+            .map(|i| (size - 1 - i, 8))
+            .chain((size - 7..size).map(|y| (8, y)));
+        for (x, y) in top_left.chain(others) {
+            if mask.is_masked(y, x) {
+                unmasked.set(x, y, !unmasked.get(x, y));
+            }
+        }
+        Self::try_extract(&unmasked)
+            .ok()
+            .filter(|info| info.mask_pattern == mask)
+    }
 
-        let bits_a_rev = Self::reverse_15(bits_a);
-        let bits_b_rev = Self::reverse_15(bits_b);
+    /// [`Self::try_extract`] with both read words XORed with `flip` first.
+    fn try_extract_flipped(matrix: &BitMatrix, flip: u16) -> Result<Self, DecodeFailure> {
+        Self::read_flipped(matrix, flip).map(|(info, _)| info)
+    }
+
+    /// [`Self::try_extract_flipped`] with the Hamming distance of the copy
+    /// it went by.
+    fn read_flipped(matrix: &BitMatrix, flip: u16) -> Result<(Self, u32), DecodeFailure> {
+        let bits_a =
+            Self::read_format_bits_top_left(matrix).ok_or(DecodeFailure::InvalidGeometry)?;
+        let bits_b = Self::read_format_bits_other(matrix).ok_or(DecodeFailure::InvalidGeometry)?;
+
+        let bits_a_rev = Self::reverse_15(bits_a) ^ flip;
+        let bits_b_rev = Self::reverse_15(bits_b) ^ flip;
+        let (bits_a, bits_b) = (bits_a ^ flip, bits_b ^ flip);
 
         // Try both copies (and reversed); take the one with the smallest Hamming distance.
         let (best_a, dist_a) = Self::decode_with_distance(bits_a)
-            .or_else(|| Self::decode_with_distance(bits_a_rev))?;
+            .or_else(|| Self::decode_with_distance(bits_a_rev))
+            .ok_or(DecodeFailure::FormatInfo)?;
         let (best_b, dist_b) = Self::decode_with_distance(bits_b)
-            .or_else(|| Self::decode_with_distance(bits_b_rev))?;
+            .or_else(|| Self::decode_with_distance(bits_b_rev))
+            .ok_or(DecodeFailure::FormatInfo)?;
 
         if dist_a <= dist_b {
-            Some(best_a)
+            Ok((best_a, dist_a))
         } else {
-            Some(best_b)
+            Ok((best_b, dist_b))
         }
     }
 
@@ -77,7 +150,7 @@ impl FormatInfo {
                 for _ in 0..10 {
                     rem = (rem << 1) ^ (((rem >> 9) & 1) * 0x537);
                 }
-                let candidate = ((data << 10) | rem) ^ 0x5412;
+                let candidate = ((data << 10) | rem) ^ FORMAT_MASK;
                 let dist = (candidate ^ format_bits).count_ones();
                 if dist <= 3 {
                     let ec_level = match ecl_bits {
@@ -128,4 +201,13 @@ mod tests {
         // Just verify the extraction function doesn't panic
         let _ = FormatInfo::extract(&matrix);
     }
+
+    #[test]
+    fn test_try_extract_reports_cause() {
+        let small = BitMatrix::new(11, 11);
+        assert!(matches!(
+            FormatInfo::try_extract(&small),
+            Err(DecodeFailure::InvalidGeometry)
+        ));
+    }
 }
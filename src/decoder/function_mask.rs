@@ -62,12 +62,13 @@ impl FunctionMask {
         // Dark module
         mask.set(8, size - 8, true);
 
-        // Version info (v7+)
+        // Version info (v7+): 3 wide by 6 tall left of the top-right finder,
+        // 6 wide by 3 tall above the bottom-left one
         if version >= 7 {
-            for dy in 0..6 {
-                for dx in 0..3 {
-                    mask.set(size - 11 + dx, dy, true);
-                    mask.set(dx, size - 11 + dy, true);
+            for i in 0..6 {
+                for j in 0..3 {
+                    mask.set(size - 11 + j, i, true);
+                    mask.set(i, size - 11 + j, true);
                 }
             }
         }
@@ -75,6 +76,15 @@ impl FunctionMask {
         Self { mask, version }
     }
 
+    /// Function modules as an encoder without the dark module lays them
+    /// out: (8, size - 8) is a data module.
+    pub fn without_dark_module(version: u8) -> Self {
+        let mut func = Self::new(version);
+        let size = func.size();
+        func.mask.set(8, size - 8, false);
+        func
+    }
+
     pub fn size(&self) -> usize {
         self.mask.width()
     }
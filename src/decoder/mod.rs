@@ -21,7 +21,7 @@ pub mod modes;
 pub mod qr_decoder;
 /// Reed-Solomon error correction
 pub mod reed_solomon;
-/// QR specification tables (ECC codewords/blocks)
+/// QR specification tables (codeword counts, EC blocks, capacities)
 pub mod tables;
 /// QR code unmasking (removes mask patterns)
 pub mod unmask;
@@ -11,6 +11,14 @@ const ALPHANUMERIC_TABLE: [char; 45] = [
 pub struct AlphanumericDecoder;
 
 impl AlphanumericDecoder {
+    /// Table index of `byte`, or `None` outside the 45-character set
+    pub fn char_value(byte: u8) -> Option<u32> {
+        ALPHANUMERIC_TABLE
+            .iter()
+            .position(|&c| c as u32 == byte as u32)
+            .map(|i| i as u32)
+    }
+
     pub fn decode(bits: &[bool], character_count: usize) -> Option<(String, usize)> {
         let mut result = String::new();
         let mut bit_idx = 0;
@@ -1,12 +1,31 @@
+use crate::FailedGrid;
+use crate::decoder::format::FormatInfo;
+use crate::decoder::version::VersionInfo;
+use crate::detector::transform::GridRefiner;
 /// Main QR code decoder - wires everything together
-use crate::models::{BitMatrix, Point, QRCode};
-use std::cell::RefCell;
+use crate::error::DecodeFailure;
+use crate::frame::Planes;
+use crate::models::{
+    BitMatrix, ImagePoint, ModulePoint, Orientation, Point, QRCode, SymbolInfo, Version,
+};
+use crate::{Ablation, EncoderQuirk};
+use std::cell::{Cell, RefCell};
 use std::time::Instant;
 
 mod geometry;
 mod matrix_decode;
 mod orientation;
 mod payload;
+mod session;
+mod structure;
+
+pub(crate) use geometry::{GridSampling, scoped_grid_sampling};
+
+/// Mean grid-fit residual, in modules, above which a high-density transform
+/// is dropped before sampling: at 150+ modules a looser fit has drifted by
+/// a module somewhere across the symbol.
+const HIGH_DENSITY_MAX_RESIDUAL: f32 = 0.35;
+pub use session::{BlockCorrection, CodewordBlock, DecodeSession, Segment, SegmentMode};
 
 /// Main QR decoder that processes a detected QR region
 pub struct QrDecoder;
@@ -20,13 +39,34 @@ pub(crate) struct DecodeCounters {
     pub scale_retry_attempts: usize,
     pub scale_retry_successes: usize,
     pub scale_retry_skipped_by_budget: usize,
+    pub threshold_sweep_attempts: usize,
+    pub threshold_sweep_successes: usize,
     pub hv_subpixel_attempts: usize,
     pub hv_refine_attempts: usize,
     pub hv_refine_successes: usize,
+    pub grid_refine_attempts: usize,
+    pub grid_refine_successes: usize,
+    pub grid_refine_iterations: usize,
+    pub grid_refine_residual_drop_px: f64,
+    pub grid_sample_cache_hits: usize,
     pub rs_erasure_attempts: usize,
     pub rs_erasure_successes: usize,
     pub rs_erasure_count_hist: [usize; 4],
+    pub rs_blocks_failed: usize,
+    pub rs_block_load_hist: [usize; 4],
+    pub high_density_version_pins: usize,
+    pub high_density_residual_rejects: usize,
+    pub synthetic_snap_decodes: usize,
+    pub header_prefilter_skips: usize,
+    pub rotated_format_rescues: [usize; 3],
+    pub wrong_format_mask_rescues: usize,
     pub phase11_time_budget_skips: usize,
+    pub column_scans_skipped: usize,
+    pub quirk_rescues: [usize; EncoderQuirk::ALL.len()],
+    pub decode_memo_hits: usize,
+    pub stream_decode_attempts: usize,
+    pub pathology_guard_trips: usize,
+    pub recovery_ms: f64,
 }
 
 impl DecodeCounters {
@@ -39,14 +79,95 @@ impl DecodeCounters {
             scale_retry_attempts: 0,
             scale_retry_successes: 0,
             scale_retry_skipped_by_budget: 0,
+            threshold_sweep_attempts: 0,
+            threshold_sweep_successes: 0,
             hv_subpixel_attempts: 0,
             hv_refine_attempts: 0,
             hv_refine_successes: 0,
+            grid_refine_attempts: 0,
+            grid_refine_successes: 0,
+            grid_refine_iterations: 0,
+            grid_refine_residual_drop_px: 0.0,
+            grid_sample_cache_hits: 0,
             rs_erasure_attempts: 0,
             rs_erasure_successes: 0,
             rs_erasure_count_hist: [0; 4],
+            rs_blocks_failed: 0,
+            rs_block_load_hist: [0; 4],
+            high_density_version_pins: 0,
+            high_density_residual_rejects: 0,
+            synthetic_snap_decodes: 0,
+            header_prefilter_skips: 0,
+            rotated_format_rescues: [0; 3],
+            wrong_format_mask_rescues: 0,
             phase11_time_budget_skips: 0,
+            column_scans_skipped: 0,
+            quirk_rescues: [0; EncoderQuirk::ALL.len()],
+            decode_memo_hits: 0,
+            stream_decode_attempts: 0,
+            pathology_guard_trips: 0,
+            recovery_ms: 0.0,
+        }
+    }
+}
+
+impl DecodeCounters {
+    fn add(&mut self, other: &Self) {
+        self.deskew_attempts += other.deskew_attempts;
+        self.deskew_successes += other.deskew_successes;
+        self.high_version_precision_attempts += other.high_version_precision_attempts;
+        self.recovery_mode_attempts += other.recovery_mode_attempts;
+        self.scale_retry_attempts += other.scale_retry_attempts;
+        self.scale_retry_successes += other.scale_retry_successes;
+        self.scale_retry_skipped_by_budget += other.scale_retry_skipped_by_budget;
+        self.threshold_sweep_attempts += other.threshold_sweep_attempts;
+        self.threshold_sweep_successes += other.threshold_sweep_successes;
+        self.hv_subpixel_attempts += other.hv_subpixel_attempts;
+        self.hv_refine_attempts += other.hv_refine_attempts;
+        self.hv_refine_successes += other.hv_refine_successes;
+        self.grid_refine_attempts += other.grid_refine_attempts;
+        self.grid_refine_successes += other.grid_refine_successes;
+        self.grid_refine_iterations += other.grid_refine_iterations;
+        self.grid_refine_residual_drop_px += other.grid_refine_residual_drop_px;
+        self.grid_sample_cache_hits += other.grid_sample_cache_hits;
+        self.rs_erasure_attempts += other.rs_erasure_attempts;
+        self.rs_erasure_successes += other.rs_erasure_successes;
+        for (total, count) in self
+            .rs_erasure_count_hist
+            .iter_mut()
+            .zip(other.rs_erasure_count_hist)
+        {
+            *total += count;
+        }
+        self.rs_blocks_failed += other.rs_blocks_failed;
+        for (total, count) in self
+            .rs_block_load_hist
+            .iter_mut()
+            .zip(other.rs_block_load_hist)
+        {
+            *total += count;
+        }
+        self.high_density_version_pins += other.high_density_version_pins;
+        self.high_density_residual_rejects += other.high_density_residual_rejects;
+        self.synthetic_snap_decodes += other.synthetic_snap_decodes;
+        self.header_prefilter_skips += other.header_prefilter_skips;
+        for (total, count) in self
+            .rotated_format_rescues
+            .iter_mut()
+            .zip(other.rotated_format_rescues)
+        {
+            *total += count;
         }
+        self.wrong_format_mask_rescues += other.wrong_format_mask_rescues;
+        self.phase11_time_budget_skips += other.phase11_time_budget_skips;
+        self.column_scans_skipped += other.column_scans_skipped;
+        for (total, count) in self.quirk_rescues.iter_mut().zip(other.quirk_rescues) {
+            *total += count;
+        }
+        self.decode_memo_hits += other.decode_memo_hits;
+        self.stream_decode_attempts += other.stream_decode_attempts;
+        self.pathology_guard_trips += other.pathology_guard_trips;
+        self.recovery_ms += other.recovery_ms;
     }
 }
 
@@ -58,28 +179,280 @@ impl Default for DecodeCounters {
 
 thread_local! {
     static DECODE_COUNTERS: RefCell<DecodeCounters> = const { RefCell::new(DecodeCounters::new()) };
+    static GRID_REFINEMENT: Cell<bool> = const { Cell::new(false) };
+    static HIGH_DENSITY: Cell<bool> = const { Cell::new(false) };
+    static SYNTHETIC: Cell<bool> = const { Cell::new(false) };
+    static INVERTED_FINDERS: Cell<[bool; 3]> = const { Cell::new([false; 3]) };
+    static PAYLOAD_PRIOR: RefCell<Option<PriorConstraint>> = const { RefCell::new(None) };
+    static FAILED_GRIDS: RefCell<Option<Vec<FailedGrid>>> = const { RefCell::new(None) };
+    static FAILED_GRID_ORIGIN: Cell<(f32, f32)> = const { Cell::new((0.0, 0.0)) };
+}
+
+/// Most [`FailedGrid`] records one call keeps; the first ones come from the
+/// best-ranked candidates.
+const MAX_FAILED_GRIDS: usize = 32;
+
+/// What a batch of earlier payloads says about the next one: its segment mode
+/// and the characters every payload so far has started with.
+#[derive(Debug, Clone)]
+pub(crate) struct PriorConstraint {
+    pub mode: SegmentMode,
+    pub prefix: Vec<u8>,
+}
+
+/// Run `f` with `prior` available to Reed-Solomon correction on this thread:
+/// a block that fails on its own is retried with the data codewords the prior
+/// pins down filled in, and codes rescued that way come back `assisted`.
+pub(crate) fn with_payload_prior<R>(prior: Option<PriorConstraint>, f: impl FnOnce() -> R) -> R {
+    let prev = PAYLOAD_PRIOR.with(|p| p.replace(prior));
+    let out = f();
+    PAYLOAD_PRIOR.with(|p| *p.borrow_mut() = prev);
+    out
+}
+
+/// Restores the previous grid-refinement setting when dropped.
+pub(crate) struct GridRefinementScope(bool);
+
+impl Drop for GridRefinementScope {
+    fn drop(&mut self) {
+        GRID_REFINEMENT.with(|g| g.set(self.0));
+    }
+}
+
+/// Run the iterative [`GridRefiner`] for every candidate on this thread, not
+/// just heavy-recovery ones, until the returned scope is dropped.
+pub(crate) fn scoped_grid_refinement(enabled: bool) -> GridRefinementScope {
+    GridRefinementScope(GRID_REFINEMENT.with(|g| g.replace(enabled)))
+}
+
+/// Restores the previous high-density setting when dropped.
+pub(crate) struct HighDensityScope(bool);
+
+impl Drop for HighDensityScope {
+    fn drop(&mut self) {
+        HIGH_DENSITY.with(|h| h.set(self.0));
+    }
+}
+
+/// Decode candidates on this thread as dense symbols until the returned
+/// scope is dropped: the version is pinned from the version information
+/// blocks when they read, every transform is fitted to the whole timing and
+/// alignment grid before sampling and dropped if the fit stays loose, and
+/// Reed-Solomon load is recorded per block.
+pub(crate) fn scoped_high_density(enabled: bool) -> HighDensityScope {
+    HighDensityScope(HIGH_DENSITY.with(|h| h.replace(enabled)))
+}
+
+fn high_density() -> bool {
+    HIGH_DENSITY.with(Cell::get)
+}
+
+/// Restores the previous synthetic-image setting when dropped.
+pub(crate) struct SyntheticScope(bool);
+
+impl Drop for SyntheticScope {
+    fn drop(&mut self) {
+        SYNTHETIC.with(|s| s.set(self.0));
+    }
 }
 
-pub(crate) fn reset_decode_counters() {
-    DECODE_COUNTERS.with(|c| *c.borrow_mut() = DecodeCounters::new());
-    payload::reset_erasure_counters();
-    payload::reset_rs_erasure_global_counter();
+/// Decode candidates on this thread as rendered images (screenshots, UI
+/// captures) until the returned scope is dropped: each version is first
+/// tried on an axis-aligned grid snapped to the module pitch and read one
+/// pixel per module, so resampling halos at module edges never reach the
+/// sampler.
+pub(crate) fn scoped_synthetic(enabled: bool) -> SyntheticScope {
+    SyntheticScope(SYNTHETIC.with(|s| s.replace(enabled)))
 }
 
-pub(crate) fn take_decode_counters() -> DecodeCounters {
-    let mut out = DecodeCounters::new();
-    DECODE_COUNTERS.with(|c| {
-        out = *c.borrow();
-        *c.borrow_mut() = DecodeCounters::new();
+fn synthetic() -> bool {
+    SYNTHETIC.with(Cell::get)
+}
+
+/// Restores the previous inverted-finder flags when dropped.
+pub(crate) struct InvertedFindersScope([bool; 3]);
+
+impl Drop for InvertedFindersScope {
+    fn drop(&mut self) {
+        INVERTED_FINDERS.with(|i| i.set(self.0));
+    }
+}
+
+/// Decode candidates on this thread as having reversed-polarity top-left,
+/// top-right and bottom-left finders where `inverted` says so, until the
+/// returned scope is dropped: those finder blocks of every sampled grid are
+/// flipped back before the orientation check, the rest of the grid is read
+/// as sampled.
+pub(crate) fn scoped_inverted_finders(inverted: [bool; 3]) -> InvertedFindersScope {
+    InvertedFindersScope(INVERTED_FINDERS.with(|i| i.replace(inverted)))
+}
+
+fn inverted_finders() -> [bool; 3] {
+    INVERTED_FINDERS.with(Cell::get)
+}
+
+/// Adds the time spent in heavy recovery paths to the decode counters when dropped,
+/// so every early return out of a recovery branch is still accounted for.
+struct RecoveryTimer(Instant);
+
+impl RecoveryTimer {
+    fn start() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Drop for RecoveryTimer {
+    fn drop(&mut self) {
+        let ms = self.0.elapsed().as_secs_f64() * 1000.0;
+        DECODE_COUNTERS.with(|c| c.borrow_mut().recovery_ms += ms);
+    }
+}
+
+/// Count a finder scan that left out columns because the rows were conclusive.
+pub(crate) fn record_column_scan_skip() {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().column_scans_skipped += 1);
+}
+
+/// Count a candidate decode skipped because the same geometry already failed.
+pub(crate) fn record_decode_memo_hit() {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().decode_memo_hits += 1);
+}
+
+/// Count a pathology guard (finder or group cap, rank time cap, re-entry
+/// limit) cutting the work of a call short.
+pub(crate) fn record_pathology_guard_trip() {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().pathology_guard_trips += 1);
+}
+
+/// Count a decode that only succeeded under the `quirk` rule.
+pub(crate) fn record_quirk_rescue(quirk: EncoderQuirk) {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().quirk_rescues[quirk.index()] += 1);
+}
+
+/// Count a decode whose format was read `quarter_turns` clockwise turns
+/// from upright.
+pub(crate) fn record_rotated_format_rescue(quarter_turns: usize) {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().rotated_format_rescues[quarter_turns - 1] += 1);
+}
+
+/// Count a decode whose format was masked with the bit-reversed constant.
+pub(crate) fn record_wrong_format_mask_rescue() {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().wrong_format_mask_rescues += 1);
+}
+
+/// Count brute-forced format guesses the header pre-filter ruled out.
+pub(crate) fn record_header_prefilter_skips(count: usize) {
+    DECODE_COUNTERS.with(|c| c.borrow_mut().header_prefilter_skips += count);
+}
+
+/// Run `f` and return the grids it sampled that failed at error correction
+/// or payload parsing, when [`crate::DetectorConfig::export_failed_grids`]
+/// is set (otherwise none are kept). An enclosing collection gets the inner
+/// records too.
+pub(crate) fn with_failed_grids<R>(f: impl FnOnce() -> R) -> (R, Vec<FailedGrid>) {
+    if !crate::config::with_active(|c| c.export_failed_grids) {
+        return (f(), Vec::new());
+    }
+    let outer = FAILED_GRIDS.with(|g| g.replace(Some(Vec::new())));
+    let out = f();
+    let grids = FAILED_GRIDS.with(|g| g.replace(outer)).unwrap_or_default();
+    FAILED_GRIDS.with(|g| {
+        if let Some(outer) = g.borrow_mut().as_mut() {
+            let room = MAX_FAILED_GRIDS.saturating_sub(outer.len());
+            outer.extend(grids.iter().take(room).cloned());
+        }
     });
-    let (rs_erasure_attempts, rs_erasure_successes, rs_erasure_count_hist) =
-        payload::take_erasure_counters();
-    out.rs_erasure_attempts = rs_erasure_attempts;
-    out.rs_erasure_successes = rs_erasure_successes;
-    out.rs_erasure_count_hist = rs_erasure_count_hist;
+    (out, grids)
+}
+
+/// Run `f` on a crop whose top-left corner sits at `(x0, y0)` in the frame,
+/// so the grids it records are shifted back into frame coordinates.
+pub(crate) fn with_failed_grid_origin<R>(x0: f32, y0: f32, f: impl FnOnce() -> R) -> R {
+    let prev = FAILED_GRID_ORIGIN.with(|o| o.replace((o.get().0 + x0, o.get().1 + y0)));
+    let out = f();
+    FAILED_GRID_ORIGIN.with(|o| o.set(prev));
     out
 }
 
+/// Keep `grid` for [`with_failed_grids`] if a collection is open and the
+/// decode got as far as error correction. The transform and corners are
+/// shifted by the enclosing [`with_failed_grid_origin`], if any.
+fn record_failed_grid(
+    grid: &BitMatrix,
+    confidence: &[u8],
+    transform: &crate::utils::geometry::PerspectiveTransform,
+    version: u8,
+    failure: DecodeFailure,
+) {
+    if !matches!(
+        failure,
+        DecodeFailure::ReedSolomon(_) | DecodeFailure::Payload
+    ) {
+        return;
+    }
+    FAILED_GRIDS.with(|g| {
+        let mut sink = g.borrow_mut();
+        let Some(grids) = sink.as_mut().filter(|g| g.len() < MAX_FAILED_GRIDS) else {
+            return;
+        };
+        let d = grid.width() as f32;
+        let (dx, dy) = FAILED_GRID_ORIGIN.with(Cell::get);
+        // Translating the image side adds the projective row to each of the
+        // first two rows.
+        let mut m = transform.coefficients();
+        for i in 0..3 {
+            m[i] += dx * m[6 + i];
+            m[3 + i] += dy * m[6 + i];
+        }
+        grids.push(FailedGrid {
+            version,
+            modules: grid.clone(),
+            confidence: confidence.to_vec(),
+            transform: m,
+            corners: [(0.0, 0.0), (d, 0.0), (d, d), (0.0, d)].map(|(x, y)| {
+                let mut p = transform.to_image(ModulePoint::new(x, y));
+                p.x += dx;
+                p.y += dy;
+                p
+            }),
+            failure,
+        });
+    });
+}
+
+/// Run `f` with fresh decode counters on this thread and return them along
+/// with its result. An enclosing scope's counters are set aside meanwhile and
+/// get the inner counts added back afterwards, so nested telemetry calls each
+/// see only their own work. The RS erasure cap is charged against the same
+/// counters, so it applies per scope rather than per process.
+pub(crate) fn with_decode_counters<R>(f: impl FnOnce() -> R) -> (R, DecodeCounters) {
+    let outer = DECODE_COUNTERS.with(|c| c.replace(DecodeCounters::new()));
+    let out = f();
+    let counters = DECODE_COUNTERS.with(|c| c.replace(outer));
+    DECODE_COUNTERS.with(|c| c.borrow_mut().add(&counters));
+    (out, counters)
+}
+
+/// Restores the enclosing decode counters, with this scope's counts added
+/// in, when dropped.
+pub(crate) struct DecodeCountersScope(DecodeCounters);
+
+impl Drop for DecodeCountersScope {
+    fn drop(&mut self) {
+        let outer = std::mem::take(&mut self.0);
+        let counters = DECODE_COUNTERS.with(|c| c.replace(outer));
+        DECODE_COUNTERS.with(|c| c.borrow_mut().add(&counters));
+    }
+}
+
+/// [`with_decode_counters`] for a whole detection call, until the returned
+/// scope is dropped: every public entry point opens one, so per-image limits
+/// start from zero and nothing an earlier call on this thread counted
+/// carries over to the next frame.
+pub(crate) fn scoped_decode_counters() -> DecodeCountersScope {
+    DecodeCountersScope(DECODE_COUNTERS.with(|c| c.replace(DecodeCounters::new())))
+}
+
 impl QrDecoder {
     /// Decode a QR code from a binary matrix and finder pattern locations
     pub fn decode(
@@ -123,28 +496,53 @@ impl QrDecoder {
             }
         }
 
+        let candidates = Self::rank_versions(candidates, |version_num| {
+            let dimension = 17 + 4 * version_num as usize;
+            let transform =
+                Self::build_transform(top_left, top_right, bottom_left, &bottom_right, dimension)?;
+            let transform = Self::refine_transform_with_alignment(
+                matrix,
+                &transform,
+                version_num,
+                dimension,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            )
+            .unwrap_or(transform);
+            Some(Self::extract_qr_region_with_transform(
+                matrix, &transform, dimension,
+            ))
+        });
+
         for version_num in candidates {
             if version_num >= 7 {
                 DECODE_COUNTERS.with(|c| c.borrow_mut().high_version_precision_attempts += 1);
             }
             let dimension = 17 + 4 * version_num as usize;
-            for br in &br_candidates {
-                let transform =
-                    match Self::build_transform(top_left, top_right, bottom_left, br, dimension) {
-                        Some(t) => t,
-                        None => continue,
-                    };
-                let transform = Self::refine_transform_with_alignment(
-                    matrix,
-                    &transform,
-                    version_num,
-                    dimension,
-                    module_size,
-                    top_left,
-                    top_right,
-                    bottom_left,
-                )
-                .unwrap_or(transform);
+            let anchored = geometry::alignment_anchored_transform(
+                matrix,
+                version_num,
+                dimension,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            );
+            // The blind offsets are only for symbols with no alignment pattern
+            // to anchor the corner on, or whose pattern wasn't found.
+            let blind = anchored.is_none();
+            for transform in anchored.into_iter().chain(Self::blind_corner_transforms(
+                matrix,
+                &br_candidates,
+                blind,
+                version_num,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            )) {
                 let qr_matrix =
                     Self::extract_qr_region_with_transform(matrix, &transform, dimension);
 
@@ -180,10 +578,66 @@ impl QrDecoder {
         module_size: f32,
         allow_heavy_recovery: bool,
     ) -> Option<QRCode> {
+        Self::try_decode_with_gray(
+            binary,
+            gray,
+            width,
+            height,
+            top_left,
+            top_right,
+            bottom_left,
+            module_size,
+            allow_heavy_recovery,
+        )
+        .ok()
+    }
+
+    /// Like [`Self::decode_with_gray`], but reports the deepest stage any attempt reached.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_decode_with_gray(
+        binary: &BitMatrix,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+        allow_heavy_recovery: bool,
+    ) -> Result<QRCode, DecodeFailure> {
+        debug_assert_eq!((binary.width(), binary.height()), (width, height));
+        Self::try_decode_planes(
+            Planes::new(binary, &gray[..width * height]),
+            top_left,
+            top_right,
+            bottom_left,
+            module_size,
+            allow_heavy_recovery,
+        )
+    }
+
+    /// [`Self::try_decode_with_gray`] over a binary matrix and its gray.
+    pub(crate) fn try_decode_planes(
+        planes: Planes<'_>,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+        allow_heavy_recovery: bool,
+    ) -> Result<QRCode, DecodeFailure> {
+        let Planes {
+            binary,
+            gray,
+            width,
+            height,
+        } = planes;
+        let allow_heavy_recovery = allow_heavy_recovery && crate::features::FULL_RECOVERY;
         let started = Instant::now();
         let candidate_budget_ms = crate::decoder::config::candidate_time_budget_ms();
         let budget_exhausted = || started.elapsed().as_millis() as u64 >= candidate_budget_ms;
-        let bottom_right = Self::calculate_bottom_right(top_left, top_right, bottom_left)?;
+        let bottom_right = Self::calculate_bottom_right(top_left, top_right, bottom_left)
+            .ok_or(DecodeFailure::InvalidGeometry)?;
+        let high_density = high_density();
         let mut br_candidates = Vec::new();
         let step = module_size.max(1.0) * 2.0;
         for dy in [-4.0f32, -2.0, 0.0, 2.0, 4.0] {
@@ -194,62 +648,181 @@ impl QrDecoder {
                 ));
             }
         }
+        if high_density {
+            // The grid fit below moves the corner where the offsets would
+            // only guess at it.
+            br_candidates = vec![bottom_right];
+        }
         let estimated_dimension =
-            Self::estimate_dimension(top_left, top_right, &bottom_right, module_size)?;
+            Self::estimate_dimension(top_left, top_right, &bottom_right, module_size)
+                .ok_or(DecodeFailure::InvalidGeometry)?;
 
         let estimated_version = ((estimated_dimension - 17) / 4) as i32;
         let candidates = Self::version_candidates(estimated_version);
+        let mut failure = DecodeFailure::InvalidGeometry;
+        let mut grid_cache = geometry::GridSampleCache::default();
+        let mut version_info = None;
+        // Each version's alignment-anchored transform, or failing that its
+        // unshifted corner, is tried below and finds these samplings cached.
+        let candidates = Self::rank_versions(candidates, |version_num| {
+            let dimension = 17 + 4 * version_num as usize;
+            let transform =
+                Self::build_transform(top_left, top_right, bottom_left, &bottom_right, dimension)?;
+            let transform = Self::refine_transform_with_alignment(
+                binary,
+                &transform,
+                version_num,
+                dimension,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            )
+            .unwrap_or(transform);
+            let grid = grid_cache
+                .sample(gray, width, height, &transform, dimension, 1.0)
+                .0;
+            if high_density && version_info.is_none() {
+                // The blocks sit next to the finders, so a grid a version or
+                // two off still reads them.
+                version_info = VersionInfo::extract(&grid);
+            }
+            Some(grid)
+        });
+        let candidates = match version_info {
+            Some(pinned) if (pinned as i32 - estimated_version).abs() <= 6 => {
+                DECODE_COUNTERS.with(|c| c.borrow_mut().high_density_version_pins += 1);
+                vec![pinned]
+            }
+            _ => candidates,
+        };
 
         for version_num in candidates {
             let dimension = 17 + 4 * version_num as usize;
-            for br in &br_candidates {
-                let transform =
-                    match Self::build_transform(top_left, top_right, bottom_left, br, dimension) {
-                        Some(t) => t,
-                        None => continue,
-                    };
-                let transform = Self::refine_transform_with_alignment(
-                    binary,
-                    &transform,
-                    version_num,
-                    dimension,
-                    module_size,
-                    top_left,
-                    top_right,
-                    bottom_left,
-                )
-                .unwrap_or(transform);
-
+            if synthetic()
+                && let Some(transform) =
+                    geometry::snapped_axis_transform(top_left, top_right, bottom_left, dimension)
+            {
+                let _center = scoped_grid_sampling(GridSampling::CenterPixel);
                 let (qr_matrix, module_confidence) =
-                    Self::extract_qr_region_gray_with_transform_and_confidence(
-                        gray, width, height, &transform, dimension,
-                    );
+                    grid_cache.sample(gray, width, height, &transform, dimension, 1.0);
+                let decoded = orientation::validate_timing_patterns(&qr_matrix)
+                    .then(|| {
+                        Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &qr_matrix,
+                                version_num,
+                                &module_confidence,
+                            ),
+                            &mut failure,
+                        )
+                    })
+                    .flatten();
+                if let Some(qr) = decoded {
+                    DECODE_COUNTERS.with(|c| c.borrow_mut().synthetic_snap_decodes += 1);
+                    return Ok(Self::with_corners(qr, &transform, dimension));
+                }
+            }
+            let anchored = geometry::alignment_anchored_transform(
+                binary,
+                version_num,
+                dimension,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            );
+            // The blind offsets are only for symbols with no alignment pattern
+            // to anchor the corner on, or whose pattern wasn't found.
+            let blind = anchored.is_none();
+            for transform in anchored.into_iter().chain(Self::blind_corner_transforms(
+                binary,
+                &br_candidates,
+                blind,
+                version_num,
+                module_size,
+                top_left,
+                top_right,
+                bottom_left,
+            )) {
+                let transform = if high_density && version_num >= 2 {
+                    let (fitted, fit) =
+                        GridRefiner::default().refine(binary, &transform, version_num);
+                    if fit.features > 0
+                        && fit.final_residual > HIGH_DENSITY_MAX_RESIDUAL * module_size
+                    {
+                        DECODE_COUNTERS.with(|c| c.borrow_mut().high_density_residual_rejects += 1);
+                        continue;
+                    }
+                    fitted
+                } else {
+                    transform
+                };
+                let (qr_matrix, module_confidence) =
+                    grid_cache.sample(gray, width, height, &transform, dimension, 1.0);
                 if version_num >= 7 {
                     DECODE_COUNTERS.with(|c| c.borrow_mut().hv_subpixel_attempts += 1);
                 }
                 if !orientation::validate_timing_patterns(&qr_matrix) {
+                    failure = failure.deeper(DecodeFailure::TimingPattern);
                     continue;
                 }
 
-                if let Some(qr) = Self::decode_from_matrix_with_confidence(
+                let upright = Self::try_decode_from_matrix_with_confidence(
                     &qr_matrix,
                     version_num,
                     &module_confidence,
-                ) {
-                    return Some(qr);
+                );
+                let rs_failed = matches!(upright, Err(DecodeFailure::ReedSolomon(_)));
+                if let Err(e) = upright {
+                    record_failed_grid(&qr_matrix, &module_confidence, &transform, version_num, e);
+                }
+                if let Some(qr) = Self::note_failure(upright, &mut failure) {
+                    return Ok(Self::with_corners(qr, &transform, dimension));
                 }
 
                 let inverted = orientation::invert_matrix(&qr_matrix);
-                if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                    &inverted,
-                    version_num,
-                    &module_confidence,
+                if let Some(qr) = Self::note_failure(
+                    Self::try_decode_from_matrix_with_confidence(
+                        &inverted,
+                        version_num,
+                        &module_confidence,
+                    ),
+                    &mut failure,
                 ) {
-                    return Some(qr);
+                    return Ok(Self::with_corners(qr, &transform, dimension));
+                }
+
+                // The grid read far enough to fail only at error correction:
+                // re-threshold the same samples either side of the local mean
+                // before moving on to resampling.
+                if rs_failed && !budget_exhausted() {
+                    for (swept, swept_conf) in
+                        geometry::threshold_sweep_grids(gray, width, height, &transform, dimension)
+                    {
+                        if budget_exhausted() {
+                            break;
+                        }
+                        DECODE_COUNTERS.with(|c| c.borrow_mut().threshold_sweep_attempts += 1);
+                        if let Some(qr) = Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &swept,
+                                version_num,
+                                &swept_conf,
+                            ),
+                            &mut failure,
+                        ) {
+                            DECODE_COUNTERS.with(|c| c.borrow_mut().threshold_sweep_successes += 1);
+                            return Ok(Self::with_corners(qr, &transform, dimension));
+                        }
+                    }
                 }
 
+                let _recovery_timer = allow_heavy_recovery.then(RecoveryTimer::start);
                 let should_scale_retry = module_size <= 2.4 || version_num >= 7 || dimension >= 85;
-                if allow_heavy_recovery && should_scale_retry && !budget_exhausted() {
+                if crate::config::ablated(Ablation::ScaleRetry) {
+                    // Switched off: neither tried nor counted as skipped.
+                } else if allow_heavy_recovery && should_scale_retry && !budget_exhausted() {
                     for &scale in &[1.25f32, 1.5f32] {
                         if budget_exhausted() {
                             DECODE_COUNTERS.with(|c| c.borrow_mut().phase11_time_budget_skips += 1);
@@ -257,28 +830,32 @@ impl QrDecoder {
                         }
                         DECODE_COUNTERS.with(|c| c.borrow_mut().scale_retry_attempts += 1);
                         let (scaled_matrix, scaled_conf) =
-                            Self::extract_qr_region_gray_with_transform_and_confidence_scaled(
-                                gray, width, height, &transform, dimension, scale,
-                            );
+                            grid_cache.sample(gray, width, height, &transform, dimension, scale);
                         if !orientation::validate_timing_patterns(&scaled_matrix) {
                             continue;
                         }
-                        if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                            &scaled_matrix,
-                            version_num,
-                            &scaled_conf,
+                        if let Some(qr) = Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &scaled_matrix,
+                                version_num,
+                                &scaled_conf,
+                            ),
+                            &mut failure,
                         ) {
                             DECODE_COUNTERS.with(|c| c.borrow_mut().scale_retry_successes += 1);
-                            return Some(qr);
+                            return Ok(Self::with_corners(qr, &transform, dimension));
                         }
                         let scaled_inverted = orientation::invert_matrix(&scaled_matrix);
-                        if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                            &scaled_inverted,
-                            version_num,
-                            &scaled_conf,
+                        if let Some(qr) = Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &scaled_inverted,
+                                version_num,
+                                &scaled_conf,
+                            ),
+                            &mut failure,
                         ) {
                             DECODE_COUNTERS.with(|c| c.borrow_mut().scale_retry_successes += 1);
-                            return Some(qr);
+                            return Ok(Self::with_corners(qr, &transform, dimension));
                         }
                     }
                 } else {
@@ -297,23 +874,29 @@ impl QrDecoder {
                         top_right,
                         bottom_left,
                     ) {
-                        let (hv_matrix, hv_conf) =
-                            Self::extract_qr_region_gray_with_transform_and_confidence_scaled(
-                                gray,
-                                width,
-                                height,
-                                &refined_hv_transform,
-                                dimension,
-                                1.35,
-                            );
+                        let (hv_matrix, hv_conf) = grid_cache.sample(
+                            gray,
+                            width,
+                            height,
+                            &refined_hv_transform,
+                            dimension,
+                            1.35,
+                        );
                         if orientation::validate_timing_patterns(&hv_matrix) {
-                            if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                                &hv_matrix,
-                                version_num,
-                                &hv_conf,
+                            if let Some(qr) = Self::note_failure(
+                                Self::try_decode_from_matrix_with_confidence(
+                                    &hv_matrix,
+                                    version_num,
+                                    &hv_conf,
+                                ),
+                                &mut failure,
                             ) {
                                 DECODE_COUNTERS.with(|c| c.borrow_mut().hv_refine_successes += 1);
-                                return Some(qr);
+                                return Ok(Self::with_corners(
+                                    qr,
+                                    &refined_hv_transform,
+                                    dimension,
+                                ));
                             }
                         }
                     }
@@ -321,51 +904,96 @@ impl QrDecoder {
 
                 // Rotation-specialized deskew fallback: apply a bounded mesh warp variant
                 // only after strict decode misses.
-                if allow_heavy_recovery && version_num >= 2 && !budget_exhausted() {
+                if allow_heavy_recovery
+                    && version_num >= 2
+                    && !budget_exhausted()
+                    && !crate::config::ablated(Ablation::Deskew)
+                {
                     DECODE_COUNTERS.with(|c| c.borrow_mut().deskew_attempts += 1);
-                    let (deskew_matrix, deskew_conf) = Self::extract_qr_region_gray_with_mesh_warp(
-                        gray, width, height, &transform, dimension,
-                    );
+                    let (deskew_matrix, deskew_conf) =
+                        grid_cache.sample_mesh_warp(gray, width, height, &transform, dimension);
                     if orientation::validate_timing_patterns(&deskew_matrix) {
-                        if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                            &deskew_matrix,
-                            version_num,
-                            &deskew_conf,
+                        if let Some(qr) = Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &deskew_matrix,
+                                version_num,
+                                &deskew_conf,
+                            ),
+                            &mut failure,
                         ) {
                             DECODE_COUNTERS.with(|c| c.borrow_mut().deskew_successes += 1);
-                            return Some(qr);
+                            return Ok(Self::with_corners(qr, &transform, dimension));
+                        }
+                    }
+                }
+
+                let refine_grid = crate::features::HIGH_VERSION
+                    && (allow_heavy_recovery || GRID_REFINEMENT.with(Cell::get));
+                if refine_grid && version_num >= 2 && !budget_exhausted() {
+                    DECODE_COUNTERS.with(|c| c.borrow_mut().grid_refine_attempts += 1);
+                    let (refined, refine_tel) =
+                        GridRefiner::default().refine(binary, &transform, version_num);
+                    DECODE_COUNTERS.with(|c| {
+                        let mut c = c.borrow_mut();
+                        c.grid_refine_iterations += refine_tel.iterations;
+                        c.grid_refine_residual_drop_px += refine_tel.residual_drop() as f64;
+                    });
+                    if refine_tel.improved() {
+                        let (refined_matrix, refined_conf) =
+                            grid_cache.sample(gray, width, height, &refined, dimension, 1.0);
+                        if orientation::validate_timing_patterns(&refined_matrix) {
+                            for candidate in [
+                                refined_matrix.clone(),
+                                orientation::invert_matrix(&refined_matrix),
+                            ] {
+                                if let Some(qr) = Self::note_failure(
+                                    Self::try_decode_from_matrix_with_confidence(
+                                        &candidate,
+                                        version_num,
+                                        &refined_conf,
+                                    ),
+                                    &mut failure,
+                                ) {
+                                    DECODE_COUNTERS
+                                        .with(|c| c.borrow_mut().grid_refine_successes += 1);
+                                    return Ok(Self::with_corners(qr, &refined, dimension));
+                                }
+                            }
                         }
                     }
                 }
 
                 if allow_heavy_recovery && !budget_exhausted() {
-                    let (mesh_matrix, mesh_conf) = Self::extract_qr_region_gray_with_mesh_warp(
-                        gray, width, height, &transform, dimension,
-                    );
+                    let (mesh_matrix, mesh_conf) =
+                        grid_cache.sample_mesh_warp(gray, width, height, &transform, dimension);
                     if orientation::validate_timing_patterns(&mesh_matrix) {
-                        if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                            &mesh_matrix,
-                            version_num,
-                            &mesh_conf,
+                        if let Some(qr) = Self::note_failure(
+                            Self::try_decode_from_matrix_with_confidence(
+                                &mesh_matrix,
+                                version_num,
+                                &mesh_conf,
+                            ),
+                            &mut failure,
                         ) {
-                            return Some(qr);
+                            return Ok(Self::with_corners(qr, &transform, dimension));
                         }
                     }
                 }
 
                 if allow_heavy_recovery && !budget_exhausted() {
-                    if let Some((radial_matrix, radial_conf)) =
-                        Self::extract_qr_region_gray_with_radial_compensation(
-                            gray, width, height, &transform, dimension,
-                        )
+                    if let Some((radial_matrix, radial_conf)) = grid_cache
+                        .sample_radial_compensation(gray, width, height, &transform, dimension)
                     {
                         if orientation::validate_timing_patterns(&radial_matrix) {
-                            if let Some(qr) = Self::decode_from_matrix_with_confidence(
-                                &radial_matrix,
-                                version_num,
-                                &radial_conf,
+                            if let Some(qr) = Self::note_failure(
+                                Self::try_decode_from_matrix_with_confidence(
+                                    &radial_matrix,
+                                    version_num,
+                                    &radial_conf,
+                                ),
+                                &mut failure,
                             ) {
-                                return Some(qr);
+                                return Ok(Self::with_corners(qr, &transform, dimension));
                             }
                         }
                     }
@@ -379,7 +1007,7 @@ impl QrDecoder {
                     }
                     DECODE_COUNTERS.with(|c| c.borrow_mut().recovery_mode_attempts += 1);
                     if let Some(qr) = Self::decode_from_matrix(&qr_matrix, version_num) {
-                        return Some(qr);
+                        return Ok(Self::with_corners(qr, &transform, dimension));
                     }
                 } else if budget_exhausted() {
                     DECODE_COUNTERS.with(|c| c.borrow_mut().phase11_time_budget_skips += 1);
@@ -387,7 +1015,285 @@ impl QrDecoder {
             }
         }
 
-        None
+        Err(failure)
+    }
+
+    /// Sample the module grid for a finder triplet at its estimated version,
+    /// without attempting to decode it.
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn sample_grid(
+        planes: Planes<'_>,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Option<BitMatrix> {
+        let Planes {
+            binary,
+            gray,
+            width,
+            height,
+        } = planes;
+        let (transform, dimension) =
+            Self::grid_transform(binary, top_left, top_right, bottom_left, module_size)?;
+        let (grid, _) = Self::extract_qr_region_gray_with_transform_and_confidence(
+            gray, width, height, &transform, dimension,
+        );
+        Some(grid)
+    }
+
+    /// Grids sampled for each plausible version of a finder triplet, best
+    /// structured first (see [`structure::ranking_score`]), each with its
+    /// transform and module confidence. Versions with an alignment pattern
+    /// are sampled through it; grids whose timing doesn't alternate are left
+    /// out.
+    pub(crate) fn sample_version_grids(
+        planes: Planes<'_>,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Vec<(
+        u8,
+        crate::utils::geometry::PerspectiveTransform,
+        BitMatrix,
+        Vec<u8>,
+    )> {
+        let Planes {
+            binary,
+            gray,
+            width,
+            height,
+        } = planes;
+        let Some(bottom_right) = Self::calculate_bottom_right(top_left, top_right, bottom_left)
+        else {
+            return Vec::new();
+        };
+        let Some(estimated_dimension) =
+            Self::estimate_dimension(top_left, top_right, &bottom_right, module_size)
+        else {
+            return Vec::new();
+        };
+        let estimated_version = ((estimated_dimension - 17) / 4) as i32;
+        let mut grids: Vec<_> = Self::version_candidates(estimated_version)
+            .into_iter()
+            .filter_map(|version_num| {
+                let dimension = 17 + 4 * version_num as usize;
+                let transform = match geometry::alignment_anchored_transform(
+                    binary,
+                    version_num,
+                    dimension,
+                    module_size,
+                    top_left,
+                    top_right,
+                    bottom_left,
+                ) {
+                    Some(t) => t,
+                    None => Self::build_transform(
+                        top_left,
+                        top_right,
+                        bottom_left,
+                        &bottom_right,
+                        dimension,
+                    )?,
+                };
+                let (grid, confidence) = Self::extract_qr_region_gray_with_transform_and_confidence(
+                    gray, width, height, &transform, dimension,
+                );
+                orientation::validate_timing_patterns(&grid).then_some((
+                    version_num,
+                    transform,
+                    grid,
+                    confidence,
+                ))
+            })
+            .collect();
+        grids.sort_by(|a, b| {
+            structure::ranking_score(&b.2).total_cmp(&structure::ranking_score(&a.2))
+        });
+        grids
+    }
+
+    /// Decode a grid sampled through `transform`, as read or inverted, with
+    /// the symbol's corners and pose taken from the transform.
+    pub(crate) fn decode_sampled_grid(
+        grid: &BitMatrix,
+        version_num: u8,
+        module_confidence: &[u8],
+        transform: &crate::utils::geometry::PerspectiveTransform,
+    ) -> Option<QRCode> {
+        let dimension = grid.width();
+        Self::try_decode_from_matrix_with_confidence(grid, version_num, module_confidence)
+            .or_else(|_| {
+                let inverted = orientation::invert_matrix(grid);
+                Self::try_decode_from_matrix_with_confidence(
+                    &inverted,
+                    version_num,
+                    module_confidence,
+                )
+            })
+            .ok()
+            .map(|qr| Self::with_corners(qr, transform, dimension))
+    }
+
+    /// Read the version, EC level and mask of a finder triplet's symbol
+    /// without decoding its data. The grid is sampled and oriented as for a
+    /// full decode; then only the format and, from version 7, the version
+    /// information are read.
+    pub(crate) fn read_symbol_info(
+        planes: Planes<'_>,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Option<SymbolInfo> {
+        let Planes {
+            binary,
+            gray,
+            width,
+            height,
+        } = planes;
+        let (transform, dimension) =
+            Self::grid_transform(binary, top_left, top_right, bottom_left, module_size)?;
+        let (grid, _) = Self::extract_qr_region_gray_with_transform_and_confidence(
+            gray, width, height, &transform, dimension,
+        );
+        let estimated = ((dimension - 17) / 4) as u8;
+        let mut orientations = orientation::candidate_orientations(&grid);
+        if orientations.is_empty() {
+            let mismatches = crate::decoder::config::relaxed_finder_mismatch();
+            orientations = orientation::candidate_orientations_relaxed(&grid, mismatches);
+        }
+        orientations.into_iter().find_map(|(turn, oriented)| {
+            let format = FormatInfo::extract(&oriented)?;
+            // From version 7 the version blocks beat the sampled size, which
+            // can be off by a version or two on large symbols.
+            let version = VersionInfo::extract(&oriented).unwrap_or(estimated);
+            let corners = Self::symbol_corners(&transform, dimension, turn.inverse());
+            let aspect = crate::config::pixel_aspect_ratio();
+            Some(SymbolInfo {
+                version: Version::Model2(version),
+                ec_level: format.ec_level,
+                mask_pattern: format.mask_pattern,
+                orientation: Self::image_orientation(&corners, aspect),
+                position: corners.map(Point::from),
+            })
+        })
+    }
+
+    /// Sample per-module signed contrast (positive is dark) for a finder
+    /// triplet, returning the grid dimension alongside the levels.
+    pub(crate) fn sample_module_contrast(
+        planes: Planes<'_>,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Option<(usize, Vec<f32>)> {
+        let Planes {
+            binary,
+            gray,
+            width,
+            height,
+        } = planes;
+        let (transform, dimension) =
+            Self::best_corner_transform(binary, top_left, top_right, bottom_left, module_size)?;
+        let levels = geometry::sample_module_contrast(gray, width, height, &transform, dimension);
+        Some((dimension, levels))
+    }
+
+    /// Decode a grid of signed module levels accumulated outside the image,
+    /// e.g. averaged across frames. Weak levels become low-confidence modules.
+    pub(crate) fn decode_levels(levels: &[f32], dimension: usize) -> Option<QRCode> {
+        if dimension < 21 || (dimension - 17) % 4 != 0 || levels.len() != dimension * dimension {
+            return None;
+        }
+        let mut matrix = BitMatrix::new(dimension, dimension);
+        let mut confidence = vec![0u8; levels.len()];
+        for y in 0..dimension {
+            for x in 0..dimension {
+                let level = levels[y * dimension + x];
+                matrix.set(x, y, level > 0.0);
+                confidence[y * dimension + x] = ((level.abs() * 4.0).min(1.0) * 255.0) as u8;
+            }
+        }
+        let version_num = ((dimension - 17) / 4) as u8;
+        Self::try_decode_from_matrix_with_confidence(&matrix, version_num, &confidence).ok()
+    }
+
+    /// Like [`Self::grid_transform`], but searches the same bottom-right
+    /// offsets as [`Self::try_decode_with_gray`] and keeps the transform whose
+    /// timing and alignment patterns score best.
+    fn best_corner_transform(
+        binary: &BitMatrix,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Option<(crate::utils::geometry::PerspectiveTransform, usize)> {
+        let (base, dimension) =
+            Self::grid_transform(binary, top_left, top_right, bottom_left, module_size)?;
+        let version_num = ((dimension - 17) / 4) as u8;
+        let bottom_right = Self::calculate_bottom_right(top_left, top_right, bottom_left)?;
+        let score = |t: &crate::utils::geometry::PerspectiveTransform| {
+            geometry::transform_quality(binary, t, dimension, version_num, module_size)
+        };
+        let mut best = (score(&base), base);
+        let step = module_size.max(1.0) * 2.0;
+        for dy in [-4.0f32, -2.0, 0.0, 2.0, 4.0] {
+            for dx in [-4.0f32, -2.0, 0.0, 2.0, 4.0] {
+                let br = Point::new(bottom_right.x + dx * step, bottom_right.y + dy * step);
+                let Some(t) =
+                    Self::build_transform(top_left, top_right, bottom_left, &br, dimension)
+                else {
+                    continue;
+                };
+                let t = Self::refine_transform_with_alignment(
+                    binary,
+                    &t,
+                    version_num,
+                    dimension,
+                    module_size,
+                    top_left,
+                    top_right,
+                    bottom_left,
+                )
+                .unwrap_or(t);
+                let s = score(&t);
+                if s > best.0 {
+                    best = (s, t);
+                }
+            }
+        }
+        Some((best.1, dimension))
+    }
+
+    /// Sampling transform and dimension for a finder triplet, refined with the
+    /// alignment pattern when one is found.
+    fn grid_transform(
+        binary: &BitMatrix,
+        top_left: &Point,
+        top_right: &Point,
+        bottom_left: &Point,
+        module_size: f32,
+    ) -> Option<(crate::utils::geometry::PerspectiveTransform, usize)> {
+        let bottom_right = Self::calculate_bottom_right(top_left, top_right, bottom_left)?;
+        let dimension = Self::estimate_dimension(top_left, top_right, &bottom_right, module_size)?;
+        let version_num = ((dimension - 17) / 4) as u8;
+        let transform =
+            Self::build_transform(top_left, top_right, bottom_left, &bottom_right, dimension)?;
+        let transform = Self::refine_transform_with_alignment(
+            binary,
+            &transform,
+            version_num,
+            dimension,
+            module_size,
+            top_left,
+            top_right,
+            bottom_left,
+        )
+        .unwrap_or(transform);
+        Some((transform, dimension))
     }
 
     fn calculate_bottom_right(
@@ -411,6 +1317,27 @@ impl QrDecoder {
         geometry::version_candidates(estimated_version)
     }
 
+    /// `candidates` reordered best first by the fixed-pattern structure of
+    /// the grid `sample` returns for each (see [`structure::ranking_score`]),
+    /// so versions whose timing and separators line up reach RS first.
+    /// Ties keep their order.
+    fn rank_versions(
+        candidates: Vec<u8>,
+        mut sample: impl FnMut(u8) -> Option<BitMatrix>,
+    ) -> Vec<u8> {
+        let mut scored: Vec<(u8, f32)> = candidates
+            .into_iter()
+            .map(|v| {
+                (
+                    v,
+                    sample(v).map_or(0.0, |grid| structure::ranking_score(&grid)),
+                )
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().map(|(v, _)| v).collect()
+    }
+
     #[allow(dead_code)]
     fn extract_qr_region(
         matrix: &BitMatrix,
@@ -446,6 +1373,42 @@ impl QrDecoder {
         ))
     }
 
+    /// Transforms through each of `br_candidates` as the bottom-right
+    /// corner, alignment-refined where possible; empty unless `enabled`.
+    #[allow(clippy::too_many_arguments)]
+    fn blind_corner_transforms<'a>(
+        binary: &'a BitMatrix,
+        br_candidates: &'a [Point],
+        enabled: bool,
+        version_num: u8,
+        module_size: f32,
+        top_left: &'a Point,
+        top_right: &'a Point,
+        bottom_left: &'a Point,
+    ) -> impl Iterator<Item = crate::utils::geometry::PerspectiveTransform> + 'a {
+        let dimension = 17 + 4 * version_num as usize;
+        br_candidates
+            .iter()
+            .filter(move |_| enabled)
+            .filter_map(move |br| {
+                let transform =
+                    Self::build_transform(top_left, top_right, bottom_left, br, dimension)?;
+                Some(
+                    Self::refine_transform_with_alignment(
+                        binary,
+                        &transform,
+                        version_num,
+                        dimension,
+                        module_size,
+                        top_left,
+                        top_right,
+                        bottom_left,
+                    )
+                    .unwrap_or(transform),
+                )
+            })
+    }
+
     fn build_transform(
         top_left: &Point,
         top_right: &Point,
@@ -486,46 +1449,6 @@ impl QrDecoder {
         )
     }
 
-    fn extract_qr_region_gray_with_transform_and_confidence_scaled(
-        gray: &[u8],
-        width: usize,
-        height: usize,
-        transform: &crate::utils::geometry::PerspectiveTransform,
-        dimension: usize,
-        sample_scale: f32,
-    ) -> (BitMatrix, Vec<u8>) {
-        geometry::extract_qr_region_gray_with_transform_and_confidence_scaled(
-            gray,
-            width,
-            height,
-            transform,
-            dimension,
-            sample_scale,
-        )
-    }
-
-    fn extract_qr_region_gray_with_mesh_warp(
-        gray: &[u8],
-        width: usize,
-        height: usize,
-        transform: &crate::utils::geometry::PerspectiveTransform,
-        dimension: usize,
-    ) -> (BitMatrix, Vec<u8>) {
-        geometry::extract_qr_region_gray_with_mesh_warp(gray, width, height, transform, dimension)
-    }
-
-    fn extract_qr_region_gray_with_radial_compensation(
-        gray: &[u8],
-        width: usize,
-        height: usize,
-        transform: &crate::utils::geometry::PerspectiveTransform,
-        dimension: usize,
-    ) -> Option<(BitMatrix, Vec<u8>)> {
-        geometry::extract_qr_region_gray_with_radial_compensation(
-            gray, width, height, transform, dimension,
-        )
-    }
-
     #[allow(clippy::too_many_arguments)]
     fn refine_transform_with_alignment(
         binary: &BitMatrix,
@@ -553,15 +1476,88 @@ impl QrDecoder {
         matrix_decode::decode_from_matrix(qr_matrix, version_num)
     }
 
-    pub(crate) fn decode_from_matrix_with_confidence(
+    pub(crate) fn try_decode_from_matrix_with_confidence(
         qr_matrix: &BitMatrix,
         version_num: u8,
         module_confidence: &[u8],
+    ) -> Result<QRCode, DecodeFailure> {
+        matrix_decode::try_decode_from_matrix_with_confidence(
+            qr_matrix,
+            version_num,
+            module_confidence,
+        )
+    }
+
+    /// Record the symbol outline (TL, TR, BR, BL) implied by the sampling
+    /// transform, along with the pose it implies.
+    fn with_corners(
+        mut qr: QRCode,
+        transform: &crate::utils::geometry::PerspectiveTransform,
+        dimension: usize,
+    ) -> QRCode {
+        let d = dimension as f32;
+        // `qr.orientation` is how the symbol sat in the sampled grid.
+        let in_grid = qr.orientation;
+        let corners = Self::symbol_corners(transform, dimension, in_grid);
+        qr.position = corners.map(Point::from);
+        let aspect = crate::config::pixel_aspect_ratio();
+        let centre = Point::new(d / 2.0, d / 2.0);
+        let (rotation, tilt) = transform.pose_angles(&centre, aspect);
+        let rotation = rotation + 90.0 * in_grid.quarter_turns() as f32;
+        let rotation = rotation.rem_euclid(360.0);
+        qr.rotation_deg = if rotation > 180.0 {
+            rotation - 360.0
+        } else {
+            rotation
+        };
+        qr.tilt_estimate = tilt;
+        qr.orientation = Self::image_orientation(&corners, aspect);
+        qr.physical_size_mm = crate::config::physical_size_mm(&qr);
+        qr.binarization = crate::config::active_binarization();
+        qr
+    }
+
+    /// The symbol's own corners (TL, TR, BR, BL), not the grid's, mapped into
+    /// the image for a symbol that sat in the sampled grid turned `in_grid`.
+    fn symbol_corners(
+        transform: &crate::utils::geometry::PerspectiveTransform,
+        dimension: usize,
+        in_grid: Orientation,
+    ) -> [ImagePoint; 4] {
+        let d = dimension as f32;
+        [(0.0, 0.0), (d, 0.0), (d, d), (0.0, d)].map(|(x, y)| {
+            let corner = in_grid.apply(Point::new(x, y), d);
+            transform.to_image(ModulePoint::new(corner.x, corner.y))
+        })
+    }
+
+    /// Orientation of a symbol with corners `position` (top-left, top-right,
+    /// bottom-right, bottom-left), measured in square pixels.
+    fn image_orientation(position: &[ImagePoint; 4], aspect: f32) -> Orientation {
+        let [tl, tr, _, bl] = position;
+        let (ux, uy) = ((tr.x - tl.x) * aspect, tr.y - tl.y);
+        let (vx, vy) = ((bl.x - tl.x) * aspect, bl.y - tl.y);
+        let mirrored = ux * vy - uy * vx < 0.0;
+        // The left edge points straight down when upright, mirrored or not.
+        let turns = ((vy.atan2(vx).to_degrees() - 90.0) / 90.0).round() as i32;
+        Orientation::from_parts(turns.rem_euclid(4) as u8, mirrored)
+    }
+
+    /// Unwrap a decode result, folding any failure into the running cause.
+    fn note_failure(
+        result: Result<QRCode, DecodeFailure>,
+        failure: &mut DecodeFailure,
     ) -> Option<QRCode> {
-        matrix_decode::decode_from_matrix_with_confidence(qr_matrix, version_num, module_confidence)
+        match result {
+            Ok(qr) => Some(qr),
+            Err(e) => {
+                *failure = failure.deeper(e);
+                None
+            }
+        }
     }
 }
 
 #[cfg(test)]
 #[allow(clippy::needless_range_loop)]
-mod tests;
+pub(crate) mod tests;
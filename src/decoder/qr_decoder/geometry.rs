@@ -1,16 +1,79 @@
 use crate::decoder::function_mask::alignment_pattern_positions;
-use crate::models::{BitMatrix, Point};
+use crate::models::{BitMatrix, ImagePoint, ModulePoint, Point};
+use crate::utils::fixed_point::{Fixed, FixedPerspective};
 use crate::utils::geometry::PerspectiveTransform;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// How gray samples are gathered around each projected module center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) enum GridSampling {
+    /// Fixed-pitch grid of bilinear taps, sized by the local module size.
+    #[default]
+    Kernel,
+    /// Tent-weighted bilinear taps spread along the module's projected
+    /// footprint (the transform Jacobian), so small or sheared modules are
+    /// sampled from their own pixels rather than a square around the center.
+    AreaWeighted,
+    /// Integer-only sampling (16.16 transform, 8-bit bilinear weights) for
+    /// targets without an FPU. Default with the `fixed-point` feature.
+    FixedPoint,
+    /// The one pixel under the module center, for rendered images whose
+    /// module edges carry resampling halos a kernel would average in.
+    CenterPixel,
+    /// Every pixel the module's projected quadrilateral covers, weighted by
+    /// the fraction of it inside, for anti-aliased renders whose edge pixels
+    /// blend neighbouring modules in proportion to their coverage.
+    Coverage,
+}
+
+thread_local! {
+    static GRID_SAMPLING: Cell<GridSampling> = const {
+        Cell::new(if cfg!(feature = "fixed-point") {
+            GridSampling::FixedPoint
+        } else {
+            GridSampling::Kernel
+        })
+    };
+}
+
+/// Mode in effect for this thread: [`crate::DetectorConfig::fixed_point_sampling`]
+/// forces the integer path, otherwise the scoped mode applies.
+fn active_grid_sampling() -> GridSampling {
+    if crate::config::with_active(|c| c.fixed_point_sampling) {
+        GridSampling::FixedPoint
+    } else {
+        GRID_SAMPLING.with(Cell::get)
+    }
+}
+
+/// Restores the previous [`GridSampling`] mode when dropped.
+pub(crate) struct GridSamplingScope(GridSampling);
+
+impl Drop for GridSamplingScope {
+    fn drop(&mut self) {
+        GRID_SAMPLING.with(|m| m.set(self.0));
+    }
+}
+
+/// Use `mode` for every gray grid extraction on this thread until the
+/// returned scope is dropped.
+pub(crate) fn scoped_grid_sampling(mode: GridSampling) -> GridSamplingScope {
+    GridSamplingScope(GRID_SAMPLING.with(|m| m.replace(mode)))
+}
 
 pub(super) fn calculate_bottom_right(
     top_left: &Point,
     top_right: &Point,
     bottom_left: &Point,
 ) -> Option<Point> {
-    // In a perfect QR code, bottom_right = top_right + bottom_left - top_left
-    let x = top_right.x + bottom_left.x - top_left.x;
-    let y = top_right.y + bottom_left.y - top_left.y;
-    Some(Point::new(x, y))
+    // In a perfect QR code, bottom_right = top_right + bottom_left - top_left,
+    // which holds in undistorted coordinates.
+    let lens = crate::config::lens_model();
+    let ideal = |p: &Point| lens.map_or(*p, |l| l.undistort(*p));
+    let (tl, tr, bl) = (ideal(top_left), ideal(top_right), ideal(bottom_left));
+    let br = Point::new(tr.x + bl.x - tl.x, tr.y + bl.y - tl.y);
+    Some(lens.map_or(br, |l| l.distort(br)))
 }
 
 pub(super) fn estimate_dimension(
@@ -19,8 +82,10 @@ pub(super) fn estimate_dimension(
     _bottom_right: &Point,
     module_size: f32,
 ) -> Option<usize> {
-    // Calculate width in modules
-    let width_pixels = top_left.distance(top_right);
+    // Calculate width in modules, measured in square pixels.
+    let aspect = crate::config::pixel_aspect_ratio();
+    let width_pixels = Point::new(top_left.x * aspect, top_left.y)
+        .distance(&Point::new(top_right.x * aspect, top_right.y));
     let width_modules = (width_pixels / module_size).round() as i32;
 
     // Infer version from measured width.
@@ -83,14 +148,101 @@ pub(super) fn build_transform(
     bottom_right: &Point,
     dimension: usize,
 ) -> Option<PerspectiveTransform> {
+    let far = dimension as f32 - 3.5;
+    let src = [
+        ModulePoint::new(3.5, 3.5),
+        ModulePoint::new(far, 3.5),
+        ModulePoint::new(3.5, far),
+        ModulePoint::new(far, far),
+    ];
+    let dst = [*top_left, *top_right, *bottom_left, *bottom_right].map(ImagePoint::from);
+    transform_to_image(&src, &dst)
+}
+
+/// Most a finder-to-finder side may lean off an image axis, in modules over
+/// the side, for [`snapped_axis_transform`] to take the symbol as unrotated.
+const SNAP_MAX_LEAN: f32 = 0.5;
+
+/// How close to a whole number of pixels the module pitch must be to snap to
+/// it exactly.
+const SNAP_PITCH_TOLERANCE: f32 = 0.1;
+
+/// Axis-aligned transform for a symbol rendered straight onto the pixel grid
+/// (in any of the four right-angle orientations), or `None` if the finders
+/// lean off the image axes.
+///
+/// Finder centres found on a resampled rendering jitter by a fraction of a
+/// pixel, and a perspective fit through them tilts the grid to match. Here
+/// the pitch along each side is kept on its axis, rounded to whole pixels
+/// when within [`SNAP_PITCH_TOLERANCE`] of them, and the origin is the mean
+/// the three finders agree on, itself on a pixel boundary once the pitch is
+/// whole.
+pub(super) fn snapped_axis_transform(
+    top_left: &Point,
+    top_right: &Point,
+    bottom_left: &Point,
+    dimension: usize,
+) -> Option<PerspectiveTransform> {
+    let span = dimension as f32 - 7.0;
+    let snap_axis = |from: &Point, to: &Point| -> Option<(f32, f32, bool)> {
+        let (dx, dy) = ((to.x - from.x) / span, (to.y - from.y) / span);
+        let (major, minor) = if dx.abs() >= dy.abs() {
+            (dx, dy)
+        } else {
+            (dy, dx)
+        };
+        if major.abs() < 1.0 || minor.abs() * span > SNAP_MAX_LEAN * major.abs() {
+            return None;
+        }
+        let whole = major.round();
+        let snapped = (major - whole).abs() <= SNAP_PITCH_TOLERANCE;
+        let major = if snapped { whole } else { major };
+        Some(if dx.abs() >= dy.abs() {
+            (major, 0.0, snapped)
+        } else {
+            (0.0, major, snapped)
+        })
+    };
+    let (ux, uy, u_whole) = snap_axis(top_left, top_right)?;
+    let (vx, vy, v_whole) = snap_axis(top_left, bottom_left)?;
+    // The two sides must run along different axes.
+    if (ux == 0.0) == (vx == 0.0) {
+        return None;
+    }
+    let at = |gx: f32, gy: f32| (gx * ux + gy * vx, gx * uy + gy * vy);
+    let far = dimension as f32 - 3.5;
+    let origins = [
+        (top_left, at(3.5, 3.5)),
+        (top_right, at(far, 3.5)),
+        (bottom_left, at(3.5, far)),
+    ];
+    let mut ox = origins.iter().map(|(p, o)| p.x - o.0).sum::<f32>() / 3.0;
+    let mut oy = origins.iter().map(|(p, o)| p.y - o.1).sum::<f32>() / 3.0;
+    if u_whole && v_whole {
+        ox = ox.round();
+        oy = oy.round();
+    }
+    let d = dimension as f32;
     let src = [
-        Point::new(3.5, 3.5),
-        Point::new(dimension as f32 - 3.5, 3.5),
-        Point::new(3.5, dimension as f32 - 3.5),
-        Point::new(dimension as f32 - 3.5, dimension as f32 - 3.5),
+        ModulePoint::new(0.0, 0.0),
+        ModulePoint::new(d, 0.0),
+        ModulePoint::new(0.0, d),
+        ModulePoint::new(d, d),
     ];
-    let dst = [*top_left, *top_right, *bottom_left, *bottom_right];
-    PerspectiveTransform::from_points(&src, &dst)
+    let dst = src.map(|m| {
+        let (x, y) = at(m.x, m.y);
+        ImagePoint::new(ox + x, oy + y)
+    });
+    PerspectiveTransform::module_to_image(&src, &dst, None)
+}
+
+/// Transform from grid points `src` to image points `dst`, through the
+/// configured lens if any.
+fn transform_to_image(
+    src: &[ModulePoint; 4],
+    dst: &[ImagePoint; 4],
+) -> Option<PerspectiveTransform> {
+    PerspectiveTransform::module_to_image(src, dst, crate::config::lens_model())
 }
 
 pub(super) fn extract_qr_region_with_transform(
@@ -102,8 +254,7 @@ pub(super) fn extract_qr_region_with_transform(
 
     for y in 0..dimension {
         for x in 0..dimension {
-            let module_center = Point::new(x as f32 + 0.5, y as f32 + 0.5);
-            let img_point = transform.transform(&module_center);
+            let img_point = transform.to_image(ModulePoint::center_of(x, y));
 
             let img_x = img_point.x.round() as isize;
             let img_y = img_point.y.round() as isize;
@@ -158,51 +309,369 @@ pub(super) fn extract_qr_region_gray_with_transform_and_confidence(
     extract_qr_region_gray_with_variant(gray, width, height, transform, dimension, 0.0, 0.0, 1.0)
 }
 
-pub(super) fn extract_qr_region_gray_with_transform_and_confidence_scaled(
+/// Key for [`GridSampleCache`]: the grid corners in image space, quantized
+/// to 1/16 px, plus everything else that changes the sampled values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GridSampleKey {
+    corners: [(i32, i32); 4],
+    dimension: usize,
+    radial_k1: u32,
+    mesh_strength: u32,
+    sample_scale: u32,
+    mode: GridSampling,
+}
+
+impl GridSampleKey {
+    fn new(
+        transform: &PerspectiveTransform,
+        dimension: usize,
+        radial_k1: f32,
+        mesh_strength: f32,
+        sample_scale: f32,
+    ) -> Self {
+        let d = dimension as f32;
+        let quantize = |x: f32, y: f32| {
+            let p = transform.to_image(ModulePoint::new(x, y));
+            ((p.x * 16.0).round() as i32, (p.y * 16.0).round() as i32)
+        };
+        Self {
+            corners: [
+                quantize(0.0, 0.0),
+                quantize(d, 0.0),
+                quantize(0.0, d),
+                quantize(d, d),
+            ],
+            dimension,
+            radial_k1: radial_k1.to_bits(),
+            mesh_strength: mesh_strength.to_bits(),
+            sample_scale: sample_scale.to_bits(),
+            mode: active_grid_sampling(),
+        }
+    }
+}
+
+/// Gray grids already sampled for one finder triplet. Neighbouring
+/// bottom-right guesses often snap to the same transform after alignment
+/// refinement, and the recovery ladder re-samples identical variants, so
+/// repeated requests are served from here instead of re-projecting every
+/// module. Only valid for a single gray image.
+#[derive(Default)]
+pub(super) struct GridSampleCache {
+    entries: HashMap<GridSampleKey, (BitMatrix, Vec<u8>)>,
+}
+
+impl GridSampleCache {
+    #[allow(clippy::too_many_arguments)]
+    fn get_or_sample(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+        transform: &PerspectiveTransform,
+        dimension: usize,
+        radial_k1: f32,
+        mesh_strength: f32,
+        sample_scale: f32,
+    ) -> (BitMatrix, Vec<u8>) {
+        let key = GridSampleKey::new(transform, dimension, radial_k1, mesh_strength, sample_scale);
+        if let Some(cached) = self.entries.get(&key) {
+            super::DECODE_COUNTERS.with(|c| c.borrow_mut().grid_sample_cache_hits += 1);
+            return cached.clone();
+        }
+        let sampled = extract_qr_region_gray_with_variant(
+            gray,
+            width,
+            height,
+            transform,
+            dimension,
+            radial_k1,
+            mesh_strength,
+            sample_scale,
+        );
+        self.entries.insert(key, sampled.clone());
+        sampled
+    }
+
+    /// Gray grid with confidence, sampled with taps spread by `sample_scale`.
+    pub(super) fn sample(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+        transform: &PerspectiveTransform,
+        dimension: usize,
+        sample_scale: f32,
+    ) -> (BitMatrix, Vec<u8>) {
+        self.get_or_sample(
+            gray,
+            width,
+            height,
+            transform,
+            dimension,
+            0.0,
+            0.0,
+            sample_scale,
+        )
+    }
+
+    /// Gray grid sampled through a bounded mesh warp for rotated or bent codes.
+    pub(super) fn sample_mesh_warp(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+        transform: &PerspectiveTransform,
+        dimension: usize,
+    ) -> (BitMatrix, Vec<u8>) {
+        self.get_or_sample(gray, width, height, transform, dimension, 0.0, 0.9, 1.0)
+    }
+
+    /// Gray grid with estimated barrel distortion undone, if any is detected.
+    pub(super) fn sample_radial_compensation(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+        transform: &PerspectiveTransform,
+        dimension: usize,
+    ) -> Option<(BitMatrix, Vec<u8>)> {
+        let k1 = estimate_radial_k1(transform, dimension)?;
+        Some(self.get_or_sample(gray, width, height, transform, dimension, k1, 0.0, 1.0))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn extract_qr_region_gray_with_variant(
     gray: &[u8],
     width: usize,
     height: usize,
     transform: &PerspectiveTransform,
     dimension: usize,
+    radial_k1: f32,
+    mesh_strength: f32,
     sample_scale: f32,
 ) -> (BitMatrix, Vec<u8>) {
-    extract_qr_region_gray_with_variant(
+    // Radial and mesh variants are recovery-only and stay on the float path.
+    if active_grid_sampling() == GridSampling::FixedPoint
+        && radial_k1 == 0.0
+        && mesh_strength == 0.0
+        && let Some(fixed) = FixedPerspective::from_transform(transform)
+    {
+        let scale_q8 = (sample_scale * 256.0) as i64;
+        return extract_qr_region_gray_fixed(gray, width, height, &fixed, dimension, scale_q8);
+    }
+    let (samples, local_std_dev) = sample_module_means(
         gray,
         width,
         height,
         transform,
         dimension,
-        0.0,
-        0.0,
+        radial_k1,
+        mesh_strength,
         sample_scale,
-    )
+    );
+
+    threshold_samples(&samples, &local_std_dev, dimension, |x, y| {
+        local_threshold(&samples, dimension, x, y)
+    })
 }
 
-pub(super) fn extract_qr_region_gray_with_radial_compensation(
+/// Module grid and confidence from sampled means, each module dark when
+/// below `threshold(x, y)`.
+fn threshold_samples(
+    samples: &[f32],
+    local_std_dev: &[f32],
+    dimension: usize,
+    threshold: impl Fn(usize, usize) -> f32,
+) -> (BitMatrix, Vec<u8>) {
+    let mut result = BitMatrix::new(dimension, dimension);
+    let mut confidence = vec![0u8; dimension * dimension];
+    for y in 0..dimension {
+        for x in 0..dimension {
+            let idx = y * dimension + x;
+            let local_t = threshold(x, y);
+            let s = samples[idx];
+            result.set(x, y, s < local_t);
+
+            let margin = (s - local_t).abs();
+            let var_penalty = (local_std_dev[idx] / 96.0).clamp(0.0, 1.0);
+            let conf = ((margin / 64.0) * (1.0 - 0.45 * var_penalty)).clamp(0.0, 1.0);
+            confidence[idx] = (conf * 255.0).round() as u8;
+        }
+    }
+
+    (result, confidence)
+}
+
+/// Where in each module's local gray range the threshold sweep puts the
+/// threshold, either side of the local mean the first sampling uses.
+const THRESHOLD_SWEEP_FRACTIONS: [f32; 2] = [0.4, 0.6];
+/// Local gray range below which a window counts as flat and keeps its mean
+/// threshold, so the sweep doesn't split sensor noise into modules.
+const THRESHOLD_SWEEP_MIN_RANGE: f32 = 16.0;
+
+/// The grid re-thresholded at 40% and then 60% of each module's 5x5
+/// neighbourhood gray range in place of its mean. Shadow or glare across a
+/// window drags the mean off the midpoint between its dark and light
+/// modules; one of the two lands nearer it.
+pub(super) fn threshold_sweep_grids(
     gray: &[u8],
     width: usize,
     height: usize,
     transform: &PerspectiveTransform,
     dimension: usize,
-) -> Option<(BitMatrix, Vec<u8>)> {
-    let k1 = estimate_radial_k1(transform, dimension)?;
-    Some(extract_qr_region_gray_with_variant(
-        gray, width, height, transform, dimension, k1, 0.0, 1.0,
-    ))
+) -> Vec<(BitMatrix, Vec<u8>)> {
+    let (samples, local_std_dev) =
+        sample_module_means(gray, width, height, transform, dimension, 0.0, 0.0, 1.0);
+    THRESHOLD_SWEEP_FRACTIONS
+        .iter()
+        .map(|&fraction| {
+            threshold_samples(&samples, &local_std_dev, dimension, |x, y| {
+                let (lo, hi) = local_range(&samples, dimension, x, y);
+                if hi - lo < THRESHOLD_SWEEP_MIN_RANGE {
+                    local_threshold(&samples, dimension, x, y)
+                } else {
+                    lo + fraction * (hi - lo)
+                }
+            })
+        })
+        .collect()
 }
 
-pub(super) fn extract_qr_region_gray_with_mesh_warp(
+/// Integer-only counterpart of [`extract_qr_region_gray_with_variant`].
+///
+/// Module centers come from `transform` in 16.16, each module is the mean of
+/// a 3x3 grid of bilinear taps (8-bit weights) spaced by a fifth of the local
+/// module pitch, and thresholding and confidence follow the float path with
+/// samples kept in 1/16 gray levels.
+fn extract_qr_region_gray_fixed(
     gray: &[u8],
     width: usize,
     height: usize,
-    transform: &PerspectiveTransform,
+    transform: &FixedPerspective,
     dimension: usize,
+    sample_scale_q8: i64,
 ) -> (BitMatrix, Vec<u8>) {
-    extract_qr_region_gray_with_variant(gray, width, height, transform, dimension, 0.0, 0.9, 1.0)
+    const ONE: i64 = 1 << 16;
+    let n = dimension * dimension;
+    let mut samples = vec![255i64 * 16; n];
+    let mut std_dev = vec![0i64; n];
+    let mut centers = Vec::with_capacity(dimension + 1);
+    for y in 0..dimension {
+        // One extra center past the edge gives every module a right-hand
+        // neighbour for its pitch.
+        transform.map_row(
+            Fixed::from_bits((ONE / 2) as i32),
+            Fixed::from_bits((y as i64 * ONE + ONE / 2) as i32),
+            Fixed::from_i32(1),
+            dimension + 1,
+            &mut centers,
+        );
+        for x in 0..dimension {
+            let (Some((cx, cy)), Some((nx, ny))) = (centers[x], centers[x + 1]) else {
+                continue;
+            };
+            let (cx, cy) = (i64::from(cx.to_bits()), i64::from(cy.to_bits()));
+            let pitch = (i64::from(nx.to_bits()) - cx)
+                .abs()
+                .max((i64::from(ny.to_bits()) - cy).abs());
+            let step = (pitch * 51 / 256 * sample_scale_q8 / 256).clamp(ONE / 4, ONE * 3 / 2);
+
+            let (mut sum, mut sum_sq, mut count) = (0i64, 0i64, 0i64);
+            for oy in -1..=1 {
+                for ox in -1..=1 {
+                    if let Some(v) =
+                        bilinear_sample_fixed(gray, width, height, cx + ox * step, cy + oy * step)
+                    {
+                        sum += v;
+                        sum_sq += v * v;
+                        count += 1;
+                    }
+                }
+            }
+            if count > 0 {
+                let idx = y * dimension + x;
+                let mean = sum / count;
+                samples[idx] = mean;
+                std_dev[idx] = (sum_sq / count - mean * mean).max(0).isqrt();
+            }
+        }
+    }
+
+    let mut result = BitMatrix::new(dimension, dimension);
+    let mut confidence = vec![0u8; n];
+    for y in 0..dimension {
+        for x in 0..dimension {
+            let idx = y * dimension + x;
+            let (x0, x1) = (x.saturating_sub(2), (x + 2).min(dimension - 1));
+            let (y0, y1) = (y.saturating_sub(2), (y + 2).min(dimension - 1));
+            let mut local = 0i64;
+            for yy in y0..=y1 {
+                local += samples[yy * dimension + x0..=yy * dimension + x1]
+                    .iter()
+                    .sum::<i64>();
+            }
+            let threshold = local / ((x1 - x0 + 1) * (y1 - y0 + 1)) as i64 - 3 * 16;
+            let s = samples[idx];
+            result.set(x, y, s < threshold);
+
+            // Same as the float path, in 1/1024 units.
+            let margin = ((s - threshold).abs() * 1024 / (64 * 16)).min(1024);
+            let var_penalty = (std_dev[idx] * 1024 / (96 * 16)).min(1024);
+            let conf = margin * (1024 - 461 * var_penalty / 1024) / 1024;
+            confidence[idx] = ((conf * 255 + 512) / 1024) as u8;
+        }
+    }
+
+    (result, confidence)
 }
 
+/// Bilinear sample at a 16.16 position, in 1/16 gray levels.
+fn bilinear_sample_fixed(gray: &[u8], width: usize, height: usize, x: i64, y: i64) -> Option<i64> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    let (x0, y0) = ((x >> 16) as usize, (y >> 16) as usize);
+    let (fx, fy) = ((x & 0xffff) >> 8, (y & 0xffff) >> 8);
+    if x0 >= width || y0 >= height || (x0 == width - 1 && fx > 0) || (y0 == height - 1 && fy > 0) {
+        return None;
+    }
+    let x1 = (x0 + 1).min(width - 1);
+    let y1 = (y0 + 1).min(height - 1);
+    let p = |px: usize, py: usize| i64::from(gray[py * width + px]);
+    let top = p(x0, y0) * (256 - fx) + p(x1, y0) * fx;
+    let bottom = p(x0, y1) * (256 - fx) + p(x1, y1) * fx;
+    Some((top * (256 - fy) + bottom * fy) >> 12)
+}
+
+/// Per-module signed contrast against the local threshold, scaled by the
+/// grid's dark/light span: positive is dark. Comparable across frames with
+/// different exposure, so it can be averaged for multi-frame fusion.
+pub(super) fn sample_module_contrast(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    transform: &PerspectiveTransform,
+    dimension: usize,
+) -> Vec<f32> {
+    let (samples, _) =
+        sample_module_means(gray, width, height, transform, dimension, 0.0, 0.0, 1.0);
+    let lo = samples.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = samples.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let span = (hi - lo).max(1.0);
+    let mut levels = vec![0.0f32; dimension * dimension];
+    for y in 0..dimension {
+        for x in 0..dimension {
+            let idx = y * dimension + x;
+            levels[idx] = (local_threshold(&samples, dimension, x, y) - samples[idx]) / span;
+        }
+    }
+    levels
+}
+
+/// Mean and standard deviation of the gray samples around each module center.
 #[allow(clippy::too_many_arguments)]
-fn extract_qr_region_gray_with_variant(
+fn sample_module_means(
     gray: &[u8],
     width: usize,
     height: usize,
@@ -211,18 +680,21 @@ fn extract_qr_region_gray_with_variant(
     radial_k1: f32,
     mesh_strength: f32,
     sample_scale: f32,
-) -> (BitMatrix, Vec<u8>) {
+) -> (Vec<f32>, Vec<f32>) {
     let mut samples: Vec<f32> = vec![255.0; dimension * dimension];
     let mut local_std_dev: Vec<f32> = vec![0.0; dimension * dimension];
-    let center_module = Point::new(
+    let center_module = ModulePoint::new(
         (dimension as f32 - 1.0) * 0.5,
         (dimension as f32 - 1.0) * 0.5,
     );
-    let center_image = transform.transform(&center_module);
+    let center_image = transform.to_image(center_module);
+    let mode = active_grid_sampling();
+    let strong_perspective =
+        perspective_scale_ratio(transform, dimension) >= STRONG_PERSPECTIVE_RATIO;
     for y in 0..dimension {
+        let row_scale = strong_perspective.then(|| RowScale::new(transform, y, dimension));
         for x in 0..dimension {
-            let module_center = Point::new(x as f32 + 0.5, y as f32 + 0.5);
-            let mut img_point = transform.transform(&module_center);
+            let mut img_point = transform.to_image(ModulePoint::center_of(x, y));
             if radial_k1 != 0.0 {
                 let ux = ((x as f32 + 0.5) / dimension as f32) - 0.5;
                 let uy = ((y as f32 + 0.5) / dimension as f32) - 0.5;
@@ -239,32 +711,54 @@ fn extract_qr_region_gray_with_variant(
                 img_point.x += dx;
                 img_point.y += dy;
             }
-            let module_px = estimate_local_module_pixels(transform, x, y);
-            let radius =
-                ((adaptive_kernel_radius(module_px) as f32) * sample_scale).round() as usize;
-            let radius = radius.clamp(1, 4);
-            let sample_step = (0.35 / sample_scale.max(0.8)).clamp(0.2, 0.45);
-
-            let mut sum = 0.0f32;
-            let mut sum_sq = 0.0f32;
-            let mut count = 0usize;
-            for oy in -(radius as isize)..=(radius as isize) {
-                for ox in -(radius as isize)..=(radius as isize) {
-                    let sx = img_point.x + ox as f32 * sample_step;
-                    let sy = img_point.y + oy as f32 * sample_step;
-                    if let Some(v) = bilinear_sample(gray, width, height, sx, sy) {
-                        sum += v;
-                        sum_sq += v * v;
-                        count += 1;
-                    }
-                }
-            }
+            let (sum, sum_sq, count) = match mode {
+                GridSampling::Kernel | GridSampling::FixedPoint => match &row_scale {
+                    Some(row) => kernel_taps(
+                        gray,
+                        width,
+                        height,
+                        img_point,
+                        row.at(x),
+                        sample_scale,
+                        true,
+                    ),
+                    None => kernel_taps(
+                        gray,
+                        width,
+                        height,
+                        img_point,
+                        estimate_local_module_pixels(transform, x, y),
+                        sample_scale,
+                        false,
+                    ),
+                },
+                GridSampling::AreaWeighted => footprint_taps(
+                    gray,
+                    width,
+                    height,
+                    transform,
+                    x,
+                    y,
+                    img_point,
+                    sample_scale,
+                ),
+                GridSampling::CenterPixel => center_tap(gray, width, height, img_point),
+                GridSampling::Coverage => coverage_taps(
+                    gray,
+                    width,
+                    height,
+                    transform,
+                    x,
+                    y,
+                    img_point,
+                    sample_scale,
+                ),
+            };
 
             let idx = y * dimension + x;
-            let avg = if count > 0 { sum / count as f32 } else { 255.0 };
-            let variance = if count > 1 {
-                let c = count as f32;
-                (sum_sq / c) - avg * avg
+            let avg = if count > 0.0 { sum / count } else { 255.0 };
+            let variance = if count > 1.0 {
+                (sum_sq / count) - avg * avg
             } else {
                 0.0
             };
@@ -273,23 +767,212 @@ fn extract_qr_region_gray_with_variant(
         }
     }
 
-    let mut result = BitMatrix::new(dimension, dimension);
-    let mut confidence = vec![0u8; dimension * dimension];
-    for y in 0..dimension {
-        for x in 0..dimension {
-            let idx = y * dimension + x;
-            let local_t = local_threshold(&samples, dimension, x, y);
-            let s = samples[idx];
-            result.set(x, y, s < local_t);
+    (samples, local_std_dev)
+}
 
-            let margin = (s - local_t).abs();
-            let var_penalty = (local_std_dev[idx] / 96.0).clamp(0.0, 1.0);
-            let conf = ((margin / 64.0) * (1.0 - 0.45 * var_penalty)).clamp(0.0, 1.0);
-            confidence[idx] = (conf * 255.0).round() as u8;
+/// Sum, sum of squares and weight of the single pixel containing `center`.
+fn center_tap(gray: &[u8], width: usize, height: usize, center: ImagePoint) -> (f32, f32, f32) {
+    let (x, y) = (center.x.floor(), center.y.floor());
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return (0.0, 0.0, 0.0);
+    }
+    let v = gray[y as usize * width + x as usize] as f32;
+    (v, v * v, 1.0)
+}
+
+/// Weighted sum, sum of squares and total weight of a square kernel of
+/// bilinear taps. With `scale_to_module` the tap pitch follows the module's
+/// pixel size instead of a fixed sub-pixel step, so tiny modules are sampled
+/// tightly around their centre and large ones spread their taps wider.
+fn kernel_taps(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    center: ImagePoint,
+    module_px: f32,
+    sample_scale: f32,
+    scale_to_module: bool,
+) -> (f32, f32, f32) {
+    let radius = ((adaptive_kernel_radius(module_px) as f32) * sample_scale).round() as usize;
+    let radius = radius.clamp(1, 4);
+    let sample_step = if scale_to_module {
+        (module_px * 0.1 * sample_scale / radius as f32).clamp(0.2, 1.5)
+    } else {
+        (0.35 / sample_scale.max(0.8)).clamp(0.2, 0.45)
+    };
+
+    let mut sum = 0.0f32;
+    let mut sum_sq = 0.0f32;
+    let mut count = 0.0f32;
+    for oy in -(radius as isize)..=(radius as isize) {
+        for ox in -(radius as isize)..=(radius as isize) {
+            let sx = center.x + ox as f32 * sample_step;
+            let sy = center.y + oy as f32 * sample_step;
+            if let Some(v) = bilinear_sample(gray, width, height, sx, sy) {
+                sum += v;
+                sum_sq += v * v;
+                count += 1.0;
+            }
         }
     }
+    (sum, sum_sq, count)
+}
 
-    (result, confidence)
+/// Largest over smallest module pixel size among the four corner modules.
+fn perspective_scale_ratio(transform: &PerspectiveTransform, dimension: usize) -> f32 {
+    let last = dimension.saturating_sub(1);
+    let sizes = [
+        estimate_local_module_pixels(transform, 0, 0),
+        estimate_local_module_pixels(transform, last, 0),
+        estimate_local_module_pixels(transform, 0, last),
+        estimate_local_module_pixels(transform, last, last),
+    ];
+    let lo = sizes.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = sizes.iter().copied().fold(0.0f32, f32::max);
+    hi / lo.max(0.5)
+}
+
+/// Corner size ratio above which the kernel adapts to per-row module scale.
+const STRONG_PERSPECTIVE_RATIO: f32 = 1.5;
+
+/// Module pixel size along one grid row, measured from the transform
+/// Jacobian at both ends and interpolated across the row.
+struct RowScale {
+    start: f32,
+    end: f32,
+    last: f32,
+}
+
+impl RowScale {
+    fn new(transform: &PerspectiveTransform, y: usize, dimension: usize) -> Self {
+        let last = dimension.saturating_sub(1);
+        Self {
+            start: estimate_local_module_pixels(transform, 0, y),
+            end: estimate_local_module_pixels(transform, last, y),
+            last: last.max(1) as f32,
+        }
+    }
+
+    fn at(&self, x: usize) -> f32 {
+        let t = x as f32 / self.last;
+        self.start + (self.end - self.start) * t
+    }
+}
+
+/// Weighted sum, sum of squares and total weight of tent-weighted taps laid
+/// out along the module's projected axes, covering the inner part of its
+/// footprint so neighbouring modules do not bleed in.
+#[allow(clippy::too_many_arguments)]
+fn footprint_taps(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    transform: &PerspectiveTransform,
+    x: usize,
+    y: usize,
+    center: ImagePoint,
+    sample_scale: f32,
+) -> (f32, f32, f32) {
+    // Jacobian columns: image-space extent of one module along each grid axis.
+    let module = ModulePoint::center_of(x, y);
+    let left = transform.to_image(module.offset(-0.5, 0.0));
+    let right = transform.to_image(module.offset(0.5, 0.0));
+    let up = transform.to_image(module.offset(0.0, -0.5));
+    let down = transform.to_image(module.offset(0.0, 0.5));
+    let (ux, uy) = (right.x - left.x, right.y - left.y);
+    let (vx, vy) = (down.x - up.x, down.y - up.y);
+
+    let module_px = estimate_local_module_pixels(transform, x, y);
+    let taps: isize = if module_px >= 4.0 { 2 } else { 1 };
+    // Half-extent of the sampled area as a fraction of the module: tight for
+    // small modules, where blur reaches the centre, wider for large ones.
+    let extent = ((0.42 * (1.0 - 1.6 / module_px)).clamp(0.08, 0.42) * sample_scale).min(0.45);
+    let step = extent / taps as f32;
+
+    let mut sum = 0.0f32;
+    let mut sum_sq = 0.0f32;
+    let mut weight = 0.0f32;
+    for j in -taps..=taps {
+        for i in -taps..=taps {
+            let (fu, fv) = (i as f32 * step, j as f32 * step);
+            let w = (1.0 - fu.abs() / (extent * 2.0)) * (1.0 - fv.abs() / (extent * 2.0));
+            let sx = center.x + ux * fu + vx * fv;
+            let sy = center.y + uy * fu + vy * fv;
+            if let Some(v) = bilinear_sample(gray, width, height, sx, sy) {
+                sum += w * v;
+                sum_sq += w * v * v;
+                weight += w;
+            }
+        }
+    }
+    (sum, sum_sq, weight)
+}
+
+/// Half-extent, in modules, of the footprint [`coverage_taps`] integrates:
+/// the inner 70% of the module, so a finder fit a fraction of a module off
+/// still keeps the neighbours' share of the edge pixels small.
+const COVERAGE_HALF_EXTENT: f32 = 0.35;
+
+/// Weighted sum, sum of squares and total weight of the pixels under the
+/// module's projected footprint, each weighted by the share of its area
+/// inside the footprint. The transform is taken as affine across one module,
+/// so pixel centres map back to module coordinates through the inverse
+/// Jacobian.
+#[allow(clippy::too_many_arguments)]
+fn coverage_taps(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    transform: &PerspectiveTransform,
+    x: usize,
+    y: usize,
+    center: ImagePoint,
+    sample_scale: f32,
+) -> (f32, f32, f32) {
+    let module = ModulePoint::center_of(x, y);
+    let left = transform.to_image(module.offset(-0.5, 0.0));
+    let right = transform.to_image(module.offset(0.5, 0.0));
+    let up = transform.to_image(module.offset(0.0, -0.5));
+    let down = transform.to_image(module.offset(0.0, 0.5));
+    let (ux, uy) = (right.x - left.x, right.y - left.y);
+    let (vx, vy) = (down.x - up.x, down.y - up.y);
+    let det = ux * vy - vx * uy;
+    if det.abs() < 1e-3 {
+        return center_tap(gray, width, height, center);
+    }
+    // Inverse Jacobian rows: module units per image pixel.
+    let (iux, iuy) = (vy / det, -vx / det);
+    let (ivx, ivy) = (-uy / det, ux / det);
+    // A pixel's extent along each module axis.
+    let (su, sv) = (iux.abs() + iuy.abs(), ivx.abs() + ivy.abs());
+    let half = COVERAGE_HALF_EXTENT * sample_scale.min(1.0);
+
+    let reach_x = (ux.abs() + vx.abs()) * half + 1.0;
+    let reach_y = (uy.abs() + vy.abs()) * half + 1.0;
+    let x0 = (center.x - reach_x).floor().max(0.0) as usize;
+    let y0 = (center.y - reach_y).floor().max(0.0) as usize;
+    let x1 = ((center.x + reach_x).ceil() as usize).min(width);
+    let y1 = ((center.y + reach_y).ceil() as usize).min(height);
+    let overlap =
+        |f: f32, s: f32| ((f + s * 0.5).min(half) - (f - s * 0.5).max(-half)).max(0.0) / s;
+
+    let mut sum = 0.0f32;
+    let mut sum_sq = 0.0f32;
+    let mut weight = 0.0f32;
+    for py in y0..y1 {
+        let dy = py as f32 + 0.5 - center.y;
+        for px in x0..x1 {
+            let dx = px as f32 + 0.5 - center.x;
+            let w = overlap(iux * dx + iuy * dy, su) * overlap(ivx * dx + ivy * dy, sv);
+            if w > 0.0 {
+                let v = gray[py * width + px] as f32;
+                sum += w * v;
+                sum_sq += w * v * v;
+                weight += w;
+            }
+        }
+    }
+    (sum, sum_sq, weight)
 }
 
 fn estimate_radial_k1(transform: &PerspectiveTransform, dimension: usize) -> Option<f32> {
@@ -333,9 +1016,9 @@ pub(super) fn refine_transform_with_alignment(
 
     let centers = alignment_centers(version_num, dimension);
     let (ax, ay) = centers.iter().max_by_key(|(x, y)| x + y)?;
-    let align_src = Point::new(*ax as f32 + 0.5, *ay as f32 + 0.5);
-    let predicted = transform.transform(&align_src);
-    let found = find_alignment_center(binary, predicted, module_size)?;
+    let align_src = ModulePoint::center_of(*ax, *ay);
+    let predicted = transform.to_image(align_src);
+    let found = find_alignment_center(binary, predicted.into(), module_size)?;
     let best = best_refined_transform(
         binary,
         dimension,
@@ -344,7 +1027,7 @@ pub(super) fn refine_transform_with_alignment(
         top_right,
         bottom_left,
         align_src,
-        found,
+        found.into(),
         module_size,
     )?;
 
@@ -357,6 +1040,33 @@ pub(super) fn refine_transform_with_alignment(
     Some(best)
 }
 
+/// Transform through the finder centers and the bottom-right alignment
+/// pattern, searched for where the parallelogram corner predicts it, so the
+/// fourth corner follows from the symbol instead of blind offsets. `None`
+/// for version 1, or when no pattern is found or it fits worse.
+pub(super) fn alignment_anchored_transform(
+    binary: &BitMatrix,
+    version_num: u8,
+    dimension: usize,
+    module_size: f32,
+    top_left: &Point,
+    top_right: &Point,
+    bottom_left: &Point,
+) -> Option<PerspectiveTransform> {
+    let bottom_right = calculate_bottom_right(top_left, top_right, bottom_left)?;
+    let nominal = build_transform(top_left, top_right, bottom_left, &bottom_right, dimension)?;
+    refine_transform_with_alignment(
+        binary,
+        &nominal,
+        version_num,
+        dimension,
+        module_size,
+        top_left,
+        top_right,
+        bottom_left,
+    )
+}
+
 fn alignment_centers(version: u8, dimension: usize) -> Vec<(usize, usize)> {
     let positions = alignment_pattern_positions(version);
     if positions.is_empty() {
@@ -475,9 +1185,10 @@ fn bilinear_sample(gray: &[u8], width: usize, height: usize, x: f32, y: f32) ->
 }
 
 fn estimate_local_module_pixels(transform: &PerspectiveTransform, x: usize, y: usize) -> f32 {
-    let p = transform.transform(&Point::new(x as f32 + 0.5, y as f32 + 0.5));
-    let px = transform.transform(&Point::new(x as f32 + 1.5, y as f32 + 0.5));
-    let py = transform.transform(&Point::new(x as f32 + 0.5, y as f32 + 1.5));
+    let module = ModulePoint::center_of(x, y);
+    let p = transform.to_image(module);
+    let px = transform.to_image(module.offset(1.0, 0.0));
+    let py = transform.to_image(module.offset(0.0, 1.0));
     let sx = p.distance(&px);
     let sy = p.distance(&py);
     ((sx + sy) * 0.5).clamp(0.5, 8.0)
@@ -495,6 +1206,15 @@ fn adaptive_kernel_radius(module_px: f32) -> usize {
     }
 }
 
+/// Darkest and lightest sample in the window [`local_threshold`] averages.
+fn local_range(samples: &[f32], dimension: usize, x: usize, y: usize) -> (f32, f32) {
+    let (x0, x1) = (x.saturating_sub(2), (x + 2).min(dimension - 1));
+    let (y0, y1) = (y.saturating_sub(2), (y + 2).min(dimension - 1));
+    (y0..=y1)
+        .flat_map(|yy| &samples[yy * dimension + x0..=yy * dimension + x1])
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)))
+}
+
 fn local_threshold(samples: &[f32], dimension: usize, x: usize, y: usize) -> f32 {
     let radius = 2usize;
     let min_x = x.saturating_sub(radius);
@@ -514,7 +1234,7 @@ fn local_threshold(samples: &[f32], dimension: usize, x: usize, y: usize) -> f32
     mean - 3.0
 }
 
-fn transform_quality(
+pub(super) fn transform_quality(
     binary: &BitMatrix,
     transform: &PerspectiveTransform,
     dimension: usize,
@@ -527,8 +1247,8 @@ fn transform_quality(
     if version_num >= 2 {
         let centers = alignment_centers(version_num, dimension);
         if let Some((ax, ay)) = centers.iter().max_by_key(|(x, y)| x + y) {
-            let p = transform.transform(&Point::new(*ax as f32 + 0.5, *ay as f32 + 0.5));
-            if let Some(mm) = alignment_pattern_mismatch(binary, &p, module_size.max(1.0)) {
+            let p = transform.to_image(ModulePoint::center_of(*ax, *ay));
+            if let Some(mm) = alignment_pattern_mismatch(binary, &p.into(), module_size.max(1.0)) {
                 let align = 1.0 - (mm as f32 / 25.0).clamp(0.0, 1.0);
                 score += align * 0.25;
             }
@@ -543,7 +1263,7 @@ fn transform_quality(
 fn timing_quality(binary: &BitMatrix, transform: &PerspectiveTransform, dimension: usize) -> f32 {
     let mut h_bits = Vec::new();
     for m in 8..=(dimension.saturating_sub(9)) {
-        let p = transform.transform(&Point::new(m as f32 + 0.5, 6.5));
+        let p = transform.to_image(ModulePoint::center_of(m, 6));
         let ix = p.x.round() as isize;
         let iy = p.y.round() as isize;
         if ix < 0 || iy < 0 || ix as usize >= binary.width() || iy as usize >= binary.height() {
@@ -554,7 +1274,7 @@ fn timing_quality(binary: &BitMatrix, transform: &PerspectiveTransform, dimensio
 
     let mut v_bits = Vec::new();
     for m in 8..=(dimension.saturating_sub(9)) {
-        let p = transform.transform(&Point::new(6.5, m as f32 + 0.5));
+        let p = transform.to_image(ModulePoint::center_of(6, m));
         let ix = p.x.round() as isize;
         let iy = p.y.round() as isize;
         if ix < 0 || iy < 0 || ix as usize >= binary.width() || iy as usize >= binary.height() {
@@ -591,14 +1311,15 @@ fn best_refined_transform(
     top_left: &Point,
     top_right: &Point,
     bottom_left: &Point,
-    align_src: Point,
-    align_dst: Point,
+    align_src: ModulePoint,
+    align_dst: ImagePoint,
     module_size: f32,
 ) -> Option<PerspectiveTransform> {
+    let far = dimension as f32 - 3.5;
     let src = [
-        Point::new(3.5, 3.5),
-        Point::new(dimension as f32 - 3.5, 3.5),
-        Point::new(3.5, dimension as f32 - 3.5),
+        ModulePoint::new(3.5, 3.5),
+        ModulePoint::new(far, 3.5),
+        ModulePoint::new(3.5, far),
         align_src,
     ];
 
@@ -607,12 +1328,12 @@ fn best_refined_transform(
     for oy in [-1.0f32, 0.0, 1.0] {
         for ox in [-1.0f32, 0.0, 1.0] {
             let dst = [
-                *top_left,
-                *top_right,
-                *bottom_left,
-                Point::new(align_dst.x + ox * step, align_dst.y + oy * step),
+                (*top_left).into(),
+                (*top_right).into(),
+                (*bottom_left).into(),
+                ImagePoint::new(align_dst.x + ox * step, align_dst.y + oy * step),
             ];
-            let Some(t) = PerspectiveTransform::from_points(&src, &dst) else {
+            let Some(t) = transform_to_image(&src, &dst) else {
                 continue;
             };
             let s = transform_quality(binary, &t, dimension, version_num, module_size);
@@ -643,6 +1364,104 @@ mod tests {
         assert!(estimate_radial_k1(&transform, 21).is_none());
     }
 
+    #[test]
+    fn threshold_sweep_reads_shadowed_light_module() {
+        // A light module in shadow among light neighbours falls below their
+        // mean, but stays above 40% of the range to the dark module nearby.
+        let (dim, px) = (21usize, 6usize);
+        let side = dim * px;
+        let module_gray = |x: usize, y: usize| match (x, y) {
+            (10, 10) => 30u8,
+            (11, 10) => 150,
+            _ => 230,
+        };
+        let gray: Vec<u8> = (0..side * side)
+            .map(|i| module_gray(i % side / px, i / side / px))
+            .collect();
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(dim as f32, 0.0),
+            Point::new(0.0, dim as f32),
+            Point::new(dim as f32, dim as f32),
+        ];
+        let image_corners = corners.map(|p| Point::new(p.x * px as f32, p.y * px as f32));
+        let transform = PerspectiveTransform::from_points(&corners, &image_corners).unwrap();
+
+        let (mean_grid, _) =
+            extract_qr_region_gray_with_variant(&gray, side, side, &transform, dim, 0.0, 0.0, 1.0);
+        assert!(
+            mean_grid.get(11, 10),
+            "mean threshold reads the shadow dark"
+        );
+
+        let sweeps = threshold_sweep_grids(&gray, side, side, &transform, dim);
+        assert_eq!(sweeps.len(), THRESHOLD_SWEEP_FRACTIONS.len());
+        let (low, _) = &sweeps[0];
+        assert!(low.get(10, 10));
+        assert!(!low.get(11, 10));
+        assert!(!low.get(0, 0), "flat windows keep the mean threshold");
+    }
+
+    #[test]
+    fn alignment_anchored_transform_recovers_keystoned_corner() {
+        // Version 2 function patterns seen in keystone: the far corner sits
+        // well off the finders' parallelogram.
+        let dim = 25usize;
+        let module_dark = |x: usize, y: usize| {
+            let ring = |cx: usize, cy: usize| x.abs_diff(cx).max(y.abs_diff(cy));
+            for (cx, cy) in [(3, 3), (dim - 4, 3), (3, dim - 4)] {
+                if ring(cx, cy) <= 3 {
+                    return ring(cx, cy) != 2;
+                }
+            }
+            if ring(18, 18) <= 2 {
+                return ring(18, 18) != 1;
+            }
+            (x == 6 || y == 6) && (x + y) % 2 == 0
+        };
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(dim as f32, 0.0),
+            Point::new(0.0, dim as f32),
+            Point::new(dim as f32, dim as f32),
+        ];
+        let image_corners = [
+            Point::new(40.0, 40.0),
+            Point::new(290.0, 50.0),
+            Point::new(30.0, 300.0),
+            Point::new(325.0, 345.0),
+        ];
+        let truth = PerspectiveTransform::from_points(&corners, &image_corners).unwrap();
+        let to_module = PerspectiveTransform::from_points(&image_corners, &corners).unwrap();
+        let mut binary = BitMatrix::new(380, 380);
+        for py in 0..380 {
+            for px in 0..380 {
+                let m = to_module.transform(&Point::new(px as f32 + 0.5, py as f32 + 0.5));
+                let inside = (0.0..dim as f32).contains(&m.x) && (0.0..dim as f32).contains(&m.y);
+                binary.set(px, py, inside && module_dark(m.x as usize, m.y as usize));
+            }
+        }
+
+        let center = |x: f32, y: f32| truth.transform(&Point::new(x, y));
+        let (tl, tr, bl) = (
+            center(3.5, 3.5),
+            center(dim as f32 - 3.5, 3.5),
+            center(3.5, dim as f32 - 3.5),
+        );
+        let far = center(dim as f32 - 3.5, dim as f32 - 3.5);
+        let nominal = calculate_bottom_right(&tl, &tr, &bl).unwrap();
+        assert!(nominal.distance(&far) > 15.0);
+
+        let anchored =
+            alignment_anchored_transform(&binary, 2, dim, 10.0, &tl, &tr, &bl).expect("anchored");
+        let corner = anchored.transform(&Point::new(dim as f32 - 3.5, dim as f32 - 3.5));
+        assert!(corner.distance(&far) < 5.0, "{corner:?} vs {far:?}");
+        assert!(
+            alignment_anchored_transform(&binary, 1, 21, 10.0, &tl, &tr, &bl).is_none(),
+            "version 1 has no alignment pattern"
+        );
+    }
+
     #[test]
     fn confidence_extraction_returns_expected_shape() {
         let dim = 21usize;
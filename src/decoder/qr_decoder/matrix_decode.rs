@@ -1,7 +1,11 @@
+use crate::EncoderQuirk;
+use crate::config;
 use crate::decoder::format::FormatInfo;
 use crate::decoder::function_mask::FunctionMask;
-use crate::decoder::qr_decoder::{orientation, payload};
-use crate::models::{BitMatrix, ECLevel, MaskPattern, QRCode};
+use crate::decoder::qr_decoder::{SegmentMode, orientation, payload};
+use crate::decoder::tables::{self, EcBlockInfo};
+use crate::error::DecodeFailure;
+use crate::models::{BitMatrix, ECLevel, FormatQuirk, MaskPattern, Orientation, QRCode};
 
 fn fallback_ec_levels() -> &'static [ECLevel] {
     if crate::decoder::config::format_fallback_full_ec() {
@@ -15,15 +19,41 @@ fn strict_fallback_version_match() -> bool {
     crate::decoder::config::strict_fallback_version_match()
 }
 
+/// Data traversal orders as `(start_upward, swap_columns)`. The first is
+/// the placement the specification fixes (strips of two columns from the
+/// right, right-hand module first, the first strip upward) and the only one
+/// a strict read follows; the rest are tried under
+/// [`EncoderQuirk::NonstandardTraversal`].
+const TRAVERSALS: [(bool, bool); 4] = [(true, false), (true, true), (false, false), (false, true)];
+
+/// The specified placement alone.
+const SPEC_TRAVERSAL: &[(bool, bool)] = &[TRAVERSALS[0]];
+
+/// Traversals the brute-forced format guesses read the data in.
+fn fallback_traversals() -> &'static [(bool, bool)] {
+    if config::tolerates(EncoderQuirk::NonstandardTraversal) {
+        &TRAVERSALS
+    } else {
+        SPEC_TRAVERSAL
+    }
+}
+
+/// Bits of a segment header checked ahead of a brute-forced decode: a mode
+/// indicator and the widest character count field.
+const HEADER_BITS: usize = 20;
+
+/// Codewords of the first block that hold [`HEADER_BITS`].
+const HEADER_CODEWORDS: usize = HEADER_BITS.div_ceil(8);
+
 pub(super) fn decode_from_matrix(qr_matrix: &BitMatrix, version_num: u8) -> Option<QRCode> {
-    decode_from_matrix_internal(qr_matrix, version_num, None)
+    decode_from_matrix_internal(qr_matrix, version_num, None).ok()
 }
 
-pub(super) fn decode_from_matrix_with_confidence(
+pub(super) fn try_decode_from_matrix_with_confidence(
     qr_matrix: &BitMatrix,
     version_num: u8,
     module_confidence: &[u8],
-) -> Option<QRCode> {
+) -> Result<QRCode, DecodeFailure> {
     decode_from_matrix_internal(qr_matrix, version_num, Some(module_confidence))
 }
 
@@ -31,7 +61,14 @@ fn decode_from_matrix_internal(
     qr_matrix: &BitMatrix,
     version_num: u8,
     module_confidence: Option<&[u8]>,
-) -> Option<QRCode> {
+) -> Result<QRCode, DecodeFailure> {
+    let restored;
+    let qr_matrix = if super::inverted_finders().contains(&true) {
+        restored = orientation::restore_inverted_finders(qr_matrix, super::inverted_finders());
+        &restored
+    } else {
+        qr_matrix
+    };
     let mut orientations = orientation::candidate_orientations(qr_matrix);
     if orientations.is_empty() {
         // Quiet-zone reconstruction fallback: tolerate more finder mismatches.
@@ -39,75 +76,348 @@ fn decode_from_matrix_internal(
         orientations = orientation::candidate_orientations_relaxed(qr_matrix, mismatches);
     }
     if orientations.is_empty() {
-        return None;
+        return Err(DecodeFailure::Orientation);
     }
+    let mut failure = DecodeFailure::Orientation;
 
-    let traversal_opts = [(true, false), (true, true), (false, false), (false, true)];
+    let func = FunctionMask::new(version_num);
 
     // Fast path: if format BCH extraction succeeds, use only that format.
-    for oriented in &orientations {
+    let mut format_unread = vec![false; orientations.len()];
+    for (i, (turn, oriented)) in orientations.iter().enumerate() {
         if !orientation::version_matches_candidate(oriented, version_num) {
             continue;
         }
-        if let Some(format_info) = FormatInfo::extract(oriented) {
-            for &(start_upward, swap_columns) in &traversal_opts {
-                if let Some(qr) = payload::try_decode_single(
+        match FormatInfo::try_extract(oriented) {
+            Ok(format_info) => {
+                match decode_traversals(
                     oriented,
-                    version_num,
+                    &func,
                     &format_info,
-                    start_upward,
-                    swap_columns,
-                    true,
-                    false,
+                    SPEC_TRAVERSAL,
                     module_confidence,
                 ) {
-                    return Some(qr);
+                    Ok(qr) => return Ok(read_in(qr, *turn)),
+                    Err(e) => failure = failure.deeper(e),
                 }
             }
+            Err(e) => {
+                format_unread[i] = e == DecodeFailure::FormatInfo;
+                failure = failure.deeper(e);
+            }
+        }
+    }
+
+    // Opted-in encoder quirks, ahead of the brute force that would otherwise
+    // find (and misattribute) the format-area ones.
+    for (turn, oriented) in &orientations {
+        if !orientation::version_matches_candidate(oriented, version_num) {
+            continue;
+        }
+        if let Some((qr, quirk)) = decode_with_quirks(oriented, &func, module_confidence) {
+            super::record_quirk_rescue(quirk);
+            return Ok(read_in(qr, *turn));
+        }
+    }
+
+    // Format written as if the symbol were turned: a handful of BCH reads
+    // that, when one lands, name the format outright instead of guessing it.
+    if crate::decoder::config::rotated_format_fallback() {
+        for (i, (turn, oriented)) in orientations.iter().enumerate() {
+            if !format_unread[i] {
+                continue;
+            }
+            if let Some((qr, quarter_turns)) =
+                decode_with_rotated_format(oriented, &func, module_confidence)
+            {
+                super::record_rotated_format_rescue(quarter_turns);
+                return Ok(read_in(qr, *turn));
+            }
+        }
+    }
+
+    // Format masked with the bit-reversed constant. Such a word sits three
+    // bits from a valid one with both EC bits and the top mask bit flipped,
+    // so the strict read names the wrong format rather than failing, and
+    // the brute force could only guess the EC level.
+    for (turn, oriented) in &orientations {
+        if !orientation::version_matches_candidate(oriented, version_num) {
+            continue;
+        }
+        if let Some(mut qr) = FormatInfo::extract_wrong_mask(oriented).and_then(|info| {
+            decode_traversals(oriented, &func, &info, SPEC_TRAVERSAL, module_confidence).ok()
+        }) {
+            super::record_wrong_format_mask_rescue();
+            qr.format_quirk = Some(FormatQuirk::WrongFormatMask);
+            return Ok(read_in(qr, *turn));
         }
     }
 
     // Last-resort fallback: limited EC/mask subset (not full 32-combo brute force).
     let strict_version_match = strict_fallback_version_match();
-    for oriented in &orientations {
+    let prefilter = crate::decoder::config::fallback_header_prefilter();
+    let mut skipped = 0usize;
+    for (turn, oriented) in &orientations {
         if strict_version_match && !orientation::version_matches_candidate(oriented, version_num) {
             continue;
         }
+        // Each mask and traversal opens the stream differently; read those
+        // openings once, far enough for every EC level's block interleave.
+        let prefix_len = if prefilter {
+            fallback_ec_levels()
+                .iter()
+                .filter_map(|&ec| tables::ec_block_info(version_num, ec))
+                .map(|blocks| (HEADER_CODEWORDS - 1) * blocks.num_blocks + 1)
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let traversals = fallback_traversals();
+        let prefixes = MaskPattern::ALL.map(|mask| {
+            traversals
+                .iter()
+                .map(|&(upward, swap)| {
+                    stream_prefix(oriented, &func, mask, upward, swap, prefix_len)
+                })
+                .collect::<Vec<_>>()
+        });
         for &ec in fallback_ec_levels() {
-            for mask in 0..8u8 {
-                if let Some(mask_pattern) = MaskPattern::from_bits(mask) {
-                    let info = FormatInfo {
-                        ec_level: ec,
-                        mask_pattern,
-                    };
-                    for &(start_upward, swap_columns) in &traversal_opts {
-                        if let Some(qr) = payload::try_decode_single(
-                            oriented,
-                            version_num,
-                            &info,
-                            start_upward,
-                            swap_columns,
-                            true,
-                            false,
-                            module_confidence,
-                        ) {
-                            return Some(qr);
+            let blocks = tables::ec_block_info(version_num, ec);
+            for (mask_pattern, prefixes) in MaskPattern::ALL.into_iter().zip(&prefixes) {
+                let info = FormatInfo {
+                    ec_level: ec,
+                    mask_pattern,
+                };
+                for (&(upward, swap), prefix) in traversals.iter().zip(prefixes) {
+                    let header = blocks.and_then(|b| block_header(prefix, &b));
+                    if header.is_some_and(|h| !plausible_header(h, version_num, ec)) {
+                        skipped += 1;
+                        continue;
+                    }
+                    // Brute-forced format guesses say little about the real
+                    // cause, so they only count once decode got past RS.
+                    match payload::try_decode_single(
+                        oriented,
+                        &func,
+                        &info,
+                        upward,
+                        swap,
+                        module_confidence,
+                    ) {
+                        Ok(qr) => {
+                            super::record_header_prefilter_skips(skipped);
+                            if (upward, swap) != TRAVERSALS[0] {
+                                super::record_quirk_rescue(EncoderQuirk::NonstandardTraversal);
+                            }
+                            return Ok(read_in(qr, *turn));
                         }
+                        Err(DecodeFailure::Payload) => {
+                            failure = failure.deeper(DecodeFailure::Payload)
+                        }
+                        Err(_) => {}
                     }
                 }
             }
         }
     }
+    super::record_header_prefilter_skips(skipped);
 
     if let Some(conf) = module_confidence {
         if let Some(qr) = attempt_uncertain_module_beam_repair(qr_matrix, version_num, conf) {
-            return Some(qr);
+            return Ok(qr);
         }
     }
 
+    Err(failure)
+}
+
+/// Decode `oriented` under `format_info`, reading the data in each of
+/// `traversals` in turn.
+fn decode_traversals(
+    oriented: &BitMatrix,
+    func: &FunctionMask,
+    format_info: &FormatInfo,
+    traversals: &[(bool, bool)],
+    module_confidence: Option<&[u8]>,
+) -> Result<QRCode, DecodeFailure> {
+    let mut failure = DecodeFailure::Orientation;
+    for &(start_upward, swap_columns) in traversals {
+        match payload::try_decode_single(
+            oriented,
+            func,
+            format_info,
+            start_upward,
+            swap_columns,
+            module_confidence,
+        ) {
+            Ok(qr) => return Ok(qr),
+            Err(e) => failure = failure.deeper(e),
+        }
+    }
+    Err(failure)
+}
+
+/// The first `len` codewords of the stream `oriented` holds under `mask`,
+/// read in the given traversal order. Only the modules read are unmasked;
+/// the rest of the grid is never touched.
+pub(super) fn stream_prefix(
+    oriented: &BitMatrix,
+    func: &FunctionMask,
+    mask: MaskPattern,
+    start_upward: bool,
+    swap_columns: bool,
+    len: usize,
+) -> Vec<u8> {
+    let dim = oriented.width();
+    let mut codewords = Vec::with_capacity(len);
+    let (mut byte, mut bits) = (0u8, 0usize);
+    let mut upward = start_upward;
+    let mut col = dim as i32 - 1;
+    while col > 0 && codewords.len() < len {
+        if col == 6 {
+            col -= 1;
+            continue;
+        }
+        let pair = if swap_columns {
+            [col - 1, col]
+        } else {
+            [col, col - 1]
+        };
+        for i in 0..dim {
+            let row = if upward { dim - 1 - i } else { i };
+            for x in pair.map(|c| c as usize) {
+                if func.is_function(x, row) {
+                    continue;
+                }
+                byte = byte << 1 | (oriented.get(x, row) ^ mask.is_masked(row, x)) as u8;
+                bits += 1;
+                if bits == 8 {
+                    codewords.push(byte);
+                    (byte, bits) = (0, 0);
+                }
+            }
+        }
+        upward = !upward;
+        col -= 2;
+    }
+    codewords.truncate(len);
+    codewords
+}
+
+/// The first [`HEADER_BITS`] of the segment data in `blocks`' first block,
+/// whose codewords sit every `num_blocks` apart in the interleaved stream
+/// `prefix` opens. `None` when `prefix` is too short.
+pub(super) fn block_header(prefix: &[u8], blocks: &EcBlockInfo) -> Option<u32> {
+    let mut header = 0u32;
+    for k in 0..HEADER_CODEWORDS {
+        header = header << 8 | u32::from(*prefix.get(k * blocks.num_blocks)?);
+    }
+    Some(header >> (HEADER_CODEWORDS * 8 - HEADER_BITS))
+}
+
+/// Whether a stream opening with `header` could decode under `ec_level`,
+/// allowing for one wrong bit that Reed-Solomon would repair: a header the
+/// payload parser reads must be at most one flip away.
+pub(super) fn plausible_header(header: u32, version: u8, ec_level: ECLevel) -> bool {
+    header_fits(header, version, ec_level)
+        || (0..HEADER_BITS).any(|bit| header_fits(header ^ (1 << bit), version, ec_level))
+}
+
+/// Whether `header` opens a segment the symbol has room for: a data mode
+/// with a character count within capacity, a structured append header whose
+/// index is within its total, or an ECI designator of a valid length. An
+/// empty stream never yields a payload, so a leading terminator is as
+/// hopeless as an unknown mode.
+fn header_fits(header: u32, version: u8, ec_level: ECLevel) -> bool {
+    let field =
+        |offset: usize, bits: usize| (header >> (HEADER_BITS - offset - bits)) & ((1 << bits) - 1);
+    let mode = match field(0, 4) {
+        1 => SegmentMode::Numeric,
+        2 => SegmentMode::Alphanumeric,
+        4 => SegmentMode::Byte,
+        8 => SegmentMode::Kanji,
+        3 => return field(4, 4) <= field(8, 4),
+        7 => return field(4, 3) != 0b111,
+        _ => return false,
+    };
+    let count = field(4, tables::char_count_bits(mode, version));
+    tables::capacity(version, ec_level, mode).is_some_and(|max| count as usize <= max)
+}
+
+/// Decode `oriented` under the format read from it turned 90°, 180° and
+/// 270° clockwise, for generators that place the format modules as if the
+/// symbol were rotated while the data stays put. Returns the decode and the
+/// quarter turns whose format worked.
+fn decode_with_rotated_format(
+    oriented: &BitMatrix,
+    func: &FunctionMask,
+    module_confidence: Option<&[u8]>,
+) -> Option<(QRCode, usize)> {
+    let turns: [fn(&BitMatrix) -> BitMatrix; 3] = [
+        orientation::rotate90,
+        orientation::rotate180,
+        orientation::rotate270,
+    ];
+    turns.iter().enumerate().find_map(|(i, turn)| {
+        let info = FormatInfo::try_extract(&turn(oriented)).ok()?;
+        decode_traversals(oriented, func, &info, SPEC_TRAVERSAL, module_confidence)
+            .ok()
+            .map(|qr| (qr, i + 1))
+    })
+}
+
+/// Try the configured [`EncoderQuirk`] rules that apply to the symbol grid,
+/// returning the first decode and the rule behind it.
+fn decode_with_quirks(
+    oriented: &BitMatrix,
+    func: &FunctionMask,
+    module_confidence: Option<&[u8]>,
+) -> Option<(QRCode, EncoderQuirk)> {
+    let decode = |func: &FunctionMask, info: &FormatInfo| {
+        decode_traversals(oriented, func, info, SPEC_TRAVERSAL, module_confidence).ok()
+    };
+    if config::tolerates(EncoderQuirk::UnmaskedFormat)
+        && let Some(qr) = FormatInfo::try_extract_unmasked(oriented)
+            .ok()
+            .and_then(|info| decode(func, &info))
+    {
+        return Some((qr, EncoderQuirk::UnmaskedFormat));
+    }
+    if config::tolerates(EncoderQuirk::MaskedFormatArea) {
+        for mask in (0..8).filter_map(MaskPattern::from_bits) {
+            if let Some(qr) =
+                FormatInfo::extract_under_mask(oriented, mask).and_then(|info| decode(func, &info))
+            {
+                return Some((qr, EncoderQuirk::MaskedFormatArea));
+            }
+        }
+    }
+    if config::tolerates(EncoderQuirk::MissingDarkModule) {
+        let shifted = FunctionMask::without_dark_module(func.version());
+        if let Some(qr) = FormatInfo::try_extract(oriented)
+            .ok()
+            .and_then(|info| decode(&shifted, &info))
+        {
+            return Some((qr, EncoderQuirk::MissingDarkModule));
+        }
+    }
+    if config::tolerates(EncoderQuirk::NonstandardTraversal)
+        && let Some(qr) = FormatInfo::try_extract(oriented).ok().and_then(|info| {
+            decode_traversals(oriented, func, &info, &TRAVERSALS[1..], module_confidence).ok()
+        })
+    {
+        return Some((qr, EncoderQuirk::NonstandardTraversal));
+    }
     None
 }
 
+/// Record how the symbol sat in the grid, given the `turn` that made it upright.
+fn read_in(mut qr: QRCode, turn: Orientation) -> QRCode {
+    qr.orientation = turn.inverse();
+    qr
+}
+
 fn attempt_uncertain_module_beam_repair(
     qr_matrix: &BitMatrix,
     version_num: u8,
@@ -215,5 +525,5 @@ fn decode_with_flips(qr_matrix: &BitMatrix, version_num: u8, flips: &[usize]) ->
         let y = idx / dim;
         mutated.set(x, y, !mutated.get(x, y));
     }
-    decode_from_matrix_internal(&mutated, version_num, None)
+    decode_from_matrix_internal(&mutated, version_num, None).ok()
 }
@@ -1,6 +1,6 @@
 use crate::decoder::version::VersionInfo;
 use crate::detector::timing::read_timing_pattern;
-use crate::models::{BitMatrix, Point};
+use crate::models::{BitMatrix, Orientation, Point};
 
 #[allow(dead_code)]
 pub(super) fn score_content(content: &str) -> i32 {
@@ -104,6 +104,25 @@ pub(super) fn invert_matrix(matrix: &BitMatrix) -> BitMatrix {
     out
 }
 
+/// `matrix` with the 7x7 finder blocks at its top-left, top-right and
+/// bottom-left corners flipped where `inverted` says so, in that order.
+pub(super) fn restore_inverted_finders(matrix: &BitMatrix, inverted: [bool; 3]) -> BitMatrix {
+    let dim = matrix.width().min(matrix.height());
+    let mut out = matrix.clone();
+    if dim < 7 {
+        return out;
+    }
+    let origins = [(0, 0), (dim - 7, 0), (0, dim - 7)];
+    for ((x0, y0), _) in origins.into_iter().zip(inverted).filter(|(_, inv)| *inv) {
+        for y in y0..y0 + 7 {
+            for x in x0..x0 + 7 {
+                out.set(x, y, !matrix.get(x, y));
+            }
+        }
+    }
+    out
+}
+
 /// Check whether the matrix has finder patterns in the correct positions
 /// for a properly oriented QR code: top-left (0,0), top-right (dim-7,0),
 /// bottom-left (0,dim-7). Checks a small set of diagnostic cells at each
@@ -144,96 +163,75 @@ pub(super) fn has_finders_correct(matrix: &BitMatrix) -> bool {
     mismatches <= 3
 }
 
-pub(super) fn candidate_orientations(matrix: &BitMatrix) -> Vec<BitMatrix> {
-    let strict_tolerance = 3usize;
-    let relaxed_tolerance = 7usize;
-    let mut candidates = Vec::new();
-
-    let r0 = matrix.clone();
-    if has_finders_with_tolerance(&r0, strict_tolerance) {
-        candidates.push(r0);
-    }
-
-    let r90 = rotate90(matrix);
-    if has_finders_with_tolerance(&r90, strict_tolerance) {
-        candidates.push(r90);
-    }
-
-    let r180 = rotate180(matrix);
-    if has_finders_with_tolerance(&r180, strict_tolerance) {
-        candidates.push(r180);
-    }
-
-    let r270 = rotate270(matrix);
-    if has_finders_with_tolerance(&r270, strict_tolerance) {
-        candidates.push(r270);
+/// `matrix` turned by `orientation`: mirrored left-right if it says so, then
+/// rotated clockwise.
+pub(super) fn reorient(matrix: &BitMatrix, orientation: Orientation) -> BitMatrix {
+    match orientation {
+        Orientation::Rot0 => matrix.clone(),
+        Orientation::Rot90 => rotate90(matrix),
+        Orientation::Rot180 => rotate180(matrix),
+        Orientation::Rot270 => rotate270(matrix),
+        Orientation::Rot0Mirrored => flip_horizontal(matrix),
+        Orientation::Rot90Mirrored => rotate90(&flip_horizontal(matrix)),
+        Orientation::Rot180Mirrored => flip_vertical(matrix),
+        Orientation::Rot270Mirrored => rotate90(&flip_vertical(matrix)),
     }
+}
 
+const ROTATIONS: [Orientation; 4] = [
+    Orientation::Rot0,
+    Orientation::Rot90,
+    Orientation::Rot180,
+    Orientation::Rot270,
+];
+
+/// Turnings of `matrix` that put finder patterns in the three upright
+/// corners, each with the turn applied. Mirrored turnings are only tried
+/// when no plain rotation fits.
+pub(super) fn candidate_orientations(matrix: &BitMatrix) -> Vec<(Orientation, BitMatrix)> {
+    let strict_tolerance = 3usize;
+    let relaxed_tolerance = 7usize;
+    let candidates = fitting_orientations(matrix, &ROTATIONS, strict_tolerance);
     if !candidates.is_empty() {
         return candidates;
     }
-
-    let fh = flip_horizontal(matrix);
-    if has_finders_with_tolerance(&fh, relaxed_tolerance) {
-        candidates.push(fh);
-    }
-
-    let fv = flip_vertical(matrix);
-    if has_finders_with_tolerance(&fv, relaxed_tolerance) {
-        candidates.push(fv);
-    }
-
-    let fhr90 = rotate90(&flip_horizontal(matrix));
-    if has_finders_with_tolerance(&fhr90, relaxed_tolerance) {
-        candidates.push(fhr90);
-    }
-
-    let fvr90 = rotate90(&flip_vertical(matrix));
-    if has_finders_with_tolerance(&fvr90, relaxed_tolerance) {
-        candidates.push(fvr90);
-    }
-
-    candidates
+    fitting_orientations(
+        matrix,
+        &[
+            Orientation::Rot0Mirrored,
+            Orientation::Rot180Mirrored,
+            Orientation::Rot90Mirrored,
+            Orientation::Rot270Mirrored,
+        ],
+        relaxed_tolerance,
+    )
 }
 
 pub(super) fn candidate_orientations_relaxed(
     matrix: &BitMatrix,
     max_mismatches: usize,
-) -> Vec<BitMatrix> {
-    let mut candidates = Vec::new();
-    let r0 = matrix.clone();
-    if has_finders_with_tolerance(&r0, max_mismatches) {
-        candidates.push(r0);
-    }
-
-    let r90 = rotate90(matrix);
-    if has_finders_with_tolerance(&r90, max_mismatches) {
-        candidates.push(r90);
-    }
-
-    let r180 = rotate180(matrix);
-    if has_finders_with_tolerance(&r180, max_mismatches) {
-        candidates.push(r180);
-    }
-
-    let r270 = rotate270(matrix);
-    if has_finders_with_tolerance(&r270, max_mismatches) {
-        candidates.push(r270);
-    }
-
-    let fh = flip_horizontal(matrix);
-    if has_finders_with_tolerance(&fh, max_mismatches) {
-        candidates.push(fh);
-    }
-
-    let fv = flip_vertical(matrix);
-    if has_finders_with_tolerance(&fv, max_mismatches) {
-        candidates.push(fv);
-    }
-
+) -> Vec<(Orientation, BitMatrix)> {
+    let mut candidates = fitting_orientations(matrix, &ROTATIONS, max_mismatches);
+    candidates.extend(fitting_orientations(
+        matrix,
+        &[Orientation::Rot0Mirrored, Orientation::Rot180Mirrored],
+        max_mismatches,
+    ));
     candidates
 }
 
+fn fitting_orientations(
+    matrix: &BitMatrix,
+    orientations: &[Orientation],
+    max_mismatches: usize,
+) -> Vec<(Orientation, BitMatrix)> {
+    orientations
+        .iter()
+        .map(|&o| (o, reorient(matrix, o)))
+        .filter(|(_, m)| has_finders_with_tolerance(m, max_mismatches))
+        .collect()
+}
+
 pub(super) fn has_finders_with_tolerance(matrix: &BitMatrix, max_mismatches: usize) -> bool {
     let dim = matrix.width();
     if dim < 21 || matrix.height() < 21 {
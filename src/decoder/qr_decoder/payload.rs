@@ -1,101 +1,70 @@
+use super::session::{Segment, SegmentMode};
+use super::{PAYLOAD_PRIOR, PriorConstraint};
 use crate::decoder::bitstream::BitstreamExtractor;
 use crate::decoder::format::FormatInfo;
 use crate::decoder::function_mask::FunctionMask;
 use crate::decoder::modes::{alphanumeric::AlphanumericDecoder, numeric::NumericDecoder};
 use crate::decoder::reed_solomon::ReedSolomonDecoder;
-use crate::decoder::tables::ec_block_info;
+use crate::decoder::tables::{char_count_bits, ec_block_info};
 use crate::decoder::unmask::unmask;
 use crate::decoder::version::VersionInfo;
-use crate::models::{BitMatrix, ECLevel, QRCode, Version};
-use std::cell::RefCell;
-
-#[derive(Clone, Copy, Default)]
-struct ErasureCounters {
-    attempts: usize,
-    successes: usize,
-    hist_1: usize,
-    hist_2_3: usize,
-    hist_4_6: usize,
-    hist_7_plus: usize,
-}
-
-thread_local! {
-    static ERASURE_COUNTERS: RefCell<ErasureCounters> = const { RefCell::new(ErasureCounters {
-        attempts: 0,
-        successes: 0,
-        hist_1: 0,
-        hist_2_3: 0,
-        hist_4_6: 0,
-        hist_7_plus: 0,
-    }) };
-}
+use crate::error::DecodeFailure;
+use crate::models::{
+    BitMatrix, ECLevel, IntermediateData, QRCode, StructuredAppend, TextEncoding, Version,
+};
 
-/// Global counter for RS erasure attempts (across all blocks in an image)
-static RS_ERASURE_GLOBAL_ATTEMPTS: std::sync::atomic::AtomicUsize =
-    std::sync::atomic::AtomicUsize::new(0);
-
-pub(crate) fn reset_rs_erasure_global_counter() {
-    RS_ERASURE_GLOBAL_ATTEMPTS.store(0, std::sync::atomic::Ordering::Relaxed);
-}
-
-pub(crate) fn get_rs_erasure_global_counter() -> usize {
-    RS_ERASURE_GLOBAL_ATTEMPTS.load(std::sync::atomic::Ordering::Relaxed)
-}
-
-pub(crate) fn increment_rs_erasure_global_counter() -> usize {
-    RS_ERASURE_GLOBAL_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-}
-
-pub(super) fn reset_erasure_counters() {
-    ERASURE_COUNTERS.with(|c| *c.borrow_mut() = ErasureCounters::default());
-}
-
-pub(super) fn take_erasure_counters() -> (usize, usize, [usize; 4]) {
-    ERASURE_COUNTERS.with(|c| {
-        let ec = *c.borrow();
-        *c.borrow_mut() = ErasureCounters::default();
-        (
-            ec.attempts,
-            ec.successes,
-            [ec.hist_1, ec.hist_2_3, ec.hist_4_6, ec.hist_7_plus],
-        )
-    })
+fn record_erasure_hist(count: usize) {
+    let bucket = match count {
+        0 => return,
+        1 => 0,
+        2..=3 => 1,
+        4..=6 => 2,
+        _ => 3,
+    };
+    super::DECODE_COUNTERS.with(|c| c.borrow_mut().rs_erasure_count_hist[bucket] += 1);
 }
 
-fn record_erasure_hist(count: usize) {
-    ERASURE_COUNTERS.with(|c| {
-        let mut ec = c.borrow_mut();
-        match count {
-            0 => {}
-            1 => ec.hist_1 += 1,
-            2..=3 => ec.hist_2_3 += 1,
-            4..=6 => ec.hist_4_6 += 1,
-            _ => ec.hist_7_plus += 1,
-        }
+/// Count a block's outcome in high-density mode: the codewords correction
+/// changed, by quarter of the `ecc_per_block / 2` errors the block can take,
+/// or a failure.
+fn record_block_load(received: &[u8], corrected: Option<&[u8]>, ecc_per_block: usize) {
+    super::DECODE_COUNTERS.with(|c| {
+        let mut c = c.borrow_mut();
+        let Some(corrected) = corrected else {
+            c.rs_blocks_failed += 1;
+            return;
+        };
+        let changed = received
+            .iter()
+            .zip(corrected)
+            .filter(|(a, b)| a != b)
+            .count();
+        let capacity = (ecc_per_block / 2).max(1);
+        let bucket = (changed.saturating_sub(1) * 4 / capacity).min(3);
+        c.rs_block_load_hist[bucket] += 1;
     });
 }
 
 #[allow(clippy::too_many_arguments)]
 pub(super) fn try_decode_single(
     oriented: &BitMatrix,
-    version_num: u8,
+    func: &FunctionMask,
     format_info: &FormatInfo,
     start_upward: bool,
     swap_columns: bool,
-    use_msb: bool,
-    reverse_stream: bool,
     module_confidence: Option<&[u8]>,
-) -> Option<QRCode> {
+) -> Result<QRCode, DecodeFailure> {
+    super::DECODE_COUNTERS.with(|c| c.borrow_mut().stream_decode_attempts += 1);
     let dimension = oriented.width();
-    let func = FunctionMask::new(version_num);
+    let version_num = func.version();
     let mut unmasked = oriented.clone();
-    unmask(&mut unmasked, &format_info.mask_pattern, &func);
+    unmask(&mut unmasked, &format_info.mask_pattern, func);
 
     let (bits, bit_confidence) = if let Some(conf) = module_confidence {
         BitstreamExtractor::extract_with_confidence(
             &unmasked,
             dimension,
-            &func,
+            func,
             start_upward,
             swap_columns,
             conf,
@@ -105,30 +74,19 @@ pub(super) fn try_decode_single(
             BitstreamExtractor::extract_with_options(
                 &unmasked,
                 dimension,
-                &func,
+                func,
                 start_upward,
                 swap_columns,
             ),
             Vec::new(),
         )
     };
-    let (bits, bit_confidence) = if reverse_stream {
-        let mut rev_bits = bits;
-        rev_bits.reverse();
-        let mut rev_conf = bit_confidence;
-        rev_conf.reverse();
-        (rev_bits, rev_conf)
-    } else {
-        (bits, bit_confidence)
-    };
-
-    let (codewords, codeword_confidence) = if use_msb {
-        bits_to_codewords_with_confidence(&bits, &bit_confidence, true)
-    } else {
-        bits_to_codewords_with_confidence(&bits, &bit_confidence, false)
-    };
+    let (codewords, codeword_confidence) =
+        bits_to_codewords_with_confidence(&bits, &bit_confidence);
 
-    let data_codewords = deinterleave_and_correct_with_confidence(
+    let keep_intermediate = crate::config::with_active(|c| c.keep_intermediate_data);
+    let mut corrected = Vec::new();
+    let (data_codewords, assisted) = deinterleave_and_correct_with_confidence(
         &codewords,
         version_num,
         format_info.ec_level,
@@ -137,11 +95,24 @@ pub(super) fn try_decode_single(
         } else {
             Some(&codeword_confidence)
         },
+        keep_intermediate.then_some(&mut corrected),
     )?;
 
-    let (data, content) = decode_payload(&data_codewords, version_num)?;
+    let segments = payload_segments(&data_codewords, version_num).ok_or(DecodeFailure::Payload)?;
+    let (data, content, text_encoding) = join_segments(&segments);
     if data.is_empty() {
-        return None;
+        return Err(DecodeFailure::Payload);
+    }
+    // Filled-in codewords only fix the prefix; a payload that does not carry
+    // it means the prior did not apply to this symbol.
+    if assisted
+        && !PAYLOAD_PRIOR.with(|p| {
+            p.borrow()
+                .as_ref()
+                .is_some_and(|p| data.starts_with(&p.prefix))
+        })
+    {
+        return Err(DecodeFailure::Payload);
     }
 
     let version = if dimension >= 45 {
@@ -152,13 +123,28 @@ pub(super) fn try_decode_single(
         Version::Model2(version_num)
     };
 
-    Some(QRCode::new(
+    let mut qr = QRCode::new(
         data,
         content,
         version,
         format_info.ec_level,
         format_info.mask_pattern,
-    ))
+    );
+    qr.text_encoding = text_encoding;
+    qr.assisted = assisted;
+    qr.fixed_pattern_damage = super::structure::fixed_pattern_damage(oriented);
+    qr.structured_append = segments.iter().find_map(|s| match s.mode {
+        SegmentMode::StructuredAppend(header) => Some(header),
+        _ => None,
+    });
+    if keep_intermediate {
+        qr.intermediate = Some(Box::new(IntermediateData {
+            raw_codewords: codewords,
+            corrected_codewords: corrected,
+            unmasked_modules: unmasked,
+        }));
+    }
+    Ok(qr)
 }
 
 #[allow(dead_code)]
@@ -199,30 +185,73 @@ pub(super) fn deinterleave_and_correct(
     version: u8,
     ec_level: ECLevel,
 ) -> Option<Vec<u8>> {
-    deinterleave_and_correct_with_confidence(codewords, version, ec_level, None)
+    deinterleave_and_correct_with_confidence(codewords, version, ec_level, None, None)
+        .ok()
+        .map(|(data, _)| data)
 }
 
-pub(super) fn deinterleave_and_correct_with_confidence(
+/// Codewords regrouped into their Reed-Solomon blocks, each holding its data
+/// codewords followed by its EC codewords.
+pub(super) struct RsBlocks {
+    pub blocks: Vec<Vec<u8>>,
+    pub confidence: Vec<Vec<u8>>,
+    pub data_lens: Vec<usize>,
+    pub ecc_per_block: usize,
+}
+
+impl RsBlocks {
+    /// Undo [`deinterleave_blocks`], restoring symbol order.
+    pub fn interleave(&self) -> Vec<u8> {
+        let long_len = self.data_lens.iter().copied().max().unwrap_or(0);
+        let mut out = Vec::with_capacity(self.blocks.iter().map(Vec::len).sum());
+        for i in 0..long_len {
+            for (block, &data_len) in self.blocks.iter().zip(&self.data_lens) {
+                if i < data_len {
+                    out.push(block[i]);
+                }
+            }
+        }
+        for j in 0..self.ecc_per_block {
+            for (block, &data_len) in self.blocks.iter().zip(&self.data_lens) {
+                out.push(block[data_len + j]);
+            }
+        }
+        out
+    }
+}
+
+/// Split interleaved codewords into RS blocks per the version/EC layout.
+pub(super) fn deinterleave_blocks(
     codewords: &[u8],
     version: u8,
     ec_level: ECLevel,
     codeword_confidence: Option<&[u8]>,
-) -> Option<Vec<u8>> {
-    let info = ec_block_info(version, ec_level)?;
+) -> Result<RsBlocks, DecodeFailure> {
+    let info =
+        ec_block_info(version, ec_level).ok_or(DecodeFailure::UnsupportedVersion(version))?;
     let total = codewords.len();
     let ecc_total = info.num_blocks * info.ecc_per_block;
     if total < ecc_total {
-        return None;
+        return Err(DecodeFailure::Codewords);
     }
     let data_total = total - ecc_total;
     if data_total == 0 {
-        return None;
+        return Err(DecodeFailure::Codewords);
     }
 
     let num_long_blocks = data_total % info.num_blocks;
     let num_short_blocks = info.num_blocks - num_long_blocks;
     let short_len = data_total / info.num_blocks;
     let long_len = short_len + 1;
+    let data_lens: Vec<usize> = (0..info.num_blocks)
+        .map(|b| {
+            if b < num_short_blocks {
+                short_len
+            } else {
+                long_len
+            }
+        })
+        .collect();
 
     let mut blocks: Vec<Vec<u8>> = (0..info.num_blocks)
         .map(|_| Vec::with_capacity(long_len + info.ecc_per_block))
@@ -233,15 +262,10 @@ pub(super) fn deinterleave_and_correct_with_confidence(
 
     let mut idx = 0;
     for i in 0..long_len {
-        for (b, block) in blocks.iter_mut().enumerate().take(info.num_blocks) {
-            let block_len = if b < num_short_blocks {
-                short_len
-            } else {
-                long_len
-            };
-            if i < block_len {
+        for (b, block) in blocks.iter_mut().enumerate() {
+            if i < data_lens[b] {
                 if idx >= total {
-                    return None;
+                    return Err(DecodeFailure::Codewords);
                 }
                 block.push(codewords[idx]);
                 if let Some(conf) = codeword_confidence {
@@ -253,9 +277,9 @@ pub(super) fn deinterleave_and_correct_with_confidence(
     }
 
     for _ in 0..info.ecc_per_block {
-        for (b, block) in blocks.iter_mut().enumerate().take(info.num_blocks) {
+        for (b, block) in blocks.iter_mut().enumerate() {
             if idx >= total {
-                return None;
+                return Err(DecodeFailure::Codewords);
             }
             block.push(codewords[idx]);
             if let Some(conf) = codeword_confidence {
@@ -265,41 +289,134 @@ pub(super) fn deinterleave_and_correct_with_confidence(
         }
     }
 
-    let rs = ReedSolomonDecoder::new(info.ecc_per_block);
-    let mut data_out = Vec::with_capacity(data_total);
-    for (b, block) in blocks.iter_mut().enumerate() {
-        let mut corrected = rs.decode(block).is_ok();
-        if !corrected {
-            if let Some(conf) = codeword_confidence {
-                let erasures = low_confidence_positions(
-                    &block_conf[b],
-                    erasure_threshold(),
-                    max_erasures_per_block(info.ecc_per_block),
-                );
-                if !erasures.is_empty() {
-                    corrected = try_erasure_with_cap(&rs, block, &erasures);
-                }
-                let _ = conf;
+    Ok(RsBlocks {
+        blocks,
+        confidence: block_conf,
+        data_lens,
+        ecc_per_block: info.ecc_per_block,
+    })
+}
+
+/// Correct each RS block and return the data codewords, plus whether any block
+/// needed the active payload prior to correct. When `corrected_out` is given
+/// it receives every codeword (data and EC) after correction, in the same
+/// interleaved order as `codewords`.
+pub(super) fn deinterleave_and_correct_with_confidence(
+    codewords: &[u8],
+    version: u8,
+    ec_level: ECLevel,
+    codeword_confidence: Option<&[u8]>,
+    corrected_out: Option<&mut Vec<u8>>,
+) -> Result<(Vec<u8>, bool), DecodeFailure> {
+    let mut layout = deinterleave_blocks(codewords, version, ec_level, codeword_confidence)?;
+    let prior_codewords = PAYLOAD_PRIOR.with(|p| {
+        p.borrow()
+            .as_ref()
+            .map(|prior| prior_data_codewords(prior, version))
+            .unwrap_or_default()
+    });
+
+    let rs = ReedSolomonDecoder::new(layout.ecc_per_block);
+    let block_stats = super::high_density();
+    let mut data_out = Vec::with_capacity(layout.data_lens.iter().sum());
+    let mut assisted = false;
+    let mut block_start = 0;
+    for (b, block) in layout.blocks.iter_mut().enumerate() {
+        let data_len = layout.data_lens[b];
+        let pinned: Vec<(usize, u8)> = prior_codewords
+            .iter()
+            .filter(|&&(i, _)| (block_start..block_start + data_len).contains(&i))
+            .map(|&(i, value)| (i - block_start, value))
+            .collect();
+        block_start += data_len;
+        let received = (!pinned.is_empty()).then(|| block.clone());
+        let as_read = block_stats.then(|| block.clone());
+
+        let mut outcome = rs.try_decode(block);
+        if outcome.is_err() && codeword_confidence.is_some() {
+            let erasures = low_confidence_positions(
+                &layout.confidence[b],
+                erasure_threshold(),
+                max_erasures_per_block(layout.ecc_per_block),
+            );
+            if !erasures.is_empty() && try_erasure_with_cap(&rs, block, &erasures) {
+                outcome = Ok(());
             }
         }
-        if !corrected {
-            return None;
+        if outcome.is_err()
+            && let Some(mut filled) = received
+        {
+            for &(i, value) in &pinned {
+                filled[i] = value;
+            }
+            if rs.try_decode(&mut filled).is_ok() {
+                block.copy_from_slice(&filled);
+                assisted = true;
+                outcome = Ok(());
+            }
         }
-        let data_len = if b < num_short_blocks {
-            short_len
-        } else {
-            long_len
-        };
+        if let Some(as_read) = &as_read {
+            let corrected = outcome.is_ok().then_some(block.as_slice());
+            record_block_load(as_read, corrected, layout.ecc_per_block);
+        }
+        outcome?;
         data_out.extend_from_slice(&block[..data_len]);
     }
 
-    Some(data_out)
+    if let Some(out) = corrected_out {
+        *out = layout.interleave();
+    }
+
+    Ok((data_out, assisted))
 }
 
-fn bits_to_codewords_with_confidence(
+/// Data codewords a payload prior pins down for `version`, as `(index in the
+/// data stream, value)`. Only codewords whose eight bits all follow from the
+/// mode indicator and whole character groups of the shared prefix count; the
+/// character count field stays unknown because batch payloads may differ in
+/// length.
+fn prior_data_codewords(prior: &PriorConstraint, version: u8) -> Vec<(usize, u8)> {
+    let (indicator, group_len, group_bits) = match prior.mode {
+        SegmentMode::Numeric => (0b0001, 3, 10),
+        SegmentMode::Alphanumeric => (0b0010, 2, 11),
+        _ => return Vec::new(),
+    };
+    let mut bits: Vec<Option<bool>> = Vec::new();
+    let push = |bits: &mut Vec<Option<bool>>, value: u32, count: usize| {
+        bits.extend((0..count).rev().map(|i| Some((value >> i) & 1 == 1)));
+    };
+    push(&mut bits, indicator, 4);
+    bits.extend(std::iter::repeat_n(
+        None,
+        char_count_bits(prior.mode, version),
+    ));
+    for group in prior.prefix.chunks_exact(group_len) {
+        let value = match prior.mode {
+            SegmentMode::Numeric => group.iter().try_fold(0, |acc, &c| {
+                (c as char).to_digit(10).map(|digit| acc * 10 + digit)
+            }),
+            _ => AlphanumericDecoder::char_value(group[0])
+                .zip(AlphanumericDecoder::char_value(group[1]))
+                .map(|(hi, lo)| hi * 45 + lo),
+        };
+        let Some(value) = value else {
+            break;
+        };
+        push(&mut bits, value, group_bits);
+    }
+    bits.chunks_exact(8)
+        .enumerate()
+        .filter_map(|(i, byte)| {
+            byte.iter()
+                .try_fold(0u8, |acc, bit| bit.map(|bit| (acc << 1) | bit as u8))
+                .map(|value| (i, value))
+        })
+        .collect()
+}
+
+pub(super) fn bits_to_codewords_with_confidence(
     bits: &[bool],
     bit_confidence: &[u8],
-    msb: bool,
 ) -> (Vec<u8>, Vec<u8>) {
     let mut codewords = Vec::with_capacity(bits.len() / 8);
     let mut conf = Vec::with_capacity(bits.len() / 8);
@@ -307,12 +424,8 @@ fn bits_to_codewords_with_confidence(
     while idx + 8 <= bits.len() {
         let mut byte = 0u8;
         let mut min_c = u8::MAX;
-        for bit in 0..8 {
-            if msb {
-                byte = (byte << 1) | (bits[idx] as u8);
-            } else if bits[idx] {
-                byte |= 1 << bit;
-            }
+        for _ in 0..8 {
+            byte = (byte << 1) | (bits[idx] as u8);
             if !bit_confidence.is_empty() {
                 min_c = min_c.min(bit_confidence[idx]);
             }
@@ -353,17 +466,14 @@ fn low_confidence_positions(confidence: &[u8], threshold: u8, max_count: usize)
         .collect()
 }
 
-/// Check if RS erasure should be attempted based on global cap
+/// Check if RS erasure should be attempted: the cap bounds attempts across all
+/// blocks of the current decode-counter scope (one image under telemetry).
 fn should_attempt_erasure() -> bool {
-    let global_cap = crate::decoder::config::rs_erasure_global_cap();
-    if global_cap == 0 {
-        return true; // No cap
-    }
-    let current = get_rs_erasure_global_counter();
-    if current >= global_cap {
-        return false; // Cap reached
+    if crate::config::ablated(crate::Ablation::RsErasure) {
+        return false;
     }
-    true
+    let global_cap = crate::decoder::config::rs_erasure_global_cap();
+    global_cap == 0 || super::DECODE_COUNTERS.with(|c| c.borrow().rs_erasure_attempts) < global_cap
 }
 
 /// Attempt RS erasure with global cap tracking
@@ -371,76 +481,121 @@ fn try_erasure_with_cap(rs: &ReedSolomonDecoder, block: &mut [u8], erasures: &[u
     if !should_attempt_erasure() {
         return false;
     }
-    let current = increment_rs_erasure_global_counter();
-    if current > crate::decoder::config::rs_erasure_global_cap() {
-        return false;
-    }
-    ERASURE_COUNTERS.with(|c| c.borrow_mut().attempts += 1);
+    super::DECODE_COUNTERS.with(|c| c.borrow_mut().rs_erasure_attempts += 1);
     record_erasure_hist(erasures.len());
     if rs.decode_with_erasures(block, erasures).is_ok() {
-        ERASURE_COUNTERS.with(|c| c.borrow_mut().successes += 1);
+        super::DECODE_COUNTERS.with(|c| c.borrow_mut().rs_erasure_successes += 1);
         return true;
     }
     false
 }
 
-pub(super) fn decode_payload(data_codewords: &[u8], version: u8) -> Option<(Vec<u8>, String)> {
-    let mut bits = Vec::with_capacity(data_codewords.len() * 8);
-    for &byte in data_codewords {
-        for i in (0..8).rev() {
-            bits.push(((byte >> i) & 1) != 0);
-        }
-    }
+#[cfg(test)]
+pub(super) fn decode_payload(
+    data_codewords: &[u8],
+    version: u8,
+) -> Option<(Vec<u8>, String, TextEncoding)> {
+    let segments = payload_segments(data_codewords, version)?;
+    Some(join_segments(&segments))
+}
 
-    decode_payload_from_bits(&bits, version)
+/// Data bits of `data_codewords` split into mode segments.
+fn payload_segments(data_codewords: &[u8], version: u8) -> Option<Vec<Segment>> {
+    let bits: Vec<bool> = data_codewords
+        .iter()
+        .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 != 0))
+        .collect();
+    parse_segments_from_bits(&bits, version)
 }
 
-pub(super) fn decode_payload_from_bits(bits: &[bool], version: u8) -> Option<(Vec<u8>, String)> {
-    let mut reader = BitReader::new(bits);
+/// Payload bytes, text and character set of parsed segments.
+fn join_segments(segments: &[Segment]) -> (Vec<u8>, String, TextEncoding) {
     let mut data = Vec::new();
     let mut content = String::new();
+    let mut text_encoding = TextEncoding::Utf8;
+    for segment in segments {
+        data.extend_from_slice(&segment.data);
+        content.push_str(&segment.text);
+        if segment.encoding == TextEncoding::Latin1 {
+            text_encoding = TextEncoding::Latin1;
+        }
+    }
+    (data, content, text_encoding)
+}
+
+/// Split the data bit stream into its mode segments, stopping at the
+/// terminator or when fewer than four bits remain.
+pub(super) fn parse_segments_from_bits(bits: &[bool], version: u8) -> Option<Vec<Segment>> {
+    let mut reader = BitReader::new(bits);
+    let mut segments = Vec::new();
+    let byte_override = crate::config::with_active(|c| c.byte_encoding);
+    // Set by an ECI designator; applies to every following byte segment.
+    let mut eci_encoding: Option<TextEncoding> = None;
 
     loop {
         if reader.remaining() < 4 {
             break;
         }
+        let bit_offset = reader.index();
         let mode = reader.read_bits(4)? as u8;
         if mode == 0 {
             break;
         }
 
-        match mode {
+        let segment = match mode {
             1 => {
-                let count_bits = char_count_bits(mode, version);
+                let count_bits = char_count_bits(SegmentMode::Numeric, version);
                 let count = reader.read_bits(count_bits)? as usize;
                 let start = reader.index();
                 let (decoded, used) = NumericDecoder::decode(&bits[start..], count)?;
                 reader.advance(used);
-                data.extend_from_slice(decoded.as_bytes());
-                content.push_str(&decoded);
+                Segment::text(SegmentMode::Numeric, bit_offset, count, decoded)
             }
             2 => {
-                let count_bits = char_count_bits(mode, version);
+                let count_bits = char_count_bits(SegmentMode::Alphanumeric, version);
                 let count = reader.read_bits(count_bits)? as usize;
                 let start = reader.index();
                 let (decoded, used) = AlphanumericDecoder::decode(&bits[start..], count)?;
                 reader.advance(used);
-                data.extend_from_slice(decoded.as_bytes());
-                content.push_str(&decoded);
+                Segment::text(SegmentMode::Alphanumeric, bit_offset, count, decoded)
             }
             4 => {
-                let count_bits = char_count_bits(mode, version);
+                let count_bits = char_count_bits(SegmentMode::Byte, version);
                 let count = reader.read_bits(count_bits)? as usize;
                 let mut bytes = Vec::with_capacity(count);
                 for _ in 0..count {
                     let byte = reader.read_bits(8)? as u8;
                     bytes.push(byte);
                 }
-                data.extend_from_slice(&bytes);
-                content.push_str(&String::from_utf8_lossy(&bytes));
+                let encoding = eci_encoding
+                    .or(byte_override)
+                    .unwrap_or_else(|| TextEncoding::sniff(&bytes));
+                Segment {
+                    mode: SegmentMode::Byte,
+                    bit_offset,
+                    char_count: count,
+                    text: encoding.decode(&bytes),
+                    data: bytes,
+                    encoding,
+                }
+            }
+            3 => {
+                let index = reader.read_bits(4)? as u8;
+                let total = reader.read_bits(4)? as u8 + 1;
+                let parity = reader.read_bits(8)? as u8;
+                let header = StructuredAppend {
+                    index,
+                    total,
+                    parity,
+                };
+                Segment::text(
+                    SegmentMode::StructuredAppend(header),
+                    bit_offset,
+                    0,
+                    String::new(),
+                )
             }
             7 => {
-                // ECI: parse and ignore for now (assume UTF-8)
                 let mut eci = reader.read_bits(8)?;
                 if (eci & 0x80) != 0 {
                     eci = ((eci & 0x7F) << 8) | reader.read_bits(8)?;
@@ -448,12 +603,19 @@ pub(super) fn decode_payload_from_bits(bits: &[bool], version: u8) -> Option<(Ve
                         eci = ((eci & 0x3FFF) << 8) | reader.read_bits(8)?;
                     }
                 }
-                let _ = eci;
+                // Only the ISO-8859-1 (1, 3) and UTF-8 (26) designators are
+                // understood; other character sets fall back to sniffing.
+                eci_encoding = match eci {
+                    1 | 3 => Some(TextEncoding::Latin1),
+                    26 => Some(TextEncoding::Utf8),
+                    _ => None,
+                };
+                Segment::text(SegmentMode::Eci(eci), bit_offset, 0, String::new())
             }
             8 => {
                 // Kanji mode: decode Shift-JIS code units from 13-bit values.
                 // We preserve bytes in `data` and append a lossy textual representation.
-                let count_bits = char_count_bits(mode, version);
+                let count_bits = char_count_bits(SegmentMode::Kanji, version);
                 let count = reader.read_bits(count_bits)? as usize;
                 let mut sjis_bytes = Vec::with_capacity(count * 2);
                 for _ in 0..count {
@@ -467,14 +629,21 @@ pub(super) fn decode_payload_from_bits(bits: &[bool], version: u8) -> Option<(Ve
                     sjis_bytes.push((intermediate >> 8) as u8);
                     sjis_bytes.push((intermediate & 0xFF) as u8);
                 }
-                data.extend_from_slice(&sjis_bytes);
-                content.push_str(&String::from_utf8_lossy(&sjis_bytes));
+                Segment {
+                    mode: SegmentMode::Kanji,
+                    bit_offset,
+                    char_count: count,
+                    text: String::from_utf8_lossy(&sjis_bytes).into_owned(),
+                    data: sjis_bytes,
+                    encoding: TextEncoding::Utf8,
+                }
             }
             _ => return None,
-        }
+        };
+        segments.push(segment);
     }
 
-    Some((data, content))
+    Some(segments)
 }
 
 struct BitReader<'a> {
@@ -512,38 +681,6 @@ impl<'a> BitReader<'a> {
     }
 }
 
-fn char_count_bits(mode: u8, version: u8) -> usize {
-    let ver = version as usize;
-    match mode {
-        1 => {
-            if ver <= 9 {
-                10
-            } else if ver <= 26 {
-                12
-            } else {
-                14
-            }
-        }
-        2 => {
-            if ver <= 9 {
-                9
-            } else if ver <= 26 {
-                11
-            } else {
-                13
-            }
-        }
-        4 => {
-            if ver <= 9 {
-                8
-            } else {
-                16
-            }
-        }
-        _ => 0,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -564,8 +701,80 @@ mod tests {
         let conf = vec![
             80, 70, 60, 50, 40, 30, 20, 10, 255, 255, 255, 255, 255, 255, 255, 255,
         ];
-        let (cw, cc) = bits_to_codewords_with_confidence(&bits, &conf, true);
+        let (cw, cc) = bits_to_codewords_with_confidence(&bits, &conf);
         assert_eq!(cw.len(), 2);
         assert_eq!(cc, vec![10, 255]);
     }
+
+    #[test]
+    fn erasure_cap_is_charged_per_counter_scope() {
+        use super::super::{DECODE_COUNTERS, scoped_decode_counters, with_decode_counters};
+        let cap = crate::decoder::config::rs_erasure_global_cap();
+        if cap == 0 {
+            return;
+        }
+        let ((exhausted, nested, framed, other_thread), _) = with_decode_counters(|| {
+            DECODE_COUNTERS.with(|c| c.borrow_mut().rs_erasure_attempts = cap);
+            let nested = with_decode_counters(should_attempt_erasure).0;
+            let framed = {
+                let _frame = scoped_decode_counters();
+                should_attempt_erasure()
+            };
+            let other_thread =
+                std::thread::spawn(|| with_decode_counters(should_attempt_erasure).0)
+                    .join()
+                    .unwrap();
+            (should_attempt_erasure(), nested, framed, other_thread)
+        });
+        assert!(!exhausted);
+        assert!(nested);
+        assert!(framed);
+        assert!(other_thread);
+    }
+
+    #[test]
+    fn kanji_segment_reads_its_character_count() {
+        // ISO/IEC 18004 example "点茗": mode 1000, 8-bit count 2, then
+        // 13-bit values 0x0D9F and 0x1AAA, then the terminator.
+        let fields = [(0b1000, 4), (2, 8), (0x0D9F, 13), (0x1AAA, 13), (0, 4)];
+        let bits: Vec<bool> = fields
+            .iter()
+            .flat_map(|&(v, n)| (0..n).rev().map(move |i| (v >> i) & 1 != 0))
+            .collect();
+        let segments = parse_segments_from_bits(&bits, 1).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].mode, SegmentMode::Kanji);
+        assert_eq!(segments[0].char_count, 2);
+        assert_eq!(segments[0].data, [0x93, 0x5F, 0xE4, 0xAA]);
+    }
+
+    #[test]
+    fn structured_append_header_precedes_the_data() {
+        // Symbol 2 of 4 (index 1, total - 1 = 3), parity 0x5A, then the
+        // numeric segment "12".
+        let fields = [
+            (0b0011, 4),
+            (1, 4),
+            (3, 4),
+            (0x5A, 8),
+            (0b0001, 4),
+            (2, 10),
+            (12, 7),
+        ];
+        let bits: Vec<bool> = fields
+            .iter()
+            .flat_map(|&(v, n)| (0..n).rev().map(move |i| (v >> i) & 1 != 0))
+            .collect();
+        let segments = parse_segments_from_bits(&bits, 1).unwrap();
+        assert_eq!(
+            segments[0].mode,
+            SegmentMode::StructuredAppend(StructuredAppend {
+                index: 1,
+                total: 4,
+                parity: 0x5A,
+            })
+        );
+        assert!(segments[0].data.is_empty());
+        assert_eq!(join_segments(&segments).0, b"12");
+    }
 }
@@ -0,0 +1,262 @@
+//! Step-wise symbol decoding
+//!
+//! [`DecodeSession`] runs the same stages as the matrix decoder — format
+//! read, unmask, bit extraction, block deinterleaving, Reed-Solomon and
+//! segment parsing — one call at a time, keeping each stage's output so a
+//! failing symbol can be inspected at the point it goes wrong.
+
+use super::payload;
+use crate::decoder::bitstream::BitstreamExtractor;
+use crate::decoder::format::FormatInfo;
+use crate::decoder::function_mask::FunctionMask;
+use crate::decoder::reed_solomon::{ReedSolomonDecoder, RsError};
+use crate::decoder::unmask::unmask;
+use crate::error::DecodeFailure;
+use crate::models::{BitMatrix, StructuredAppend, TextEncoding};
+
+/// Encoding mode of a payload segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentMode {
+    /// Digits, three per 10 bits
+    Numeric,
+    /// 45-character set, two per 11 bits
+    Alphanumeric,
+    /// Raw 8-bit bytes
+    Byte,
+    /// ECI designator switching the character set of later byte segments
+    Eci(u32),
+    /// Shift-JIS double-byte characters, one per 13 bits
+    Kanji,
+    /// Structured-append header placing the symbol in a split message
+    StructuredAppend(StructuredAppend),
+}
+
+/// One mode segment of the decoded data bit stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// Segment mode
+    pub mode: SegmentMode,
+    /// Offset of the mode indicator in the data bit stream
+    pub bit_offset: usize,
+    /// Character count field (0 for ECI and structured append)
+    pub char_count: usize,
+    /// Bytes this segment contributes to [`crate::QRCode::data`]
+    pub data: Vec<u8>,
+    /// Text this segment contributes to [`crate::QRCode::content`]
+    pub text: String,
+    /// Character set `data` was decoded with
+    pub encoding: TextEncoding,
+}
+
+impl Segment {
+    pub(super) fn text(
+        mode: SegmentMode,
+        bit_offset: usize,
+        char_count: usize,
+        text: String,
+    ) -> Self {
+        Self {
+            mode,
+            bit_offset,
+            char_count,
+            data: text.as_bytes().to_vec(),
+            text,
+            encoding: TextEncoding::Utf8,
+        }
+    }
+}
+
+/// Codewords of one Reed-Solomon block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodewordBlock {
+    /// Data codewords
+    pub data: Vec<u8>,
+    /// Error-correction codewords
+    pub ec: Vec<u8>,
+}
+
+/// Reed-Solomon result for one block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCorrection {
+    /// Block after correction; unchanged when correction failed
+    pub block: CodewordBlock,
+    /// Indices into data-then-EC order of codewords correction changed
+    pub corrected: Vec<usize>,
+    /// Whether the block decoded
+    pub outcome: Result<(), RsError>,
+}
+
+/// Step-by-step decoder for an upright module grid.
+///
+/// Each step runs any earlier step that hasn't been run yet and caches its
+/// output, so steps can be called in order to watch the symbol move through
+/// the pipeline or jumped straight to the last one. Unlike the detector this
+/// tries a single orientation, traversal and format reading, and does no
+/// confidence-guided erasure decoding.
+#[derive(Debug, Clone)]
+pub struct DecodeSession {
+    matrix: BitMatrix,
+    version: u8,
+    format: Option<FormatInfo>,
+    unmasked: Option<BitMatrix>,
+    bits: Option<Vec<bool>>,
+    blocks: Option<Vec<CodewordBlock>>,
+    corrections: Option<Vec<BlockCorrection>>,
+    segments: Option<Vec<Segment>>,
+}
+
+impl DecodeSession {
+    /// Start a session on `matrix`, a `17 + 4 * version` module square grid
+    /// with the finder patterns top-left, top-right and bottom-left.
+    pub fn new(matrix: BitMatrix, version: u8) -> Self {
+        Self {
+            matrix,
+            version,
+            format: None,
+            unmasked: None,
+            bits: None,
+            blocks: None,
+            corrections: None,
+            segments: None,
+        }
+    }
+
+    /// Use `format` instead of reading the format information, e.g. to test
+    /// a guess when the format bits are damaged.
+    pub fn with_format(mut self, format: FormatInfo) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// The module grid as given
+    pub fn matrix(&self) -> &BitMatrix {
+        &self.matrix
+    }
+
+    /// Read the EC level and mask pattern from the format information.
+    pub fn read_format(&mut self) -> Result<&FormatInfo, DecodeFailure> {
+        let dim = 17 + 4 * self.version as usize;
+        if !(1..=40).contains(&self.version)
+            || self.matrix.width() != dim
+            || self.matrix.height() != dim
+        {
+            return Err(DecodeFailure::UnsupportedVersion(self.version));
+        }
+        if self.format.is_none() {
+            self.format = Some(FormatInfo::try_extract(&self.matrix)?);
+        }
+        Ok(self.format.as_ref().unwrap())
+    }
+
+    /// Remove the data mask; function modules are left as sampled.
+    pub fn unmask(&mut self) -> Result<&BitMatrix, DecodeFailure> {
+        if self.unmasked.is_none() {
+            let mask = self.read_format()?.mask_pattern;
+            let mut unmasked = self.matrix.clone();
+            unmask(&mut unmasked, &mask, &FunctionMask::new(self.version));
+            self.unmasked = Some(unmasked);
+        }
+        Ok(self.unmasked.as_ref().unwrap())
+    }
+
+    /// Read the data modules in zigzag order, function modules skipped.
+    pub fn extract_bits(&mut self) -> Result<&[bool], DecodeFailure> {
+        if self.bits.is_none() {
+            self.unmask()?;
+            let unmasked = self.unmasked.as_ref().unwrap();
+            self.bits = Some(BitstreamExtractor::extract(
+                unmasked,
+                unmasked.width(),
+                &FunctionMask::new(self.version),
+            ));
+        }
+        Ok(self.bits.as_deref().unwrap())
+    }
+
+    /// Pack the bits into codewords and split them into their RS blocks.
+    pub fn deinterleave(&mut self) -> Result<&[CodewordBlock], DecodeFailure> {
+        if self.blocks.is_none() {
+            self.extract_bits()?;
+            let ec_level = self.format.as_ref().unwrap().ec_level;
+            let (codewords, _) =
+                payload::bits_to_codewords_with_confidence(self.bits.as_ref().unwrap(), &[]);
+            let layout = payload::deinterleave_blocks(&codewords, self.version, ec_level, None)?;
+            let blocks = layout
+                .blocks
+                .into_iter()
+                .zip(layout.data_lens)
+                .map(|(mut data, data_len)| {
+                    let ec = data.split_off(data_len);
+                    CodewordBlock { data, ec }
+                })
+                .collect();
+            self.blocks = Some(blocks);
+        }
+        Ok(self.blocks.as_deref().unwrap())
+    }
+
+    /// Run Reed-Solomon on every block. Failed blocks are reported in their
+    /// [`BlockCorrection::outcome`] rather than as an error, so the other
+    /// blocks can still be examined.
+    pub fn correct_blocks(&mut self) -> Result<&[BlockCorrection], DecodeFailure> {
+        if self.corrections.is_none() {
+            self.deinterleave()?;
+            let blocks = self.blocks.as_ref().unwrap();
+            let ecc_per_block = blocks.first().map_or(0, |b| b.ec.len());
+            let rs = ReedSolomonDecoder::new(ecc_per_block);
+            let corrections = blocks
+                .iter()
+                .map(|block| {
+                    let mut codewords = [block.data.as_slice(), block.ec.as_slice()].concat();
+                    let outcome = rs.try_decode(&mut codewords);
+                    if outcome.is_err() {
+                        return BlockCorrection {
+                            block: block.clone(),
+                            corrected: Vec::new(),
+                            outcome,
+                        };
+                    }
+                    let corrected = block
+                        .data
+                        .iter()
+                        .chain(&block.ec)
+                        .zip(&codewords)
+                        .enumerate()
+                        .filter(|(_, (before, after))| before != after)
+                        .map(|(i, _)| i)
+                        .collect();
+                    let ec = codewords.split_off(block.data.len());
+                    BlockCorrection {
+                        block: CodewordBlock {
+                            data: codewords,
+                            ec,
+                        },
+                        corrected,
+                        outcome,
+                    }
+                })
+                .collect();
+            self.corrections = Some(corrections);
+        }
+        Ok(self.corrections.as_deref().unwrap())
+    }
+
+    /// Parse the corrected data codewords into mode segments.
+    pub fn parse_segments(&mut self) -> Result<&[Segment], DecodeFailure> {
+        if self.segments.is_none() {
+            let mut data = Vec::new();
+            for correction in self.correct_blocks()? {
+                correction.outcome.map_err(DecodeFailure::ReedSolomon)?;
+                data.extend_from_slice(&correction.block.data);
+            }
+            let bits: Vec<bool> = data
+                .iter()
+                .flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1 != 0))
+                .collect();
+            let segments = payload::parse_segments_from_bits(&bits, self.version)
+                .ok_or(DecodeFailure::Payload)?;
+            self.segments = Some(segments);
+        }
+        Ok(self.segments.as_deref().unwrap())
+    }
+}
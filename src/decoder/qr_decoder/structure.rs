@@ -0,0 +1,118 @@
+//! Fixed function pattern checks on a sampled grid
+//!
+//! Cheap next to Reed-Solomon: a grid sampled at the wrong version, or from
+//! a misplaced corner, breaks the timing alternation and smears data into
+//! the separators, while the right one leaves them intact.
+
+use super::orientation::alternation_ratio;
+use crate::models::BitMatrix;
+
+/// Separator modules: the light L inside the symbol around each finder.
+const SEPARATOR_MODULES: usize = 3 * 15;
+
+/// The fixed patterns of an upright grid, as sampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct StructureCheck {
+    /// Whether the dark module at (8, size - 8) is dark
+    pub dark_module: bool,
+    /// Share of adjacent timing-pattern modules that alternate, both lines
+    pub timing_alternation: f32,
+    /// Share of separator modules that are light
+    pub separator_whiteness: f32,
+    /// Adjacent timing-module pairs checked
+    timing_pairs: usize,
+}
+
+impl StructureCheck {
+    /// Check `grid` with dark modules read as `dark`; `None` unless it is a
+    /// square of at least version 1 size.
+    pub fn measure(grid: &BitMatrix, dark: bool) -> Option<Self> {
+        let size = grid.width();
+        if size < 21 || grid.height() != size {
+            return None;
+        }
+        let row: Vec<bool> = (8..size - 8).map(|x| grid.get(x, 6)).collect();
+        let column: Vec<bool> = (8..size - 8).map(|y| grid.get(6, y)).collect();
+        let timing_pairs = row.len() - 1;
+        let timing_alternation = (alternation_ratio(&row) + alternation_ratio(&column)) / 2.0;
+
+        let far = size - 8;
+        let separators = (0..8)
+            .flat_map(|i| [(i, 7), (far + i, 7), (i, far)])
+            .chain((0..7).flat_map(|i| [(7, i), (far, i), (7, far + 1 + i)]));
+        let light = separators.filter(|&(x, y)| grid.get(x, y) != dark).count();
+        Some(Self {
+            dark_module: grid.get(8, size - 8) == dark,
+            timing_alternation,
+            separator_whiteness: light as f32 / SEPARATOR_MODULES as f32,
+            timing_pairs,
+        })
+    }
+
+    /// Share of the checked modules that are as the specification draws
+    /// them, each module (or timing pair) weighing the same.
+    pub fn score(&self) -> f32 {
+        let timing = 2 * self.timing_pairs;
+        let intact = self.timing_alternation * timing as f32
+            + self.separator_whiteness * SEPARATOR_MODULES as f32
+            + f32::from(u8::from(self.dark_module));
+        intact / (timing + SEPARATOR_MODULES + 1) as f32
+    }
+}
+
+/// Fixed pattern damage of an upright, dark-on-light grid: the share of
+/// checked modules that are wrong, 0.0 when intact.
+pub(super) fn fixed_pattern_damage(grid: &BitMatrix) -> f32 {
+    StructureCheck::measure(grid, true).map_or(1.0, |check| 1.0 - check.score())
+}
+
+/// Score for ranking version candidates: the structure of `grid` in
+/// whichever polarity fits it better, since inverted symbols are decoded too.
+pub(super) fn ranking_score(grid: &BitMatrix) -> f32 {
+    [true, false]
+        .into_iter()
+        .filter_map(|dark| StructureCheck::measure(grid, dark))
+        .map(|check| check.score())
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol_frame(size: usize) -> BitMatrix {
+        let mut grid = BitMatrix::new(size, size);
+        for i in 8..size - 8 {
+            grid.set(i, 6, i % 2 == 0);
+            grid.set(6, i, i % 2 == 0);
+        }
+        grid.set(8, size - 8, true);
+        grid
+    }
+
+    #[test]
+    fn intact_frame_scores_full_and_damage_counts_modules() {
+        let mut grid = symbol_frame(25);
+        let check = StructureCheck::measure(&grid, true).unwrap();
+        assert!(check.dark_module);
+        assert_eq!(check.timing_alternation, 1.0);
+        assert_eq!(check.separator_whiteness, 1.0);
+        assert_eq!(fixed_pattern_damage(&grid), 0.0);
+
+        // 2 x 8 timing pairs + 45 separator modules + the dark module.
+        grid.set(7, 3, true);
+        grid.set(8, 17, false);
+        let damage = fixed_pattern_damage(&grid);
+        assert!((damage - 2.0 / 62.0).abs() < 1e-6, "{damage}");
+
+        // The inverted grid is as sound for ranking, not as printed.
+        let mut inverted = symbol_frame(25);
+        for y in 0..25 {
+            for x in 0..25 {
+                inverted.set(x, y, !inverted.get(x, y));
+            }
+        }
+        assert_eq!(ranking_score(&inverted), 1.0);
+        assert!(fixed_pattern_damage(&inverted) > 0.5);
+    }
+}
@@ -1,7 +1,101 @@
 use super::*;
+use crate::models::Binarization;
 use crate::models::ECLevel;
+use crate::models::GrayFrame;
+use crate::models::MaskPattern;
+use crate::models::Orientation;
+use crate::models::TextEncoding;
 use crate::models::Version;
 
+/// Known-good 21x21 QR matrix for "4376471154038" (Version 1),
+/// generated with the Python qrcode library.
+const GOLDEN_V1_GRID: [[bool; 21]; 21] = [
+    [
+        true, true, true, true, true, true, true, false, false, false, false, false, true, false,
+        true, true, true, true, true, true, true,
+    ],
+    [
+        true, false, false, false, false, false, true, false, false, true, false, false, false,
+        false, true, false, false, false, false, false, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, false, false, true, true, false, false,
+        true, false, true, true, true, false, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, false, false, true, false, false, false,
+        true, false, true, true, true, false, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, false, true, true, true, true, false,
+        true, false, true, true, true, false, true,
+    ],
+    [
+        true, false, false, false, false, false, true, false, true, false, true, false, false,
+        false, true, false, false, false, false, false, true,
+    ],
+    [
+        true, true, true, true, true, true, true, false, true, false, true, false, true, false,
+        true, true, true, true, true, true, true,
+    ],
+    [
+        false, false, false, false, false, false, false, false, false, true, false, false, false,
+        false, false, false, false, false, false, false, false,
+    ],
+    [
+        true, false, false, true, false, true, true, false, true, true, true, true, true, true,
+        false, true, false, false, false, false, false,
+    ],
+    [
+        true, true, true, false, true, false, false, true, true, false, false, true, false, true,
+        false, true, false, true, true, false, false,
+    ],
+    [
+        true, false, false, true, false, true, true, true, true, false, true, true, false, false,
+        true, true, true, false, false, false, true,
+    ],
+    [
+        false, false, true, false, true, false, false, true, false, false, false, false, true,
+        true, true, true, true, false, false, false, false,
+    ],
+    [
+        false, false, true, false, false, false, true, true, false, true, false, true, false, true,
+        true, true, false, true, true, false, false,
+    ],
+    [
+        false, false, false, false, false, false, false, false, true, false, true, false, false,
+        true, true, true, true, false, true, true, false,
+    ],
+    [
+        true, true, true, true, true, true, true, false, false, false, true, true, true, false,
+        true, false, true, true, true, true, false,
+    ],
+    [
+        true, false, false, false, false, false, true, false, true, false, false, false, false,
+        false, true, true, false, false, false, false, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, false, true, true, false, true, true,
+        true, false, false, true, false, true, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, true, false, true, false, false, true,
+        true, true, true, false, false, true, true,
+    ],
+    [
+        true, false, true, true, true, false, true, false, false, true, true, true, false, true,
+        true, true, false, true, false, false, true,
+    ],
+    [
+        true, false, false, false, false, false, true, false, false, true, true, true, true, false,
+        false, true, true, false, false, true, false,
+    ],
+    [
+        true, true, true, true, true, true, true, false, true, true, true, false, false, true,
+        false, true, true, true, false, false, false,
+    ],
+];
+
 #[test]
 fn test_decoder_basic() {
     // Use intentionally inconsistent geometry so decode exits quickly.
@@ -26,11 +120,54 @@ fn test_decode_payload_byte_mode() {
     push_bits(&mut bits, 0, 4); // terminator
 
     let codewords = payload::bits_to_codewords(&bits);
-    let (data, content) = payload::decode_payload(&codewords, 1).unwrap();
+    let (data, content, _) = payload::decode_payload(&codewords, 1).unwrap();
     assert_eq!(content, "HI");
     assert_eq!(data, b"HI");
 }
 
+#[test]
+fn test_decode_payload_byte_mode_charsets() {
+    let byte_segment = |eci: Option<u32>, bytes: &[u8]| {
+        let mut bits = Vec::new();
+        if let Some(eci) = eci {
+            push_bits(&mut bits, 0b0111, 4);
+            push_bits(&mut bits, eci, 8);
+        }
+        push_bits(&mut bits, 0b0100, 4);
+        push_bits(&mut bits, bytes.len() as u32, 8);
+        for &b in bytes {
+            push_bits(&mut bits, b as u32, 8);
+        }
+        push_bits(&mut bits, 0, 4);
+        payload::bits_to_codewords(&bits)
+    };
+    let latin1 = byte_segment(None, b"caf\xe9");
+    let utf8 = byte_segment(None, "café".as_bytes());
+
+    // No ECI: valid UTF-8 stays UTF-8, anything else is ISO-8859-1.
+    let (data, content, encoding) = payload::decode_payload(&latin1, 1).unwrap();
+    assert_eq!((content.as_str(), encoding), ("café", TextEncoding::Latin1));
+    assert_eq!(data, b"caf\xe9");
+    let (_, content, encoding) = payload::decode_payload(&utf8, 1).unwrap();
+    assert_eq!((content.as_str(), encoding), ("café", TextEncoding::Utf8));
+
+    // The config override replaces sniffing, but an ECI designator wins.
+    let forced = crate::DetectorConfig {
+        byte_encoding: Some(TextEncoding::Latin1),
+        ..Default::default()
+    };
+    forced.apply(|| {
+        let (_, content, encoding) = payload::decode_payload(&utf8, 1).unwrap();
+        assert_eq!(
+            (content.as_str(), encoding),
+            ("cafÃ©", TextEncoding::Latin1)
+        );
+        let eci_utf8 = byte_segment(Some(26), "café".as_bytes());
+        let (_, content, encoding) = payload::decode_payload(&eci_utf8, 1).unwrap();
+        assert_eq!((content.as_str(), encoding), ("café", TextEncoding::Utf8));
+    });
+}
+
 fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
     for i in (0..count).rev() {
         bits.push(((value >> i) & 1) != 0);
@@ -39,8 +176,296 @@ fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
 
 #[test]
 fn test_golden_matrix_decode() {
-    // Known-good 21x21 QR matrix for "4376471154038" (Version 1-M)
-    // Generated with Python qrcode library
+    let grid = GOLDEN_V1_GRID;
+
+    let mut matrix = BitMatrix::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, grid[y][x]);
+        }
+    }
+
+    let result = QrDecoder::decode_from_matrix(&matrix, 1);
+    assert!(result.is_some(), "Failed to decode golden QR matrix");
+    let qr = result.unwrap();
+    assert_eq!(qr.content, "4376471154038");
+}
+
+#[test]
+fn test_format_brute_force_skips_implausible_headers() {
+    // The golden grid's format bits are wrong (H/mask 0 for L/mask 7 data),
+    // so it only decodes through the brute-forced format guesses.
+    let mut matrix = BitMatrix::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, GOLDEN_V1_GRID[y][x]);
+        }
+    }
+    let (qr, counters) = with_decode_counters(|| QrDecoder::decode_from_matrix(&matrix, 1));
+    assert_eq!(qr.unwrap().content, "4376471154038");
+    assert!(counters.header_prefilter_skips > 0);
+
+    // L/mask 7 read upward opens with a numeric header for 13 digits.
+    let func = crate::decoder::function_mask::FunctionMask::new(1);
+    let prefix =
+        matrix_decode::stream_prefix(&matrix, &func, MaskPattern::Pattern7, true, false, 3);
+    let blocks = crate::decoder::tables::ec_block_info(1, ECLevel::L).unwrap();
+    let header = matrix_decode::block_header(&prefix, &blocks).unwrap();
+    assert_eq!(header >> 6, 0b0001_0000001101);
+    assert!(matrix_decode::plausible_header(header, 1, ECLevel::L));
+    // 41 digits fit a version 1-L symbol; 63 are one bit off 31 and pass as
+    // a damaged header, 200 are two bits from anything that fits.
+    let numeric = |count: u32| (0b0001 << 10 | count) << 6;
+    assert!(matrix_decode::plausible_header(numeric(41), 1, ECLevel::L));
+    assert!(matrix_decode::plausible_header(numeric(63), 1, ECLevel::L));
+    assert!(!matrix_decode::plausible_header(
+        numeric(200),
+        1,
+        ECLevel::L
+    ));
+    // A terminator is one flip from a data mode; 0b1101 is two from any.
+    assert!(matrix_decode::plausible_header(0, 1, ECLevel::L));
+    assert!(!matrix_decode::plausible_header(
+        0b1101_1111 << 12,
+        1,
+        ECLevel::L
+    ));
+}
+
+#[test]
+fn test_decode_session_exposes_each_stage() {
+    let grid = GOLDEN_V1_GRID;
+    let mut matrix = BitMatrix::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, grid[y][x]);
+        }
+    }
+
+    // The golden grid's format bits read as H/mask 0, but its data was
+    // masked with pattern 7; the session shows decoding break down at RS.
+    let mut session = DecodeSession::new(matrix.clone(), 1);
+    let format = *session.read_format().unwrap();
+    assert_eq!(
+        (format.ec_level, format.mask_pattern),
+        (ECLevel::H, MaskPattern::Pattern0)
+    );
+    assert!(session.correct_blocks().unwrap()[0].outcome.is_err());
+    assert!(matches!(
+        session.parse_segments(),
+        Err(DecodeFailure::ReedSolomon(_))
+    ));
+
+    // With the format the decoder falls back to, flip the first data module
+    // (bottom-right corner) and watch RS repair it.
+    let format = crate::decoder::format::FormatInfo {
+        ec_level: ECLevel::L,
+        mask_pattern: MaskPattern::Pattern7,
+    };
+    matrix.set(20, 20, !matrix.get(20, 20));
+    let mut session = DecodeSession::new(matrix.clone(), 1).with_format(format);
+    assert_ne!(session.unmask().unwrap().get(20, 20), grid[20][20]);
+    assert_eq!(session.extract_bits().unwrap().len(), 208);
+
+    let blocks = session.deinterleave().unwrap();
+    assert_eq!(
+        (blocks.len(), blocks[0].data.len(), blocks[0].ec.len()),
+        (1, 19, 7)
+    );
+    let raw_first = blocks[0].data[0];
+
+    let corrections = session.correct_blocks().unwrap();
+    assert_eq!(corrections[0].outcome, Ok(()));
+    assert_eq!(corrections[0].corrected, vec![0]);
+    assert_eq!(corrections[0].block.data[0], raw_first ^ 0x80);
+
+    let segments = session.parse_segments().unwrap();
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].mode, SegmentMode::Numeric);
+    assert_eq!((segments[0].bit_offset, segments[0].char_count), (0, 13));
+    assert_eq!(segments[0].text, "4376471154038");
+
+    // Steps can be jumped to directly, and a wrong version is caught up front.
+    let mut session = DecodeSession::new(matrix.clone(), 1).with_format(format);
+    assert_eq!(session.parse_segments().unwrap()[0].text, "4376471154038");
+    let mut session = DecodeSession::new(matrix, 2).with_format(format);
+    assert_eq!(
+        session.parse_segments().unwrap_err(),
+        DecodeFailure::UnsupportedVersion(2)
+    );
+}
+
+#[test]
+fn test_has_finders_correct_golden_matrix() {
+    // The golden matrix is correctly oriented — has_finders_correct should return true
+    let grid = GOLDEN_V1_GRID;
+
+    let mut matrix = BitMatrix::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, grid[y][x]);
+        }
+    }
+
+    assert!(
+        orientation::has_finders_correct(&matrix),
+        "has_finders_correct should return true for the golden matrix"
+    );
+
+    // A rotated version should NOT pass the check (finders in wrong positions)
+    let rotated = orientation::rotate90(&matrix);
+    assert!(
+        !orientation::has_finders_correct(&rotated),
+        "has_finders_correct should return false for a 90° rotated matrix"
+    );
+}
+
+#[test]
+fn test_golden_matrix_verify_ec_and_version() {
+    // Test that we correctly extract EC level and version from the golden matrix
+    let grid = GOLDEN_V1_GRID;
+
+    let mut matrix = BitMatrix::new(21, 21);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, grid[y][x]);
+        }
+    }
+
+    let result = QrDecoder::decode_from_matrix(&matrix, 1);
+    assert!(result.is_some(), "Failed to decode golden QR matrix");
+    let qr = result.unwrap();
+
+    // Verify content
+    assert_eq!(qr.content, "4376471154038", "Content mismatch");
+
+    // Verify metadata
+    assert_eq!(qr.version, Version::Model2(1), "Version should be 1");
+    // Note: The golden matrix uses EC level L (as determined by the decoder)
+    assert_eq!(qr.error_correction, ECLevel::L, "EC level should be L");
+
+    assert!(qr.raw_codewords().is_none(), "intermediates are opt-in");
+
+    // Flip one data module; the kept intermediates show the corrected codeword.
+    matrix.set(20, 20, !matrix.get(20, 20));
+    let keep = crate::DetectorConfig {
+        keep_intermediate_data: true,
+        ..Default::default()
+    };
+    let qr = keep
+        .apply(|| QrDecoder::decode_from_matrix(&matrix, 1))
+        .expect("single flipped module is correctable");
+    assert_eq!(qr.content, "4376471154038");
+    let raw = qr.raw_codewords().unwrap();
+    let corrected = qr.corrected_codewords().unwrap();
+    assert_eq!(raw.len(), 26);
+    assert_eq!(corrected.len(), 26);
+    assert_eq!(raw.iter().zip(corrected).filter(|(a, b)| a != b).count(), 1);
+    assert_eq!(qr.unmasked_modules().unwrap().width(), 21);
+}
+
+#[test]
+fn test_decode_numeric_mode() {
+    // Test that numeric mode decoding works by testing the payload decoder
+    // Simpler test that doesn't require exact encoding knowledge
+    let mut bits = Vec::new();
+    push_bits(&mut bits, 0b0001, 4); // Numeric mode
+    push_bits(&mut bits, 3, 10); // Count: 3 digits for version 1
+
+    // Encode "123" in numeric mode: single group of 3 digits
+    push_bits(&mut bits, 123, 10); // 123 in 10 bits
+
+    push_bits(&mut bits, 0, 4); // Terminator
+
+    // Pad to byte boundary
+    while bits.len() % 8 != 0 {
+        bits.push(false);
+    }
+
+    let codewords = payload::bits_to_codewords(&bits);
+    let result = payload::decode_payload(&codewords, 1);
+
+    // This test verifies the numeric decoder works
+    assert!(result.is_some(), "Numeric mode decode should succeed");
+    if let Some((data, content, _)) = result {
+        assert_eq!(content, "123");
+        assert_eq!(data, b"123");
+    }
+}
+
+#[test]
+fn test_decode_alphanumeric_mode() {
+    // Test that alphanumeric mode decoding works
+    let mut bits = Vec::new();
+    push_bits(&mut bits, 0b0010, 4); // Alphanumeric mode
+    push_bits(&mut bits, 2, 9); // Count: 2 characters for version 1
+
+    // Encode "AB" in alphanumeric mode
+    // A=10, B=11 in alphanumeric table
+    // Pair: AB = 10*45 + 11 = 461
+    push_bits(&mut bits, 461, 11); // 2 chars = 11 bits
+
+    push_bits(&mut bits, 0, 4); // Terminator
+
+    // Pad to byte boundary
+    while bits.len() % 8 != 0 {
+        bits.push(false);
+    }
+
+    let codewords = payload::bits_to_codewords(&bits);
+    let result = payload::decode_payload(&codewords, 1);
+
+    assert!(result.is_some(), "Alphanumeric mode decode should succeed");
+    if let Some((data, content, _)) = result {
+        assert_eq!(content, "AB");
+        assert_eq!(data, b"AB");
+    }
+}
+
+#[test]
+fn test_decode_mixed_modes() {
+    // Test a QR code with multiple encoding modes in sequence
+    let mut bits = Vec::new();
+
+    // First segment: Numeric "123"
+    push_bits(&mut bits, 0b0001, 4); // Numeric mode
+    push_bits(&mut bits, 3, 10); // Count: 3 digits
+    push_bits(&mut bits, 123, 10); // 3 digits
+
+    // Second segment: Byte "ABC"
+    push_bits(&mut bits, 0b0100, 4); // Byte mode
+    push_bits(&mut bits, 3, 8); // Count: 3 bytes
+    push_bits(&mut bits, b'A' as u32, 8);
+    push_bits(&mut bits, b'B' as u32, 8);
+    push_bits(&mut bits, b'C' as u32, 8);
+
+    push_bits(&mut bits, 0, 4); // Terminator
+
+    let codewords = payload::bits_to_codewords(&bits);
+    let (data, content, _) = payload::decode_payload(&codewords, 1).unwrap();
+    assert_eq!(content, "123ABC");
+    assert_eq!(data, b"123ABC");
+}
+
+#[test]
+fn test_decode_empty_data() {
+    // Test that empty data is rejected
+    let mut bits = Vec::new();
+    push_bits(&mut bits, 0, 4); // Terminator only
+
+    let codewords = payload::bits_to_codewords(&bits);
+    let result = payload::decode_payload(&codewords, 1);
+
+    // Empty data should return Some with empty content
+    assert!(result.is_some());
+    let (data, content, _) = result.unwrap();
+    assert!(data.is_empty());
+    assert!(content.is_empty());
+}
+
+#[test]
+fn test_orientation_detection() {
+    // Test that we correctly detect and fix orientation
     let grid: [[bool; 21]; 21] = [
         [
             true, true, true, true, true, true, true, false, false, false, false, false, true,
@@ -128,458 +553,1555 @@ fn test_golden_matrix_decode() {
         ],
     ];
 
-    let mut matrix = BitMatrix::new(21, 21);
+    let mut correct_matrix = BitMatrix::new(21, 21);
     for y in 0..21 {
         for x in 0..21 {
-            matrix.set(x, y, grid[y][x]);
+            correct_matrix.set(x, y, grid[y][x]);
         }
     }
 
-    let result = QrDecoder::decode_from_matrix(&matrix, 1);
-    assert!(result.is_some(), "Failed to decode golden QR matrix");
-    let qr = result.unwrap();
-    assert_eq!(qr.content, "4376471154038");
+    // Test all rotations - the decoder should handle them
+    let rotated_90 = orientation::rotate90(&correct_matrix);
+    let rotated_180 = orientation::rotate180(&correct_matrix);
+    let rotated_270 = orientation::rotate270(&correct_matrix);
+
+    // All rotations should decode to the same content
+    let result_0 = QrDecoder::decode_from_matrix(&correct_matrix, 1);
+    let result_90 = QrDecoder::decode_from_matrix(&rotated_90, 1);
+    let result_180 = QrDecoder::decode_from_matrix(&rotated_180, 1);
+    let result_270 = QrDecoder::decode_from_matrix(&rotated_270, 1);
+
+    assert!(result_0.is_some(), "Failed to decode correct orientation");
+    assert!(result_90.is_some(), "Failed to decode 90° rotation");
+    assert!(result_180.is_some(), "Failed to decode 180° rotation");
+    assert!(result_270.is_some(), "Failed to decode 270° rotation");
+
+    let content_0 = result_0.unwrap().content;
+    let content_90 = result_90.unwrap().content;
+    let content_180 = result_180.unwrap().content;
+    let content_270 = result_270.unwrap().content;
+
+    assert_eq!(content_0, "4376471154038");
+    assert_eq!(content_90, "4376471154038");
+    assert_eq!(content_180, "4376471154038");
+    assert_eq!(content_270, "4376471154038");
+}
+
+/// Whether module (`mx`, `my`) of a 29-module grid holding the golden
+/// symbol inside a 4-module quiet zone is dark.
+pub(crate) fn golden_v1_dark(mx: usize, my: usize) -> bool {
+    (4..25).contains(&mx) && (4..25).contains(&my) && GOLDEN_V1_GRID[my - 4][mx - 4]
+}
+
+/// The golden symbol with its bottom-right data area scrambled: format and
+/// finders read, error correction does not.
+pub(crate) fn golden_v1_scrambled_dark(mx: usize, my: usize) -> bool {
+    let scrambled = (13..25).contains(&mx) && (13..25).contains(&my) && (mx * 7 + my * 3) % 5 < 2;
+    golden_v1_dark(mx, my) ^ scrambled
+}
+
+/// Render a 29-module grid upright into `width`x`height` gray pixels,
+/// `module` pixels per module, the grid's top left corner at `origin`.
+pub(crate) fn render_upright_grid(
+    module: f32,
+    origin: (f32, f32),
+    width: usize,
+    height: usize,
+    dark: impl Fn(usize, usize) -> bool,
+) -> Vec<u8> {
+    let dst = [(0.0, 0.0), (29.0, 0.0), (0.0, 29.0), (29.0, 29.0)]
+        .map(|(x, y)| Point::new(origin.0 + x * module, origin.1 + y * module));
+    render_module_grid(29, dst, width, height, dark).0
+}
+
+/// The golden symbol and its quiet zone rendered upright (see
+/// [`render_upright_grid`]).
+pub(crate) fn golden_v1_gray(
+    module: f32,
+    origin: (f32, f32),
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    render_upright_grid(module, origin, width, height, golden_v1_dark)
+}
+
+/// [`golden_v1_gray`] as RGB, with its size.
+pub(crate) fn golden_v1_rgb(
+    module: f32,
+    origin: (f32, f32),
+    width: usize,
+    height: usize,
+) -> (Vec<u8>, usize, usize) {
+    let gray = golden_v1_gray(module, origin, width, height);
+    (gray_to_rgb(&gray), width, height)
+}
+
+/// Use up the RS erasure cap on this thread's decode counters, as a long
+/// run of earlier calls would.
+pub(crate) fn exhaust_rs_erasure_cap() {
+    DECODE_COUNTERS.with(|c| {
+        c.borrow_mut().rs_erasure_attempts = crate::decoder::config::rs_erasure_global_cap()
+    });
+}
+
+/// Gray pixels repeated into the three RGB channels.
+fn gray_to_rgb(gray: &[u8]) -> Vec<u8> {
+    gray.iter().flat_map(|&v| [v, v, v]).collect()
+}
+
+/// Render a `dimension`-module grid into `width`x`height` gray pixels with
+/// 8x8 supersampling, mapping the grid's outer corners to `dst`.
+pub(crate) fn render_module_grid(
+    dimension: usize,
+    dst: [Point; 4],
+    width: usize,
+    height: usize,
+    dark: impl Fn(usize, usize) -> bool,
+) -> (Vec<u8>, crate::utils::geometry::PerspectiveTransform) {
+    let d = dimension as f32;
+    let src = [
+        Point::new(0.0, 0.0),
+        Point::new(d, 0.0),
+        Point::new(0.0, d),
+        Point::new(d, d),
+    ];
+    let transform = crate::utils::geometry::PerspectiveTransform::from_points(&src, &dst).unwrap();
+    let inverse = crate::utils::geometry::PerspectiveTransform::from_points(&dst, &src).unwrap();
+    let mut gray = vec![230u8; width * height];
+    for py in 0..height {
+        for px in 0..width {
+            let mut dark_hits = 0;
+            for sy in 0..8 {
+                for sx in 0..8 {
+                    let p = Point::new(
+                        px as f32 + (sx as f32 + 0.5) / 8.0,
+                        py as f32 + (sy as f32 + 0.5) / 8.0,
+                    );
+                    let m = inverse.transform(&p);
+                    if m.x >= 0.0
+                        && m.y >= 0.0
+                        && (m.x as usize) < dimension
+                        && (m.y as usize) < dimension
+                        && dark(m.x as usize, m.y as usize)
+                    {
+                        dark_hits += 1;
+                    }
+                }
+            }
+            gray[py * width + px] = 230 - (dark_hits * 200 / 64) as u8;
+        }
+    }
+    (gray, transform)
+}
+
+/// Modules the gray extractor gets wrong for a rendered grid.
+fn sampling_errors(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    transform: &crate::utils::geometry::PerspectiveTransform,
+    dimension: usize,
+    dark: impl Fn(usize, usize) -> bool,
+) -> usize {
+    let (grid, _) = QrDecoder::extract_qr_region_gray_with_transform_and_confidence(
+        gray, width, height, transform, dimension,
+    );
+    (0..dimension * dimension)
+        .filter(|i| grid.get(i % dimension, i / dimension) != dark(i % dimension, i / dimension))
+        .count()
 }
 
 #[test]
-fn test_has_finders_correct_golden_matrix() {
-    // The golden matrix is correctly oriented — has_finders_correct should return true
-    let grid: [[bool; 21]; 21] = [
-        [
-            true, true, true, true, true, true, true, false, false, false, false, false, true,
-            false, true, true, true, true, true, true, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, false, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, true, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, false, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, true,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, true, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, false, true, false, true, false,
-            true, true, true, true, true, true, true,
-        ],
-        [
-            false, false, false, false, false, false, false, false, false, true, false, false,
-            false, false, false, false, false, false, false, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, false, true, true, true, true, true, true,
-            false, true, false, false, false, false, false,
-        ],
-        [
-            true, true, true, false, true, false, false, true, true, false, false, true, false,
-            true, false, true, false, true, true, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, true, true, false, true, true, false,
-            false, true, true, true, false, false, false, true,
-        ],
-        [
-            false, false, true, false, true, false, false, true, false, false, false, false, true,
-            true, true, true, true, false, false, false, false,
-        ],
-        [
-            false, false, true, false, false, false, true, true, false, true, false, true, false,
-            true, true, true, false, true, true, false, false,
-        ],
-        [
-            false, false, false, false, false, false, false, false, true, false, true, false,
-            false, true, true, true, true, false, true, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, false, false, true, true, true, false,
-            true, false, true, true, true, true, false,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, false, false, false,
-            false, true, true, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, false, true,
-            true, true, false, false, true, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, true, false, true, false, false,
-            true, true, true, true, false, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, false,
-            true, true, true, false, true, false, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, true, true, true,
-            false, false, true, true, false, false, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, true, true, false, false, true,
-            false, true, true, true, false, false, false,
-        ],
+fn test_area_weighted_sampling_on_small_sheared_modules() {
+    // ~2 px per module under a shear, so axis-aligned kernels straddle
+    // neighbouring modules.
+    let dimension = 25;
+    let dark = |mx: usize, my: usize| (mx * 7 + my * 13 + mx * my) % 5 < 2;
+    let (width, height) = (90, 70);
+    let dst = [
+        Point::new(10.0, 8.0),
+        Point::new(62.0, 14.0),
+        Point::new(22.0, 58.0),
+        Point::new(74.0, 64.0),
+    ];
+    let (gray, transform) = render_module_grid(dimension, dst, width, height, dark);
+
+    let errors = |mode: GridSampling| {
+        let _scope = scoped_grid_sampling(mode);
+        sampling_errors(&gray, width, height, &transform, dimension, dark)
+    };
+    let kernel = errors(GridSampling::Kernel);
+    let area = errors(GridSampling::AreaWeighted);
+    assert!(area < kernel, "area-weighted {area} vs kernel {kernel}");
+}
+
+#[test]
+fn test_coverage_sampling_on_anti_aliased_render() {
+    // A page downscaled to ~1.7 px per module with anti-aliasing: most
+    // module edges fall inside a pixel and blend both neighbours.
+    let dimension = 25;
+    let dark = |mx: usize, my: usize| (mx * 7 + my * 13 + mx * my) % 5 < 2;
+    let (width, height) = (52, 52);
+    let dst = [(0.0, 0.0), (25.0, 0.0), (0.0, 25.0), (25.0, 25.0)]
+        .map(|(x, y)| Point::new(4.3 + x * 1.7, 4.6 + y * 1.7));
+    let (gray, _) = render_module_grid(dimension, dst, width, height, dark);
+    // The resampler's own filter softens every edge a little further.
+    let gray: Vec<u8> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let (x0, x1) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            let tap = |xx: usize| gray[y * width + xx] as u32;
+            ((tap(x0) + 2 * tap(x) + tap(x1)) / 4) as u8
+        })
+        .collect();
+    // Fitted from the finders, a quarter pixel off.
+    let d = dimension as f32;
+    let src = [(0.0, 0.0), (d, 0.0), (0.0, d), (d, d)].map(|(x, y)| Point::new(x, y));
+    let fitted = dst.map(|p| Point::new(p.x + 0.25, p.y - 0.2));
+    let transform =
+        crate::utils::geometry::PerspectiveTransform::from_points(&src, &fitted).unwrap();
+
+    let errors = |mode: GridSampling| {
+        let _scope = scoped_grid_sampling(mode);
+        sampling_errors(&gray, width, height, &transform, dimension, dark)
+    };
+    let kernel = errors(GridSampling::Kernel);
+    let coverage = errors(GridSampling::Coverage);
+    assert!(
+        coverage * 2 < kernel,
+        "coverage {coverage} vs kernel {kernel}"
+    );
+}
+
+#[test]
+fn test_kernel_sampling_under_strong_perspective() {
+    // Far edge ~1.5 px per module, near edge ~7 px per module.
+    let dimension = 29;
+    let dark = |mx: usize, my: usize| (mx * 5 + my * 11 + mx * my) % 7 < 3;
+    let (width, height) = (240, 200);
+    let dst = [
+        Point::new(90.0, 10.0),
+        Point::new(134.0, 10.0),
+        Point::new(10.0, 190.0),
+        Point::new(214.0, 190.0),
     ];
+    let (gray, transform) = render_module_grid(dimension, dst, width, height, dark);
+    let errors = sampling_errors(&gray, width, height, &transform, dimension, dark);
+    // A fixed sub-pixel tap pitch gets 10 modules wrong here.
+    assert!(errors <= 6, "{errors} modules wrong");
+}
+
+#[test]
+fn test_grid_sample_cache_reuses_near_identical_transforms() {
+    let dimension = 21;
+    let dark = |mx: usize, my: usize| (mx * 3 + my * 7) % 4 < 2;
+    let (width, height) = (120, 120);
+    let corners = |shift: f32| {
+        [
+            Point::new(10.0 + shift, 12.0),
+            Point::new(100.0 + shift, 10.0),
+            Point::new(12.0 + shift, 104.0),
+            Point::new(102.0 + shift, 106.0),
+        ]
+    };
+    let (gray, transform) = render_module_grid(dimension, corners(0.0), width, height, dark);
+    let d = dimension as f32;
+    let src = [
+        Point::new(0.0, 0.0),
+        Point::new(d, 0.0),
+        Point::new(0.0, d),
+        Point::new(d, d),
+    ];
+    let nudged = |shift: f32| {
+        crate::utils::geometry::PerspectiveTransform::from_points(&src, &corners(shift)).unwrap()
+    };
+
+    let mut cache = geometry::GridSampleCache::default();
+    let ((), counters) = with_decode_counters(|| {
+        let first = cache.sample(&gray, width, height, &transform, dimension, 1.0);
+        let again = cache.sample(&gray, width, height, &nudged(0.01), dimension, 1.0);
+        assert_eq!(first.0.as_words(), again.0.as_words());
+        assert_eq!(first.1, again.1);
+    });
+    assert_eq!(counters.grid_sample_cache_hits, 1);
 
+    let ((), counters) = with_decode_counters(|| {
+        cache.sample(&gray, width, height, &nudged(0.5), dimension, 1.0);
+        cache.sample(&gray, width, height, &transform, dimension, 1.25);
+        let _scope = scoped_grid_sampling(GridSampling::AreaWeighted);
+        cache.sample(&gray, width, height, &transform, dimension, 1.0);
+    });
+    assert_eq!(counters.grid_sample_cache_hits, 0);
+}
+
+#[test]
+fn test_fixed_point_sampling_matches_float_on_golden_matrix() {
+    let dark = |mx: usize, my: usize| GOLDEN_V1_GRID[my][mx];
+    let cases = [
+        // ~6 px modules, mild perspective.
+        (
+            [
+                Point::new(20.0, 18.0),
+                Point::new(150.0, 26.0),
+                Point::new(16.0, 150.0),
+                Point::new(156.0, 160.0),
+            ],
+            (180, 180),
+        ),
+        // ~2.5 px modules on the far edge.
+        (
+            [
+                Point::new(60.0, 10.0),
+                Point::new(114.0, 12.0),
+                Point::new(10.0, 150.0),
+                Point::new(170.0, 154.0),
+            ],
+            (180, 170),
+        ),
+    ];
+    let fixed = crate::DetectorConfig {
+        fixed_point_sampling: true,
+        ..Default::default()
+    };
+    for (dst, (width, height)) in cases {
+        let (gray, transform) = render_module_grid(21, dst, width, height, dark);
+        let float_errors = sampling_errors(&gray, width, height, &transform, 21, dark);
+        let (fixed_errors, decoded) = fixed.apply(|| {
+            let (grid, conf) = QrDecoder::extract_qr_region_gray_with_transform_and_confidence(
+                &gray, width, height, &transform, 21,
+            );
+            (
+                sampling_errors(&gray, width, height, &transform, 21, dark),
+                QrDecoder::try_decode_from_matrix_with_confidence(&grid, 1, &conf),
+            )
+        });
+        assert!(
+            fixed_errors <= float_errors + 1,
+            "fixed {fixed_errors} vs float {float_errors} modules wrong"
+        );
+        assert_eq!(decoded.unwrap().content, "4376471154038");
+    }
+}
+
+#[test]
+fn test_non_square_pixels_decode_with_aspect_ratio() {
+    // Golden symbol with a 4-module quiet zone and 8-unit modules, captured
+    // by sensors whose pixels are `aspect` times wider than tall. None of
+    // these decode with square pixels assumed.
+    for aspect in [0.5f32, 0.75, 1.6, 2.0] {
+        let side = 29.0 * 8.0;
+        let (width, height) = ((side / aspect) as usize + 20, side as usize + 20);
+        let x = |px: f32| 10.0 + px / aspect;
+        let dst = [
+            Point::new(x(0.0), 10.0),
+            Point::new(x(side), 10.0),
+            Point::new(x(0.0), 10.0 + side),
+            Point::new(x(side), 10.0 + side),
+        ];
+        let (gray, _) = render_module_grid(29, dst, width, height, golden_v1_dark);
+        let config = crate::DetectorConfig {
+            pixel_aspect_ratio: Some(aspect),
+            ..Default::default()
+        };
+        let codes = config.apply(|| crate::detect_from_grayscale(&gray, width, height));
+        assert_eq!(codes.len(), 1, "aspect {aspect}");
+        assert_eq!(codes[0].content, "4376471154038");
+    }
+}
+
+#[test]
+fn test_decodes_report_the_symbol_outline() {
+    // Corners of the 29-module grid under perspective; the symbol spans
+    // modules 4..25 of it.
+    let dst = [
+        Point::new(30.0, 20.0),
+        Point::new(250.0, 30.0),
+        Point::new(20.0, 240.0),
+        Point::new(240.0, 250.0),
+    ];
+    let (gray, transform) = render_module_grid(29, dst, 280, 280, golden_v1_dark);
+    let codes = crate::detect_from_grayscale(&gray, 280, 280);
+    assert_eq!(codes.len(), 1);
+    let outline = [(4.0, 4.0), (25.0, 4.0), (25.0, 25.0), (4.0, 25.0)]
+        .map(|(x, y)| transform.transform(&Point::new(x, y)));
+    for (got, want) in codes[0].position.iter().zip(&outline) {
+        assert!(got.distance(want) < 2.0, "{:?}", codes[0].position);
+    }
+}
+
+#[test]
+fn test_with_corners_takes_the_sampling_transform() {
+    let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+    let qr = crate::detect(&rgb, width, height).remove(0);
+    let src = [(0.0, 0.0), (21.0, 0.0), (0.0, 21.0), (21.0, 21.0)].map(|(x, y)| Point::new(x, y));
+    let dst =
+        [(40.0, 10.0), (90.0, 20.0), (30.0, 60.0), (85.0, 75.0)].map(|(x, y)| Point::new(x, y));
+    let transform = crate::utils::geometry::PerspectiveTransform::from_points(&src, &dst).unwrap();
+    let qr = QrDecoder::with_corners(qr, &transform, 21);
+    // Top-left, top-right, bottom-right, bottom-left.
+    for (got, want) in qr.position.iter().zip([dst[0], dst[1], dst[3], dst[2]]) {
+        assert!(got.distance(&want) < 1e-3, "{:?}", qr.position);
+    }
+}
+
+#[test]
+fn test_reports_rotation_and_tilt() {
+    // (rotation, horizontal squash): the squash foreshortens the symbol as
+    // if turned acos(squash) away from the camera about its vertical axis
+    for (rotation, squash) in [(0.0f32, 1.0f32), (30.0, 1.0), (-100.0, 1.0), (15.0, 0.85)] {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let place = |u: f32, v: f32| {
+            let (x, y) = ((u - 0.5) * 240.0 * squash, (v - 0.5) * 240.0);
+            Point::new(160.0 + x * cos - y * sin, 160.0 + x * sin + y * cos)
+        };
+        let dst = [
+            place(0.0, 0.0),
+            place(1.0, 0.0),
+            place(0.0, 1.0),
+            place(1.0, 1.0),
+        ];
+        let (gray, _) = render_module_grid(29, dst, 320, 320, golden_v1_dark);
+        let codes = crate::detect_from_grayscale(&gray, 320, 320);
+        assert_eq!(codes.len(), 1, "rotation {rotation}");
+        let qr = &codes[0];
+        let tilt = squash.acos().to_degrees();
+        assert!(
+            (qr.rotation_deg - rotation).abs() < 1.0,
+            "rotation {rotation}: got {}",
+            qr.rotation_deg
+        );
+        assert!(
+            (qr.tilt_estimate - tilt).abs() < 5.0,
+            "tilt {tilt}: got {}",
+            qr.tilt_estimate
+        );
+    }
+}
+
+#[test]
+fn test_verify_payload_matches_bytes() {
+    let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+    assert!(crate::verify_payload(&rgb, width, height, b"4376471154038"));
+    assert!(!crate::verify_payload(
+        &rgb,
+        width,
+        height,
+        b"4376471154039"
+    ));
+    assert!(!crate::verify_payload(
+        &rgb[3..],
+        width,
+        height,
+        b"4376471154038"
+    ));
+}
+
+#[test]
+fn test_multi_exposure_recovers_code_lost_to_glare() {
+    let (width, height) = (194, 194);
+    let gray = golden_v1_gray(6.0, (10.0, 10.0), width, height);
+    // A long exposure blown out over the left half and a short one, darker
+    // overall, with a highlight over the right half; neither decodes alone.
+    let long: Vec<u8> = gray
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| if i % width < 97 { 251 + v / 64 } else { v })
+        .collect();
+    let short: Vec<u8> = gray
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| if i % width >= 97 { 251 + v / 64 } else { v / 2 })
+        .collect();
+    let frames = [
+        GrayFrame::new(&long, width, height).unwrap(),
+        GrayFrame::new(&short, width, height).unwrap(),
+    ];
+    let codes = crate::detect_multi_exposure(&frames).unwrap();
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+
+    let small = GrayFrame::new(&long[..width * 96], width, 96).unwrap();
+    assert!(crate::detect_multi_exposure(&[frames[0], small]).is_err());
+}
+
+#[test]
+fn test_decode_counter_scopes_nest_and_stay_per_thread() {
+    let bump = |n: usize| DECODE_COUNTERS.with(|c| c.borrow_mut().deskew_attempts += n);
+    let ((inner, other_thread), outer) = with_decode_counters(|| {
+        bump(2);
+        let ((), inner) = with_decode_counters(|| bump(3));
+        let other_thread = std::thread::spawn(move || with_decode_counters(|| bump(7)).1)
+            .join()
+            .unwrap();
+        (inner, other_thread)
+    });
+    assert_eq!(inner.deskew_attempts, 3);
+    assert_eq!(other_thread.deskew_attempts, 7);
+    assert_eq!(outer.deskew_attempts, 5);
+}
+
+#[test]
+fn test_payload_prior_rescues_failed_block() {
     let mut matrix = BitMatrix::new(21, 21);
     for y in 0..21 {
         for x in 0..21 {
-            matrix.set(x, y, grid[y][x]);
+            matrix.set(x, y, GOLDEN_V1_GRID[y][x]);
         }
     }
+    let keep = crate::DetectorConfig {
+        keep_intermediate_data: true,
+        ..Default::default()
+    };
+    let qr = keep
+        .apply(|| QrDecoder::decode_from_matrix(&matrix, 1))
+        .unwrap();
+    let mut codewords = qr.raw_codewords().unwrap().to_vec();
 
-    assert!(
-        orientation::has_finders_correct(&matrix),
-        "has_finders_correct should return true for the golden matrix"
-    );
+    // v1-L corrects 3 errors. Codewords 2-4 carry digit groups 1-3 of the
+    // payload; damaging them and one more leaves RS on its own with 4.
+    for i in [2, 3, 4, 10] {
+        codewords[i] ^= 0x5a;
+    }
+    let correct =
+        || payload::deinterleave_and_correct_with_confidence(&codewords, 1, ECLevel::L, None, None);
+    assert!(correct().is_err());
 
-    // A rotated version should NOT pass the check (finders in wrong positions)
-    let rotated = orientation::rotate90(&matrix);
+    let prior = crate::PayloadPrior::from_payloads(["4376471154781", "4376471154502"]);
+    let (data, assisted) = prior.apply(correct).unwrap();
+    assert!(assisted);
+    let (_, content, _) = payload::decode_payload(&data, 1).unwrap();
+    assert_eq!(content, "4376471154038");
+
+    // A prior from another batch pins the wrong values and cannot help.
+    let other = crate::PayloadPrior::from_payloads(["9999999999001", "9999999999002"]);
+    assert!(other.apply(correct).is_err());
+}
+
+#[test]
+fn test_detector_feeds_payload_prior() {
+    let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+
+    let prior = crate::PayloadPrior::from_payloads(["4376471154781"]);
+    let mut detector = crate::Detector::new().with_payload_prior(prior);
+    let codes = detector.detect(&rgb, width, height);
+    assert_eq!(codes.len(), 1);
+    assert!(!codes[0].assisted);
+    let prior = detector.payload_prior().unwrap();
+    assert_eq!(prior.observations(), 2);
+    assert_eq!(prior.shared_prefix(), b"4376471154");
+}
+
+#[test]
+fn test_decode_memo_skips_geometry_that_failed_reed_solomon() {
+    // The golden symbol with its bottom-right data area scrambled: format
+    // and finders read, error correction does not.
+    let (width, height) = (194, 194);
+    let gray = render_upright_grid(6.0, (10.0, 10.0), width, height, golden_v1_scrambled_dark);
+    let otsu = crate::utils::binarization::otsu_binarize(&gray, width, height);
+    let adaptive = crate::utils::binarization::adaptive_binarize(&gray, width, height, 31);
+    let decode = |binary: &BitMatrix| {
+        let patterns = crate::detect_finder_patterns(binary, width, height);
+        crate::pipeline::decode_groups(crate::frame::Planes::new(binary, &gray), &patterns)
+    };
+
+    let ((), counters) = with_decode_counters(|| {
+        crate::pipeline::with_decode_memo(|| {
+            assert!(decode(&otsu).is_empty());
+            assert!(decode(&adaptive).is_empty());
+        })
+    });
+    assert!(counters.decode_memo_hits > 0);
+
+    // Decodes set aside from the scope, as ROI crops are, or outside any
+    // scope, decode afresh.
+    let ((), counters) = with_decode_counters(|| {
+        crate::pipeline::with_decode_memo(|| {
+            assert!(decode(&otsu).is_empty());
+            crate::pipeline::without_decode_memo(|| assert!(decode(&adaptive).is_empty()));
+        })
+    });
+    assert_eq!(counters.decode_memo_hits, 0);
+    let ((), counters) = with_decode_counters(|| {
+        assert!(decode(&otsu).is_empty());
+        assert!(decode(&adaptive).is_empty());
+    });
+    assert_eq!(counters.decode_memo_hits, 0);
+}
+
+#[test]
+fn test_failed_grids_are_exported_when_enabled() {
+    // The scrambled symbol above: it samples cleanly and fails at RS.
+    let side = 194;
+    let gray = render_upright_grid(6.0, (10.0, 10.0), side, side, golden_v1_scrambled_dark);
+    let rgb = gray_to_rgb(&gray);
+
+    let config = crate::DetectorConfig {
+        export_failed_grids: true,
+        ..crate::DetectorConfig::default()
+    };
+    let (codes, tel) = config.apply(|| crate::detect_with_telemetry(&rgb, side, side));
+    assert!(codes.is_empty());
+    assert!(!tel.failed_grids.is_empty() && tel.failed_grids.len() <= 32);
+    for grid in &tel.failed_grids {
+        let dimension = 17 + 4 * grid.version as usize;
+        assert_eq!(grid.modules.width(), dimension);
+        assert_eq!(grid.confidence.len(), dimension * dimension);
+        assert!(matches!(
+            grid.failure,
+            DecodeFailure::ReedSolomon(_) | DecodeFailure::Payload
+        ));
+    }
+    // Blind bottom-right guesses are kept too, but one grid sits on the
+    // symbol, four quiet-zone modules in.
+    let expected = [(34.0, 34.0), (160.0, 34.0), (160.0, 160.0), (34.0, 160.0)];
     assert!(
-        !orientation::has_finders_correct(&rotated),
-        "has_finders_correct should return false for a 90° rotated matrix"
+        tel.failed_grids.iter().any(|grid| {
+            grid.version == 1
+                && grid
+                    .corners
+                    .iter()
+                    .zip(expected)
+                    .all(|(c, (x, y))| (c.x - x).abs() < 3.0 && (c.y - y).abs() < 3.0)
+        }),
+        "{:?}",
+        tel.failed_grids
+            .iter()
+            .map(|g| g.corners)
+            .collect::<Vec<_>>()
     );
 }
 
 #[test]
-fn test_golden_matrix_verify_ec_and_version() {
-    // Test that we correctly extract EC level and version from the golden matrix
-    let grid: [[bool; 21]; 21] = [
-        [
-            true, true, true, true, true, true, true, false, false, false, false, false, true,
-            false, true, true, true, true, true, true, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, false, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, true, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, false, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, true,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, true, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, false, true, false, true, false,
-            true, true, true, true, true, true, true,
-        ],
-        [
-            false, false, false, false, false, false, false, false, false, true, false, false,
-            false, false, false, false, false, false, false, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, false, true, true, true, true, true, true,
-            false, true, false, false, false, false, false,
-        ],
-        [
-            true, true, true, false, true, false, false, true, true, false, false, true, false,
-            true, false, true, false, true, true, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, true, true, false, true, true, false,
-            false, true, true, true, false, false, false, true,
-        ],
-        [
-            false, false, true, false, true, false, false, true, false, false, false, false, true,
-            true, true, true, true, false, false, false, false,
-        ],
-        [
-            false, false, true, false, false, false, true, true, false, true, false, true, false,
-            true, true, true, false, true, true, false, false,
-        ],
-        [
-            false, false, false, false, false, false, false, false, true, false, true, false,
-            false, true, true, true, true, false, true, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, false, false, true, true, true, false,
-            true, false, true, true, true, true, false,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, false, false, false,
-            false, true, true, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, false, true,
-            true, true, false, false, true, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, true, false, true, false, false,
-            true, true, true, true, false, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, false,
-            true, true, true, false, true, false, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, true, true, true,
-            false, false, true, true, false, false, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, true, true, false, false, true,
-            false, true, true, true, false, false, false,
-        ],
+fn test_reentering_geometry_trips_pathology_guard() {
+    // Same scrambled symbol: every pass re-enters the same failing geometry.
+    let (width, height) = (194, 194);
+    let gray = render_upright_grid(6.0, (10.0, 10.0), width, height, golden_v1_scrambled_dark);
+    let otsu = crate::utils::binarization::otsu_binarize(&gray, width, height);
+    let patterns = crate::detect_finder_patterns(&otsu, width, height);
+    let passes = |count: usize| {
+        with_decode_counters(|| {
+            crate::pipeline::with_decode_memo(|| {
+                for _ in 0..count {
+                    crate::pipeline::decode_groups(
+                        crate::frame::Planes::new(&otsu, &gray),
+                        &patterns,
+                    );
+                }
+            })
+        })
+        .1
+    };
+
+    // A handful of binarizations is ordinary; a retry loop is not.
+    assert_eq!(passes(4).pathology_guard_trips, 0);
+    let looping = passes(crate::decoder::config::pathology_max_reentries() + 1);
+    assert!(looping.pathology_guard_trips > 0);
+
+    let (_, tel) = crate::detect_with_telemetry(&gray_to_rgb(&gray), width, height);
+    assert!(!tel.pathology_guard_tripped);
+}
+
+#[test]
+fn test_candidates_sample_from_region_binarization() {
+    let (width, height) = (194, 194);
+    let gray = golden_v1_gray(6.0, (10.0, 10.0), width, height);
+    let binary = crate::utils::binarization::otsu_binarize(&gray, width, height);
+    let patterns = crate::detect_finder_patterns(&binary, width, height);
+    assert_eq!(patterns.len(), 3);
+
+    // A frame-wide binary of pure noise: decoding reads the candidate's
+    // own re-binarized region instead.
+    let mut blank = BitMatrix::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            blank.set(x, y, (x / 3 + y / 5) % 2 == 0);
+        }
+    }
+    let codes = crate::pipeline::decode_groups(crate::frame::Planes::new(&blank, &gray), &patterns);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+    // Corners come back in frame coordinates, not the region's.
+    let direct =
+        crate::pipeline::decode_groups(crate::frame::Planes::new(&binary, &gray), &patterns);
+    // Outside a detection call nothing names the matrix, and the
+    // candidate's own region binarization doesn't either.
+    assert_eq!(codes[0].binarization, None);
+    assert_eq!(direct[0].binarization, None);
+    for (a, b) in codes[0].position.iter().zip(&direct[0].position) {
+        assert!(
+            (a.x - b.x).abs() < 2.0 && (a.y - b.y).abs() < 2.0,
+            "{a:?} vs {b:?}"
+        );
+    }
+}
+
+#[test]
+fn test_reports_orientation_of_turned_codes() {
+    let (width, height) = (194, 194);
+    for orientation in [
+        Orientation::Rot0,
+        Orientation::Rot90,
+        Orientation::Rot180,
+        Orientation::Rot270,
+    ] {
+        // Grid corners top-left, top-right, bottom-left, bottom-right.
+        let dst = [(0.0, 0.0), (29.0, 0.0), (0.0, 29.0), (29.0, 29.0)].map(|(x, y)| {
+            let p = orientation.apply(Point::new(x, y), 29.0);
+            Point::new(10.0 + p.x * 6.0, 10.0 + p.y * 6.0)
+        });
+        let (gray, _) = render_module_grid(29, dst, width, height, golden_v1_dark);
+        let rgb = gray_to_rgb(&gray);
+        let codes = crate::detect(&rgb, width, height);
+        assert_eq!(codes.len(), 1, "{orientation:?}");
+        assert_eq!(codes[0].content, "4376471154038");
+        assert_eq!(codes[0].orientation, orientation);
+        // The symbol's top-left corner, 4 quiet-zone modules in.
+        let tl = orientation.apply(Point::new(4.0, 4.0), 29.0);
+        let (x, y) = (10.0 + tl.x * 6.0, 10.0 + tl.y * 6.0);
+        let got = codes[0].position[0];
+        assert!(
+            (got.x - x).abs() < 4.0 && (got.y - y).abs() < 4.0,
+            "{orientation:?}: {got:?}"
+        );
+    }
+}
+
+#[test]
+fn test_telemetry_reports_decoded_finder_quality() {
+    let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+    let (codes, tel) = crate::detect_with_telemetry(&rgb, width, height);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(tel.decoded_finders.len(), 1, "{:?}", tel.decoded_finders);
+    // Finder centres sit 7.5 modules from the grid corner: (4 + 3.5) * 6 + 10.
+    let expected = [(55.0, 55.0), (139.0, 55.0), (55.0, 139.0)];
+    for (f, (x, y)) in tel.decoded_finders[0].iter().zip(expected) {
+        assert!(
+            (f.center.x - x).abs() < 2.0 && (f.center.y - y).abs() < 2.0,
+            "{f:?}"
+        );
+        assert!(f.quality > 0.5, "{f:?}");
+    }
+}
+
+#[test]
+fn test_decodes_report_their_binarization() {
+    let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+
+    let codes = crate::detect(&rgb, width, height);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].binarization, Some(Binarization::Otsu));
+
+    let (codes, tel) = crate::detect_with_telemetry(&rgb, width, height);
+    assert_eq!(codes.len(), 1);
+    let mut expected = [0; Binarization::ALL.len()];
+    expected[codes[0].binarization.unwrap().index()] = 1;
+    assert_eq!(tel.binarization_decodes, expected);
+}
+
+#[test]
+fn test_min_module_size_scan_still_decodes() {
+    let (rgb, width, height) = golden_v1_rgb(10.0, (5.0, 5.0), 300, 300);
+    let config = crate::DetectorConfig {
+        min_module_size: Some(8.0),
+        ..Default::default()
+    };
+    let codes = crate::detect_with_config(&rgb, width, height, &config);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+}
+
+#[test]
+fn test_inverted_finders_decode_when_enabled() {
+    // Etched finders: each finder block and its separator read light where
+    // the symbol is dark and vice versa; format and data read normally.
+    let dark = |mx: usize, my: usize| {
+        if !(4..25).contains(&mx) || !(4..25).contains(&my) {
+            return false;
+        }
+        let (x, y) = (mx - 4, my - 4);
+        let in_finder = !(8..13).contains(&x) && y < 8 || x < 8 && y >= 13;
+        GOLDEN_V1_GRID[y][x] ^ in_finder
+    };
+    let (width, height) = (300, 300);
+    let gray = render_upright_grid(10.0, (5.0, 5.0), width, height, dark);
+    let rgb = gray_to_rgb(&gray);
+
+    assert!(crate::detect(&rgb, width, height).is_empty());
+
+    let config = crate::DetectorConfig {
+        inverted_finders: true,
+        ..Default::default()
+    };
+    let binary = crate::utils::binarization::otsu_binarize(&gray, width, height);
+    let patterns = config.apply(|| crate::detect_finder_patterns(&binary, width, height));
+    assert!(patterns.iter().filter(|p| p.inverted).count() >= 3);
+    let codes = crate::detect_with_config(&rgb, width, height, &config);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+}
+
+#[test]
+fn test_pooled_telemetry_matches_unpooled() {
+    let frame = |module: f32, side: usize| {
+        let (rgb, side, _) = golden_v1_rgb(module, (20.0, 20.0), side, side);
+        (rgb, side)
+    };
+    let summary = |(codes, tel): (Vec<crate::QRCode>, crate::DetectionTelemetry)| {
+        let contents: Vec<String> = codes.into_iter().map(|qr| qr.content).collect();
+        (
+            contents,
+            tel.qr_codes_found,
+            tel.finder_patterns_found,
+            tel.bin_policy_attempts,
+            tel.binarization_decodes,
+        )
+    };
+
+    // A larger frame first, so the second reuses a dirty, oversized buffer.
+    let (big, small) = (frame(8.0, 290), frame(6.0, 214));
+    let mut pool = crate::utils::memory_pool::BufferPool::new();
+    for (rgb, side) in [&big, &small] {
+        let pooled = summary(crate::detect_with_pool_and_telemetry(
+            rgb, *side, *side, &mut pool,
+        ));
+        assert_eq!(pooled.0, ["4376471154038"]);
+        assert_eq!(
+            pooled,
+            summary(crate::detect_with_telemetry(rgb, *side, *side))
+        );
+    }
+}
+
+#[test]
+fn test_physical_scale_sizes_decoded_symbol() {
+    let (rgb, width, height) = golden_v1_rgb(10.0, (5.0, 5.0), 300, 300);
+    assert_eq!(crate::detect(&rgb, width, height)[0].physical_size_mm, None);
+
+    // 254 dpi puts 10px modules at 1mm.
+    let config = crate::DetectorConfig {
+        physical_scale: Some(crate::PhysicalScale::Dpi { x: 254.0, y: 254.0 }),
+        ..Default::default()
+    };
+    let codes = crate::detect_with_config(&rgb, width, height, &config);
+    assert_eq!(codes.len(), 1);
+    let size = codes[0].physical_size_mm.unwrap();
+    assert!((size - 21.0).abs() < 0.5, "{size}");
+    assert!((codes[0].module_size_mm().unwrap() - 1.0).abs() < 0.03);
+}
+
+#[test]
+fn test_two_finder_fallback_guesses_any_missing_corner() {
+    // Smudge one finder's core at a time so it no longer passes the 1:1:3:1:1
+    // scan: with the top-left gone the surviving pair is the symbol's
+    // diagonal, otherwise it is one of its sides.
+    for (fx, fy) in [(0usize, 0usize), (14, 0), (0, 14)] {
+        let dark = |mx: usize, my: usize| {
+            let (gx, gy) = (mx.wrapping_sub(4), my.wrapping_sub(4));
+            if (fx + 2..fx + 5).contains(&gx) && (fy + 2..fy + 5).contains(&gy) {
+                return false;
+            }
+            gx < 21 && gy < 21 && GOLDEN_V1_GRID[gy][gx]
+        };
+        let gray = render_upright_grid(8.0, (20.0, 20.0), 272, 272, dark);
+        let binary = crate::utils::binarization::otsu_binarize(&gray, 272, 272);
+        let patterns = crate::detect_finder_patterns(&binary, 272, 272);
+        assert_eq!(patterns.len(), 2, "finder ({fx}, {fy})");
+        let codes =
+            crate::decode_two_finder_fallback(crate::frame::Planes::new(&binary, &gray), &patterns);
+        assert_eq!(codes.len(), 1, "finder ({fx}, {fy})");
+        assert_eq!(codes[0].content, "4376471154038");
+
+        let ablated = crate::DetectorConfig {
+            ablations: vec![crate::Ablation::TwoFinder],
+            ..Default::default()
+        };
+        let codes = ablated.apply(|| {
+            crate::decode_two_finder_fallback(crate::frame::Planes::new(&binary, &gray), &patterns)
+        });
+        assert!(
+            codes.is_empty(),
+            "finder ({fx}, {fy}) with the fallback off"
+        );
+    }
+}
+
+#[test]
+fn test_scan_metadata_only_lists_each_symbol() {
+    // Two copies side by side, the right one upside down.
+    let (width, height) = (380, 194);
+    let right = [
+        Point::new(370.0, 184.0),
+        Point::new(196.0, 184.0),
+        Point::new(370.0, 10.0),
+        Point::new(196.0, 10.0),
+    ];
+    let a = golden_v1_gray(6.0, (10.0, 10.0), width, height);
+    let (b, _) = render_module_grid(29, right, width, height, golden_v1_dark);
+    let rgb: Vec<u8> = a
+        .iter()
+        .zip(&b)
+        .flat_map(|(&x, &y)| [x.min(y); 3])
+        .collect();
+
+    let mut symbols = crate::scan_metadata_only(&rgb, width, height);
+    assert_eq!(symbols.len(), 2);
+    symbols.sort_by(|p, q| p.position[0].x.total_cmp(&q.position[0].x));
+    for symbol in &symbols {
+        // The golden grid's format bits read as H/mask 0 (see
+        // test_decode_session_exposes_each_stage); only the format is read.
+        assert_eq!(symbol.version, Version::Model2(1));
+        assert_eq!(
+            (symbol.ec_level, symbol.mask_pattern),
+            (ECLevel::H, MaskPattern::Pattern0)
+        );
+    }
+    assert_eq!(symbols[0].orientation, Orientation::Rot0);
+    assert_eq!(symbols[1].orientation, Orientation::Rot180);
+    // Each symbol's own top-left corner, inside a 4-module quiet zone.
+    assert!(symbols[0].position[0].distance(&Point::new(34.0, 34.0)) < 3.0);
+    assert!(symbols[1].position[0].distance(&Point::new(346.0, 160.0)) < 3.0);
+
+    assert!(crate::scan_metadata_only(&rgb[3..], width, height).is_empty());
+}
+
+#[test]
+fn test_lens_model_reads_barrel_distorted_code() {
+    let lens = crate::LensModel {
+        fx: 260.0,
+        fy: 260.0,
+        cx: 200.0,
+        cy: 200.0,
+        k1: -0.3,
+        k2: 0.05,
+        p1: 0.0,
+        p2: 0.0,
+    };
+    // A flat grid seen through the lens, off-centre where it bends most.
+    let (width, height) = (400, 400);
+    let d = 29.0;
+    let ideal = [
+        Point::new(40.0, 30.0),
+        Point::new(240.0, 30.0),
+        Point::new(40.0, 230.0),
+        Point::new(240.0, 230.0),
+    ];
+    let grid = [
+        Point::new(0.0, 0.0),
+        Point::new(d, 0.0),
+        Point::new(0.0, d),
+        Point::new(d, d),
+    ];
+    let gray = render_through_lens(&lens, ideal, width, height, (0, 0, width, height));
+
+    // The grid's image corners: fitted through the lens, every module is
+    // sampled where the lens put it; as a plain homography the bow costs
+    // modules.
+    let corners = ideal.map(|p| lens.distort(p));
+    let plain = crate::utils::geometry::PerspectiveTransform::from_points(&grid, &corners).unwrap();
+    let bent =
+        crate::utils::geometry::PerspectiveTransform::through_lens(&grid, &corners, Some(lens))
+            .unwrap();
+    assert_eq!(
+        sampling_errors(&gray, width, height, &bent, 29, golden_v1_dark),
+        0
+    );
+    assert!(sampling_errors(&gray, width, height, &plain, 29, golden_v1_dark) > 0);
+
+    let rgb = gray_to_rgb(&gray);
+    let config = crate::DetectorConfig {
+        lens: Some(lens),
+        ..Default::default()
+    };
+    let codes = crate::detect_with_config(&rgb, width, height, &config);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+    let symbol_tl = lens.distort(Point::new(40.0 + 4.0 * 200.0 / d, 30.0 + 4.0 * 200.0 / d));
+    assert!(
+        codes[0].position[0].distance(&symbol_tl) < 3.0,
+        "{:?}",
+        codes[0].position
+    );
+}
+
+/// The golden symbol and its quiet zone, a flat 29-module grid whose
+/// corners (TL, TR, BL, BR) sit at `ideal` before the lens bends them, seen
+/// through `lens` with 4x4 supersampling. Only pixels inside `window`
+/// (x0, y0, x1, y1) are rendered; the rest stay background.
+pub(crate) fn render_through_lens(
+    lens: &crate::LensModel,
+    ideal: [Point; 4],
+    width: usize,
+    height: usize,
+    window: (usize, usize, usize, usize),
+) -> Vec<u8> {
+    let d = 29.0;
+    let grid = [
+        Point::new(0.0, 0.0),
+        Point::new(d, 0.0),
+        Point::new(0.0, d),
+        Point::new(d, d),
     ];
+    let to_grid = crate::utils::geometry::PerspectiveTransform::from_points(&ideal, &grid).unwrap();
+    let mut gray = vec![230u8; width * height];
+    for py in window.1..window.3 {
+        for px in window.0..window.2 {
+            let mut dark_hits = 0;
+            for sy in 0..4 {
+                for sx in 0..4 {
+                    let p = Point::new(
+                        px as f32 + (sx as f32 + 0.5) / 4.0,
+                        py as f32 + (sy as f32 + 0.5) / 4.0,
+                    );
+                    let m = to_grid.transform(&lens.undistort(p));
+                    if (0.0..d).contains(&m.x)
+                        && (0.0..d).contains(&m.y)
+                        && golden_v1_dark(m.x as usize, m.y as usize)
+                    {
+                        dark_hits += 1;
+                    }
+                }
+            }
+            gray[py * width + px] = 230 - (dark_hits * 200 / 16) as u8;
+        }
+    }
+    gray
+}
+
+/// The golden symbol redrawn as an encoder with `quirk` would draw it, its
+/// format information (L, mask 7) in the bit order the reader tries first.
+fn golden_with_quirk(quirk: Option<crate::EncoderQuirk>) -> BitMatrix {
+    let format_mask = if quirk == Some(crate::EncoderQuirk::UnmaskedFormat) {
+        0
+    } else {
+        0x5412
+    };
+    golden_with_format_mask(quirk, format_mask)
+}
 
+/// [`golden_with_quirk`] with the format word XORed with `format_mask`.
+fn golden_with_format_mask(quirk: Option<crate::EncoderQuirk>, format_mask: u16) -> BitMatrix {
+    use crate::EncoderQuirk;
+    use crate::decoder::bitstream::BitstreamExtractor;
+    use crate::decoder::function_mask::FunctionMask;
+
+    let mask = MaskPattern::Pattern7;
     let mut matrix = BitMatrix::new(21, 21);
     for y in 0..21 {
         for x in 0..21 {
-            matrix.set(x, y, grid[y][x]);
+            matrix.set(x, y, GOLDEN_V1_GRID[y][x] ^ mask.is_masked(y, x));
         }
     }
+    let bits =
+        BitstreamExtractor::extract_with_options(&matrix, 21, &FunctionMask::new(1), true, false);
+    let func = if quirk == Some(EncoderQuirk::MissingDarkModule) {
+        FunctionMask::without_dark_module(1)
+    } else {
+        FunctionMask::new(1)
+    };
 
-    let result = QrDecoder::decode_from_matrix(&matrix, 1);
-    assert!(result.is_some(), "Failed to decode golden QR matrix");
-    let qr = result.unwrap();
-
-    // Verify content
-    assert_eq!(qr.content, "4376471154038", "Content mismatch");
+    // Zigzag placement, two columns at a time from the right; the
+    // nonstandard traversal takes the left-hand module of each pair first.
+    let swap = quirk == Some(EncoderQuirk::NonstandardTraversal);
+    for y in 0..21 {
+        for x in 0..21 {
+            matrix.set(x, y, GOLDEN_V1_GRID[y][x]);
+        }
+    }
+    let mut next = bits.into_iter();
+    let mut right = 20;
+    loop {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vert in 0..21 {
+            let y = if upward { 20 - vert } else { vert };
+            let pair = if swap {
+                [right - 1, right]
+            } else {
+                [right, right - 1]
+            };
+            for x in pair {
+                if !func.is_function(x, y) {
+                    let bit = next.next().unwrap_or(false);
+                    matrix.set(x, y, bit ^ mask.is_masked(y, x));
+                }
+            }
+        }
+        if right < 2 {
+            break;
+        }
+        right -= 2;
+    }
 
-    // Verify metadata
-    assert_eq!(qr.version, Version::Model2(1), "Version should be 1");
-    // Note: The golden matrix uses EC level L (as determined by the decoder)
-    assert_eq!(qr.error_correction, ECLevel::L, "EC level should be L");
+    let data = (0b01 << 3) | 7;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    let word = ((data << 10) | rem) ^ format_mask;
+    let top_left = [
+        (8, 0),
+        (8, 1),
+        (8, 2),
+        (8, 3),
+        (8, 4),
+        (8, 5),
+        (8, 7),
+        (8, 8),
+        (7, 8),
+        (5, 8),
+        (4, 8),
+        (3, 8),
+        (2, 8),
+        (1, 8),
+        (0, 8),
+    ];
+    let others = (0..8).map(|j| (20 - j, 8)).chain((14..21).map(|y| (8, y)));
+    for positions in [top_left.to_vec(), others.collect()] {
+        for (j, (x, y)) in positions.into_iter().enumerate() {
+            let masked = quirk == Some(EncoderQuirk::MaskedFormatArea) && mask.is_masked(y, x);
+            matrix.set(x, y, ((word >> (14 - j)) & 1 == 1) ^ masked);
+        }
+    }
+    matrix
 }
 
 #[test]
-fn test_decode_numeric_mode() {
-    // Test that numeric mode decoding works by testing the payload decoder
-    // Simpler test that doesn't require exact encoding knowledge
-    let mut bits = Vec::new();
-    push_bits(&mut bits, 0b0001, 4); // Numeric mode
-    push_bits(&mut bits, 3, 10); // Count: 3 digits for version 1
+fn test_encoder_quirk_rules_rescue_and_report() {
+    use crate::EncoderQuirk;
 
-    // Encode "123" in numeric mode: single group of 3 digits
-    push_bits(&mut bits, 123, 10); // 123 in 10 bits
+    let strict = crate::DetectorConfig::default();
+    let lenient = crate::DetectorConfig {
+        encoder_quirks: EncoderQuirk::ALL.to_vec(),
+        ..Default::default()
+    };
+    let decode = |config: &crate::DetectorConfig, matrix: &BitMatrix| {
+        with_decode_counters(|| config.apply(|| QrDecoder::decode_from_matrix(matrix, 1)))
+    };
 
-    push_bits(&mut bits, 0, 4); // Terminator
+    let (qr, counters) = decode(&strict, &golden_with_quirk(None));
+    assert_eq!(qr.unwrap().content, "4376471154038");
+    assert_eq!(counters.quirk_rescues, [0; EncoderQuirk::ALL.len()]);
 
-    // Pad to byte boundary
-    while bits.len() % 8 != 0 {
-        bits.push(false);
+    for quirk in [
+        EncoderQuirk::UnmaskedFormat,
+        EncoderQuirk::MaskedFormatArea,
+        EncoderQuirk::MissingDarkModule,
+        EncoderQuirk::NonstandardTraversal,
+    ] {
+        let matrix = golden_with_quirk(Some(quirk));
+        let (qr, counters) = decode(&lenient, &matrix);
+        assert_eq!(qr.unwrap().content, "4376471154038", "{quirk:?}");
+        let mut expected = [0; EncoderQuirk::ALL.len()];
+        expected[quirk.index()] = 1;
+        assert_eq!(counters.quirk_rescues, expected, "{quirk:?}");
+
+        // Strict reads never credit a rule, and only the listed rules run.
+        let (_, counters) = decode(&strict, &matrix);
+        assert_eq!(counters.quirk_rescues, [0; EncoderQuirk::ALL.len()]);
+        let others = crate::DetectorConfig {
+            encoder_quirks: EncoderQuirk::ALL
+                .into_iter()
+                .filter(|&q| q != quirk)
+                .collect(),
+            ..Default::default()
+        };
+        let (_, counters) = decode(&others, &matrix);
+        assert_eq!(counters.quirk_rescues[quirk.index()], 0);
     }
+}
 
-    let codewords = payload::bits_to_codewords(&bits);
-    let result = payload::decode_payload(&codewords, 1);
+#[test]
+fn test_reversed_format_mask_is_read_and_tagged() {
+    // The mask applied least significant bit first: L, mask 7 reads back
+    // strictly as H, mask 3.
+    let matrix = golden_with_format_mask(None, 0x2415);
+    let strict = crate::decoder::format::FormatInfo::try_extract(&matrix).unwrap();
+    assert_eq!(
+        (strict.ec_level, strict.mask_pattern),
+        (ECLevel::H, MaskPattern::Pattern3)
+    );
 
-    // This test verifies the numeric decoder works
-    assert!(result.is_some(), "Numeric mode decode should succeed");
-    if let Some((data, content)) = result {
-        assert_eq!(content, "123");
-        assert_eq!(data, b"123");
-    }
+    let (qr, counters) = with_decode_counters(|| QrDecoder::decode_from_matrix(&matrix, 1));
+    let qr = qr.unwrap();
+    assert_eq!(qr.content, "4376471154038");
+    assert_eq!(qr.error_correction, ECLevel::L);
+    assert_eq!(qr.format_quirk, Some(crate::FormatQuirk::WrongFormatMask));
+    assert_eq!(counters.wrong_format_mask_rescues, 1);
+    // Read outright, so the brute force never ran.
+    assert_eq!(counters.header_prefilter_skips, 0);
+
+    let (qr, counters) =
+        with_decode_counters(|| QrDecoder::decode_from_matrix(&golden_with_quirk(None), 1));
+    assert_eq!(qr.unwrap().format_quirk, None);
+    assert_eq!(counters.wrong_format_mask_rescues, 0);
 }
 
+#[cfg(feature = "tools")]
 #[test]
-fn test_decode_alphanumeric_mode() {
-    // Test that alphanumeric mode decoding works
-    let mut bits = Vec::new();
-    push_bits(&mut bits, 0b0010, 4); // Alphanumeric mode
-    push_bits(&mut bits, 2, 9); // Count: 2 characters for version 1
+fn test_rotated_format_placement_is_read_and_reported() {
+    // Level H, so the data modules the misplaced format lands on are repaired.
+    let upright = crate::tools::encode::encode(
+        "31415926535897932384",
+        SegmentMode::Numeric,
+        2,
+        ECLevel::H,
+        MaskPattern::Pattern7,
+    )
+    .unwrap();
+    let n = upright.width();
+    let top_left = (0..9)
+        .filter(|&i| i != 6)
+        .map(|y| (8, y))
+        .chain((0..8).filter(|&i| i != 6).map(|x| (x, 8)));
+    let others = (0..8)
+        .map(|j| (n - 1 - j, 8))
+        .chain((n - 7..n).map(|y| (8, y)));
+    let format: Vec<(usize, usize)> = top_left.chain(others).collect();
+    let turn = |matrix: &BitMatrix, quarter_turns: usize| {
+        (0..quarter_turns).fold(matrix.clone(), |m, _| orientation::rotate90(&m))
+    };
 
-    // Encode "AB" in alphanumeric mode
-    // A=10, B=11 in alphanumeric table
-    // Pair: AB = 10*45 + 11 = 461
-    push_bits(&mut bits, 461, 11); // 2 chars = 11 bits
+    // The generator fills the upright format modules with `fill`, then
+    // writes the format where a symbol turned `quarter_turns` clockwise
+    // keeps it, over whatever was there.
+    let misplaced = |quarter_turns: usize, fill: u16| {
+        let mut matrix = upright.clone();
+        for (j, &(x, y)) in format.iter().enumerate() {
+            matrix.set(x, y, (fill >> (j % 15)) & 1 == 1);
+        }
+        let mut turned = turn(&matrix, quarter_turns);
+        for &(x, y) in &format {
+            turned.set(x, y, upright.get(x, y));
+        }
+        turn(&turned, 4 - quarter_turns)
+    };
 
-    push_bits(&mut bits, 0, 4); // Terminator
+    for quarter_turns in 1..=3 {
+        // Some turns land on the upright copies; pick a fill neither reads.
+        let matrix = (0u16..)
+            .map(|fill| misplaced(quarter_turns, fill))
+            .find(|m| crate::decoder::format::FormatInfo::try_extract(m).is_err())
+            .unwrap();
 
-    // Pad to byte boundary
-    while bits.len() % 8 != 0 {
-        bits.push(false);
+        let (qr, counters) = with_decode_counters(|| QrDecoder::decode_from_matrix(&matrix, 2));
+        assert_eq!(qr.unwrap().content, "31415926535897932384");
+        let mut expected = [0; 3];
+        expected[quarter_turns - 1] = 1;
+        assert_eq!(counters.rotated_format_rescues, expected);
+        // Read outright, so the brute force never ran.
+        assert_eq!(counters.header_prefilter_skips, 0);
     }
+}
 
-    let codewords = payload::bits_to_codewords(&bits);
-    let result = payload::decode_payload(&codewords, 1);
+#[test]
+fn test_narrow_quiet_zone_rule_reads_flush_code() {
+    use crate::EncoderQuirk;
 
-    assert!(result.is_some(), "Alphanumeric mode decode should succeed");
-    if let Some((data, content)) = result {
-        assert_eq!(content, "AB");
-        assert_eq!(data, b"AB");
+    // Dark artwork right up against the top and left edges of the symbol.
+    let dark = |mx: usize, my: usize| mx < 4 || my < 4 || golden_v1_dark(mx, my);
+    let (width, height) = (174, 174);
+    let rgb = gray_to_rgb(&render_upright_grid(6.0, (0.0, 0.0), width, height, dark));
+    assert!(crate::detect(&rgb, width, height).is_empty());
+
+    let lenient = crate::DetectorConfig {
+        encoder_quirks: vec![EncoderQuirk::NarrowQuietZone],
+        ..Default::default()
+    };
+    let (codes, tel) = lenient.apply(|| crate::detect_with_telemetry(&rgb, width, height));
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+    assert_eq!(tel.quirk_rescues, [0, 0, 0, 1, 0]);
+}
+
+#[test]
+fn test_fixed_pattern_damage_reports_smudged_separators() {
+    let mut matrix = golden_with_quirk(None);
+    let qr = QrDecoder::decode_from_matrix(&matrix, 1).unwrap();
+    assert_eq!(qr.fixed_pattern_damage, 0.0);
+
+    // Ink bleeding into the top-left separator costs nothing in the data.
+    for i in 0..4 {
+        matrix.set(7, i, true);
     }
+    let qr = QrDecoder::decode_from_matrix(&matrix, 1).unwrap();
+    assert_eq!(qr.content, "4376471154038");
+    assert!((qr.fixed_pattern_damage - 4.0 / 54.0).abs() < 1e-6);
 }
 
 #[test]
-fn test_decode_mixed_modes() {
-    // Test a QR code with multiple encoding modes in sequence
-    let mut bits = Vec::new();
+fn test_version_candidates_rank_by_structure() {
+    let gray = golden_v1_gray(6.0, (0.0, 0.0), 174, 174);
+    let mut binary = BitMatrix::new(174, 174);
+    for y in 0..174 {
+        for x in 0..174 {
+            binary.set(x, y, gray[y * 174 + x] < 128);
+        }
+    }
+    // Finder centres 3.5 modules in from the v1 symbol's corners.
+    let at = |mx: f32, my: f32| Point::new((4.0 + mx) * 6.0, (4.0 + my) * 6.0);
+    let (tl, tr, bl) = (at(3.5, 3.5), at(17.5, 3.5), at(3.5, 17.5));
+    let br = QrDecoder::calculate_bottom_right(&tl, &tr, &bl).unwrap();
+    let ranked = QrDecoder::rank_versions(vec![3, 2, 1], |v| {
+        let dimension = 17 + 4 * v as usize;
+        let transform = QrDecoder::build_transform(&tl, &tr, &bl, &br, dimension)?;
+        Some(QrDecoder::extract_qr_region_with_transform(
+            &binary, &transform, dimension,
+        ))
+    });
+    assert_eq!(ranked[0], 1);
+}
 
-    // First segment: Numeric "123"
-    push_bits(&mut bits, 0b0001, 4); // Numeric mode
-    push_bits(&mut bits, 3, 10); // Count: 3 digits
-    push_bits(&mut bits, 123, 10); // 3 digits
+#[test]
+fn test_stage_pipeline_accepts_a_custom_localizer() {
+    use crate::stages::{Candidate, Pipeline, SampledGrid, StageImage};
 
-    // Second segment: Byte "ABC"
-    push_bits(&mut bits, 0b0100, 4); // Byte mode
-    push_bits(&mut bits, 3, 8); // Count: 3 bytes
-    push_bits(&mut bits, b'A' as u32, 8);
-    push_bits(&mut bits, b'B' as u32, 8);
-    push_bits(&mut bits, b'C' as u32, 8);
+    let dst = [
+        Point::new(30.0, 20.0),
+        Point::new(250.0, 30.0),
+        Point::new(20.0, 240.0),
+        Point::new(240.0, 250.0),
+    ];
+    let (gray, transform) = render_module_grid(29, dst, 280, 280, golden_v1_dark);
+    let frame = || GrayFrame::new(&gray, 280, 280).unwrap();
 
-    push_bits(&mut bits, 0, 4); // Terminator
+    let default = Pipeline::new();
+    assert!(default.is_default());
+    let expected = crate::detect_from_grayscale(&gray, 280, 280);
+    let codes = default.run(frame());
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, expected[0].content);
 
-    let codewords = payload::bits_to_codewords(&bits);
-    let (data, content) = payload::decode_payload(&codewords, 1).unwrap();
-    assert_eq!(content, "123ABC");
-    assert_eq!(data, b"123ABC");
+    // Composed stage by stage, the defaults read it too.
+    let keep = |_: &StageImage<'_>, candidates: Vec<Candidate>| candidates;
+    let codes = Pipeline::new().with_ranker(keep).run(frame());
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+
+    // A localizer that already knows where the finders are, feeding the
+    // crate's own sampler and decoder.
+    let finder = move |x: f32, y: f32| transform.transform(&Point::new(x, y));
+    let localizer = move |_: &StageImage<'_>| {
+        vec![Candidate {
+            top_left: finder(7.5, 7.5),
+            top_right: finder(21.5, 7.5),
+            bottom_left: finder(7.5, 21.5),
+            module_size: 7.6,
+            score: 1.0,
+        }]
+    };
+    let codes = Pipeline::new().with_localizer(localizer).run(frame());
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, "4376471154038");
+    for (p, q) in codes[0].position.iter().zip(&expected[0].position) {
+        assert!(p.distance(q) < 4.0, "{p:?} vs {q:?}");
+    }
+
+    // Nothing localized, nothing decoded; a decoder that refuses every grid
+    // still sees the sampled ones.
+    let nothing = |_: &StageImage<'_>| Vec::new();
+    assert!(
+        Pipeline::new()
+            .with_localizer(nothing)
+            .run(frame())
+            .is_empty()
+    );
+    let seen = std::rc::Rc::new(std::cell::Cell::new(0));
+    let counter = seen.clone();
+    let refuse = move |grid: &SampledGrid| {
+        assert_eq!(grid.modules.width(), 17 + 4 * grid.version as usize);
+        counter.set(counter.get() + 1);
+        None
+    };
+    assert!(Pipeline::new().with_decoder(refuse).run(frame()).is_empty());
+    assert!(seen.get() > 0);
 }
 
 #[test]
-fn test_decode_empty_data() {
-    // Test that empty data is rejected
-    let mut bits = Vec::new();
-    push_bits(&mut bits, 0, 4); // Terminator only
+fn test_scan_line_profiles_follow_the_finder_lines() {
+    use crate::stages::{Candidate, StageImage};
 
-    let codewords = payload::bits_to_codewords(&bits);
-    let result = payload::decode_payload(&codewords, 1);
+    let dst = [
+        Point::new(20.0, 20.0),
+        Point::new(252.0, 20.0),
+        Point::new(20.0, 252.0),
+        Point::new(252.0, 252.0),
+    ];
+    let (gray, transform) = render_module_grid(29, dst, 280, 280, golden_v1_dark);
+    let image = StageImage::new(GrayFrame::new(&gray, 280, 280).unwrap());
+    let finder = |x: f32, y: f32| transform.transform(&Point::new(x, y));
+    let candidate = Candidate {
+        top_left: finder(7.5, 7.5),
+        top_right: finder(21.5, 7.5),
+        bottom_left: finder(7.5, 21.5),
+        module_size: 8.0,
+        score: 1.0,
+    };
 
-    // Empty data should return Some with empty content
-    assert!(result.is_some());
-    let (data, content) = result.unwrap();
-    assert!(data.is_empty());
-    assert!(content.is_empty());
+    let profiles = image.scan_line_profiles(&candidate);
+    for profile in [&profiles.horizontal, &profiles.vertical] {
+        assert_eq!(profile.start, candidate.top_left);
+        let length = profile.start.distance(&profile.end).round() as usize;
+        assert_eq!(profile.samples.len(), length + 1);
+        let luma: Vec<u8> = profile.samples.iter().map(|s| s.unwrap()).collect();
+        // Dark finder cores at both ends, light separators between.
+        assert!(luma[0] < 128 && luma[length] < 128);
+        assert!(luma.iter().any(|&v| v > 128));
+    }
+
+    // Off the frame the samples are missing, not clamped.
+    let outside = Candidate {
+        top_right: Point::new(400.0, candidate.top_left.y),
+        ..candidate
+    };
+    let samples = image.scan_line_profiles(&outside).horizontal.samples;
+    assert!(samples[0].is_some());
+    assert!(samples.last().unwrap().is_none());
 }
 
+#[cfg(feature = "tools")]
 #[test]
-fn test_orientation_detection() {
-    // Test that we correctly detect and fix orientation
-    let grid: [[bool; 21]; 21] = [
-        [
-            true, true, true, true, true, true, true, false, false, false, false, false, true,
-            false, true, true, true, true, true, true, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, false, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, true, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, false, true, false, false,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, true,
-            false, true, false, true, true, true, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, true, false, false,
-            false, true, false, false, false, false, false, true,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, false, true, false, true, false,
-            true, true, true, true, true, true, true,
-        ],
-        [
-            false, false, false, false, false, false, false, false, false, true, false, false,
-            false, false, false, false, false, false, false, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, false, true, true, true, true, true, true,
-            false, true, false, false, false, false, false,
-        ],
-        [
-            true, true, true, false, true, false, false, true, true, false, false, true, false,
-            true, false, true, false, true, true, false, false,
-        ],
-        [
-            true, false, false, true, false, true, true, true, true, false, true, true, false,
-            false, true, true, true, false, false, false, true,
-        ],
-        [
-            false, false, true, false, true, false, false, true, false, false, false, false, true,
-            true, true, true, true, false, false, false, false,
-        ],
-        [
-            false, false, true, false, false, false, true, true, false, true, false, true, false,
-            true, true, true, false, true, true, false, false,
-        ],
-        [
-            false, false, false, false, false, false, false, false, true, false, true, false,
-            false, true, true, true, true, false, true, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, false, false, true, true, true, false,
-            true, false, true, true, true, true, false,
-        ],
-        [
-            true, false, false, false, false, false, true, false, true, false, false, false, false,
-            false, true, true, false, false, false, false, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, false, true,
-            true, true, false, false, true, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, true, false, true, false, false,
-            true, true, true, true, false, false, true, true,
-        ],
-        [
-            true, false, true, true, true, false, true, false, false, true, true, true, false,
-            true, true, true, false, true, false, false, true,
-        ],
-        [
-            true, false, false, false, false, false, true, false, false, true, true, true, true,
-            false, false, true, true, false, false, true, false,
-        ],
-        [
-            true, true, true, true, true, true, true, false, true, true, true, false, false, true,
-            false, true, true, true, false, false, false,
-        ],
+fn test_high_density_profile_reads_version_35() {
+    let payload = "HIGH DENSITY PRINT 0123456789 ".repeat(20);
+    let symbol = crate::tools::encode::encode(
+        &payload,
+        SegmentMode::Alphanumeric,
+        35,
+        ECLevel::M,
+        MaskPattern::Pattern2,
+    )
+    .unwrap();
+    let dimension = symbol.width();
+    let padded = dimension + 8;
+    let dark = |mx: usize, my: usize| {
+        (4..dimension + 4).contains(&mx)
+            && (4..dimension + 4).contains(&my)
+            && symbol.get(mx - 4, my - 4)
+    };
+    // ~3 px modules, slightly keystoned.
+    let (width, height) = (540, 540);
+    let dst = [
+        Point::new(20.0, 16.0),
+        Point::new(515.0, 22.0),
+        Point::new(14.0, 512.0),
+        Point::new(522.0, 520.0),
     ];
+    let (gray, _) = render_module_grid(padded, dst, width, height, dark);
+    let rgb: Vec<u8> = gray.iter().flat_map(|&g| [g, g, g]).collect();
 
-    let mut correct_matrix = BitMatrix::new(21, 21);
-    for y in 0..21 {
-        for x in 0..21 {
-            correct_matrix.set(x, y, grid[y][x]);
+    let (codes, tel) = crate::detect_with_telemetry(&rgb, width, height);
+    assert_eq!(tel.strategy_profile, "high_density");
+    assert!(tel.high_density_version_pins >= 1);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, payload);
+}
+
+/// Resize `src` by `scale` with a Catmull-Rom kernel, the way a browser or
+/// OS scales a screenshot: fractional factors leave anti-aliased module
+/// edges with a little over- and undershoot.
+#[cfg(feature = "tools")]
+fn resize_catmull_rom(
+    src: &[u8],
+    width: usize,
+    height: usize,
+    scale: f32,
+) -> (Vec<u8>, usize, usize) {
+    let weight = |t: f32| {
+        let t = t.abs();
+        if t < 1.0 {
+            1.5 * t * t * t - 2.5 * t * t + 1.0
+        } else if t < 2.0 {
+            -0.5 * t * t * t + 2.5 * t * t - 4.0 * t + 2.0
+        } else {
+            0.0
+        }
+    };
+    let taps = |i: usize, len: usize| {
+        let c = (i as f32 + 0.5) / scale - 0.5;
+        let base = c.floor() as isize;
+        (base - 1..=base + 2)
+            .map(|s| (s.clamp(0, len as isize - 1) as usize, weight(c - s as f32)))
+            .collect::<Vec<_>>()
+    };
+    let (w, h) = (
+        (width as f32 * scale) as usize,
+        (height as f32 * scale) as usize,
+    );
+    let mut out = vec![0u8; w * h];
+    for y in 0..h {
+        let ys = taps(y, height);
+        for x in 0..w {
+            let xs = taps(x, width);
+            let mut acc = 0.0;
+            for &(sy, wy) in &ys {
+                for &(sx, wx) in &xs {
+                    acc += wy * wx * src[sy * width + sx] as f32;
+                }
+            }
+            out[y * w + x] = acc.round().clamp(0.0, 255.0) as u8;
         }
     }
+    (out, w, h)
+}
 
-    // Test all rotations - the decoder should handle them
-    let rotated_90 = orientation::rotate90(&correct_matrix);
-    let rotated_180 = orientation::rotate180(&correct_matrix);
-    let rotated_270 = orientation::rotate270(&correct_matrix);
-
-    // All rotations should decode to the same content
-    let result_0 = QrDecoder::decode_from_matrix(&correct_matrix, 1);
-    let result_90 = QrDecoder::decode_from_matrix(&rotated_90, 1);
-    let result_180 = QrDecoder::decode_from_matrix(&rotated_180, 1);
-    let result_270 = QrDecoder::decode_from_matrix(&rotated_270, 1);
-
-    assert!(result_0.is_some(), "Failed to decode correct orientation");
-    assert!(result_90.is_some(), "Failed to decode 90° rotation");
-    assert!(result_180.is_some(), "Failed to decode 180° rotation");
-    assert!(result_270.is_some(), "Failed to decode 270° rotation");
+#[cfg(feature = "tools")]
+#[test]
+fn test_synthetic_profile_reads_fractionally_scaled_screenshot() {
+    let payload = "https://example.com/screenshot?id=0123456789";
+    let symbol = crate::tools::encode::encode(
+        payload,
+        SegmentMode::Byte,
+        4,
+        ECLevel::M,
+        MaskPattern::Pattern1,
+    )
+    .unwrap();
+    let dimension = symbol.width();
+    let (pitch, quiet) = (4, 4);
+    let side = (dimension + 2 * quiet) * pitch;
+    let gray: Vec<u8> = (0..side * side)
+        .map(|i| {
+            let (mx, my) = ((i % side) / pitch, (i / side) / pitch);
+            let inside = (quiet..dimension + quiet).contains(&mx)
+                && (quiet..dimension + quiet).contains(&my);
+            if inside && symbol.get(mx - quiet, my - quiet) {
+                0
+            } else {
+                255
+            }
+        })
+        .collect();
+    // 4 px modules shown at 137%: 5.48 px, never the same run twice.
+    let (gray, width, height) = resize_catmull_rom(&gray, side, side, 1.37);
+    let rgb: Vec<u8> = gray.iter().flat_map(|&g| [g, g, g]).collect();
 
-    let content_0 = result_0.unwrap().content;
-    let content_90 = result_90.unwrap().content;
-    let content_180 = result_180.unwrap().content;
-    let content_270 = result_270.unwrap().content;
+    let (codes, tel) = crate::detect_with_telemetry(&rgb, width, height);
+    assert_eq!(tel.strategy_profile, "synthetic");
+    assert!(tel.router_flatness >= 0.95, "{}", tel.router_flatness);
+    assert_eq!(codes.len(), 1);
+    assert_eq!(codes[0].content, payload);
+    assert!(tel.synthetic_snap_decodes >= 1);
+}
 
-    assert_eq!(content_0, "4376471154038");
-    assert_eq!(content_90, "4376471154038");
-    assert_eq!(content_180, "4376471154038");
-    assert_eq!(content_270, "4376471154038");
+#[test]
+fn test_snapped_axis_transform_rounds_pitch_and_rejects_rotation() {
+    // Version 1 at 3 px modules, origin (10, 20), finders a little off.
+    let (tl, tr, bl) = (
+        Point::new(20.6, 30.4),
+        Point::new(62.3, 30.7),
+        Point::new(20.4, 72.6),
+    );
+    let transform = geometry::snapped_axis_transform(&tl, &tr, &bl, 21).unwrap();
+    let corner = transform.transform(&Point::new(0.0, 0.0));
+    let far = transform.transform(&Point::new(21.0, 21.0));
+    assert_eq!((corner.x, corner.y), (10.0, 20.0));
+    assert!((far.x - 73.0).abs() < 1e-3 && (far.y - 83.0).abs() < 1e-3);
+    // The same symbol turned a quarter: sides swap axes and still snap.
+    let turned = geometry::snapped_axis_transform(&tr, &Point::new(62.3, 72.6), &tl, 21);
+    assert!(turned.is_some());
+    // Rotated 10 degrees: not a rendering on the pixel grid.
+    let (s, c) = 10f32.to_radians().sin_cos();
+    let rot = |p: Point| Point::new(p.x * c - p.y * s + 40.0, p.x * s + p.y * c);
+    assert!(geometry::snapped_axis_transform(&rot(tl), &rot(tr), &rot(bl), 21).is_none());
 }
@@ -83,6 +83,47 @@ impl Gf256 {
     }
 }
 
+/// Reason a Reed-Solomon block could not be corrected
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RsError {
+    /// Residual syndrome was non-zero after correction.
+    Uncorrectable,
+    /// More erasures than ECC codewords.
+    TooManyErasures,
+    /// An erasure index was outside the block.
+    ErasureOutOfBounds,
+    /// The erasure linear system was singular.
+    ErasureSolveFailed,
+    /// Chien search root count disagreed with the locator degree.
+    ErrorPositions,
+    /// Forney denominator vanished at an error location.
+    ZeroDerivative,
+}
+
+impl RsError {
+    /// Short description, matching the legacy `&'static str` errors.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RsError::Uncorrectable => "Uncorrectable error",
+            RsError::TooManyErasures => "Too many erasures",
+            RsError::ErasureOutOfBounds => "Erasure out of bounds",
+            RsError::ErasureSolveFailed => "Erasure solve failed",
+            RsError::ErrorPositions => "Wrong number of error positions found",
+            RsError::ZeroDerivative => "Sigma derivative is zero",
+        }
+    }
+}
+
+impl std::fmt::Display for RsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for RsError {}
+
 /// Reed-Solomon decoder for QR codes
 pub struct ReedSolomonDecoder {
     num_ecc_codewords: usize,
@@ -94,6 +135,11 @@ impl ReedSolomonDecoder {
     }
 
     pub fn decode(&self, received: &mut [u8]) -> Result<(), &'static str> {
+        self.try_decode(received).map_err(RsError::as_str)
+    }
+
+    /// Correct `received` in place, reporting a typed cause on failure.
+    pub fn try_decode(&self, received: &mut [u8]) -> Result<(), RsError> {
         // Calculate syndrome
         let syndrome = self.calculate_syndrome(received);
 
@@ -121,7 +167,7 @@ impl ReedSolomonDecoder {
         // Verify syndrome is now zero
         let new_syndrome = self.calculate_syndrome(received);
         if new_syndrome.iter().any(|&s| s != 0) {
-            return Err("Uncorrectable error");
+            return Err(RsError::Uncorrectable);
         }
 
         Ok(())
@@ -137,21 +183,31 @@ impl ReedSolomonDecoder {
         received: &mut [u8],
         erasures: &[usize],
     ) -> Result<(), &'static str> {
+        self.try_decode_with_erasures(received, erasures)
+            .map_err(RsError::as_str)
+    }
+
+    /// Erasure-aware variant of [`Self::try_decode`].
+    pub fn try_decode_with_erasures(
+        &self,
+        received: &mut [u8],
+        erasures: &[usize],
+    ) -> Result<(), RsError> {
         if erasures.is_empty() {
-            return self.decode(received);
+            return self.try_decode(received);
         }
         if erasures.len() > self.num_ecc_codewords {
-            return Err("Too many erasures");
+            return Err(RsError::TooManyErasures);
         }
         if erasures.iter().any(|&p| p >= received.len()) {
-            return Err("Erasure out of bounds");
+            return Err(RsError::ErasureOutOfBounds);
         }
 
         let mut unique = erasures.to_vec();
         unique.sort_unstable();
         unique.dedup();
         if unique.len() > self.num_ecc_codewords {
-            return Err("Too many unique erasures");
+            return Err(RsError::TooManyErasures);
         }
 
         // Build A*x=b over GF(256) from the first `e` syndrome equations.
@@ -167,12 +223,12 @@ impl ReedSolomonDecoder {
             }
         }
 
-        let values = solve_gf256_linear(&a, &b).ok_or("Erasure solve failed")?;
+        let values = solve_gf256_linear(&a, &b).ok_or(RsError::ErasureSolveFailed)?;
         for (i, &pos) in unique.iter().enumerate() {
             received[pos] = values[i];
         }
 
-        self.decode(received)
+        self.try_decode(received)
     }
 
     fn calculate_syndrome(&self, received: &[u8]) -> Vec<u8> {
@@ -254,7 +310,7 @@ impl ReedSolomonDecoder {
         sigma
     }
 
-    fn find_error_positions(&self, sigma: &[u8], n: usize) -> Result<Vec<usize>, &'static str> {
+    fn find_error_positions(&self, sigma: &[u8], n: usize) -> Result<Vec<usize>, RsError> {
         let mut positions = Vec::new();
 
         // Chien search: sigma(x) = prod(1 - X_k * x) where X_k = alpha^(n-1-pos)
@@ -281,7 +337,7 @@ impl ReedSolomonDecoder {
         }
 
         if positions.len() != sigma.len() - 1 {
-            return Err("Wrong number of error positions found");
+            return Err(RsError::ErrorPositions);
         }
 
         Ok(positions)
@@ -293,7 +349,7 @@ impl ReedSolomonDecoder {
         syndrome: &[u8],
         error_positions: &[usize],
         n: usize,
-    ) -> Result<Vec<u8>, &'static str> {
+    ) -> Result<Vec<u8>, RsError> {
         // Forney algorithm
         // omega = syndrome * sigma mod x^(2t)
         let mut omega = vec![0u8; syndrome.len()];
@@ -334,7 +390,7 @@ impl ReedSolomonDecoder {
             }
 
             if sigma_prime_val == 0 {
-                return Err("Sigma derivative is zero");
+                return Err(RsError::ZeroDerivative);
             }
 
             // Forney: e_k = X_k * omega(X_k^{-1}) / sigma'(X_k^{-1})
@@ -577,5 +633,9 @@ mod tests {
         let mut data = vec![0u8; 16];
         let decoder = ReedSolomonDecoder::new(10);
         assert!(decoder.decode_with_erasures(&mut data, &[99]).is_err());
+        assert_eq!(
+            decoder.try_decode_with_erasures(&mut data, &[99]),
+            Err(RsError::ErasureOutOfBounds)
+        );
     }
 }
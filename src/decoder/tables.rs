@@ -1,8 +1,46 @@
+//! Version and error-correction tables
+//!
+//! Codeword counts, Reed-Solomon block structure, character count field
+//! widths and per-mode capacities for every Model 2 version and EC level,
+//! as given in ISO/IEC 18004 tables 3, 7 and 9.
+
+use crate::decoder::qr_decoder::SegmentMode;
 use crate::models::ECLevel;
 
+/// Codeword layout of one version at one EC level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EcBlockInfo {
+    /// Number of Reed-Solomon blocks
     pub num_blocks: usize,
+    /// Error-correction codewords in every block
     pub ecc_per_block: usize,
+    /// Codewords in the symbol, data and error correction together
+    pub total_codewords: usize,
+    /// Data codewords across all blocks
+    pub data_codewords: usize,
+}
+
+impl EcBlockInfo {
+    /// Blocks holding [`Self::short_block_data_len`] data codewords. They
+    /// come first; the remaining blocks hold one more.
+    pub fn short_blocks(&self) -> usize {
+        self.num_blocks - self.data_codewords % self.num_blocks
+    }
+
+    /// Data codewords in each of the first [`Self::short_blocks`] blocks
+    pub fn short_block_data_len(&self) -> usize {
+        self.data_codewords / self.num_blocks
+    }
+
+    /// Data codewords in block `index` (0-based, in symbol order)
+    pub fn data_len(&self, index: usize) -> usize {
+        self.short_block_data_len() + usize::from(index >= self.short_blocks())
+    }
+
+    /// Data bits available for segments, mode indicators and terminator
+    pub fn data_bits(&self) -> usize {
+        self.data_codewords * 8
+    }
 }
 
 // Tables from the QR Code specification (Model 2) via Nayuki QR Code generator.
@@ -45,22 +83,78 @@ const NUM_ERROR_CORRECTION_BLOCKS: [[i8; 41]; 4] = [
     ], // High
 ];
 
+/// Block structure of `version` (1-40) at `ec_level`
 pub fn ec_block_info(version: u8, ec_level: ECLevel) -> Option<EcBlockInfo> {
-    if !(1..=40).contains(&version) {
-        return None;
-    }
+    let total_codewords = raw_codewords(version)?;
     let idx = ec_level_index(ec_level);
     let ecc = ECC_CODEWORDS_PER_BLOCK[idx][version as usize];
     let blocks = NUM_ERROR_CORRECTION_BLOCKS[idx][version as usize];
     if ecc <= 0 || blocks <= 0 {
         return None;
     }
+    let (num_blocks, ecc_per_block) = (blocks as usize, ecc as usize);
     Some(EcBlockInfo {
-        num_blocks: blocks as usize,
-        ecc_per_block: ecc as usize,
+        num_blocks,
+        ecc_per_block,
+        total_codewords,
+        data_codewords: total_codewords - num_blocks * ecc_per_block,
     })
 }
 
+/// Codewords that fit in the data area of `version` (1-40): the modules
+/// left after function patterns and format and version information, less
+/// the 0-7 remainder bits that don't fill a whole codeword.
+pub fn raw_codewords(version: u8) -> Option<usize> {
+    if !(1..=40).contains(&version) {
+        return None;
+    }
+    let v = version as usize;
+    let mut modules = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let align = v / 7 + 2;
+        modules -= (25 * align - 10) * align - 55;
+        if v >= 7 {
+            modules -= 36;
+        }
+    }
+    Some(modules / 8)
+}
+
+/// Width of the character count field for `mode` in `version`; 0 for ECI
+/// and structured append, which have none.
+pub fn char_count_bits(mode: SegmentMode, version: u8) -> usize {
+    let band = match version {
+        0..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    match mode {
+        SegmentMode::Numeric => [10, 12, 14][band],
+        SegmentMode::Alphanumeric => [9, 11, 13][band],
+        SegmentMode::Byte => [8, 16, 16][band],
+        SegmentMode::Kanji => [8, 10, 12][band],
+        SegmentMode::Eci(_) | SegmentMode::StructuredAppend(_) => 0,
+    }
+}
+
+/// Most characters (digits, alphanumerics, bytes or Kanji) a symbol of
+/// `version` at `ec_level` holds in a single `mode` segment. `None` for an
+/// unsupported version and for ECI and structured append.
+pub fn capacity(version: u8, ec_level: ECLevel, mode: SegmentMode) -> Option<usize> {
+    let count_bits = char_count_bits(mode, version);
+    let bits = ec_block_info(version, ec_level)?
+        .data_bits()
+        .checked_sub(4 + count_bits)?;
+    let chars = match mode {
+        SegmentMode::Numeric => bits / 10 * 3 + [0, 0, 0, 0, 1, 1, 1, 2, 2, 2][bits % 10],
+        SegmentMode::Alphanumeric => bits / 11 * 2 + usize::from(bits % 11 >= 6),
+        SegmentMode::Byte => bits / 8,
+        SegmentMode::Kanji => bits / 13,
+        SegmentMode::Eci(_) | SegmentMode::StructuredAppend(_) => return None,
+    };
+    Some(chars.min((1 << count_bits) - 1))
+}
+
 fn ec_level_index(ec_level: ECLevel) -> usize {
     match ec_level {
         ECLevel::L => 0,
@@ -69,3 +163,142 @@ fn ec_level_index(ec_level: ECLevel) -> usize {
         ECLevel::H => 3,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::function_mask::FunctionMask;
+
+    const LEVELS: [ECLevel; 4] = [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H];
+
+    // ISO/IEC 18004 table 1, total codewords per version
+    const TOTAL_CODEWORDS: [usize; 40] = [
+        26, 44, 70, 100, 134, 172, 196, 242, 292, 346, 404, 466, 532, 581, 655, 733, 815, 901, 991,
+        1085, 1156, 1258, 1364, 1474, 1588, 1706, 1828, 1921, 2051, 2185, 2323, 2465, 2611, 2761,
+        2876, 3034, 3196, 3362, 3532, 3706,
+    ];
+
+    // Table 7, data codewords per version for L, M, Q, H
+    const DATA_CODEWORDS: [[usize; 40]; 4] = [
+        [
+            19, 34, 55, 80, 108, 136, 156, 194, 232, 274, 324, 370, 428, 461, 523, 589, 647, 721,
+            795, 861, 932, 1006, 1094, 1174, 1276, 1370, 1468, 1531, 1631, 1735, 1843, 1955, 2071,
+            2191, 2306, 2434, 2566, 2702, 2812, 2956,
+        ],
+        [
+            16, 28, 44, 64, 86, 108, 124, 154, 182, 216, 254, 290, 334, 365, 415, 453, 507, 563,
+            627, 669, 714, 782, 860, 914, 1000, 1062, 1128, 1193, 1267, 1373, 1455, 1541, 1631,
+            1725, 1812, 1914, 1992, 2102, 2216, 2334,
+        ],
+        [
+            13, 22, 34, 48, 62, 76, 88, 110, 132, 154, 180, 206, 244, 261, 295, 325, 367, 397, 445,
+            485, 512, 568, 614, 664, 718, 754, 808, 871, 911, 985, 1033, 1115, 1171, 1231, 1286,
+            1354, 1426, 1502, 1582, 1666,
+        ],
+        [
+            9, 16, 26, 36, 46, 60, 66, 86, 100, 122, 140, 158, 180, 197, 223, 253, 283, 313, 341,
+            385, 406, 442, 464, 514, 538, 596, 628, 661, 701, 745, 793, 845, 901, 961, 986, 1054,
+            1096, 1142, 1222, 1276,
+        ],
+    ];
+
+    #[test]
+    fn codeword_counts_match_spec() {
+        for version in 1..=40u8 {
+            let total = TOTAL_CODEWORDS[version as usize - 1];
+            assert_eq!(raw_codewords(version), Some(total), "v{version}");
+            let data_modules = FunctionMask::new(version).data_modules_count();
+            assert_eq!(data_modules / 8, total, "v{version} data modules");
+            for (i, &level) in LEVELS.iter().enumerate() {
+                let info = ec_block_info(version, level).unwrap();
+                assert_eq!(info.total_codewords, total);
+                assert_eq!(
+                    info.data_codewords,
+                    DATA_CODEWORDS[i][version as usize - 1],
+                    "v{version} {level:?}"
+                );
+            }
+        }
+        for version in [0, 41] {
+            assert_eq!(raw_codewords(version), None);
+            assert_eq!(ec_block_info(version, ECLevel::L), None);
+        }
+    }
+
+    #[test]
+    fn block_structure_is_consistent() {
+        for version in 1..=40u8 {
+            for level in LEVELS {
+                let info = ec_block_info(version, level).unwrap();
+                let data_lens: Vec<usize> =
+                    (0..info.num_blocks).map(|b| info.data_len(b)).collect();
+                assert_eq!(data_lens.iter().sum::<usize>(), info.data_codewords);
+                assert!(data_lens.windows(2).all(|w| w[0] <= w[1]));
+                assert!(data_lens[info.num_blocks - 1] - data_lens[0] <= 1);
+                assert_eq!(
+                    info.data_codewords + info.num_blocks * info.ecc_per_block,
+                    info.total_codewords
+                );
+            }
+        }
+        // Table 9: 5-Q is 2 blocks of (33,15) and 2 of (34,16)
+        let info = ec_block_info(5, ECLevel::Q).unwrap();
+        assert_eq!((info.num_blocks, info.ecc_per_block), (4, 18));
+        assert_eq!((info.short_blocks(), info.short_block_data_len()), (2, 15));
+        assert_eq!(info.data_len(3), 16);
+    }
+
+    #[test]
+    fn capacities_match_spec() {
+        use SegmentMode::{Alphanumeric, Byte, Kanji, Numeric};
+        // Table 7: numeric, alphanumeric, byte, Kanji for L, M, Q, H
+        let expected: [(u8, [[usize; 4]; 4]); 3] = [
+            (
+                1,
+                [
+                    [41, 25, 17, 10],
+                    [34, 20, 14, 8],
+                    [27, 16, 11, 7],
+                    [17, 10, 7, 4],
+                ],
+            ),
+            (
+                10,
+                [
+                    [652, 395, 271, 167],
+                    [513, 311, 213, 131],
+                    [364, 221, 151, 93],
+                    [288, 174, 119, 74],
+                ],
+            ),
+            (
+                40,
+                [
+                    [7089, 4296, 2953, 1817],
+                    [5596, 3391, 2331, 1435],
+                    [3993, 2420, 1663, 1024],
+                    [3057, 1852, 1273, 784],
+                ],
+            ),
+        ];
+        for (version, rows) in expected {
+            for (level, row) in LEVELS.into_iter().zip(rows) {
+                let got = [Numeric, Alphanumeric, Byte, Kanji]
+                    .map(|mode| capacity(version, level, mode).unwrap());
+                assert_eq!(got, row, "v{version} {level:?}");
+            }
+        }
+        assert_eq!(capacity(1, ECLevel::L, SegmentMode::Eci(26)), None);
+        assert_eq!(capacity(41, ECLevel::L, Byte), None);
+    }
+
+    #[test]
+    fn char_count_widths_follow_version_bands() {
+        let widths = |mode| [1, 9, 10, 26, 27, 40].map(|v| char_count_bits(mode, v));
+        assert_eq!(widths(SegmentMode::Numeric), [10, 10, 12, 12, 14, 14]);
+        assert_eq!(widths(SegmentMode::Alphanumeric), [9, 9, 11, 11, 13, 13]);
+        assert_eq!(widths(SegmentMode::Byte), [8, 8, 16, 16, 16, 16]);
+        assert_eq!(widths(SegmentMode::Kanji), [8, 8, 10, 10, 12, 12]);
+        assert_eq!(widths(SegmentMode::Eci(3)), [0; 6]);
+    }
+}
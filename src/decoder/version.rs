@@ -30,12 +30,12 @@ impl VersionInfo {
         let size = matrix.width();
         let mut bits: u32 = 0;
 
-        // Version info is in a 6x3 block below the top-right finder pattern
-        // At the top-right corner, going down
+        // Version info is in a 6x3 block left of the top-right finder
+        // pattern, least significant bit first along each row
         for row in 0..6 {
-            for col in (size - 11)..(size - 8) {
+            for (k, col) in ((size - 11)..(size - 8)).enumerate() {
                 let is_black = matrix.get(col, row);
-                bits = (bits << 1) | (is_black as u32);
+                bits |= (is_black as u32) << (row * 3 + k);
             }
         }
 
@@ -46,11 +46,12 @@ impl VersionInfo {
         let size = matrix.width();
         let mut bits: u32 = 0;
 
-        // Version info is in a 3x6 block to the right of the bottom-left finder pattern
+        // Version info is in a 3x6 block above the bottom-left finder
+        // pattern, the transpose of the top-right block
         for col in 0..6 {
-            for row in (size - 11)..(size - 8) {
+            for (k, row) in ((size - 11)..(size - 8)).enumerate() {
                 let is_black = matrix.get(col, row);
-                bits = (bits << 1) | (is_black as u32);
+                bits |= (is_black as u32) << (col * 3 + k);
             }
         }
 
@@ -58,17 +59,17 @@ impl VersionInfo {
     }
 
     fn decode(version_bits: u32) -> Option<u8> {
-        // BCH(18,6) decoding
-        let corrected = Self::correct_errors(version_bits)?;
-
-        // Top 6 bits are version number
-        let version = (corrected >> 12) as u8;
-
-        if (7..=40).contains(&version) {
-            Some(version)
-        } else {
-            None
+        if Self::check_version(version_bits) {
+            let version = (version_bits >> 12) as u8;
+            return (7..=40).contains(&version).then_some(version);
         }
+        // BCH(18,6) has minimum distance 8: take the nearest valid version
+        // within the 3 errors it can correct
+        (7..=40u8)
+            .map(|v| (v, (Self::codeword(v) ^ version_bits).count_ones()))
+            .filter(|&(_, distance)| distance <= 3)
+            .min_by_key(|&(_, distance)| distance)
+            .map(|(v, _)| v)
     }
 
     fn decode_with_correction(bits1: u32, bits2: u32) -> Option<u8> {
@@ -83,37 +84,23 @@ impl VersionInfo {
         None
     }
 
-    fn correct_errors(codeword: u32) -> Option<u32> {
-        // BCH(18,6) can correct up to 3 errors
-        if Self::check_version(codeword) {
-            return Some(codeword);
-        }
-
-        // Try single-bit corrections
-        for i in 0..18 {
-            let test = codeword ^ (1 << i);
-            if Self::check_version(test) {
-                return Some(test);
-            }
-        }
-
-        None
-    }
-
-    fn check_version(codeword: u32) -> bool {
+    /// The 18-bit version information for `version`: the version in the
+    /// top 6 bits, its BCH remainder in the low 12.
+    fn codeword(version: u8) -> u32 {
         // BCH(18,6) generator: x^12 + x^11 + x^10 + x^9 + x^8 + x^5 + x^2 + 1
         const GENERATOR: u32 = 0x1f25;
-        let mut remainder = codeword;
-
-        for _ in 0..6 {
-            if remainder & 0x20000 != 0 {
-                remainder ^= GENERATOR << 5;
+        let data = u32::from(version) & 0x3f;
+        let mut remainder = data << 12;
+        for shift in (0..6).rev() {
+            if remainder & (1 << (shift + 12)) != 0 {
+                remainder ^= GENERATOR << shift;
             }
-            remainder <<= 1;
         }
+        (data << 12) | remainder
+    }
 
-        let syndrome = (remainder >> 6) & 0xFFF;
-        syndrome == 0
+    fn check_version(codeword: u32) -> bool {
+        codeword < 1 << 18 && Self::codeword((codeword >> 12) as u8) == codeword
     }
 }
 
@@ -125,5 +112,18 @@ mod tests {
     fn test_version_check() {
         // Valid version info should pass check
         assert!(VersionInfo::check_version(0));
+        // Version 7 from the specification's table
+        assert!(VersionInfo::check_version(0x07c94));
+        assert!(!VersionInfo::check_version(0x07c95));
+    }
+
+    #[test]
+    fn test_version_decode_corrects_three_errors() {
+        assert_eq!(VersionInfo::decode(0x07c94), Some(7));
+        assert_eq!(
+            VersionInfo::decode(0x28c69 ^ 0b1001_0000_0000_0001),
+            Some(40)
+        );
+        assert_eq!(VersionInfo::decode(0), None);
     }
 }
@@ -32,76 +32,128 @@ impl UnionFind {
 
 /// Find connected black regions and return their bounding boxes
 pub fn find_black_regions(matrix: &BitMatrix) -> Vec<(usize, usize, usize, usize)> {
+    label_regions(matrix, |_, _| {})
+}
+
+/// Per-binarization statistics shared by the router and the contour
+/// detector, gathered in one sweep instead of one traversal each
+pub struct ImageStats {
+    /// Bounding boxes of the connected black regions, as from
+    /// [`find_black_regions`]
+    pub regions: Vec<(usize, usize, usize, usize)>,
+    /// Fraction of grayscale pixels at or above 245
+    pub saturation_ratio: f32,
+    /// Black regions per megapixel
+    pub region_density: f32,
+}
+
+impl ImageStats {
+    /// Label `matrix` and measure `gray` (same dimensions) in one pass
+    pub fn compute(matrix: &BitMatrix, gray: &[u8]) -> Self {
+        let width = matrix.width();
+        let pixels = width * matrix.height();
+        let mut saturated = 0usize;
+        let regions = if gray.len() == pixels {
+            label_regions(matrix, |y, _| {
+                let row = &gray[y * width..(y + 1) * width];
+                saturated += row.iter().filter(|&&v| v >= 245).count();
+            })
+        } else {
+            find_black_regions(matrix)
+        };
+        let megapixels = (pixels as f32 / 1_000_000.0).max(0.1);
+        Self {
+            saturation_ratio: if pixels == 0 || gray.len() != pixels {
+                0.0
+            } else {
+                (saturated as f32 / pixels as f32).clamp(0.0, 1.0)
+            },
+            region_density: regions.len() as f32 / megapixels,
+            regions,
+        }
+    }
+}
+
+/// Label black regions row by row, growing each provisional label's
+/// bounding box as it goes so no second pass over the pixels is needed.
+/// `on_row(y, width)` runs once per row for callers folding their own
+/// per-pixel statistics into the same sweep.
+fn label_regions(
+    matrix: &BitMatrix,
+    mut on_row: impl FnMut(usize, usize),
+) -> Vec<(usize, usize, usize, usize)> {
     let width = matrix.width();
     let height = matrix.height();
 
-    let mut labels = vec![0u32; width * height];
-    let mut next_label = 1u32;
-    let mut uf = UnionFind::new(width * height);
+    // Two rows of labels suffice: neighbours are left, above and the
+    // upper diagonals.
+    let mut prev = vec![0u32; width];
+    let mut curr = vec![0u32; width];
+    // Index 0 is the background label.
+    let mut boxes: Vec<(usize, usize, usize, usize)> = vec![(0, 0, 0, 0)];
+    let mut uf = UnionFind::new(1);
 
-    // First pass: label components
     for y in 0..height {
+        on_row(y, width);
         for x in 0..width {
+            curr[x] = 0;
             if !matrix.get(x, y) {
                 continue;
             }
 
-            let idx = y * width + x;
-            let mut neighbor_labels = Vec::new();
-
-            // Check left (4-connectivity)
-            if x > 0 && matrix.get(x - 1, y) {
-                neighbor_labels.push(labels[y * width + x - 1]);
-            }
-            // Check above (4-connectivity)
-            if y > 0 && matrix.get(x, y - 1) {
-                neighbor_labels.push(labels[(y - 1) * width + x]);
-            }
-            // Check upper-left diagonal (8-connectivity for finder patterns)
-            if x > 0 && y > 0 && matrix.get(x - 1, y - 1) {
-                neighbor_labels.push(labels[(y - 1) * width + x - 1]);
+            // 4-connectivity left/above plus both upper diagonals
+            // (8-connectivity for finder patterns)
+            let mut neighbor_labels = [0u32; 4];
+            if x > 0 {
+                neighbor_labels[0] = curr[x - 1];
             }
-            // Check upper-right diagonal (8-connectivity)
-            if x + 1 < width && y > 0 && matrix.get(x + 1, y - 1) {
-                neighbor_labels.push(labels[(y - 1) * width + x + 1]);
+            if y > 0 {
+                neighbor_labels[1] = prev[x];
+                if x > 0 {
+                    neighbor_labels[2] = prev[x - 1];
+                }
+                if x + 1 < width {
+                    neighbor_labels[3] = prev[x + 1];
+                }
             }
 
-            if neighbor_labels.is_empty() {
-                labels[idx] = next_label;
-                next_label += 1;
-            } else {
-                let min_label = *neighbor_labels.iter().min().unwrap();
-                labels[idx] = min_label;
-                for &l in &neighbor_labels {
-                    if l != min_label {
-                        uf.union(min_label, l);
+            let label = match neighbor_labels.iter().copied().filter(|&l| l != 0).min() {
+                Some(min_label) => {
+                    for &l in &neighbor_labels {
+                        if l != 0 && l != min_label {
+                            uf.union(min_label, l);
+                        }
                     }
+                    min_label
                 }
-            }
-        }
-    }
-
-    // Compute bounding boxes
-    let mut bboxes: std::collections::HashMap<u32, (usize, usize, usize, usize)> =
-        std::collections::HashMap::new();
-
-    for y in 0..height {
-        for x in 0..width {
-            let label = labels[y * width + x];
-            if label == 0 {
-                continue;
-            }
-            let root = uf.find(label);
-
-            let entry = bboxes.entry(root).or_insert((x, y, x, y));
+                None => {
+                    boxes.push((x, y, x, y));
+                    uf.parent.push(boxes.len() as u32 - 1);
+                    boxes.len() as u32 - 1
+                }
+            };
+            curr[x] = label;
+            let entry = &mut boxes[label as usize];
             entry.0 = entry.0.min(x);
             entry.1 = entry.1.min(y);
             entry.2 = entry.2.max(x);
             entry.3 = entry.3.max(y);
         }
+        std::mem::swap(&mut prev, &mut curr);
     }
 
-    bboxes.values().cloned().collect()
+    // Fold provisional boxes into their roots.
+    let mut merged: Vec<Option<(usize, usize, usize, usize)>> = vec![None; boxes.len()];
+    for label in 1..boxes.len() as u32 {
+        let root = uf.find(label) as usize;
+        let b = boxes[label as usize];
+        let entry = merged[root].get_or_insert(b);
+        entry.0 = entry.0.min(b.0);
+        entry.1 = entry.1.min(b.1);
+        entry.2 = entry.2.max(b.2);
+        entry.3 = entry.3.max(b.3);
+    }
+    merged.into_iter().flatten().collect()
 }
 
 #[cfg(test)]
@@ -121,4 +173,27 @@ mod tests {
         assert_eq!(regions.len(), 1);
         assert_eq!(regions[0], (2, 2, 3, 3));
     }
+
+    #[test]
+    fn test_image_stats_matches_separate_passes() {
+        let mut matrix = BitMatrix::new(12, 8);
+        // A U shape whose arms only join on the bottom row, plus a dot.
+        for y in 1..6 {
+            matrix.set(1, y, true);
+            matrix.set(5, y, true);
+        }
+        for x in 1..6 {
+            matrix.set(x, 6, true);
+        }
+        matrix.set(9, 2, true);
+        let mut gray = vec![128u8; 12 * 8];
+        gray[..24].fill(250);
+
+        let stats = ImageStats::compute(&matrix, &gray);
+        let mut regions = stats.regions.clone();
+        regions.sort_unstable();
+        assert_eq!(regions, vec![(1, 1, 5, 6), (9, 2, 9, 2)]);
+        assert_eq!(regions.len(), find_black_regions(&matrix).len());
+        assert_eq!(stats.saturation_ratio, 0.25);
+    }
 }
@@ -11,10 +11,18 @@ impl ContourDetector {
     /// struggles (noncompliant/pathological/curved). It intentionally prefers
     /// higher precision over recall and is used with a bounded decode budget.
     pub fn detect(matrix: &BitMatrix) -> Vec<FinderPattern> {
-        let regions = find_black_regions(matrix);
+        Self::detect_in_regions(matrix, &find_black_regions(matrix))
+    }
+
+    /// [`Self::detect`] over black regions already labeled, e.g. by
+    /// [`ImageStats::compute`](crate::detector::connected_components::ImageStats::compute)
+    pub fn detect_in_regions(
+        matrix: &BitMatrix,
+        regions: &[(usize, usize, usize, usize)],
+    ) -> Vec<FinderPattern> {
         let mut candidates = Vec::new();
 
-        for (min_x, min_y, max_x, max_y) in regions {
+        for &(min_x, min_y, max_x, max_y) in regions {
             let w = max_x.saturating_sub(min_x) + 1;
             let h = max_y.saturating_sub(min_y) + 1;
             let area = w * h;
@@ -4,94 +4,389 @@ use crate::detector::pyramid::ImagePyramid;
 use crate::models::{BitMatrix, Point};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FinderPattern {
     pub center: Point,
     pub module_size: f32,
+    /// Cross-check score in [0, 1]; higher means closer to 1:1:3:1:1 on
+    /// both axes. Used to pick the survivor when merging duplicates.
+    pub quality: f32,
+    /// Found with reversed polarity, light rings around a light core on a
+    /// dark surround, as laser-etched and metallic marks can read (see
+    /// [`crate::DetectorConfig::inverted_finders`]).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inverted: bool,
 }
 
 impl FinderPattern {
+    /// Pattern with a neutral quality of 0.5.
     pub fn new(x: f32, y: f32, module_size: f32) -> Self {
+        Self::with_quality(x, y, module_size, 0.5)
+    }
+
+    pub fn with_quality(x: f32, y: f32, module_size: f32, quality: f32) -> Self {
         Self {
             center: Point::new(x, y),
             module_size,
+            quality,
+            inverted: false,
+        }
+    }
+
+    /// Sharpness in [0, 1] of the outer dark ring's edge in `gray`: the
+    /// steepest one-pixel step across the edge over the full dark-to-light
+    /// span, averaged over the four axis directions. A crisp edge scores 1,
+    /// an edge blurred over n pixels about 1/n; `None` when no direction
+    /// stays inside the image with enough contrast to measure.
+    pub fn edge_sharpness(&self, gray: &[u8], width: usize, height: usize) -> Option<f32> {
+        let unit = self.module_size.max(1.0);
+        // The edge sits 3.5 modules out; look one module either side of it.
+        let (near, far) = ((2.5 * unit) as i32, (4.5 * unit).ceil() as i32);
+        let (cx, cy) = (self.center.x.round() as i32, self.center.y.round() as i32);
+        let mut total = 0.0f32;
+        let mut measured = 0usize;
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let mut prev: Option<i32> = None;
+            let (mut lo, mut hi, mut step) = (i32::MAX, i32::MIN, 0i32);
+            for r in near..=far {
+                let (x, y) = (cx + dx * r, cy + dy * r);
+                if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+                    prev = None;
+                    break;
+                }
+                let v = gray[y as usize * width + x as usize] as i32;
+                lo = lo.min(v);
+                hi = hi.max(v);
+                if let Some(p) = prev {
+                    step = step.max((v - p).abs());
+                }
+                prev = Some(v);
+            }
+            if prev.is_some() && hi - lo >= 16 {
+                total += step as f32 / (hi - lo) as f32;
+                measured += 1;
+            }
+        }
+        (measured > 0).then(|| total / measured as f32)
+    }
+}
+
+/// Tunables for [`FinderDetector::detect_with_config`].
+#[derive(Debug, Clone, Copy)]
+pub struct FinderConfig {
+    /// Also accept patterns whose outer dark ring runs into the image edge or
+    /// into adjacent dark content, as happens with codes printed without a
+    /// quiet zone.
+    pub tolerate_missing_quiet_zone: bool,
+    /// Candidates closer than this many module sizes are treated as the same
+    /// finder and merged.
+    pub merge_radius: f32,
+    /// Smallest module size, in pixels, expected in the image. Rows and
+    /// columns are then scanned every [`line_step`] lines and densified
+    /// around hits instead of line by line; `None` scans every line.
+    pub min_module_size: Option<f32>,
+    /// When to follow the row scan with a column scan.
+    pub column_scan: ColumnScan,
+}
+
+/// When finder detection scans columns as well as rows.
+///
+/// A row through a finder's centre shows 1:1:3:1:1 at any rotation, so the
+/// column pass mostly re-finds the same patterns; it earns its cost on
+/// rotated codes whose finders the rows cross badly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnScan {
+    /// Skip columns when the rows already found a consistent finder triple,
+    /// or a pair of agreeing patterns on a common row or column (an upright
+    /// code, whose finders columns see exactly as rows do).
+    #[default]
+    Auto,
+    /// Always scan columns.
+    Always,
+    /// Scan rows only.
+    Never,
+}
+
+/// Scan-line spacing that still puts at least two lines through the
+/// 3-module core of every finder with modules of `min_module_size` pixels.
+pub fn line_step(min_module_size: f32) -> usize {
+    if min_module_size.is_finite() {
+        ((min_module_size * 1.5) as usize).max(1)
+    } else {
+        1
+    }
+}
+
+impl Default for FinderConfig {
+    fn default() -> Self {
+        Self {
+            tolerate_missing_quiet_zone: false,
+            merge_radius: 5.0,
+            min_module_size: None,
+            column_scan: ColumnScan::Auto,
         }
     }
 }
 
+/// Whether merged row-scan patterns leave nothing for a column scan to add:
+/// three with agreeing module sizes form a finder triple (a right angle
+/// with legs of similar length), or two that agree lie on a common row or
+/// column, which only an upright code produces.
+fn rows_conclusive(patterns: &[FinderPattern]) -> bool {
+    // Best-quality first after merging; a handful is enough to decide.
+    let top = &patterns[..patterns.len().min(8)];
+    let agree = |a: &FinderPattern, b: &FinderPattern| {
+        let (lo, hi) = (
+            a.module_size.min(b.module_size),
+            a.module_size.max(b.module_size),
+        );
+        lo > 0.0 && hi / lo <= 1.4
+    };
+    for (i, a) in top.iter().enumerate() {
+        for (j, b) in top.iter().enumerate().skip(i + 1) {
+            if !agree(a, b) {
+                continue;
+            }
+            let (dx, dy) = (b.center.x - a.center.x, b.center.y - a.center.y);
+            let module = (a.module_size + b.module_size) / 2.0;
+            let far = dx.hypot(dy) >= 10.0 * module;
+            // Within about 2 degrees of the horizontal or vertical.
+            if far && dx.abs().min(dy.abs()) <= 0.035 * dx.abs().max(dy.abs()) {
+                return true;
+            }
+            for c in top.iter().skip(j + 1) {
+                if agree(a, c) && agree(b, c) && is_finder_triple(a, b, c) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Whether one of the three centres is a right-angle corner with legs of
+/// similar length, each at least 10 modules.
+fn is_finder_triple(a: &FinderPattern, b: &FinderPattern, c: &FinderPattern) -> bool {
+    let module = (a.module_size + b.module_size + c.module_size) / 3.0;
+    let ps = [a.center, b.center, c.center];
+    (0..3).any(|i| {
+        let (o, p, q) = (ps[i], ps[(i + 1) % 3], ps[(i + 2) % 3]);
+        let (ux, uy, vx, vy) = (p.x - o.x, p.y - o.y, q.x - o.x, q.y - o.y);
+        let (lu, lv) = (ux.hypot(uy), vx.hypot(vy));
+        lu.min(lv) >= 10.0 * module
+            && lu.max(lv) <= 1.25 * lu.min(lv)
+            && ((ux * vx + uy * vy) / (lu * lv)).abs() <= 0.15
+    })
+}
+
+/// The most recent runs along a scan line; finder checks only ever look at
+/// the last five, so nothing older is kept.
+#[derive(Default)]
+struct RunWindow {
+    lengths: [usize; 5],
+    colors: [bool; 5],
+    count: usize,
+}
+
+impl RunWindow {
+    fn push(&mut self, length: usize, color: bool) {
+        self.lengths.copy_within(1.., 0);
+        self.colors.copy_within(1.., 0);
+        self.lengths[4] = length;
+        self.colors[4] = color;
+        self.count += 1;
+    }
+
+    /// Lengths of the last five runs, once five have been seen
+    fn last_five(&self) -> Option<&[usize; 5]> {
+        (self.count >= 5).then_some(&self.lengths)
+    }
+
+    /// Whether the last five runs go dark-light-dark-light-dark
+    fn finder_order(&self) -> bool {
+        self.colors == [true, false, true, false, true]
+    }
+}
+
+/// Reusable buffers for [`FinderDetector::detect_into`].
+///
+/// Once they have grown to fit a frame's candidates, scanning frames of
+/// that size again allocates nothing.
+#[derive(Debug, Default)]
+pub struct FinderScratch {
+    candidates: Vec<FinderPattern>,
+    order: Vec<u32>,
+    absorbed: Vec<bool>,
+    patterns: Vec<FinderPattern>,
+}
+
+impl FinderScratch {
+    /// Create empty scratch buffers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Patterns found by the last [`FinderDetector::detect_into`] call
+    pub fn patterns(&self) -> &[FinderPattern] {
+        &self.patterns
+    }
+}
+
 pub struct FinderDetector;
 
 impl FinderDetector {
     pub fn detect(matrix: &BitMatrix) -> Vec<FinderPattern> {
+        let mut scratch = FinderScratch::new();
+        Self::detect_into(matrix, &mut scratch);
+        scratch.patterns
+    }
+
+    /// [`Self::detect`] into reusable buffers; the result is also available
+    /// from [`FinderScratch::patterns`] until the next call.
+    pub fn detect_into<'a>(
+        matrix: &BitMatrix,
+        scratch: &'a mut FinderScratch,
+    ) -> &'a [FinderPattern] {
+        Self::detect_into_with_config(matrix, &FinderConfig::default(), scratch)
+    }
+
+    /// [`Self::detect_with_config`] into reusable buffers.
+    pub fn detect_into_with_config<'a>(
+        matrix: &BitMatrix,
+        config: &FinderConfig,
+        scratch: &'a mut FinderScratch,
+    ) -> &'a [FinderPattern] {
         let width = matrix.width();
         let height = matrix.height();
-        let mut candidates = Vec::new();
+        let step = config.min_module_size.map_or(1, line_step);
+        let candidates = &mut scratch.candidates;
+        candidates.clear();
 
-        // Scan every row - edge detection provides the speedup
-        let row_step = 1;
-        for y in (0..height).step_by(row_step) {
+        // Rows, with edge detection skipping flat ones
+        Self::stepped_lines(height, step, |y| {
             // Early termination 1: Skip rows with low variance (no edges)
             if !Self::has_significant_edges(matrix, y, width) {
-                continue;
+                return false;
             }
+            let before = candidates.len();
+            Self::scan_row(matrix, y, width, candidates);
+            candidates.len() > before
+        });
 
-            let row_candidates = Self::scan_row(matrix, y, width);
-            candidates.extend(row_candidates);
+        let scan_columns = match config.column_scan {
+            ColumnScan::Always => true,
+            ColumnScan::Never => false,
+            ColumnScan::Auto => {
+                Self::suppress_duplicates_into(
+                    candidates,
+                    config.merge_radius,
+                    &mut scratch.order,
+                    &mut scratch.absorbed,
+                    &mut scratch.patterns,
+                );
+                let conclusive = rows_conclusive(&scratch.patterns);
+                if conclusive {
+                    crate::decoder::qr_decoder::record_column_scan_skip();
+                }
+                !conclusive
+            }
+        };
+
+        // Columns, for vertically-oriented finder patterns (rotated QR codes)
+        if scan_columns {
+            Self::stepped_lines(width, step, |x| {
+                if !Self::has_significant_edges_column(matrix, x, height) {
+                    return false;
+                }
+                let before = candidates.len();
+                Self::scan_column(matrix, x, height, candidates);
+                candidates.len() > before
+            });
         }
+        if config.tolerate_missing_quiet_zone {
+            candidates.extend(Self::detect_flush(matrix));
+        }
+        Self::suppress_duplicates_into(
+            candidates,
+            config.merge_radius,
+            &mut scratch.order,
+            &mut scratch.absorbed,
+            &mut scratch.patterns,
+        );
+        &scratch.patterns
+    }
 
-        // Scan every column for vertically-oriented finder patterns (rotated QR codes)
-        for x in 0..width {
-            if !Self::has_significant_edges_column(matrix, x, height) {
-                continue;
+    /// Call `scan` on lines `0..len` every `step` lines. Where it reports a
+    /// hit, the `step` lines either side are scanned one by one so the
+    /// finder is crossed several times for merging.
+    fn stepped_lines(len: usize, step: usize, mut scan: impl FnMut(usize) -> bool) {
+        let step = step.max(1);
+        let (mut unscanned, mut dense_until) = (0usize, 0usize);
+        let mut line = 0;
+        while line < len {
+            if scan(line) && step > 1 {
+                for back in unscanned.max((line + 1).saturating_sub(step))..line {
+                    scan(back);
+                }
+                dense_until = dense_until.max(line + step);
             }
-            let col_candidates = Self::scan_column(matrix, x, height);
-            candidates.extend(col_candidates);
+            unscanned = line + 1;
+            line += if line + 1 < dense_until { 1 } else { step };
         }
-
-        Self::merge_candidates(candidates)
     }
 
     /// Detect finder patterns using parallel processing
-    /// Processes rows and columns in parallel for multi-core speedup
+    /// Processes rows and columns in parallel for multi-core speedup, on at
+    /// most [`crate::DetectorConfig::max_threads`] threads
     pub fn detect_parallel(matrix: &BitMatrix) -> Vec<FinderPattern> {
         use rayon::prelude::*;
 
         let width = matrix.width();
         let height = matrix.height();
 
-        // Collect candidates from all rows in parallel
-        let all_row_candidates: Vec<Vec<FinderPattern>> = (0..height)
-            .into_par_iter()
-            .filter_map(|y| {
-                // Early termination: Skip rows with low variance
-                if !Self::has_significant_edges(matrix, y, width) {
-                    return None;
-                }
-
-                let row_candidates = Self::scan_row(matrix, y, width);
-                if row_candidates.is_empty() {
-                    None
-                } else {
-                    Some(row_candidates)
-                }
-            })
-            .collect();
+        let scan_row = |y: usize| {
+            // Early termination: Skip rows with low variance
+            if !Self::has_significant_edges(matrix, y, width) {
+                return None;
+            }
 
-        // Collect candidates from all columns in parallel
-        let all_col_candidates: Vec<Vec<FinderPattern>> = (0..width)
-            .into_par_iter()
-            .filter_map(|x| {
-                if !Self::has_significant_edges_column(matrix, x, height) {
-                    return None;
-                }
+            let mut row_candidates = Vec::new();
+            Self::scan_row(matrix, y, width, &mut row_candidates);
+            if row_candidates.is_empty() {
+                None
+            } else {
+                Some(row_candidates)
+            }
+        };
+        let scan_column = |x: usize| {
+            if !Self::has_significant_edges_column(matrix, x, height) {
+                return None;
+            }
 
-                let col_candidates = Self::scan_column(matrix, x, height);
-                if col_candidates.is_empty() {
-                    None
+            let mut col_candidates = Vec::new();
+            Self::scan_column(matrix, x, height, &mut col_candidates);
+            if col_candidates.is_empty() {
+                None
+            } else {
+                Some(col_candidates)
+            }
+        };
+
+        // Collect candidates from all rows, then all columns
+        let (all_row_candidates, all_col_candidates): (Vec<_>, Vec<_>) =
+            crate::config::with_worker_pool(|parallel| {
+                if parallel {
+                    (
+                        (0..height).into_par_iter().filter_map(scan_row).collect(),
+                        (0..width).into_par_iter().filter_map(scan_column).collect(),
+                    )
                 } else {
-                    Some(col_candidates)
+                    (
+                        (0..height).filter_map(scan_row).collect(),
+                        (0..width).filter_map(scan_column).collect(),
+                    )
                 }
-            })
-            .collect();
+            });
 
         // Flatten all candidates
         let mut candidates = Vec::new();
@@ -131,8 +426,7 @@ impl FinderDetector {
             if !Self::has_significant_edges(coarse_level, y, coarse_width) {
                 continue;
             }
-            let row_candidates = Self::scan_row(coarse_level, y, coarse_width);
-            coarse_candidates.extend(row_candidates);
+            Self::scan_row(coarse_level, y, coarse_width, &mut coarse_candidates);
         }
 
         // Also scan columns at coarse level for rotated QR codes
@@ -140,8 +434,7 @@ impl FinderDetector {
             if !Self::has_significant_edges_column(coarse_level, x, coarse_height) {
                 continue;
             }
-            let col_candidates = Self::scan_column(coarse_level, x, coarse_height);
-            coarse_candidates.extend(col_candidates);
+            Self::scan_column(coarse_level, x, coarse_height, &mut coarse_candidates);
         }
 
         // If no candidates found at coarse level, fall back to full detection
@@ -231,70 +524,64 @@ impl FinderDetector {
         transitions >= 2
     }
 
-    fn scan_row(matrix: &BitMatrix, y: usize, width: usize) -> Vec<FinderPattern> {
-        let mut candidates = Vec::new();
-        let mut run_lengths: Vec<usize> = Vec::new();
-        let mut run_colors: Vec<bool> = Vec::new();
+    fn scan_row(matrix: &BitMatrix, y: usize, width: usize, candidates: &mut Vec<FinderPattern>) {
+        let first = candidates.len();
+        let mut runs = RunWindow::default();
         let mut run_start = 0usize;
         let mut current_color = matrix.get(0, y);
 
         // Early termination 2: Max patterns per row
         const MAX_PATTERNS_PER_ROW: usize = 5;
 
-        for x in 1..width {
-            let color = matrix.get(x, y);
-
-            if color != current_color {
-                // Save completed run
-                let run_len = x - run_start;
-                run_lengths.push(run_len);
-                run_colors.push(current_color);
-
-                run_start = x;
-                current_color = color;
-
-                // Check if we have enough runs for a pattern
-                if run_colors.len() >= 5 {
-                    let end_idx = run_colors.len();
-                    let colors = &run_colors[end_idx - 5..end_idx];
-                    let lengths = &run_lengths[end_idx - 5..end_idx];
-
-                    // Pattern should be: black-white-black-white-black
-                    if colors[0] && !colors[1] && colors[2] && !colors[3] && colors[4] {
-                        // Early termination 3: Quick ratio check before full validation
-                        if Self::quick_ratio_check(lengths) {
-                            if let Some((center_x, _unit, total)) = Self::check_pattern(lengths, x)
+        // Jump edge to edge on the packed row; the run still open at the
+        // row end is never checked.
+        loop {
+            let x = matrix.run_end(run_start, y).min(width);
+            if x >= width {
+                break;
+            }
+            // Save completed run
+            let run_len = x - run_start;
+            runs.push(run_len, current_color);
+
+            run_start = x;
+            current_color = !current_color;
+
+            // Check if we have enough runs for a pattern
+            if let Some(lengths) = runs.last_five() {
+                // Pattern should be: black-white-black-white-black
+                if runs.finder_order() {
+                    // Early termination 3: Quick ratio check before full validation
+                    if Self::quick_ratio_check(lengths) {
+                        if let Some((center_x, _unit, total)) = Self::check_pattern(lengths, x) {
+                            if let Some((center_y, unit_v, fit_v)) =
+                                Self::cross_check_vertical(matrix, center_x, y, total)
                             {
-                                if let Some((center_y, unit_v)) =
-                                    Self::cross_check_vertical(matrix, center_x, y, total)
-                                {
-                                    if let Some((refined_x, unit_h)) = Self::cross_check_horizontal(
+                                let vertical = (unit_v, fit_v);
+                                candidates.push(
+                                    match Self::cross_check_horizontal(
                                         matrix, center_x, center_y, total,
                                     ) {
-                                        let module_size = (unit_h + unit_v) / 2.0;
-                                        candidates.push(FinderPattern::new(
+                                        Some((refined_x, unit_h, fit_h)) => Self::scored(
                                             refined_x,
                                             center_y,
-                                            module_size,
-                                        ));
-                                    } else {
-                                        candidates
-                                            .push(FinderPattern::new(center_x, center_y, unit_v));
-                                    }
-                                }
-
-                                // Early termination 4: Stop after finding enough patterns
-                                if candidates.len() >= MAX_PATTERNS_PER_ROW {
-                                    break;
-                                }
+                                            vertical,
+                                            Some((unit_h, fit_h)),
+                                        ),
+                                        None => Self::scored(center_x, center_y, vertical, None),
+                                    },
+                                );
+                            }
+
+                            // Early termination 4: Stop after finding enough patterns
+                            if candidates.len() - first >= MAX_PATTERNS_PER_ROW {
+                                break;
                             }
                         }
                     }
                 }
             }
         }
-
-        candidates
     }
 
     fn scan_row_in_range(
@@ -305,8 +592,7 @@ impl FinderDetector {
         max_x: usize,
     ) -> Vec<FinderPattern> {
         let mut candidates = Vec::new();
-        let mut run_lengths: Vec<usize> = Vec::new();
-        let mut run_colors: Vec<bool> = Vec::new();
+        let mut runs = RunWindow::default();
 
         // Clamp the range to valid row bounds
         let start_x = min_x.min(width - 1);
@@ -323,52 +609,47 @@ impl FinderDetector {
         // Early termination: Max patterns per row
         const MAX_PATTERNS_PER_ROW: usize = 5;
 
-        for x in (start_x + 1)..=end_x {
-            let color = matrix.get(x, y);
-
-            if color != current_color {
-                // Save completed run
-                let run_len = x - run_start;
-                run_lengths.push(run_len);
-                run_colors.push(current_color);
-
-                run_start = x;
-                current_color = color;
-
-                // Check if we have enough runs for a pattern
-                if run_colors.len() >= 5 {
-                    let end_idx = run_colors.len();
-                    let colors = &run_colors[end_idx - 5..end_idx];
-                    let lengths = &run_lengths[end_idx - 5..end_idx];
-
-                    // Pattern should be: black-white-black-white-black
-                    if colors[0] && !colors[1] && colors[2] && !colors[3] && colors[4] {
-                        // Quick ratio check before full validation
-                        if Self::quick_ratio_check(lengths) {
-                            if let Some((center_x, _unit, total)) = Self::check_pattern(lengths, x)
+        loop {
+            let x = matrix.run_end(run_start, y);
+            if x > end_x {
+                break;
+            }
+            // Save completed run
+            let run_len = x - run_start;
+            runs.push(run_len, current_color);
+
+            run_start = x;
+            current_color = !current_color;
+
+            // Check if we have enough runs for a pattern
+            if let Some(lengths) = runs.last_five() {
+                // Pattern should be: black-white-black-white-black
+                if runs.finder_order() {
+                    // Quick ratio check before full validation
+                    if Self::quick_ratio_check(lengths) {
+                        if let Some((center_x, _unit, total)) = Self::check_pattern(lengths, x) {
+                            if let Some((center_y, unit_v, fit_v)) =
+                                Self::cross_check_vertical(matrix, center_x, y, total)
                             {
-                                if let Some((center_y, unit_v)) =
-                                    Self::cross_check_vertical(matrix, center_x, y, total)
-                                {
-                                    if let Some((refined_x, unit_h)) = Self::cross_check_horizontal(
+                                let vertical = (unit_v, fit_v);
+                                candidates.push(
+                                    match Self::cross_check_horizontal(
                                         matrix, center_x, center_y, total,
                                     ) {
-                                        let module_size = (unit_h + unit_v) / 2.0;
-                                        candidates.push(FinderPattern::new(
+                                        Some((refined_x, unit_h, fit_h)) => Self::scored(
                                             refined_x,
                                             center_y,
-                                            module_size,
-                                        ));
-                                    } else {
-                                        candidates
-                                            .push(FinderPattern::new(center_x, center_y, unit_v));
-                                    }
-                                }
-
-                                // Early termination: Stop after finding enough patterns
-                                if candidates.len() >= MAX_PATTERNS_PER_ROW {
-                                    break;
-                                }
+                                            vertical,
+                                            Some((unit_h, fit_h)),
+                                        ),
+                                        None => Self::scored(center_x, center_y, vertical, None),
+                                    },
+                                );
+                            }
+
+                            // Early termination: Stop after finding enough patterns
+                            if candidates.len() >= MAX_PATTERNS_PER_ROW {
+                                break;
                             }
                         }
                     }
@@ -487,12 +768,58 @@ impl FinderDetector {
         None
     }
 
+    /// Horizontal and vertical module sizes of the finder centred at
+    /// `center`, from a 1:1:3:1:1 cross-check along each axis.
+    pub(crate) fn axis_module_sizes(matrix: &BitMatrix, center: &Point) -> Option<(f32, f32)> {
+        let (_, unit_h, _) = Self::cross_check_horizontal(matrix, center.x, center.y, 0)?;
+        let row = center.y.round();
+        if row < 0.0 {
+            return None;
+        }
+        let (_, unit_v, _) = Self::cross_check_vertical(matrix, center.x, row as usize, 0)?;
+        Some((unit_h, unit_v))
+    }
+
+    /// Fraction of the 7x7 modules around `center` that match a finder's
+    /// dark ring, light ring and dark core, sampling along the unit `axis`
+    /// and its perpendicular. Unlike the cross-checks this still scores a
+    /// finder that is smudged or partly covered.
+    pub(crate) fn template_agreement(
+        matrix: &BitMatrix,
+        center: &Point,
+        axis: (f32, f32),
+        module_size: f32,
+    ) -> f32 {
+        let (ux, uy) = (axis.0 * module_size, axis.1 * module_size);
+        let mut matches = 0usize;
+        for j in -3i32..=3 {
+            for i in -3i32..=3 {
+                let x = center.x + i as f32 * ux - j as f32 * uy;
+                let y = center.y + i as f32 * uy + j as f32 * ux;
+                if x < 0.0 || y < 0.0 {
+                    continue;
+                }
+                let (x, y) = (x.round() as usize, y.round() as usize);
+                if x >= matrix.width() || y >= matrix.height() {
+                    continue;
+                }
+                let dark = i.abs().max(j.abs()) != 2;
+                if matrix.get(x, y) == dark {
+                    matches += 1;
+                }
+            }
+        }
+        matches as f32 / 49.0
+    }
+
+    /// Returns (center, unit, fit) where `fit` is 1.0 for exact 1:1:3:1:1
+    /// ratios, falling to 0.0 at the tolerance limit.
     fn cross_check_vertical(
         matrix: &BitMatrix,
         center_x: f32,
         center_y: usize,
         total: usize,
-    ) -> Option<(f32, f32)> {
+    ) -> Option<(f32, f32, f32)> {
         let x = center_x.round() as isize;
         if x < 0 || (x as usize) >= matrix.width() {
             return None;
@@ -564,25 +891,27 @@ impl FinderDetector {
         let r5 = counts[4] as f32 / unit;
 
         const TOL: f32 = 0.7;
-        if (r1 - 1.0).abs() > TOL
-            || (r2 - 1.0).abs() > TOL
-            || (r3 - 3.0).abs() > TOL
-            || (r4 - 1.0).abs() > TOL
-            || (r5 - 1.0).abs() > TOL
-        {
+        let worst = (r1 - 1.0)
+            .abs()
+            .max((r2 - 1.0).abs())
+            .max((r3 - 3.0).abs())
+            .max((r4 - 1.0).abs())
+            .max((r5 - 1.0).abs());
+        if worst > TOL {
             return None;
         }
 
         let center = y as f32 - counts[4] as f32 - counts[3] as f32 - (counts[2] as f32 / 2.0);
-        Some((center, unit))
+        Some((center, unit, 1.0 - worst / TOL))
     }
 
+    /// Horizontal counterpart of [`Self::cross_check_vertical`].
     fn cross_check_horizontal(
         matrix: &BitMatrix,
         center_x: f32,
         center_y: f32,
         total: usize,
-    ) -> Option<(f32, f32)> {
+    ) -> Option<(f32, f32, f32)> {
         let y = center_y.round() as isize;
         if y < 0 || (y as usize) >= matrix.height() {
             return None;
@@ -654,17 +983,18 @@ impl FinderDetector {
         let r5 = counts[4] as f32 / unit;
 
         const TOL: f32 = 0.7;
-        if (r1 - 1.0).abs() > TOL
-            || (r2 - 1.0).abs() > TOL
-            || (r3 - 3.0).abs() > TOL
-            || (r4 - 1.0).abs() > TOL
-            || (r5 - 1.0).abs() > TOL
-        {
+        let worst = (r1 - 1.0)
+            .abs()
+            .max((r2 - 1.0).abs())
+            .max((r3 - 3.0).abs())
+            .max((r4 - 1.0).abs())
+            .max((r5 - 1.0).abs());
+        if worst > TOL {
             return None;
         }
 
         let center = x as f32 - counts[4] as f32 - counts[3] as f32 - (counts[2] as f32 / 2.0);
-        Some((center, unit))
+        Some((center, unit, 1.0 - worst / TOL))
     }
 
     /// Check if column has enough edge transitions to potentially contain patterns
@@ -692,14 +1022,18 @@ impl FinderDetector {
         transitions >= 2
     }
 
-    fn scan_column(matrix: &BitMatrix, x: usize, height: usize) -> Vec<FinderPattern> {
-        let mut candidates = Vec::new();
+    fn scan_column(
+        matrix: &BitMatrix,
+        x: usize,
+        height: usize,
+        candidates: &mut Vec<FinderPattern>,
+    ) {
         if height == 0 {
-            return candidates;
+            return;
         }
+        let first = candidates.len();
 
-        let mut run_lengths: Vec<usize> = Vec::new();
-        let mut run_colors: Vec<bool> = Vec::new();
+        let mut runs = RunWindow::default();
         let mut run_start = 0usize;
         let mut current_color = matrix.get(x, 0);
 
@@ -710,49 +1044,40 @@ impl FinderDetector {
 
             if color != current_color {
                 let run_len = y - run_start;
-                run_lengths.push(run_len);
-                run_colors.push(current_color);
+                runs.push(run_len, current_color);
 
                 run_start = y;
                 current_color = color;
 
-                if run_colors.len() >= 5 {
-                    let end_idx = run_colors.len();
-                    let colors = &run_colors[end_idx - 5..end_idx];
-                    let lengths = &run_lengths[end_idx - 5..end_idx];
-
+                if let Some(lengths) = runs.last_five() {
                     // Pattern should be: black-white-black-white-black
-                    if colors[0]
-                        && !colors[1]
-                        && colors[2]
-                        && !colors[3]
-                        && colors[4]
-                        && Self::quick_ratio_check(lengths)
-                    {
+                    if runs.finder_order() && Self::quick_ratio_check(lengths) {
                         if let Some((center_y, _unit, total)) = Self::check_pattern(lengths, y) {
                             // Cross-check horizontally first (primary axis is vertical)
-                            if let Some((center_x, unit_h)) =
+                            if let Some((center_x, unit_h, fit_h)) =
                                 Self::cross_check_horizontal(matrix, x as f32, center_y, total)
                             {
                                 // Then refine vertically
-                                if let Some((refined_y, unit_v)) = Self::cross_check_vertical(
-                                    matrix,
-                                    center_x,
-                                    center_y.round() as usize,
-                                    total,
-                                ) {
-                                    let module_size = (unit_h + unit_v) / 2.0;
-                                    candidates.push(FinderPattern::new(
+                                let horizontal = (unit_h, fit_h);
+                                candidates.push(
+                                    match Self::cross_check_vertical(
+                                        matrix,
                                         center_x,
-                                        refined_y,
-                                        module_size,
-                                    ));
-                                } else {
-                                    candidates.push(FinderPattern::new(center_x, center_y, unit_h));
-                                }
+                                        center_y.round() as usize,
+                                        total,
+                                    ) {
+                                        Some((refined_y, unit_v, fit_v)) => Self::scored(
+                                            center_x,
+                                            refined_y,
+                                            horizontal,
+                                            Some((unit_v, fit_v)),
+                                        ),
+                                        None => Self::scored(center_x, center_y, horizontal, None),
+                                    },
+                                );
                             }
 
-                            if candidates.len() >= MAX_PATTERNS_PER_COL {
+                            if candidates.len() - first >= MAX_PATTERNS_PER_COL {
                                 break;
                             }
                         }
@@ -760,8 +1085,6 @@ impl FinderDetector {
                 }
             }
         }
-
-        candidates
     }
 
     fn scan_column_in_range(
@@ -776,8 +1099,7 @@ impl FinderDetector {
             return candidates;
         }
 
-        let mut run_lengths: Vec<usize> = Vec::new();
-        let mut run_colors: Vec<bool> = Vec::new();
+        let mut runs = RunWindow::default();
 
         let start_y = min_y.min(height - 1);
         let end_y = max_y.min(height - 1);
@@ -796,43 +1118,35 @@ impl FinderDetector {
 
             if color != current_color {
                 let run_len = y - run_start;
-                run_lengths.push(run_len);
-                run_colors.push(current_color);
+                runs.push(run_len, current_color);
 
                 run_start = y;
                 current_color = color;
 
-                if run_colors.len() >= 5 {
-                    let end_idx = run_colors.len();
-                    let colors = &run_colors[end_idx - 5..end_idx];
-                    let lengths = &run_lengths[end_idx - 5..end_idx];
-
-                    if colors[0]
-                        && !colors[1]
-                        && colors[2]
-                        && !colors[3]
-                        && colors[4]
-                        && Self::quick_ratio_check(lengths)
-                    {
+                if let Some(lengths) = runs.last_five() {
+                    // Pattern should be: black-white-black-white-black
+                    if runs.finder_order() && Self::quick_ratio_check(lengths) {
                         if let Some((center_y, _unit, total)) = Self::check_pattern(lengths, y) {
-                            if let Some((center_x, unit_h)) =
+                            if let Some((center_x, unit_h, fit_h)) =
                                 Self::cross_check_horizontal(matrix, x as f32, center_y, total)
                             {
-                                if let Some((refined_y, unit_v)) = Self::cross_check_vertical(
-                                    matrix,
-                                    center_x,
-                                    center_y.round() as usize,
-                                    total,
-                                ) {
-                                    let module_size = (unit_h + unit_v) / 2.0;
-                                    candidates.push(FinderPattern::new(
+                                let horizontal = (unit_h, fit_h);
+                                candidates.push(
+                                    match Self::cross_check_vertical(
+                                        matrix,
                                         center_x,
-                                        refined_y,
-                                        module_size,
-                                    ));
-                                } else {
-                                    candidates.push(FinderPattern::new(center_x, center_y, unit_h));
-                                }
+                                        center_y.round() as usize,
+                                        total,
+                                    ) {
+                                        Some((refined_y, unit_v, fit_v)) => Self::scored(
+                                            center_x,
+                                            refined_y,
+                                            horizontal,
+                                            Some((unit_v, fit_v)),
+                                        ),
+                                        None => Self::scored(center_x, center_y, horizontal, None),
+                                    },
+                                );
                             }
 
                             if candidates.len() >= MAX_PATTERNS_PER_COL {
@@ -847,36 +1161,93 @@ impl FinderDetector {
         candidates
     }
 
-    fn merge_candidates(candidates: Vec<FinderPattern>) -> Vec<FinderPattern> {
-        let mut merged: Vec<FinderPattern> = Vec::new();
-
-        for candidate in candidates {
-            let mut found = false;
-            for existing in &mut merged {
-                let dx = candidate.center.x - existing.center.x;
-                let dy = candidate.center.y - existing.center.y;
-                let dist_sq = dx * dx + dy * dy;
-                let merge_dist = (existing.module_size + candidate.module_size) * 2.5;
-                let merge_dist_sq = merge_dist * merge_dist;
-
-                if dist_sq < merge_dist_sq {
-                    let new_x = (existing.center.x + candidate.center.x) / 2.0;
-                    let new_y = (existing.center.y + candidate.center.y) / 2.0;
-                    let new_module = (existing.module_size + candidate.module_size) / 2.0;
-                    *existing = FinderPattern::new(new_x, new_y, new_module);
-                    found = true;
-                    break;
-                }
+    /// Candidate from one or two cross-checks, each given as (unit, fit).
+    /// Quality is the weaker fit scaled by how well the two module estimates
+    /// agree; a single cross-check is trusted half as much.
+    fn scored(x: f32, y: f32, primary: (f32, f32), secondary: Option<(f32, f32)>) -> FinderPattern {
+        let (module_size, quality) = match secondary {
+            Some((unit, fit)) => {
+                let agreement = primary.0.min(unit) / primary.0.max(unit);
+                ((primary.0 + unit) / 2.0, primary.1.min(fit) * agreement)
             }
+            None => (primary.0, primary.1 * 0.5),
+        };
+        FinderPattern::with_quality(x, y, module_size, quality)
+    }
 
-            if !found {
-                merged.push(candidate);
-            }
-        }
+    fn merge_candidates(candidates: Vec<FinderPattern>) -> Vec<FinderPattern> {
+        Self::suppress_duplicates(candidates, FinderConfig::default().merge_radius)
+    }
 
+    /// Quality-weighted non-maximum suppression. The best remaining candidate
+    /// absorbs every other candidate within `radius` module sizes of it; the
+    /// survivor sits at the quality-weighted mean of the cluster, which keeps
+    /// sub-pixel accuracy without letting weak duplicates drag it around.
+    fn suppress_duplicates(candidates: Vec<FinderPattern>, radius: f32) -> Vec<FinderPattern> {
+        let mut merged = Vec::new();
+        Self::suppress_duplicates_into(
+            &candidates,
+            radius,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            &mut merged,
+        );
         merged
     }
 
+    /// [`Self::suppress_duplicates`] with caller-owned working buffers.
+    fn suppress_duplicates_into(
+        candidates: &[FinderPattern],
+        radius: f32,
+        order: &mut Vec<u32>,
+        absorbed: &mut Vec<bool>,
+        merged: &mut Vec<FinderPattern>,
+    ) {
+        // Best first; the index tie-break keeps equal-quality candidates in
+        // scan order without the buffer a stable sort would allocate.
+        order.clear();
+        order.extend(0..candidates.len() as u32);
+        order.sort_unstable_by(|&a, &b| {
+            candidates[b as usize]
+                .quality
+                .total_cmp(&candidates[a as usize].quality)
+                .then(a.cmp(&b))
+        });
+        absorbed.clear();
+        absorbed.resize(candidates.len(), false);
+        merged.clear();
+
+        for i in 0..order.len() {
+            if absorbed[i] {
+                continue;
+            }
+            let anchor = &candidates[order[i] as usize];
+            let (mut sx, mut sy, mut sm, mut sw) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+            for j in i..order.len() {
+                if absorbed[j] {
+                    continue;
+                }
+                let candidate = &candidates[order[j] as usize];
+                let dx = candidate.center.x - anchor.center.x;
+                let dy = candidate.center.y - anchor.center.y;
+                let merge_dist = radius * (anchor.module_size + candidate.module_size) / 2.0;
+                if j != i && dx * dx + dy * dy >= merge_dist * merge_dist {
+                    continue;
+                }
+                absorbed[j] = true;
+                let w = candidate.quality.max(0.05);
+                sx += candidate.center.x * w;
+                sy += candidate.center.y * w;
+                sm += candidate.module_size * w;
+                sw += w;
+            }
+            merged.push(FinderPattern {
+                inverted: anchor.inverted,
+                ..FinderPattern::with_quality(sx / sw, sy / sw, sm / sw, anchor.quality)
+            });
+        }
+    }
+
     /// Detect finder patterns using connected components approach
     /// O(k) where k = number of black regions instead of O(n²)
     pub fn detect_with_connected_components(matrix: &BitMatrix) -> Vec<FinderPattern> {
@@ -922,6 +1293,185 @@ impl FinderDetector {
 
         Self::merge_candidates(candidates)
     }
+
+    /// Detect finder patterns with the given options.
+    pub fn detect_with_config(matrix: &BitMatrix, config: &FinderConfig) -> Vec<FinderPattern> {
+        let mut scratch = FinderScratch::new();
+        Self::detect_into_with_config(matrix, config, &mut scratch);
+        scratch.patterns
+    }
+
+    /// Add quiet-zone-less candidates to an existing detection result.
+    pub(crate) fn with_flush_candidates(
+        matrix: &BitMatrix,
+        mut candidates: Vec<FinderPattern>,
+    ) -> Vec<FinderPattern> {
+        candidates.extend(Self::detect_flush(matrix));
+        Self::merge_candidates(candidates)
+    }
+
+    /// Add candidates with reversed polarity, tagged
+    /// [`FinderPattern::inverted`], to an existing detection result. The
+    /// inverted matrix is scanned both ways, since the etched rings usually
+    /// run straight into the dark surround with no quiet zone of their own.
+    pub(crate) fn with_inverted_candidates(
+        matrix: &BitMatrix,
+        config: &FinderConfig,
+        mut candidates: Vec<FinderPattern>,
+    ) -> Vec<FinderPattern> {
+        let mut inverted = matrix.clone();
+        inverted.invert();
+        let mut found = Self::detect_with_config(&inverted, config);
+        found.extend(Self::detect_flush(&inverted));
+        candidates.extend(found.into_iter().map(|p| FinderPattern {
+            inverted: true,
+            ..p
+        }));
+        Self::merge_candidates(candidates)
+    }
+
+    /// Scan for 1:1:3:1:1 patterns where only the inner 1:3:1 core is held to
+    /// ratio; the outer dark runs may be clipped by the image edge (the final
+    /// run of a line is never closed by a transition) or merged into dark
+    /// surroundings.
+    fn detect_flush(matrix: &BitMatrix) -> Vec<FinderPattern> {
+        let width = matrix.width();
+        let height = matrix.height();
+        let mut candidates = Vec::new();
+        if width == 0 || height == 0 {
+            return candidates;
+        }
+
+        for y in 0..height {
+            for (cx, unit) in Self::scan_line_flush(|x| matrix.get(x, y), width) {
+                let x = cx.round() as usize;
+                let Some((cy, unit_v)) = Self::flush_cross_check(|i| matrix.get(x, i), height, y)
+                else {
+                    continue;
+                };
+                let row = cy.round() as usize;
+                let (cx, unit_h) =
+                    Self::flush_cross_check(|i| matrix.get(i, row), width, x).unwrap_or((cx, unit));
+                if unit_v < unit * 0.5 || unit_v > unit * 2.0 {
+                    continue;
+                }
+                candidates.push(FinderPattern::new(cx, cy, (unit_h + unit_v) / 2.0));
+            }
+        }
+        for x in 0..width {
+            for (cy, unit) in Self::scan_line_flush(|y| matrix.get(x, y), height) {
+                let y = cy.round() as usize;
+                let Some((cx, unit_h)) = Self::flush_cross_check(|i| matrix.get(i, y), width, x)
+                else {
+                    continue;
+                };
+                if unit_h < unit * 0.5 || unit_h > unit * 2.0 {
+                    continue;
+                }
+                candidates.push(FinderPattern::new(cx, cy, (unit_h + unit) / 2.0));
+            }
+        }
+
+        Self::merge_candidates(candidates)
+    }
+
+    /// Run-length scan of one line, including the trailing run that touches
+    /// the far edge. Returns (center, unit) for every dark-light-dark-light-dark
+    /// window whose core passes [`Self::flush_unit`].
+    fn scan_line_flush(get: impl Fn(usize) -> bool, len: usize) -> Vec<(f32, f32)> {
+        let mut runs: Vec<(usize, usize, bool)> = Vec::new();
+        let mut start = 0usize;
+        let mut color = get(0);
+        for i in 1..=len {
+            if i == len || get(i) != color {
+                runs.push((start, i - start, color));
+                if i < len {
+                    start = i;
+                    color = !color;
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        for w in runs.windows(5) {
+            if !(w[0].2 && !w[1].2 && w[2].2 && !w[3].2 && w[4].2) {
+                continue;
+            }
+            let counts = [w[0].1, w[1].1, w[2].1, w[3].1, w[4].1];
+            if let Some(unit) = Self::flush_unit(&counts) {
+                found.push((w[2].0 as f32 + w[2].1 as f32 / 2.0, unit));
+            }
+        }
+        found
+    }
+
+    /// Cross-check through `center` along one axis. The outer dark runs stop
+    /// at the edge or after a bounded distance instead of requiring light.
+    fn flush_cross_check(
+        get: impl Fn(usize) -> bool,
+        len: usize,
+        center: usize,
+    ) -> Option<(f32, f32)> {
+        if center >= len || !get(center) {
+            return None;
+        }
+        let mut lo = center;
+        while lo > 0 && get(lo - 1) {
+            lo -= 1;
+        }
+        let mut hi = center + 1;
+        while hi < len && get(hi) {
+            hi += 1;
+        }
+        let mut wlo = lo;
+        while wlo > 0 && !get(wlo - 1) {
+            wlo -= 1;
+        }
+        let mut whi = hi;
+        while whi < len && !get(whi) {
+            whi += 1;
+        }
+        // The dark outer ring must exist on both sides, even if clipped.
+        if wlo == 0 || whi == len {
+            return None;
+        }
+        let cap = (hi - lo) * 2;
+        let mut olo = wlo;
+        while olo > 0 && wlo - olo < cap && get(olo - 1) {
+            olo -= 1;
+        }
+        let mut ohi = whi;
+        while ohi < len && ohi - whi < cap && get(ohi) {
+            ohi += 1;
+        }
+
+        let counts = [wlo - olo, lo - wlo, hi - lo, whi - hi, ohi - whi];
+        let unit = Self::flush_unit(&counts)?;
+        Some(((lo + hi) as f32 / 2.0, unit))
+    }
+
+    /// Module size from the 1:3:1 core; outer runs only need to be at least
+    /// half a module wide.
+    fn flush_unit(counts: &[usize; 5]) -> Option<f32> {
+        let core = counts[1] + counts[2] + counts[3];
+        if !(5..=1400).contains(&core) {
+            return None;
+        }
+        let unit = core as f32 / 5.0;
+
+        const TOL: f32 = 0.5;
+        let r2 = counts[1] as f32 / unit;
+        let r3 = counts[2] as f32 / unit;
+        let r4 = counts[3] as f32 / unit;
+        if (r2 - 1.0).abs() > TOL || (r3 - 3.0).abs() > TOL || (r4 - 1.0).abs() > TOL {
+            return None;
+        }
+        let outer_min = unit * (1.0 - TOL);
+        if (counts[0] as f32) < outer_min || (counts[4] as f32) < outer_min {
+            return None;
+        }
+        Some(unit)
+    }
 }
 
 #[cfg(test)]
@@ -978,6 +1528,216 @@ mod tests {
         assert!(!FinderDetector::quick_ratio_check(&bad_center));
     }
 
+    fn draw_finder(matrix: &mut BitMatrix, left: usize, top: usize, u: usize) {
+        for my in 0..7 {
+            for mx in 0..7 {
+                let is_border = mx == 0 || mx == 6 || my == 0 || my == 6;
+                let is_center = (2..=4).contains(&mx) && (2..=4).contains(&my);
+                if is_border || is_center {
+                    for y in top + my * u..top + (my + 1) * u {
+                        for x in left + mx * u..left + (mx + 1) * u {
+                            matrix.set(x, y, true);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn has_pattern_near(patterns: &[FinderPattern], x: f32, y: f32, tol: f32) -> bool {
+        patterns
+            .iter()
+            .any(|p| (p.center.x - x).abs() < tol && (p.center.y - y).abs() < tol)
+    }
+
+    #[test]
+    fn test_tolerate_missing_quiet_zone() {
+        let u = 3;
+        let tolerant = FinderConfig {
+            tolerate_missing_quiet_zone: true,
+            ..Default::default()
+        };
+
+        // Flush against the bottom-right image corner.
+        let mut flush = BitMatrix::new(40, 40);
+        draw_finder(&mut flush, 40 - 7 * u, 40 - 7 * u, u);
+        let expected = 40.0 - 3.5 * u as f32;
+        assert!(!has_pattern_near(
+            &FinderDetector::detect(&flush),
+            expected,
+            expected,
+            3.0
+        ));
+        let patterns = FinderDetector::detect_with_config(&flush, &tolerant);
+        assert!(
+            has_pattern_near(&patterns, expected, expected, 3.0),
+            "{:?}",
+            patterns
+        );
+
+        // Outer ring merged into adjacent dark content on the left and top.
+        let mut merged = BitMatrix::new(60, 60);
+        for y in 0..60 {
+            for x in 0..20 {
+                merged.set(x, y, true);
+                merged.set(y, x, true);
+            }
+        }
+        draw_finder(&mut merged, 20, 20, u);
+        let expected = 20.0 + 3.5 * u as f32;
+        assert!(!has_pattern_near(
+            &FinderDetector::detect(&merged),
+            expected,
+            expected,
+            3.0
+        ));
+        let patterns = FinderDetector::detect_with_config(&merged, &tolerant);
+        assert!(
+            has_pattern_near(&patterns, expected, expected, 3.0),
+            "{:?}",
+            patterns
+        );
+    }
+
+    #[test]
+    fn test_suppress_duplicates_prefers_quality() {
+        let candidates = vec![
+            FinderPattern::with_quality(10.0, 10.0, 2.0, 0.1),
+            FinderPattern::with_quality(12.0, 10.0, 2.0, 0.9),
+            FinderPattern::with_quality(12.5, 10.5, 2.0, 0.9),
+            FinderPattern::with_quality(80.0, 10.0, 2.0, 0.4),
+        ];
+        let merged = FinderDetector::suppress_duplicates(candidates.clone(), 5.0);
+        assert_eq!(merged.len(), 2);
+        // The weak outlier barely moves the strong pair's mean.
+        assert!((merged[0].center.x - 12.1).abs() < 0.1, "{:?}", merged[0]);
+        assert_eq!(merged[0].quality, 0.9);
+        assert!(has_pattern_near(&merged, 80.0, 10.0, 0.01));
+
+        assert_eq!(
+            FinderDetector::suppress_duplicates(candidates, 0.0).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_detected_finder_is_scored_and_deduplicated() {
+        let u = 4;
+        let mut matrix = BitMatrix::new(60, 60);
+        draw_finder(&mut matrix, 10, 10, u);
+        let patterns = FinderDetector::detect(&matrix);
+        assert_eq!(patterns.len(), 1, "{:?}", patterns);
+        let center = 10.0 + 3.5 * u as f32;
+        assert!(has_pattern_near(&patterns, center, center, 1.0));
+        assert!(patterns[0].quality > 0.8, "{:?}", patterns[0]);
+    }
+
+    #[test]
+    fn test_edge_sharpness_drops_with_blur() {
+        let (u, w) = (4usize, 60usize);
+        let mut matrix = BitMatrix::new(w, w);
+        draw_finder(&mut matrix, 10, 10, u);
+        let crisp: Vec<u8> = (0..w * w)
+            .map(|i| if matrix.get(i % w, i / w) { 20 } else { 230 })
+            .collect();
+        // 5x5 box blur spreads each edge over several pixels.
+        let mut blurred = crisp.clone();
+        for y in 2..w - 2 {
+            for x in 2..w - 2 {
+                let mut sum = 0u32;
+                for dy in 0..5 {
+                    for dx in 0..5 {
+                        sum += crisp[(y + dy - 2) * w + x + dx - 2] as u32;
+                    }
+                }
+                blurred[y * w + x] = (sum / 25) as u8;
+            }
+        }
+        let center = 10.0 + 3.5 * u as f32;
+        let pattern = FinderPattern::new(center, center, u as f32);
+        let sharp = pattern.edge_sharpness(&crisp, w, w).unwrap();
+        let soft = pattern.edge_sharpness(&blurred, w, w).unwrap();
+        assert!(sharp > 0.95, "{sharp}");
+        assert!(soft < 0.5, "{soft}");
+        // Flat surroundings give nothing to measure.
+        assert_eq!(pattern.edge_sharpness(&[128; 3600], w, w), None);
+    }
+
+    #[test]
+    fn test_stepped_lines_densify_around_hits() {
+        let mut visited = Vec::new();
+        FinderDetector::stepped_lines(40, 6, |line| {
+            visited.push(line);
+            line == 18
+        });
+        // Every 6th line, plus the five either side of the hit at 18.
+        let mut expected = vec![0, 6, 12, 18, 13, 14, 15, 16, 17, 19, 20, 21, 22, 23, 29, 35];
+        assert_eq!(visited, expected);
+        visited.clear();
+        FinderDetector::stepped_lines(5, 1, |line| {
+            visited.push(line);
+            true
+        });
+        expected = vec![0, 1, 2, 3, 4];
+        assert_eq!(visited, expected);
+    }
+
+    #[test]
+    fn test_stepped_scan_matches_full_scan_for_large_modules() {
+        let u = 10;
+        let mut matrix = BitMatrix::new(300, 300);
+        draw_finder(&mut matrix, 20, 20, u);
+        draw_finder(&mut matrix, 200, 23, u);
+        draw_finder(&mut matrix, 22, 210, u);
+        let full = FinderDetector::detect(&matrix);
+        let config = FinderConfig {
+            min_module_size: Some(u as f32),
+            ..Default::default()
+        };
+        let stepped = FinderDetector::detect_with_config(&matrix, &config);
+        assert_eq!(stepped.len(), 3, "{stepped:?}");
+        assert_eq!(full.len(), 3, "{full:?}");
+        for p in &full {
+            assert!(
+                has_pattern_near(&stepped, p.center.x, p.center.y, 1.0),
+                "{p:?}"
+            );
+        }
+        assert_eq!(line_step(f32::NAN), 1);
+        assert_eq!(line_step(0.5), 1);
+    }
+
+    #[test]
+    fn test_column_scan_skipped_for_upright_finders() {
+        let u = 4;
+        let mut matrix = BitMatrix::new(200, 200);
+        draw_finder(&mut matrix, 20, 20, u);
+        draw_finder(&mut matrix, 140, 20, u);
+        draw_finder(&mut matrix, 20, 140, u);
+        let always = FinderConfig {
+            column_scan: ColumnScan::Always,
+            ..Default::default()
+        };
+        let full = FinderDetector::detect_with_config(&matrix, &always);
+        let (auto, counters) =
+            crate::decoder::qr_decoder::with_decode_counters(|| FinderDetector::detect(&matrix));
+        assert_eq!(counters.column_scans_skipped, 1);
+        assert_eq!(auto.len(), full.len(), "{auto:?}");
+        for p in &full {
+            assert!(
+                has_pattern_near(&auto, p.center.x, p.center.y, 1.0),
+                "{p:?}"
+            );
+        }
+
+        // A lone finder is not conclusive, so columns are still scanned.
+        let mut lone = BitMatrix::new(60, 60);
+        draw_finder(&mut lone, 10, 10, u);
+        let (_, counters) =
+            crate::decoder::qr_decoder::with_decode_counters(|| FinderDetector::detect(&lone));
+        assert_eq!(counters.column_scans_skipped, 0);
+    }
+
     #[test]
     fn test_detect_zero_height_matrix() {
         let matrix = BitMatrix::new(8, 0);
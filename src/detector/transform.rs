@@ -1,5 +1,7 @@
 /// Sample grid extraction from perspective-corrected QR code
-use crate::models::{BitMatrix, Point};
+use crate::detector::alignment::get_alignment_positions;
+use crate::models::{BitMatrix, ImagePoint, ModulePoint, Point};
+use crate::utils::geometry::PerspectiveTransform;
 
 /// Extract sample grid from transformed image
 pub fn extract_sample_grid(
@@ -13,3 +15,381 @@ pub fn extract_sample_grid(
     // TODO: Implement sample grid extraction with sub-pixel sampling
     BitMatrix::new(dimension, dimension)
 }
+
+/// Iterative homography refinement from measured grid features
+///
+/// Each iteration projects the finder, alignment and timing modules through
+/// the current transform, searches the binary image for where each feature
+/// actually sits, refits the homography to all measured positions and
+/// repeats until the mean residual stops dropping.
+#[derive(Debug, Clone, Copy)]
+pub struct GridRefiner {
+    /// Maximum sample/measure/refit iterations
+    pub max_iterations: usize,
+    /// Stop once an iteration lowers the mean residual by less than this (pixels)
+    pub min_residual_drop: f32,
+}
+
+impl Default for GridRefiner {
+    fn default() -> Self {
+        Self {
+            max_iterations: 4,
+            min_residual_drop: 0.05,
+        }
+    }
+}
+
+/// Statistics for one [`GridRefiner::refine`] run
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RefineTelemetry {
+    /// Measure/refit iterations performed
+    pub iterations: usize,
+    /// Features located in the final measurement
+    pub features: usize,
+    /// Mean feature residual under the input transform (pixels)
+    pub initial_residual: f32,
+    /// Mean feature residual under the returned transform (pixels)
+    pub final_residual: f32,
+}
+
+impl RefineTelemetry {
+    /// Residual reduction achieved, in pixels
+    pub fn residual_drop(&self) -> f32 {
+        (self.initial_residual - self.final_residual).max(0.0)
+    }
+
+    /// Whether the returned transform fits the measured features better
+    pub fn improved(&self) -> bool {
+        self.iterations > 0 && self.final_residual < self.initial_residual
+    }
+}
+
+/// Search step when locating a feature, in modules.
+const SEARCH_STEP: f32 = 0.25;
+
+/// A grid feature: its center, a template of `(offset, dark)` modules
+/// around it, the search radius in modules and the largest template
+/// mismatch accepted. Offsets and the axis are in modules.
+struct Feature {
+    center: ModulePoint,
+    template: Vec<((f32, f32), bool)>,
+    radius: f32,
+    max_mismatch: usize,
+    /// Timing features are only observable along their line.
+    axis: Option<(f32, f32)>,
+}
+
+impl GridRefiner {
+    /// Refine `transform` (module space to image space) for a symbol of
+    /// `version`, returning the best transform found and its statistics.
+    ///
+    /// The input transform is returned unchanged when too few features can
+    /// be located or no iteration improves the fit.
+    pub fn refine(
+        &self,
+        binary: &BitMatrix,
+        transform: &PerspectiveTransform,
+        version: u8,
+    ) -> (PerspectiveTransform, RefineTelemetry) {
+        let features = grid_features(version);
+        let mut telemetry = RefineTelemetry::default();
+        let Some((mut src, mut dst, mut residual)) = measure(binary, transform, &features) else {
+            return (*transform, telemetry);
+        };
+        telemetry.initial_residual = residual;
+        telemetry.final_residual = residual;
+        telemetry.features = src.len();
+
+        let mut best = *transform;
+        for _ in 0..self.max_iterations {
+            if src.len() < 5 {
+                break;
+            }
+            let Some(candidate) =
+                PerspectiveTransform::module_to_image(&src, &dst, transform.lens())
+            else {
+                break;
+            };
+            telemetry.iterations += 1;
+            let Some((next_src, next_dst, next_residual)) = measure(binary, &candidate, &features)
+            else {
+                break;
+            };
+            if next_residual >= residual {
+                break;
+            }
+            let drop = residual - next_residual;
+            best = candidate;
+            (src, dst, residual) = (next_src, next_dst, next_residual);
+            telemetry.final_residual = residual;
+            telemetry.features = src.len();
+            if drop < self.min_residual_drop {
+                break;
+            }
+        }
+        (best, telemetry)
+    }
+}
+
+/// Finder centers, alignment centers and timing modules for `version`.
+fn grid_features(version: u8) -> Vec<Feature> {
+    let dimension = 17 + 4 * version as usize;
+    let d = dimension as f32;
+    let mut features = Vec::new();
+
+    let finder_template = square_template(3, |ring| ring != 2);
+    for (cx, cy) in [(3.5, 3.5), (d - 3.5, 3.5), (3.5, d - 3.5)] {
+        features.push(Feature {
+            center: ModulePoint::new(cx, cy),
+            template: finder_template.clone(),
+            radius: 1.5,
+            max_mismatch: 8,
+            axis: None,
+        });
+    }
+
+    let alignment_template = square_template(2, |ring| ring != 1);
+    for (row, col) in get_alignment_positions(version) {
+        features.push(Feature {
+            center: ModulePoint::center_of(col, row),
+            template: alignment_template.clone(),
+            radius: 1.5,
+            max_mismatch: 4,
+            axis: None,
+        });
+    }
+
+    // Every other timing module, with a window of five alternating modules
+    // along the line. The period is two modules, so search under one.
+    for k in (10..dimension.saturating_sub(10)).step_by(2) {
+        for horizontal in [true, false] {
+            let axis = if horizontal { (1.0, 0.0) } else { (0.0, 1.0) };
+            let template = (-2i32..=2)
+                .map(|i| {
+                    let offset = (axis.0 * i as f32, axis.1 * i as f32);
+                    (offset, (k as i32 + i) % 2 == 0)
+                })
+                .collect();
+            let center = if horizontal {
+                ModulePoint::center_of(k, 6)
+            } else {
+                ModulePoint::center_of(6, k)
+            };
+            features.push(Feature {
+                center,
+                template,
+                radius: 0.75,
+                max_mismatch: 0,
+                axis: Some(axis),
+            });
+        }
+    }
+    features
+}
+
+/// Square template of `(2 * half + 1)^2` modules, dark where `dark(ring)`
+/// holds for the module's Chebyshev distance from the center.
+fn square_template(half: i32, dark: impl Fn(i32) -> bool) -> Vec<((f32, f32), bool)> {
+    let mut template = Vec::new();
+    for j in -half..=half {
+        for i in -half..=half {
+            let ring = i.abs().max(j.abs());
+            template.push(((i as f32, j as f32), dark(ring)));
+        }
+    }
+    template
+}
+
+/// Locate every feature under `transform`, returning the feature centers,
+/// their measured image positions and the mean residual in pixels.
+fn measure(
+    binary: &BitMatrix,
+    transform: &PerspectiveTransform,
+    features: &[Feature],
+) -> Option<(Vec<ModulePoint>, Vec<ImagePoint>, f32)> {
+    let mut src = Vec::new();
+    let mut dst = Vec::new();
+    let mut residual = 0.0f32;
+    for feature in features {
+        let Some((du, dv)) = locate(binary, transform, feature) else {
+            continue;
+        };
+        let predicted = transform.to_image(feature.center);
+        let measured = transform.to_image(feature.center.offset(du, dv));
+        residual += predicted.distance(&measured);
+        src.push(feature.center);
+        dst.push(measured);
+    }
+    if src.len() < 4 {
+        return None;
+    }
+    let residual = residual / src.len() as f32;
+    Some((src, dst, residual))
+}
+
+/// Offset of `feature` from its predicted position, in modules: the
+/// centroid of all search offsets tied for the fewest template mismatches.
+fn locate(
+    binary: &BitMatrix,
+    transform: &PerspectiveTransform,
+    feature: &Feature,
+) -> Option<(f32, f32)> {
+    let steps = (feature.radius / SEARCH_STEP).round() as i32;
+    let mut best = usize::MAX;
+    let mut sum = (0.0f32, 0.0f32);
+    let mut ties = 0usize;
+    for sv in -steps..=steps {
+        for su in -steps..=steps {
+            let (du, dv) = match feature.axis {
+                Some(_) if sv != 0 => continue,
+                Some((ax, ay)) => (ax * su as f32 * SEARCH_STEP, ay * su as f32 * SEARCH_STEP),
+                None => (su as f32 * SEARCH_STEP, sv as f32 * SEARCH_STEP),
+            };
+            let mut mismatch = 0usize;
+            for &((ox, oy), dark) in &feature.template {
+                let p = transform.to_image(feature.center.offset(du + ox, dv + oy));
+                if dark_at(binary, p) != Some(dark) {
+                    mismatch += 1;
+                }
+                if mismatch > best {
+                    break;
+                }
+            }
+            if mismatch < best {
+                best = mismatch;
+                sum = (du, dv);
+                ties = 1;
+            } else if mismatch == best {
+                sum = (sum.0 + du, sum.1 + dv);
+                ties += 1;
+            }
+        }
+    }
+    (best <= feature.max_mismatch && ties > 0).then(|| (sum.0 / ties as f32, sum.1 / ties as f32))
+}
+
+fn dark_at(binary: &BitMatrix, p: ImagePoint) -> Option<bool> {
+    if !p.x.is_finite() || !p.y.is_finite() || p.x < 0.0 || p.y < 0.0 {
+        return None;
+    }
+    let (x, y) = (p.x as usize, p.y as usize);
+    (x < binary.width() && y < binary.height()).then(|| binary.get(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Binary rendering of a version-7 symbol's function patterns (data
+    /// modules pseudo-random) through `truth`.
+    fn render(truth: &PerspectiveTransform, width: usize, height: usize) -> BitMatrix {
+        let version = 7u8;
+        let dim = 17 + 4 * version as usize;
+        let mut modules = vec![false; dim * dim];
+        for y in 0..dim {
+            for x in 0..dim {
+                modules[y * dim + x] = (x * 31 + y * 17 + x * y) % 7 < 3;
+            }
+        }
+        let mut paint = |cx: i32, cy: i32, half: i32, dark: &dyn Fn(i32) -> bool| {
+            for j in -(half + 1)..=(half + 1) {
+                for i in -(half + 1)..=(half + 1) {
+                    let (x, y) = (cx + i, cy + j);
+                    if x >= 0 && y >= 0 && (x as usize) < dim && (y as usize) < dim {
+                        let ring = i.abs().max(j.abs());
+                        modules[y as usize * dim + x as usize] = ring <= half && dark(ring);
+                    }
+                }
+            }
+        };
+        let last = dim as i32 - 4;
+        for (cx, cy) in [(3, 3), (last, 3), (3, last)] {
+            paint(cx, cy, 3, &|ring| ring != 2);
+        }
+        for (row, col) in get_alignment_positions(version) {
+            paint(col as i32, row as i32, 2, &|ring| ring != 1);
+        }
+        for k in 8..dim - 8 {
+            modules[6 * dim + k] = k % 2 == 0;
+            modules[k * dim + 6] = k % 2 == 0;
+        }
+
+        let d = dim as f32;
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(d, 0.0),
+            Point::new(0.0, d),
+            Point::new(d, d),
+        ];
+        let dst = corners.map(|c| truth.transform(&c));
+        let inverse = PerspectiveTransform::from_points(&dst, &corners).unwrap();
+        let mut binary = BitMatrix::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let m = inverse.transform(&Point::new(x as f32 + 0.5, y as f32 + 0.5));
+                if m.x >= 0.0 && m.y >= 0.0 && m.x < d && m.y < d {
+                    binary.set(x, y, modules[m.y as usize * dim + m.x as usize]);
+                }
+            }
+        }
+        binary
+    }
+
+    fn grid_error(a: &PerspectiveTransform, b: &PerspectiveTransform, dim: usize) -> f32 {
+        let mut worst = 0.0f32;
+        for y in 0..dim {
+            for x in 0..dim {
+                let m = ModulePoint::center_of(x, y);
+                worst = worst.max(a.to_image(m).distance(&b.to_image(m)));
+            }
+        }
+        worst
+    }
+
+    #[test]
+    fn refiner_pulls_a_misplaced_corner_back() {
+        let d = 45.0;
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(d, 0.0),
+            Point::new(0.0, d),
+            Point::new(d, d),
+        ];
+        let truth_dst = [
+            Point::new(30.0, 20.0),
+            Point::new(250.0, 35.0),
+            Point::new(20.0, 240.0),
+            Point::new(230.0, 270.0),
+        ];
+        let truth = PerspectiveTransform::from_points(&corners, &truth_dst).unwrap();
+        let binary = render(&truth, 290, 300);
+
+        // Bottom-right corner guessed ~1.5 modules off, as from a parallelogram fit.
+        let mut guess_dst = truth_dst;
+        guess_dst[3] = Point::new(238.0, 262.0);
+        let guess = PerspectiveTransform::from_points(&corners, &guess_dst).unwrap();
+
+        let (refined, tel) = GridRefiner::default().refine(&binary, &guess, 7);
+        assert!(tel.improved(), "{tel:?}");
+        assert!(tel.iterations >= 1);
+        assert!(tel.residual_drop() > 0.0);
+        let before = grid_error(&guess, &truth, 45);
+        let after = grid_error(&refined, &truth, 45);
+        assert!(after < before * 0.5, "before {before}, after {after}");
+    }
+
+    #[test]
+    fn refiner_keeps_transform_without_features() {
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(45.0, 0.0),
+            Point::new(0.0, 45.0),
+            Point::new(45.0, 45.0),
+        ];
+        let t = PerspectiveTransform::from_points(&corners, &corners).unwrap();
+        let (out, tel) = GridRefiner::default().refine(&BitMatrix::new(60, 60), &t, 7);
+        assert!(!tel.improved());
+        assert_eq!(tel.iterations, 0);
+        assert!(grid_error(&out, &t, 45) < 1e-4);
+    }
+}
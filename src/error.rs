@@ -0,0 +1,146 @@
+/// Error types for the public API and the decoder internals
+use std::fmt;
+
+use crate::decoder::reed_solomon::RsError;
+
+/// Top-level error returned by the `try_*` entry points.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Image buffer length does not match `width * height * channels`.
+    InvalidImage {
+        width: usize,
+        height: usize,
+        expected_len: usize,
+        actual_len: usize,
+    },
+    /// The image was processed but no QR code was found.
+    NotFound,
+    /// A located symbol could not be decoded.
+    Decode(DecodeFailure),
+    /// A limit or load-shed policy stopped the call before a heavy stage.
+    Shed(crate::Shed),
+}
+
+/// Cause of a failed symbol decode, ordered roughly by pipeline stage.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecodeFailure {
+    /// Finder geometry could not produce a plausible symbol outline.
+    InvalidGeometry,
+    /// Sampled grid failed timing-pattern validation.
+    TimingPattern,
+    /// No orientation of the sampled grid showed three finder patterns.
+    Orientation,
+    /// Neither format-info copy decoded within BCH distance.
+    FormatInfo,
+    /// No EC block layout exists for this version/EC level.
+    UnsupportedVersion(u8),
+    /// Codeword stream is too short for the EC block layout.
+    Codewords,
+    /// Reed-Solomon correction failed for at least one block.
+    ReedSolomon(RsError),
+    /// Corrected data did not parse into a valid payload.
+    Payload,
+}
+
+impl DecodeFailure {
+    /// Pipeline depth of the failure; deeper failures carry more information.
+    fn depth(self) -> u8 {
+        match self {
+            DecodeFailure::InvalidGeometry => 0,
+            DecodeFailure::TimingPattern => 1,
+            DecodeFailure::Orientation => 2,
+            DecodeFailure::FormatInfo => 3,
+            DecodeFailure::UnsupportedVersion(_) => 4,
+            DecodeFailure::Codewords => 5,
+            DecodeFailure::ReedSolomon(_) => 6,
+            DecodeFailure::Payload => 7,
+        }
+    }
+
+    /// Keep whichever of two failures got further through the pipeline.
+    pub(crate) fn deeper(self, other: DecodeFailure) -> DecodeFailure {
+        if other.depth() >= self.depth() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidImage {
+                width,
+                height,
+                expected_len,
+                actual_len,
+            } => write!(
+                f,
+                "invalid image buffer for {}x{}: expected {} bytes, got {}",
+                width, height, expected_len, actual_len
+            ),
+            Error::NotFound => write!(f, "no QR code found"),
+            Error::Decode(cause) => write!(f, "decode failed: {}", cause),
+            Error::Shed(shed) => write!(f, "shed: {}", shed),
+        }
+    }
+}
+
+impl fmt::Display for DecodeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeFailure::InvalidGeometry => write!(f, "invalid finder geometry"),
+            DecodeFailure::TimingPattern => write!(f, "timing pattern mismatch"),
+            DecodeFailure::Orientation => write!(f, "no valid orientation"),
+            DecodeFailure::FormatInfo => write!(f, "format info unreadable"),
+            DecodeFailure::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            DecodeFailure::Codewords => write!(f, "codeword stream too short"),
+            DecodeFailure::ReedSolomon(e) => write!(f, "reed-solomon: {}", e),
+            DecodeFailure::Payload => write!(f, "payload parse failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl std::error::Error for DecodeFailure {}
+
+impl From<DecodeFailure> for Error {
+    fn from(cause: DecodeFailure) -> Self {
+        Error::Decode(cause)
+    }
+}
+
+impl From<RsError> for DecodeFailure {
+    fn from(e: RsError) -> Self {
+        DecodeFailure::ReedSolomon(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deeper_keeps_furthest_stage() {
+        let f = DecodeFailure::TimingPattern.deeper(DecodeFailure::FormatInfo);
+        assert_eq!(f, DecodeFailure::FormatInfo);
+        let f = f.deeper(DecodeFailure::InvalidGeometry);
+        assert_eq!(f, DecodeFailure::FormatInfo);
+        let f = f.deeper(DecodeFailure::ReedSolomon(RsError::Uncorrectable));
+        assert_eq!(f, DecodeFailure::ReedSolomon(RsError::Uncorrectable));
+    }
+
+    #[test]
+    fn errors_display_cause() {
+        let e: Error = DecodeFailure::ReedSolomon(RsError::TooManyErasures).into();
+        assert_eq!(
+            e.to_string(),
+            "decode failed: reed-solomon: Too many erasures"
+        );
+    }
+}
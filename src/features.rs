@@ -0,0 +1,21 @@
+//! Compile-time switches for optional decode strategies
+//!
+//! Each constant mirrors a Cargo feature of the same name. Call sites branch
+//! on the constant instead of using `#[cfg]`, so every feature combination is
+//! type-checked and the optimizer drops disabled strategies from the binary.
+//! Building with `--no-default-features` leaves the fast path and the basic
+//! decoder.
+
+/// Phase-4 fallbacks (binarization ensemble, contrast stretch, rotation,
+/// contour finders), two-finder and ROI re-binarization retries, and the
+/// per-candidate recovery ladder (scale retry, deskew, mesh and radial
+/// warps).
+pub(crate) const FULL_RECOVERY: bool = cfg!(feature = "full-recovery");
+
+/// The multi-QR strategy profile and multi-region expansion. Without it
+/// decoding stops at the first accepted code.
+pub(crate) const MULTI_QR: bool = cfg!(feature = "multi-qr");
+
+/// The high-version precision and high-density profiles: area-weighted
+/// sampling and iterative grid refinement for small modules.
+pub(crate) const HIGH_VERSION: bool = cfg!(feature = "high-version");
@@ -0,0 +1,208 @@
+//! Stable C ABI, enabled with the `ffi` feature.
+//!
+//! Every symbol carries the ABI version in its name (`rustqr_v1_*`), and the
+//! layout of the `RustqrV1*` types never changes once released: new fields
+//! or behaviour arrive as `rustqr_v2_*` alongside, so binaries linked
+//! against v1 keep working. The matching header is checked in at
+//! `include/rustqr.h`; regenerate it with `scripts/generate_c_header.sh`
+//! after changing this file.
+//!
+//! ```c
+//! #include "rustqr.h"
+//!
+//! RustqrV1Result *result = NULL;
+//! if (rustqr_v1_detect(rgb, width, height, width * 3, RUSTQR_V1_FORMAT_RGB8,
+//!                      &result) == RUSTQR_V1_OK) {
+//!     for (size_t i = 0; i < result->len; i++)
+//!         printf("%s\n", result->codes[i].content);
+//!     rustqr_v1_result_free(result);
+//! }
+//! ```
+
+use std::os::raw::c_char;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::ptr;
+
+use crate::{ECLevel, ImageRef, PixelFormat, QRCode};
+
+/// Version of the C ABI these symbols implement.
+pub const RUSTQR_ABI_VERSION: u32 = 1;
+
+/// The call succeeded.
+pub const RUSTQR_V1_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const RUSTQR_V1_NULL_POINTER: i32 = 1;
+/// The dimensions, stride or pixel format do not describe a valid image.
+pub const RUSTQR_V1_INVALID_IMAGE: i32 = 2;
+/// Detection failed internally; no result was produced.
+pub const RUSTQR_V1_INTERNAL_ERROR: i32 = 3;
+
+/// 8-bit grayscale pixels.
+pub const RUSTQR_V1_FORMAT_LUMA8: u32 = 0;
+/// 8-bit RGB pixels.
+pub const RUSTQR_V1_FORMAT_RGB8: u32 = 1;
+/// 8-bit RGBA pixels; alpha is ignored.
+pub const RUSTQR_V1_FORMAT_RGBA8: u32 = 2;
+
+/// A point in image coordinates.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RustqrV1Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// One decoded QR code, owned by the `RustqrV1Result` it belongs to.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RustqrV1Code {
+    /// Raw payload bytes, `data_len` long.
+    pub data: *const u8,
+    pub data_len: usize,
+    /// Payload as UTF-8 text, `content_len` bytes plus a terminating NUL.
+    pub content: *const c_char,
+    pub content_len: usize,
+    /// Outline from the symbol's top-left, clockwise in image space: TL,
+    /// TR, BR, BL (TL, BL, BR, TR when mirrored).
+    pub corners: [RustqrV1Point; 4],
+    /// Calibrated probability in `[0, 1]` that the payload is correct.
+    pub confidence: f32,
+    /// Symbol version, 1 to 40.
+    pub version: u8,
+    /// Error correction level: 0 = L, 1 = M, 2 = Q, 3 = H.
+    pub ec_level: u8,
+    /// Data mask pattern, 0 to 7.
+    pub mask: u8,
+}
+
+/// The codes found by one detection call, best first. Free with
+/// `rustqr_v1_result_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RustqrV1Result {
+    pub codes: *mut RustqrV1Code,
+    pub len: usize,
+}
+
+/// The ABI version of the loaded library, to check against
+/// `RUSTQR_ABI_VERSION` at startup.
+#[unsafe(no_mangle)]
+pub extern "C" fn rustqr_v1_abi_version() -> u32 {
+    RUSTQR_ABI_VERSION
+}
+
+/// Detect QR codes in an image whose rows are `stride` bytes apart.
+///
+/// On `RUSTQR_V1_OK`, `*out` points to a result, possibly with no codes,
+/// that the caller frees with `rustqr_v1_result_free`; on any other status
+/// `*out` is set to null.
+///
+/// # Safety
+/// `pixels` must be readable for `stride * (height - 1) + width * bpp`
+/// bytes, where `bpp` is the pixel size of `format`, and `out` must be
+/// writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustqr_v1_detect(
+    pixels: *const u8,
+    width: usize,
+    height: usize,
+    stride: usize,
+    format: u32,
+    out: *mut *mut RustqrV1Result,
+) -> i32 {
+    if out.is_null() {
+        return RUSTQR_V1_NULL_POINTER;
+    }
+    // SAFETY: `out` is non-null and writable per the contract.
+    unsafe { *out = ptr::null_mut() };
+    if pixels.is_null() {
+        return RUSTQR_V1_NULL_POINTER;
+    }
+    let format = match format {
+        RUSTQR_V1_FORMAT_LUMA8 => PixelFormat::Luma8,
+        RUSTQR_V1_FORMAT_RGB8 => PixelFormat::Rgb8,
+        RUSTQR_V1_FORMAT_RGBA8 => PixelFormat::Rgba8,
+        _ => return RUSTQR_V1_INVALID_IMAGE,
+    };
+    let len = width
+        .checked_mul(format.bytes_per_pixel())
+        .filter(|&row_len| width > 0 && height > 0 && stride >= row_len)
+        .and_then(|row_len| stride.checked_mul(height - 1)?.checked_add(row_len));
+    let Some(len) = len else {
+        return RUSTQR_V1_INVALID_IMAGE;
+    };
+    // SAFETY: the caller guarantees `len` readable bytes at `pixels`.
+    let data = unsafe { std::slice::from_raw_parts(pixels, len) };
+    let Ok(image) = ImageRef::with_stride(data, width, height, stride, format) else {
+        return RUSTQR_V1_INVALID_IMAGE;
+    };
+    // Unwinding into C is undefined; report a panic as a status instead.
+    let Ok(codes) = catch_unwind(AssertUnwindSafe(|| crate::detect_image_ref(&image))) else {
+        return RUSTQR_V1_INTERNAL_ERROR;
+    };
+    let codes: Box<[RustqrV1Code]> = codes.iter().map(code_to_c).collect();
+    let len = codes.len();
+    let result = RustqrV1Result {
+        codes: Box::into_raw(codes) as *mut RustqrV1Code,
+        len,
+    };
+    // SAFETY: as above.
+    unsafe { *out = Box::into_raw(Box::new(result)) };
+    RUSTQR_V1_OK
+}
+
+/// Free a result returned by `rustqr_v1_detect`. Null is ignored.
+///
+/// # Safety
+/// `result` must be null or a pointer from `rustqr_v1_detect` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rustqr_v1_result_free(result: *mut RustqrV1Result) {
+    if result.is_null() {
+        return;
+    }
+    // SAFETY: `result` and everything it owns were boxed by
+    // `rustqr_v1_detect` with these exact lengths.
+    unsafe {
+        let result = Box::from_raw(result);
+        let codes = Box::from_raw(ptr::slice_from_raw_parts_mut(result.codes, result.len));
+        for code in codes.iter() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                code.data as *mut u8,
+                code.data_len,
+            )));
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+                code.content as *mut u8,
+                code.content_len + 1,
+            )));
+        }
+    }
+}
+
+fn code_to_c(qr: &QRCode) -> RustqrV1Code {
+    let data: Box<[u8]> = qr.data.clone().into_boxed_slice();
+    let content_len = qr.content.len();
+    let content: Box<[u8]> = qr
+        .content
+        .bytes()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u8>>()
+        .into_boxed_slice();
+    let corners = qr.outline().map(|p| RustqrV1Point { x: p.x, y: p.y });
+    RustqrV1Code {
+        data_len: data.len(),
+        data: Box::into_raw(data) as *const u8,
+        content: Box::into_raw(content) as *const c_char,
+        content_len,
+        corners,
+        confidence: qr.confidence,
+        version: qr.version.number(),
+        ec_level: match qr.error_correction {
+            ECLevel::L => 0,
+            ECLevel::M => 1,
+            ECLevel::Q => 2,
+            ECLevel::H => 3,
+        },
+        mask: qr.mask_pattern as u8,
+    }
+}
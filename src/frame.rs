@@ -0,0 +1,266 @@
+//! One grayscale frame and the planes derived from it
+//!
+//! The strategies try up to a dozen binarizations of the same frame, each
+//! adaptive or Sauvola variant built on the same integral images. A
+//! [`Frame`] derives each plane on first use and keeps it, so a strategy
+//! that decodes on its first binarization never pays for the rest, and the
+//! integrals are summed once however many variants read them. Buffers
+//! already filled from a [`crate::utils::memory_pool::BufferPool`] are
+//! seeded in and borrowed rather than rebuilt.
+//!
+//! The finder, grouping and decode stages read one binary matrix and the
+//! gray it was thresholded from; [`Planes`] carries the pair, with their
+//! size, as a single argument.
+
+use std::borrow::Cow;
+use std::cell::OnceCell;
+
+use crate::models::{Binarization, BitMatrix};
+use crate::utils::binarization::{
+    adaptive_binarize_with_integral, build_integral_image, build_integral_sq_image,
+    hybrid_binarize_with_integrals, otsu_binarize, sauvola_binarize_with_integrals,
+    threshold_binarize,
+};
+
+/// Local window of [`Binarization::Hybrid`] unless overridden with
+/// [`Frame::with_hybrid_window`]
+const HYBRID_WINDOW: usize = 31;
+
+/// Gray levels either side of the median for [`Binarization::ThresholdDark`]
+/// and [`Binarization::ThresholdLight`]
+const MEDIAN_THRESHOLD_OFFSET: i16 = 26;
+
+/// A grayscale frame with its binary matrices and integral images computed
+/// lazily, each at most once
+pub(crate) struct Frame<'a> {
+    gray: &'a [u8],
+    width: usize,
+    height: usize,
+    hybrid_window: usize,
+    integral: OnceCell<Cow<'a, [u32]>>,
+    integral_sq: OnceCell<Vec<u64>>,
+    binaries: [OnceCell<Cow<'a, BitMatrix>>; Binarization::ALL.len()],
+}
+
+impl<'a> Frame<'a> {
+    pub(crate) fn new(gray: &'a [u8], width: usize, height: usize) -> Self {
+        debug_assert_eq!(gray.len(), width * height);
+        Self {
+            gray,
+            width,
+            height,
+            hybrid_window: HYBRID_WINDOW,
+            integral: OnceCell::new(),
+            integral_sq: OnceCell::new(),
+            binaries: std::array::from_fn(|_| OnceCell::new()),
+        }
+    }
+
+    /// Use `binary`, already thresholded from this frame's gray, as the
+    /// `kind` plane instead of deriving it.
+    pub(crate) fn with_binary(self, kind: Binarization, binary: &'a BitMatrix) -> Self {
+        debug_assert_eq!((binary.width(), binary.height()), (self.width, self.height));
+        let _ = self.binaries[kind.index()].set(Cow::Borrowed(binary));
+        self
+    }
+
+    /// Threshold [`Binarization::Hybrid`] over a `window` pixel square
+    /// instead of [`HYBRID_WINDOW`]. Call before it is derived.
+    pub(crate) fn with_hybrid_window(mut self, window: usize) -> Self {
+        self.hybrid_window = window;
+        self
+    }
+
+    /// Use `integral`, already built from this frame's gray, instead of
+    /// summing it again.
+    pub(crate) fn with_integral(self, integral: &'a [u32]) -> Self {
+        debug_assert_eq!(integral.len(), self.width * self.height);
+        let _ = self.integral.set(Cow::Borrowed(integral));
+        self
+    }
+
+    /// Local window of the frame-sized adaptive and Sauvola variants
+    pub(crate) fn window(&self) -> usize {
+        crate::auto_window(self.width, self.height)
+    }
+
+    /// Local window of [`Binarization::AdaptiveWide`] and
+    /// [`Binarization::SauvolaWide`], for high-version symbols
+    pub(crate) fn wide_window(&self) -> usize {
+        (self.window() * 2).clamp(63, 255)
+    }
+
+    fn integral(&self) -> &[u32] {
+        self.integral
+            .get_or_init(|| Cow::Owned(build_integral_image(self.gray, self.width, self.height)))
+    }
+
+    fn integral_sq(&self) -> &[u64] {
+        self.integral_sq
+            .get_or_init(|| build_integral_sq_image(self.gray, self.width, self.height))
+    }
+
+    /// The `kind` binarization of the whole frame, derived on first use.
+    /// `None` for the binarizations only ever run on a crop
+    /// ([`Binarization::AdaptiveModule`], [`Binarization::RoiNormalized`]).
+    pub(crate) fn binary(&self, kind: Binarization) -> Option<&BitMatrix> {
+        let cell = &self.binaries[kind.index()];
+        if let Some(binary) = cell.get() {
+            return Some(binary);
+        }
+        let binary = self.derive(kind)?;
+        Some(cell.get_or_init(|| Cow::Owned(binary)))
+    }
+
+    /// The `kind` binarization moved out of the frame, for callers that keep
+    /// the matrix after the gray is gone.
+    pub(crate) fn into_binary(mut self, kind: Binarization) -> Option<BitMatrix> {
+        self.binary(kind)?;
+        self.binaries[kind.index()].take().map(Cow::into_owned)
+    }
+
+    /// The `kind` binary matrix with this frame's gray, for the finder and
+    /// decode stages.
+    pub(crate) fn planes(&self, kind: Binarization) -> Option<Planes<'_>> {
+        let binary = self.binary(kind)?;
+        Some(Planes::new(binary, self.gray))
+    }
+
+    fn derive(&self, kind: Binarization) -> Option<BitMatrix> {
+        let (gray, width, height) = (self.gray, self.width, self.height);
+        let adaptive =
+            |window| adaptive_binarize_with_integral(gray, width, height, window, self.integral());
+        let sauvola = |window, k| {
+            sauvola_binarize_with_integrals(
+                gray,
+                width,
+                height,
+                window,
+                k,
+                self.integral(),
+                self.integral_sq(),
+            )
+        };
+        let binary = match kind {
+            Binarization::Otsu => otsu_binarize(gray, width, height),
+            Binarization::Adaptive31 => adaptive(31),
+            Binarization::Adaptive21 => adaptive(21),
+            Binarization::Hybrid => hybrid_binarize_with_integrals(
+                gray,
+                width,
+                height,
+                self.hybrid_window,
+                self.integral(),
+                self.integral_sq(),
+            ),
+            Binarization::Adaptive => adaptive(self.window()),
+            Binarization::AdaptiveWide => adaptive(self.wide_window()),
+            Binarization::Sauvola => sauvola(self.window(), 0.2),
+            Binarization::SauvolaLowK => sauvola(self.window(), 0.1),
+            Binarization::SauvolaHighK => sauvola(self.window(), 0.3),
+            Binarization::SauvolaWide => sauvola(self.wide_window(), 0.2),
+            Binarization::ThresholdDark => {
+                threshold_binarize(gray, width, height, self.median_offset(-1))
+            }
+            Binarization::ThresholdLight => {
+                threshold_binarize(gray, width, height, self.median_offset(1))
+            }
+            Binarization::AdaptiveModule | Binarization::RoiNormalized => return None,
+        };
+        Some(binary)
+    }
+
+    /// The median gray moved [`MEDIAN_THRESHOLD_OFFSET`] levels in the
+    /// direction of `sign`
+    fn median_offset(&self, sign: i16) -> u8 {
+        let mut sorted = self.gray.to_vec();
+        sorted.sort_unstable();
+        let median = sorted[sorted.len() / 2] as i16;
+        (median + sign * MEDIAN_THRESHOLD_OFFSET).clamp(0, 255) as u8
+    }
+}
+
+/// A binary matrix and the gray image it was thresholded from, both
+/// `width` x `height`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Planes<'a> {
+    pub(crate) binary: &'a BitMatrix,
+    pub(crate) gray: &'a [u8],
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+}
+
+impl<'a> Planes<'a> {
+    /// Pair `binary` with the `gray` it was thresholded from; the size is
+    /// the matrix's.
+    pub(crate) fn new(binary: &'a BitMatrix, gray: &'a [u8]) -> Self {
+        let (width, height) = (binary.width(), binary.height());
+        debug_assert_eq!(gray.len(), width * height);
+        Self {
+            binary,
+            gray,
+            width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::binarization::{adaptive_binarize, hybrid_binarize, sauvola_binarize};
+
+    fn gradient_with_blocks(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let base = 60 + (x * 120 / width) as u8;
+                if (x / 7 + y / 7) % 3 == 0 {
+                    base / 2
+                } else {
+                    base
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lazy_planes_match_the_direct_binarizations() {
+        let (width, height) = (96, 80);
+        let gray = gradient_with_blocks(width, height);
+        let frame = Frame::new(&gray, width, height);
+        let window = frame.window();
+
+        let words = |kind| frame.binary(kind).unwrap().as_words().to_vec();
+
+        assert_eq!(
+            words(Binarization::Adaptive),
+            adaptive_binarize(&gray, width, height, window).as_words()
+        );
+        assert_eq!(
+            words(Binarization::SauvolaLowK),
+            sauvola_binarize(&gray, width, height, window, 0.1).as_words()
+        );
+        assert_eq!(
+            words(Binarization::Hybrid),
+            hybrid_binarize(&gray, width, height, 31).as_words()
+        );
+        assert_eq!(
+            words(Binarization::Otsu),
+            otsu_binarize(&gray, width, height).as_words()
+        );
+        assert!(frame.binary(Binarization::RoiNormalized).is_none());
+    }
+
+    #[test]
+    fn seeded_planes_are_borrowed_and_not_rederived() {
+        let (width, height) = (64, 48);
+        let gray = gradient_with_blocks(width, height);
+        let seeded = BitMatrix::new(width, height);
+        let frame = Frame::new(&gray, width, height).with_binary(Binarization::Otsu, &seeded);
+
+        let planes = frame.planes(Binarization::Otsu).unwrap();
+        assert!(std::ptr::eq(planes.binary, &seeded));
+        assert_eq!((planes.width, planes.height), (width, height));
+    }
+}
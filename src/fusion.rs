@@ -0,0 +1,316 @@
+//! Multi-frame module fusion for video streams
+//!
+//! When a frame fails to decode, the best finder triplet is still sampled into
+//! per-module signed contrast using that frame's own perspective transform.
+//! Samples from consecutive frames that track the same symbol are averaged in
+//! module space, so noise and blur that differ frame to frame cancel out and
+//! the fused grid can decode where no single frame does.
+
+use std::collections::VecDeque;
+
+use crate::decoder::qr_decoder::QrDecoder;
+use crate::frame::{Frame, Planes};
+use crate::models::{Point, QRCode};
+
+/// Timing-pattern agreement a candidate grid needs before it can start or
+/// continue a track; noise triplets sit near 0.5.
+const MIN_TIMING_AGREEMENT: f32 = 0.7;
+
+/// Options for [`crate::Detector::with_fusion`].
+#[derive(Debug, Clone, Copy)]
+pub struct FusionConfig {
+    /// Number of recent frames averaged into the fused grid.
+    pub window: usize,
+    /// Frames that must be accumulated before a fused decode is attempted.
+    pub min_frames: usize,
+    /// How far (in modules) the top-left finder may move between frames and
+    /// still count as the same symbol.
+    pub max_drift_modules: f32,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            window: 8,
+            min_frames: 2,
+            max_drift_modules: 6.0,
+        }
+    }
+}
+
+/// Fusion counters, for observability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FusionStats {
+    /// Frames sampled into a track.
+    pub frames_sampled: usize,
+    /// Times a track was dropped because the symbol moved, changed size or
+    /// decoded on its own.
+    pub track_resets: usize,
+    /// Fused decode attempts.
+    pub fused_attempts: usize,
+    /// Fused decodes that produced a code.
+    pub fused_successes: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Geometry {
+    top_left: Point,
+    top_right: Point,
+    bottom_left: Point,
+    module_size: f32,
+}
+
+/// Grid dimension, sampling geometry and module levels for one frame.
+type FrameSample = (usize, Geometry, Vec<f32>);
+
+#[derive(Debug, Clone)]
+struct Track {
+    dimension: usize,
+    geometry: Geometry,
+    frames: VecDeque<Vec<f32>>,
+}
+
+/// Per-stream fusion state owned by [`crate::Detector`].
+#[derive(Debug, Clone)]
+pub(crate) struct FrameFusion {
+    config: FusionConfig,
+    track: Option<Track>,
+    stats: FusionStats,
+}
+
+impl FrameFusion {
+    pub(crate) fn new(config: FusionConfig) -> Self {
+        Self {
+            config: FusionConfig {
+                window: config.window.max(2),
+                min_frames: config.min_frames.clamp(1, config.window.max(2)),
+                max_drift_modules: config.max_drift_modules.max(0.0),
+            },
+            track: None,
+            stats: FusionStats::default(),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> FusionStats {
+        self.stats
+    }
+
+    /// Drop the current track, e.g. after the symbol decoded from one frame.
+    pub(crate) fn reset(&mut self) {
+        if self.track.take().is_some() {
+            self.stats.track_resets += 1;
+        }
+    }
+
+    /// Sample a frame that failed to decode and try the fused grid.
+    pub(crate) fn observe_failed_frame(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        height: usize,
+    ) -> Option<QRCode> {
+        let (dimension, geometry, levels) = self.sample_frame(gray, width, height)?;
+        self.push(dimension, geometry, levels);
+        self.try_fused()
+    }
+
+    /// Sample the candidate that best continues the current track (or the top
+    /// ranked candidate when there is none). Frames too degraded to show a
+    /// finder triplet are sampled at the track's last geometry instead.
+    fn sample_frame(&self, gray: &[u8], width: usize, height: usize) -> Option<FrameSample> {
+        let policies = crate::phase9_binarization_sequence(width, height);
+        let frame = Frame::new(gray, width, height);
+        for &policy in &policies {
+            let planes = crate::policy_planes(&frame, policy);
+            let patterns = crate::detect_finder_patterns(planes.binary, width, height);
+            if patterns.len() < 3 {
+                continue;
+            }
+            let candidates = crate::pipeline::ranked_candidate_geometry(planes, &patterns);
+            let mut best: Option<(f32, FrameSample)> = None;
+            for (top_left, top_right, bottom_left, module_size, _) in candidates {
+                let geometry = Geometry {
+                    top_left,
+                    top_right,
+                    bottom_left,
+                    module_size,
+                };
+                let Some((dimension, levels)) = sample(planes, &geometry) else {
+                    continue;
+                };
+                if timing_agreement(&levels, dimension) < MIN_TIMING_AGREEMENT {
+                    continue;
+                }
+                let cost = match &self.track {
+                    Some(track) if track.dimension == dimension => {
+                        top_left.distance(&track.geometry.top_left)
+                            / track.geometry.module_size.max(1.0)
+                    }
+                    Some(_) => f32::MAX,
+                    // Ranked order already puts the strongest candidate first.
+                    None => 0.0,
+                };
+                if best.as_ref().is_none_or(|(c, _)| cost < *c) {
+                    best = Some((cost, (dimension, geometry, levels)));
+                }
+            }
+            if let Some((_, sample)) = best {
+                return Some(sample);
+            }
+        }
+
+        let track = self.track.as_ref()?;
+        let planes = crate::policy_planes(&frame, *policies.first()?);
+        let (dimension, levels) = sample(planes, &track.geometry)?;
+        (dimension == track.dimension).then_some((dimension, track.geometry, levels))
+    }
+
+    fn push(&mut self, dimension: usize, geometry: Geometry, levels: Vec<f32>) {
+        let continues = self.track.as_ref().is_some_and(|t| {
+            t.dimension == dimension
+                && geometry.top_left.distance(&t.geometry.top_left)
+                    <= self.config.max_drift_modules * t.geometry.module_size.max(1.0)
+        });
+        if !continues {
+            self.reset();
+            self.track = Some(Track {
+                dimension,
+                geometry,
+                frames: VecDeque::with_capacity(self.config.window),
+            });
+        }
+        let track = self.track.as_mut().expect("track set above");
+        if track.frames.len() == self.config.window {
+            track.frames.pop_front();
+        }
+        track.frames.push_back(levels);
+        track.geometry = geometry;
+        self.stats.frames_sampled += 1;
+    }
+
+    fn try_fused(&mut self) -> Option<QRCode> {
+        let track = self.track.as_ref()?;
+        if track.frames.len() < self.config.min_frames {
+            return None;
+        }
+        let fused = fuse_levels(&track.frames);
+        self.stats.fused_attempts += 1;
+        let qr = QrDecoder::decode_levels(&fused, track.dimension)?;
+        self.stats.fused_successes += 1;
+        Some(qr)
+    }
+}
+
+fn sample(planes: Planes<'_>, geometry: &Geometry) -> Option<(usize, Vec<f32>)> {
+    QrDecoder::sample_module_contrast(
+        planes,
+        &geometry.top_left,
+        &geometry.top_right,
+        &geometry.bottom_left,
+        geometry.module_size,
+    )
+}
+
+/// Fraction of timing-pattern modules (row and column 6 between the
+/// finders) whose sign matches the expected dark/light alternation.
+fn timing_agreement(levels: &[f32], dimension: usize) -> f32 {
+    if dimension < 21 {
+        return 0.0;
+    }
+    let mut agree = 0usize;
+    let mut total = 0usize;
+    for i in 8..dimension - 8 {
+        let dark = i % 2 == 0;
+        for idx in [6 * dimension + i, i * dimension + 6] {
+            agree += usize::from((levels[idx] > 0.0) == dark);
+            total += 1;
+        }
+    }
+    agree as f32 / total as f32
+}
+
+/// Average signed module levels across frames.
+fn fuse_levels(frames: &VecDeque<Vec<f32>>) -> Vec<f32> {
+    let mut fused = vec![0.0f32; frames.front().map_or(0, Vec::len)];
+    for frame in frames {
+        for (acc, level) in fused.iter_mut().zip(frame) {
+            *acc += level;
+        }
+    }
+    let n = frames.len().max(1) as f32;
+    fused.iter_mut().for_each(|v| *v /= n);
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random noise in [-1, 1).
+    fn noise(seed: &mut u32) -> f32 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        (*seed as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    #[test]
+    fn fusing_noisy_frames_recovers_module_signs() {
+        let truth: Vec<f32> = (0..441)
+            .map(|i| if (i * 7) % 3 == 0 { 0.2 } else { -0.2 })
+            .collect();
+        let mut seed = 0x9e37_79b9;
+        let frames: VecDeque<Vec<f32>> = (0..8)
+            .map(|_| truth.iter().map(|t| t + noise(&mut seed) * 0.5).collect())
+            .collect();
+        let wrong = |levels: &[f32]| {
+            levels
+                .iter()
+                .zip(&truth)
+                .filter(|(l, t)| (**l > 0.0) != (**t > 0.0))
+                .count()
+        };
+        let single = wrong(&frames[0]);
+        let fused = wrong(&fuse_levels(&frames));
+        assert!(single > 20, "single frame should be noisy ({single})");
+        assert!(fused * 4 < single, "fused {fused} vs single {single}");
+    }
+
+    #[test]
+    fn timing_agreement_separates_symbols_from_noise() {
+        let dim = 25;
+        let mut levels = vec![-0.2f32; dim * dim];
+        for i in 0..dim {
+            let v = if i % 2 == 0 { 0.2 } else { -0.2 };
+            levels[6 * dim + i] = v;
+            levels[i * dim + 6] = v;
+        }
+        assert!((timing_agreement(&levels, dim) - 1.0).abs() < 1e-6);
+        let mut seed = 7;
+        let noisy: Vec<f32> = (0..dim * dim).map(|_| noise(&mut seed)).collect();
+        assert!(timing_agreement(&noisy, dim) < MIN_TIMING_AGREEMENT);
+    }
+
+    #[test]
+    fn track_resets_on_drift_or_dimension_change() {
+        let at = |x: f32, y: f32| Geometry {
+            top_left: Point::new(x, y),
+            top_right: Point::new(x + 80.0, y),
+            bottom_left: Point::new(x, y + 80.0),
+            module_size: 4.0,
+        };
+        let mut fusion = FrameFusion::new(FusionConfig::default());
+        let levels = vec![0.0; 21 * 21];
+        fusion.push(21, at(10.0, 10.0), levels.clone());
+        fusion.push(21, at(14.0, 12.0), levels.clone());
+        assert_eq!(fusion.track.as_ref().unwrap().frames.len(), 2);
+        assert_eq!(fusion.stats().track_resets, 0);
+
+        fusion.push(21, at(200.0, 10.0), levels.clone());
+        assert_eq!(fusion.track.as_ref().unwrap().frames.len(), 1);
+        fusion.push(25, at(200.0, 10.0), vec![0.0; 25 * 25]);
+        assert_eq!(fusion.track.as_ref().unwrap().dimension, 25);
+        assert_eq!(fusion.stats().track_resets, 2);
+        assert_eq!(fusion.stats().frames_sampled, 4);
+    }
+}
@@ -6,33 +6,120 @@
 #![allow(missing_docs)]
 #![allow(clippy::missing_docs_in_private_items)]
 
+/// Adaptive decode-budget controller used by `Detector`
+pub mod budget;
+/// Mapping from the internal decode score to calibrated confidence
+pub mod calibration;
+/// Detector-wide options (quiet-zone tolerance, ...)
+pub mod config;
+/// Once-per-appearance result reporting for streams
+pub mod debounce;
 /// Debug helpers (env-driven)
 pub(crate) mod debug;
 /// QR code decoding modules (error correction, format extraction, data modes)
 pub mod decoder;
 /// QR code detection modules (finder patterns, alignment, timing)
 pub mod detector;
+/// Error types for fallible entry points
+pub mod error;
+/// Compile-time strategy switches (Cargo features)
+mod features;
+/// Versioned C ABI (feature-gated)
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod frame;
+/// Multi-frame module fusion used by `Detector`
+pub mod fusion;
+/// Pixel and candidate limits and load-shed policies for service use
+pub mod load_shed;
+/// Strategy selection under `DetectorConfig::max_memory_bytes`
+mod memory;
 /// Core data structures (QRCode, BitMatrix, Point, etc.)
 pub mod models;
+/// Node.js bindings (feature-gated)
+#[cfg(feature = "node")]
+mod node;
 mod pipeline;
+/// Common imports: `use rust_qr::prelude::*;`
+pub mod prelude;
+/// Grayscale preprocessing chained in front of `Detector`
+pub mod preprocess;
+/// Shared-prefix payload prior used by `Detector`
+pub mod prior;
+/// Python bindings (feature-gated)
+#[cfg(feature = "python")]
+mod python;
+/// One-call scanning returning flat [`Scan`] results
+pub mod scan;
+/// Structured-append reassembly across detection calls
+pub mod scan_session;
+/// Replayable repro bundles for bug reports (feature-gated)
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+/// Composable localize/rank/sample/decode stages with swappable parts
+pub mod stages;
+/// Stage-grouped telemetry with derived rates, shared with `qrtool`
+pub mod telemetry;
 /// CLI/bench helpers (feature-gated)
 #[cfg(feature = "tools")]
 pub mod tools;
 /// Utility functions (grayscale, binarization, geometry)
 pub mod utils;
 
-pub use models::{BitMatrix, ECLevel, MaskPattern, Point, QRCode, Version};
+pub use budget::{BudgetBounds, BudgetOperatingPoint};
+pub use calibration::ConfidenceCalibration;
+pub use config::{Ablation, DetectorConfig, EncoderQuirk, LensModel, PhysicalScale};
+pub use debounce::{DebounceConfig, ResultDebouncer};
+pub use detector::finder::ColumnScan;
+pub use error::{DecodeFailure, Error};
+pub use fusion::{FusionConfig, FusionStats};
+pub use load_shed::{LoadShedPolicy, Shed, ShedReason, ShedStage, StageCost, TokenBucket};
+pub use models::{
+    Binarization, BitMatrix, BitMatrixView, ECLevel, FormatQuirk, GrayFrame, ImagePoint, ImageRef,
+    IntermediateData, MaskPattern, ModulePoint, Orientation, PixelFormat, Point, QRCode,
+    StructuredAppend, SymbolInfo, TextEncoding, Version,
+};
+pub use preprocess::Preprocessor;
+pub use prior::PayloadPrior;
+#[cfg(feature = "image-interop")]
+pub use scan::scan_bytes;
+#[cfg(feature = "tools")]
+pub use scan::scan_file;
+pub use scan::{Scan, scan_gray};
+pub use scan_session::{AssembledMessage, ScanEvent, ScanSession};
+#[cfg(feature = "snapshot")]
+pub use snapshot::{Snapshot, SnapshotPolicy, detect_with_snapshot};
+pub use stages::{Candidate, Pipeline, SampledGrid, ScanLineProfile, ScanLineProfiles, StageImage};
+pub use telemetry::{FailedGrid, ImageCondition, RecoveryStages, StageStats, TelemetryStages};
 
 /// Per-image telemetry tracking which pipeline stages succeeded or failed.
 ///
 /// Every stage records its highest-water-mark count across all binarization
-/// strategies tried (primary + fallback).
+/// strategies tried (primary + fallback). [`DetectionTelemetry::stages`]
+/// groups the counters by stage with success rates and costs.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DetectionTelemetry {
     /// Whether binarization produced a non-empty binary matrix.
     pub binarize_ok: bool,
     /// Peak number of finder patterns detected across all binarization attempts.
     pub finder_patterns_found: usize,
+    /// Finder patterns (top-left, top-right, bottom-left) of each group that
+    /// decoded, in image coordinates, with their quality after edge-sharpness
+    /// weighting. Useful for tuning optics and lighting.
+    pub decoded_finders: Vec<[FinderPattern; 3]>,
+    /// Grids that read as far as error correction or payload parsing but
+    /// did not decode, best-ranked candidates first and at most 32; empty
+    /// unless [`DetectorConfig::export_failed_grids`] is set.
+    pub failed_grids: Vec<FailedGrid>,
+    /// Seed of the last stratified finder sample (see `finder_sample_kept`),
+    /// derived from the frame size; 0 when no pass sampled.
+    pub finder_sample_seed: u64,
+    /// Finder candidates seen by passes that had more than
+    /// `QR_FINDER_SAMPLE_CAP` and grouped a sample of them instead.
+    pub finder_sample_input: usize,
+    /// Finder candidates those passes kept in their sample.
+    pub finder_sample_kept: usize,
     /// Peak number of valid groups (triplets) formed from finder patterns.
     pub groups_found: usize,
     /// Number of groups where a perspective transform could be built.
@@ -60,12 +147,31 @@ pub struct DetectionTelemetry {
     pub budget_lane_medium: usize,
     /// Decode attempts consumed in the low-confidence lane.
     pub budget_lane_low: usize,
+    /// Of those, attempts on candidates sent there for noisy finder rings
+    /// (signal-to-noise under `QR_LOW_SNR_MAX`) whatever their geometry.
+    pub budget_lane_low_snr: usize,
+    /// Decode attempts the budget allowed, summed over ranked-group decodes;
+    /// compare with `decode_attempts`.
+    pub budget_attempts_allocated: usize,
+    /// Perspective transforms the budget allowed, summed likewise.
+    pub budget_transforms_allocated: usize,
+    /// Perspective transforms built against that allowance.
+    pub budget_transforms_used: usize,
+    /// Decode attempts allowed per confidence lane [high, medium, low];
+    /// compare with `budget_lane_high` and friends.
+    pub budget_lane_allocation: [usize; 3],
     /// Fallback transition count from Otsu to adaptive(31).
     pub bin_fallback_otsu_to_adaptive31: usize,
     /// Fallback transition count from adaptive(31) to adaptive(21).
     pub bin_fallback_adaptive31_to_adaptive21: usize,
     /// Number of successful decodes that happened on fallback binarization.
     pub bin_fallback_successes: usize,
+    /// Decode attempts each binarization policy was allowed after reserving
+    /// slices for later fallbacks: [otsu, adaptive(31), adaptive(21),
+    /// hybrid].
+    pub bin_policy_allowance: [usize; 4],
+    /// Decode attempts each binarization policy consumed, same order.
+    pub bin_policy_attempts: [usize; 4],
     /// Whether geometry rerank path was active for this image.
     pub rerank_enabled: bool,
     /// Number of top-1 reranked candidate decode attempts.
@@ -106,8 +212,36 @@ pub struct DetectionTelemetry {
     pub router_skew_estimate_deg: f32,
     /// Fast-signal region density proxy used by router v2.
     pub router_region_density_proxy: f32,
+    /// Fast-signal flatness (share of exactly equal same-tone neighbours)
+    /// the router uses to recognise screenshots and renders.
+    pub router_flatness: f32,
+    /// Fast-signal gray-level span (2nd to 98th percentile) used by the
+    /// condition classifier.
+    pub router_contrast_span: f32,
+    /// Finder ring signal-to-noise of the top-ranked group: the gap between
+    /// the mean gray of its light and dark rings over their pooled standard
+    /// deviation.
+    pub router_top_snr: f32,
+    /// Module sizes, in pixels, around which the finder candidates cluster,
+    /// the most populated first; two or more mean codes at different scales.
+    pub router_module_size_modes: Vec<f32>,
+    /// [`ImageCondition`] name the fast signals label this image with;
+    /// empty when no finder group reached the router.
+    pub image_condition: String,
     /// Number of decodes rejected by acceptance calibration threshold.
     pub acceptance_rejected: usize,
+    /// Number of ranked candidates skipped because they overlap a region
+    /// already solved by a strong decode.
+    pub solved_region_skips: usize,
+    /// Number of ranked candidates skipped because their geometry caps the
+    /// calibrated confidence below `DetectorConfig::min_confidence`.
+    pub min_confidence_skips: usize,
+    /// Number of times a confidence lane's run of decode failures at one
+    /// stage reordered the rest of a region's queue to try unlike
+    /// candidates (another module size or location) first.
+    pub failure_reorders: usize,
+    /// Number of codes decoded from candidates such a reorder moved forward.
+    pub failure_reorder_decodes: usize,
     /// Number of deskew decode attempts.
     pub deskew_attempts: usize,
     /// Number of successful deskew decode recoveries.
@@ -122,20 +256,98 @@ pub struct DetectionTelemetry {
     pub scale_retry_successes: usize,
     /// Number of candidates skipped from multi-scale retry due to budget/guardrails.
     pub scale_retry_skipped_by_budget: usize,
+    /// Number of threshold sweep decodes of grids that failed only at error correction.
+    pub threshold_sweep_attempts: usize,
+    /// Number of successful threshold sweep decodes.
+    pub threshold_sweep_successes: usize,
     /// Number of high-version subpixel precision attempts.
     pub hv_subpixel_attempts: usize,
     /// Number of high-version refinement attempts.
     pub hv_refine_attempts: usize,
     /// Number of successful high-version refinement decodes.
     pub hv_refine_successes: usize,
+    /// Number of iterative grid refinement runs.
+    pub grid_refine_attempts: usize,
+    /// Number of decodes that succeeded on an iteratively refined grid.
+    pub grid_refine_successes: usize,
+    /// Total measure/refit iterations across grid refinement runs.
+    pub grid_refine_iterations: usize,
+    /// Total feature residual removed by grid refinement (pixels).
+    pub grid_refine_residual_drop_px: f64,
+    /// Gray grid samplings served from the per-candidate cache.
+    pub grid_sample_cache_hits: usize,
     /// Number of RS erasure decode attempts.
     pub rs_erasure_attempts: usize,
     /// Number of successful RS erasure decodes.
     pub rs_erasure_successes: usize,
     /// RS erasure count histogram buckets: [1, 2-3, 4-6, 7+].
     pub rs_erasure_count_hist: [usize; 4],
+    /// RS blocks that failed to correct while decoding in the `high_density`
+    /// strategy profile.
+    pub rs_blocks_failed: usize,
+    /// RS blocks corrected in the `high_density` profile, by share of the
+    /// block's error capacity used: [0-25%, 25-50%, 50-75%, 75-100%].
+    pub rs_block_load_hist: [usize; 4],
+    /// High-density candidates whose version was pinned from the version
+    /// information blocks.
+    pub high_density_version_pins: usize,
+    /// High-density transforms dropped because the grid fit left too much
+    /// residual.
+    pub high_density_residual_rejects: usize,
+    /// Decodes read from the pitch-snapped, one-pixel-per-module grid of the
+    /// `synthetic` strategy profile.
+    pub synthetic_snap_decodes: usize,
+    /// Brute-forced format guesses skipped because the unmasked stream
+    /// could not open with a segment header that fits the symbol.
+    pub header_prefilter_skips: usize,
+    /// Decodes whose format info only read from the grid turned 90°, 180°
+    /// and 270° clockwise, for generators that place it as if the symbol
+    /// were rotated.
+    pub rotated_format_rescues: [usize; 3],
+    /// Decodes whose format info was masked with the `0x5412` constant in
+    /// reversed bit order, tagged [`FormatQuirk::WrongFormatMask`].
+    pub wrong_format_mask_rescues: usize,
+    /// Returned codes per binarization whose matrix decoded them, indexed
+    /// by [`Binarization::index`].
+    pub binarization_decodes: [usize; Binarization::ALL.len()],
     /// Number of candidate decode branches skipped by phase 9.11 time budget.
     pub phase11_time_budget_skips: usize,
+    /// Number of finder scans that skipped the column pass because the row
+    /// pass was already conclusive (see [`ColumnScan::Auto`]).
+    pub column_scans_skipped: usize,
+    /// Decodes rescued by each [`EncoderQuirk`] rule of
+    /// `DetectorConfig::encoder_quirks`, in [`EncoderQuirk::ALL`] order.
+    pub quirk_rescues: [usize; EncoderQuirk::ALL.len()],
+    /// Candidate decodes skipped because the same finder geometry already
+    /// failed Reed-Solomon under an earlier binarization of the image.
+    pub decode_memo_hits: usize,
+    /// Bit streams read out of a sampled grid and run through error
+    /// correction, one per format, traversal and grid tried.
+    pub stream_decode_attempts: usize,
+    /// Whether a pathology guard cut the work short instead of letting an
+    /// exploding image stall the call: too many finder candidates or finder
+    /// triples, triple ranking over its time budget, or one finder geometry
+    /// re-entering decoding more often than any sane retry would. See the
+    /// `QR_PATHOLOGY_*` and `QR_RANK_TIME_BUDGET_MS` knobs.
+    pub pathology_guard_tripped: bool,
+    /// Names of the [`Preprocessor`]s applied to the grayscale frame, in
+    /// order (see [`Detector::with_preprocessor`]).
+    pub preprocessors: Vec<String>,
+    /// Wall-clock time spent converting RGB input to grayscale, preprocessing
+    /// included (ms).
+    pub time_grayscale_ms: f64,
+    /// Wall-clock time spent across all binarization attempts, including ROI normalization (ms).
+    pub time_binarize_ms: f64,
+    /// Wall-clock time spent scanning binary matrices for finder patterns (ms).
+    pub time_finder_scan_ms: f64,
+    /// Wall-clock time spent grouping and reranking finder triplets (ms).
+    pub time_group_rank_ms: f64,
+    /// Wall-clock time spent inside candidate decode attempts, recovery included (ms).
+    pub time_decode_ms: f64,
+    /// Portion of `time_decode_ms` spent in heavy recovery paths (ms).
+    pub time_recovery_ms: f64,
+    /// Total wall-clock time for the whole telemetry detect call (ms).
+    pub time_total_ms: f64,
 }
 
 impl DetectionTelemetry {
@@ -153,6 +365,13 @@ impl DetectionTelemetry {
     }
 
     fn merge_high_water_from(&mut self, other: &Self) {
+        self.decoded_finders
+            .extend(other.decoded_finders.iter().cloned());
+        if other.finder_sample_seed != 0 {
+            self.finder_sample_seed = other.finder_sample_seed;
+        }
+        self.finder_sample_input += other.finder_sample_input;
+        self.finder_sample_kept += other.finder_sample_kept;
         self.groups_found = self.groups_found.max(other.groups_found);
         self.transforms_built = self.transforms_built.max(other.transforms_built);
         self.format_extracted = self.format_extracted.max(other.format_extracted);
@@ -164,9 +383,20 @@ impl DetectionTelemetry {
         self.budget_lane_high += other.budget_lane_high;
         self.budget_lane_medium += other.budget_lane_medium;
         self.budget_lane_low += other.budget_lane_low;
+        self.budget_lane_low_snr += other.budget_lane_low_snr;
+        self.budget_attempts_allocated += other.budget_attempts_allocated;
+        self.budget_transforms_allocated += other.budget_transforms_allocated;
+        self.budget_transforms_used += other.budget_transforms_used;
+        for i in 0..self.budget_lane_allocation.len() {
+            self.budget_lane_allocation[i] += other.budget_lane_allocation[i];
+        }
         self.bin_fallback_otsu_to_adaptive31 += other.bin_fallback_otsu_to_adaptive31;
         self.bin_fallback_adaptive31_to_adaptive21 += other.bin_fallback_adaptive31_to_adaptive21;
         self.bin_fallback_successes += other.bin_fallback_successes;
+        for i in 0..self.bin_policy_allowance.len() {
+            self.bin_policy_allowance[i] += other.bin_policy_allowance[i];
+            self.bin_policy_attempts[i] += other.bin_policy_attempts[i];
+        }
         self.rerank_enabled = self.rerank_enabled || other.rerank_enabled;
         self.rerank_top1_attempts += other.rerank_top1_attempts;
         self.rerank_top1_successes += other.rerank_top1_successes;
@@ -195,7 +425,17 @@ impl DetectionTelemetry {
         self.router_region_density_proxy = self
             .router_region_density_proxy
             .max(other.router_region_density_proxy);
+        self.router_flatness = self.router_flatness.max(other.router_flatness);
+        self.router_contrast_span = self.router_contrast_span.max(other.router_contrast_span);
+        self.router_top_snr = self.router_top_snr.max(other.router_top_snr);
+        if other.router_module_size_modes.len() > self.router_module_size_modes.len() {
+            self.router_module_size_modes = other.router_module_size_modes.clone();
+        }
         self.acceptance_rejected += other.acceptance_rejected;
+        self.solved_region_skips += other.solved_region_skips;
+        self.min_confidence_skips += other.min_confidence_skips;
+        self.failure_reorders += other.failure_reorders;
+        self.failure_reorder_decodes += other.failure_reorder_decodes;
         self.deskew_attempts += other.deskew_attempts;
         self.deskew_successes += other.deskew_successes;
         self.high_version_precision_attempts += other.high_version_precision_attempts;
@@ -203,35 +443,89 @@ impl DetectionTelemetry {
         self.scale_retry_attempts += other.scale_retry_attempts;
         self.scale_retry_successes += other.scale_retry_successes;
         self.scale_retry_skipped_by_budget += other.scale_retry_skipped_by_budget;
+        self.threshold_sweep_attempts += other.threshold_sweep_attempts;
+        self.threshold_sweep_successes += other.threshold_sweep_successes;
         self.hv_subpixel_attempts += other.hv_subpixel_attempts;
         self.hv_refine_attempts += other.hv_refine_attempts;
         self.hv_refine_successes += other.hv_refine_successes;
+        self.grid_refine_attempts += other.grid_refine_attempts;
+        self.grid_refine_successes += other.grid_refine_successes;
+        self.grid_refine_iterations += other.grid_refine_iterations;
+        self.grid_refine_residual_drop_px += other.grid_refine_residual_drop_px;
+        self.grid_sample_cache_hits += other.grid_sample_cache_hits;
         self.rs_erasure_attempts += other.rs_erasure_attempts;
         self.rs_erasure_successes += other.rs_erasure_successes;
         for i in 0..self.rs_erasure_count_hist.len() {
             self.rs_erasure_count_hist[i] += other.rs_erasure_count_hist[i];
         }
+        self.rs_blocks_failed += other.rs_blocks_failed;
+        for i in 0..self.rs_block_load_hist.len() {
+            self.rs_block_load_hist[i] += other.rs_block_load_hist[i];
+        }
+        self.high_density_version_pins += other.high_density_version_pins;
+        self.high_density_residual_rejects += other.high_density_residual_rejects;
+        self.synthetic_snap_decodes += other.synthetic_snap_decodes;
+        self.header_prefilter_skips += other.header_prefilter_skips;
+        for i in 0..self.rotated_format_rescues.len() {
+            self.rotated_format_rescues[i] += other.rotated_format_rescues[i];
+        }
+        self.wrong_format_mask_rescues += other.wrong_format_mask_rescues;
+        for i in 0..self.binarization_decodes.len() {
+            self.binarization_decodes[i] += other.binarization_decodes[i];
+        }
         self.phase11_time_budget_skips += other.phase11_time_budget_skips;
+        self.column_scans_skipped += other.column_scans_skipped;
+        for i in 0..self.quirk_rescues.len() {
+            self.quirk_rescues[i] += other.quirk_rescues[i];
+        }
+        self.decode_memo_hits += other.decode_memo_hits;
+        self.stream_decode_attempts += other.stream_decode_attempts;
+        self.pathology_guard_tripped =
+            self.pathology_guard_tripped || other.pathology_guard_tripped;
+        self.time_group_rank_ms += other.time_group_rank_ms;
+        self.time_decode_ms += other.time_decode_ms;
         if self.strategy_profile.is_empty() && !other.strategy_profile.is_empty() {
             self.strategy_profile = other.strategy_profile.clone();
         }
+        if self.image_condition.is_empty() && !other.image_condition.is_empty() {
+            self.image_condition = other.image_condition.clone();
+        }
+        if self.preprocessors.is_empty() {
+            self.preprocessors = other.preprocessors.clone();
+        }
         for i in 0..self.candidate_score_buckets.len() {
             self.candidate_score_buckets[i] += other.candidate_score_buckets[i];
         }
     }
 }
 
-use decoder::qr_decoder::{reset_decode_counters, take_decode_counters};
-use detector::contour::ContourDetector;
-use detector::finder::{FinderDetector, FinderPattern};
-use utils::binarization::{
-    adaptive_binarize, adaptive_binarize_into, otsu_binarize, otsu_binarize_into, sauvola_binarize,
-    threshold_binarize,
+use decoder::qr_decoder::{
+    scoped_decode_counters, with_decode_counters, with_failed_grid_origin, with_failed_grids,
 };
+use detector::connected_components::ImageStats;
+use detector::contour::ContourDetector;
+use detector::finder::{FinderConfig, FinderDetector, FinderPattern, FinderScratch};
+use frame::{Frame, Planes};
+use pipeline::CodeSink;
+use utils::binarization::{adaptive_binarize_into, otsu_binarize, otsu_binarize_into};
 use utils::grayscale::{
-    normalize_roi_local_contrast, rgb_to_grayscale, rgb_to_grayscale_with_buffer,
+    normalize_roi_local_contrast, percentile_contrast_span, rgb_to_grayscale,
+    rgb_to_grayscale_with_buffer,
 };
-use utils::memory_pool::BufferPool;
+use utils::memory_pool::{BufferPool, RoiScratch};
+
+use std::cell::RefCell;
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+thread_local! {
+    static ROI_SCRATCH: RefCell<RoiScratch> = RefCell::new(RoiScratch::new());
+}
+
+/// Milliseconds elapsed since `started`, for telemetry stage timers.
+pub(crate) fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
 
 fn auto_window(width: usize, height: usize) -> usize {
     let base = (width.min(height) / 24).max(31);
@@ -250,7 +544,7 @@ fn contrast_stretch(gray: &[u8]) -> Vec<u8> {
         max_v = max_v.max(v);
     }
 
-    if max_v <= min_v + 8 {
+    if max_v <= min_v.saturating_add(8) {
         return gray.to_vec();
     }
 
@@ -285,76 +579,133 @@ fn rotate_gray_45(gray: &[u8], width: usize, height: usize) -> Vec<u8> {
     out
 }
 
-fn run_detection_strategies(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
-    let window = auto_window(width, height);
-    let otsu = otsu_binarize(gray, width, height);
-    let adaptive = adaptive_binarize(gray, width, height, window);
-    let sauvola_k02 = sauvola_binarize(gray, width, height, window, 0.2);
-    let sauvola_k01 = sauvola_binarize(gray, width, height, window, 0.1);
-    let sauvola_k03 = sauvola_binarize(gray, width, height, window, 0.3);
-
-    let mut variants = vec![sauvola_k02, adaptive, otsu];
-
-    let mut sorted = gray.to_vec();
-    sorted.sort_unstable();
-    let median = sorted[sorted.len() / 2] as i16;
-    let t_dark = (median - 26).clamp(0, 255) as u8;
-    let t_light = (median + 26).clamp(0, 255) as u8;
-    variants.push(threshold_binarize(gray, width, height, t_dark));
-    variants.push(threshold_binarize(gray, width, height, t_light));
-
-    variants.push(sauvola_k01);
-    variants.push(sauvola_k03);
+/// Map codes found in a [`rotate_gray_45`] image back into frame coordinates.
+fn unrotate_codes_45(codes: &mut [QRCode], width: usize, height: usize) {
+    let cx = (width as f32 - 1.0) * 0.5;
+    let cy = (height as f32 - 1.0) * 0.5;
+    let (sin_t, cos_t) = 45.0f32.to_radians().sin_cos();
+    for code in codes {
+        for p in &mut code.position {
+            let (dx, dy) = (p.x - cx, p.y - cy);
+            *p = Point::new(cos_t * dx + sin_t * dy + cx, -sin_t * dx + cos_t * dy + cy);
+        }
+        code.rotation_deg -= 45.0;
+        if code.rotation_deg <= -180.0 {
+            code.rotation_deg += 360.0;
+        }
+        code.physical_size_mm = config::physical_size_mm(code);
+    }
+}
 
+fn run_detection_strategies(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    // The strategies threshold the hybrid pass over the frame-sized window;
+    // the policies' 31 px one reads fewer codes on multi-symbol frames.
+    let frame = Frame::new(gray, width, height).with_hybrid_window(auto_window(width, height));
+    let mut variants = vec![
+        Binarization::Hybrid,
+        Binarization::Sauvola,
+        Binarization::Adaptive,
+        Binarization::Otsu,
+        Binarization::ThresholdDark,
+        Binarization::ThresholdLight,
+        Binarization::SauvolaLowK,
+        Binarization::SauvolaHighK,
+    ];
     // Add larger window variants for high-version QR codes
-    let large_window = (window * 2).clamp(63, 255);
-    if large_window != window {
-        variants.push(sauvola_binarize(gray, width, height, large_window, 0.2));
-        variants.push(adaptive_binarize(gray, width, height, large_window));
+    if frame.wide_window() != frame.window() {
+        variants.extend([Binarization::SauvolaWide, Binarization::AdaptiveWide]);
     }
 
-    let mut results = Vec::new();
-    for binary in variants {
-        let finder_patterns = detect_finder_patterns(&binary, width, height);
-        let decoded = if finder_patterns.len() >= 2 {
-            decode_groups_with_module_aware_retry(&binary, gray, width, height, &finder_patterns)
-        } else {
-            Vec::new()
-        };
-        let finder_decode_failed = decoded.is_empty();
-        for qr in decoded {
-            if !results.iter().any(|r: &QRCode| r.content == qr.content) {
-                results.push(qr);
+    // Every variant decodes regions of the same gray; each is only
+    // binarized once the ones before it have come up empty.
+    pipeline::with_decode_memo(|| {
+        let mut results = Vec::new();
+        for kind in variants {
+            let Some(planes) = frame.planes(kind) else {
+                continue;
+            };
+            // One sweep labels the regions the contour detector needs and
+            // measures the saturation the router reads.
+            let stats = ImageStats::compute(planes.binary, gray);
+            let finder_patterns = detect_finder_patterns(planes.binary, width, height);
+            let decoded = if finder_patterns.len() >= 2 {
+                pipeline::with_image_stats(gray, &stats, || {
+                    config::with_binarization(kind, || {
+                        decode_groups_with_module_aware_retry(planes, &finder_patterns)
+                    })
+                })
+            } else {
+                Vec::new()
+            };
+            let finder_decode_failed = decoded.is_empty();
+            for qr in decoded {
+                if !results.iter().any(|r: &QRCode| r.content == qr.content) {
+                    results.push(qr);
+                }
             }
-        }
-        // Trigger contour detector more aggressively for pathological/noncompliant cases
-        // Also try when finder patterns exist but decode failed (not just <2 patterns)
-        if results.is_empty() || (finder_patterns.len() >= 2 && finder_decode_failed) {
-            let contour_patterns = ContourDetector::detect(&binary);
-            if contour_patterns.len() >= 2 {
-                let contour_decoded =
-                    pipeline::decode_groups(&binary, gray, width, height, &contour_patterns);
-                for qr in contour_decoded {
-                    if !results.iter().any(|r: &QRCode| r.content == qr.content) {
-                        results.push(qr);
+            // Trigger contour detector more aggressively for pathological/noncompliant cases
+            // Also try when finder patterns exist but decode failed (not just <2 patterns)
+            let contour_wanted =
+                results.is_empty() || (finder_patterns.len() >= 2 && finder_decode_failed);
+            if contour_wanted && !config::ablated(Ablation::ContourDetector) {
+                let contour_patterns =
+                    ContourDetector::detect_in_regions(planes.binary, &stats.regions);
+                if contour_patterns.len() >= 2 {
+                    let contour_decoded = pipeline::with_image_stats(gray, &stats, || {
+                        config::with_binarization(kind, || {
+                            pipeline::decode_groups(planes, &contour_patterns)
+                        })
+                    });
+                    for qr in contour_decoded {
+                        if !results.iter().any(|r: &QRCode| r.content == qr.content) {
+                            results.push(qr);
+                        }
                     }
                 }
             }
+            if !results.is_empty() {
+                return results;
+            }
         }
-        if !results.is_empty() {
-            return results;
-        }
-    }
 
-    results
+        results
+    })
+}
+
+/// Finder scan options from the active [`DetectorConfig`].
+fn finder_config() -> FinderConfig {
+    config::with_active(|c| FinderConfig {
+        min_module_size: c.min_module_size.filter(|m| *m > 1.0),
+        column_scan: c.column_scan,
+        ..Default::default()
+    })
 }
 
+/// Whether a `width`x`height` frame is scanned coarse-to-fine on a pyramid;
+/// a configured `min_module_size` stepped scan takes precedence.
+fn uses_pyramid_scan(width: usize, height: usize, config: &FinderConfig) -> bool {
+    width >= 1600 && height >= 1600 && config.min_module_size.is_none()
+}
+
+/// Finder patterns to group and decode; none when the call is shed at
+/// [`ShedStage::Decode`].
 fn detect_finder_patterns(binary: &BitMatrix, width: usize, height: usize) -> Vec<FinderPattern> {
-    if width >= 1600 && height >= 1600 {
+    let finder_config = finder_config();
+    let mut patterns = if uses_pyramid_scan(width, height, &finder_config) {
         FinderDetector::detect_with_pyramid(binary)
     } else {
-        FinderDetector::detect(binary)
+        FinderDetector::detect_with_config(binary, &finder_config)
+    };
+    if config::with_active(|c| c.tolerate_missing_quiet_zone) {
+        patterns = FinderDetector::with_flush_candidates(binary, patterns);
     }
+    if config::with_active(|c| c.inverted_finders) {
+        patterns = FinderDetector::with_inverted_candidates(binary, &finder_config, patterns);
+    }
+    if !load_shed::admit_candidates(width, height, patterns.len()) {
+        patterns.clear();
+    }
+    patterns
 }
 
 fn adaptive_window_from_module_size(module_size: f32) -> usize {
@@ -372,6 +723,39 @@ enum BinarizationPolicy {
     Otsu,
     Adaptive31,
     Adaptive21,
+    Hybrid,
+}
+
+impl BinarizationPolicy {
+    #[cfg(feature = "snapshot")]
+    fn as_str(self) -> &'static str {
+        match self {
+            BinarizationPolicy::Otsu => "otsu",
+            BinarizationPolicy::Adaptive31 => "adaptive31",
+            BinarizationPolicy::Adaptive21 => "adaptive21",
+            BinarizationPolicy::Hybrid => "hybrid",
+        }
+    }
+
+    /// The [`Binarization`] codes decoded from this policy's matrix report
+    fn binarization(self) -> Binarization {
+        match self {
+            BinarizationPolicy::Otsu => Binarization::Otsu,
+            BinarizationPolicy::Adaptive31 => Binarization::Adaptive31,
+            BinarizationPolicy::Adaptive21 => Binarization::Adaptive21,
+            BinarizationPolicy::Hybrid => Binarization::Hybrid,
+        }
+    }
+
+    /// Slot in the per-policy telemetry arrays
+    fn index(self) -> usize {
+        match self {
+            BinarizationPolicy::Otsu => 0,
+            BinarizationPolicy::Adaptive31 => 1,
+            BinarizationPolicy::Adaptive21 => 2,
+            BinarizationPolicy::Hybrid => 3,
+        }
+    }
 }
 
 fn initial_policy(width: usize, height: usize) -> BinarizationPolicy {
@@ -384,7 +768,9 @@ fn initial_policy(width: usize, height: usize) -> BinarizationPolicy {
 
 fn phase9_binarization_sequence(width: usize, height: usize) -> Vec<BinarizationPolicy> {
     let strict = initial_policy(width, height);
-    let mut sequence = vec![strict];
+    // The hybrid matrix covers most of what the single-threshold passes
+    // would each fix, so it goes first among the fallbacks.
+    let mut sequence = vec![strict, BinarizationPolicy::Hybrid];
     for policy in [
         BinarizationPolicy::Otsu,
         BinarizationPolicy::Adaptive31,
@@ -403,17 +789,30 @@ fn binarize_with_policy(
     height: usize,
     policy: BinarizationPolicy,
 ) -> BitMatrix {
-    match policy {
-        BinarizationPolicy::Otsu => otsu_binarize(gray, width, height),
-        BinarizationPolicy::Adaptive31 => adaptive_binarize(gray, width, height, 31),
-        BinarizationPolicy::Adaptive21 => adaptive_binarize(gray, width, height, 21),
-    }
+    Frame::new(gray, width, height)
+        .into_binary(policy.binarization())
+        .expect("policies binarize the whole frame")
+}
+
+/// The `policy` matrix of `frame` with its gray; the frame keeps each one,
+/// and the integrals they share, for the policies after it.
+fn policy_planes<'f>(frame: &'f Frame<'_>, policy: BinarizationPolicy) -> Planes<'f> {
+    frame
+        .planes(policy.binarization())
+        .expect("policies binarize the whole frame")
 }
 
 fn image_decode_attempt_budget() -> usize {
     decoder::config::image_decode_attempt_budget()
 }
 
+/// Attempts a policy may spend out of `remaining` while `reserve` stays held
+/// back for each of the `later` fallbacks after it. Whatever a policy leaves
+/// unspent rolls forward to the next one.
+fn policy_attempt_allowance(remaining: usize, reserve: usize, later: usize) -> usize {
+    remaining.saturating_sub(reserve * later)
+}
+
 fn record_binarization_transition(
     tel: &mut DetectionTelemetry,
     from: BinarizationPolicy,
@@ -426,19 +825,6 @@ fn record_binarization_transition(
     }
 }
 
-fn grayscale_contrast_span(gray: &[u8]) -> u8 {
-    if gray.is_empty() {
-        return 0;
-    }
-    let mut min_v = u8::MAX;
-    let mut max_v = u8::MIN;
-    for &v in gray {
-        min_v = min_v.min(v);
-        max_v = max_v.max(v);
-    }
-    max_v.saturating_sub(min_v)
-}
-
 fn finder_roi_bounds(
     finder_patterns: &[FinderPattern],
     width: usize,
@@ -474,19 +860,25 @@ fn finder_roi_bounds(
 }
 
 fn decode_groups_with_module_aware_retry(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     finder_patterns: &[FinderPattern],
 ) -> Vec<QRCode> {
-    let mut results = pipeline::decode_groups(binary, gray, width, height, finder_patterns);
+    let Planes {
+        gray,
+        width,
+        height,
+        ..
+    } = planes;
+    let mut results = pipeline::decode_groups(planes, finder_patterns);
     if !results.is_empty() {
         return results;
     }
 
+    if !features::FULL_RECOVERY {
+        return results;
+    }
     if finder_patterns.len() == 2 {
-        return decode_two_finder_fallback(binary, gray, width, height, finder_patterns);
+        return decode_two_finder_fallback(planes, finder_patterns);
     }
     if finder_patterns.len() < 3 {
         return results;
@@ -496,63 +888,208 @@ fn decode_groups_with_module_aware_retry(
     module_sizes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
     let median_module = module_sizes[module_sizes.len() / 2];
     let window = adaptive_window_from_module_size(median_module);
-
-    let retry_binary = adaptive_binarize(gray, width, height, window);
-    let retry_patterns = detect_finder_patterns(&retry_binary, width, height);
-    if retry_patterns.len() < 3 {
+    let Some(roi) = finder_roi_bounds(finder_patterns, width, height) else {
         return results;
-    }
+    };
 
-    results = pipeline::decode_groups(&retry_binary, gray, width, height, &retry_patterns);
+    results = with_roi_binarized(gray, width, roi, window, false, |roi_planes| {
+        let retry_patterns =
+            detect_finder_patterns(roi_planes.binary, roi_planes.width, roi_planes.height);
+        if retry_patterns.len() < 3 {
+            return Vec::new();
+        }
+        config::with_binarization(Binarization::AdaptiveModule, || {
+            pipeline::decode_groups(roi_planes, &retry_patterns)
+        })
+    });
+    offset_codes(&mut results, roi);
     results
 }
 
-fn decode_two_finder_fallback(
-    binary: &BitMatrix,
+/// Crop `roi = (x0, y0, x1, y1)` of `gray` into this thread's pooled scratch,
+/// optionally stretch its contrast, adaptive-binarize it, and run `f` on the
+/// cropped binary and gray planes in ROI coordinates.
+///
+/// The window is clamped to the ROI so small crops still threshold locally.
+fn with_roi_binarized<R>(
     gray: &[u8],
     width: usize,
-    height: usize,
+    roi: (usize, usize, usize, usize),
+    window: usize,
+    normalize: bool,
+    f: impl FnOnce(Planes<'_>) -> R,
+) -> R {
+    ROI_SCRATCH.with(|cell| {
+        let mut guard;
+        let mut fresh;
+        let scratch: &mut RoiScratch = match cell.try_borrow_mut() {
+            Ok(borrowed) => {
+                guard = borrowed;
+                &mut guard
+            }
+            // Re-entered from inside a callback: use a one-off scratch.
+            Err(_) => {
+                fresh = RoiScratch::new();
+                &mut fresh
+            }
+        };
+        let (roi_w, roi_h) = (roi.2 - roi.0, roi.3 - roi.1);
+        let (roi_gray, binary, integral) = scratch.crop(gray, width, roi);
+        if normalize {
+            let stretched =
+                normalize_roi_local_contrast(roi_gray, roi_w, roi_h, (0, 0, roi_w, roi_h));
+            roi_gray.copy_from_slice(&stretched);
+        }
+        let window = window.min(roi_w.min(roi_h).saturating_sub(1) | 1);
+        adaptive_binarize_into(roi_gray, roi_w, roi_h, window, binary, integral);
+        let (x0, y0) = (roi.0 as f32, roi.1 as f32);
+        config::with_crop_origin(x0, y0, || {
+            with_failed_grid_origin(x0, y0, || {
+                pipeline::without_decode_memo(|| f(Planes::new(binary, roi_gray)))
+            })
+        })
+    })
+}
+
+/// Shift code corners from ROI coordinates back into frame coordinates.
+fn offset_codes(codes: &mut [QRCode], roi: (usize, usize, usize, usize)) {
+    let (dx, dy) = (roi.0 as f32, roi.1 as f32);
+    for code in codes {
+        for p in &mut code.position {
+            p.x += dx;
+            p.y += dy;
+        }
+    }
+}
+
+fn decode_two_finder_fallback(
+    planes: Planes<'_>,
     finder_patterns: &[FinderPattern],
 ) -> Vec<QRCode> {
-    decode_two_finder_fallback_limited(binary, gray, width, height, finder_patterns, None, None)
+    decode_two_finder_fallback_limited(planes, finder_patterns, None, None)
 }
 
-fn decode_two_finder_fallback_limited(
-    binary: &BitMatrix,
-    gray: &[u8],
+/// Guessed third finders decoded per two-finder fallback, best rerank
+/// score first.
+const TWO_FINDER_GUESSES: usize = 4;
+
+/// Plausible centres for the finder missing next to `a` and `b`, inside a
+/// `width` x `height` frame. The pair is either a side of the symbol, with
+/// the third finder on the perpendicular through either end, or its
+/// diagonal, with the third on the perpendicular through the midpoint. Each
+/// is tried at the pair's own spacing and at the spans of the versions
+/// within two of the one that spacing implies, so a misjudged module size
+/// or mild perspective still puts a guess on the real corner.
+fn third_finder_candidates(
+    a: &FinderPattern,
+    b: &FinderPattern,
     width: usize,
     height: usize,
-    finder_patterns: &[FinderPattern],
-    mut remaining_attempts: Option<&mut usize>,
-    mut telemetry: Option<&mut DetectionTelemetry>,
-) -> Vec<QRCode> {
-    if finder_patterns.len() < 2 {
-        return Vec::new();
-    }
-    let a = &finder_patterns[0];
-    let b = &finder_patterns[1];
+) -> Vec<Point> {
     let vx = b.center.x - a.center.x;
     let vy = b.center.y - a.center.y;
     let len = (vx * vx + vy * vy).sqrt();
     if len < 6.0 {
         return Vec::new();
     }
-    let nx = -vy / len;
-    let ny = vx / len;
-    let span = len;
+    let (nx, ny) = (-vy / len, vx / len);
     let module = ((a.module_size + b.module_size) * 0.5).max(1.0);
-
-    let candidates = [
-        Point::new(a.center.x + nx * span, a.center.y + ny * span),
-        Point::new(b.center.x + nx * span, b.center.y + ny * span),
-        Point::new(a.center.x - nx * span, a.center.y - ny * span),
-        Point::new(b.center.x - nx * span, b.center.y - ny * span),
+    let mid = Point::new(
+        (a.center.x + b.center.x) * 0.5,
+        (a.center.y + b.center.y) * 0.5,
+    );
+
+    let side = [a.center, b.center];
+    let diagonal = [mid];
+    // (anchors, finder-centre spacing along a side, reach from an anchor
+    // per unit of that spacing)
+    let layouts: [(&[Point], f32, f32); 2] = [
+        (&side, len, 1.0),
+        (
+            &diagonal,
+            len / std::f32::consts::SQRT_2,
+            std::f32::consts::FRAC_1_SQRT_2,
+        ),
     ];
 
-    for c in candidates {
-        if c.x < 0.0 || c.y < 0.0 || c.x >= width as f32 || c.y >= height as f32 {
-            continue;
+    let mut candidates: Vec<Point> = Vec::new();
+    for (anchors, spacing, reach) in layouts {
+        // Finder centres sit (dimension - 7) = (4 * version + 10) modules apart.
+        let version = ((spacing / module - 10.0) / 4.0).round() as i32;
+        let spans = std::iter::once(spacing).chain(
+            ((version - 2).max(1)..=(version + 2).min(40)).map(|v| (4 * v + 10) as f32 * module),
+        );
+        for span in spans {
+            for anchor in anchors {
+                for sign in [1.0f32, -1.0] {
+                    let c = Point::new(
+                        anchor.x + sign * nx * span * reach,
+                        anchor.y + sign * ny * span * reach,
+                    );
+                    if c.x < 0.0 || c.y < 0.0 || c.x >= width as f32 || c.y >= height as f32 {
+                        continue;
+                    }
+                    if candidates.iter().any(|p| p.distance(&c) < 2.0 * module) {
+                        continue;
+                    }
+                    candidates.push(c);
+                }
+            }
         }
+    }
+    candidates
+}
+
+fn decode_two_finder_fallback_limited(
+    planes: Planes<'_>,
+    finder_patterns: &[FinderPattern],
+    mut remaining_attempts: Option<&mut usize>,
+    mut telemetry: Option<&mut DetectionTelemetry>,
+) -> Vec<QRCode> {
+    let Planes {
+        binary,
+        width,
+        height,
+        ..
+    } = planes;
+    if finder_patterns.len() < 2 || config::ablated(Ablation::TwoFinder) {
+        return Vec::new();
+    }
+    let a = &finder_patterns[0];
+    let b = &finder_patterns[1];
+    let module = ((a.module_size + b.module_size) * 0.5).max(1.0);
+    // A guess's quality is how finder-like the binary looks there, read
+    // along the axis towards the nearer known finder; it counts again next
+    // to the rerank score, since the rerank features alone cannot tell a
+    // real corner from its mirror image across the pair.
+    let triples: Vec<[FinderPattern; 3]> = third_finder_candidates(a, b, width, height)
+        .into_iter()
+        .map(|c| {
+            let near = if c.distance(&a.center) <= c.distance(&b.center) {
+                a.center
+            } else {
+                b.center
+            };
+            let d = c.distance(&near).max(1.0);
+            let axis = ((near.x - c.x) / d, (near.y - c.y) / d);
+            let quality = FinderDetector::template_agreement(binary, &c, axis, module);
+            [
+                a.clone(),
+                b.clone(),
+                FinderPattern::with_quality(c.x, c.y, module, quality),
+            ]
+        })
+        .collect();
+
+    let scores = pipeline::triple_rerank_scores(planes, &triples);
+    let mut ranked: Vec<(f32, &[FinderPattern; 3])> = scores
+        .into_iter()
+        .zip(&triples)
+        .filter_map(|(score, triple)| Some((score? + triple[2].quality, triple)))
+        .collect();
+    ranked.sort_by(|x, y| y.0.partial_cmp(&x.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (_, fused) in ranked.into_iter().take(TWO_FINDER_GUESSES) {
         if let Some(remaining) = remaining_attempts.as_deref_mut() {
             if *remaining == 0 {
                 if let Some(tel) = telemetry.as_deref_mut() {
@@ -561,29 +1098,16 @@ fn decode_two_finder_fallback_limited(
                 break;
             }
         }
-        let synthetic = FinderPattern {
-            center: c,
-            module_size: module,
-        };
-        let trial = vec![&finder_patterns[0], &finder_patterns[1], &synthetic];
-        let mut fused = Vec::with_capacity(3);
-        for p in trial {
-            fused.push(FinderPattern {
-                center: p.center,
-                module_size: p.module_size,
-            });
-        }
         let decoded = if let Some(remaining) = remaining_attempts.as_deref_mut() {
-            let (decoded, decode_tel) = pipeline::decode_groups_with_telemetry_limited(
-                binary, gray, width, height, &fused, *remaining,
-            );
+            let (decoded, decode_tel) =
+                pipeline::decode_groups_with_telemetry_limited(planes, fused, *remaining);
             *remaining = remaining.saturating_sub(decode_tel.decode_attempts);
             if let Some(tel) = telemetry.as_deref_mut() {
                 tel.merge_high_water_from(&decode_tel);
             }
             decoded
         } else {
-            pipeline::decode_groups(binary, gray, width, height, &fused)
+            pipeline::decode_groups(planes, fused)
         };
         if !decoded.is_empty() {
             return decoded;
@@ -594,16 +1118,70 @@ fn decode_two_finder_fallback_limited(
 }
 
 fn run_fast_path(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    run_fast_path_streaming(gray, width, height, None)
+}
+
+/// [`run_fast_path`], offering each code to `sink` as it decodes.
+fn run_fast_path_streaming(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    sink: Option<&mut CodeSink<'_>>,
+) -> Vec<QRCode> {
     // Fast path: one cheap global threshold pass only.
     let binary = otsu_binarize(gray, width, height);
     let finder_patterns = detect_finder_patterns(&binary, width, height);
     if finder_patterns.len() < 2 {
         return Vec::new();
     }
-    pipeline::decode_groups(&binary, gray, width, height, &finder_patterns)
+    let planes = Planes::new(&binary, gray);
+    config::with_binarization(Binarization::Otsu, || match sink {
+        Some(sink) => pipeline::decode_groups_streaming(planes, &finder_patterns, sink),
+        None => pipeline::decode_groups(planes, &finder_patterns),
+    })
+}
+
+/// [`run_fast_path`] over pooled buffers: Otsu output goes into `binary` and
+/// finder scanning into `finder`, so once they have grown to the frame size a
+/// frame with fewer than two finder patterns allocates nothing. Pyramid scans
+/// (1600px+ on both sides), `tolerate_missing_quiet_zone` and
+/// `inverted_finders` build their own
+/// candidate lists and still allocate.
+fn run_fast_path_into(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    binary: &mut BitMatrix,
+    finder: &mut FinderScratch,
+) -> Vec<QRCode> {
+    otsu_binarize_into(gray, width, height, binary);
+    let owned;
+    let finder_config = finder_config();
+    let finder_patterns: &[FinderPattern] = if uses_pyramid_scan(width, height, &finder_config)
+        || config::with_active(|c| c.tolerate_missing_quiet_zone || c.inverted_finders)
+    {
+        owned = detect_finder_patterns(binary, width, height);
+        &owned
+    } else {
+        let patterns = FinderDetector::detect_into_with_config(binary, &finder_config, finder);
+        if !load_shed::admit_candidates(width, height, patterns.len()) {
+            return Vec::new();
+        }
+        patterns
+    };
+    if finder_patterns.len() < 2 {
+        return Vec::new();
+    }
+    let planes = Planes::new(binary, gray);
+    config::with_binarization(Binarization::Otsu, || {
+        pipeline::decode_groups(planes, finder_patterns)
+    })
 }
 
 fn run_detection_with_phase4_fallbacks(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    if !features::FULL_RECOVERY || !load_shed::admit_frame(ShedStage::Fallbacks, width, height) {
+        return Vec::new();
+    }
     let mut results = run_detection_strategies(gray, width, height);
     if !results.is_empty() {
         return results;
@@ -616,7 +1194,30 @@ fn run_detection_with_phase4_fallbacks(gray: &[u8], width: usize, height: usize)
     }
 
     let rotated = rotate_gray_45(gray, width, height);
-    run_detection_strategies(&rotated, width, height)
+    let mut results = run_detection_strategies(&rotated, width, height);
+    unrotate_codes_45(&mut results, width, height);
+    if results.is_empty() {
+        results = narrow_quiet_zone_pass(gray, width, height);
+    }
+    results
+}
+
+/// The [`EncoderQuirk::NarrowQuietZone`] rule: the fast path again with
+/// `tolerate_missing_quiet_zone` on, when the rule is configured and that
+/// option is not already set.
+fn narrow_quiet_zone_pass(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    if !config::tolerates(EncoderQuirk::NarrowQuietZone)
+        || config::with_active(|c| c.tolerate_missing_quiet_zone)
+    {
+        return Vec::new();
+    }
+    let mut tolerant = config::with_active(DetectorConfig::clone);
+    tolerant.tolerate_missing_quiet_zone = true;
+    let codes = tolerant.apply(|| run_fast_path(gray, width, height));
+    if !codes.is_empty() {
+        decoder::qr_decoder::record_quirk_rescue(EncoderQuirk::NarrowQuietZone);
+    }
+    codes
 }
 
 /// Detect QR codes in an RGB image
@@ -629,8 +1230,33 @@ fn run_detection_with_phase4_fallbacks(gray: &[u8], width: usize, height: usize)
 /// # Returns
 /// Vector of detected QR codes
 ///
-/// Uses pyramid detection for large images (800px+) for better performance
+/// Uses pyramid detection for large images (800px+) for better performance.
+/// A call shed by the [`load_shed`] limits returns no codes; use
+/// [`detect_or_shed`] to tell the two apart.
 pub fn detect(image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    detect_or_shed(image, width, height).unwrap_or_default()
+}
+
+/// [`detect`], reporting a call stopped by `DetectorConfig::max_pixels`,
+/// `max_candidates` or `load_shed_policy` as the [`Shed`] that stopped it
+pub fn detect_or_shed(image: &[u8], width: usize, height: usize) -> Result<Vec<QRCode>, Shed> {
+    let _frame = scoped_decode_counters();
+    match load_shed::scoped(|| detect_rgb(image, width, height)) {
+        (_, Some(shed)) => Err(shed),
+        (codes, None) => Ok(codes),
+    }
+}
+
+fn detect_rgb(image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    if !load_shed::admit_frame(ShedStage::Frame, width, height) {
+        return Vec::new();
+    }
+    if let Some(cap) = config::with_active(|c| c.max_memory_bytes)
+        && let Some(codes) = memory::detect_within(image, width, height, cap)
+    {
+        return codes;
+    }
+
     // Step 1: Convert to grayscale
     let gray = rgb_to_grayscale(image, width, height);
     let fast = run_fast_path(&gray, width, height);
@@ -641,128 +1267,334 @@ pub fn detect(image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
     run_detection_with_phase4_fallbacks(&gray, width, height)
 }
 
-/// Detect QR codes in an RGB image, returning telemetry about which pipeline
-/// stages succeeded or failed. This is intended for benchmark diagnostics.
+/// Detect QR codes in an RGB image, handing each to `on_code` as soon as it
+/// decodes instead of after the whole pipeline
 ///
-/// The telemetry records the high-water-mark across all binarization attempts
-/// so callers can determine *where* the pipeline stalls for a given image.
-pub fn detect_with_telemetry(
+/// Delivers the codes [`detect`] would return. On the fast path each code
+/// arrives the moment its candidate is accepted; codes found by the fallback
+/// binarizations arrive together when the binarization that found them
+/// finishes. Returning [`ControlFlow::Break`] stops the call: no further
+/// candidate is decoded and no later stage runs, so
+/// `|_| ControlFlow::Break(())` is a "first code wins" scan. A call stopped
+/// by a [`load_shed`] limit delivers nothing more.
+pub fn detect_streaming(
     image: &[u8],
     width: usize,
     height: usize,
-) -> (Vec<QRCode>, DetectionTelemetry) {
-    let mut tel = DetectionTelemetry::default();
-    reset_decode_counters();
-
-    // Step 1: Convert to grayscale
-    let gray = rgb_to_grayscale(image, width, height);
-
-    // Step 2+: strict path first, then bounded fallback binarization ensemble on miss.
-    let policies = phase9_binarization_sequence(width, height);
-    let mut remaining_attempts = image_decode_attempt_budget();
-    let mut results = Vec::new();
-    let mut prev_policy = policies[0];
-    let mut best_finder_patterns: Vec<FinderPattern> = Vec::new();
-    tel.binarize_ok = true;
-    for (i, &policy) in policies.iter().enumerate() {
-        if i > 0 {
-            record_binarization_transition(&mut tel, prev_policy, policy);
-            prev_policy = policy;
+    mut on_code: impl FnMut(QRCode) -> ControlFlow<()>,
+) {
+    let _frame = scoped_decode_counters();
+    load_shed::scoped(|| {
+        if !load_shed::admit_frame(ShedStage::Frame, width, height) {
+            return;
         }
-        if remaining_attempts == 0 {
-            tel.budget_skips += 1;
-            break;
+        if let Some(cap) = config::with_active(|c| c.max_memory_bytes)
+            && let Some(codes) = memory::detect_within(image, width, height, cap)
+        {
+            let _ = codes.into_iter().try_for_each(&mut on_code);
+            return;
         }
 
-        let binary = binarize_with_policy(&gray, width, height, policy);
-        let finder_patterns = if width >= 1600 && height >= 1600 {
-            FinderDetector::detect_with_pyramid(&binary)
-        } else {
-            FinderDetector::detect(&binary)
-        };
-        if finder_patterns.len() > best_finder_patterns.len() {
-            best_finder_patterns = finder_patterns.clone();
+        let gray = rgb_to_grayscale(image, width, height);
+        let mut sink = |qr: &QRCode| on_code(qr.clone());
+        // Every code the fast path returns has been delivered, and a
+        // `Break` always follows a delivery, so codes here mean we are done.
+        if !run_fast_path_streaming(&gray, width, height, Some(&mut sink)).is_empty() {
+            return;
         }
-        tel.finder_patterns_found = tel.finder_patterns_found.max(finder_patterns.len());
-
-        if finder_patterns.len() >= 3 {
-            let (decoded, decode_tel) = pipeline::decode_groups_with_telemetry_limited(
-                &binary,
-                &gray,
-                width,
-                height,
-                &finder_patterns,
-                remaining_attempts,
-            );
-            remaining_attempts = remaining_attempts.saturating_sub(decode_tel.decode_attempts);
-            tel.merge_high_water_from(&decode_tel);
-            if !decoded.is_empty() {
-                if i > 0 {
-                    tel.bin_fallback_successes += 1;
-                }
-                results = decoded;
+        let _ = run_detection_with_phase4_fallbacks(&gray, width, height)
+            .into_iter()
+            .try_for_each(&mut on_code);
+    });
+}
+
+/// Detect QR codes in an RGB image with non-default [`DetectorConfig`] options
+///
+/// Use `tolerate_missing_quiet_zone` for labels printed flush against the
+/// edge of the media or surrounding artwork.
+pub fn detect_with_config(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    config: &DetectorConfig,
+) -> Vec<QRCode> {
+    config.apply(|| detect(image, width, height))
+}
+
+/// Detect QR codes in an RGB image, keeping those whose calibrated
+/// [`QRCode::confidence`] is at least `min_confidence`
+///
+/// Sets [`DetectorConfig::min_confidence`] on top of the configuration
+/// already applied to this thread, if any, so candidates that could not
+/// reach the floor are skipped rather than decoded and then dropped.
+pub fn detect_with_min_confidence(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    min_confidence: f32,
+) -> Vec<QRCode> {
+    let config = DetectorConfig {
+        min_confidence: Some(min_confidence),
+        ..config::with_active(DetectorConfig::clone)
+    };
+    detect_with_config(image, width, height, &config)
+}
+
+/// Fallible variant of [`detect`] that validates the buffer and reports misses.
+///
+/// Returns [`Error::InvalidImage`] when `image` is not `width * height * 3` bytes,
+/// [`Error::Shed`] when a [`load_shed`] limit stops the call and
+/// [`Error::NotFound`] when no QR code decodes.
+pub fn try_detect(image: &[u8], width: usize, height: usize) -> Result<Vec<QRCode>, Error> {
+    let expected_len = width.saturating_mul(height).saturating_mul(3);
+    if width == 0 || height == 0 || image.len() != expected_len {
+        return Err(Error::InvalidImage {
+            width,
+            height,
+            expected_len,
+            actual_len: image.len(),
+        });
+    }
+    let codes = detect_or_shed(image, width, height).map_err(Error::Shed)?;
+    if codes.is_empty() {
+        Err(Error::NotFound)
+    } else {
+        Ok(codes)
+    }
+}
+
+/// The binarization, finder and decode stages of [`detect_with_telemetry`],
+/// over a frame already converted to grayscale and accounted for in `tel`.
+fn run_telemetry_stages(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    mut tel: DetectionTelemetry,
+) -> (Vec<QRCode>, DetectionTelemetry) {
+    let results = pipeline::with_decode_memo(|| {
+        // Step 2+: strict path first, then bounded fallback binarization ensemble on miss.
+        let policies = phase9_binarization_sequence(width, height);
+        let mut remaining_attempts = image_decode_attempt_budget();
+        let reserve =
+            decoder::config::policy_attempt_reserve().min(remaining_attempts / policies.len());
+        let mut results = Vec::new();
+        let mut prev_policy = policies[0];
+        let mut best_finder_patterns: Vec<FinderPattern> = Vec::new();
+        let frame = Frame::new(gray, width, height);
+        tel.binarize_ok = true;
+        for (i, &policy) in policies.iter().enumerate() {
+            if i > 0 {
+                record_binarization_transition(&mut tel, prev_policy, policy);
+                prev_policy = policy;
+            }
+            if remaining_attempts == 0 {
+                tel.budget_skips += 1;
                 break;
             }
-        } else if finder_patterns.len() == 2 {
-            tel.two_finder_attempts += 1;
-            let decoded = decode_two_finder_fallback_limited(
-                &binary,
-                &gray,
-                width,
-                height,
-                &finder_patterns,
-                Some(&mut remaining_attempts),
-                Some(&mut tel),
-            );
-            if !decoded.is_empty() {
-                tel.two_finder_successes += 1;
-                if i > 0 {
-                    tel.bin_fallback_successes += 1;
+            let allowance =
+                policy_attempt_allowance(remaining_attempts, reserve, policies.len() - i - 1);
+            tel.bin_policy_allowance[policy.index()] = allowance;
+
+            let stage_started = Instant::now();
+            let planes = policy_planes(&frame, policy);
+            tel.time_binarize_ms += elapsed_ms(stage_started);
+            let stage_started = Instant::now();
+            let finder_patterns = detect_finder_patterns(planes.binary, width, height);
+            tel.time_finder_scan_ms += elapsed_ms(stage_started);
+            if finder_patterns.len() > best_finder_patterns.len() {
+                best_finder_patterns = finder_patterns.clone();
+            }
+            tel.finder_patterns_found = tel.finder_patterns_found.max(finder_patterns.len());
+
+            if finder_patterns.len() >= 3 {
+                let (decoded, decode_tel) =
+                    config::with_binarization(policy.binarization(), || {
+                        pipeline::decode_groups_with_telemetry_limited(
+                            planes,
+                            &finder_patterns,
+                            allowance,
+                        )
+                    });
+                remaining_attempts = remaining_attempts.saturating_sub(decode_tel.decode_attempts);
+                tel.bin_policy_attempts[policy.index()] += decode_tel.decode_attempts;
+                tel.merge_high_water_from(&decode_tel);
+                if !decoded.is_empty() {
+                    if i > 0 {
+                        tel.bin_fallback_successes += 1;
+                    }
+                    results = decoded;
+                    break;
+                }
+            } else if finder_patterns.len() == 2
+                && features::FULL_RECOVERY
+                && !config::ablated(Ablation::TwoFinder)
+            {
+                tel.two_finder_attempts += 1;
+                let mut left = allowance;
+                let decoded = config::with_binarization(policy.binarization(), || {
+                    decode_two_finder_fallback_limited(
+                        planes,
+                        &finder_patterns,
+                        Some(&mut left),
+                        Some(&mut tel),
+                    )
+                });
+                remaining_attempts -= allowance - left;
+                tel.bin_policy_attempts[policy.index()] += allowance - left;
+                if !decoded.is_empty() {
+                    tel.two_finder_successes += 1;
+                    if i > 0 {
+                        tel.bin_fallback_successes += 1;
+                    }
+                    results = decoded;
+                    break;
                 }
-                results = decoded;
-                break;
             }
         }
-    }
 
-    if results.is_empty() {
-        let weak_contrast = grayscale_contrast_span(&gray) <= 90;
-        if remaining_attempts == 0 || !weak_contrast {
-            tel.roi_norm_skipped += 1;
-        } else if let Some(roi) = finder_roi_bounds(&best_finder_patterns, width, height) {
-            tel.roi_norm_attempts += 1;
-            let normalized_gray = normalize_roi_local_contrast(&gray, width, height, roi);
-            let norm_binary = adaptive_binarize(&normalized_gray, width, height, 31);
-            let norm_patterns = if width >= 1600 && height >= 1600 {
-                FinderDetector::detect_with_pyramid(&norm_binary)
-            } else {
-                FinderDetector::detect(&norm_binary)
-            };
-            tel.finder_patterns_found = tel.finder_patterns_found.max(norm_patterns.len());
-            if norm_patterns.len() >= 3 {
-                let (decoded, decode_tel) = pipeline::decode_groups_with_telemetry_limited(
-                    &norm_binary,
-                    &normalized_gray,
-                    width,
-                    height,
-                    &norm_patterns,
-                    remaining_attempts,
-                );
-                tel.merge_high_water_from(&decode_tel);
+        if results.is_empty() && !config::ablated(Ablation::RoiNormalization) {
+            // 5th to 95th percentile, so a hot pixel or a specular spot doesn't
+            // make a washed-out frame look contrasty and skip normalization.
+            let weak_contrast = percentile_contrast_span(gray, width, height, 1, 5, 95)
+                <= decoder::config::roi_norm_max_contrast_span();
+            if remaining_attempts == 0 || !weak_contrast || !features::FULL_RECOVERY {
+                tel.roi_norm_skipped += 1;
+            } else if let Some(roi) = finder_roi_bounds(&best_finder_patterns, width, height) {
+                tel.roi_norm_attempts += 1;
+                let stage_started = Instant::now();
+                let decoded = with_roi_binarized(gray, width, roi, 31, true, |roi_planes| {
+                    tel.time_binarize_ms += elapsed_ms(stage_started);
+                    let stage_started = Instant::now();
+                    let norm_patterns = detect_finder_patterns(
+                        roi_planes.binary,
+                        roi_planes.width,
+                        roi_planes.height,
+                    );
+                    tel.time_finder_scan_ms += elapsed_ms(stage_started);
+                    tel.finder_patterns_found = tel.finder_patterns_found.max(norm_patterns.len());
+                    if norm_patterns.len() < 3 {
+                        tel.roi_norm_skipped += 1;
+                        return Vec::new();
+                    }
+                    let (decoded, decode_tel) =
+                        config::with_binarization(Binarization::RoiNormalized, || {
+                            pipeline::decode_groups_with_telemetry_limited(
+                                roi_planes,
+                                &norm_patterns,
+                                remaining_attempts,
+                            )
+                        });
+                    let mut decode_tel = decode_tel;
+                    for finders in &mut decode_tel.decoded_finders {
+                        for f in finders {
+                            f.center.x += roi.0 as f32;
+                            f.center.y += roi.1 as f32;
+                        }
+                    }
+                    tel.merge_high_water_from(&decode_tel);
+                    decoded
+                });
                 if !decoded.is_empty() {
                     tel.roi_norm_successes += 1;
                     results = decoded;
+                    offset_codes(&mut results, roi);
                 }
             } else {
                 tel.roi_norm_skipped += 1;
             }
-        } else {
-            tel.roi_norm_skipped += 1;
         }
-    }
+        if results.is_empty() {
+            results = narrow_quiet_zone_pass(gray, width, height);
+        }
+        results
+    });
+
+    (results, tel)
+}
+
+/// Detect QR codes in an RGB image, returning telemetry about which pipeline
+/// stages succeeded or failed. This is intended for benchmark diagnostics.
+///
+/// The telemetry records the high-water-mark across all binarization attempts
+/// so callers can determine *where* the pipeline stalls for a given image.
+/// Counters are collected per call, so concurrent calls on different threads
+/// (or a call nested inside another) report only their own work.
+pub fn detect_with_telemetry(
+    image: &[u8],
+    width: usize,
+    height: usize,
+) -> (Vec<QRCode>, DetectionTelemetry) {
+    detect_with_telemetry_preprocessed(image, width, height, &[])
+}
+
+fn detect_with_telemetry_preprocessed(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    preprocessors: &[Box<dyn Preprocessor>],
+) -> (Vec<QRCode>, DetectionTelemetry) {
+    with_call_telemetry(|| {
+        let stage_started = Instant::now();
+        let gray = rgb_to_grayscale(image, width, height);
+        let gray = preprocess::apply_chain(preprocessors, gray, width, height);
+        let tel = DetectionTelemetry {
+            preprocessors: preprocessors.iter().map(|p| p.name().to_string()).collect(),
+            time_grayscale_ms: elapsed_ms(stage_started),
+            ..Default::default()
+        };
+        run_telemetry_stages(&gray, width, height, tel)
+    })
+}
+
+/// [`detect_with_telemetry`] converting to grayscale into `pool`'s buffer
+///
+/// The stages, and so the codes and telemetry, are those of
+/// [`detect_with_telemetry`] rather than [`detect_with_pool`]'s own
+/// strategy, which the two may find different codes with; only the
+/// grayscale frame is pooled.
+///
+/// # Example
+/// ```
+/// use rust_qr::utils::memory_pool::BufferPool;
+///
+/// let mut pool = BufferPool::new();
+/// let image = vec![0u8; 640 * 480 * 3]; // RGB image buffer
+/// let (codes, tel) = rust_qr::detect_with_pool_and_telemetry(&image, 640, 480, &mut pool);
+/// assert_eq!(codes.len(), tel.qr_codes_found);
+/// ```
+pub fn detect_with_pool_and_telemetry(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    pool: &mut BufferPool,
+) -> (Vec<QRCode>, DetectionTelemetry) {
+    with_call_telemetry(|| {
+        let stage_started = Instant::now();
+        let gray = pool.get_grayscale_buffer(width * height);
+        rgb_to_grayscale_with_buffer(image, width, height, gray);
+        let tel = DetectionTelemetry {
+            time_grayscale_ms: elapsed_ms(stage_started),
+            ..Default::default()
+        };
+        run_telemetry_stages(gray, width, height, tel)
+    })
+}
 
+/// Run `stages` on fresh decode counters and fold the counters, result
+/// totals and wall time into the telemetry they return.
+fn with_call_telemetry(
+    stages: impl FnOnce() -> (Vec<QRCode>, DetectionTelemetry),
+) -> (Vec<QRCode>, DetectionTelemetry) {
+    let started = Instant::now();
+    let (((results, mut tel), failed_grids), counters) =
+        with_decode_counters(|| with_failed_grids(stages));
+    tel.failed_grids = failed_grids;
     tel.qr_codes_found = results.len();
-    let counters = take_decode_counters();
+    for qr in &results {
+        if let Some(binarization) = qr.binarization {
+            tel.binarization_decodes[binarization.index()] += 1;
+        }
+    }
     tel.deskew_attempts = counters.deskew_attempts;
     tel.deskew_successes = counters.deskew_successes;
     tel.high_version_precision_attempts = counters.high_version_precision_attempts;
@@ -770,26 +1602,127 @@ pub fn detect_with_telemetry(
     tel.scale_retry_attempts = counters.scale_retry_attempts;
     tel.scale_retry_successes = counters.scale_retry_successes;
     tel.scale_retry_skipped_by_budget = counters.scale_retry_skipped_by_budget;
+    tel.threshold_sweep_attempts = counters.threshold_sweep_attempts;
+    tel.threshold_sweep_successes = counters.threshold_sweep_successes;
     tel.hv_subpixel_attempts = counters.hv_subpixel_attempts;
     tel.hv_refine_attempts = counters.hv_refine_attempts;
     tel.hv_refine_successes = counters.hv_refine_successes;
+    tel.grid_refine_attempts = counters.grid_refine_attempts;
+    tel.grid_refine_successes = counters.grid_refine_successes;
+    tel.grid_refine_iterations = counters.grid_refine_iterations;
+    tel.grid_refine_residual_drop_px = counters.grid_refine_residual_drop_px;
+    tel.grid_sample_cache_hits = counters.grid_sample_cache_hits;
     tel.rs_erasure_attempts = counters.rs_erasure_attempts;
     tel.rs_erasure_successes = counters.rs_erasure_successes;
     tel.rs_erasure_count_hist = counters.rs_erasure_count_hist;
+    tel.rs_blocks_failed = counters.rs_blocks_failed;
+    tel.rs_block_load_hist = counters.rs_block_load_hist;
+    tel.high_density_version_pins = counters.high_density_version_pins;
+    tel.high_density_residual_rejects = counters.high_density_residual_rejects;
+    tel.synthetic_snap_decodes = counters.synthetic_snap_decodes;
+    tel.header_prefilter_skips = counters.header_prefilter_skips;
+    tel.rotated_format_rescues = counters.rotated_format_rescues;
+    tel.wrong_format_mask_rescues = counters.wrong_format_mask_rescues;
     tel.phase11_time_budget_skips = counters.phase11_time_budget_skips;
+    tel.column_scans_skipped = counters.column_scans_skipped;
+    tel.quirk_rescues = counters.quirk_rescues;
+    tel.decode_memo_hits = counters.decode_memo_hits;
+    tel.stream_decode_attempts = counters.stream_decode_attempts;
+    tel.pathology_guard_tripped = counters.pathology_guard_trips > 0;
+    tel.time_recovery_ms = counters.recovery_ms;
+    tel.time_total_ms = elapsed_ms(started);
     (results, tel)
 }
 
+/// Check whether an RGB image contains a QR code whose payload bytes equal
+/// `expected`.
+///
+/// Cheaper than [`detect`] followed by a comparison: candidate decoding stops
+/// at the first code that matches, and the scoring and acceptance checks used
+/// to rank results are skipped. Codes with other payloads count as misses.
+/// Returns `false` when `image` is not `width * height * 3` bytes.
+pub fn verify_payload(image: &[u8], width: usize, height: usize, expected: &[u8]) -> bool {
+    if width == 0 || height == 0 || image.len() != width.saturating_mul(height).saturating_mul(3) {
+        return false;
+    }
+    let _frame = scoped_decode_counters();
+    let gray = rgb_to_grayscale(image, width, height);
+    let matches = |codes: Vec<QRCode>| codes.iter().any(|qr| qr.data == expected);
+    pipeline::with_expected_payload(expected, || {
+        matches(run_fast_path(&gray, width, height))
+            || matches(run_detection_with_phase4_fallbacks(&gray, width, height))
+    })
+}
+
+/// Locate QR codes in an RGB image and read only their version, EC level
+/// and mask
+///
+/// For taking inventory of printed sheets: each symbol is sampled and its
+/// format information read, but no Reed-Solomon correction or payload
+/// parsing runs, which makes this several times faster than [`detect`].
+/// Symbols whose format information is unreadable are left out, and a
+/// symbol is listed once however many binarizations find it.
+pub fn scan_metadata_only(image: &[u8], width: usize, height: usize) -> Vec<SymbolInfo> {
+    if width == 0 || height == 0 || image.len() != width.saturating_mul(height).saturating_mul(3) {
+        return Vec::new();
+    }
+    let _frame = scoped_decode_counters();
+    let gray = rgb_to_grayscale(image, width, height);
+    let mut symbols: Vec<SymbolInfo> = Vec::new();
+    let frame = Frame::new(&gray, width, height);
+    for policy in phase9_binarization_sequence(width, height) {
+        let planes = policy_planes(&frame, policy);
+        let finder_patterns = detect_finder_patterns(planes.binary, width, height);
+        if finder_patterns.len() < 3 {
+            continue;
+        }
+        for info in pipeline::symbol_infos(planes, &finder_patterns) {
+            if !symbols
+                .iter()
+                .any(|s| same_position(&s.position, &info.position))
+            {
+                symbols.push(info);
+            }
+        }
+    }
+    symbols
+}
+
+/// Whether two scans of a symbol overlap: their centres lie within half
+/// the shorter side of either.
+pub(crate) fn same_position(a: &[Point; 4], b: &[Point; 4]) -> bool {
+    let centre = |p: &[Point; 4]| {
+        Point::new(
+            p.iter().map(|c| c.x).sum::<f32>() / 4.0,
+            p.iter().map(|c| c.y).sum::<f32>() / 4.0,
+        )
+    };
+    let side = |p: &[Point; 4]| p[0].distance(&p[1]).min(p[0].distance(&p[3]));
+    let reach = side(a).min(side(b)) * 0.5;
+    centre(a).distance(&centre(b)) < reach
+}
+
 /// Detect QR codes from a pre-computed grayscale image
 ///
 /// # Arguments
-/// * `image` - Grayscale bytes (1 byte per pixel)
+/// * `image` - Grayscale bytes (1 byte per pixel); bytes past
+///   `width * height` are ignored
 /// * `width` - Image width in pixels
 /// * `height` - Image height in pixels
 ///
 /// # Returns
 /// Vector of detected QR codes
 pub fn detect_from_grayscale(image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    let _frame = scoped_decode_counters();
+    let image = &image[..width * height];
+    if !load_shed::admit_frame(ShedStage::Frame, width, height) {
+        return Vec::new();
+    }
+    if let Some(cap) = config::with_active(|c| c.max_memory_bytes)
+        && let Some(codes) = memory::detect_gray_within(image, width, height, cap)
+    {
+        return codes;
+    }
     let fast = run_fast_path(image, width, height);
     if !fast.is_empty() {
         return fast;
@@ -798,10 +1731,121 @@ pub fn detect_from_grayscale(image: &[u8], width: usize, height: usize) -> Vec<Q
     run_detection_with_phase4_fallbacks(image, width, height)
 }
 
+/// Detect QR codes in a raw buffer of any [`PixelFormat`] and row stride
+pub fn detect_image_ref(image: &ImageRef<'_>) -> Vec<QRCode> {
+    // Only a converted empty `DynamicImage` or array can get here with zero size.
+    if image.width() == 0 || image.height() == 0 {
+        return Vec::new();
+    }
+    let gray = image.to_grayscale();
+    detect_from_grayscale(&gray, image.width(), image.height())
+}
+
+/// Detect QR codes in bracketed exposures of one scene
+///
+/// The frames are merged with [`utils::grayscale::fuse_exposures`] before
+/// detection, so a code washed out by glare in one exposure is read from the
+/// others. Returns [`Error::InvalidImage`] when the frames differ in size.
+pub fn detect_multi_exposure(frames: &[GrayFrame<'_>]) -> Result<Vec<QRCode>, Error> {
+    let Some(first) = frames.first() else {
+        return Ok(Vec::new());
+    };
+    let (width, height) = (first.width(), first.height());
+    if let Some(other) = frames
+        .iter()
+        .find(|f| (f.width(), f.height()) != (width, height))
+    {
+        return Err(Error::InvalidImage {
+            width,
+            height,
+            expected_len: width * height,
+            actual_len: other.data().len(),
+        });
+    }
+    let data: Vec<&[u8]> = frames.iter().map(|f| f.data()).collect();
+    let fused = utils::grayscale::fuse_exposures(&data, width, height);
+    Ok(detect_from_grayscale(&fused, width, height))
+}
+
+/// Detect QR codes in an [`image::DynamicImage`] (`image-interop` feature)
+///
+/// 8-bit luma, RGB and RGBA images are read in place; other layouts are
+/// converted to 8-bit luma first.
+#[cfg(feature = "image-interop")]
+pub fn detect_image(image: &image::DynamicImage) -> Vec<QRCode> {
+    detect_image_ref(&ImageRef::from(image))
+}
+
+/// Detect QR codes in a grayscale `ndarray` view, which may be strided
+/// (`ndarray-interop` feature)
+#[cfg(feature = "ndarray-interop")]
+pub fn detect_array(view: ndarray::ArrayView2<'_, u8>) -> Vec<QRCode> {
+    detect_image_ref(&ImageRef::from(view))
+}
+
+/// Detect QR codes in a `(height, width, channels)` `ndarray` view with 1, 3
+/// (RGB) or 4 (RGBA) channels (`ndarray-interop` feature)
+///
+/// Returns [`Error::InvalidImage`] for any other channel count.
+#[cfg(feature = "ndarray-interop")]
+pub fn detect_array3(view: ndarray::ArrayView3<'_, u8>) -> Result<Vec<QRCode>, Error> {
+    Ok(detect_image_ref(&ImageRef::try_from(view)?))
+}
+
+/// Run only the single-pass Otsu fast path over pooled buffers
+///
+/// This is the first stage of [`detect_with_pool`] on its own: grayscale
+/// conversion, one global threshold, a finder scan and decoding of whatever
+/// groups it finds, with none of the slower recovery strategies.
+///
+/// # Allocation contract
+/// Once `pool` has processed a frame of the same size, a call that finds fewer
+/// than two finder patterns performs no heap allocation, which keeps idle
+/// frames in a video loop free of allocator traffic. Decoding candidate groups
+/// and building the returned codes do allocate. Frames of 1600px+ on both
+/// sides (pyramid scan) and [`DetectorConfig::tolerate_missing_quiet_zone`]
+/// are outside the contract.
+///
+/// # Example
+/// ```
+/// use rust_qr::utils::memory_pool::BufferPool;
+///
+/// let mut pool = BufferPool::new();
+/// let image = vec![0u8; 640 * 480 * 3]; // RGB image buffer
+/// let codes = rust_qr::detect_fast_with_pool(&image, 640, 480, &mut pool);
+/// assert!(codes.is_empty());
+/// ```
+pub fn detect_fast_with_pool(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    pool: &mut BufferPool,
+) -> Vec<QRCode> {
+    let _frame = scoped_decode_counters();
+    if !load_shed::admit_frame(ShedStage::Frame, width, height) {
+        return Vec::new();
+    }
+    fast_path_with_pool(image, width, height, pool)
+}
+
+fn fast_path_with_pool(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    pool: &mut BufferPool,
+) -> Vec<QRCode> {
+    let (gray_buffer, bin_otsu, finder) = pool.fast_path_buffers(width, height);
+    rgb_to_grayscale_with_buffer(image, width, height, gray_buffer);
+    run_fast_path_into(gray_buffer, width, height, bin_otsu, finder)
+}
+
 /// Detect QR codes using a reusable buffer pool (faster for batch processing)
 ///
 /// This version uses pre-allocated buffers to avoid repeated memory allocations.
-/// Use this when processing multiple images of similar size.
+/// Use this when processing multiple images of similar size. The first stage
+/// is [`detect_fast_with_pool`] and carries its allocation contract; frames it
+/// misses go on to the slower strategies, which allocate. Results do not
+/// depend on what the pool processed before.
 ///
 /// # Example
 /// ```
@@ -817,91 +1861,81 @@ pub fn detect_with_pool(
     height: usize,
     pool: &mut BufferPool,
 ) -> Vec<QRCode> {
-    // Get all buffers at once via split borrowing
-    let (gray_buffer, bin_adaptive, bin_otsu, integral) = pool.get_all_buffers(width, height);
-
-    // Step 1: Convert to grayscale using pre-allocated buffer
-    rgb_to_grayscale_with_buffer(image, width, height, gray_buffer);
+    let _frame = scoped_decode_counters();
+    if !load_shed::admit_frame(ShedStage::Frame, width, height) {
+        return Vec::new();
+    }
+    if let Some(cap) = config::with_active(|c| c.max_memory_bytes)
+        && let Some(codes) = memory::detect_within(image, width, height, cap)
+    {
+        return codes;
+    }
 
     // Fast path: one Otsu pass and decode.
-    let fast = run_fast_path(gray_buffer, width, height);
+    let fast = fast_path_with_pool(image, width, height, pool);
     if !fast.is_empty() {
         return fast;
     }
 
+    // Get all buffers at once via split borrowing; the grayscale frame
+    // converted by the fast path is still in place.
+    let (gray_buffer, bin_adaptive, bin_otsu, integral) = pool.get_all_buffers(width, height);
+
     // Slow path: additional strategies.
     // Step 2: Binarize into pooled BitMatrix buffers
     adaptive_binarize_into(gray_buffer, width, height, 31, bin_adaptive, integral);
     otsu_binarize_into(gray_buffer, width, height, bin_otsu);
 
-    // Step 3: Detect finder patterns
-    let mut finder_patterns = if width >= 800 || height >= 800 {
-        detect_finder_patterns(bin_adaptive, width, height)
+    // The pooled matrices and the integral the adaptive pass summed are
+    // borrowed; any fallback binarization derives from that integral.
+    let frame = Frame::new(gray_buffer, width, height)
+        .with_binary(Binarization::Adaptive31, bin_adaptive)
+        .with_binary(Binarization::Otsu, bin_otsu)
+        .with_integral(integral);
+    let (primary, secondary) = if width >= 800 || height >= 800 {
+        (BinarizationPolicy::Adaptive31, BinarizationPolicy::Otsu)
     } else {
-        detect_finder_patterns(bin_otsu, width, height)
+        (BinarizationPolicy::Otsu, BinarizationPolicy::Adaptive31)
     };
 
-    // Select which binary image to use for decoding (no clone needed — just a reference)
-    let mut binary: &BitMatrix = if width >= 800 || height >= 800 {
-        bin_adaptive
-    } else {
-        bin_otsu
-    };
+    // Step 3: Detect finder patterns
+    let mut planes = policy_planes(&frame, primary);
+    let mut kind = primary.binarization();
+    let mut finder_patterns = detect_finder_patterns(planes.binary, width, height);
 
     if finder_patterns.len() < 3 {
-        let fallback_patterns = if width >= 800 || height >= 800 {
-            detect_finder_patterns(bin_otsu, width, height)
-        } else {
-            detect_finder_patterns(bin_adaptive, width, height)
-        };
+        let fallback = policy_planes(&frame, secondary);
+        let fallback_patterns = detect_finder_patterns(fallback.binary, width, height);
         if fallback_patterns.len() >= 2 {
             finder_patterns = fallback_patterns;
-            binary = if width >= 800 || height >= 800 {
-                bin_otsu
-            } else {
-                bin_adaptive
-            };
+            (planes, kind) = (fallback, secondary.binarization());
         }
     }
 
     // Step 4: Group and decode
-    let mut results =
-        decode_groups_with_module_aware_retry(binary, gray_buffer, width, height, &finder_patterns);
+    let mut results = config::with_binarization(kind, || {
+        decode_groups_with_module_aware_retry(planes, &finder_patterns)
+    });
 
     // Sauvola fallback: adapts to local contrast (handles shadows/glare)
-    if results.is_empty() {
-        let sauvola = sauvola_binarize(gray_buffer, width, height, 31, 0.2);
-        let sauvola_patterns = detect_finder_patterns(&sauvola, width, height);
+    if results.is_empty()
+        && let Some(sauvola) = frame.planes(Binarization::Sauvola)
+    {
+        let sauvola_patterns = detect_finder_patterns(sauvola.binary, width, height);
         if sauvola_patterns.len() >= 2 {
-            results = decode_groups_with_module_aware_retry(
-                &sauvola,
-                gray_buffer,
-                width,
-                height,
-                &sauvola_patterns,
-            );
+            results = config::with_binarization(Binarization::Sauvola, || {
+                decode_groups_with_module_aware_retry(sauvola, &sauvola_patterns)
+            });
         }
     }
 
     if results.is_empty() {
-        let fallback_patterns = if width >= 800 || height >= 800 {
-            detect_finder_patterns(bin_otsu, width, height)
-        } else {
-            detect_finder_patterns(bin_adaptive, width, height)
-        };
+        let fallback = policy_planes(&frame, secondary);
+        let fallback_patterns = detect_finder_patterns(fallback.binary, width, height);
         if fallback_patterns.len() >= 2 {
-            let fallback_binary: &BitMatrix = if width >= 800 || height >= 800 {
-                bin_otsu
-            } else {
-                bin_adaptive
-            };
-            results = decode_groups_with_module_aware_retry(
-                fallback_binary,
-                gray_buffer,
-                width,
-                height,
-                &fallback_patterns,
-            );
+            results = config::with_binarization(secondary.binarization(), || {
+                decode_groups_with_module_aware_retry(fallback, &fallback_patterns)
+            });
         }
     }
 
@@ -909,21 +1943,54 @@ pub fn detect_with_pool(
 }
 
 /// Detector with configuration options and optional buffer pool
+///
+/// A detector is safe to reuse across frames. The pool only lends scratch
+/// buffers that every call overwrites, and per-image limits start afresh
+/// with each call, so detecting the same frame twice gives the same codes
+/// whatever came in between. Only the stream features carry state from one
+/// frame to the next, and only once enabled: the adaptive budget, fusion,
+/// the payload prior and the [`Detector::detect_new`] debouncer.
 pub struct Detector {
     /// Optional buffer pool for memory reuse
     pool: Option<BufferPool>,
+    /// Optional feedback controller for the decode attempt budget
+    budget: Option<budget::AdaptiveBudget>,
+    /// Options applied to every call
+    config: Option<DetectorConfig>,
+    /// Optional cross-frame module fusion for frames that fail on their own
+    fusion: Option<fusion::FrameFusion>,
+    /// Optional shared-prefix prior learned from earlier payloads
+    prior: Option<PayloadPrior>,
+    /// Optional once-per-appearance filter for `detect_new`
+    debouncer: Option<ResultDebouncer>,
+    /// Grayscale steps run before detection, in order
+    preprocessors: Vec<Box<dyn Preprocessor>>,
 }
 
 impl Detector {
     /// Create a new detector with default settings
     pub fn new() -> Self {
-        Self { pool: None }
+        Self {
+            pool: None,
+            budget: None,
+            config: None,
+            fusion: None,
+            prior: None,
+            debouncer: None,
+            preprocessors: Vec::new(),
+        }
     }
 
     /// Create a detector with buffer pooling enabled
     pub fn with_pool() -> Self {
         Self {
             pool: Some(BufferPool::new()),
+            budget: None,
+            config: None,
+            fusion: None,
+            prior: None,
+            debouncer: None,
+            preprocessors: Vec::new(),
         }
     }
 
@@ -931,17 +1998,162 @@ impl Detector {
     pub fn with_pool_capacity(capacity: usize) -> Self {
         Self {
             pool: Some(BufferPool::with_capacity(capacity)),
+            budget: None,
+            config: None,
+            fusion: None,
+            prior: None,
+            debouncer: None,
+            preprocessors: Vec::new(),
         }
     }
 
+    /// Adapt the decode attempt budget and lane split to recent frames,
+    /// staying within `bounds`
+    pub fn with_adaptive_budget(mut self, bounds: BudgetBounds) -> Self {
+        self.budget = Some(budget::AdaptiveBudget::new(bounds));
+        self
+    }
+
+    /// Apply `config` to every detection made through this detector
+    pub fn with_config(mut self, config: DetectorConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Treat successive `detect` calls as frames of one stream and, when a
+    /// frame fails, try decoding module samples averaged over recent frames
+    pub fn with_fusion(mut self, config: FusionConfig) -> Self {
+        self.fusion = Some(fusion::FrameFusion::new(config));
+        self
+    }
+
+    /// Use `prior` to rescue codes from a batch that fail error correction on
+    /// their own, and keep feeding it every code read without its help
+    pub fn with_payload_prior(mut self, prior: PayloadPrior) -> Self {
+        self.prior = Some(prior);
+        self
+    }
+
+    /// Report each code from [`Detector::detect_new`] once until it has
+    /// been out of view for `config.cooldown`
+    pub fn with_debouncer(mut self, config: DebounceConfig) -> Self {
+        self.debouncer = Some(ResultDebouncer::new(config));
+        self
+    }
+
+    /// Run `preprocessor` on each frame's grayscale before detection, after
+    /// any added earlier
+    ///
+    /// Preprocessed frames are detected from grayscale, so the buffer pool
+    /// goes unused while any preprocessor is set.
+    pub fn with_preprocessor(mut self, preprocessor: impl Preprocessor + 'static) -> Self {
+        self.preprocessors.push(Box::new(preprocessor));
+        self
+    }
+
+    /// The payload prior, if enabled
+    pub fn payload_prior(&self) -> Option<&PayloadPrior> {
+        self.prior.as_ref()
+    }
+
+    /// Fusion counters, if fusion is enabled
+    pub fn fusion_stats(&self) -> Option<FusionStats> {
+        self.fusion.as_ref().map(fusion::FrameFusion::stats)
+    }
+
+    /// Current adaptive budget state, if the controller is enabled
+    pub fn budget_operating_point(&self) -> Option<BudgetOperatingPoint> {
+        self.budget
+            .as_ref()
+            .map(budget::AdaptiveBudget::operating_point)
+    }
+
     /// Detect QR codes in an image
     pub fn detect(&mut self, image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
-        match &mut self.pool {
-            Some(pool) => detect_with_pool(image, width, height, pool),
-            None => detect(image, width, height),
+        let pool = &mut self.pool;
+        let config = &self.config;
+        let prior = &self.prior;
+        let preprocessors = &self.preprocessors;
+        let mut run = || {
+            let mut run_once = || match pool {
+                _ if !preprocessors.is_empty() => {
+                    let gray = rgb_to_grayscale(image, width, height);
+                    let gray = preprocess::apply_chain(preprocessors, gray, width, height);
+                    detect_from_grayscale(&gray, width, height)
+                }
+                Some(pool) => detect_with_pool(image, width, height, pool),
+                None => detect(image, width, height),
+            };
+            let run_configured = || match config {
+                Some(config) => config.apply(run_once),
+                None => run_once(),
+            };
+            match prior {
+                Some(prior) => prior.apply(run_configured),
+                None => run_configured(),
+            }
+        };
+        let (codes, shed) = load_shed::scoped(|| match &mut self.budget {
+            Some(controller) => {
+                let (codes, attempts_used) =
+                    pipeline::with_budget_override(controller.current_override(), run);
+                controller.observe(attempts_used, !codes.is_empty());
+                codes
+            }
+            None => run(),
+        });
+        // A shed frame was never looked at; don't feed it to fusion.
+        if shed.is_some() {
+            return codes;
+        }
+        if let Some(prior) = &mut self.prior {
+            for code in codes.iter().filter(|code| !code.assisted) {
+                prior.observe(&code.data);
+            }
+        }
+        let Some(fusion) = &mut self.fusion else {
+            return codes;
+        };
+        if !codes.is_empty() {
+            fusion.reset();
+            return codes;
+        }
+        let _frame = scoped_decode_counters();
+        let gray = rgb_to_grayscale(image, width, height);
+        let gray = preprocess::apply_chain(&self.preprocessors, gray, width, height);
+        let fused = match &self.config {
+            Some(config) => config.apply(|| fusion.observe_failed_frame(&gray, width, height)),
+            None => fusion.observe_failed_frame(&gray, width, height),
+        };
+        fused.into_iter().collect()
+    }
+
+    /// [`detect_with_telemetry`] under this detector's config and
+    /// preprocessors; the pool, budget controller, fusion and prior are
+    /// left out
+    pub fn detect_with_telemetry(
+        &self,
+        image: &[u8],
+        width: usize,
+        height: usize,
+    ) -> (Vec<QRCode>, DetectionTelemetry) {
+        let run = || detect_with_telemetry_preprocessed(image, width, height, &self.preprocessors);
+        match &self.config {
+            Some(config) => config.apply(run),
+            None => run(),
         }
     }
 
+    /// Detect QR codes in a frame of a stream, returning only those that
+    /// weren't already in view (see [`ResultDebouncer`]); debounces with the
+    /// default [`DebounceConfig`] unless [`Detector::with_debouncer`] set one
+    pub fn detect_new(&mut self, image: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+        let codes = self.detect(image, width, height);
+        self.debouncer
+            .get_or_insert_with(|| ResultDebouncer::new(DebounceConfig::default()))
+            .observe(codes)
+    }
+
     /// Detect a single QR code (faster if you know there's only one)
     pub fn detect_single(&mut self, image: &[u8], width: usize, height: usize) -> Option<QRCode> {
         let codes = self.detect(image, width, height);
@@ -987,6 +2199,191 @@ mod tests {
         assert!(codes.is_empty());
     }
 
+    #[test]
+    fn test_try_detect_errors() {
+        assert!(matches!(
+            try_detect(&[0u8; 10], 10, 10),
+            Err(Error::InvalidImage { actual_len: 10, .. })
+        ));
+        let image = vec![255u8; 300];
+        assert!(matches!(try_detect(&image, 10, 10), Err(Error::NotFound)));
+    }
+
+    #[test]
+    fn test_detector_adaptive_budget_observes_frames() {
+        let mut detector = Detector::new().with_adaptive_budget(BudgetBounds::default());
+        let image = vec![255u8; 32 * 32 * 3];
+        for _ in 0..3 {
+            assert!(detector.detect(&image, 32, 32).is_empty());
+        }
+        let op = detector
+            .budget_operating_point()
+            .expect("controller enabled");
+        assert_eq!(op.frames_observed, 3);
+        assert_eq!(op.success_rate, 0.0);
+        assert!(Detector::new().budget_operating_point().is_none());
+    }
+
+    #[test]
+    fn test_detector_preprocessors_run_in_detect_and_telemetry() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mut detector = Detector::new()
+            .with_preprocessor(preprocess::Clahe::default())
+            .with_preprocessor(move |gray: &[u8], _: usize, _: usize| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                gray.to_vec()
+            });
+        let image = vec![255u8; 32 * 32 * 3];
+        assert!(detector.detect(&image, 32, 32).is_empty());
+        let (codes, tel) = detector.detect_with_telemetry(&image, 32, 32);
+        assert!(codes.is_empty());
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(tel.preprocessors, ["clahe", "custom"]);
+        assert!(
+            detect_with_telemetry(&image, 32, 32)
+                .1
+                .preprocessors
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_policy_allowance_reserves_fallback_slices() {
+        // 72 attempts over three policies with 12 held back per later fallback.
+        let first = policy_attempt_allowance(72, 12, 2);
+        assert_eq!(first, 48);
+        // A greedy first policy still leaves the reserved slices...
+        let second = policy_attempt_allowance(72 - first, 12, 1);
+        assert_eq!(second, 12);
+        // ...and whatever the second leaves unspent rolls into the third.
+        assert_eq!(policy_attempt_allowance(72 - first - 5, 12, 0), 19);
+        assert_eq!(policy_attempt_allowance(3, 12, 1), 0);
+    }
+
+    #[test]
+    fn test_telemetry_reports_policy_budget_use() {
+        let image = vec![255u8; 64 * 64 * 3];
+        let (_, tel) = detect_with_telemetry(&image, 64, 64);
+        let budget = image_decode_attempt_budget();
+        let reserve = decoder::config::policy_attempt_reserve().min(budget / 4);
+        // Run as otsu, hybrid, adaptive(31), adaptive(21); reported with
+        // hybrid last.
+        assert_eq!(
+            tel.bin_policy_allowance,
+            [
+                budget - 3 * reserve,
+                budget - reserve,
+                budget,
+                budget - 2 * reserve
+            ]
+        );
+        assert_eq!(tel.bin_policy_attempts, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_roi_binarization_uses_roi_coordinates() {
+        let (width, height) = (40, 30);
+        let mut gray = vec![220u8; width * height];
+        for y in 10..16 {
+            for x in 20..26 {
+                gray[y * width + x] = 20;
+            }
+        }
+        let roi = (15, 5, 35, 25);
+        let (w, h, dark, light) = with_roi_binarized(&gray, width, roi, 151, false, |planes| {
+            let (b, g, w, h) = (planes.binary, planes.gray, planes.width, planes.height);
+            assert_eq!(g[7 * w + 7], 20);
+            (w, h, b.get(7, 7), b.get(1, 1))
+        });
+        assert_eq!((w, h), (20, 20));
+        assert!(dark);
+        assert!(!light);
+    }
+
+    #[test]
+    fn test_roi_failed_grids_use_frame_coordinates() {
+        use decoder::qr_decoder::tests::{golden_v1_scrambled_dark, render_upright_grid};
+        // Symbol (inside its 4-module quiet zone) at (64, 54)-(190, 180).
+        let (width, height) = (260, 240);
+        let gray = render_upright_grid(6.0, (40.0, 30.0), width, height, golden_v1_scrambled_dark);
+        let roi = (20, 20, 240, 220);
+        let config = DetectorConfig {
+            export_failed_grids: true,
+            ..DetectorConfig::default()
+        };
+        let (codes, grids) = config.apply(|| {
+            with_failed_grids(|| {
+                with_roi_binarized(&gray, width, roi, 31, false, |planes| {
+                    let patterns =
+                        detect_finder_patterns(planes.binary, planes.width, planes.height);
+                    pipeline::decode_groups(planes, &patterns)
+                })
+            })
+        });
+        assert!(codes.is_empty());
+        let expected = [(64.0, 54.0), (190.0, 54.0), (190.0, 180.0), (64.0, 180.0)];
+        let grid = grids
+            .iter()
+            .find(|grid| {
+                grid.corners
+                    .iter()
+                    .zip(expected)
+                    .all(|(c, (x, y))| (c.x - x).abs() < 3.0 && (c.y - y).abs() < 3.0)
+            })
+            .unwrap_or_else(|| panic!("{:?}", grids.iter().map(|g| g.corners).collect::<Vec<_>>()));
+        let m = grid.transform;
+        let origin = (m[2] / m[8], m[5] / m[8]);
+        assert!((origin.0 - grid.corners[0].x).abs() < 0.01, "{origin:?}");
+        assert!((origin.1 - grid.corners[0].y).abs() < 0.01, "{origin:?}");
+    }
+
+    #[test]
+    fn test_scan_helpers_flatten_results() {
+        let path = "benches/images/custom/decoding/v2Phone.png";
+        let luma = image::open(path).expect("Failed to load image").to_luma8();
+        let (w, h) = (luma.width() as usize, luma.height() as usize);
+        let scans = scan_gray(luma.as_raw(), w, h);
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].text, "TEL:555-123-5555");
+        assert_eq!(scans[0].payload, b"TEL:555-123-5555");
+        assert!(scans[0].confidence > 0.0);
+        let code = detect_from_grayscale(luma.as_raw(), w, h).remove(0);
+        assert_eq!(scans[0].corners, code.outline());
+        // A truncated buffer is no scan rather than a panic.
+        assert!(scan_gray(&luma.as_raw()[..w * h / 2], w, h).is_empty());
+        // Trailing bytes past the frame are ignored.
+        let mut padded = luma.as_raw().clone();
+        padded.extend(std::iter::repeat_n(255, w));
+        assert_eq!(scan_gray(&padded, w, h), scans);
+
+        #[cfg(feature = "image-interop")]
+        {
+            let bytes = std::fs::read(path).expect("Failed to read image");
+            assert_eq!(scan_bytes(&bytes).expect("decodes"), scans);
+            assert!(scan_bytes(b"not an image").is_err());
+        }
+        #[cfg(feature = "tools")]
+        assert_eq!(scan_file(path).expect("decodes"), scans);
+    }
+
+    #[test]
+    fn test_telemetry_stage_timings() {
+        let image = vec![255u8; 64 * 64 * 3];
+        let (_, tel) = detect_with_telemetry(&image, 64, 64);
+        let stages = tel.time_grayscale_ms
+            + tel.time_binarize_ms
+            + tel.time_finder_scan_ms
+            + tel.time_group_rank_ms
+            + tel.time_decode_ms;
+        assert!(tel.time_binarize_ms >= 0.0);
+        assert!(tel.time_recovery_ms <= tel.time_decode_ms);
+        assert!(stages <= tel.time_total_ms + 1e-6);
+    }
+
     #[test]
     fn test_real_qr() {
         // Load a real QR code image and see how many finder patterns we detect
@@ -1047,4 +2444,116 @@ mod tests {
             patterns.len()
         );
     }
+
+    #[test]
+    fn test_pooled_fast_path_matches_detect() {
+        use decoder::qr_decoder::tests::golden_v1_rgb;
+        let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+        let expected = crate::detect(&rgb, width, height);
+        assert_eq!(expected.len(), 1);
+
+        // The second call runs on buffers grown by the first.
+        let mut pool = crate::utils::memory_pool::BufferPool::with_capacity(16);
+        for _ in 0..2 {
+            let codes = crate::detect_fast_with_pool(&rgb, width, height, &mut pool);
+            assert_eq!(codes.len(), 1);
+            assert_eq!(codes[0].content, expected[0].content);
+            assert_eq!(codes[0].position, expected[0].position);
+        }
+    }
+
+    #[test]
+    fn test_repeated_detection_on_one_frame_is_idempotent() {
+        use decoder::qr_decoder::tests::{exhaust_rs_erasure_cap, golden_v1_rgb};
+        let frame = |module: f32, origin: f32, width: usize, height: usize| {
+            golden_v1_rgb(module, (origin, origin), width, height)
+        };
+        let (a, b) = (frame(6.0, 20.0, 214, 214), frame(5.0, 37.0, 260, 240));
+        // Counts left on the thread by earlier calls, the RS erasure cap among
+        // them, must not reach the next frame.
+        let codes = |codes: Vec<crate::QRCode>| format!("{codes:?}");
+
+        let mut pool = crate::utils::memory_pool::BufferPool::new();
+        let first = codes(crate::detect_with_pool(&a.0, a.1, a.2, &mut pool));
+        assert!(first.contains("4376471154038"));
+        crate::detect_with_pool(&b.0, b.1, b.2, &mut pool);
+        exhaust_rs_erasure_cap();
+        assert_eq!(
+            codes(crate::detect_with_pool(&a.0, a.1, a.2, &mut pool)),
+            first
+        );
+
+        let mut detector = crate::Detector::with_pool();
+        let first = codes(detector.detect(&a.0, a.1, a.2));
+        detector.detect(&b.0, b.1, b.2);
+        exhaust_rs_erasure_cap();
+        assert_eq!(codes(detector.detect(&a.0, a.1, a.2)), first);
+        assert_eq!(codes(crate::detect(&a.0, a.1, a.2)), first);
+    }
+
+    #[test]
+    fn test_detect_streaming_delivers_codes_and_stops_on_break() {
+        use decoder::qr_decoder::tests::{golden_v1_dark, golden_v1_gray, render_module_grid};
+        use std::ops::ControlFlow;
+
+        // Two copies side by side, the right one upside down.
+        let (width, height) = (380, 194);
+        let right = [
+            Point::new(370.0, 184.0),
+            Point::new(196.0, 184.0),
+            Point::new(370.0, 10.0),
+            Point::new(196.0, 10.0),
+        ];
+        let a = golden_v1_gray(6.0, (10.0, 10.0), width, height);
+        let (b, _) = render_module_grid(29, right, width, height, golden_v1_dark);
+        let rgb: Vec<u8> = a
+            .iter()
+            .zip(&b)
+            .flat_map(|(&x, &y)| [x.min(y); 3])
+            .collect();
+        let expected = crate::detect(&rgb, width, height);
+        assert_eq!(expected.len(), 2);
+
+        let mut streamed = Vec::new();
+        crate::detect_streaming(&rgb, width, height, |qr| {
+            streamed.push(qr);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(streamed.len(), expected.len());
+        for (got, want) in streamed.iter().zip(&expected) {
+            assert_eq!(got.content, want.content);
+            assert_eq!(got.position, want.position);
+        }
+
+        let mut first = Vec::new();
+        crate::detect_streaming(&rgb, width, height, |qr| {
+            first.push(qr);
+            ControlFlow::Break(())
+        });
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].position, expected[0].position);
+    }
+
+    #[test]
+    fn test_min_confidence_filters_and_skips_hopeless_candidates() {
+        use decoder::qr_decoder::tests::golden_v1_rgb;
+        let (rgb, width, height) = golden_v1_rgb(6.0, (20.0, 20.0), 214, 214);
+
+        let codes = crate::detect(&rgb, width, height);
+        assert_eq!(codes.len(), 1);
+        let confidence = codes[0].confidence;
+        let kept = crate::detect_with_min_confidence(&rgb, width, height, confidence);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].content, "4376471154038");
+
+        // Above anything the calibration can give: nothing is decoded at all.
+        let config = crate::DetectorConfig {
+            min_confidence: Some(0.999),
+            ..Default::default()
+        };
+        let (codes, tel) = config.apply(|| crate::detect_with_telemetry(&rgb, width, height));
+        assert!(codes.is_empty());
+        assert!(tel.min_confidence_skips >= 1);
+        assert_eq!(tel.decode_attempts, 0);
+    }
 }
@@ -0,0 +1,351 @@
+//! Cooperative load shedding for shared services
+//!
+//! One oversized or adversarial frame can keep a worker busy for seconds.
+//! `DetectorConfig::max_pixels` and `max_candidates` put hard ceilings on a
+//! call, and a [`LoadShedPolicy`] is consulted before each heavy stage so a
+//! service can refuse work by its own rules (e.g. a [`TokenBucket`] of
+//! pixels per second). A refused stage ends the call with a [`Shed`]
+//! instead of running: [`crate::detect_or_shed`] and [`crate::try_detect`]
+//! report it, while [`crate::detect`] returns no codes.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::config;
+
+/// Heavy stage a detection call is about to enter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShedStage {
+    /// Grayscale conversion and the first binarization of a new frame
+    Frame,
+    /// Grouping and decoding the finder candidates of one binarization
+    Decode,
+    /// The fallback ensemble run after the fast path finds nothing
+    Fallbacks,
+}
+
+/// Work a stage is about to take on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageCost {
+    /// Pixels in the frame
+    pub pixels: usize,
+    /// Finder candidates to group and decode; 0 before finder scanning
+    pub candidates: usize,
+}
+
+/// Why a call was shed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShedReason {
+    /// The frame is larger than `DetectorConfig::max_pixels`
+    MaxPixels { pixels: usize, limit: usize },
+    /// A binarization produced more finder candidates than
+    /// `DetectorConfig::max_candidates`
+    MaxCandidates { candidates: usize, limit: usize },
+    /// The configured [`LoadShedPolicy`] refused the stage
+    Policy,
+}
+
+/// A detection call that stopped before `stage` instead of running it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shed {
+    /// Stage that was refused
+    pub stage: ShedStage,
+    /// Limit or policy that refused it
+    pub reason: ShedReason,
+    /// What the stage would have cost
+    pub cost: StageCost,
+}
+
+impl fmt::Display for Shed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason {
+            ShedReason::MaxPixels { pixels, limit } => {
+                write!(f, "{pixels} pixels exceed the limit of {limit}")
+            }
+            ShedReason::MaxCandidates { candidates, limit } => {
+                write!(
+                    f,
+                    "{candidates} finder candidates exceed the limit of {limit}"
+                )
+            }
+            ShedReason::Policy => write!(f, "load-shed policy refused {:?}", self.stage),
+        }
+    }
+}
+
+/// Decides, before each heavy stage, whether a detection call may go on.
+///
+/// Policies are shared across worker threads through
+/// `DetectorConfig::load_shed_policy`, so they must be `Send + Sync`.
+pub trait LoadShedPolicy: Send + Sync + fmt::Debug {
+    /// Whether `stage` may run at `cost`; `false` sheds the call.
+    fn admit(&self, stage: ShedStage, cost: StageCost) -> bool;
+}
+
+/// Rate limit on pixels per second across every call sharing the bucket.
+///
+/// Each new frame draws its pixel count from a bucket of `capacity` tokens
+/// that refills at `refill_per_second`; a frame the bucket cannot cover is
+/// shed. Frames larger than `capacity` are always shed. Later stages of an
+/// admitted frame are not charged again.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    /// A full bucket of `capacity` pixels refilling at `refill_per_second`
+    pub fn new(capacity: u64, refill_per_second: u64) -> Self {
+        Self {
+            capacity: capacity as f64,
+            refill_per_second: refill_per_second as f64,
+            state: Mutex::new((capacity as f64, Instant::now())),
+        }
+    }
+
+    /// Pixels that could be drawn right now
+    pub fn available(&self) -> u64 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        state.0 as u64
+    }
+
+    fn refill(&self, state: &mut (f64, Instant)) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.1).as_secs_f64();
+        state.0 = (state.0 + elapsed * self.refill_per_second).min(self.capacity);
+        state.1 = now;
+    }
+}
+
+impl LoadShedPolicy for TokenBucket {
+    fn admit(&self, stage: ShedStage, cost: StageCost) -> bool {
+        if stage != ShedStage::Frame {
+            return true;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        let pixels = cost.pixels as f64;
+        if state.0 < pixels {
+            return false;
+        }
+        state.0 -= pixels;
+        true
+    }
+}
+
+thread_local! {
+    /// `None` outside [`scoped`]; inside, the shed that ended the call, if any.
+    static SHED: RefCell<Option<Option<Shed>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` as one detection call, returning the shed that cut it short.
+/// Scopes nest; the inner call's shed does not leak into the outer one.
+pub(crate) fn scoped<R>(f: impl FnOnce() -> R) -> (R, Option<Shed>) {
+    let prev = SHED.with(|s| s.replace(Some(None)));
+    let out = f();
+    let shed = SHED.with(|s| s.replace(prev)).flatten();
+    (out, shed)
+}
+
+/// Whether `stage` may run at `cost` under the active limits and policy.
+/// Inside [`scoped`] the first refusal is recorded and every later stage
+/// of the call is refused with it.
+pub(crate) fn admit(stage: ShedStage, cost: StageCost) -> bool {
+    if SHED.with(|s| matches!(*s.borrow(), Some(Some(_)))) {
+        return false;
+    }
+    let reason = config::with_active(|c| {
+        if let Some(limit) = c.max_pixels.filter(|&limit| cost.pixels > limit) {
+            return Some(ShedReason::MaxPixels {
+                pixels: cost.pixels,
+                limit,
+            });
+        }
+        if let Some(limit) = c.max_candidates.filter(|&limit| cost.candidates > limit) {
+            return Some(ShedReason::MaxCandidates {
+                candidates: cost.candidates,
+                limit,
+            });
+        }
+        match &c.load_shed_policy {
+            Some(policy) if !policy.admit(stage, cost) => Some(ShedReason::Policy),
+            _ => None,
+        }
+    });
+    let Some(reason) = reason else {
+        return true;
+    };
+    SHED.with(|s| {
+        if let Some(slot) = s.borrow_mut().as_mut() {
+            *slot = Some(Shed {
+                stage,
+                reason,
+                cost,
+            });
+        }
+    });
+    false
+}
+
+/// [`admit`] for a stage whose cost is the frame alone
+pub(crate) fn admit_frame(stage: ShedStage, width: usize, height: usize) -> bool {
+    admit(
+        stage,
+        StageCost {
+            pixels: width.saturating_mul(height),
+            candidates: 0,
+        },
+    )
+}
+
+/// [`admit`] for decoding `candidates` finder patterns found in a frame
+pub(crate) fn admit_candidates(width: usize, height: usize, candidates: usize) -> bool {
+    admit(
+        ShedStage::Decode,
+        StageCost {
+            pixels: width.saturating_mul(height),
+            candidates,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket_sheds_frames_it_cannot_cover() {
+        let bucket = TokenBucket::new(1000, 0);
+        let frame = StageCost {
+            pixels: 600,
+            candidates: 0,
+        };
+        assert!(bucket.admit(ShedStage::Frame, frame));
+        assert!(!bucket.admit(ShedStage::Frame, frame));
+        // Later stages of an admitted frame are free.
+        assert!(bucket.admit(ShedStage::Fallbacks, frame));
+        assert_eq!(bucket.available(), 400);
+    }
+
+    #[test]
+    fn first_refusal_ends_the_scoped_call() {
+        let config = crate::DetectorConfig {
+            max_candidates: Some(10),
+            ..Default::default()
+        };
+        let cost = |candidates| StageCost {
+            pixels: 100,
+            candidates,
+        };
+        let (admitted, shed) = config.apply(|| {
+            scoped(|| {
+                [
+                    admit(ShedStage::Decode, cost(10)),
+                    admit(ShedStage::Decode, cost(11)),
+                    admit(ShedStage::Fallbacks, cost(0)),
+                ]
+            })
+        });
+        assert_eq!(admitted, [true, false, false]);
+        let shed = shed.unwrap();
+        assert_eq!(shed.stage, ShedStage::Decode);
+        assert_eq!(
+            shed.reason,
+            ShedReason::MaxCandidates {
+                candidates: 11,
+                limit: 10
+            }
+        );
+        // Outside a scope nothing is remembered.
+        assert!(config.apply(|| admit(ShedStage::Fallbacks, cost(0))));
+    }
+
+    #[test]
+    fn limits_stop_the_call() {
+        use crate::Error;
+        use crate::decoder::qr_decoder::tests::golden_v1_rgb;
+        use std::sync::Arc;
+
+        /// Refuses one stage and logs every stage it was asked about.
+        #[derive(Debug, Default)]
+        struct Refuse(Option<ShedStage>, Mutex<Vec<ShedStage>>);
+        impl LoadShedPolicy for Refuse {
+            fn admit(&self, stage: ShedStage, _: StageCost) -> bool {
+                self.1.lock().unwrap().push(stage);
+                self.0 != Some(stage)
+            }
+        }
+
+        let (rgb, width, height) = golden_v1_rgb(6.0, (10.0, 10.0), 194, 194);
+        let blank = vec![255u8; rgb.len()];
+
+        let small = crate::DetectorConfig {
+            max_pixels: Some(width * height - 1),
+            ..Default::default()
+        };
+        let Err(Error::Shed(shed)) = small.apply(|| crate::try_detect(&rgb, width, height)) else {
+            panic!("expected the frame to be shed");
+        };
+        assert_eq!(shed.stage, ShedStage::Frame);
+        assert_eq!(
+            shed.reason,
+            ShedReason::MaxPixels {
+                pixels: width * height,
+                limit: width * height - 1
+            }
+        );
+        assert!(
+            small
+                .apply(|| crate::detect(&rgb, width, height))
+                .is_empty()
+        );
+
+        // Three finders fit a limit of three but not of two.
+        for (limit, decodes) in [(3, true), (2, false)] {
+            let config = crate::DetectorConfig {
+                max_candidates: Some(limit),
+                ..Default::default()
+            };
+            let result = config.apply(|| crate::detect_or_shed(&rgb, width, height));
+            match result {
+                Ok(codes) => assert!(decodes && codes[0].content == "4376471154038"),
+                Err(shed) => assert!(!decodes && shed.stage == ShedStage::Decode),
+            }
+        }
+
+        // A blank frame reaches the fallback ensemble, which the policy refuses.
+        let policy = Arc::new(Refuse(Some(ShedStage::Fallbacks), Mutex::default()));
+        let config = crate::DetectorConfig {
+            load_shed_policy: Some(policy.clone()),
+            ..Default::default()
+        };
+        let shed = config
+            .apply(|| crate::detect_or_shed(&blank, width, height))
+            .unwrap_err();
+        assert_eq!(
+            (shed.stage, shed.reason),
+            (ShedStage::Fallbacks, ShedReason::Policy)
+        );
+        assert_eq!(
+            *policy.1.lock().unwrap(),
+            [ShedStage::Frame, ShedStage::Decode, ShedStage::Fallbacks]
+        );
+        let admitted = Arc::new(Refuse::default());
+        let config = crate::DetectorConfig {
+            load_shed_policy: Some(admitted),
+            ..Default::default()
+        };
+        assert_eq!(
+            config
+                .apply(|| crate::try_detect(&rgb, width, height))
+                .unwrap()[0]
+                .content,
+            "4376471154038"
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Per-image memory cap (`DetectorConfig::max_memory_bytes`)
+//!
+//! The full pipeline holds a dozen full-frame binarizations and integral
+//! images at its peak. Under a cap, [`MemoryPlan::for_frame`] picks the
+//! cheapest degradation that fits instead of failing: drop the fallback
+//! ensemble and pyramid, then scan a half-size frame, then scan the frame in
+//! overlapping tiles, and as a last resort downscale further. Codes found
+//! under a degraded plan come back with [`QRCode::memory_degraded`] set.
+
+use crate::detector::finder::FinderDetector;
+use crate::frame::Planes;
+use crate::models::{Binarization, QRCode};
+use crate::utils::binarization::otsu_binarize;
+use crate::{config, load_shed, offset_codes, pipeline, same_position};
+
+/// Peak working set of the full pipeline per pixel: grayscale and contrast
+/// copies, the strategy binarizations and Sauvola's u32 + u64 integrals.
+const FULL_BYTES_PER_PIXEL: usize = 16;
+
+/// A single Otsu pass: the grayscale frame, its bit matrix and decode
+/// headroom, in quarter bytes per pixel (1.25 bytes).
+const FAST_QUARTER_BYTES_PER_PIXEL: usize = 5;
+
+/// Tiles smaller than this hold too little of a code to be worth scanning;
+/// below it the frame is downscaled harder instead.
+const MIN_TILE: usize = 256;
+
+/// How much of the pipeline a frame can afford under a memory cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MemoryPlan {
+    /// Everything fits; run the normal pipeline.
+    Full,
+    /// One Otsu pass at full resolution, without the pyramid or fallbacks.
+    FastOnly,
+    /// Otsu on a frame downscaled by this integer factor.
+    Downscaled(usize),
+    /// Otsu on square tiles of this side, overlapping by half.
+    Tiled(usize),
+}
+
+fn fast_bytes(pixels: usize) -> usize {
+    pixels.saturating_mul(FAST_QUARTER_BYTES_PER_PIXEL) / 4
+}
+
+impl MemoryPlan {
+    /// The least degraded plan whose working set fits in `cap` bytes. The
+    /// caller's RGB buffer is not counted.
+    pub(crate) fn for_frame(width: usize, height: usize, cap: usize) -> Self {
+        let pixels = width.saturating_mul(height);
+        if pixels.saturating_mul(FULL_BYTES_PER_PIXEL) <= cap {
+            return Self::Full;
+        }
+        if fast_bytes(pixels) <= cap {
+            return Self::FastOnly;
+        }
+        if fast_bytes(pixels / 4) <= cap {
+            return Self::Downscaled(2);
+        }
+        let tile = (cap.saturating_mul(4) / FAST_QUARTER_BYTES_PER_PIXEL).isqrt();
+        if tile >= MIN_TILE {
+            return Self::Tiled(tile);
+        }
+        let mut factor = 3;
+        while fast_bytes(pixels / (factor * factor)) > cap && factor < width.min(height) {
+            factor += 1;
+        }
+        Self::Downscaled(factor)
+    }
+}
+
+/// The caller's frame, read one luma value at a time
+#[derive(Clone, Copy)]
+enum Pixels<'a> {
+    Rgb(&'a [u8]),
+    Gray(&'a [u8]),
+}
+
+impl Pixels<'_> {
+    fn luma(self, i: usize) -> u32 {
+        match self {
+            Self::Rgb(rgb) => luma(rgb, i),
+            Self::Gray(gray) => gray[i] as u32,
+        }
+    }
+}
+
+/// Run [`crate::detect`] on an RGB frame within `cap` bytes, or `None` when
+/// the full pipeline fits and should run as usual.
+pub(crate) fn detect_within(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    cap: usize,
+) -> Option<Vec<QRCode>> {
+    detect_pixels_within(Pixels::Rgb(image), width, height, cap)
+}
+
+/// [`detect_within`] for a grayscale frame, as taken by
+/// [`crate::detect_from_grayscale`].
+pub(crate) fn detect_gray_within(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    cap: usize,
+) -> Option<Vec<QRCode>> {
+    detect_pixels_within(Pixels::Gray(gray), width, height, cap)
+}
+
+fn detect_pixels_within(
+    image: Pixels<'_>,
+    width: usize,
+    height: usize,
+    cap: usize,
+) -> Option<Vec<QRCode>> {
+    let mut codes = match MemoryPlan::for_frame(width, height, cap) {
+        MemoryPlan::Full => return None,
+        MemoryPlan::FastOnly => match image {
+            Pixels::Rgb(rgb) => {
+                let gray = crate::rgb_to_grayscale(rgb, width, height);
+                fast_path(&gray, width, height)
+            }
+            Pixels::Gray(gray) => fast_path(gray, width, height),
+        },
+        MemoryPlan::Downscaled(factor) => {
+            let (gray, w, h) = downscaled_gray(image, width, height, factor);
+            if w == 0 || h == 0 {
+                return Some(Vec::new());
+            }
+            let mut codes = config::with_downscale(factor as f32, || fast_path(&gray, w, h));
+            for code in &mut codes {
+                for p in &mut code.position {
+                    p.x *= factor as f32;
+                    p.y *= factor as f32;
+                }
+                code.physical_size_mm = config::physical_size_mm(code);
+            }
+            codes
+        }
+        MemoryPlan::Tiled(tile) => detect_tiled(image, width, height, tile),
+    };
+    for code in &mut codes {
+        code.memory_degraded = true;
+    }
+    Some(codes)
+}
+
+/// Otsu, a single-resolution finder scan and decode; unlike
+/// [`crate::run_fast_path`] it never builds a pyramid.
+fn fast_path(gray: &[u8], width: usize, height: usize) -> Vec<QRCode> {
+    let binary = otsu_binarize(gray, width, height);
+    let finder_config = crate::finder_config();
+    let mut patterns = FinderDetector::detect_with_config(&binary, &finder_config);
+    if config::with_active(|c| c.tolerate_missing_quiet_zone) {
+        patterns = FinderDetector::with_flush_candidates(&binary, patterns);
+    }
+    if config::with_active(|c| c.inverted_finders) {
+        patterns = FinderDetector::with_inverted_candidates(&binary, &finder_config, patterns);
+    }
+    if patterns.len() < 2 || !load_shed::admit_candidates(width, height, patterns.len()) {
+        return Vec::new();
+    }
+    config::with_binarization(Binarization::Otsu, || {
+        pipeline::decode_groups(Planes::new(&binary, gray), &patterns)
+    })
+}
+
+/// Luma averaged over `factor`x`factor` blocks, read straight from the
+/// caller's frame so a full-size grayscale copy is never held.
+fn downscaled_gray(
+    image: Pixels<'_>,
+    width: usize,
+    height: usize,
+    factor: usize,
+) -> (Vec<u8>, usize, usize) {
+    let (w, h) = (width / factor, height / factor);
+    let mut gray = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = 0u32;
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    sum += image.luma(sy * width + sx);
+                }
+            }
+            gray.push((sum / (factor * factor) as u32) as u8);
+        }
+    }
+    (gray, w, h)
+}
+
+/// Grayscale of one RGB pixel with the coefficients `rgb_to_grayscale` uses.
+fn luma(rgb: &[u8], i: usize) -> u32 {
+    let (r, g, b) = (
+        rgb[3 * i] as u32,
+        rgb[3 * i + 1] as u32,
+        rgb[3 * i + 2] as u32,
+    );
+    (76 * r + 150 * g + 29 * b) >> 8
+}
+
+/// Scan `tile`-sided windows stepping by half a tile, so every code up to
+/// half a tile across lies wholly inside one of them, each with the lens
+/// re-centred on it. Only one tile's
+/// grayscale and binarization are alive at a time. A code read from several
+/// tiles is kept once, by position, so distinct codes that share a payload
+/// are all reported.
+fn detect_tiled(image: Pixels<'_>, width: usize, height: usize, tile: usize) -> Vec<QRCode> {
+    let origins = |len: usize| {
+        let step = (tile / 2).max(1);
+        let last = len.saturating_sub(tile);
+        let mut starts: Vec<usize> = (0..=last).step_by(step).collect();
+        if starts.last() != Some(&last) {
+            starts.push(last);
+        }
+        starts
+    };
+    let mut results: Vec<QRCode> = Vec::new();
+    for y0 in origins(height) {
+        for x0 in origins(width) {
+            let roi = (x0, y0, (x0 + tile).min(width), (y0 + tile).min(height));
+            let (w, h) = (roi.2 - roi.0, roi.3 - roi.1);
+            let mut gray = Vec::with_capacity(w * h);
+            for y in roi.1..roi.3 {
+                gray.extend((roi.0..roi.2).map(|x| image.luma(y * width + x) as u8));
+            }
+            let mut codes =
+                config::with_crop_origin(x0 as f32, y0 as f32, || fast_path(&gray, w, h));
+            offset_codes(&mut codes, roi);
+            for code in codes {
+                if !results
+                    .iter()
+                    .any(|r| same_position(&r.position, &code.position))
+                {
+                    results.push(code);
+                }
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_degrades_as_the_cap_shrinks() {
+        let (w, h) = (4000, 3000);
+        let pixels = w * h;
+        assert_eq!(MemoryPlan::for_frame(w, h, 16 * pixels), MemoryPlan::Full);
+        assert_eq!(
+            MemoryPlan::for_frame(w, h, 2 * pixels),
+            MemoryPlan::FastOnly
+        );
+        assert_eq!(
+            MemoryPlan::for_frame(w, h, pixels / 2),
+            MemoryPlan::Downscaled(2)
+        );
+        assert_eq!(
+            MemoryPlan::for_frame(w, h, 1_250_000),
+            MemoryPlan::Tiled(1000)
+        );
+        // Too tight even for a minimum tile: shrink until a pass fits.
+        let MemoryPlan::Downscaled(factor) = MemoryPlan::for_frame(w, h, 50_000) else {
+            panic!("expected a downscaled plan");
+        };
+        assert!(fast_bytes(pixels / (factor * factor)) <= 50_000);
+        assert!(matches!(
+            MemoryPlan::for_frame(w, h, 0),
+            MemoryPlan::Downscaled(_)
+        ));
+    }
+
+    #[test]
+    fn degraded_plans_keep_the_lens_in_frame_coordinates() {
+        use crate::decoder::qr_decoder::tests::render_through_lens;
+        use crate::models::Point;
+        let lens = |c: f32| crate::LensModel {
+            fx: 260.0,
+            fy: 260.0,
+            cx: c,
+            cy: c,
+            k1: -0.3,
+            k2: 0.05,
+            p1: 0.0,
+            p2: 0.0,
+        };
+        // Grid corners before the lens bends them, `o` pixels from its centre.
+        let ideal = |o: f32| {
+            [
+                (-160.0, -170.0),
+                (40.0, -170.0),
+                (-160.0, 30.0),
+                (40.0, 30.0),
+            ]
+            .map(|(x, y)| Point::new(o + x, o + y))
+        };
+        let detect = |lens: crate::LensModel, gray: &[u8], side: usize, cap: usize| {
+            let plan = MemoryPlan::for_frame(side, side, cap);
+            let config = crate::DetectorConfig {
+                lens: Some(lens),
+                max_memory_bytes: Some(cap),
+                ..Default::default()
+            };
+            let codes = config.apply(|| crate::detect_from_grayscale(gray, side, side));
+            assert_eq!(codes.len(), 1, "{plan:?}");
+            assert_eq!(codes[0].content, "4376471154038");
+            // The symbol's top-left, four modules into the grid.
+            let [tl, ..] = ideal(lens.cx);
+            lens.distort(Point::new(
+                tl.x + 4.0 * 200.0 / 29.0,
+                tl.y + 4.0 * 200.0 / 29.0,
+            ))
+            .distance(&codes[0].position[0])
+        };
+
+        let side = 400;
+        let small = lens(200.0);
+        let gray = render_through_lens(&small, ideal(200.0), side, side, (0, 0, side, side));
+        assert_eq!(
+            MemoryPlan::for_frame(side, side, 60_000),
+            MemoryPlan::Downscaled(2)
+        );
+        let err = detect(small, &gray, side, 60_000);
+        assert!(err < 4.0, "{err}");
+
+        // The lens centre sits in the middle of a tile that does not start
+        // at the frame origin.
+        let side = 1200;
+        let large = lens(800.0);
+        let gray = render_through_lens(&large, ideal(800.0), side, side, (600, 600, 1000, 1000));
+        assert_eq!(
+            MemoryPlan::for_frame(side, side, 200_000),
+            MemoryPlan::Tiled(400)
+        );
+        let err = detect(large, &gray, side, 200_000);
+        assert!(err < 3.0, "{err}");
+    }
+
+    #[test]
+    fn cap_degrades_instead_of_failing() {
+        use crate::decoder::qr_decoder::tests::{golden_v1_gray, golden_v1_rgb};
+        let detect_capped = |module: f32, origin: (f32, f32), size: usize, cap: usize| {
+            let (rgb, ..) = golden_v1_rgb(module, origin, size, size);
+            let config = crate::DetectorConfig {
+                max_memory_bytes: Some(cap),
+                ..Default::default()
+            };
+            crate::detect_with_config(&rgb, size, size, &config)
+        };
+
+        // Half-size frame: 10px modules still read at 5px.
+        let codes = detect_capped(10.0, (10.0, 10.0), 300, 50_000);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].content, "4376471154038");
+        assert!(codes[0].memory_degraded);
+        assert!(
+            (codes[0].position[0].x - 50.0).abs() < 4.0,
+            "{:?}",
+            codes[0]
+        );
+
+        // 256px tiles over a 600px frame, the code far from the origin.
+        let codes = detect_capped(4.0, (300.0, 250.0), 600, 82_000);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].content, "4376471154038");
+        assert!(codes[0].memory_degraded);
+        assert!(
+            (codes[0].position[0].x - 316.0).abs() < 3.0,
+            "{:?}",
+            codes[0]
+        );
+
+        // Two copies of one payload in different tiles are both reported.
+        let first = golden_v1_gray(4.0, (20.0, 20.0), 600, 600);
+        let second = golden_v1_gray(4.0, (400.0, 380.0), 600, 600);
+        let gray: Vec<u8> = first.iter().zip(&second).map(|(&a, &b)| a.min(b)).collect();
+        let config = crate::DetectorConfig {
+            max_memory_bytes: Some(82_000),
+            ..Default::default()
+        };
+        let codes = config.apply(|| crate::detect_from_grayscale(&gray, 600, 600));
+        assert_eq!(codes.len(), 2, "{codes:?}");
+        assert!(codes.iter().all(|c| c.memory_degraded));
+        assert!(codes.iter().all(|c| c.content == "4376471154038"));
+
+        let uncapped = detect_capped(10.0, (10.0, 10.0), 300, usize::MAX);
+        assert!(!uncapped[0].memory_degraded);
+    }
+}
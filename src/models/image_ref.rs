@@ -0,0 +1,366 @@
+//! Borrowed raw image buffers
+//!
+//! `ImageRef` carries the pixel layout and row stride alongside the bytes so
+//! callers holding padded or RGBA buffers don't have to repack them into the
+//! tight RGB that [`crate::detect`] expects. The `image-interop` and
+//! `ndarray-interop` features add conversions from those crates' types.
+
+use std::borrow::Cow;
+
+use crate::error::Error;
+use crate::utils::grayscale::{rgb_to_grayscale, rgba_to_grayscale};
+
+/// Pixel layout of an [`ImageRef`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8-bit grayscale
+    Luma8,
+    /// 8-bit RGB
+    Rgb8,
+    /// 8-bit RGBA (alpha is ignored)
+    Rgba8,
+}
+
+impl PixelFormat {
+    /// Bytes per pixel
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Luma8 => 1,
+            PixelFormat::Rgb8 => 3,
+            PixelFormat::Rgba8 => 4,
+        }
+    }
+}
+
+/// Raw image buffer with its dimensions, row stride and pixel format
+#[derive(Debug, Clone)]
+pub struct ImageRef<'a> {
+    data: Cow<'a, [u8]>,
+    width: usize,
+    height: usize,
+    stride: usize,
+    format: PixelFormat,
+}
+
+impl<'a> ImageRef<'a> {
+    /// Tightly packed rows (`stride = width * bytes_per_pixel`).
+    pub fn new(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        format: PixelFormat,
+    ) -> Result<Self, Error> {
+        Self::with_stride(
+            data,
+            width,
+            height,
+            width * format.bytes_per_pixel(),
+            format,
+        )
+    }
+
+    /// Rows `stride` bytes apart; padding after each row is ignored and the
+    /// last row need not be padded.
+    pub fn with_stride(
+        data: &'a [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+        format: PixelFormat,
+    ) -> Result<Self, Error> {
+        let row_len = width.saturating_mul(format.bytes_per_pixel());
+        let expected_len = stride
+            .saturating_mul(height.saturating_sub(1))
+            .saturating_add(row_len);
+        if width == 0 || height == 0 || stride < row_len || data.len() < expected_len {
+            return Err(Error::InvalidImage {
+                width,
+                height,
+                expected_len,
+                actual_len: data.len(),
+            });
+        }
+        Ok(Self {
+            data: Cow::Borrowed(data),
+            width,
+            height,
+            stride,
+            format,
+        })
+    }
+
+    /// Image width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Image height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Bytes between the starts of consecutive rows
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Pixel layout
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Tightly packed grayscale pixels; borrowed when the image already is.
+    pub fn to_grayscale(&self) -> Cow<'_, [u8]> {
+        let row_len = self.width * self.format.bytes_per_pixel();
+        let packed: Cow<'_, [u8]> = if self.stride == row_len {
+            Cow::Borrowed(&self.data[..row_len * self.height])
+        } else {
+            let mut packed = Vec::with_capacity(row_len * self.height);
+            for row in self.data.chunks(self.stride).take(self.height) {
+                packed.extend_from_slice(&row[..row_len]);
+            }
+            Cow::Owned(packed)
+        };
+        match self.format {
+            PixelFormat::Luma8 => packed,
+            PixelFormat::Rgb8 => Cow::Owned(rgb_to_grayscale(&packed, self.width, self.height)),
+            PixelFormat::Rgba8 => Cow::Owned(rgba_to_grayscale(&packed, self.width, self.height)),
+        }
+    }
+}
+
+/// Tightly packed 8-bit grayscale frame, e.g. one exposure of a bracketed
+/// capture for [`crate::detect_multi_exposure`]
+#[derive(Debug, Clone, Copy)]
+pub struct GrayFrame<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> GrayFrame<'a> {
+    /// `data` must be exactly `width * height` bytes.
+    pub fn new(data: &'a [u8], width: usize, height: usize) -> Result<Self, Error> {
+        let expected_len = width.saturating_mul(height);
+        if width == 0 || height == 0 || data.len() != expected_len {
+            return Err(Error::InvalidImage {
+                width,
+                height,
+                expected_len,
+                actual_len: data.len(),
+            });
+        }
+        Ok(Self {
+            data,
+            width,
+            height,
+        })
+    }
+
+    /// Luma bytes, row by row
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Frame width in pixels
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Frame height in pixels
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// Borrows 8-bit luma, RGB and RGBA images as-is; other layouts are
+/// converted to 8-bit luma, which is all detection needs.
+#[cfg(feature = "image-interop")]
+impl<'a> From<&'a image::DynamicImage> for ImageRef<'a> {
+    fn from(image: &'a image::DynamicImage) -> Self {
+        use image::DynamicImage;
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let (data, format) = match image {
+            DynamicImage::ImageLuma8(buf) => {
+                (Cow::Borrowed(buf.as_raw().as_slice()), PixelFormat::Luma8)
+            }
+            DynamicImage::ImageRgb8(buf) => {
+                (Cow::Borrowed(buf.as_raw().as_slice()), PixelFormat::Rgb8)
+            }
+            DynamicImage::ImageRgba8(buf) => {
+                (Cow::Borrowed(buf.as_raw().as_slice()), PixelFormat::Rgba8)
+            }
+            other => (Cow::Owned(other.to_luma8().into_raw()), PixelFormat::Luma8),
+        };
+        Self {
+            data,
+            width,
+            height,
+            stride: width * format.bytes_per_pixel(),
+            format,
+        }
+    }
+}
+
+/// Standard-layout arrays are borrowed; strided or transposed views are
+/// gathered into packed luma.
+#[cfg(feature = "ndarray-interop")]
+impl<'a> From<ndarray::ArrayView2<'a, u8>> for ImageRef<'a> {
+    fn from(view: ndarray::ArrayView2<'a, u8>) -> Self {
+        let (height, width) = view.dim();
+        let data = match view.to_slice() {
+            Some(slice) => Cow::Borrowed(slice),
+            None => Cow::Owned(view.iter().copied().collect()),
+        };
+        Self {
+            data,
+            width,
+            height,
+            stride: width,
+            format: PixelFormat::Luma8,
+        }
+    }
+}
+
+/// `(height, width, channels)` views with 1, 3 or 4 channels. Standard-layout
+/// arrays are borrowed; strided views are converted to luma a row at a time,
+/// so no packed copy of the color data is made.
+#[cfg(feature = "ndarray-interop")]
+impl<'a> TryFrom<ndarray::ArrayView3<'a, u8>> for ImageRef<'a> {
+    type Error = Error;
+
+    fn try_from(view: ndarray::ArrayView3<'a, u8>) -> Result<Self, Error> {
+        let (height, width, channels) = view.dim();
+        let format = match channels {
+            1 => PixelFormat::Luma8,
+            3 => PixelFormat::Rgb8,
+            4 => PixelFormat::Rgba8,
+            _ => {
+                return Err(Error::InvalidImage {
+                    width,
+                    height,
+                    expected_len: width * height * 3,
+                    actual_len: view.len(),
+                });
+            }
+        };
+        if let Some(slice) = view.to_slice() {
+            return Ok(Self {
+                data: Cow::Borrowed(slice),
+                width,
+                height,
+                stride: width * channels,
+                format,
+            });
+        }
+        let mut gray = Vec::with_capacity(width * height);
+        let mut row = Vec::with_capacity(width * channels);
+        for line in view.outer_iter() {
+            row.clear();
+            row.extend(line.iter().copied());
+            match format {
+                PixelFormat::Luma8 => gray.extend_from_slice(&row),
+                PixelFormat::Rgb8 => gray.extend(rgb_to_grayscale(&row, width, 1)),
+                PixelFormat::Rgba8 => gray.extend(rgba_to_grayscale(&row, width, 1)),
+            }
+        }
+        Ok(Self {
+            data: Cow::Owned(gray),
+            width,
+            height,
+            stride: width,
+            format: PixelFormat::Luma8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strided_buffers_match_packed_ones() {
+        // 2x2 RGB with 2 bytes of row padding, last row unpadded.
+        let packed = [10, 20, 30, 200, 210, 220, 0, 0, 0, 255, 255, 255];
+        let mut padded = packed[..6].to_vec();
+        padded.extend_from_slice(&[99, 99]);
+        padded.extend_from_slice(&packed[6..]);
+
+        let tight = ImageRef::new(&packed, 2, 2, PixelFormat::Rgb8).unwrap();
+        let strided = ImageRef::with_stride(&padded, 2, 2, 8, PixelFormat::Rgb8).unwrap();
+        assert_eq!(tight.to_grayscale(), strided.to_grayscale());
+        assert_eq!(
+            strided.to_grayscale().as_ref(),
+            rgb_to_grayscale(&packed, 2, 2).as_slice()
+        );
+
+        let luma = [1u8, 2, 3, 4];
+        let image = ImageRef::new(&luma, 2, 2, PixelFormat::Luma8).unwrap();
+        assert!(matches!(image.to_grayscale(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn rejects_short_buffers_and_narrow_strides() {
+        let data = [0u8; 11];
+        assert_eq!(
+            ImageRef::new(&data, 2, 2, PixelFormat::Rgb8).unwrap_err(),
+            Error::InvalidImage {
+                width: 2,
+                height: 2,
+                expected_len: 12,
+                actual_len: 11,
+            }
+        );
+        assert!(ImageRef::with_stride(&data, 2, 2, 5, PixelFormat::Rgb8).is_err());
+        assert!(ImageRef::new(&data, 0, 2, PixelFormat::Luma8).is_err());
+        assert!(GrayFrame::new(&data, 3, 4).is_err());
+        assert!(GrayFrame::new(&data[..10], 5, 2).is_ok());
+    }
+
+    #[cfg(feature = "image-interop")]
+    #[test]
+    fn dynamic_images_pick_the_cheapest_layout() {
+        let rgb = image::DynamicImage::new_rgb8(4, 3);
+        let view = ImageRef::from(&rgb);
+        assert_eq!(
+            (view.width(), view.height(), view.format()),
+            (4, 3, PixelFormat::Rgb8)
+        );
+        assert!(matches!(view.data, Cow::Borrowed(_)));
+
+        let wide = image::DynamicImage::new_rgb16(4, 3);
+        let view = ImageRef::from(&wide);
+        assert_eq!(view.format(), PixelFormat::Luma8);
+        assert_eq!(view.to_grayscale().len(), 12);
+    }
+
+    #[cfg(feature = "ndarray-interop")]
+    #[test]
+    fn ndarray_views_borrow_or_gather() {
+        use ndarray::{Array2, Array3, s};
+
+        let gray = Array2::from_shape_fn((4, 6), |(y, x)| (y * 6 + x) as u8);
+        let view = ImageRef::from(gray.view());
+        assert!(matches!(view.data, Cow::Borrowed(_)));
+        let cropped = ImageRef::from(gray.slice(s![1..3, 2..5]));
+        assert_eq!((cropped.width(), cropped.height()), (3, 2));
+        assert_eq!(cropped.to_grayscale().as_ref(), &[8, 9, 10, 14, 15, 16]);
+        let transposed = ImageRef::from(gray.t());
+        assert_eq!(transposed.to_grayscale()[..3], [0, 6, 12]);
+
+        let rgb = Array3::from_shape_fn((4, 6, 3), |(y, x, c)| (y * 40 + x * 7 + c * 20) as u8);
+        let full = ImageRef::try_from(rgb.view()).unwrap();
+        assert_eq!(full.format(), PixelFormat::Rgb8);
+        let strided = ImageRef::try_from(rgb.slice(s![..;2, 1..5, ..])).unwrap();
+        assert_eq!(strided.format(), PixelFormat::Luma8);
+        let packed: Vec<u8> = rgb.slice(s![..;2, 1..5, ..]).iter().copied().collect();
+        assert_eq!(
+            strided.to_grayscale().as_ref(),
+            rgb_to_grayscale(&packed, 4, 2).as_slice()
+        );
+
+        let two_channel = Array3::<u8>::zeros((2, 2, 2));
+        assert!(ImageRef::try_from(two_channel.view()).is_err());
+    }
+}
@@ -1,19 +1,31 @@
 /// Compact bit matrix for storing binary data
+///
+/// Rows are packed LSB-first into `u64` words and start on a word boundary,
+/// so a row can be walked a word at a time; bits past `width` stay zero.
+/// With the `serde` feature it serializes in that packed form as
+/// `{ width, height, data }`.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "PackedBits", into = "PackedBits")
+)]
 pub struct BitMatrix {
     width: usize,
     height: usize,
-    data: Vec<u8>,
+    row_words: usize,
+    data: Vec<u64>,
 }
 
 impl BitMatrix {
     /// Create a new bit matrix with given dimensions
     pub fn new(width: usize, height: usize) -> Self {
-        let bytes_needed = (width * height + 7) / 8;
+        let row_words = width.div_ceil(64);
         Self {
             width,
             height,
-            data: vec![0; bytes_needed],
+            row_words,
+            data: vec![0; row_words * height],
         }
     }
 
@@ -28,28 +40,25 @@ impl BitMatrix {
     }
 
     /// Get bit at (x, y)
+    #[inline]
     pub fn get(&self, x: usize, y: usize) -> bool {
         if x >= self.width || y >= self.height {
             return false;
         }
-        let index = y * self.width + x;
-        let byte_index = index / 8;
-        let bit_index = index % 8;
-        (self.data[byte_index] >> bit_index) & 1 == 1
+        (self.data[y * self.row_words + x / 64] >> (x % 64)) & 1 == 1
     }
 
     /// Set bit at (x, y)
+    #[inline]
     pub fn set(&mut self, x: usize, y: usize, value: bool) {
         if x >= self.width || y >= self.height {
             return;
         }
-        let index = y * self.width + x;
-        let byte_index = index / 8;
-        let bit_index = index % 8;
+        let word = &mut self.data[y * self.row_words + x / 64];
         if value {
-            self.data[byte_index] |= 1 << bit_index;
+            *word |= 1 << (x % 64);
         } else {
-            self.data[byte_index] &= !(1 << bit_index);
+            *word &= !(1 << (x % 64));
         }
     }
 
@@ -58,10 +67,24 @@ impl BitMatrix {
         if x >= self.width || y >= self.height {
             return;
         }
-        let index = y * self.width + x;
-        let byte_index = index / 8;
-        let bit_index = index % 8;
-        self.data[byte_index] ^= 1 << bit_index;
+        self.data[y * self.row_words + x / 64] ^= 1 << (x % 64);
+    }
+
+    /// Flip every bit, a word at a time; bits past `width` stay zero.
+    pub fn invert(&mut self) {
+        if self.row_words == 0 {
+            return;
+        }
+        let tail = self.width % 64;
+        let last_mask = if tail == 0 { !0 } else { (1u64 << tail) - 1 };
+        for row in self.data.chunks_exact_mut(self.row_words) {
+            for word in row.iter_mut() {
+                *word = !*word;
+            }
+            if let Some(last) = row.last_mut() {
+                *last &= last_mask;
+            }
+        }
     }
 
     /// Clear all bits to 0
@@ -71,17 +94,236 @@ impl BitMatrix {
 
     /// Reset matrix to new dimensions, reusing allocation if possible
     pub fn reset(&mut self, width: usize, height: usize) {
-        let bytes_needed = (width * height + 7) / 8;
         self.width = width;
         self.height = height;
-        self.data.resize(bytes_needed, 0);
-        self.data.fill(0);
+        self.row_words = width.div_ceil(64);
+        self.data.clear();
+        self.data.resize(self.row_words * height, 0);
+    }
+
+    /// Packed words of row `y`, bit `x % 64` of word `x / 64` holding
+    /// column `x`. Empty for rows outside the matrix.
+    pub fn row(&self, y: usize) -> &[u64] {
+        if y >= self.height {
+            return &[];
+        }
+        &self.data[y * self.row_words..(y + 1) * self.row_words]
+    }
+
+    /// Overwrite row `y` with `is_set(x)` for every column, packing a word
+    /// at a time. Binarizers fill rows through this instead of per-bit
+    /// [`Self::set`] calls.
+    #[inline]
+    pub fn fill_row(&mut self, y: usize, mut is_set: impl FnMut(usize) -> bool) {
+        if y >= self.height {
+            return;
+        }
+        let width = self.width;
+        let row = &mut self.data[y * self.row_words..(y + 1) * self.row_words];
+        for (i, word) in row.iter_mut().enumerate() {
+            let base = i * 64;
+            let mut bits = 0u64;
+            for bit in 0..(width - base).min(64) {
+                bits |= (is_set(base + bit) as u64) << bit;
+            }
+            *word = bits;
+        }
     }
 
-    /// Get raw data as bytes
-    pub fn as_bytes(&self) -> &[u8] {
+    /// Column just past the run of equal bits that contains (x, y) in row
+    /// `y`: the first column after `x` holding the other value, or the width.
+    /// Skips whole words of equal bits, so the row scanners step from edge
+    /// to edge instead of pixel by pixel.
+    pub fn run_end(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || y >= self.height {
+            return self.width;
+        }
+        let row = self.row(y);
+        // XOR with the run's value turns differing bits into ones; padding
+        // past the width reads as a change, which the clamp absorbs.
+        let flip = if self.get(x, y) { !0 } else { 0 };
+        let first = (row[x / 64] ^ flip) >> (x % 64);
+        if first != 0 {
+            return (x + first.trailing_zeros() as usize).min(self.width);
+        }
+        for (i, &word) in row.iter().enumerate().skip(x / 64 + 1) {
+            let diff = word ^ flip;
+            if diff != 0 {
+                return (i * 64 + diff.trailing_zeros() as usize).min(self.width);
+            }
+        }
+        self.width
+    }
+
+    /// First column of the run of equal bits that contains (x, y) in row `y`.
+    pub fn run_start(&self, x: usize, y: usize) -> usize {
+        if x >= self.width || y >= self.height {
+            return x.min(self.width);
+        }
+        let row = self.row(y);
+        let flip = if self.get(x, y) { !0 } else { 0 };
+        // Shift bit `x` up to the top so leading zeros count back from it.
+        let first = (row[x / 64] ^ flip) << (63 - x % 64);
+        if first != 0 {
+            return x + 1 - first.leading_zeros() as usize;
+        }
+        for i in (0..x / 64).rev() {
+            let diff = row[i] ^ flip;
+            if diff != 0 {
+                return i * 64 + 64 - diff.leading_zeros() as usize;
+            }
+        }
+        0
+    }
+
+    /// Get raw data as packed row words
+    pub fn as_words(&self) -> &[u64] {
         &self.data
     }
+
+    /// Get raw data as bytes in the layout used before rows moved to `u64`
+    /// words: bit `i = y * width + x` is bit `i % 8` of byte `i / 8`. Packed
+    /// on every call.
+    #[deprecated(note = "use `as_words`, which borrows the row-aligned words")]
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; (self.width * self.height).div_ceil(8)];
+        for y in 0..self.height {
+            for x in (0..self.width).filter(|&x| self.get(x, y)) {
+                let i = y * self.width + x;
+                bytes[i / 8] |= 1 << (i % 8);
+            }
+        }
+        bytes
+    }
+
+    /// Borrow a rectangular region without copying. The region is clipped
+    /// to the matrix bounds.
+    pub fn view(&self, x: usize, y: usize, width: usize, height: usize) -> BitMatrixView<'_> {
+        BitMatrixView::new(self, 0, 0, self.width, self.height).view(x, y, width, height)
+    }
+}
+
+/// Read-only window into a [`BitMatrix`], addressed in its own coordinates
+/// Serialized form of [`BitMatrix`]: `data` holds `height` rows of
+/// `width.div_ceil(64)` words each
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PackedBits {
+    width: usize,
+    height: usize,
+    data: Vec<u64>,
+}
+
+#[cfg(feature = "serde")]
+impl From<BitMatrix> for PackedBits {
+    fn from(matrix: BitMatrix) -> Self {
+        Self {
+            width: matrix.width,
+            height: matrix.height,
+            data: matrix.data,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PackedBits> for BitMatrix {
+    type Error = String;
+
+    fn try_from(packed: PackedBits) -> Result<Self, Self::Error> {
+        let row_words = packed.width.div_ceil(64);
+        if packed.data.len() != row_words * packed.height {
+            return Err(format!(
+                "{} words do not fill a {}x{} bit matrix",
+                packed.data.len(),
+                packed.width,
+                packed.height
+            ));
+        }
+        let mut matrix = Self {
+            width: packed.width,
+            height: packed.height,
+            row_words,
+            data: packed.data,
+        };
+        // Keep the invariant that bits past `width` are zero.
+        let spare = row_words * 64 - packed.width;
+        if spare > 0 {
+            for row in matrix.data.chunks_mut(row_words) {
+                row[row_words - 1] &= u64::MAX >> spare;
+            }
+        }
+        Ok(matrix)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BitMatrixView<'a> {
+    parent: &'a BitMatrix,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a> BitMatrixView<'a> {
+    fn new(parent: &'a BitMatrix, x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            parent,
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Get view width
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Get view height
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Offset of the view's origin in the parent matrix
+    pub fn offset(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    /// Get bit at (x, y) relative to the view origin
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        self.parent.get(self.x + x, self.y + y)
+    }
+
+    /// Narrow to a sub-region (relative coordinates), clipped to this view
+    pub fn view(&self, x: usize, y: usize, width: usize, height: usize) -> BitMatrixView<'a> {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        Self::new(
+            self.parent,
+            self.x + x,
+            self.y + y,
+            width.min(self.width - x),
+            height.min(self.height - y),
+        )
+    }
+
+    /// Copy the region into a standalone matrix
+    pub fn to_owned(&self) -> BitMatrix {
+        let mut out = BitMatrix::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y) {
+                    out.set(x, y, true);
+                }
+            }
+        }
+        out
+    }
 }
 
 impl Default for BitMatrix {
@@ -111,6 +353,95 @@ mod tests {
         assert!(!matrix.get(3, 4));
     }
 
+    #[test]
+    fn test_view_reads_parent_without_copying() {
+        let mut matrix = BitMatrix::new(10, 10);
+        matrix.set(4, 5, true);
+        matrix.set(9, 9, true);
+
+        let view = matrix.view(3, 4, 4, 4);
+        assert_eq!((view.width(), view.height()), (4, 4));
+        assert_eq!(view.offset(), (3, 4));
+        assert!(view.get(1, 1));
+        assert!(!view.get(0, 0));
+        assert!(!view.get(6, 5), "reads outside the view are false");
+
+        let nested = view.view(1, 1, 10, 10);
+        assert_eq!((nested.width(), nested.height()), (3, 3));
+        assert!(nested.get(0, 0));
+
+        let clipped = matrix.view(8, 8, 5, 5);
+        assert_eq!((clipped.width(), clipped.height()), (2, 2));
+        let owned = clipped.to_owned();
+        assert!(owned.get(1, 1) && !owned.get(0, 0));
+        assert_eq!(matrix.view(20, 20, 3, 3).width(), 0);
+    }
+
+    #[test]
+    fn test_runs_cross_word_boundaries() {
+        let mut matrix = BitMatrix::new(150, 2);
+        for x in 60..140 {
+            matrix.set(x, 1, true);
+        }
+        assert_eq!(matrix.run_end(0, 1), 60);
+        assert_eq!(matrix.run_end(60, 1), 140);
+        assert_eq!(matrix.run_end(100, 1), 140);
+        assert_eq!(matrix.run_end(140, 1), 150);
+        assert_eq!(matrix.run_end(0, 0), 150);
+        assert_eq!(matrix.run_start(139, 1), 60);
+        assert_eq!(matrix.run_start(70, 1), 60);
+        assert_eq!(matrix.run_start(59, 1), 0);
+        assert_eq!(matrix.run_start(149, 1), 140);
+        // Walking edge to edge agrees with reading every bit.
+        let mut x = 0;
+        while x < matrix.width() {
+            let end = matrix.run_end(x, 1);
+            assert!((x..end).all(|i| matrix.get(i, 1) == matrix.get(x, 1)));
+            assert!(end == matrix.width() || matrix.get(end, 1) != matrix.get(x, 1));
+            x = end;
+        }
+    }
+
+    #[test]
+    fn test_fill_row_matches_set() {
+        let mut filled = BitMatrix::new(70, 3);
+        let mut set = BitMatrix::new(70, 3);
+        filled.fill_row(1, |x| x % 3 == 0);
+        for x in (0..70).step_by(3) {
+            set.set(x, 1, true);
+        }
+        assert_eq!(filled.as_words(), set.as_words());
+        assert_eq!(filled.row(1).len(), 2);
+        assert!(filled.row(3).is_empty());
+    }
+
+    #[test]
+    fn test_invert_keeps_padding_clear() {
+        let mut m = BitMatrix::new(70, 2);
+        m.set(3, 0, true);
+        m.set(69, 1, true);
+        m.invert();
+        for y in 0..2 {
+            for x in 0..70 {
+                assert_eq!(m.get(x, y), !(x == 3 && y == 0 || x == 69 && y == 1));
+            }
+        }
+        assert_eq!(m.row(0)[1], (1 << 6) - 1);
+        assert_eq!(m.row(1)[1], (1 << 5) - 1);
+        BitMatrix::new(0, 3).invert();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_as_bytes_keeps_the_packed_layout() {
+        let mut matrix = BitMatrix::new(5, 3);
+        matrix.set(0, 0, true);
+        matrix.set(4, 1, true);
+        matrix.set(2, 2, true);
+        // Bits 0, 9 and 12 of the row-major bit string.
+        assert_eq!(matrix.as_bytes(), vec![0b0000_0001, 0b0001_0010]);
+    }
+
     #[test]
     fn test_out_of_bounds() {
         let mut matrix = BitMatrix::new(8, 8);
@@ -3,13 +3,23 @@
 //! This module defines the main types used throughout the library:
 //! - BitMatrix: Compact storage for binary QR data
 //! - Point: 2D coordinates for geometry calculations
+//! - ImagePoint, ModulePoint: positions typed by coordinate space (pixels or
+//!   modules)
 //! - QRCode: Result type containing decoded data
-//! - Version, ECLevel, MaskPattern: QR code metadata
+//! - SymbolInfo: Format and version of a symbol scanned without decoding
+//! - ImageRef: Borrowed raw image buffer with stride and pixel format
+//! - GrayFrame: Borrowed packed grayscale frame
+//! - Version, ECLevel, MaskPattern, Orientation: QR code metadata
 
+pub mod image_ref;
 pub mod matrix;
 pub mod point;
 pub mod qr_code;
 
-pub use matrix::BitMatrix;
-pub use point::Point;
-pub use qr_code::{ECLevel, MaskPattern, QRCode, Version};
+pub use image_ref::{GrayFrame, ImageRef, PixelFormat};
+pub use matrix::{BitMatrix, BitMatrixView};
+pub use point::{ImagePoint, ModulePoint, Point};
+pub use qr_code::{
+    Binarization, ECLevel, FormatQuirk, IntermediateData, MaskPattern, Orientation, QRCode,
+    StructuredAppend, SymbolInfo, TextEncoding, Version,
+};
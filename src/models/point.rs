@@ -1,5 +1,6 @@
 /// 2D point with floating point coordinates
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     /// X coordinate
     pub x: f32,
@@ -36,8 +37,81 @@ impl Point {
     }
 }
 
+/// A position in image pixels: x to the right, y down, `(0, 0)` the outer
+/// corner of the top-left pixel
+///
+/// Untyped [`Point`]s in this crate's public API (finder centres, code
+/// corners) are image positions too and convert freely. Getting here from a
+/// [`ModulePoint`] takes a transform, see
+/// [`crate::utils::geometry::PerspectiveTransform::to_image`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImagePoint {
+    /// X coordinate, pixels
+    pub x: f32,
+    /// Y coordinate, pixels
+    pub y: f32,
+}
+
+impl ImagePoint {
+    /// Create a new image point
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Distance to another image point, in pixels
+    pub fn distance(&self, other: &ImagePoint) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+impl From<Point> for ImagePoint {
+    fn from(p: Point) -> Self {
+        Self::new(p.x, p.y)
+    }
+}
+
+impl From<ImagePoint> for Point {
+    fn from(p: ImagePoint) -> Self {
+        Point::new(p.x, p.y)
+    }
+}
+
+/// A position on a symbol's module grid, one unit per module: `(0, 0)` is
+/// the outer corner of the top-left module, so module `(col, row)` spans
+/// `col..col + 1` and has its centre at `col + 0.5`
+///
+/// There is deliberately no conversion to or from pixels other than through
+/// a transform.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModulePoint {
+    /// Column coordinate, modules
+    pub x: f32,
+    /// Row coordinate, modules
+    pub y: f32,
+}
+
+impl ModulePoint {
+    /// Create a new module point
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Centre of the module in column `col`, row `row`
+    pub fn center_of(col: usize, row: usize) -> Self {
+        Self::new(col as f32 + 0.5, row as f32 + 0.5)
+    }
+
+    /// This point moved by `dx` columns and `dy` rows
+    pub fn offset(&self, dx: f32, dy: f32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
+    }
+}
+
 /// Integer point for grid coordinates
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointI {
     /// X coordinate
     pub x: i32,
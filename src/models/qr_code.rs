@@ -1,7 +1,8 @@
-use super::{BitMatrix, Point};
+use super::{BitMatrix, ImagePoint, Point};
 
 /// QR Code version (1-40 for Model 2)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Version {
     /// Model 1 QR code (versions 1-14)
     Model1(u8),
@@ -44,6 +45,7 @@ impl Version {
 
 /// Error correction level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ECLevel {
     /// Low (~7% recovery capacity)
     L = 0,
@@ -91,6 +93,7 @@ impl ECLevel {
 
 /// Mask pattern (0-7)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MaskPattern {
     /// (i + j) % 2 == 0
     Pattern0 = 0,
@@ -111,6 +114,18 @@ pub enum MaskPattern {
 }
 
 impl MaskPattern {
+    /// Every pattern, in reference order
+    pub const ALL: [MaskPattern; 8] = [
+        MaskPattern::Pattern0,
+        MaskPattern::Pattern1,
+        MaskPattern::Pattern2,
+        MaskPattern::Pattern3,
+        MaskPattern::Pattern4,
+        MaskPattern::Pattern5,
+        MaskPattern::Pattern6,
+        MaskPattern::Pattern7,
+    ];
+
     /// Get mask pattern from bits
     pub fn from_bits(bits: u8) -> Option<Self> {
         match bits & 0x07 {
@@ -141,25 +156,331 @@ impl MaskPattern {
     }
 }
 
+/// Character set used to turn byte-mode segments into text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextEncoding {
+    /// UTF-8 (ECI 26, or bytes that form valid UTF-8)
+    #[default]
+    Utf8,
+    /// ISO-8859-1, the spec default when no ECI is present
+    Latin1,
+}
+
+impl TextEncoding {
+    /// Decode `bytes` with this character set
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            TextEncoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+        }
+    }
+
+    /// UTF-8 when `bytes` are valid UTF-8, ISO-8859-1 otherwise
+    pub fn sniff(bytes: &[u8]) -> Self {
+        if std::str::from_utf8(bytes).is_ok() {
+            TextEncoding::Utf8
+        } else {
+            TextEncoding::Latin1
+        }
+    }
+}
+
+/// How a symbol appears in the image: quarter turns clockwise from upright,
+/// optionally after a left-right mirror (a code read through glass or
+/// printed reversed). Upright means the finder patterns sit top-left,
+/// top-right and bottom-left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientation {
+    /// Upright
+    #[default]
+    Rot0,
+    /// Turned a quarter clockwise
+    Rot90,
+    /// Upside down
+    Rot180,
+    /// Turned a quarter counter-clockwise
+    Rot270,
+    /// Mirrored, otherwise upright
+    Rot0Mirrored,
+    /// Mirrored, then turned a quarter clockwise
+    Rot90Mirrored,
+    /// Mirrored, then turned upside down
+    Rot180Mirrored,
+    /// Mirrored, then turned a quarter counter-clockwise
+    Rot270Mirrored,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 8] = [
+        Orientation::Rot0,
+        Orientation::Rot90,
+        Orientation::Rot180,
+        Orientation::Rot270,
+        Orientation::Rot0Mirrored,
+        Orientation::Rot90Mirrored,
+        Orientation::Rot180Mirrored,
+        Orientation::Rot270Mirrored,
+    ];
+
+    /// Orientation from clockwise quarter turns (taken mod 4) and mirroring
+    pub fn from_parts(quarter_turns: u8, mirrored: bool) -> Self {
+        Self::ALL[(quarter_turns % 4) as usize + if mirrored { 4 } else { 0 }]
+    }
+
+    /// Clockwise quarter turns, 0-3
+    pub fn quarter_turns(self) -> u8 {
+        self as u8 % 4
+    }
+
+    /// Whether the symbol is seen mirrored
+    pub fn is_mirrored(self) -> bool {
+        self as u8 >= 4
+    }
+
+    /// The orientation that undoes this one
+    pub fn inverse(self) -> Self {
+        if self.is_mirrored() {
+            // A mirror followed by any turn is its own inverse.
+            self
+        } else {
+            Self::from_parts(4 - self.quarter_turns(), false)
+        }
+    }
+
+    /// Where the point `p` of an upright `size`x`size` square lands once the
+    /// square is turned to this orientation in place
+    pub fn apply(self, p: Point, size: f32) -> Point {
+        let mut p = if self.is_mirrored() {
+            Point::new(size - p.x, p.y)
+        } else {
+            p
+        };
+        for _ in 0..self.quarter_turns() {
+            p = Point::new(size - p.y, p.x);
+        }
+        p
+    }
+}
+
+/// The binarization whose matrix a code was decoded from
+///
+/// Reported in [`QRCode::binarization`] and counted per decode in
+/// [`crate::DetectionTelemetry::binarization_decodes`], to tune the order in
+/// which the fallback binarizations are tried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Binarization {
+    /// Global Otsu threshold
+    Otsu,
+    /// Adaptive mean threshold over a 31px window
+    Adaptive31,
+    /// Adaptive mean threshold over a 21px window
+    Adaptive21,
+    /// Global threshold refined by a local one where the two disagree
+    Hybrid,
+    /// Adaptive mean threshold over a window sized to the frame
+    Adaptive,
+    /// Adaptive mean threshold over twice the frame-sized window, for
+    /// high-version symbols
+    AdaptiveWide,
+    /// Adaptive mean threshold over the finder patterns' bounding box, with
+    /// a window sized to their module size
+    AdaptiveModule,
+    /// Adaptive threshold of a crop around the finder patterns after its
+    /// contrast was stretched
+    RoiNormalized,
+    /// Sauvola with k = 0.2
+    Sauvola,
+    /// Sauvola with k = 0.1
+    SauvolaLowK,
+    /// Sauvola with k = 0.3
+    SauvolaHighK,
+    /// Sauvola with k = 0.2 over twice the frame-sized window
+    SauvolaWide,
+    /// Global threshold 26 levels below the median gray
+    ThresholdDark,
+    /// Global threshold 26 levels above the median gray
+    ThresholdLight,
+}
+
+impl Binarization {
+    /// Every binarization, in the order of
+    /// [`crate::DetectionTelemetry::binarization_decodes`]
+    pub const ALL: [Binarization; 14] = [
+        Binarization::Otsu,
+        Binarization::Adaptive31,
+        Binarization::Adaptive21,
+        Binarization::Hybrid,
+        Binarization::Adaptive,
+        Binarization::AdaptiveWide,
+        Binarization::AdaptiveModule,
+        Binarization::RoiNormalized,
+        Binarization::Sauvola,
+        Binarization::SauvolaLowK,
+        Binarization::SauvolaHighK,
+        Binarization::SauvolaWide,
+        Binarization::ThresholdDark,
+        Binarization::ThresholdLight,
+    ];
+
+    /// Short snake_case name for logs and reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Binarization::Otsu => "otsu",
+            Binarization::Adaptive31 => "adaptive31",
+            Binarization::Adaptive21 => "adaptive21",
+            Binarization::Hybrid => "hybrid",
+            Binarization::Adaptive => "adaptive",
+            Binarization::AdaptiveWide => "adaptive_wide",
+            Binarization::AdaptiveModule => "adaptive_module",
+            Binarization::RoiNormalized => "roi_normalized",
+            Binarization::Sauvola => "sauvola_k02",
+            Binarization::SauvolaLowK => "sauvola_k01",
+            Binarization::SauvolaHighK => "sauvola_k03",
+            Binarization::SauvolaWide => "sauvola_wide",
+            Binarization::ThresholdDark => "threshold_median_minus26",
+            Binarization::ThresholdLight => "threshold_median_plus26",
+        }
+    }
+
+    /// Slot in [`Self::ALL`]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A departure from the specification a code was read despite, reported
+/// in [`QRCode::format_quirk`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FormatQuirk {
+    /// Format information XORed with the `0x5412` mask in reversed bit
+    /// order, whose EC level bits come out swapped, so a strict read takes
+    /// L for H and M for Q. Counted in
+    /// [`crate::DetectionTelemetry::wrong_format_mask_rescues`].
+    WrongFormatMask,
+}
+
+impl FormatQuirk {
+    /// Short snake_case name for logs and reports
+    pub fn name(self) -> &'static str {
+        match self {
+            FormatQuirk::WrongFormatMask => "wrong_format_mask",
+        }
+    }
+}
+
+/// Decoder intermediates, kept only when
+/// `DetectorConfig::keep_intermediate_data` is set
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntermediateData {
+    /// Codewords in symbol (interleaved) order, before error correction
+    pub raw_codewords: Vec<u8>,
+    /// The same codewords, data and EC, after error correction
+    pub corrected_codewords: Vec<u8>,
+    /// Module grid with the data mask removed
+    pub unmasked_modules: BitMatrix,
+}
+
+/// Position of a symbol in a structured-append sequence, which splits one
+/// message over up to 16 symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StructuredAppend {
+    /// Zero-based position of this symbol in the sequence
+    pub index: u8,
+    /// Number of symbols in the sequence (1 - 16)
+    pub total: u8,
+    /// XOR of every byte of the whole message, shared by all its symbols
+    pub parity: u8,
+}
+
+/// Format and version of a located symbol, read without decoding its data
+///
+/// Returned by [`crate::scan_metadata_only`] for inventorying sheets of
+/// codes, where payloads are not needed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolInfo {
+    /// QR code version
+    pub version: Version,
+    /// Error correction level from the format information
+    pub ec_level: ECLevel,
+    /// Data mask from the format information
+    pub mask_pattern: MaskPattern,
+    /// Corner positions in the image (top-left, top-right, bottom-right,
+    /// bottom-left)
+    pub position: [Point; 4],
+    /// How the symbol sits in the image
+    pub orientation: Orientation,
+}
+
 /// Detected QR code
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QRCode {
     /// Raw decoded bytes
     pub data: Vec<u8>,
-    /// Decoded content as UTF-8 string
+    /// Decoded content; byte-mode segments are interpreted per `text_encoding`
     pub content: String,
+    /// Character set used for byte-mode segments (`Latin1` if any segment
+    /// was read as ISO-8859-1)
+    pub text_encoding: TextEncoding,
     /// QR code version
     pub version: Version,
     /// Error correction level
     pub error_correction: ECLevel,
     /// Mask pattern used
     pub mask_pattern: MaskPattern,
-    /// Corner points in image coordinates
+    /// Corner points in image coordinates (top-left, top-right, bottom-right, bottom-left);
+    /// see [`Self::corners`]
     pub position: [Point; 4],
+    /// In-plane rotation in degrees (-180 to 180, clockwise in image
+    /// coordinates), 0 when the finder patterns sit upright top-left,
+    /// top-right and bottom-left
+    pub rotation_deg: f32,
+    /// Which of the eight rotations and mirrorings the symbol was read in,
+    /// e.g. `Rot180` for an upside-down label
+    pub orientation: Orientation,
+    /// Estimated out-of-plane tilt in degrees (0 when facing the camera),
+    /// from the perspective foreshortening at the symbol centre
+    pub tilt_estimate: f32,
     /// Module matrix (true = black, false = white)
     pub modules: BitMatrix,
-    /// Detection confidence (0.0 - 1.0)
+    /// Estimated probability (0.0 - 1.0) that the decode is correct,
+    /// calibrated from `score`
     pub confidence: f32,
+    /// Uncalibrated internal quality score (0.0 - 1.0)
+    pub score: f32,
+    /// Share of the fixed patterns checked in the sampled grid (timing
+    /// alternation, separators, dark module) that came out wrong, 0.0 for
+    /// an intact symbol: the fixed pattern damage of a print-quality grade
+    pub fixed_pattern_damage: f32,
+    /// Whether error correction only succeeded with codewords filled in from
+    /// a payload prior (see `PayloadPrior`); the payload is then known to
+    /// start with the prior's shared prefix but the rest was read unaided
+    pub assisted: bool,
+    /// Whether the frame was scanned on a reduced pipeline to stay within
+    /// `DetectorConfig::max_memory_bytes`
+    pub memory_degraded: bool,
+    /// Spec violation in the symbol that the read had to work around; `None`
+    /// for a compliant symbol
+    pub format_quirk: Option<FormatQuirk>,
+    /// Side length of the symbol (without quiet zone) in millimetres,
+    /// estimated from its corners when `DetectorConfig::physical_scale` is set
+    pub physical_size_mm: Option<f32>,
+    /// Sequence header when the symbol is one part of a split message; see
+    /// `ScanSession` for reassembly
+    pub structured_append: Option<StructuredAppend>,
+    /// Decoder intermediates (see [`QRCode::raw_codewords`])
+    pub intermediate: Option<Box<IntermediateData>>,
+    /// Binarization of the pass that found and decoded the symbol (the
+    /// per-candidate region re-threshold is part of every pass); `None` when
+    /// decoded from a caller-supplied matrix rather than by a detection call
+    pub binarization: Option<Binarization>,
 }
 
 impl QRCode {
@@ -174,14 +495,87 @@ impl QRCode {
         Self {
             data,
             content,
+            text_encoding: TextEncoding::Utf8,
             version,
             error_correction,
             mask_pattern,
             position: [Point::default(); 4],
+            rotation_deg: 0.0,
+            orientation: Orientation::Rot0,
+            tilt_estimate: 0.0,
             modules: BitMatrix::new(0, 0),
             confidence: 1.0,
+            score: 1.0,
+            fixed_pattern_damage: 0.0,
+            assisted: false,
+            memory_degraded: false,
+            format_quirk: None,
+            physical_size_mm: None,
+            structured_append: None,
+            intermediate: None,
+            binarization: None,
         }
     }
+
+    /// The symbol's outer corners in image pixels, in the symbol's own
+    /// order (top-left, top-right, bottom-right, bottom-left, however it is
+    /// turned in the image). Each is the outer corner of a corner module, so
+    /// the quiet zone lies outside them; [`Self::position`] untyped.
+    pub fn corners(&self) -> [ImagePoint; 4] {
+        self.position.map(ImagePoint::from)
+    }
+
+    /// [`Self::corners`] wound clockwise in image space, starting at the
+    /// symbol's top-left. That is TL, TR, BR, BL for every orientation
+    /// except the mirrored ones, which come back TL, BL, BR, TR; polygon
+    /// helpers in [`crate::utils::geometry`] assume this winding.
+    pub fn outline(&self) -> [ImagePoint; 4] {
+        crate::utils::geometry::clockwise(self.position).map(ImagePoint::from)
+    }
+
+    /// Side length in millimetres implied by `scale`: the mean of the four
+    /// edges of [`Self::position`], each measured in millimetres. Tilt
+    /// foreshortens the edges, so the estimate reads low on oblique shots.
+    pub fn measure_physical_size(&self, scale: &crate::config::PhysicalScale) -> Option<f32> {
+        let (sx, sy) = scale.mm_per_pixel()?;
+        let p = &self.position;
+        let edges: f32 = (0..4)
+            .map(|i| {
+                let (a, b) = (p[i], p[(i + 1) % 4]);
+                ((b.x - a.x) * sx).hypot((b.y - a.y) * sy)
+            })
+            .sum();
+        Some(edges / 4.0).filter(|size| *size > 0.0)
+    }
+
+    /// Module pitch in millimetres, from [`Self::physical_size_mm`].
+    pub fn module_size_mm(&self) -> Option<f32> {
+        let modules = self.version.size();
+        self.physical_size_mm
+            .filter(|_| modules > 0)
+            .map(|size| size / modules as f32)
+    }
+
+    /// Codewords as read from the symbol, before error correction.
+    /// `None` unless `DetectorConfig::keep_intermediate_data` was set.
+    pub fn raw_codewords(&self) -> Option<&[u8]> {
+        self.intermediate
+            .as_deref()
+            .map(|d| d.raw_codewords.as_slice())
+    }
+
+    /// Codewords after error correction, in the same order as
+    /// [`Self::raw_codewords`].
+    pub fn corrected_codewords(&self) -> Option<&[u8]> {
+        self.intermediate
+            .as_deref()
+            .map(|d| d.corrected_codewords.as_slice())
+    }
+
+    /// Module grid with the data mask removed.
+    pub fn unmasked_modules(&self) -> Option<&BitMatrix> {
+        self.intermediate.as_deref().map(|d| &d.unmasked_modules)
+    }
 }
 
 #[cfg(test)]
@@ -203,6 +597,116 @@ mod tests {
         assert_eq!(ECLevel::from_bits(0b11), Some(ECLevel::H));
     }
 
+    #[test]
+    fn test_orientation_parts_and_inverse() {
+        for o in Orientation::ALL {
+            assert_eq!(
+                Orientation::from_parts(o.quarter_turns(), o.is_mirrored()),
+                o
+            );
+            let there = o.apply(Point::new(3.0, 5.0), 21.0);
+            let back = o.inverse().apply(there, 21.0);
+            assert_eq!((back.x, back.y), (3.0, 5.0), "{o:?}");
+        }
+        // Turning clockwise carries the top-left corner to the top-right.
+        let p = Orientation::Rot90.apply(Point::new(0.0, 0.0), 21.0);
+        assert_eq!((p.x, p.y), (21.0, 0.0));
+        let p = Orientation::Rot0Mirrored.apply(Point::new(0.0, 21.0), 21.0);
+        assert_eq!((p.x, p.y), (21.0, 21.0));
+    }
+
+    #[test]
+    fn test_measure_physical_size() {
+        use crate::config::PhysicalScale;
+        let mut qr = QRCode::new(
+            Vec::new(),
+            String::new(),
+            Version::Model2(1),
+            ECLevel::M,
+            MaskPattern::Pattern0,
+        );
+        // 210px square, pixels twice as tall as wide.
+        qr.position =
+            [(0.0, 0.0), (210.0, 0.0), (210.0, 105.0), (0.0, 105.0)].map(|(x, y)| Point::new(x, y));
+        let scan = PhysicalScale::Dpi { x: 254.0, y: 127.0 };
+        let size = qr.measure_physical_size(&scan).unwrap();
+        assert!((size - 21.0).abs() < 1e-3, "{size}");
+        let camera = PhysicalScale::Camera {
+            focal_x_px: 1000.0,
+            focal_y_px: 500.0,
+            distance_mm: 100.0,
+        };
+        assert!((qr.measure_physical_size(&camera).unwrap() - 21.0).abs() < 1e-3);
+        assert_eq!(
+            qr.measure_physical_size(&PhysicalScale::Dpi { x: 0.0, y: 300.0 }),
+            None
+        );
+
+        assert_eq!(qr.module_size_mm(), None);
+        qr.physical_size_mm = Some(size);
+        assert!((qr.module_size_mm().unwrap() - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_outline_winds_clockwise() {
+        let mut qr = QRCode::new(
+            Vec::new(),
+            String::new(),
+            Version::Model2(1),
+            ECLevel::M,
+            MaskPattern::Pattern0,
+        );
+        // Mirrored: the symbol's TL, TR, BR, BL run counter-clockwise.
+        qr.position =
+            [(0.0, 0.0), (0.0, 21.0), (21.0, 21.0), (21.0, 0.0)].map(|(x, y)| Point::new(x, y));
+        let outline = qr.outline().map(Point::from);
+        assert_eq!(
+            outline,
+            [
+                qr.position[0],
+                qr.position[3],
+                qr.position[2],
+                qr.position[1]
+            ]
+        );
+        assert!(crate::utils::geometry::signed_area(&outline) > 0.0);
+        qr.position = outline;
+        assert_eq!(qr.outline().map(Point::from), outline);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut qr = QRCode::new(
+            b"hi".to_vec(),
+            "hi".to_string(),
+            Version::Model2(2),
+            ECLevel::Q,
+            MaskPattern::Pattern5,
+        );
+        qr.modules = BitMatrix::new(70, 2);
+        qr.modules.set(69, 1, true);
+        qr.structured_append = Some(StructuredAppend {
+            index: 1,
+            total: 3,
+            parity: 0x5a,
+        });
+
+        let json = serde_json::to_string(&qr).unwrap();
+        assert!(json.contains(r#""version":{"Model2":2}"#), "{json}");
+        assert!(json.contains(r#""error_correction":"Q""#), "{json}");
+        assert!(json.contains(r#""mask_pattern":"Pattern5""#), "{json}");
+        let back: QRCode = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.content, "hi");
+        assert_eq!(back.version, qr.version);
+        assert_eq!(back.structured_append, qr.structured_append);
+        assert!(back.modules.get(69, 1));
+        assert_eq!(back.modules.width(), 70);
+
+        let short = r#"{"width":70,"height":2,"data":[0]}"#;
+        assert!(serde_json::from_str::<BitMatrix>(short).is_err());
+    }
+
     #[test]
     fn test_mask_pattern() {
         let mask = MaskPattern::Pattern0;
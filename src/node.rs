@@ -0,0 +1,160 @@
+//! Node.js bindings (napi-rs), enabled with the `node` feature.
+//!
+//! Build the addon with
+//! `cargo rustc --lib --release --features node --crate-type cdylib` and copy
+//! the shared library to `rust_qr.node`, then:
+//!
+//! ```js
+//! const { detect } = require('./rust_qr.node');
+//! const codes = await detect(rgbBuffer, width, height);
+//! const sure = await detect(rgbBuffer, width, height, 0.95);
+//! ```
+//!
+//! The input `Buffer` is borrowed for the lifetime of the task rather than
+//! copied, and detection runs on the libuv worker pool.
+
+use napi::bindgen_prelude::{AsyncTask, Buffer};
+use napi::{Env, Error, Result, Status, Task};
+use napi_derive::napi;
+
+use crate::{DetectorConfig, QRCode};
+
+/// Corner point in image coordinates.
+#[napi(object)]
+pub struct JsPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Decoded QR code as seen from JavaScript.
+#[napi(object)]
+pub struct JsQrCode {
+    pub content: String,
+    pub data: Buffer,
+    /// Character set used for byte-mode text: `Utf8` or `Latin1`.
+    pub text_encoding: String,
+    pub version: u32,
+    pub ec_level: String,
+    pub mask: u32,
+    pub confidence: f64,
+    /// Outline from the symbol's top-left, clockwise in image space: TL,
+    /// TR, BR, BL (TL, BL, BR, TR when mirrored).
+    pub corners: Vec<JsPoint>,
+    /// In-plane rotation in degrees, clockwise.
+    pub rotation_deg: f64,
+    /// `Rot0`..`Rot270`, with a `Mirrored` suffix for mirrored symbols.
+    pub orientation: String,
+    /// Out-of-plane tilt in degrees.
+    pub tilt_estimate: f64,
+    /// Share of the fixed patterns found damaged (0 when intact).
+    pub fixed_pattern_damage: f64,
+    /// Whether error correction needed the payload prior.
+    pub assisted: bool,
+    /// Whether a memory cap forced a reduced pipeline.
+    pub memory_degraded: bool,
+    /// Spec violation the read worked around, e.g. `"wrong_format_mask"`.
+    pub format_quirk: Option<String>,
+    /// Symbol side length in millimetres, when a physical scale was given.
+    pub physical_size_mm: Option<f64>,
+    /// Binarization the symbol was decoded from, e.g. `"otsu"`.
+    pub binarization: Option<String>,
+}
+
+impl From<QRCode> for JsQrCode {
+    fn from(qr: QRCode) -> Self {
+        let outline = qr.outline();
+        Self {
+            content: qr.content,
+            data: qr.data.into(),
+            text_encoding: format!("{:?}", qr.text_encoding),
+            version: qr.version.number() as u32,
+            ec_level: format!("{:?}", qr.error_correction),
+            mask: qr.mask_pattern as u32,
+            confidence: qr.confidence as f64,
+            corners: outline
+                .iter()
+                .map(|p| JsPoint {
+                    x: p.x as f64,
+                    y: p.y as f64,
+                })
+                .collect(),
+            rotation_deg: qr.rotation_deg as f64,
+            orientation: format!("{:?}", qr.orientation),
+            tilt_estimate: qr.tilt_estimate as f64,
+            fixed_pattern_damage: qr.fixed_pattern_damage as f64,
+            assisted: qr.assisted,
+            memory_degraded: qr.memory_degraded,
+            format_quirk: qr.format_quirk.map(|q| q.name().to_string()),
+            physical_size_mm: qr.physical_size_mm.map(f64::from),
+            binarization: qr.binarization.map(|b| b.name().to_string()),
+        }
+    }
+}
+
+pub struct DetectTask {
+    image: Buffer,
+    width: usize,
+    height: usize,
+    min_confidence: Option<f32>,
+}
+
+impl Task for DetectTask {
+    type Output = Vec<QRCode>;
+    type JsValue = Vec<JsQrCode>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let Some(expected) = self
+            .width
+            .checked_mul(self.height)
+            .and_then(|pixels| pixels.checked_mul(3))
+        else {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("{}x{} RGB image is too large", self.width, self.height),
+            ));
+        };
+        if self.image.len() != expected {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!(
+                    "expected {} RGB bytes for {}x{}, got {}",
+                    expected,
+                    self.width,
+                    self.height,
+                    self.image.len()
+                ),
+            ));
+        }
+        let config = DetectorConfig {
+            min_confidence: self.min_confidence,
+            ..DetectorConfig::default()
+        };
+        Ok(crate::detect_with_config(
+            &self.image,
+            self.width,
+            self.height,
+            &config,
+        ))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output.into_iter().map(JsQrCode::from).collect())
+    }
+}
+
+/// Detect QR codes in a packed RGB buffer without blocking the event loop.
+/// With `minConfidence`, codes calibrated below it are left out.
+#[napi(ts_return_type = "Promise<JsQrCode[]>")]
+pub fn detect(
+    image: Buffer,
+    width: u32,
+    height: u32,
+    min_confidence: Option<f64>,
+) -> AsyncTask<DetectTask> {
+    AsyncTask::new(DetectTask {
+        image,
+        width: width as usize,
+        height: height as usize,
+        min_confidence: min_confidence.map(|m| m as f32),
+    })
+}
@@ -1,39 +1,226 @@
+use crate::DecodeFailure;
 use crate::DetectionTelemetry;
-use crate::decoder::qr_decoder::QrDecoder;
-use crate::detector::finder::FinderPattern;
-use crate::models::{BitMatrix, ECLevel, Point, QRCode};
+use crate::ImageCondition;
+use crate::decoder::qr_decoder::{
+    GridSampling, QrDecoder, scoped_grid_refinement, scoped_grid_sampling, scoped_high_density,
+    scoped_synthetic,
+};
+use crate::detector::connected_components::ImageStats;
+use crate::detector::finder::{FinderDetector, FinderPattern};
+use crate::features;
+use crate::frame::Planes;
+use crate::models::{BitMatrix, ECLevel, ImagePoint, Point, QRCode, SymbolInfo};
+use crate::utils::grayscale::percentile_contrast_span;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env;
+use std::mem;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
 
 const MAX_GROUP_CANDIDATES: usize = 40;
+const DEFAULT_MAX_GROUPS_PER_REGION: usize = 12;
 const DEFAULT_DECODE_TOP_K: usize = 6;
 const MAX_DECODE_TOP_K: usize = 64;
 const HIGH_GROUP_CONFIDENCE: f32 = 0.80;
 const LOW_TOP_GROUP_CONFIDENCE: f32 = 0.62;
 const SINGLE_QR_CONFIDENCE_FLOOR: f32 = 0.78;
 const DEFAULT_MAX_TRANSFORMS: usize = 24;
-const DEFAULT_MAX_DECODE_ATTEMPTS: usize = 48;
+pub(crate) const DEFAULT_MAX_DECODE_ATTEMPTS: usize = 48;
 const DEFAULT_MAX_REGIONS: usize = 8;
 const DEFAULT_PER_REGION_TOP_K: usize = 4;
 const HIGH_CONFIDENCE_LANE_MIN: f32 = 0.78;
 const MEDIUM_CONFIDENCE_LANE_MIN: f32 = 0.56;
+const SOLVED_REGION_ACCEPTANCE: f32 = 0.86;
+const SOLVED_REGION_OVERLAP: f32 = 0.5;
 const CLUSTER_GROUP_TRIGGER: usize = 64;
 const CLUSTER_TARGET_SIZE: usize = 28;
+/// Modules across (version 21 and up) from which the router treats a
+/// symbol as high density.
+const HIGH_DENSITY_MIN_MODULES: f32 = 100.0;
+/// Share of neighbouring same-tone pixels that must be exactly equal, in
+/// both the dark and the light tone, for the router to take the image as
+/// rendered rather than photographed.
+const SYNTHETIC_MIN_FLATNESS: f32 = 0.95;
+/// Most the finder row may lean off an image axis, in degrees, for the
+/// synthetic profile.
+const SYNTHETIC_MAX_LEAN_DEG: f32 = 1.0;
+/// Modules across (version 10 and up) from which the condition classifier
+/// files an image as high version.
+const CONDITION_HIGH_VERSION_MIN_MODULES: f32 = 57.0;
+/// Lean off the image axes, in degrees, from which an image reads as rotated.
+const CONDITION_MIN_ROTATION_DEG: f32 = 10.0;
+/// Gray-level span below which an image reads as badly exposed.
+const CONDITION_MAX_LOW_CONTRAST_SPAN: f32 = 96.0;
+/// Finder ring signal-to-noise below which a group goes to the low
+/// confidence lane, and a top group routes the image to low-contrast
+/// recovery (`QR_LOW_SNR_MAX` overrides; 0 disables).
+const DEFAULT_LOW_SNR_MAX: f32 = 3.0;
 // Increased from 40 to 64 for better multi-QR coverage in "lots" category
 const CLUSTER_MAX_SIZE: usize = 64;
+/// Finder candidates past which grouping works from a stratified sample
+/// (`QR_FINDER_SAMPLE_CAP`, 0 for none). Dense multi-code photos reach
+/// 3-4k; a 2k sample reads as many codes in under half the time.
+const DEFAULT_FINDER_SAMPLE_CAP: usize = 2048;
+/// Cells across and down the frame that finder sampling is stratified over.
+const FINDER_SAMPLE_GRID: usize = 8;
+/// Groups ranked between checks of the rank time budget.
+const RANK_DEADLINE_STRIDE: usize = 64;
+/// Bins per octave of the finder module-size histogram, about 9% apart.
+const SIZE_BINS_PER_OCTAVE: f32 = 8.0;
+/// Smallest module size the histogram counts, in pixels.
+const SIZE_HISTOGRAM_MIN: f32 = 0.5;
+/// Histogram bins, covering module sizes up to 128 times the smallest.
+const SIZE_HISTOGRAM_BINS: usize = 56;
+/// Finders a size mode needs: one symbol's worth.
+const SIZE_MODE_MIN_FINDERS: usize = 3;
+/// Fewest bins between two size modes, about 30% in module size.
+const SIZE_MODE_MIN_SEPARATION: usize = 3;
+/// Most size modes reported.
+const MAX_SIZE_MODES: usize = 4;
+
+/// Per-thread decode budget installed by the adaptive controller in `Detector`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BudgetOverride {
+    pub(crate) max_decode_attempts: usize,
+    /// Upper bound for strategy-driven budget expansion (multi-QR scenes).
+    pub(crate) max_expanded_attempts: usize,
+    pub(crate) lane_high_frac: f32,
+    pub(crate) lane_medium_frac: f32,
+}
+
+/// Receives codes as ranked-group decoding accepts them; `Break` stops it.
+pub(crate) type CodeSink<'a> = dyn FnMut(&QRCode) -> ControlFlow<()> + 'a;
+
+thread_local! {
+    static BUDGET_OVERRIDE: Cell<Option<BudgetOverride>> = const { Cell::new(None) };
+    static ATTEMPTS_USED: Cell<usize> = const { Cell::new(0) };
+    static EXPECTED_PAYLOAD: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    /// Saturation of the grayscale buffer at `(address, len)`
+    static SATURATION_RATIO: Cell<Option<(usize, usize, f32)>> = const { Cell::new(None) };
+    static DECODE_MEMO: RefCell<Option<DecodeMemo>> = const { RefCell::new(None) };
+}
+
+/// Candidate decodes of one grayscale buffer, keyed by quantized finder
+/// geometry.
+struct DecodeMemo {
+    /// Region decodes that failed Reed-Solomon: the failure, and whether
+    /// heavy recovery was already tried. Left empty with `QR_DECODE_MEMO=0`.
+    hopeless: HashMap<[i32; 8], (DecodeFailure, bool)>,
+    /// Times each geometry has entered decoding
+    entries: HashMap<[i32; 8], usize>,
+}
+
+/// Run `f` remembering which candidates proved undecodable, for `f`'s
+/// binarizations of one grayscale frame: a region decode re-binarizes the
+/// candidate's own region from that frame, so under another frame-wide
+/// binarization the same finder geometry would fail the same way. Decodes
+/// of other buffers, such as a cropped or normalized ROI, run under
+/// [`without_decode_memo`]. The scope also counts how often each geometry
+/// comes back, to cut off retry loops that keep re-entering it.
+pub(crate) fn with_decode_memo<R>(f: impl FnOnce() -> R) -> R {
+    let memo = DecodeMemo {
+        hopeless: HashMap::new(),
+        entries: HashMap::new(),
+    };
+    let prev = DECODE_MEMO.with(|m| m.replace(Some(memo)));
+    let out = f();
+    DECODE_MEMO.with(|m| *m.borrow_mut() = prev);
+    out
+}
+
+/// Run `f`, whose decodes read a buffer other than the memoized frame,
+/// with the enclosing [`with_decode_memo`] scope set aside.
+pub(crate) fn without_decode_memo<R>(f: impl FnOnce() -> R) -> R {
+    let prev = DECODE_MEMO.with(RefCell::take);
+    let out = f();
+    DECODE_MEMO.with(|m| *m.borrow_mut() = prev);
+    out
+}
+
+/// Memo key for `candidate`: finder centres on a grid of a quarter module
+/// (half a pixel at least), so the sub-pixel jitter between binarizations
+/// lands in the same cell, plus the module size in quarter pixels and which
+/// finders were inverted.
+fn memo_key(candidate: &RankedGroupCandidate) -> [i32; 8] {
+    let module = (candidate.module_size * 4.0).round();
+    let cell = (module / 16.0).max(0.5);
+    let q = |v: f32| (v / cell).round() as i32;
+    [
+        q(candidate.tl.x),
+        q(candidate.tl.y),
+        q(candidate.tr.x),
+        q(candidate.tr.y),
+        q(candidate.bl.x),
+        q(candidate.bl.y),
+        module as i32,
+        candidate
+            .inverted_finders
+            .iter()
+            .fold(0, |bits, &inv| bits << 1 | inv as i32),
+    ]
+}
+
+/// Run `f` with `budget` applied to every ranked-group decode on this thread,
+/// returning the number of decode attempts it consumed.
+pub(crate) fn with_budget_override<R>(budget: BudgetOverride, f: impl FnOnce() -> R) -> (R, usize) {
+    let prev = BUDGET_OVERRIDE.with(|b| b.replace(Some(budget)));
+    let prev_used = ATTEMPTS_USED.with(|c| c.replace(0));
+    let out = f();
+    BUDGET_OVERRIDE.with(|b| b.set(prev));
+    let used = ATTEMPTS_USED.with(|c| c.replace(prev_used));
+    (out, used)
+}
+
+/// Run `f` with ranked-group decoding on this thread looking only for a code
+/// whose bytes equal `expected`: it returns that code as soon as a candidate
+/// matches and skips scoring and acceptance. When no candidate matches, the
+/// first code read with another payload is returned instead, so callers stop
+/// retrying a frame that decodes to something else.
+pub(crate) fn with_expected_payload<R>(expected: &[u8], f: impl FnOnce() -> R) -> R {
+    let prev = EXPECTED_PAYLOAD.with(|e| e.replace(Some(expected.to_vec())));
+    let out = f();
+    EXPECTED_PAYLOAD.with(|e| *e.borrow_mut() = prev);
+    out
+}
+
+/// Run `f` with ranked-group decoding on this thread taking the saturation
+/// of `gray` from `stats` instead of re-scanning it. Decodes of other
+/// buffers, such as re-binarized ROIs, still measure their own.
+pub(crate) fn with_image_stats<R>(gray: &[u8], stats: &ImageStats, f: impl FnOnce() -> R) -> R {
+    let key = (gray.as_ptr() as usize, gray.len(), stats.saturation_ratio);
+    let prev = SATURATION_RATIO.with(|s| s.replace(Some(key)));
+    let out = f();
+    SATURATION_RATIO.with(|s| s.set(prev));
+    out
+}
+
+fn verifying_payload() -> bool {
+    EXPECTED_PAYLOAD.with(|e| e.borrow().is_some())
+}
+
+fn note_attempt_used() {
+    ATTEMPTS_USED.with(|c| c.set(c.get() + 1));
+}
 
 #[derive(Clone, Copy)]
 struct RankedGroupCandidate {
     group: [usize; 3],
-    tl: Point,
-    tr: Point,
-    bl: Point,
+    tl: ImagePoint,
+    tr: ImagePoint,
+    bl: ImagePoint,
     module_size: f32,
     raw_score: f32,
     rerank_score: f32,
     saturation_coverage: f32,
     geometry_confidence: f32,
+    /// Finder ring signal-to-noise; see [`finder_ring_snr`]
+    snr: f32,
+    /// Which of the top-left, top-right and bottom-left finders were found
+    /// with reversed polarity
+    inverted_finders: [bool; 3],
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -42,7 +229,9 @@ enum StrategyProfile {
     MultiQrHeavy,
     RotationHeavy,
     HighVersionPrecision,
+    HighDensity,
     LowContrastRecovery,
+    Synthetic,
 }
 
 #[derive(Clone, Copy)]
@@ -52,40 +241,206 @@ enum ConfidenceLane {
     Low,
 }
 
+impl ConfidenceLane {
+    fn index(self) -> usize {
+        match self {
+            ConfidenceLane::High => 0,
+            ConfidenceLane::Medium => 1,
+            ConfidenceLane::Low => 2,
+        }
+    }
+}
+
+/// Units of one budget handed out and spent so far.
+#[derive(Clone, Copy, Debug, Default)]
+struct Quota {
+    allocated: usize,
+    consumed: usize,
+}
+
+impl Quota {
+    fn new(allocated: usize) -> Self {
+        Self {
+            allocated,
+            consumed: 0,
+        }
+    }
+
+    fn remaining(self) -> usize {
+        self.allocated - self.consumed
+    }
+
+    fn take(&mut self) -> bool {
+        if self.consumed == self.allocated {
+            return false;
+        }
+        self.consumed += 1;
+        true
+    }
+}
+
+/// Part of ranked-group decoding that spends from a [`DecodeBudget`].
 #[derive(Clone, Copy)]
-struct LaneBudget {
-    high: usize,
-    medium: usize,
-    low: usize,
+enum DecodeStage {
+    /// The top-ranked candidate, tried before any expansion
+    Top1,
+    /// Candidates visited region by region after it
+    Regions,
 }
 
-impl LaneBudget {
-    fn consume(&mut self, lane: ConfidenceLane) -> bool {
-        match lane {
-            ConfidenceLane::High => {
-                if self.high == 0 {
-                    false
-                } else {
-                    self.high -= 1;
-                    true
-                }
+/// Transform and attempt quotas of each [`DecodeStage`], the confidence
+/// lane split of the attempts and the per-region caps for one ranked-group
+/// decode.
+///
+/// Env, adaptive-controller and caller overrides all apply in
+/// [`DecodeBudget::attempt_quota`] and [`DecodeBudget::new`]; the decode
+/// loop only asks for room and consumes it.
+struct DecodeBudget {
+    /// `[transforms, attempts]` per stage, in [`DecodeStage`] order
+    stages: [[Quota; 2]; 2],
+    /// Attempts per [`ConfidenceLane`], high first; sums to the attempts
+    lanes: [Quota; 3],
+    max_regions: usize,
+    per_region_top_k: usize,
+    per_region_attempt_cap: usize,
+}
+
+impl DecodeBudget {
+    /// Attempts before any strategy expansion: `QR_MAX_DECODE_ATTEMPTS`, or
+    /// the adaptive controller's budget, capped at the caller's `limit`.
+    fn attempt_quota(limit: Option<usize>) -> usize {
+        let attempts = match BUDGET_OVERRIDE.with(Cell::get) {
+            Some(budget) => budget.max_decode_attempts,
+            None => decode_usize_env(
+                "QR_MAX_DECODE_ATTEMPTS",
+                DEFAULT_MAX_DECODE_ATTEMPTS,
+                1,
+                1024,
+            ),
+        };
+        limit.map_or(attempts, |limit| attempts.min(limit))
+    }
+
+    /// Split `attempts` (at least one) for `strategy` over `top_k` ranked
+    /// candidates.
+    fn new(attempts: usize, strategy: StrategyProfile, top_k: usize) -> Self {
+        debug_assert!(attempts > 0);
+        let budget_override = BUDGET_OVERRIDE.with(Cell::get);
+        let mut attempts = attempts;
+        let mut transforms =
+            decode_usize_env("QR_MAX_TRANSFORMS", DEFAULT_MAX_TRANSFORMS, 1, 512).min(attempts);
+        let mut max_regions = decode_usize_env("QR_MAX_REGIONS", DEFAULT_MAX_REGIONS, 1, 64);
+        let mut per_region_top_k = decode_usize_env(
+            "QR_PER_REGION_TOP_K",
+            DEFAULT_PER_REGION_TOP_K,
+            1,
+            MAX_DECODE_TOP_K,
+        );
+        let mut per_region_attempt_cap = decode_usize_env("QR_PER_REGION_ATTEMPTS", 3, 1, 64);
+        if matches!(strategy, StrategyProfile::MultiQrHeavy) {
+            let scaled_budget = (max_regions * per_region_top_k.max(16) * 2).min(512);
+            // Multi-QR images require substantially larger attempt budgets.
+            attempts = attempts.max(scaled_budget);
+            // Keep transform and decode budgets aligned for dense scenes.
+            transforms = transforms.max(attempts).min(512);
+            if let Some(budget) = budget_override {
+                attempts = attempts.min(budget.max_expanded_attempts);
+                transforms = transforms.min(attempts);
             }
-            ConfidenceLane::Medium => {
-                if self.medium == 0 {
-                    false
-                } else {
-                    self.medium -= 1;
-                    true
-                }
+        }
+        match strategy {
+            StrategyProfile::MultiQrHeavy => {
+                max_regions = max_regions.max(32);
+                per_region_top_k = per_region_top_k.max(16);
+                per_region_attempt_cap = per_region_attempt_cap.max(48);
             }
-            ConfidenceLane::Low => {
-                if self.low == 0 {
-                    false
-                } else {
-                    self.low -= 1;
-                    true
-                }
+            StrategyProfile::HighVersionPrecision | StrategyProfile::HighDensity => {
+                per_region_attempt_cap = per_region_attempt_cap.min(2);
+            }
+            StrategyProfile::LowContrastRecovery => {
+                per_region_top_k = per_region_top_k.min(3);
+            }
+            StrategyProfile::RotationHeavy
+            | StrategyProfile::FastSingle
+            | StrategyProfile::Synthetic => {}
+        }
+
+        let (high_frac, medium_frac) = budget_override
+            .map(|b| (b.lane_high_frac, b.lane_medium_frac))
+            .unwrap_or((0.5, 0.3));
+        let lanes = lane_budget_from_attempts(attempts, strategy, high_frac, medium_frac);
+        let budget = Self {
+            stages: [
+                [Quota::new(1), Quota::new(1)],
+                [Quota::new(transforms - 1), Quota::new(attempts - 1)],
+            ],
+            lanes: lanes.map(Quota::new),
+            max_regions,
+            per_region_top_k: per_region_top_k.min(top_k),
+            per_region_attempt_cap,
+        };
+        budget.check();
+        budget
+    }
+
+    /// Whether `stage` can build another transform and make another attempt.
+    fn has_room(&self, stage: DecodeStage) -> bool {
+        self.stages[stage as usize]
+            .iter()
+            .all(|quota| quota.remaining() > 0)
+    }
+
+    /// Spend one transform and attempt of `stage` from `lane`; `false`, with
+    /// nothing spent, when the lane is used up. Call after [`Self::has_room`].
+    fn take(&mut self, stage: DecodeStage, lane: ConfidenceLane) -> bool {
+        debug_assert!(self.has_room(stage));
+        if !self.lanes[lane.index()].take() {
+            return false;
+        }
+        for quota in &mut self.stages[stage as usize] {
+            quota.take();
+        }
+        self.check();
+        true
+    }
+
+    /// Attempts spent across stages.
+    fn attempts_used(&self) -> usize {
+        self.stages
+            .iter()
+            .map(|[_, attempts]| attempts.consumed)
+            .sum()
+    }
+
+    /// With every candidate in one region, let that region use the rest of
+    /// the attempts (multi-QR scenes).
+    fn widen_single_region(&mut self) {
+        let remaining = self.stages[DecodeStage::Regions as usize][1].remaining();
+        self.per_region_top_k = self.per_region_top_k.max(remaining.min(64));
+        self.per_region_attempt_cap = self.per_region_attempt_cap.max(remaining.min(128));
+    }
+
+    /// Add what was allocated to `tel`; the matching usage is counted by
+    /// `transforms_built`, `decode_attempts` and `budget_lane_*`.
+    fn record_allocation(&self, tel: &mut DetectionTelemetry) {
+        for [transforms, attempts] in &self.stages {
+            tel.budget_transforms_allocated += transforms.allocated;
+            tel.budget_attempts_allocated += attempts.allocated;
+        }
+        for (i, lane) in self.lanes.iter().enumerate() {
+            tel.budget_lane_allocation[i] += lane.allocated;
+        }
+    }
+
+    fn check(&self) {
+        if cfg!(debug_assertions) {
+            let quotas = self.stages.iter().flatten().chain(&self.lanes);
+            for quota in quotas {
+                debug_assert!(quota.consumed <= quota.allocated, "{quota:?}");
             }
+            let lane_total: usize = self.lanes.iter().map(|q| q.allocated).sum();
+            let attempts: usize = self.stages.iter().map(|[_, a]| a.allocated).sum();
+            debug_assert_eq!(lane_total, attempts);
         }
     }
 }
@@ -97,7 +452,9 @@ impl StrategyProfile {
             StrategyProfile::MultiQrHeavy => "multi_qr_heavy",
             StrategyProfile::RotationHeavy => "rotation_heavy",
             StrategyProfile::HighVersionPrecision => "high_version_precision",
+            StrategyProfile::HighDensity => "high_density",
             StrategyProfile::LowContrastRecovery => "low_contrast_recovery",
+            StrategyProfile::Synthetic => "synthetic",
         }
     }
 }
@@ -114,6 +471,29 @@ struct FastSignals {
     saturation_ratio: f32,
     skew_estimate_deg: f32,
     region_density_proxy: f32,
+    flatness: f32,
+    contrast_span: f32,
+    size_modes: SizeModes,
+}
+
+/// Distinct module sizes among a frame's finder candidates, the most
+/// populated first: how many scales of code the frame holds.
+#[derive(Clone, Copy, Debug, Default)]
+struct SizeModes {
+    sizes: [f32; MAX_SIZE_MODES],
+    len: usize,
+}
+
+impl SizeModes {
+    fn as_slice(&self) -> &[f32] {
+        &self.sizes[..self.len]
+    }
+
+    /// The mode closest to `module_size` in ratio, if there are any.
+    fn nearest(&self, module_size: f32) -> Option<usize> {
+        let distance = |mode: f32| (module_size.max(f32::MIN_POSITIVE) / mode).ln().abs();
+        (0..self.len).min_by(|&a, &b| distance(self.sizes[a]).total_cmp(&distance(self.sizes[b])))
+    }
 }
 
 fn order_finder_patterns(
@@ -178,7 +558,16 @@ fn order_finder_patterns(
 
     let module_size = (d_tr + d_bl) / 2.0 / (dim as f32 - 7.0);
     let module_ratio = module_size / avg_module;
-    if !(0.7..=1.3).contains(&module_ratio) {
+    // Flush finders (and inverted ones, found by the same scan) measure their
+    // outer rings against neighbouring dark content, so their module size
+    // estimates are noisier.
+    let ratio_window =
+        if crate::config::with_active(|c| c.tolerate_missing_quiet_zone || c.inverted_finders) {
+            0.6..=1.45
+        } else {
+            0.7..=1.3
+        };
+    if !ratio_window.contains(&module_ratio) {
         return None;
     }
 
@@ -200,6 +589,68 @@ fn estimate_dimension_from_distance(distance: f32, module_size: f32) -> Option<u
     Some(17 + 4 * version as usize)
 }
 
+/// Finder patterns rescaled to square pixels for `pixel_aspect_ratio`.
+/// Detectors disagree on which runs a module size comes from, so each
+/// finder is re-measured along both axes; when that fails its size is
+/// taken as the mean of the two runs.
+fn square_pixel_patterns<'a>(
+    binary: &BitMatrix,
+    patterns: &'a [FinderPattern],
+    aspect: f32,
+) -> Cow<'a, [FinderPattern]> {
+    if aspect == 1.0 {
+        return Cow::Borrowed(patterns);
+    }
+    Cow::Owned(
+        patterns
+            .iter()
+            .map(|p| {
+                let module_size = match FinderDetector::axis_module_sizes(binary, &p.center) {
+                    Some((unit_h, unit_v)) => (unit_h * aspect + unit_v) / 2.0,
+                    None => p.module_size * 2.0 * aspect / (aspect + 1.0),
+                };
+                FinderPattern {
+                    center: Point::new(p.center.x * aspect, p.center.y),
+                    module_size,
+                    quality: p.quality,
+                    inverted: p.inverted,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Scale each pattern's cross-check quality by the sharpness of its outer
+/// edge in `gray`, so blurred or ringing finders count for less when groups
+/// are scored. Patterns whose edge cannot be measured keep their quality.
+fn sharpness_weighted_patterns(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    patterns: &[FinderPattern],
+    aspect: f32,
+) -> Vec<FinderPattern> {
+    patterns
+        .iter()
+        .map(|p| {
+            let in_image = FinderPattern {
+                center: image_point(p.center, aspect),
+                ..p.clone()
+            };
+            let sharpness = in_image.edge_sharpness(gray, width, height).unwrap_or(1.0);
+            FinderPattern {
+                quality: p.quality * (0.5 + 0.5 * sharpness),
+                ..p.clone()
+            }
+        })
+        .collect()
+}
+
+/// Map a square-pixel point back to image coordinates.
+fn image_point(p: Point, aspect: f32) -> Point {
+    Point::new(p.x / aspect, p.y)
+}
+
 /// Simplified finder pattern grouping with relaxed constraints.
 pub(crate) fn group_finder_patterns(patterns: &[FinderPattern]) -> Vec<Vec<usize>> {
     if patterns.len() < 3 {
@@ -260,6 +711,12 @@ pub(crate) fn group_finder_patterns(patterns: &[FinderPattern]) -> Vec<Vec<usize
 
 fn build_groups(patterns: &[FinderPattern], indices: &[usize]) -> Vec<Vec<usize>> {
     let mut groups = Vec::new();
+    let max_size_ratio =
+        if crate::config::with_active(|c| c.tolerate_missing_quiet_zone || c.inverted_finders) {
+            3.0
+        } else {
+            2.5
+        };
 
     for idx_i in 0..indices.len() {
         let i = indices[idx_i];
@@ -275,7 +732,7 @@ fn build_groups(patterns: &[FinderPattern], indices: &[usize]) -> Vec<Vec<usize>
                 let max_size = sizes.iter().fold(0.0f32, |a, &b| a.max(b));
                 // Relaxed from 2.0 to 2.5 for perspective-distorted QR codes
                 let size_ratio = max_size / min_size;
-                if size_ratio > 2.5 {
+                if size_ratio > max_size_ratio {
                     continue;
                 }
 
@@ -547,25 +1004,37 @@ fn module_size_agreement(patterns: &[FinderPattern], group: &[usize]) -> f32 {
     (1.0 - cv.clamp(0.0, 1.0)).clamp(0.0, 1.0)
 }
 
+/// Pixels a straight scan from `start` to `end` visits: one step per pixel
+/// of length, both ends included, each rounded to the nearest pixel and
+/// `None` where the line leaves the `width` x `height` frame.
+pub(crate) fn line_pixels(
+    start: &Point,
+    end: &Point,
+    width: usize,
+    height: usize,
+) -> impl Iterator<Item = Option<(usize, usize)>> {
+    let (sx, sy) = (start.x, start.y);
+    let dx = end.x - sx;
+    let dy = end.y - sy;
+    let steps = ((dx * dx + dy * dy).sqrt().round() as usize).max(1);
+    (0..=steps).map(move |i| {
+        let t = i as f32 / steps as f32;
+        let x = (sx + dx * t).round() as isize;
+        let y = (sy + dy * t).round() as isize;
+        (x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height)
+            .then_some((x as usize, y as usize))
+    })
+}
+
 fn stripe_alternation_agreement(binary: &BitMatrix, start: &Point, end: &Point) -> f32 {
-    let dx = end.x - start.x;
-    let dy = end.y - start.y;
-    let distance = (dx * dx + dy * dy).sqrt();
-    if distance < 6.0 {
+    if start.distance(end) < 6.0 {
         return 0.0;
     }
-    let steps = distance.round() as usize;
     let mut prev: Option<bool> = None;
     let mut transitions = 0usize;
     let mut valid_samples = 0usize;
-    for i in 0..=steps {
-        let t = i as f32 / steps as f32;
-        let x = (start.x + dx * t).round() as isize;
-        let y = (start.y + dy * t).round() as isize;
-        if x < 0 || y < 0 || x as usize >= binary.width() || y as usize >= binary.height() {
-            continue;
-        }
-        let bit = binary.get(x as usize, y as usize);
+    for (x, y) in line_pixels(start, end, binary.width(), binary.height()).flatten() {
+        let bit = binary.get(x, y);
         if let Some(p) = prev {
             if p != bit {
                 transitions += 1;
@@ -588,6 +1057,11 @@ fn timing_line_agreement(binary: &BitMatrix, tl: &Point, tr: &Point, bl: &Point)
 }
 
 fn global_saturation_ratio(gray: &[u8]) -> f32 {
+    if let Some((address, len, ratio)) = SATURATION_RATIO.with(Cell::get)
+        && (gray.as_ptr() as usize, gray.len()) == (address, len)
+    {
+        return ratio;
+    }
     if gray.is_empty() {
         return 0.0;
     }
@@ -631,12 +1105,8 @@ fn line_saturation_coverage(
     }
 }
 
-#[allow(clippy::too_many_arguments)]
 fn geometry_rerank_score(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     saturation_mask_enabled: bool,
     patterns: &[FinderPattern],
     group: &[usize],
@@ -644,6 +1114,12 @@ fn geometry_rerank_score(
     tr: &Point,
     bl: &Point,
 ) -> (f32, f32) {
+    let Planes {
+        binary,
+        gray,
+        width,
+        height,
+    } = planes;
     let timing = timing_line_agreement(binary, tl, tr, bl);
     let saturation_coverage = if saturation_mask_enabled {
         let h = line_saturation_coverage(gray, width, height, tl, tr);
@@ -659,25 +1135,47 @@ fn geometry_rerank_score(
     };
     let module_agreement = module_size_agreement(patterns, group);
     let right_angle = 1.0 - right_angle_residual(patterns, group);
+    let finder_quality = group.iter().map(|&i| patterns[i].quality).sum::<f32>() / 3.0;
     (
-        (0.40 * timing_adjusted + 0.35 * module_agreement + 0.25 * right_angle).clamp(0.0, 1.0),
+        (0.36 * timing_adjusted
+            + 0.32 * module_agreement
+            + 0.22 * right_angle
+            + 0.10 * finder_quality)
+            .clamp(0.0, 1.0),
         saturation_coverage,
     )
 }
 
 fn rank_groups(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     saturation_mask_enabled: bool,
     patterns: &[FinderPattern],
-    raw_groups: Vec<Vec<usize>>,
+    aspect: f32,
+    mut raw_groups: Vec<Vec<usize>>,
 ) -> (Vec<RankedGroupCandidate>, usize) {
+    let Planes {
+        gray,
+        width,
+        height,
+        ..
+    } = planes;
+    let max_groups = crate::decoder::config::pathology_max_groups();
+    if max_groups > 0 && raw_groups.len() > max_groups {
+        crate::decoder::qr_decoder::record_pathology_guard_trip();
+        raw_groups = best_raw_groups(patterns, raw_groups, max_groups);
+    }
+    let budget_ms = crate::decoder::config::rank_time_budget_ms();
+    let deadline = (budget_ms > 0).then(|| Instant::now() + Duration::from_millis(budget_ms));
     let mut ranked = Vec::with_capacity(raw_groups.len());
     let mut rejected = 0usize;
 
-    for group in &raw_groups {
+    for (i, group) in raw_groups.iter().enumerate() {
+        if i % RANK_DEADLINE_STRIDE == RANK_DEADLINE_STRIDE - 1
+            && deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            crate::decoder::qr_decoder::record_pathology_guard_trip();
+            break;
+        }
         if group.len() < 3 {
             continue;
         }
@@ -685,11 +1183,18 @@ fn rank_groups(
         if let Some((tl, tr, bl, module_size)) =
             order_finder_patterns(&patterns[gi[0]], &patterns[gi[1]], &patterns[gi[2]])
         {
+            let inverted_finders = [tl, tr, bl].map(|corner| {
+                gi.iter()
+                    .any(|&i| patterns[i].inverted && patterns[i].center == corner)
+            });
+            let (tl, tr, bl) = (
+                image_point(tl, aspect),
+                image_point(tr, aspect),
+                image_point(bl, aspect),
+            );
+            let snr = finder_ring_snr(gray, width, height, &tl, &tr, &bl, module_size);
             let (rerank_score, saturation_coverage) = geometry_rerank_score(
-                binary,
-                gray,
-                width,
-                height,
+                planes,
                 saturation_mask_enabled,
                 patterns,
                 &gi,
@@ -699,14 +1204,16 @@ fn rank_groups(
             );
             ranked.push(RankedGroupCandidate {
                 group: gi,
-                tl,
-                tr,
-                bl,
+                tl: tl.into(),
+                tr: tr.into(),
+                bl: bl.into(),
                 module_size,
                 raw_score: group_raw_score(patterns, &gi),
                 rerank_score,
                 saturation_coverage,
                 geometry_confidence: geometry_confidence(patterns, &gi),
+                snr,
+                inverted_finders,
             });
         } else {
             rejected += 1;
@@ -728,6 +1235,10 @@ fn rank_groups(
         if conf_order != Ordering::Equal {
             return conf_order;
         }
+        let snr_order = b.snr.partial_cmp(&a.snr).unwrap_or(Ordering::Equal);
+        if snr_order != Ordering::Equal {
+            return snr_order;
+        }
         let raw_order = a
             .raw_score
             .partial_cmp(&b.raw_score)
@@ -740,6 +1251,96 @@ fn rank_groups(
     (ranked, rejected)
 }
 
+/// Signal-to-noise of a group's finder rings: the gap between the mean gray
+/// of their dark and their light tone over the pooled standard deviation
+/// within each. Each finder's 7x7-module footprint is sampled at half-module
+/// pitch along the group's own axes and split into the two tones by
+/// two-means rather than by ring geometry, since at two or three pixels a
+/// module a centre half a pixel off would put the ring samples on edges.
+/// Noise is floored at one gray level so a clean render reads high rather
+/// than infinite.
+fn finder_ring_snr(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    tl: &Point,
+    tr: &Point,
+    bl: &Point,
+    module_size: f32,
+) -> f32 {
+    let axis = |to: &Point| {
+        let (dx, dy) = (to.x - tl.x, to.y - tl.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        (len > 0.0).then(|| (dx / len * module_size * 0.5, dy / len * module_size * 0.5))
+    };
+    let (Some(u), Some(v)) = (axis(tr), axis(bl)) else {
+        return 0.0;
+    };
+    let mut samples = Vec::with_capacity(3 * 13 * 13);
+    for center in [tl, tr, bl] {
+        for j in -6i32..=6 {
+            for i in -6i32..=6 {
+                let x = (center.x + i as f32 * u.0 + j as f32 * v.0).round();
+                let y = (center.y + i as f32 * u.1 + j as f32 * v.1).round();
+                if x >= 0.0 && y >= 0.0 && x < width as f32 && y < height as f32 {
+                    samples.push(gray[y as usize * width + x as usize] as f32);
+                }
+            }
+        }
+    }
+    let (Some(&lo), Some(&hi)) = (
+        samples.iter().min_by(|a, b| a.total_cmp(b)),
+        samples.iter().max_by(|a, b| a.total_cmp(b)),
+    ) else {
+        return 0.0;
+    };
+    let mut threshold = (lo + hi) * 0.5;
+    // Count, sum and sum of squares of the dark and the light samples
+    let mut tones = [[0.0f32; 3]; 2];
+    for _ in 0..4 {
+        tones = [[0.0; 3]; 2];
+        for &g in &samples {
+            let tone = &mut tones[usize::from(g > threshold)];
+            tone[0] += 1.0;
+            tone[1] += g;
+            tone[2] += g * g;
+        }
+        if tones.iter().any(|tone| tone[0] == 0.0) {
+            return 0.0;
+        }
+        threshold = (tones[0][1] / tones[0][0] + tones[1][1] / tones[1][0]) * 0.5;
+    }
+    let [(dark_mean, dark_var), (light_mean, light_var)] = tones.map(|[n, sum, squares]| {
+        let mean = sum / n;
+        (mean, (squares / n - mean * mean).max(0.0))
+    });
+    let noise = ((dark_var + light_var) * 0.5).sqrt().max(1.0);
+    (light_mean - dark_mean) / noise
+}
+/// The `cap` groups with the lowest (best) raw geometry score, in that
+/// order, so a rank cut short by its time budget has ranked the likeliest.
+fn best_raw_groups(
+    patterns: &[FinderPattern],
+    groups: Vec<Vec<usize>>,
+    cap: usize,
+) -> Vec<Vec<usize>> {
+    let mut scored: Vec<(f32, Vec<usize>)> = groups
+        .into_iter()
+        .map(|group| (group_raw_score(patterns, &group), group))
+        .collect();
+    let by_score = |a: &(f32, Vec<usize>), b: &(f32, Vec<usize>)| {
+        a.0.partial_cmp(&b.0)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.1.cmp(&b.1))
+    };
+    if cap < scored.len() {
+        scored.select_nth_unstable_by(cap, by_score);
+        scored.truncate(cap);
+    }
+    scored.sort_by(by_score);
+    scored.into_iter().map(|(_, group)| group).collect()
+}
+
 fn decode_top_k_limit(total_candidates: usize) -> usize {
     if total_candidates == 0 {
         return 0;
@@ -773,6 +1374,10 @@ fn high_group_confidence() -> f32 {
     decode_f32_env("QR_GROUP_HIGH_CONF", HIGH_GROUP_CONFIDENCE, 0.3, 0.99)
 }
 
+fn low_snr_max() -> f32 {
+    decode_f32_env("QR_LOW_SNR_MAX", DEFAULT_LOW_SNR_MAX, 0.0, 100.0)
+}
+
 fn low_top_group_confidence() -> f32 {
     decode_f32_env("QR_GROUP_LOW_TOP_CONF", LOW_TOP_GROUP_CONFIDENCE, 0.2, 0.95)
 }
@@ -799,33 +1404,182 @@ fn decode_proxy_confidence(qr: &QRCode) -> f32 {
     (0.45 * bytes_component + 0.35 * content_component + 0.20 * ec_component).clamp(0.0, 1.0)
 }
 
+/// Decode one ranked candidate. The error carries the deepest stage any
+/// attempt reached, or `None` when a code was read but its calibrated
+/// confidence fell under `DetectorConfig::min_confidence`.
 fn decode_candidate(
     candidate: &RankedGroupCandidate,
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     allow_heavy_recovery: bool,
     blur_metric: f32,
-) -> Option<QRCode> {
+) -> Result<QRCode, Option<DecodeFailure>> {
+    let Planes {
+        gray,
+        width,
+        height,
+        ..
+    } = planes;
     // Skip heavy recovery for very blurry images - it's unlikely to succeed and wastes time
     let recovery_threshold = crate::decoder::config::blur_disable_recovery_threshold();
     let effective_heavy_recovery = allow_heavy_recovery && blur_metric >= recovery_threshold;
 
-    let mut qr = QrDecoder::decode_with_gray(
-        binary,
-        gray,
-        width,
-        height,
-        &candidate.tl,
-        &candidate.tr,
-        &candidate.bl,
-        candidate.module_size,
-        effective_heavy_recovery,
-    )?;
+    let region = (features::FULL_RECOVERY && crate::decoder::config::region_binarization())
+        .then(|| candidate_region(candidate, width, height))
+        .flatten();
+    let _inverted = crate::decoder::qr_decoder::scoped_inverted_finders(candidate.inverted_finders);
+    let key = memo_key(candidate);
+    let max_entries = crate::decoder::config::pathology_max_reentries();
+    let entries = DECODE_MEMO.with(|m| {
+        m.borrow_mut().as_mut().map_or(0, |memo| {
+            let count = memo.entries.entry(key).or_default();
+            *count += 1;
+            *count
+        })
+    });
+    if max_entries > 0 && entries > max_entries {
+        crate::decoder::qr_decoder::record_pathology_guard_trip();
+        return Err(None);
+    }
+    let mut qr = match region {
+        Some(roi) => {
+            let known = DECODE_MEMO.with(|m| {
+                m.borrow()
+                    .as_ref()
+                    .and_then(|memo| memo.hopeless.get(&key).copied())
+            });
+            if let Some((failure, heavy)) = known
+                && (heavy || !effective_heavy_recovery)
+            {
+                crate::decoder::qr_decoder::record_decode_memo_hit();
+                return Err(Some(failure));
+            }
+            let decoded =
+                decode_candidate_region(candidate, gray, width, roi, effective_heavy_recovery);
+            if let Err(failure @ DecodeFailure::ReedSolomon(_)) = decoded
+                && crate::decoder::config::decode_memo()
+            {
+                DECODE_MEMO.with(|m| {
+                    if let Some(memo) = m.borrow_mut().as_mut() {
+                        memo.hopeless
+                            .insert(key, (failure, effective_heavy_recovery));
+                    }
+                });
+            }
+            decoded?
+        }
+        None => QrDecoder::try_decode_planes(
+            planes,
+            &candidate.tl.into(),
+            &candidate.tr.into(),
+            &candidate.bl.into(),
+            candidate.module_size,
+            effective_heavy_recovery,
+        )?,
+    };
+    if verifying_payload() {
+        return Ok(qr);
+    }
     let proxy = decode_proxy_confidence(&qr);
-    qr.confidence = (0.75 * candidate.geometry_confidence + 0.25 * proxy).clamp(0.0, 1.0);
-    Some(qr)
+    qr.score = candidate_score(candidate, proxy);
+    if qr.score < crate::calibration::score_floor(crate::config::min_confidence()) {
+        return Err(None);
+    }
+    qr.confidence = crate::calibration::calibrated(qr.score);
+    Ok(qr)
+}
+
+/// Internal score of a code decoded from `candidate` with payload proxy
+/// confidence `proxy`.
+fn candidate_score(candidate: &RankedGroupCandidate, proxy: f32) -> f32 {
+    (0.75 * candidate.geometry_confidence + 0.25 * proxy).clamp(0.0, 1.0)
+}
+
+/// Whether a code from `candidate` could score `score_floor` or above: the
+/// payload proxy is at most 1.
+fn can_reach_confidence(candidate: &RankedGroupCandidate, score_floor: f32) -> bool {
+    candidate_score(candidate, 1.0) >= score_floor
+}
+
+/// Pixel bounds `(x0, y0, x1, y1)` of the symbol spanned by `candidate`,
+/// padded by the quiet zone plus slack for the bottom-right search.
+fn candidate_region(
+    candidate: &RankedGroupCandidate,
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let br = ImagePoint::new(
+        candidate.tr.x + candidate.bl.x - candidate.tl.x,
+        candidate.tr.y + candidate.bl.y - candidate.tl.y,
+    );
+    let corners = [candidate.tl, candidate.tr, candidate.bl, br];
+    let min_x = corners.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+    let min_y = corners.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+    let max_x = corners.iter().map(|p| p.x).fold(0.0f32, f32::max);
+    let max_y = corners.iter().map(|p| p.y).fold(0.0f32, f32::max);
+    // Finder centres sit 3.5 modules inside the symbol; the decoder probes
+    // the bottom-right corner up to 8 modules away from the estimate.
+    let pad = candidate.module_size.max(1.0) * 12.0;
+    let x0 = (min_x - pad).floor().max(0.0) as usize;
+    let y0 = (min_y - pad).floor().max(0.0) as usize;
+    let x1 = ((max_x + pad).ceil().max(0.0) as usize).min(width);
+    let y1 = ((max_y + pad).ceil().max(0.0) as usize).min(height);
+    (x0 < x1 && y0 < y1).then_some((x0, y0, x1, y1))
+}
+
+/// Decode `candidate` from its own region, adaptive-binarized with a window
+/// tuned to the group's module size in place of the frame-wide 31/21
+/// windows. Modules are thresholded from gray either way; the binary drives
+/// alignment search, grid refinement and the binary recovery grid, which
+/// need module edges thresholded against their local background.
+fn decode_candidate_region(
+    candidate: &RankedGroupCandidate,
+    gray: &[u8],
+    width: usize,
+    roi: (usize, usize, usize, usize),
+    allow_heavy_recovery: bool,
+) -> Result<QRCode, DecodeFailure> {
+    let window = crate::adaptive_window_from_module_size(candidate.module_size);
+    let shift = |p: &ImagePoint| Point::new(p.x - roi.0 as f32, p.y - roi.1 as f32);
+    let qr = crate::with_roi_binarized(gray, width, roi, window, false, |planes| {
+        QrDecoder::try_decode_planes(
+            planes,
+            &shift(&candidate.tl),
+            &shift(&candidate.tr),
+            &shift(&candidate.bl),
+            candidate.module_size,
+            allow_heavy_recovery,
+        )
+    })?;
+    let mut codes = [qr];
+    crate::offset_codes(&mut codes, roi);
+    let [qr] = codes;
+    Ok(qr)
+}
+
+/// The candidate's finder patterns in image coordinates, ordered top-left,
+/// top-right, bottom-left.
+fn candidate_finders(
+    candidate: &RankedGroupCandidate,
+    patterns: &[FinderPattern],
+    aspect: f32,
+) -> [FinderPattern; 3] {
+    [candidate.tl, candidate.tr, candidate.bl].map(|corner| {
+        let corner = Point::from(corner);
+        let p = candidate
+            .group
+            .iter()
+            .map(|&i| &patterns[i])
+            .min_by(|a, b| {
+                let da = image_point(a.center, aspect).distance(&corner);
+                let db = image_point(b.center, aspect).distance(&corner);
+                da.total_cmp(&db)
+            })
+            .expect("groups have three patterns");
+        FinderPattern {
+            center: image_point(p.center, aspect),
+            ..p.clone()
+        }
+    })
 }
 
 fn candidate_center(c: &RankedGroupCandidate) -> Point {
@@ -887,7 +1641,7 @@ fn payload_plausibility(content: &str) -> f32 {
 }
 
 fn acceptance_score(qr: &QRCode, geometry_conf: f32) -> f32 {
-    let rs_quality = qr.confidence.clamp(0.0, 1.0);
+    let rs_quality = qr.score.clamp(0.0, 1.0);
     let format_version_consistency = match qr.version {
         crate::models::Version::Model2(v) if v >= 7 => 0.95,
         _ => 0.85,
@@ -907,6 +1661,38 @@ fn acceptance_score(qr: &QRCode, geometry_conf: f32) -> f32 {
         .clamp(0.0, 1.0)
 }
 
+fn solved_region_acceptance() -> f32 {
+    decode_f32_env(
+        "QR_SOLVED_REGION_ACCEPTANCE",
+        SOLVED_REGION_ACCEPTANCE,
+        0.5,
+        1.0,
+    )
+}
+
+/// A decode this strong (maximum EC headroom, high acceptance) settles its
+/// region: other candidates there cannot produce a better read.
+fn solves_region(qr: &QRCode, acceptance: f32) -> bool {
+    qr.error_correction == ECLevel::H && acceptance >= solved_region_acceptance()
+}
+
+/// True when at least half of `candidate`'s box lies inside a solved region.
+fn overlaps_solved_region(
+    candidate: &RankedGroupCandidate,
+    solved: &[(f32, f32, f32, f32)],
+) -> bool {
+    let geom = candidate_bbox(candidate);
+    let area = (geom.2 - geom.0).max(0.0) * (geom.3 - geom.1).max(0.0);
+    if area <= 0.0 {
+        return false;
+    }
+    solved.iter().any(|s| {
+        let iw = (geom.2.min(s.2) - geom.0.max(s.0)).max(0.0);
+        let ih = (geom.3.min(s.3) - geom.1.max(s.1)).max(0.0);
+        iw * ih / area >= SOLVED_REGION_OVERLAP
+    })
+}
+
 fn dedupe_results(
     results: &mut Vec<QRCode>,
     accepted_geometries: &mut Vec<(f32, f32, f32, f32)>,
@@ -960,45 +1746,331 @@ fn cluster_regions(candidates: &[RankedGroupCandidate], max_regions: usize) -> V
     regions
 }
 
-fn estimate_blur_metric(gray: &[u8], width: usize, height: usize) -> f32 {
-    if width < 3 || height < 3 || gray.len() != width * height {
-        return 0.0;
+/// `regions` taken in turns from each of the frame's size modes, by the
+/// median module size of their candidates, keeping their order within a
+/// mode. The region attempts are then shared between the scales in the
+/// frame rather than spent on the biggest clusters of whichever scale has
+/// the most candidates.
+fn interleave_size_modes(
+    regions: Vec<RegionCluster>,
+    candidates: &[RankedGroupCandidate],
+    modes: &SizeModes,
+) -> Vec<RegionCluster> {
+    if modes.len < 2 {
+        return regions;
     }
-    let mut acc = 0.0f32;
-    let mut count = 0usize;
-    for y in (1..height - 1).step_by(2) {
-        for x in (1..width - 1).step_by(2) {
-            let idx = y * width + x;
-            let c = gray[idx] as f32;
-            let lap = (4.0 * c)
-                - gray[idx - 1] as f32
-                - gray[idx + 1] as f32
-                - gray[idx - width] as f32
-                - gray[idx + width] as f32;
-            acc += lap.abs();
-            count += 1;
+    let total = regions.len();
+    let mut by_mode: Vec<(usize, VecDeque<RegionCluster>)> = Vec::new();
+    for region in regions {
+        let mut sizes: Vec<f32> = region
+            .indices
+            .iter()
+            .map(|&i| candidates[i].module_size)
+            .collect();
+        sizes.sort_by(f32::total_cmp);
+        let mode = modes.nearest(sizes[sizes.len() / 2]).unwrap_or(0);
+        match by_mode.iter_mut().find(|(m, _)| *m == mode) {
+            Some((_, queue)) => queue.push_back(region),
+            None => by_mode.push((mode, VecDeque::from([region]))),
         }
     }
-    if count == 0 {
-        0.0
-    } else {
-        (acc / count as f32).clamp(0.0, 255.0)
+    let mut interleaved = Vec::with_capacity(total);
+    while interleaved.len() < total {
+        for (_, queue) in &mut by_mode {
+            interleaved.extend(queue.pop_front());
+        }
     }
+    interleaved
 }
 
-fn estimate_skew_deg(candidate: &RankedGroupCandidate) -> f32 {
-    let dx = candidate.tr.x - candidate.tl.x;
-    let dy = candidate.tr.y - candidate.tl.y;
+fn finder_sample_cap() -> usize {
+    decode_usize_env(
+        "QR_FINDER_SAMPLE_CAP",
+        DEFAULT_FINDER_SAMPLE_CAP,
+        0,
+        1 << 16,
+    )
+}
+
+/// Seed of the finder sample for a `width`x`height` frame, so the same
+/// frame always keeps the same candidates.
+fn finder_sample_seed(width: usize, height: usize) -> u64 {
+    splitmix64((width as u64) << 32 | height as u64)
+}
+
+fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Deterministic stratified sample of at most `cap` finder candidates.
+///
+/// The frame is cut into a [`FINDER_SAMPLE_GRID`] square grid. Each cell
+/// orders its candidates by quality in steps of 0.1, shuffled within a step
+/// by a draw from `seed` and the candidate's position, then the cells give
+/// up one candidate each in turn until `cap` are taken.
+/// A noisy texture can't crowd the rest of the frame out, and scan order
+/// favours no part of it. The sample keeps the input order.
+fn sample_finder_candidates(
+    patterns: &[FinderPattern],
+    width: usize,
+    height: usize,
+    cap: usize,
+    seed: u64,
+) -> Vec<FinderPattern> {
+    let cell_of = |p: &FinderPattern| {
+        let cx = (p.center.x.max(0.0) * FINDER_SAMPLE_GRID as f32 / width.max(1) as f32) as usize;
+        let cy = (p.center.y.max(0.0) * FINDER_SAMPLE_GRID as f32 / height.max(1) as f32) as usize;
+        cy.min(FINDER_SAMPLE_GRID - 1) * FINDER_SAMPLE_GRID + cx.min(FINDER_SAMPLE_GRID - 1)
+    };
+    let key = |p: &FinderPattern| {
+        let bits = u64::from(p.center.x.to_bits()) << 32 | u64::from(p.center.y.to_bits());
+        let u = (splitmix64(seed ^ bits) >> 11) as f64 / (1u64 << 53) as f64;
+        (f64::from(p.quality.clamp(0.0, 1.0)) * 10.0).floor() + u
+    };
+
+    let mut cells: Vec<Vec<(f64, usize)>> =
+        vec![Vec::new(); FINDER_SAMPLE_GRID * FINDER_SAMPLE_GRID];
+    for (idx, p) in patterns.iter().enumerate() {
+        cells[cell_of(p)].push((key(p), idx));
+    }
+    for cell in &mut cells {
+        // Drawn last first, so `pop` hands out the best key.
+        cell.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(Ordering::Equal)
+                .then(b.1.cmp(&a.1))
+        });
+    }
+
+    let mut keep = vec![false; patterns.len()];
+    let mut kept = 0;
+    while kept < cap.min(patterns.len()) {
+        for cell in &mut cells {
+            if kept == cap {
+                break;
+            }
+            if let Some((_, idx)) = cell.pop() {
+                keep[idx] = true;
+                kept += 1;
+            }
+        }
+    }
+    patterns
+        .iter()
+        .zip(keep)
+        .filter(|&(_, keep)| keep)
+        .map(|(p, _)| p.clone())
+        .collect()
+}
+
+/// Trim ranked candidates to `MAX_GROUP_CANDIDATES` without letting one dense
+/// cluster take every slot: regions contribute round-robin (each region's
+/// best, then each region's second best, ...) up to `QR_MAX_GROUPS_PER_REGION`
+/// apiece, then any spare slots go to the remaining candidates by rank.
+/// The result keeps rank order.
+fn spatially_diverse_candidates(ranked: &[RankedGroupCandidate]) -> Vec<RankedGroupCandidate> {
+    if ranked.len() <= MAX_GROUP_CANDIDATES {
+        return ranked.to_vec();
+    }
+    let per_region = decode_usize_env(
+        "QR_MAX_GROUPS_PER_REGION",
+        DEFAULT_MAX_GROUPS_PER_REGION,
+        1,
+        MAX_GROUP_CANDIDATES,
+    );
+    // Region indices are ascending, i.e. each region lists its best first.
+    let mut regions = cluster_regions(ranked, usize::MAX);
+    regions.sort_by_key(|r| r.indices[0]);
+
+    let mut keep = vec![false; ranked.len()];
+    let mut kept = 0usize;
+    'rounds: for round in 0..per_region {
+        let mut any = false;
+        for region in &regions {
+            if let Some(&idx) = region.indices.get(round) {
+                if kept >= MAX_GROUP_CANDIDATES {
+                    break 'rounds;
+                }
+                keep[idx] = true;
+                kept += 1;
+                any = true;
+            }
+        }
+        if !any {
+            break;
+        }
+    }
+    for slot in keep.iter_mut().filter(|k| !**k) {
+        if kept >= MAX_GROUP_CANDIDATES {
+            break;
+        }
+        *slot = true;
+        kept += 1;
+    }
+    ranked
+        .iter()
+        .zip(keep)
+        .filter_map(|(c, k)| k.then_some(*c))
+        .collect()
+}
+
+fn estimate_blur_metric(gray: &[u8], width: usize, height: usize) -> f32 {
+    if width < 3 || height < 3 || gray.len() != width * height {
+        return 0.0;
+    }
+    let mut acc = 0.0f32;
+    let mut count = 0usize;
+    for y in (1..height - 1).step_by(2) {
+        for x in (1..width - 1).step_by(2) {
+            let idx = y * width + x;
+            let c = gray[idx] as f32;
+            let lap = (4.0 * c)
+                - gray[idx - 1] as f32
+                - gray[idx + 1] as f32
+                - gray[idx - width] as f32
+                - gray[idx + width] as f32;
+            acc += lap.abs();
+            count += 1;
+        }
+    }
+    if count == 0 {
+        0.0
+    } else {
+        (acc / count as f32).clamp(0.0, 255.0)
+    }
+}
+
+/// Share of horizontally adjacent pixel pairs of one tone (both below or both
+/// at or above mid-gray, at least two pixels from an edge) that are exactly
+/// equal, the lower of the dark and the light tone's. Sensor noise keeps
+/// photographs well short of 1 even where they clip to white, while
+/// screenshots and renders are flat in both tones away from module edges.
+fn estimate_flatness(gray: &[u8], width: usize, height: usize) -> f32 {
+    if width < 2 || height == 0 || gray.len() != width * height {
+        return 0.0;
+    }
+    // [dark, light] x [equal, near]
+    let mut pairs = [[0usize; 2]; 2];
+    for y in (0..height).step_by(2) {
+        let row = &gray[y * width..(y + 1) * width];
+        // The middle pair of each six pixels, skipped when any step in the
+        // window is an edge: resampled edges ripple for a pixel or two.
+        for window in row.windows(6) {
+            if window.windows(2).any(|p| p[0].abs_diff(p[1]) > 6) {
+                continue;
+            }
+            let (a, b) = (window[2], window[3]);
+            if (a < 128) != (b < 128) {
+                continue;
+            }
+            pairs[(a >= 128) as usize][(a != b) as usize] += 1;
+        }
+    }
+    pairs
+        .iter()
+        .map(|&[equal, near]| {
+            if equal + near == 0 {
+                1.0
+            } else {
+                equal as f32 / (equal + near) as f32
+            }
+        })
+        .fold(1.0, f32::min)
+}
+
+/// Gray levels between the 2nd and the 98th percentile of every other pixel
+/// of every other row, so specular spots and deep shadows don't widen it.
+fn estimate_contrast_span(gray: &[u8], width: usize, height: usize) -> f32 {
+    percentile_contrast_span(gray, width, height, 2, 2, 98) as f32
+}
+
+/// Degrees the finder row leans off the nearest image axis.
+fn axis_lean_deg(skew_deg: f32) -> f32 {
+    let lean = skew_deg.rem_euclid(90.0);
+    lean.min(90.0 - lean)
+}
+
+fn estimate_skew_deg(candidate: &RankedGroupCandidate) -> f32 {
+    let dx = candidate.tr.x - candidate.tl.x;
+    let dy = candidate.tr.y - candidate.tl.y;
     dy.atan2(dx).to_degrees().abs()
 }
 
+/// Module-size modes of `patterns`, from a histogram over log module size
+/// so that every scale gets the same relative resolution.
+///
+/// Each bin is judged with its neighbours, since one symbol's finders
+/// straddle bin edges. Bins holding [`SIZE_MODE_MIN_FINDERS`] are taken
+/// busiest first, each at least [`SIZE_MODE_MIN_SEPARATION`] bins from
+/// those already taken and with the histogram dipping to half the smaller
+/// count between them, so one code seen in perspective stays one mode. A
+/// mode's size is the geometric mean of the finders around it.
+fn module_size_modes(patterns: &[FinderPattern]) -> SizeModes {
+    let mut counts = [0usize; SIZE_HISTOGRAM_BINS];
+    let mut log_sums = [0f32; SIZE_HISTOGRAM_BINS];
+    for pattern in patterns {
+        let bin = (pattern.module_size / SIZE_HISTOGRAM_MIN).log2() * SIZE_BINS_PER_OCTAVE;
+        if (0.0..SIZE_HISTOGRAM_BINS as f32).contains(&bin) {
+            counts[bin as usize] += 1;
+            log_sums[bin as usize] += pattern.module_size.ln();
+        }
+    }
+    let around = |i: usize| i.saturating_sub(1)..(i + 2).min(SIZE_HISTOGRAM_BINS);
+    let window: Vec<usize> = (0..SIZE_HISTOGRAM_BINS)
+        .map(|i| counts[around(i)].iter().sum())
+        .collect();
+
+    let mut peaks: Vec<usize> = (0..SIZE_HISTOGRAM_BINS)
+        .filter(|&i| window[i] >= SIZE_MODE_MIN_FINDERS)
+        .collect();
+    peaks.sort_by(|&a, &b| window[b].cmp(&window[a]).then(a.cmp(&b)));
+    let mut taken: Vec<usize> = Vec::new();
+    for peak in peaks {
+        if taken.len() == MAX_SIZE_MODES {
+            break;
+        }
+        let distinct = taken.iter().all(|&mode| {
+            let (lo, hi) = (mode.min(peak), mode.max(peak));
+            let valley = window[lo..=hi].iter().copied().min().unwrap_or(0);
+            hi - lo >= SIZE_MODE_MIN_SEPARATION && valley * 2 <= window[peak].min(window[mode])
+        });
+        if distinct {
+            taken.push(peak);
+        }
+    }
+
+    let mut modes = SizeModes::default();
+    for (slot, &bin) in modes.sizes.iter_mut().zip(&taken) {
+        let log_sum: f32 = log_sums[around(bin)].iter().sum();
+        *slot = (log_sum / window[bin] as f32).exp();
+    }
+    modes.len = taken.len();
+    modes
+}
+
+/// Whether confident groups sit at two or more of the frame's size modes:
+/// codes of different scales, however few candidates each has.
+fn spans_size_modes(candidates: &[RankedGroupCandidate], modes: &SizeModes) -> bool {
+    let mut seen = [false; MAX_SIZE_MODES];
+    for candidate in candidates.iter().filter(|c| c.geometry_confidence >= 0.76) {
+        if let Some(mode) = modes.nearest(candidate.module_size) {
+            seen[mode] = true;
+        }
+    }
+    seen.iter().filter(|&&s| s).count() >= 2
+}
+
 fn extract_fast_signals(
     gray: &[u8],
     width: usize,
     height: usize,
+    saturation_ratio: f32,
     candidates: &[RankedGroupCandidate],
+    patterns: &[FinderPattern],
 ) -> FastSignals {
-    let saturation_ratio = global_saturation_ratio(gray);
     let blur_metric = estimate_blur_metric(gray, width, height);
     let skew_estimate_deg = candidates.first().map(estimate_skew_deg).unwrap_or(0.0);
     let megapixels = ((width * height) as f32 / 1_000_000.0).max(0.1);
@@ -1008,6 +2080,9 @@ fn extract_fast_signals(
         saturation_ratio,
         skew_estimate_deg,
         region_density_proxy,
+        flatness: estimate_flatness(gray, width, height),
+        contrast_span: estimate_contrast_span(gray, width, height),
+        size_modes: module_size_modes(patterns),
     }
 }
 
@@ -1027,19 +2102,36 @@ fn select_strategy(candidates: &[RankedGroupCandidate], signals: FastSignals) ->
         0.0
     };
 
-    if signals.region_density_proxy >= 18.0 && candidates.len() >= 3 {
+    // A dense symbol's alignment patterns pass for finders and would
+    // otherwise read as a crowd of codes.
+    if features::HIGH_VERSION && estimated_modules(&candidates[0]) >= HIGH_DENSITY_MIN_MODULES {
+        return StrategyProfile::HighDensity;
+    }
+    if features::MULTI_QR && signals.region_density_proxy >= 18.0 && candidates.len() >= 3 {
         return StrategyProfile::MultiQrHeavy;
     }
+    // Checked before the contrast test: a screenshot's clipped black and
+    // white would read as saturation.
+    if signals.flatness >= SYNTHETIC_MIN_FLATNESS
+        && axis_lean_deg(signals.skew_estimate_deg) <= SYNTHETIC_MAX_LEAN_DEG
+    {
+        return StrategyProfile::Synthetic;
+    }
     if signals.skew_estimate_deg >= 16.0 {
         return StrategyProfile::RotationHeavy;
     }
-    if signals.saturation_ratio >= 0.08 || signals.blur_metric < 14.0 {
+    // Noisy finder rings mean noisy modules: spend the attempts where
+    // recovery is tried rather than on the high lane's plain reads.
+    if signals.saturation_ratio >= 0.08
+        || signals.blur_metric < 14.0
+        || candidates[0].snr < low_snr_max()
+    {
         return StrategyProfile::LowContrastRecovery;
     }
-    if high_conf >= 3 {
+    if features::MULTI_QR && (high_conf >= 3 || spans_size_modes(candidates, &signals.size_modes)) {
         return StrategyProfile::MultiQrHeavy;
     }
-    if top_module <= 2.0 {
+    if features::HIGH_VERSION && top_module <= 2.0 {
         return StrategyProfile::HighVersionPrecision;
     }
     if top_conf < 0.55 {
@@ -1051,6 +2143,160 @@ fn select_strategy(candidates: &[RankedGroupCandidate], signals: FastSignals) ->
     StrategyProfile::FastSingle
 }
 
+/// The benchmark condition the fast signals point to. Checked in
+/// [`ImageCondition::ALL`] order, which follows the router's: geometry
+/// first, since a dense or crowded frame also trips the photometric tests.
+fn classify_condition(candidates: &[RankedGroupCandidate], signals: FastSignals) -> ImageCondition {
+    let Some(top) = candidates.first() else {
+        return ImageCondition::Nominal;
+    };
+    let high_conf = candidates
+        .iter()
+        .filter(|c| c.geometry_confidence >= 0.76)
+        .count();
+    if estimated_modules(top) >= CONDITION_HIGH_VERSION_MIN_MODULES {
+        return ImageCondition::HighVersion;
+    }
+    if (signals.region_density_proxy >= 18.0 && candidates.len() >= 3)
+        || high_conf >= 3
+        || spans_size_modes(candidates, &signals.size_modes)
+    {
+        return ImageCondition::Multi;
+    }
+    let lean = axis_lean_deg(signals.skew_estimate_deg);
+    if signals.flatness >= SYNTHETIC_MIN_FLATNESS && lean <= SYNTHETIC_MAX_LEAN_DEG {
+        return ImageCondition::Synthetic;
+    }
+    if lean >= CONDITION_MIN_ROTATION_DEG {
+        return ImageCondition::Rotated;
+    }
+    if signals.saturation_ratio >= 0.08 {
+        return ImageCondition::Glare;
+    }
+    if signals.blur_metric < 14.0 {
+        return ImageCondition::Blurry;
+    }
+    if signals.contrast_span < CONDITION_MAX_LOW_CONTRAST_SPAN {
+        return ImageCondition::LowContrast;
+    }
+    ImageCondition::Nominal
+}
+
+/// Modules across the symbol, from the longer finder-to-finder side.
+fn estimated_modules(candidate: &RankedGroupCandidate) -> f32 {
+    let side = candidate
+        .tl
+        .distance(&candidate.tr)
+        .max(candidate.tl.distance(&candidate.bl));
+    side / candidate.module_size.max(0.5) + 7.0
+}
+
+/// Consecutive same-stage failures in one lane before the rest of the
+/// region's queue is reordered; 0 keeps the original ranking.
+fn failure_reorder_streak() -> usize {
+    decode_usize_env("QR_FAILURE_REORDER_STREAK", 2, 0, 16)
+}
+
+/// The current run of decode failures of one [`ConfidenceLane`]: the stage
+/// they all stopped at and the candidates that made it up.
+#[derive(Default)]
+struct LaneFailureRun {
+    stage: Option<mem::Discriminant<DecodeFailure>>,
+    failed: Vec<usize>,
+}
+
+/// Per-lane failure runs for the adaptive reordering policy: when the
+/// candidates of a lane keep failing at the same stage, marching further
+/// down the ranking mostly retries the same mistake, so the remaining queue
+/// is reordered to try unlike candidates first.
+struct FailureRuns {
+    streak: usize,
+    lanes: [LaneFailureRun; 3],
+}
+
+impl FailureRuns {
+    fn new(streak: usize) -> Self {
+        Self {
+            streak,
+            lanes: Default::default(),
+        }
+    }
+
+    /// Note how the attempt on candidate `idx` ended: `Some` stage for a
+    /// failed decode, `None` for a read (accepted or not), which ends the
+    /// run. Returns the run's candidates, and starts a fresh run, once
+    /// `streak` of them failed at the same stage in a row.
+    fn record(
+        &mut self,
+        lane: ConfidenceLane,
+        idx: usize,
+        failure: Option<DecodeFailure>,
+    ) -> Option<Vec<usize>> {
+        let run = &mut self.lanes[lane.index()];
+        let Some(failure) = failure.filter(|_| self.streak > 0) else {
+            *run = LaneFailureRun::default();
+            return None;
+        };
+        let stage = mem::discriminant(&failure);
+        if run.stage != Some(stage) {
+            run.stage = Some(stage);
+            run.failed.clear();
+        }
+        run.failed.push(idx);
+        (run.failed.len() >= self.streak).then(|| mem::take(run).failed)
+    }
+}
+
+/// Half-octave bucket of a candidate's module size.
+fn module_size_bucket(module_size: f32) -> i32 {
+    (module_size.max(0.25).log2() * 2.0).round() as i32
+}
+
+/// Whether `a` looks like `b` to the reordering policy: the same module
+/// size bucket, centred within half a symbol of it.
+fn resembles_candidate(a: &RankedGroupCandidate, b: &RankedGroupCandidate) -> bool {
+    let side = (b.tl.distance(&b.tr) + b.tl.distance(&b.bl)) * 0.5;
+    module_size_bucket(a.module_size) == module_size_bucket(b.module_size)
+        && candidate_center(a).distance(&candidate_center(b)) <= side * 0.5
+}
+
+/// Stable-move the candidates of `queue` unlike every `failed` one ahead of
+/// those resembling one. Returns the candidates moved forward.
+fn diversify_queue(
+    queue: &mut [usize],
+    candidates: &[RankedGroupCandidate],
+    failed: &[usize],
+) -> Vec<usize> {
+    let before = queue.to_vec();
+    queue.sort_by_key(|&idx| {
+        failed
+            .iter()
+            .any(|&f| resembles_candidate(&candidates[idx], &candidates[f]))
+    });
+    queue
+        .iter()
+        .enumerate()
+        .filter(|&(pos, idx)| {
+            before
+                .iter()
+                .position(|b| b == idx)
+                .is_some_and(|old| pos < old)
+        })
+        .map(|(_, &idx)| idx)
+        .collect()
+}
+
+/// Lane for `candidate`, and whether noisy finder rings (signal-to-noise
+/// under `low_snr`) sent it to the low lane whatever its geometry.
+fn candidate_lane(candidate: &RankedGroupCandidate, low_snr: f32) -> (ConfidenceLane, bool) {
+    let lane = confidence_lane(candidate.geometry_confidence);
+    if candidate.snr < low_snr && !matches!(lane, ConfidenceLane::Low) {
+        (ConfidenceLane::Low, true)
+    } else {
+        (lane, false)
+    }
+}
+
 fn confidence_lane(geometry_confidence: f32) -> ConfidenceLane {
     if geometry_confidence >= HIGH_CONFIDENCE_LANE_MIN {
         ConfidenceLane::High
@@ -1061,17 +2307,19 @@ fn confidence_lane(geometry_confidence: f32) -> ConfidenceLane {
     }
 }
 
-fn lane_budget_from_attempts(max_decode_attempts: usize, strategy: StrategyProfile) -> LaneBudget {
+/// `[high, medium, low]` lane shares of `max_decode_attempts`.
+fn lane_budget_from_attempts(
+    max_decode_attempts: usize,
+    strategy: StrategyProfile,
+    high_frac: f32,
+    medium_frac: f32,
+) -> [usize; 3] {
     if max_decode_attempts <= 1 {
-        return LaneBudget {
-            high: max_decode_attempts,
-            medium: 0,
-            low: 0,
-        };
+        return [max_decode_attempts, 0, 0];
     }
 
-    let mut high = ((max_decode_attempts as f32) * 0.5).floor() as usize;
-    let mut medium = ((max_decode_attempts as f32) * 0.3).floor() as usize;
+    let mut high = ((max_decode_attempts as f32) * high_frac).floor() as usize;
+    let mut medium = ((max_decode_attempts as f32) * medium_frac).floor() as usize;
     let reserved = high + medium;
     let mut low = max_decode_attempts.saturating_sub(reserved);
 
@@ -1091,7 +2339,7 @@ fn lane_budget_from_attempts(max_decode_attempts: usize, strategy: StrategyProfi
                 low += 1;
             }
         }
-        StrategyProfile::HighVersionPrecision => {
+        StrategyProfile::HighVersionPrecision | StrategyProfile::HighDensity => {
             if low > 0 {
                 low -= 1;
                 high += 1;
@@ -1106,7 +2354,9 @@ fn lane_budget_from_attempts(max_decode_attempts: usize, strategy: StrategyProfi
                 low += 1;
             }
         }
-        StrategyProfile::RotationHeavy | StrategyProfile::FastSingle => {}
+        StrategyProfile::RotationHeavy
+        | StrategyProfile::FastSingle
+        | StrategyProfile::Synthetic => {}
     }
 
     while high + medium + low > max_decode_attempts {
@@ -1122,44 +2372,79 @@ fn lane_budget_from_attempts(max_decode_attempts: usize, strategy: StrategyProfi
         high += 1;
     }
 
-    LaneBudget { high, medium, low }
+    [high, medium, low]
 }
 
-fn record_lane_attempt(telemetry: &mut Option<&mut DetectionTelemetry>, lane: ConfidenceLane) {
+fn record_lane_attempt(
+    telemetry: &mut Option<&mut DetectionTelemetry>,
+    lane: ConfidenceLane,
+    low_snr: bool,
+) {
     if let Some(tel) = telemetry.as_mut() {
         match lane {
             ConfidenceLane::High => tel.budget_lane_high += 1,
             ConfidenceLane::Medium => tel.budget_lane_medium += 1,
             ConfidenceLane::Low => tel.budget_lane_low += 1,
         }
+        if low_snr {
+            tel.budget_lane_low_snr += 1;
+        }
     }
 }
 
 fn decode_ranked_groups(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     finder_patterns: &[FinderPattern],
     attempt_limit: Option<usize>,
     mut telemetry: Option<&mut DetectionTelemetry>,
+    mut sink: Option<&mut CodeSink<'_>>,
 ) -> Vec<QRCode> {
-    let saturation_ratio = global_saturation_ratio(gray);
-    let saturation_mask_enabled = saturation_ratio >= 0.06;
-    let raw_groups = group_finder_patterns(finder_patterns);
-    let (ranked, rerank_rejected) = rank_groups(
+    let Planes {
         binary,
         gray,
         width,
         height,
+    } = planes;
+    let rank_started = Instant::now();
+    let sample_cap = finder_sample_cap();
+    let sampled = (sample_cap > 0 && finder_patterns.len() > sample_cap).then(|| {
+        let seed = finder_sample_seed(width, height);
+        let sample = sample_finder_candidates(finder_patterns, width, height, sample_cap, seed);
+        if let Some(tel) = telemetry.as_mut() {
+            tel.finder_sample_seed = seed;
+            tel.finder_sample_input += finder_patterns.len();
+            tel.finder_sample_kept += sample.len();
+        }
+        sample
+    });
+    let finder_patterns = sampled.as_deref().unwrap_or(finder_patterns);
+    // Hard backstop for when sampling is off or set above it.
+    let max_finders = crate::decoder::config::pathology_max_finders();
+    let capped = (max_finders > 0 && finder_patterns.len() > max_finders).then(|| {
+        crate::decoder::qr_decoder::record_pathology_guard_trip();
+        let seed = finder_sample_seed(width, height);
+        sample_finder_candidates(finder_patterns, width, height, max_finders, seed)
+    });
+    let finder_patterns = capped.as_deref().unwrap_or(finder_patterns);
+    let saturation_ratio = global_saturation_ratio(gray);
+    let saturation_mask_enabled = saturation_ratio >= 0.06;
+    let aspect = crate::config::pixel_aspect_ratio();
+    let square_patterns = square_pixel_patterns(binary, finder_patterns, aspect);
+    let square_patterns =
+        sharpness_weighted_patterns(gray, width, height, &square_patterns, aspect);
+    let raw_groups = group_finder_patterns(&square_patterns);
+    let (ranked, rerank_rejected) = rank_groups(
+        planes,
         saturation_mask_enabled,
-        finder_patterns,
+        &square_patterns,
+        aspect,
         raw_groups,
     );
-    let consider = ranked.len().min(MAX_GROUP_CANDIDATES);
-    let candidates = &ranked[..consider];
+    let kept = spatially_diverse_candidates(&ranked);
+    let candidates = &kept[..];
 
     if let Some(tel) = telemetry.as_mut() {
+        tel.time_group_rank_ms += crate::elapsed_ms(rank_started);
         tel.groups_found = candidates.len();
         tel.candidate_groups_scored = ranked.len();
         tel.decode_attempts = 0;
@@ -1187,52 +2472,69 @@ fn decode_ranked_groups(
     }
 
     let top_k = decode_top_k_limit(candidates.len());
-    let mut max_decode_attempts = decode_usize_env(
-        "QR_MAX_DECODE_ATTEMPTS",
-        DEFAULT_MAX_DECODE_ATTEMPTS,
-        1,
-        1024,
-    );
-    if let Some(limit) = attempt_limit {
-        max_decode_attempts = max_decode_attempts.min(limit);
-    }
+    let max_decode_attempts = DecodeBudget::attempt_quota(attempt_limit);
     if max_decode_attempts == 0 {
         if let Some(tel) = telemetry.as_mut() {
             tel.budget_skips += 1;
         }
         return Vec::new();
     }
-    let mut max_transforms = decode_usize_env("QR_MAX_TRANSFORMS", DEFAULT_MAX_TRANSFORMS, 1, 512)
-        .min(max_decode_attempts.max(1));
     let high_group_conf = high_group_confidence();
     let low_top_group_conf = low_top_group_confidence();
     let single_qr_floor = single_qr_confidence_floor();
+    let low_snr = low_snr_max();
     let top = candidates[0];
-    let fast_signals = extract_fast_signals(gray, width, height, candidates);
+    let fast_signals = extract_fast_signals(
+        gray,
+        width,
+        height,
+        saturation_ratio,
+        candidates,
+        &square_patterns,
+    );
     let strategy = select_strategy(candidates, fast_signals);
-    if matches!(strategy, StrategyProfile::MultiQrHeavy) {
-        let base_regions = decode_usize_env("QR_MAX_REGIONS", DEFAULT_MAX_REGIONS, 1, 64);
-        let mut base_top_k = decode_usize_env(
-            "QR_PER_REGION_TOP_K",
-            DEFAULT_PER_REGION_TOP_K,
-            1,
-            MAX_DECODE_TOP_K,
-        );
-        base_top_k = base_top_k.max(16);
-        let scaled_budget = (base_regions * base_top_k * 2).min(512);
-        // Multi-QR images require substantially larger attempt budgets.
-        max_decode_attempts = max_decode_attempts.max(scaled_budget);
-        // Keep transform and decode budgets aligned for dense scenes.
-        max_transforms = max_transforms.max(max_decode_attempts).min(512);
-    }
+    // Small modules need samples taken along their projected footprint and
+    // a grid fitted to every timing/alignment feature, not just the corners.
+    let _precision = matches!(
+        strategy,
+        StrategyProfile::HighVersionPrecision | StrategyProfile::HighDensity
+    )
+    .then(|| {
+        (
+            scoped_grid_sampling(GridSampling::AreaWeighted),
+            scoped_grid_refinement(true),
+        )
+    });
+    // Past ~100 modules sampling error adds up across the symbol: pin the
+    // version, fit the full alignment grid and gate on how well it fits.
+    let _high_density =
+        matches!(strategy, StrategyProfile::HighDensity).then(|| scoped_high_density(true));
+    // Rendered images resampled by a fractional factor halo every module
+    // edge: try a pitch-snapped, one-pixel-per-module read first, then
+    // integrate each module's footprint so anti-aliased edge pixels count
+    // by their coverage rather than tipping a small kernel.
+    let _synthetic = matches!(strategy, StrategyProfile::Synthetic).then(|| {
+        (
+            scoped_synthetic(true),
+            scoped_grid_sampling(GridSampling::Coverage),
+        )
+    });
+    let mut budget = DecodeBudget::new(max_decode_attempts, strategy, top_k);
     if let Some(tel) = telemetry.as_mut() {
+        budget.record_allocation(tel);
         tel.strategy_profile = strategy.as_str().to_string();
         tel.router_blur_metric = fast_signals.blur_metric;
         tel.router_saturation_ratio = fast_signals.saturation_ratio;
         tel.router_skew_estimate_deg = fast_signals.skew_estimate_deg;
         tel.router_region_density_proxy = fast_signals.region_density_proxy;
+        tel.router_flatness = fast_signals.flatness;
+        tel.router_contrast_span = fast_signals.contrast_span;
+        tel.router_top_snr = top.snr;
+        tel.router_module_size_modes = fast_signals.size_modes.as_slice().to_vec();
+        tel.image_condition = classify_condition(candidates, fast_signals)
+            .name()
+            .to_string();
     }
-    let mut lane_budget = lane_budget_from_attempts(max_decode_attempts, strategy);
     let heavy_recovery_top_n = decode_usize_env("QR_HEAVY_RECOVERY_TOP_N", 2, 0, 16);
     let mut should_expand = candidates
         .iter()
@@ -1245,42 +2547,66 @@ fn decode_ranked_groups(
         should_expand = true;
     }
 
-    let mut used_transforms = 0usize;
-    let mut used_attempts = 0usize;
     let mut results = Vec::new();
     let dedupe_by_payload = !matches!(strategy, StrategyProfile::MultiQrHeavy);
     let mut accepted_payloads: HashSet<String> = HashSet::new();
     let mut accepted_geometries: Vec<(f32, f32, f32, f32)> = Vec::new();
+    let skip_solved_regions = !crate::config::with_active(|c| c.decode_solved_regions);
+    let expected_payload = EXPECTED_PAYLOAD.with(|e| e.borrow().clone());
+    // A readable code with another payload: returned when nothing matches so
+    // the caller's fallback passes don't keep searching a frame already read
+    let mut mismatched: Option<QRCode> = None;
+    let mut solved_regions: Vec<(f32, f32, f32, f32)> = Vec::new();
+    let score_floor = crate::calibration::score_floor(crate::config::min_confidence());
+    let mut failure_runs = FailureRuns::new(failure_reorder_streak());
+    // A run completed by the top candidate, applied to the first region
+    let mut steer_away: Option<Vec<usize>> = None;
+    // Candidates a reorder moved forward, to credit the decodes they make
+    let mut promoted: HashSet<usize> = HashSet::new();
 
     let first = top;
-    if used_transforms < max_transforms && used_attempts < max_decode_attempts {
+    if !can_reach_confidence(&first, score_floor) {
+        if let Some(tel) = telemetry.as_mut() {
+            tel.min_confidence_skips += 1;
+        }
+        // Candidates further down may still clear the floor.
+        should_expand = true;
+    } else if budget.has_room(DecodeStage::Top1) {
         if let Some(tel) = telemetry.as_mut() {
             tel.rerank_top1_attempts += 1;
         }
-        let lane = confidence_lane(first.geometry_confidence);
-        if !lane_budget.consume(lane) {
+        let (lane, noisy) = candidate_lane(&first, low_snr);
+        if !budget.take(DecodeStage::Top1, lane) {
             if let Some(tel) = telemetry.as_mut() {
                 tel.budget_skips += 1;
             }
             return results;
         }
-        record_lane_attempt(&mut telemetry, lane);
+        record_lane_attempt(&mut telemetry, lane, noisy);
         if let Some(tel) = telemetry.as_mut() {
             tel.transforms_built += 1;
+            tel.budget_transforms_used += 1;
             tel.decode_attempts += 1;
         }
-        used_transforms += 1;
-        used_attempts += 1;
-        let allow_heavy = used_attempts <= heavy_recovery_top_n;
-        if let Some(qr) = decode_candidate(
-            &first,
-            binary,
-            gray,
-            width,
-            height,
-            allow_heavy,
-            fast_signals.blur_metric,
-        ) {
+        note_attempt_used();
+        let allow_heavy = budget.attempts_used() <= heavy_recovery_top_n;
+        let decode_started = Instant::now();
+        let outcome = decode_candidate(&first, planes, allow_heavy, fast_signals.blur_metric);
+        if let Some(tel) = telemetry.as_mut() {
+            tel.time_decode_ms += crate::elapsed_ms(decode_started);
+        }
+        steer_away = failure_runs.record(lane, 0, outcome.as_ref().err().copied().flatten());
+        let decoded = outcome.ok();
+        if let Some(expected) = expected_payload.as_deref() {
+            if let Some(qr) = decoded {
+                if qr.data == expected {
+                    return vec![qr];
+                }
+                mismatched = Some(qr);
+            }
+            // Look at the other candidates before giving up
+            should_expand = true;
+        } else if let Some(qr) = decoded {
             let acceptance = acceptance_score(&qr, first.geometry_confidence);
             let floor = decode_acceptance_floor();
             if acceptance >= floor {
@@ -1288,21 +2614,35 @@ fn decode_ranked_groups(
                     tel.rs_decode_ok += 1;
                     tel.payload_decoded += 1;
                 }
-                if qr.confidence < single_qr_floor {
+                if qr.score < single_qr_floor {
                     should_expand = true;
                 }
                 if dedupe_by_payload {
                     accepted_payloads.insert(qr.content.clone());
                 }
                 accepted_geometries.push(candidate_bbox(&first));
+                if let Some(tel) = telemetry.as_mut() {
+                    tel.decoded_finders
+                        .push(candidate_finders(&first, &square_patterns, aspect));
+                }
+                if skip_solved_regions && solves_region(&qr, acceptance) {
+                    solved_regions.push(candidate_bbox(&first));
+                }
                 results.push(qr);
+                if let Some(sink) = sink.as_mut()
+                    && sink(&results[results.len() - 1]).is_break()
+                {
+                    return results;
+                }
                 if let Some(tel) = telemetry.as_mut() {
                     tel.rerank_top1_successes += 1;
                     if saturation_mask_enabled && first.saturation_coverage > 0.08 {
                         tel.saturation_mask_decode_successes += 1;
                     }
                 }
-                if !should_expand && !matches!(strategy, StrategyProfile::MultiQrHeavy) {
+                if !features::MULTI_QR
+                    || (!should_expand && !matches!(strategy, StrategyProfile::MultiQrHeavy))
+                {
                     return results;
                 }
             } else if let Some(tel) = telemetry.as_mut() {
@@ -1320,31 +2660,11 @@ fn decode_ranked_groups(
         return results;
     }
 
-    let mut max_regions = decode_usize_env("QR_MAX_REGIONS", DEFAULT_MAX_REGIONS, 1, 64);
-    let mut per_region_top_k = decode_usize_env(
-        "QR_PER_REGION_TOP_K",
-        DEFAULT_PER_REGION_TOP_K,
-        1,
-        MAX_DECODE_TOP_K,
+    let regions = interleave_size_modes(
+        cluster_regions(candidates, budget.max_regions),
+        candidates,
+        &fast_signals.size_modes,
     );
-    let mut per_region_attempt_cap = decode_usize_env("QR_PER_REGION_ATTEMPTS", 3, 1, 64);
-    match strategy {
-        StrategyProfile::MultiQrHeavy => {
-            max_regions = max_regions.max(32);
-            per_region_top_k = per_region_top_k.max(16);
-            per_region_attempt_cap = per_region_attempt_cap.max(48);
-        }
-        StrategyProfile::HighVersionPrecision => {
-            per_region_attempt_cap = per_region_attempt_cap.min(2);
-        }
-        StrategyProfile::LowContrastRecovery => {
-            per_region_top_k = per_region_top_k.min(3);
-        }
-        StrategyProfile::RotationHeavy | StrategyProfile::FastSingle => {}
-    }
-    per_region_top_k = per_region_top_k.min(top_k);
-
-    let regions = cluster_regions(candidates, max_regions);
     let multi_region = regions.len() > 1;
     if let Some(tel) = telemetry.as_mut() {
         tel.router_multi_region = multi_region;
@@ -1352,49 +2672,87 @@ fn decode_ranked_groups(
     }
 
     if matches!(strategy, StrategyProfile::MultiQrHeavy) && regions.len() <= 1 {
-        let remaining_attempts = max_decode_attempts.saturating_sub(used_attempts);
-        per_region_top_k = per_region_top_k.max(remaining_attempts.min(64));
-        per_region_attempt_cap = per_region_attempt_cap.max(remaining_attempts.min(128));
+        budget.widen_single_region();
     }
 
     let relaxed_floor = decode_relaxed_acceptance_floor();
     for region in regions {
-        for (region_attempts, &idx) in region.indices.iter().take(per_region_top_k).enumerate() {
-            if used_transforms >= max_transforms || used_attempts >= max_decode_attempts {
+        let per_region_top_k = budget.per_region_top_k;
+        let mut queue = region.indices;
+        if let Some(failed) = steer_away.take() {
+            let moved = diversify_queue(&mut queue, candidates, &failed);
+            if !moved.is_empty() {
+                if let Some(tel) = telemetry.as_mut() {
+                    tel.failure_reorders += 1;
+                }
+                promoted.extend(moved);
+            }
+        }
+        for region_attempts in 0..queue.len().min(per_region_top_k) {
+            let idx = queue[region_attempts];
+            if !budget.has_room(DecodeStage::Regions) {
                 if let Some(tel) = telemetry.as_mut() {
                     tel.budget_skips += 1;
                 }
                 break;
             }
-            if region_attempts >= per_region_attempt_cap {
+            if region_attempts >= budget.per_region_attempt_cap {
                 break;
             }
             let candidate = &candidates[idx];
-            let lane = confidence_lane(candidate.geometry_confidence);
-            if !lane_budget.consume(lane) {
+            if overlaps_solved_region(candidate, &solved_regions) {
+                if let Some(tel) = telemetry.as_mut() {
+                    tel.solved_region_skips += 1;
+                }
+                continue;
+            }
+            if !can_reach_confidence(candidate, score_floor) {
+                if let Some(tel) = telemetry.as_mut() {
+                    tel.min_confidence_skips += 1;
+                }
+                continue;
+            }
+            let (lane, noisy) = candidate_lane(candidate, low_snr);
+            if !budget.take(DecodeStage::Regions, lane) {
                 if let Some(tel) = telemetry.as_mut() {
                     tel.budget_skips += 1;
                 }
                 continue;
             }
-            record_lane_attempt(&mut telemetry, lane);
+            record_lane_attempt(&mut telemetry, lane, noisy);
             if let Some(tel) = telemetry.as_mut() {
                 tel.transforms_built += 1;
+                tel.budget_transforms_used += 1;
                 tel.decode_attempts += 1;
             }
-            used_transforms += 1;
-            used_attempts += 1;
-
-            let allow_heavy = used_attempts <= heavy_recovery_top_n;
-            if let Some(qr) = decode_candidate(
-                candidate,
-                binary,
-                gray,
-                width,
-                height,
-                allow_heavy,
-                fast_signals.blur_metric,
-            ) {
+            note_attempt_used();
+
+            let allow_heavy = budget.attempts_used() <= heavy_recovery_top_n;
+            let decode_started = Instant::now();
+            let outcome =
+                decode_candidate(candidate, planes, allow_heavy, fast_signals.blur_metric);
+            if let Some(tel) = telemetry.as_mut() {
+                tel.time_decode_ms += crate::elapsed_ms(decode_started);
+            }
+            let failure = outcome.as_ref().err().copied().flatten();
+            if let Some(failed) = failure_runs.record(lane, idx, failure) {
+                let moved = diversify_queue(&mut queue[region_attempts + 1..], candidates, &failed);
+                if !moved.is_empty() {
+                    if let Some(tel) = telemetry.as_mut() {
+                        tel.failure_reorders += 1;
+                    }
+                    promoted.extend(moved);
+                }
+            }
+            let decoded = outcome.ok();
+            if let Some(qr) = decoded {
+                if let Some(expected) = expected_payload.as_deref() {
+                    if qr.data == expected {
+                        return vec![qr];
+                    }
+                    mismatched.get_or_insert(qr);
+                    continue;
+                }
                 if dedupe_by_payload && accepted_payloads.contains(&qr.content) {
                     continue;
                 }
@@ -1405,6 +2763,7 @@ fn decode_ranked_groups(
                     }
                     continue;
                 }
+                let solved = skip_solved_regions && solves_region(&qr, acceptance);
                 if dedupe_results(
                     &mut results,
                     &mut accepted_geometries,
@@ -1415,50 +2774,642 @@ fn decode_ranked_groups(
                     if dedupe_by_payload {
                         accepted_payloads.insert(qr.content);
                     }
+                    if solved {
+                        solved_regions.push(candidate_bbox(candidate));
+                    }
                     if let Some(tel) = telemetry.as_mut() {
+                        tel.decoded_finders.push(candidate_finders(
+                            candidate,
+                            &square_patterns,
+                            aspect,
+                        ));
                         tel.rs_decode_ok += 1;
                         tel.payload_decoded += 1;
                         tel.router_region_decodes += 1;
+                        if promoted.contains(&idx) {
+                            tel.failure_reorder_decodes += 1;
+                        }
                         if saturation_mask_enabled && candidate.saturation_coverage > 0.08 {
                             tel.saturation_mask_decode_successes += 1;
                         }
                     }
+                    if let Some(sink) = sink.as_mut()
+                        && sink(&results[results.len() - 1]).is_break()
+                    {
+                        return results;
+                    }
+                    if !features::MULTI_QR {
+                        return results;
+                    }
                 }
             }
         }
     }
 
+    if expected_payload.is_some() {
+        return mismatched.into_iter().collect();
+    }
     results
 }
 
-pub(crate) fn decode_groups(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+/// [`geometry_rerank_score`] of each finder triple given in image
+/// coordinates, or `None` where the three do not order into a symbol
+/// corner. Lets callers that guess a missing finder rank their guesses the
+/// same way [`decode_groups`] ranks detected ones.
+pub(crate) fn triple_rerank_scores(
+    planes: Planes<'_>,
+    triples: &[[FinderPattern; 3]],
+) -> Vec<Option<f32>> {
+    let Planes { binary, gray, .. } = planes;
+    let saturation_mask_enabled = global_saturation_ratio(gray) >= 0.06;
+    let aspect = crate::config::pixel_aspect_ratio();
+    triples
+        .iter()
+        .map(|triple| {
+            let square = square_pixel_patterns(binary, triple, aspect);
+            let (tl, tr, bl, _) = order_finder_patterns(&square[0], &square[1], &square[2])?;
+            let (score, _) = geometry_rerank_score(
+                planes,
+                saturation_mask_enabled,
+                &square,
+                &[0, 1, 2],
+                &image_point(tl, aspect),
+                &image_point(tr, aspect),
+                &image_point(bl, aspect),
+            );
+            Some(score)
+        })
+        .collect()
+}
+
+/// Ranked candidate geometry `(tl, tr, bl, module_size, rerank_score)` for diagnostics.
+pub(crate) fn ranked_candidate_geometry(
+    planes: Planes<'_>,
     finder_patterns: &[FinderPattern],
+) -> Vec<(Point, Point, Point, f32, f32)> {
+    let Planes {
+        binary,
+        gray,
+        width,
+        height,
+    } = planes;
+    let saturation_mask_enabled = global_saturation_ratio(gray) >= 0.06;
+    let aspect = crate::config::pixel_aspect_ratio();
+    let square_patterns = square_pixel_patterns(binary, finder_patterns, aspect);
+    let square_patterns =
+        sharpness_weighted_patterns(gray, width, height, &square_patterns, aspect);
+    let raw_groups = group_finder_patterns(&square_patterns);
+    let (ranked, _) = rank_groups(
+        planes,
+        saturation_mask_enabled,
+        &square_patterns,
+        aspect,
+        raw_groups,
+    );
+    spatially_diverse_candidates(&ranked)
+        .iter()
+        .map(|c| {
+            (
+                c.tl.into(),
+                c.tr.into(),
+                c.bl.into(),
+                c.module_size,
+                c.rerank_score,
+            )
+        })
+        .collect()
+}
+
+/// Format and version of every ranked finder group whose format
+/// information reads, without decoding any data.
+pub(crate) fn symbol_infos(
+    planes: Planes<'_>,
+    finder_patterns: &[FinderPattern],
+) -> Vec<SymbolInfo> {
+    ranked_candidate_geometry(planes, finder_patterns)
+        .into_iter()
+        .filter_map(|(tl, tr, bl, module_size, _)| {
+            QrDecoder::read_symbol_info(planes, &tl, &tr, &bl, module_size)
+        })
+        .collect()
+}
+
+pub(crate) fn decode_groups(planes: Planes<'_>, finder_patterns: &[FinderPattern]) -> Vec<QRCode> {
+    decode_ranked_groups(planes, finder_patterns, None, None, None)
+}
+
+/// [`decode_groups`], offering each accepted code to `sink` as soon as it
+/// decodes; a `Break` skips the remaining candidates.
+pub(crate) fn decode_groups_streaming(
+    planes: Planes<'_>,
+    finder_patterns: &[FinderPattern],
+    sink: &mut CodeSink<'_>,
 ) -> Vec<QRCode> {
-    decode_ranked_groups(binary, gray, width, height, finder_patterns, None, None)
+    decode_ranked_groups(planes, finder_patterns, None, None, Some(sink))
 }
 
 /// Like `decode_groups_with_telemetry` but enforces a hard decode-attempt cap.
 pub(crate) fn decode_groups_with_telemetry_limited(
-    binary: &BitMatrix,
-    gray: &[u8],
-    width: usize,
-    height: usize,
+    planes: Planes<'_>,
     finder_patterns: &[FinderPattern],
     max_attempts: usize,
 ) -> (Vec<QRCode>, DetectionTelemetry) {
     let mut tel = DetectionTelemetry::default();
     let results = decode_ranked_groups(
-        binary,
-        gray,
-        width,
-        height,
+        planes,
         finder_patterns,
         Some(max_attempts),
         Some(&mut tel),
+        None,
     );
     (results, tel)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate_at(x: f32, y: f32, rank: usize) -> RankedGroupCandidate {
+        RankedGroupCandidate {
+            group: [rank, rank, rank],
+            tl: ImagePoint::new(x, y),
+            tr: ImagePoint::new(x + 50.0, y),
+            bl: ImagePoint::new(x, y + 50.0),
+            module_size: 2.0,
+            raw_score: 0.0,
+            rerank_score: 1.0 - rank as f32 * 0.001,
+            saturation_coverage: 0.0,
+            geometry_confidence: 0.9,
+            snr: 20.0,
+            inverted_finders: [false; 3],
+        }
+    }
+
+    #[test]
+    fn candidate_region_pads_symbol_and_clamps_to_frame() {
+        // Finder centres 50px apart with 2px modules: 12 modules of padding.
+        let region = candidate_region(&candidate_at(100.0, 100.0, 0), 400, 400);
+        assert_eq!(region, Some((76, 76, 174, 174)));
+        let region = candidate_region(&candidate_at(10.0, 300.0, 0), 400, 340);
+        assert_eq!(region, Some((0, 276, 84, 340)));
+        assert_eq!(crate::adaptive_window_from_module_size(2.0), 31);
+        assert_eq!(crate::adaptive_window_from_module_size(10.0), 71);
+    }
+
+    #[test]
+    fn router_selects_high_density_past_a_hundred_modules() {
+        let signals = FastSignals {
+            blur_metric: 40.0,
+            ..FastSignals::default()
+        };
+        // 50px between finder centres at 2px modules: 32 modules across.
+        let small = candidate_at(100.0, 100.0, 0);
+        assert!(!matches!(
+            select_strategy(&[small], signals),
+            StrategyProfile::HighDensity
+        ));
+        // Version 35 (157 modules) at 1.5px modules.
+        let dense = RankedGroupCandidate {
+            tr: ImagePoint::new(100.0 + 150.0 * 1.5, 100.0),
+            bl: ImagePoint::new(100.0, 100.0 + 150.0 * 1.5),
+            module_size: 1.5,
+            ..small
+        };
+        assert_eq!(estimated_modules(&dense), 157.0);
+        assert_eq!(
+            select_strategy(&[dense], signals).as_str(),
+            if features::HIGH_VERSION {
+                "high_density"
+            } else {
+                "fast_single"
+            }
+        );
+    }
+
+    #[test]
+    fn module_size_modes_count_distinct_scales() {
+        let at_sizes = |sizes: &[f32]| -> Vec<FinderPattern> {
+            sizes
+                .iter()
+                .enumerate()
+                .map(|(i, &m)| FinderPattern::new(i as f32 * 40.0, 0.0, m))
+                .collect()
+        };
+        // Nine finders (three codes) near 2px and six (two codes) near 5px.
+        let mut sizes = vec![1.9, 2.0, 2.0, 2.05, 2.1, 1.95, 2.0, 2.0, 2.02];
+        sizes.extend([4.8, 5.0, 5.1, 5.0, 4.9, 5.2]);
+        let modes = module_size_modes(&at_sizes(&sizes));
+        assert_eq!(modes.len, 2);
+        assert!((modes.sizes[0] - 2.0).abs() < 0.05, "{modes:?}");
+        assert!((modes.sizes[1] - 5.0).abs() < 0.1, "{modes:?}");
+        assert_eq!(modes.nearest(4.0), Some(1));
+
+        // Sizes running evenly from 2 to 3px, as across one tilted frame,
+        // never dip between the ends: one scale.
+        let spread: Vec<f32> = (0..24).map(|i| 2.0 + i as f32 / 23.0).collect();
+        assert_eq!(module_size_modes(&at_sizes(&spread)).len, 1);
+        // Fewer finders than one symbol has make no mode.
+        assert_eq!(module_size_modes(&at_sizes(&[2.0, 2.0])).len, 0);
+    }
+
+    #[test]
+    fn size_modes_steer_router_and_region_order() {
+        let modes = SizeModes {
+            sizes: [3.0, 6.0, 0.0, 0.0],
+            len: 2,
+        };
+        let signals = FastSignals {
+            blur_metric: 40.0,
+            size_modes: modes,
+            ..FastSignals::default()
+        };
+        let small = RankedGroupCandidate {
+            module_size: 3.0,
+            ..candidate_at(100.0, 100.0, 0)
+        };
+        let large = RankedGroupCandidate {
+            module_size: 6.0,
+            ..candidate_at(300.0, 100.0, 1)
+        };
+        // Two confident groups are one code each only at different scales.
+        assert_eq!(
+            select_strategy(&[small, small], signals).as_str(),
+            "fast_single"
+        );
+        assert_eq!(
+            select_strategy(&[small, large], signals).as_str(),
+            if features::MULTI_QR {
+                "multi_qr_heavy"
+            } else {
+                "fast_single"
+            }
+        );
+        assert_eq!(
+            classify_condition(&[small, large], signals).name(),
+            ImageCondition::Multi.name()
+        );
+
+        let candidates = [small, candidate_at(100.0, 300.0, 2), large];
+        let regions = (0..3)
+            .map(|i| RegionCluster {
+                indices: vec![i],
+                center: Point::new(0.0, 0.0),
+            })
+            .collect();
+        let order: Vec<usize> = interleave_size_modes(regions, &candidates, &modes)
+            .iter()
+            .map(|r| r.indices[0])
+            .collect();
+        assert_eq!(order, [0, 2, 1]);
+    }
+
+    #[test]
+    fn router_selects_synthetic_for_flat_axis_aligned_renders() {
+        // Flat black and white bars against the same bars with +-2 noise.
+        let (w, h) = (64, 8);
+        let bars: Vec<u8> = (0..w * h)
+            .map(|i| if (i % w) / 16 % 2 == 0 { 0 } else { 255 })
+            .collect();
+        let noisy: Vec<u8> = bars
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                if i % 3 == 0 {
+                    v.saturating_add(2)
+                } else {
+                    v.saturating_sub(2)
+                }
+            })
+            .collect();
+        assert_eq!(estimate_flatness(&bars, w, h), 1.0);
+        assert!(estimate_flatness(&noisy, w, h) < 0.5);
+
+        let signals = FastSignals {
+            blur_metric: 40.0,
+            flatness: 1.0,
+            ..FastSignals::default()
+        };
+        let upright = candidate_at(100.0, 100.0, 0);
+        assert_eq!(select_strategy(&[upright], signals).as_str(), "synthetic");
+        let turned = FastSignals {
+            skew_estimate_deg: 90.5,
+            ..signals
+        };
+        assert_eq!(select_strategy(&[upright], turned).as_str(), "synthetic");
+        let tilted = FastSignals {
+            skew_estimate_deg: 5.0,
+            ..signals
+        };
+        assert_ne!(select_strategy(&[upright], tilted).as_str(), "synthetic");
+    }
+
+    #[test]
+    fn condition_classifier_files_images_under_benchmark_categories() {
+        // Dark modules at 40 on a 120 background: an 80-level span.
+        let (w, h) = (64, 8);
+        let dim: Vec<u8> = (0..w * h)
+            .map(|i| if (i % w) / 8 % 2 == 0 { 40 } else { 120 })
+            .collect();
+        assert_eq!(estimate_contrast_span(&dim, w, h), 80.0);
+
+        let nominal = FastSignals {
+            blur_metric: 40.0,
+            contrast_span: 200.0,
+            ..FastSignals::default()
+        };
+        let one = [candidate_at(100.0, 100.0, 0)];
+        let label = |candidates: &[RankedGroupCandidate], signals| {
+            classify_condition(candidates, signals).name()
+        };
+        assert_eq!(label(&one, nominal), "nominal");
+        assert_eq!(label(&[], nominal), "nominal");
+        let cases = [
+            (
+                FastSignals {
+                    skew_estimate_deg: 30.0,
+                    ..nominal
+                },
+                "rotations",
+            ),
+            (
+                FastSignals {
+                    skew_estimate_deg: 92.0,
+                    ..nominal
+                },
+                "nominal",
+            ),
+            (
+                FastSignals {
+                    saturation_ratio: 0.2,
+                    ..nominal
+                },
+                "glare",
+            ),
+            (
+                FastSignals {
+                    blur_metric: 5.0,
+                    ..nominal
+                },
+                "blurred",
+            ),
+            (
+                FastSignals {
+                    contrast_span: 80.0,
+                    ..nominal
+                },
+                "brightness",
+            ),
+            (
+                FastSignals {
+                    flatness: 1.0,
+                    ..nominal
+                },
+                "synthetic",
+            ),
+        ];
+        for (signals, expected) in cases {
+            assert_eq!(label(&one, signals), expected);
+        }
+        let crowd = [0, 1, 2].map(|i| candidate_at(100.0 + 80.0 * i as f32, 100.0, i));
+        assert_eq!(label(&crowd, nominal), "lots");
+        // 100px between finder centres at 1.5px modules: ~74 modules.
+        let dense = RankedGroupCandidate {
+            tr: ImagePoint::new(200.0, 100.0),
+            bl: ImagePoint::new(100.0, 200.0),
+            module_size: 1.5,
+            ..one[0]
+        };
+        assert_eq!(label(&[dense], nominal), "high_version");
+        for condition in ImageCondition::ALL {
+            assert_eq!(ImageCondition::from_name(condition.name()), Some(condition));
+        }
+    }
+
+    #[test]
+    fn decode_budget_spends_per_stage_and_lane() {
+        // 4 attempts: lanes 2/1/1, one attempt for the top candidate.
+        let mut budget = DecodeBudget::new(4, StrategyProfile::FastSingle, 10);
+        assert_eq!(budget.lanes.map(|q| q.allocated), [2, 1, 1]);
+        assert!(budget.has_room(DecodeStage::Top1));
+        assert!(budget.take(DecodeStage::Top1, ConfidenceLane::Medium));
+        assert!(!budget.has_room(DecodeStage::Top1));
+
+        // The medium lane is spent: refused without touching the stage.
+        assert!(!budget.take(DecodeStage::Regions, ConfidenceLane::Medium));
+        assert_eq!(budget.attempts_used(), 1);
+        assert!(budget.take(DecodeStage::Regions, ConfidenceLane::High));
+        assert!(budget.take(DecodeStage::Regions, ConfidenceLane::Low));
+        assert!(budget.take(DecodeStage::Regions, ConfidenceLane::High));
+        assert!(!budget.has_room(DecodeStage::Regions));
+        assert_eq!(budget.attempts_used(), 4);
+
+        let mut tel = DetectionTelemetry::default();
+        budget.record_allocation(&mut tel);
+        assert_eq!(tel.budget_attempts_allocated, 4);
+        assert_eq!(tel.budget_transforms_allocated, 4);
+        assert_eq!(tel.budget_lane_allocation, [2, 1, 1]);
+    }
+
+    #[test]
+    fn trimming_keeps_far_apart_regions() {
+        // 60 near-duplicates in one cluster outrank a lone group far away.
+        let mut ranked: Vec<_> = (0..60)
+            .map(|i| candidate_at(100.0 + (i % 5) as f32, 100.0, i))
+            .collect();
+        ranked.push(candidate_at(900.0, 900.0, 60));
+
+        let kept = spatially_diverse_candidates(&ranked);
+        assert_eq!(kept.len(), MAX_GROUP_CANDIDATES);
+        assert!(kept.iter().any(|c| c.tl.x == 900.0));
+        // Rank order is preserved and the best candidate stays first.
+        assert_eq!(kept[0].group, ranked[0].group);
+        assert!(kept.windows(2).all(|w| w[0].group[0] < w[1].group[0]));
+
+        let few = &ranked[..10];
+        assert_eq!(spatially_diverse_candidates(few).len(), 10);
+    }
+
+    #[test]
+    fn solved_regions_cover_overlapping_candidates() {
+        let solved = vec![candidate_bbox(&candidate_at(100.0, 100.0, 0))];
+        assert!(overlaps_solved_region(
+            &candidate_at(110.0, 105.0, 1),
+            &solved
+        ));
+        assert!(!overlaps_solved_region(
+            &candidate_at(140.0, 100.0, 2),
+            &solved
+        ));
+        assert!(!overlaps_solved_region(
+            &candidate_at(400.0, 400.0, 3),
+            &solved
+        ));
+        assert!(!overlaps_solved_region(&candidate_at(100.0, 100.0, 4), &[]));
+    }
+
+    #[test]
+    fn finder_sample_is_stratified_and_reproducible() {
+        // A noisy texture packs the top-left cell; eight real finders sit
+        // elsewhere.
+        let mut patterns: Vec<FinderPattern> = (0..400)
+            .map(|i| FinderPattern::new((i % 20) as f32 * 2.0, (i / 20) as f32 * 2.0, 1.0))
+            .collect();
+        for i in 0..8 {
+            let x = 200.0 + 90.0 * (i % 4) as f32;
+            let y = 300.0 + 300.0 * (i / 4) as f32;
+            patterns.push(FinderPattern::with_quality(x, y, 4.0, 0.9));
+        }
+        let seed = finder_sample_seed(800, 800);
+        let sample = sample_finder_candidates(&patterns, 800, 800, 100, seed);
+        assert_eq!(sample.len(), 100);
+        assert_eq!(sample.iter().filter(|p| p.module_size == 4.0).count(), 8);
+        // Same frame size, same sample; the input order is kept.
+        let again = sample_finder_candidates(&patterns, 800, 800, 100, seed);
+        assert!(
+            sample
+                .iter()
+                .zip(&again)
+                .all(|(a, b)| a.center.x == b.center.x && a.center.y == b.center.y)
+        );
+        assert!(
+            sample
+                .windows(2)
+                .filter(|w| w[0].module_size == 1.0 && w[1].module_size == 1.0)
+                .all(|w| (w[0].center.y, w[0].center.x) < (w[1].center.y, w[1].center.x))
+        );
+        assert_ne!(seed, finder_sample_seed(800, 801));
+    }
+
+    #[test]
+    fn group_cap_keeps_the_best_raw_scores_in_order() {
+        // Right-angle triples whose third finder grows ever larger than the
+        // other two, so raw scores rise with the index.
+        let mut patterns = Vec::new();
+        let mut groups = Vec::new();
+        for i in 0..50 {
+            let base = patterns.len();
+            patterns.push(FinderPattern::new(0.0, 0.0, 1.0));
+            patterns.push(FinderPattern::new(100.0, 0.0, 1.0));
+            patterns.push(FinderPattern::new(0.0, 100.0, 1.0 + i as f32 * 0.02));
+            groups.push(vec![base, base + 1, base + 2]);
+        }
+        groups.reverse();
+
+        let kept = best_raw_groups(&patterns, groups.clone(), 5);
+        assert_eq!(
+            kept,
+            (0..5).map(|i| groups[49 - i].clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(best_raw_groups(&patterns, groups, 100).len(), 50);
+    }
+
+    #[test]
+    fn failure_runs_reorder_queue_away_from_repeated_stage() {
+        let mut runs = FailureRuns::new(2);
+        let lane = ConfidenceLane::High;
+        // Different stages, or a read in between, never complete a run.
+        assert!(
+            runs.record(lane, 0, Some(DecodeFailure::FormatInfo))
+                .is_none()
+        );
+        assert!(
+            runs.record(lane, 1, Some(DecodeFailure::Codewords))
+                .is_none()
+        );
+        assert!(runs.record(lane, 2, None).is_none());
+        assert!(
+            runs.record(lane, 3, Some(DecodeFailure::FormatInfo))
+                .is_none()
+        );
+        // Runs are kept per lane.
+        assert!(
+            runs.record(ConfidenceLane::Low, 4, Some(DecodeFailure::FormatInfo))
+                .is_none()
+        );
+        let failed = runs.record(lane, 5, Some(DecodeFailure::FormatInfo));
+        assert_eq!(failed, Some(vec![3, 5]));
+        assert!(runs.lanes[lane.index()].failed.is_empty());
+
+        // 0 and 1 failed; 2 sits on top of them, 3 has other modules, 4 is
+        // elsewhere in the frame.
+        let mut candidates = vec![
+            candidate_at(100.0, 100.0, 0),
+            candidate_at(104.0, 100.0, 1),
+            candidate_at(102.0, 102.0, 2),
+            candidate_at(100.0, 100.0, 3),
+            candidate_at(160.0, 100.0, 4),
+        ];
+        candidates[3].module_size = 4.0;
+        let mut queue = vec![2, 3, 4];
+        let moved = diversify_queue(&mut queue, &candidates, &[0, 1]);
+        assert_eq!(queue, vec![3, 4, 2]);
+        assert_eq!(moved, vec![3, 4]);
+        // Nothing to move when every candidate is already unlike them.
+        assert!(diversify_queue(&mut queue[..2], &candidates, &[0, 1]).is_empty());
+        assert!(
+            FailureRuns::new(0)
+                .record(lane, 0, Some(DecodeFailure::FormatInfo))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn finder_ring_snr_drops_with_noise_and_reroutes_the_lane() {
+        // Finders of 4px modules centred near (30,30), (110,30) and (30,110).
+        let (width, height) = (144, 144);
+        let draw = |dark: i32, noise: i32| {
+            let mut seed = 12345u32;
+            let mut gray = vec![160u8; width * height];
+            for (cx, cy) in [(30i32, 30i32), (110, 30), (30, 110)] {
+                for y in cy - 14..cy + 14 {
+                    for x in cx - 14..cx + 14 {
+                        let ring = ((x - cx + 14) / 4 - 3)
+                            .abs()
+                            .max(((y - cy + 14) / 4 - 3).abs());
+                        let base = if ring == 2 { 160 } else { dark };
+                        // Sum of four uniform draws: roughly Gaussian noise
+                        let jitter: i32 = (0..4)
+                            .map(|_| {
+                                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                                (seed >> 16) as i32 % (2 * noise + 1) - noise
+                            })
+                            .sum();
+                        gray[y as usize * width + x as usize] = (base + jitter).clamp(0, 255) as u8;
+                    }
+                }
+            }
+            gray
+        };
+        let (tl, tr, bl) = (
+            Point::new(29.5, 29.5),
+            Point::new(109.5, 29.5),
+            Point::new(29.5, 109.5),
+        );
+        let clean = finder_ring_snr(&draw(40, 0), width, height, &tl, &tr, &bl, 4.0);
+        let noisy = finder_ring_snr(&draw(130, 30), width, height, &tl, &tr, &bl, 4.0);
+        assert!(clean > 100.0, "{clean}");
+        assert!(noisy < DEFAULT_LOW_SNR_MAX, "{noisy}");
+
+        let candidate = RankedGroupCandidate {
+            snr: noisy,
+            ..candidate_at(0.0, 0.0, 0)
+        };
+        assert!(matches!(
+            candidate_lane(&candidate, DEFAULT_LOW_SNR_MAX),
+            (ConfidenceLane::Low, true)
+        ));
+        assert!(matches!(
+            candidate_lane(&candidate, 0.0),
+            (ConfidenceLane::High, false)
+        ));
+        let signals = FastSignals {
+            blur_metric: 40.0,
+            contrast_span: 190.0,
+            ..FastSignals::default()
+        };
+        assert_eq!(
+            select_strategy(&[candidate], signals).as_str(),
+            "low_contrast_recovery"
+        );
+    }
+}
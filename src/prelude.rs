@@ -0,0 +1,19 @@
+//! Common imports in one line.
+//!
+//! ```
+//! use rust_qr::prelude::*;
+//!
+//! let gray = vec![255u8; 64 * 64];
+//! let scans: Vec<Scan> = scan_gray(&gray, 64, 64);
+//! assert!(scans.is_empty());
+//! ```
+
+#[cfg(feature = "image-interop")]
+pub use crate::scan::scan_bytes;
+#[cfg(feature = "tools")]
+pub use crate::scan::scan_file;
+pub use crate::scan::{Scan, scan_gray};
+pub use crate::{
+    DetectionTelemetry, Detector, DetectorConfig, ECLevel, Error, ImagePoint, ImageRef, QRCode,
+    Version, detect, detect_from_grayscale, detect_image_ref, detect_with_telemetry, try_detect,
+};
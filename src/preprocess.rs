@@ -0,0 +1,303 @@
+//! Grayscale preprocessing applied by `Detector` before detection
+//!
+//! A [`Preprocessor`] maps one grayscale frame to another of the same size.
+//! [`crate::Detector::with_preprocessor`] chains them, in the order added,
+//! between grayscale conversion and binarization, so a caller fighting a
+//! dim sensor or a noisy camera sets that up once instead of around every
+//! call. The chain runs inside detection, so
+//! [`crate::Detector::detect_with_telemetry`] still times and attributes
+//! the whole pipeline, listing the steps in
+//! [`crate::DetectionTelemetry::preprocessors`].
+
+/// Maps a grayscale frame (`width * height` bytes, row by row) to another of
+/// the same size
+pub trait Preprocessor: Send + Sync {
+    /// Short label for telemetry
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn process(&self, gray: &[u8], width: usize, height: usize) -> Vec<u8>;
+}
+
+impl<F: Fn(&[u8], usize, usize) -> Vec<u8> + Send + Sync> Preprocessor for F {
+    fn process(&self, gray: &[u8], width: usize, height: usize) -> Vec<u8> {
+        self(gray, width, height)
+    }
+}
+
+/// Run `gray` through `chain` in order; a step whose output is the wrong
+/// size is skipped.
+pub(crate) fn apply_chain(
+    chain: &[Box<dyn Preprocessor>],
+    gray: Vec<u8>,
+    width: usize,
+    height: usize,
+) -> Vec<u8> {
+    chain.iter().fold(gray, |gray, step| {
+        let out = step.process(&gray, width, height);
+        if out.len() == gray.len() { out } else { gray }
+    })
+}
+
+/// Power-law tone curve on levels scaled to 0..1: `gamma` below 1 lifts
+/// shadows, above 1 darkens midtones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gamma {
+    pub gamma: f32,
+}
+
+impl Preprocessor for Gamma {
+    fn name(&self) -> &str {
+        "gamma"
+    }
+
+    fn process(&self, gray: &[u8], _width: usize, _height: usize) -> Vec<u8> {
+        let gamma = self.gamma.max(f32::EPSILON);
+        let lut: Vec<u8> = (0..=255u16)
+            .map(|v| ((v as f32 / 255.0).powf(gamma) * 255.0).round() as u8)
+            .collect();
+        gray.iter().map(|&v| lut[v as usize]).collect()
+    }
+}
+
+/// Contrast-limited adaptive histogram equalization: each of
+/// `tiles x tiles` cells is equalized on its own, with histogram bins
+/// capped at `clip_limit` times the mean to keep flat areas from turning
+/// into noise, and the cell curves blended bilinearly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clahe {
+    pub tiles: usize,
+    pub clip_limit: f32,
+}
+
+impl Default for Clahe {
+    fn default() -> Self {
+        Self {
+            tiles: 8,
+            clip_limit: 2.0,
+        }
+    }
+}
+
+impl Preprocessor for Clahe {
+    fn name(&self) -> &str {
+        "clahe"
+    }
+
+    fn process(&self, gray: &[u8], width: usize, height: usize) -> Vec<u8> {
+        if width == 0 || height == 0 {
+            return gray.to_vec();
+        }
+        let tiles_x = self.tiles.clamp(1, width);
+        let tiles_y = self.tiles.clamp(1, height);
+        let tile_w = width.div_ceil(tiles_x);
+        let tile_h = height.div_ceil(tiles_y);
+
+        let mut luts = vec![[0u8; 256]; tiles_x * tiles_y];
+        for ty in 0..tiles_y {
+            for tx in 0..tiles_x {
+                let (x0, y0) = (tx * tile_w, ty * tile_h);
+                let (x1, y1) = ((x0 + tile_w).min(width), (y0 + tile_h).min(height));
+                let mut hist = [0u32; 256];
+                for y in y0..y1 {
+                    for &v in &gray[y * width + x0..y * width + x1] {
+                        hist[v as usize] += 1;
+                    }
+                }
+                let pixels = ((x1 - x0) * (y1 - y0)) as u32;
+                luts[ty * tiles_x + tx] = clipped_equalization(&mut hist, pixels, self.clip_limit);
+            }
+        }
+
+        // Fractional tile coordinate of a pixel centre, clamped to the
+        // outermost tile centres.
+        let tile_pos = |p: usize, size: usize, tiles: usize| {
+            let f = ((p as f32 + 0.5) / size as f32 - 0.5).clamp(0.0, (tiles - 1) as f32);
+            let lo = f.floor() as usize;
+            (lo, (lo + 1).min(tiles - 1), f - lo as f32)
+        };
+        let mut out = vec![0u8; gray.len()];
+        for y in 0..height {
+            let (ty0, ty1, wy) = tile_pos(y, tile_h, tiles_y);
+            for x in 0..width {
+                let (tx0, tx1, wx) = tile_pos(x, tile_w, tiles_x);
+                let v = gray[y * width + x] as usize;
+                let at = |tx: usize, ty: usize| luts[ty * tiles_x + tx][v] as f32;
+                let top = at(tx0, ty0) * (1.0 - wx) + at(tx1, ty0) * wx;
+                let bottom = at(tx0, ty1) * (1.0 - wx) + at(tx1, ty1) * wx;
+                out[y * width + x] = (top * (1.0 - wy) + bottom * wy).round() as u8;
+            }
+        }
+        out
+    }
+}
+
+/// Equalization curve of one tile, bins clipped at `clip_limit` times the
+/// mean and the excess spread evenly.
+fn clipped_equalization(hist: &mut [u32; 256], pixels: u32, clip_limit: f32) -> [u8; 256] {
+    let limit = ((clip_limit.max(1.0) * pixels as f32 / 256.0) as u32).max(1);
+    let mut excess = 0u32;
+    for bin in hist.iter_mut() {
+        if *bin > limit {
+            excess += *bin - limit;
+            *bin = limit;
+        }
+    }
+    let (share, rest) = (excess / 256, (excess % 256) as usize);
+    for (i, bin) in hist.iter_mut().enumerate() {
+        *bin += share + (i < rest) as u32;
+    }
+
+    let mut lut = [0u8; 256];
+    let mut cdf = 0u32;
+    for (level, &count) in hist.iter().enumerate() {
+        cdf += count;
+        lut[level] = ((cdf as f32 / pixels.max(1) as f32) * 255.0).round() as u8;
+    }
+    lut
+}
+
+/// Median filter over a `(2 * radius + 1)`-pixel square, for sensor noise
+/// and speckle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoise {
+    pub radius: usize,
+}
+
+impl Default for Denoise {
+    fn default() -> Self {
+        Self { radius: 1 }
+    }
+}
+
+impl Preprocessor for Denoise {
+    fn name(&self) -> &str {
+        "denoise"
+    }
+
+    fn process(&self, gray: &[u8], width: usize, height: usize) -> Vec<u8> {
+        if self.radius == 0 || width == 0 || height == 0 {
+            return gray.to_vec();
+        }
+        let r = self.radius;
+        let mut window = Vec::with_capacity((2 * r + 1) * (2 * r + 1));
+        let mut out = vec![0u8; gray.len()];
+        for y in 0..height {
+            let (y0, y1) = (y.saturating_sub(r), (y + r).min(height - 1));
+            for x in 0..width {
+                let (x0, x1) = (x.saturating_sub(r), (x + r).min(width - 1));
+                window.clear();
+                for wy in y0..=y1 {
+                    window.extend_from_slice(&gray[wy * width + x0..=wy * width + x1]);
+                }
+                let mid = window.len() / 2;
+                out[y * width + x] = *window.select_nth_unstable(mid).1;
+            }
+        }
+        out
+    }
+}
+
+/// Unsharp mask: each pixel pushed away from its 3x3 mean by `amount`
+/// times the difference, crisping module edges softened by defocus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sharpen {
+    pub amount: f32,
+}
+
+impl Default for Sharpen {
+    fn default() -> Self {
+        Self { amount: 1.0 }
+    }
+}
+
+impl Preprocessor for Sharpen {
+    fn name(&self) -> &str {
+        "sharpen"
+    }
+
+    fn process(&self, gray: &[u8], width: usize, height: usize) -> Vec<u8> {
+        if width == 0 || height == 0 {
+            return gray.to_vec();
+        }
+        let mut out = vec![0u8; gray.len()];
+        for y in 0..height {
+            let (y0, y1) = (y.saturating_sub(1), (y + 1).min(height - 1));
+            for x in 0..width {
+                let (x0, x1) = (x.saturating_sub(1), (x + 1).min(width - 1));
+                let mut sum = 0u32;
+                for wy in y0..=y1 {
+                    sum += gray[wy * width + x0..=wy * width + x1]
+                        .iter()
+                        .map(|&v| v as u32)
+                        .sum::<u32>();
+                }
+                let mean = sum as f32 / ((y1 - y0 + 1) * (x1 - x0 + 1)) as f32;
+                let v = gray[y * width + x] as f32;
+                out[y * width + x] = (v + self.amount * (v - mean)).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clahe_stretches_a_dim_tile_and_denoise_drops_speckle() {
+        // Left half a faint 100/110 checker, right half flat 200.
+        let (w, h) = (32, 16);
+        let gray: Vec<u8> = (0..w * h)
+            .map(|i| {
+                let (x, y) = (i % w, i / w);
+                if x >= w / 2 {
+                    200
+                } else if (x / 2 + y / 2) % 2 == 0 {
+                    100
+                } else {
+                    110
+                }
+            })
+            .collect();
+        let out = Clahe {
+            tiles: 2,
+            clip_limit: 40.0,
+        }
+        .process(&gray, w, h);
+        let span = |img: &[u8]| {
+            let left: Vec<u8> = (0..h)
+                .flat_map(|y| img[y * w..y * w + w / 4].to_vec())
+                .collect();
+            left.iter().max().unwrap() - left.iter().min().unwrap()
+        };
+        assert!(
+            span(&out) > 3 * span(&gray),
+            "{} vs {}",
+            span(&out),
+            span(&gray)
+        );
+
+        let mut speckled = vec![50u8; 9 * 9];
+        speckled[4 * 9 + 4] = 255;
+        let clean = Denoise::default().process(&speckled, 9, 9);
+        assert!(clean.iter().all(|&v| v == 50));
+    }
+
+    #[test]
+    fn chain_runs_in_order_and_skips_misshapen_output() {
+        let chain: Vec<Box<dyn Preprocessor>> = vec![
+            Box::new(|g: &[u8], _: usize, _: usize| g.iter().map(|v| v / 2).collect()),
+            Box::new(|_: &[u8], _: usize, _: usize| vec![0u8; 3]),
+            Box::new(Gamma { gamma: 1.0 }),
+            Box::new(Sharpen { amount: 0.0 }),
+        ];
+        assert_eq!(chain[0].name(), "custom");
+        assert_eq!(
+            apply_chain(&chain, vec![200, 100, 50, 0], 2, 2),
+            [100, 50, 25, 0]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Payload prior for batches of related codes
+//!
+//! Numbered tickets, labels and forms from one source tend to share a long
+//! payload prefix ("TICKET-2024-000173", "TICKET-2024-000174", ...). A
+//! [`PayloadPrior`] learns that prefix from payloads decoded so far; while it
+//! is applied, a Reed-Solomon block that fails on its own is retried with the
+//! data codewords the prefix pins down filled in. Codes recovered that way are
+//! flagged [`QRCode::assisted`](crate::QRCode::assisted).
+
+use std::collections::VecDeque;
+
+use crate::decoder::modes::alphanumeric::AlphanumericDecoder;
+use crate::decoder::qr_decoder::{PriorConstraint, SegmentMode, with_payload_prior};
+
+/// Payloads remembered by default.
+const DEFAULT_WINDOW: usize = 16;
+/// Payloads needed before a shared prefix is trusted.
+const MIN_OBSERVATIONS: usize = 2;
+
+/// Shared prefix of recently decoded numeric or alphanumeric payloads.
+///
+/// Feed it decoded payloads with [`PayloadPrior::observe`], or let a
+/// [`Detector`](crate::Detector) built with
+/// [`with_payload_prior`](crate::Detector::with_payload_prior) feed it every
+/// code it reads unaided.
+#[derive(Debug, Clone)]
+pub struct PayloadPrior {
+    window: usize,
+    recent: VecDeque<Vec<u8>>,
+    prefix: Vec<u8>,
+    mode: Option<SegmentMode>,
+}
+
+impl PayloadPrior {
+    /// Create an empty prior remembering the last 16 payloads
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Create an empty prior remembering the last `window` payloads
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            window: window.max(MIN_OBSERVATIONS),
+            recent: VecDeque::new(),
+            prefix: Vec::new(),
+            mode: None,
+        }
+    }
+
+    /// Create a prior from previously decoded payloads, oldest first
+    pub fn from_payloads<I, P>(payloads: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let mut prior = Self::new();
+        for payload in payloads {
+            prior.observe(payload.as_ref());
+        }
+        prior
+    }
+
+    /// Record a decoded payload, dropping the oldest beyond the window
+    pub fn observe(&mut self, payload: &[u8]) {
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(payload.to_vec());
+        self.refresh();
+    }
+
+    /// Forget every payload seen so far
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.refresh();
+    }
+
+    /// Prefix shared by every remembered payload, empty until at least two
+    /// numeric or alphanumeric payloads agree on one
+    pub fn shared_prefix(&self) -> &[u8] {
+        if self.mode.is_some() {
+            &self.prefix
+        } else {
+            &[]
+        }
+    }
+
+    /// Number of payloads currently remembered
+    pub fn observations(&self) -> usize {
+        self.recent.len()
+    }
+
+    /// Run `f` with this prior available to the decoder on this thread
+    pub fn apply<R>(&self, f: impl FnOnce() -> R) -> R {
+        let constraint = self.mode.map(|mode| PriorConstraint {
+            mode,
+            prefix: self.prefix.clone(),
+        });
+        with_payload_prior(constraint, f)
+    }
+
+    fn refresh(&mut self) {
+        self.prefix.clear();
+        self.mode = None;
+        let Some(first) = self.recent.front() else {
+            return;
+        };
+        let shared = self.recent.iter().skip(1).fold(first.len(), |len, p| {
+            first
+                .iter()
+                .zip(p)
+                .take(len)
+                .take_while(|(a, b)| a == b)
+                .count()
+        });
+        self.prefix.extend_from_slice(&first[..shared]);
+        if self.recent.len() < MIN_OBSERVATIONS || shared == 0 {
+            return;
+        }
+        // The prior describes single-segment payloads, so the mode has to
+        // fit every whole payload rather than just the prefix.
+        let all = |accept: fn(u8) -> bool| self.recent.iter().flatten().all(|&b| accept(b));
+        self.mode = if all(|b| b.is_ascii_digit()) {
+            Some(SegmentMode::Numeric)
+        } else if all(|b| AlphanumericDecoder::char_value(b).is_some()) {
+            Some(SegmentMode::Alphanumeric)
+        } else {
+            None
+        };
+    }
+}
+
+impl Default for PayloadPrior {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learns_prefix_and_mode_from_a_batch() {
+        let mut prior = PayloadPrior::new();
+        prior.observe(b"4376471154038");
+        assert_eq!(prior.shared_prefix(), b"");
+        prior.observe(b"4376471154781");
+        assert_eq!(prior.shared_prefix(), b"4376471154");
+        prior.observe(b"43764712");
+        assert_eq!(prior.shared_prefix(), b"4376471");
+
+        let prior = PayloadPrior::from_payloads(["TICKET-0042", "TICKET-0043"]);
+        assert_eq!(prior.shared_prefix(), b"TICKET-004");
+        // Lowercase is outside the alphanumeric set, so no constraint applies.
+        let prior = PayloadPrior::from_payloads(["ticket-0042", "ticket-0043"]);
+        assert_eq!(prior.shared_prefix(), b"");
+    }
+
+    #[test]
+    fn window_drops_old_payloads() {
+        let mut prior = PayloadPrior::with_window(2);
+        for payload in ["1000", "2001", "2002"] {
+            prior.observe(payload.as_bytes());
+        }
+        assert_eq!(prior.observations(), 2);
+        assert_eq!(prior.shared_prefix(), b"200");
+        prior.clear();
+        assert_eq!(prior.shared_prefix(), b"");
+    }
+}
@@ -0,0 +1,258 @@
+//! Python bindings (pyo3 + numpy), enabled with the `python` feature.
+//!
+//! Build with `maturin develop --features python` (see `pyproject.toml`), then:
+//!
+//! ```python
+//! import numpy as np, rust_qr
+//! codes = rust_qr.detect(np.asarray(img))          # HxW gray or HxWx3/4 RGB(A)
+//! codes, tel = rust_qr.detect_with_telemetry(rgb)  # HxWx3 RGB
+//! sure = rust_qr.detect(img, min_confidence=0.95)  # drop uncertain reads
+//! ```
+
+use numpy::{PyReadonlyArrayDyn, PyUntypedArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+
+use crate::{DetectionTelemetry, DetectorConfig, QRCode};
+
+/// Image layouts accepted from numpy.
+enum PyImage {
+    Gray(Vec<u8>, usize, usize),
+    Rgb(Vec<u8>, usize, usize),
+}
+
+fn image_from_array(image: &PyReadonlyArrayDyn<'_, u8>) -> PyResult<PyImage> {
+    let shape = image.shape().to_vec();
+    let pixels: Vec<u8> = match image.as_slice() {
+        Ok(slice) => slice.to_vec(),
+        // Strided or non-contiguous views are copied in logical order.
+        Err(_) => image.as_array().iter().copied().collect(),
+    };
+    match shape.as_slice() {
+        [h, w] => Ok(PyImage::Gray(pixels, *w, *h)),
+        [h, w, 1] => Ok(PyImage::Gray(pixels, *w, *h)),
+        [h, w, 3] => Ok(PyImage::Rgb(pixels, *w, *h)),
+        [h, w, 4] => {
+            let rgb = pixels
+                .chunks_exact(4)
+                .flat_map(|px| [px[0], px[1], px[2]])
+                .collect();
+            Ok(PyImage::Rgb(rgb, *w, *h))
+        }
+        _ => Err(PyValueError::new_err(format!(
+            "expected uint8 array of shape (H, W), (H, W, 3) or (H, W, 4), got {:?}",
+            shape
+        ))),
+    }
+}
+
+fn code_to_dict<'py>(py: Python<'py>, qr: &QRCode) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("content", &qr.content)?;
+    dict.set_item("data", PyBytes::new(py, &qr.data))?;
+    dict.set_item("text_encoding", format!("{:?}", qr.text_encoding))?;
+    dict.set_item("version", qr.version.number())?;
+    dict.set_item("ec_level", format!("{:?}", qr.error_correction))?;
+    dict.set_item("mask", qr.mask_pattern as u8)?;
+    dict.set_item("confidence", qr.confidence)?;
+    let corners: Vec<(f32, f32)> = qr.outline().iter().map(|p| (p.x, p.y)).collect();
+    dict.set_item("corners", corners)?;
+    dict.set_item("rotation_deg", qr.rotation_deg)?;
+    dict.set_item("orientation", format!("{:?}", qr.orientation))?;
+    dict.set_item("tilt_estimate", qr.tilt_estimate)?;
+    dict.set_item("fixed_pattern_damage", qr.fixed_pattern_damage)?;
+    dict.set_item("assisted", qr.assisted)?;
+    dict.set_item("memory_degraded", qr.memory_degraded)?;
+    dict.set_item("format_quirk", qr.format_quirk.map(|q| q.name()))?;
+    dict.set_item("physical_size_mm", qr.physical_size_mm)?;
+    dict.set_item("binarization", qr.binarization.map(|b| b.name()))?;
+    Ok(dict)
+}
+
+fn codes_to_list<'py>(py: Python<'py>, codes: &[QRCode]) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty(py);
+    for qr in codes {
+        list.append(code_to_dict(py, qr)?)?;
+    }
+    Ok(list)
+}
+
+macro_rules! telemetry_fields {
+    ($dict:expr, $tel:expr, $($field:ident),* $(,)?) => {
+        $( $dict.set_item(stringify!($field), &$tel.$field)?; )*
+    };
+}
+
+fn telemetry_to_dict<'py>(
+    py: Python<'py>,
+    tel: &DetectionTelemetry,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    telemetry_fields!(
+        dict,
+        tel,
+        binarize_ok,
+        finder_patterns_found,
+        finder_sample_seed,
+        finder_sample_input,
+        finder_sample_kept,
+        groups_found,
+        transforms_built,
+        format_extracted,
+        rs_decode_ok,
+        payload_decoded,
+        decode_attempts,
+        candidate_groups_scored,
+        candidate_score_buckets,
+        qr_codes_found,
+        budget_skips,
+        budget_lane_high,
+        budget_lane_medium,
+        budget_lane_low,
+        budget_lane_low_snr,
+        budget_attempts_allocated,
+        budget_transforms_allocated,
+        budget_transforms_used,
+        budget_lane_allocation,
+        bin_fallback_otsu_to_adaptive31,
+        bin_fallback_adaptive31_to_adaptive21,
+        bin_fallback_successes,
+        bin_policy_allowance,
+        bin_policy_attempts,
+        rerank_enabled,
+        rerank_top1_attempts,
+        rerank_top1_successes,
+        rerank_transform_reject_count,
+        saturation_mask_enabled,
+        saturation_mask_coverage,
+        saturation_mask_decode_successes,
+        roi_norm_attempts,
+        roi_norm_successes,
+        roi_norm_skipped,
+        two_finder_attempts,
+        two_finder_successes,
+        strategy_profile,
+        regions_considered,
+        router_multi_region,
+        router_region_decodes,
+        failure_reorders,
+        failure_reorder_decodes,
+        router_blur_metric,
+        router_saturation_ratio,
+        router_skew_estimate_deg,
+        router_region_density_proxy,
+        router_flatness,
+        router_contrast_span,
+        router_top_snr,
+        router_module_size_modes,
+        image_condition,
+        acceptance_rejected,
+        deskew_attempts,
+        deskew_successes,
+        high_version_precision_attempts,
+        recovery_mode_attempts,
+        scale_retry_attempts,
+        scale_retry_successes,
+        scale_retry_skipped_by_budget,
+        threshold_sweep_attempts,
+        threshold_sweep_successes,
+        hv_subpixel_attempts,
+        hv_refine_attempts,
+        hv_refine_successes,
+        rs_erasure_attempts,
+        rs_erasure_successes,
+        rs_erasure_count_hist,
+        rs_blocks_failed,
+        rs_block_load_hist,
+        high_density_version_pins,
+        high_density_residual_rejects,
+        synthetic_snap_decodes,
+        header_prefilter_skips,
+        rotated_format_rescues,
+        wrong_format_mask_rescues,
+        binarization_decodes,
+        phase11_time_budget_skips,
+        column_scans_skipped,
+        quirk_rescues,
+        decode_memo_hits,
+        stream_decode_attempts,
+        pathology_guard_tripped,
+        preprocessors,
+        time_grayscale_ms,
+        time_binarize_ms,
+        time_finder_scan_ms,
+        time_group_rank_ms,
+        time_decode_ms,
+        time_recovery_ms,
+        time_total_ms,
+    );
+    // Each decoded group as [top-left, top-right, bottom-left] of
+    // (x, y, module_size, quality).
+    let finders: Vec<Vec<(f32, f32, f32, f32)>> = tel
+        .decoded_finders
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .map(|f| (f.center.x, f.center.y, f.module_size, f.quality))
+                .collect()
+        })
+        .collect();
+    dict.set_item("decoded_finders", finders)?;
+    Ok(dict)
+}
+
+/// Configuration for the optional `min_confidence` keyword.
+fn config_for(min_confidence: Option<f32>) -> DetectorConfig {
+    DetectorConfig {
+        min_confidence,
+        ..DetectorConfig::default()
+    }
+}
+
+/// Detect QR codes in a numpy image; returns a list of dicts. With
+/// `min_confidence`, codes calibrated below it are left out.
+#[pyfunction]
+#[pyo3(signature = (image, min_confidence=None))]
+fn detect<'py>(
+    py: Python<'py>,
+    image: PyReadonlyArrayDyn<'py, u8>,
+    min_confidence: Option<f32>,
+) -> PyResult<Bound<'py, PyList>> {
+    let image = image_from_array(&image)?;
+    let config = config_for(min_confidence);
+    let codes = py.detach(|| {
+        config.apply(|| match &image {
+            PyImage::Gray(px, w, h) => crate::detect_from_grayscale(px, *w, *h),
+            PyImage::Rgb(px, w, h) => crate::detect(px, *w, *h),
+        })
+    });
+    codes_to_list(py, &codes)
+}
+
+/// Detect QR codes and return `(codes, telemetry_dict)`; `min_confidence`
+/// as for `detect`.
+#[pyfunction]
+#[pyo3(signature = (image, min_confidence=None))]
+fn detect_with_telemetry<'py>(
+    py: Python<'py>,
+    image: PyReadonlyArrayDyn<'py, u8>,
+    min_confidence: Option<f32>,
+) -> PyResult<(Bound<'py, PyList>, Bound<'py, PyDict>)> {
+    let (rgb, w, h) = match image_from_array(&image)? {
+        PyImage::Rgb(px, w, h) => (px, w, h),
+        // The telemetry path starts from RGB; expand gray input.
+        PyImage::Gray(px, w, h) => (px.iter().flat_map(|&v| [v, v, v]).collect(), w, h),
+    };
+    let config = config_for(min_confidence);
+    let (codes, tel) = py.detach(|| config.apply(|| crate::detect_with_telemetry(&rgb, w, h)));
+    Ok((codes_to_list(py, &codes)?, telemetry_to_dict(py, &tel)?))
+}
+
+#[pymodule]
+fn rust_qr(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(detect, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_with_telemetry, m)?)?;
+    Ok(())
+}
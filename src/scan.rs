@@ -0,0 +1,86 @@
+//! One-call scanning for callers that only want the payloads.
+//!
+//! [`scan_gray`], [`scan_bytes`] and [`scan_file`] wrap the detection entry
+//! points and the `image` crate, and return a flat [`Scan`] per code. Use
+//! [`crate::detect`] and friends when you need versions, masks, telemetry or
+//! the rest of [`QRCode`].
+
+use crate::models::{ImagePoint, QRCode};
+
+/// A decoded code reduced to what most integrations read.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scan {
+    /// Raw payload bytes.
+    pub payload: Vec<u8>,
+    /// Payload decoded as text; see [`QRCode::content`].
+    pub text: String,
+    /// Outer corners wound clockwise from the symbol's top-left
+    /// ([`QRCode::outline`]).
+    pub corners: [ImagePoint; 4],
+    /// Calibrated confidence in [0, 1]; see [`QRCode::confidence`].
+    pub confidence: f32,
+}
+
+impl From<&QRCode> for Scan {
+    fn from(qr: &QRCode) -> Self {
+        Self {
+            payload: qr.data.clone(),
+            text: qr.content.clone(),
+            corners: qr.outline(),
+            confidence: qr.confidence,
+        }
+    }
+}
+
+impl From<QRCode> for Scan {
+    fn from(qr: QRCode) -> Self {
+        let corners = qr.outline();
+        Self {
+            payload: qr.data,
+            text: qr.content,
+            corners,
+            confidence: qr.confidence,
+        }
+    }
+}
+
+fn to_scans(codes: Vec<QRCode>) -> Vec<Scan> {
+    codes.into_iter().map(Scan::from).collect()
+}
+
+/// Scan an 8-bit grayscale buffer of `width * height` bytes
+///
+/// A buffer shorter than that yields no scans; use [`crate::try_detect`]
+/// when the difference between "nothing found" and "bad input" matters.
+///
+/// # Example
+/// ```
+/// let gray = vec![255u8; 64 * 64];
+/// assert!(rust_qr::scan_gray(&gray, 64, 64).is_empty());
+/// ```
+pub fn scan_gray(gray: &[u8], width: usize, height: usize) -> Vec<Scan> {
+    if width == 0 || height == 0 || gray.len() < width.saturating_mul(height) {
+        return Vec::new();
+    }
+    to_scans(crate::detect_from_grayscale(gray, width, height))
+}
+
+/// Scan an encoded image (PNG, JPEG, WebP, ...) whose format is guessed
+/// from its contents (`image-interop` feature)
+#[cfg(feature = "image-interop")]
+pub fn scan_bytes(bytes: &[u8]) -> Result<Vec<Scan>, image::ImageError> {
+    let image = image::load_from_memory(bytes)?;
+    Ok(to_scans(crate::detect_image(&image)))
+}
+
+/// Scan an image file, recognised by its contents rather than its
+/// extension (`tools` feature)
+///
+/// Reads the same formats as `qrtool detect` apart from multi-page TIFF and
+/// SVG, at full resolution; `QR_MAX_DIM` is not applied.
+#[cfg(feature = "tools")]
+pub fn scan_file<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<Scan>, image::ImageError> {
+    let image = crate::tools::open_image(path.as_ref())?;
+    Ok(to_scans(crate::detect_image(&image)))
+}
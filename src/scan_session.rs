@@ -0,0 +1,226 @@
+//! Structured-append reassembly across frames and pages
+//!
+//! A structured-append message is split over up to 16 symbols that may not
+//! all be in view at once. A [`ScanSession`] collects the parts from any
+//! number of [`detect`](crate::detect) calls, tracks which are still
+//! missing, and once every part is in checks the parity byte and hands back
+//! the joined message.
+
+use crate::models::{QRCode, StructuredAppend};
+
+/// A structured-append message with every part collected and its parity
+/// verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembledMessage {
+    /// Payload bytes of every part, in sequence order
+    pub data: Vec<u8>,
+    /// Decoded text of every part, in sequence order
+    pub content: String,
+}
+
+/// What a [`ScanSession`] did with an offered symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanEvent {
+    /// The symbol carries no structured-append header
+    NotStructured,
+    /// The symbol belongs to another sequence (different part count or
+    /// parity) than the one being collected
+    ForeignSequence,
+    /// This part was already collected with the same payload
+    Duplicate,
+    /// A part with this index was already collected with a different
+    /// payload; the first one is kept
+    Conflict,
+    /// The part was stored and others are still missing
+    Stored,
+    /// The last missing part arrived and the parity checks out; the session
+    /// is reset for the next message
+    Complete(AssembledMessage),
+    /// Every part arrived but the parity byte disagrees with their data, so
+    /// at least one was misread; the session is reset
+    ParityMismatch,
+}
+
+/// Collects the symbols of one structured-append message across
+/// [`detect`](crate::detect) calls.
+///
+/// The first structured-append symbol offered fixes the sequence (part
+/// count and parity); symbols of other sequences are reported and ignored
+/// until the message completes or [`ScanSession::reset`] is called.
+///
+/// ```
+/// use rust_qr::{ScanEvent, ScanSession};
+///
+/// let mut session = ScanSession::new();
+/// let frame = vec![255u8; 64 * 64 * 3];
+/// for code in rust_qr::detect(&frame, 64, 64) {
+///     if let ScanEvent::Complete(message) = session.offer(&code) {
+///         println!("{}", message.content);
+///     }
+/// }
+/// assert!(session.missing().is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScanSession {
+    header: Option<StructuredAppend>,
+    parts: Vec<Option<(Vec<u8>, String)>>,
+}
+
+impl ScanSession {
+    /// Create a session waiting for its first part
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offer one decoded symbol
+    pub fn offer(&mut self, code: &QRCode) -> ScanEvent {
+        let Some(header) = code.structured_append else {
+            return ScanEvent::NotStructured;
+        };
+        if header.total == 0 || header.index >= header.total {
+            return ScanEvent::ForeignSequence;
+        }
+        match self.header {
+            None => {
+                self.header = Some(header);
+                self.parts = vec![None; header.total as usize];
+            }
+            Some(current) if current.total != header.total || current.parity != header.parity => {
+                return ScanEvent::ForeignSequence;
+            }
+            Some(_) => {}
+        }
+
+        let slot = &mut self.parts[header.index as usize];
+        if let Some((data, _)) = slot {
+            return if *data == code.data {
+                ScanEvent::Duplicate
+            } else {
+                ScanEvent::Conflict
+            };
+        }
+        *slot = Some((code.data.clone(), code.content.clone()));
+        if self.parts.iter().any(Option::is_none) {
+            return ScanEvent::Stored;
+        }
+
+        let parity = header.parity;
+        let mut message = AssembledMessage {
+            data: Vec::new(),
+            content: String::new(),
+        };
+        for (data, content) in self.parts.drain(..).flatten() {
+            message.data.extend_from_slice(&data);
+            message.content.push_str(&content);
+        }
+        self.reset();
+        if message.data.iter().fold(0, |acc, b| acc ^ b) == parity {
+            ScanEvent::Complete(message)
+        } else {
+            ScanEvent::ParityMismatch
+        }
+    }
+
+    /// Offer every code from one detection call, returning the message if
+    /// one of them completed it
+    pub fn offer_all(&mut self, codes: &[QRCode]) -> Option<AssembledMessage> {
+        let mut completed = None;
+        for code in codes {
+            if let ScanEvent::Complete(message) = self.offer(code) {
+                completed = Some(message);
+            }
+        }
+        completed
+    }
+
+    /// Header of the sequence being collected, once a part has arrived
+    pub fn sequence(&self) -> Option<StructuredAppend> {
+        self.header
+    }
+
+    /// Zero-based indices of the parts still missing; empty before the first
+    /// part arrives
+    pub fn missing(&self) -> Vec<u8> {
+        (0..self.parts.len() as u8)
+            .filter(|&i| self.parts[i as usize].is_none())
+            .collect()
+    }
+
+    /// Drop every collected part and wait for a new sequence
+    pub fn reset(&mut self) {
+        self.header = None;
+        self.parts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ECLevel, MaskPattern, Version};
+
+    fn part(text: &str, index: u8, total: u8, parity: u8) -> QRCode {
+        let mut code = QRCode::new(
+            text.as_bytes().to_vec(),
+            text.to_string(),
+            Version::Model2(1),
+            ECLevel::M,
+            MaskPattern::Pattern0,
+        );
+        code.structured_append = Some(StructuredAppend {
+            index,
+            total,
+            parity,
+        });
+        code
+    }
+
+    fn parity(text: &str) -> u8 {
+        text.bytes().fold(0, |acc, b| acc ^ b)
+    }
+
+    #[test]
+    fn parts_assemble_in_sequence_order_across_calls() {
+        let p = parity("HELLO WORLD");
+        let mut session = ScanSession::new();
+        assert!(session.missing().is_empty());
+
+        assert_eq!(session.offer(&part("WOR", 2, 4, p)), ScanEvent::Stored);
+        assert_eq!(session.missing(), [0, 1, 3]);
+        assert_eq!(session.offer(&part("WOR", 2, 4, p)), ScanEvent::Duplicate);
+        assert_eq!(session.offer(&part("XXX", 2, 4, p)), ScanEvent::Conflict);
+        assert_eq!(
+            session.offer(&part("ab", 0, 2, 0x11)),
+            ScanEvent::ForeignSequence
+        );
+        let mut plain = part("plain", 0, 1, 0);
+        plain.structured_append = None;
+        assert_eq!(session.offer(&plain), ScanEvent::NotStructured);
+
+        assert_eq!(
+            session.offer_all(&[part("HEL", 0, 4, p), part("LO ", 1, 4, p)]),
+            None
+        );
+        assert_eq!(session.missing(), [3]);
+        assert_eq!(session.sequence().map(|h| h.total), Some(4));
+        let message = session.offer_all(&[part("LD", 3, 4, p)]).unwrap();
+        assert_eq!(message.content, "HELLO WORLD");
+        assert_eq!(message.data, b"HELLO WORLD");
+        assert_eq!(session.sequence(), None);
+    }
+
+    #[test]
+    fn parity_mismatch_resets_the_session() {
+        let p = parity("ABCD");
+        let mut session = ScanSession::new();
+        assert_eq!(session.offer(&part("AB", 0, 2, p)), ScanEvent::Stored);
+        assert_eq!(
+            session.offer(&part("CE", 1, 2, p)),
+            ScanEvent::ParityMismatch
+        );
+        assert_eq!(session.sequence(), None);
+        assert_eq!(
+            session.offer(&part("ABCD", 5, 2, p)),
+            ScanEvent::ForeignSequence
+        );
+    }
+}
@@ -0,0 +1,592 @@
+//! Repro bundles: a compact, replayable record of one detection run.
+//!
+//! A bundle keeps only the grayscale ROI around the strongest finder patterns,
+//! so bug reports can be shared without the original photo. `qrtool replay`
+//! re-runs the recorded stages on the stored ROI and diffs every artifact.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use miniz_oxide::inflate::{TINFLStatus, decompress_to_vec_with_limit};
+
+use crate::decoder::qr_decoder::QrDecoder;
+use crate::frame::Planes;
+use crate::models::{BitMatrix, Point, QRCode};
+use crate::utils::grayscale::rgb_to_grayscale;
+
+const MAGIC: &[u8; 4] = b"RQZ1";
+const MAX_SAMPLED_GRIDS: usize = 8;
+/// Magic and the six header fields: image size and ROI bounds.
+const HEADER_LEN: usize = 4 + 6 * 8;
+/// Inflated bytes allowed beyond two per frame pixel, for the candidates,
+/// sampled grids, payloads and framing.
+const BUNDLE_ALLOWANCE: usize = 4 << 20;
+/// Largest inflated bundle read, whatever its header claims: room for a
+/// frame of about 130 megapixels.
+const MAX_BUNDLE_BYTES: usize = 256 << 20;
+
+/// When [`detect_with_snapshot`] should produce a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotPolicy {
+    /// Only when no QR code was decoded.
+    OnFailure,
+    /// For every call.
+    Always,
+}
+
+/// One binarization pass recorded in the bundle.
+#[derive(Debug, Clone)]
+pub struct SnapshotBinarization {
+    /// Policy name (`otsu`, `adaptive31`, `adaptive21`).
+    pub policy: String,
+    /// Resulting binary matrix.
+    pub matrix: BitMatrix,
+    /// Finder patterns detected on this matrix.
+    pub finder_patterns: usize,
+}
+
+/// Ranked finder triplet recorded in the bundle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotCandidate {
+    pub top_left: Point,
+    pub top_right: Point,
+    pub bottom_left: Point,
+    pub module_size: f32,
+    pub score: f32,
+}
+
+/// Everything needed to re-run detection deterministically on a ROI.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Original image dimensions.
+    pub image_width: usize,
+    pub image_height: usize,
+    /// ROI bounds `(x0, y0, x1, y1)` in original image coordinates.
+    pub roi: (usize, usize, usize, usize),
+    /// Grayscale ROI pixels, row-major, `(x1 - x0) * (y1 - y0)` bytes.
+    pub gray: Vec<u8>,
+    pub binarizations: Vec<SnapshotBinarization>,
+    pub candidates: Vec<SnapshotCandidate>,
+    /// Grids sampled for the top-ranked candidates.
+    pub sampled_grids: Vec<BitMatrix>,
+    /// Payloads decoded from the ROI.
+    pub results: Vec<String>,
+}
+
+/// Differences found when replaying a bundle.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+    pub binarization_mismatches: usize,
+    pub candidate_mismatches: usize,
+    pub grid_mismatches: usize,
+    pub results_match: bool,
+    /// Payloads decoded during replay.
+    pub results: Vec<String>,
+}
+
+impl ReplayReport {
+    /// True when every recorded artifact was reproduced exactly.
+    pub fn is_deterministic(&self) -> bool {
+        self.binarization_mismatches == 0
+            && self.candidate_mismatches == 0
+            && self.grid_mismatches == 0
+            && self.results_match
+    }
+}
+
+/// Detect QR codes in an RGB image and capture a repro bundle per `policy`.
+pub fn detect_with_snapshot(
+    image: &[u8],
+    width: usize,
+    height: usize,
+    policy: SnapshotPolicy,
+) -> (Vec<QRCode>, Option<Snapshot>) {
+    let gray = rgb_to_grayscale(image, width, height);
+    let results = crate::detect_from_grayscale(&gray, width, height);
+    if policy == SnapshotPolicy::OnFailure && !results.is_empty() {
+        return (results, None);
+    }
+    (results, Some(Snapshot::capture(&gray, width, height)))
+}
+
+impl Snapshot {
+    /// Capture a bundle from a full grayscale image.
+    ///
+    /// A `gray` shorter than `width * height` bytes gives an empty bundle.
+    pub fn capture(gray: &[u8], width: usize, height: usize) -> Self {
+        let Some(gray) = width.checked_mul(height).and_then(|len| gray.get(..len)) else {
+            return Self::empty();
+        };
+        let roi = best_finder_roi(gray, width, height).unwrap_or((0, 0, width, height));
+        let (x0, y0, x1, y1) = roi;
+        let roi_w = x1 - x0;
+        let mut roi_gray = Vec::with_capacity(roi_w * (y1 - y0));
+        for y in y0..y1 {
+            roi_gray.extend_from_slice(&gray[y * width + x0..y * width + x1]);
+        }
+        let mut snapshot = Self::record(roi_gray, roi_w, y1 - y0);
+        snapshot.image_width = width;
+        snapshot.image_height = height;
+        snapshot.roi = roi;
+        snapshot
+    }
+
+    /// A bundle with no image, ROI or recorded stages.
+    fn empty() -> Self {
+        Self {
+            image_width: 0,
+            image_height: 0,
+            roi: (0, 0, 0, 0),
+            gray: Vec::new(),
+            binarizations: Vec::new(),
+            candidates: Vec::new(),
+            sampled_grids: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+
+    fn roi_size(&self) -> (usize, usize) {
+        (self.roi.2 - self.roi.0, self.roi.3 - self.roi.1)
+    }
+
+    /// Run every recorded stage on a grayscale buffer.
+    fn record(gray: Vec<u8>, width: usize, height: usize) -> Self {
+        if width == 0 || height == 0 {
+            return Self::empty();
+        }
+        let mut binarizations = Vec::new();
+        let mut candidates = Vec::new();
+        let mut sampled_grids = Vec::new();
+        for policy in crate::phase9_binarization_sequence(width, height) {
+            let binary = crate::binarize_with_policy(&gray, width, height, policy);
+            let patterns = crate::detect_finder_patterns(&binary, width, height);
+            if candidates.is_empty() && patterns.len() >= 3 {
+                let planes = Planes::new(&binary, &gray);
+                for (tl, tr, bl, module_size, score) in
+                    crate::pipeline::ranked_candidate_geometry(planes, &patterns)
+                {
+                    candidates.push(SnapshotCandidate {
+                        top_left: tl,
+                        top_right: tr,
+                        bottom_left: bl,
+                        module_size,
+                        score,
+                    });
+                }
+                for c in candidates.iter().take(MAX_SAMPLED_GRIDS) {
+                    if let Some(grid) = QrDecoder::sample_grid(
+                        planes,
+                        &c.top_left,
+                        &c.top_right,
+                        &c.bottom_left,
+                        c.module_size,
+                    ) {
+                        sampled_grids.push(grid);
+                    }
+                }
+            }
+            binarizations.push(SnapshotBinarization {
+                policy: policy.as_str().to_string(),
+                matrix: binary,
+                finder_patterns: patterns.len(),
+            });
+        }
+        let results = crate::detect_from_grayscale(&gray, width, height)
+            .into_iter()
+            .map(|qr| qr.content)
+            .collect();
+        Self {
+            image_width: width,
+            image_height: height,
+            roi: (0, 0, width, height),
+            gray,
+            binarizations,
+            candidates,
+            sampled_grids,
+            results,
+        }
+    }
+
+    /// Re-run the recorded stages on the stored ROI and compare.
+    pub fn replay(&self) -> ReplayReport {
+        let (w, h) = self.roi_size();
+        let fresh = Self::record(self.gray.clone(), w, h);
+        let binarization_mismatches =
+            count_mismatches(&self.binarizations, &fresh.binarizations, |a, b| {
+                a.policy == b.policy && matrices_equal(&a.matrix, &b.matrix)
+            });
+        let candidate_mismatches =
+            count_mismatches(&self.candidates, &fresh.candidates, |a, b| a == b);
+        let grid_mismatches =
+            count_mismatches(&self.sampled_grids, &fresh.sampled_grids, matrices_equal);
+        ReplayReport {
+            binarization_mismatches,
+            candidate_mismatches,
+            grid_mismatches,
+            results_match: self.results == fresh.results,
+            results: fresh.results,
+        }
+    }
+
+    /// Serialize to the deflate-compressed `.rqz` format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.bytes(MAGIC);
+        w.usize(self.image_width);
+        w.usize(self.image_height);
+        for v in [self.roi.0, self.roi.1, self.roi.2, self.roi.3] {
+            w.usize(v);
+        }
+        w.blob(&self.gray);
+        w.usize(self.binarizations.len());
+        for b in &self.binarizations {
+            w.blob(b.policy.as_bytes());
+            w.usize(b.finder_patterns);
+            w.matrix(&b.matrix);
+        }
+        w.usize(self.candidates.len());
+        for c in &self.candidates {
+            for p in [c.top_left, c.top_right, c.bottom_left] {
+                w.f32(p.x);
+                w.f32(p.y);
+            }
+            w.f32(c.module_size);
+            w.f32(c.score);
+        }
+        w.usize(self.sampled_grids.len());
+        for g in &self.sampled_grids {
+            w.matrix(g);
+        }
+        w.usize(self.results.len());
+        for r in &self.results {
+            w.blob(r.as_bytes());
+        }
+        miniz_oxide::deflate::compress_to_vec(&w.buf, 6)
+    }
+
+    /// Parse a bundle produced by [`Snapshot::to_bytes`].
+    ///
+    /// Inflation stops at the size a bundle of the frame recorded in the
+    /// header can reach, so a bundle that inflates past it is rejected
+    /// instead of exhausting memory. Headers claiming a frame whose bundle
+    /// could exceed 256 MiB are rejected before the body is inflated.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let not_deflate = || invalid("bundle is not deflate-compressed");
+        let header = match decompress_to_vec_with_limit(bytes, HEADER_LEN) {
+            Ok(raw) => raw,
+            Err(e) if e.status == TINFLStatus::HasMoreOutput => e.output,
+            Err(_) => return Err(not_deflate()),
+        };
+        let (image_width, image_height) = read_frame_size(&mut Reader {
+            buf: &header,
+            pos: 0,
+        })?;
+        let limit = bundle_limit(image_width, image_height)
+            .ok_or_else(|| invalid("bundle image is too large"))?;
+        let raw = decompress_to_vec_with_limit(bytes, limit).map_err(|e| match e.status {
+            TINFLStatus::HasMoreOutput => invalid("bundle is larger than its image allows"),
+            _ => not_deflate(),
+        })?;
+        let mut r = Reader { buf: &raw, pos: 0 };
+        read_frame_size(&mut r)?;
+        let roi = (r.usize()?, r.usize()?, r.usize()?, r.usize()?);
+        if roi.0 > roi.2 || roi.1 > roi.3 {
+            return Err(invalid("bundle ROI is inverted"));
+        }
+        if roi.2 > image_width || roi.3 > image_height {
+            return Err(invalid("bundle ROI lies outside the image"));
+        }
+        let gray = r.blob()?.to_vec();
+        if gray.len() != (roi.2 - roi.0) * (roi.3 - roi.1) {
+            return Err(invalid("bundle ROI size mismatch"));
+        }
+        let mut binarizations = Vec::new();
+        for _ in 0..r.usize()? {
+            let policy = r.string()?;
+            let finder_patterns = r.usize()?;
+            let matrix = r.matrix()?;
+            binarizations.push(SnapshotBinarization {
+                policy,
+                matrix,
+                finder_patterns,
+            });
+        }
+        let mut candidates = Vec::new();
+        for _ in 0..r.usize()? {
+            let mut pts = [Point::default(); 3];
+            for p in &mut pts {
+                *p = Point::new(r.f32()?, r.f32()?);
+            }
+            candidates.push(SnapshotCandidate {
+                top_left: pts[0],
+                top_right: pts[1],
+                bottom_left: pts[2],
+                module_size: r.f32()?,
+                score: r.f32()?,
+            });
+        }
+        let mut sampled_grids = Vec::new();
+        for _ in 0..r.usize()? {
+            sampled_grids.push(r.matrix()?);
+        }
+        let mut results = Vec::new();
+        for _ in 0..r.usize()? {
+            results.push(r.string()?);
+        }
+        Ok(Self {
+            image_width,
+            image_height,
+            roi,
+            gray,
+            binarizations,
+            candidates,
+            sampled_grids,
+            results,
+        })
+    }
+
+    /// Write the bundle to `path`.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// Read a bundle from `path`.
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+fn best_finder_roi(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let mut best = Vec::new();
+    for policy in crate::phase9_binarization_sequence(width, height) {
+        let binary = crate::binarize_with_policy(gray, width, height, policy);
+        let patterns = crate::detect_finder_patterns(&binary, width, height);
+        if patterns.len() > best.len() {
+            best = patterns;
+        }
+    }
+    crate::finder_roi_bounds(&best, width, height)
+}
+
+fn matrices_equal(a: &BitMatrix, b: &BitMatrix) -> bool {
+    a.width() == b.width() && a.height() == b.height() && a.as_words() == b.as_words()
+}
+
+/// Most inflated bytes a bundle of a `width`x`height` image takes: its gray
+/// ROI is at most a byte per pixel and each binarization a bit per pixel,
+/// within two bytes per pixel for every policy, plus [`BUNDLE_ALLOWANCE`].
+/// `None` past [`MAX_BUNDLE_BYTES`].
+fn bundle_limit(width: usize, height: usize) -> Option<usize> {
+    width
+        .checked_mul(height)?
+        .checked_mul(2)?
+        .checked_add(BUNDLE_ALLOWANCE)
+        .filter(|&limit| limit <= MAX_BUNDLE_BYTES)
+}
+
+/// Check the magic and read the image size that opens every bundle.
+fn read_frame_size(r: &mut Reader<'_>) -> io::Result<(usize, usize)> {
+    if r.take(4)? != MAGIC {
+        return Err(invalid("not a RustQR repro bundle"));
+    }
+    Ok((r.usize()?, r.usize()?))
+}
+
+fn count_mismatches<T>(a: &[T], b: &[T], eq: impl Fn(&T, &T) -> bool) -> usize {
+    let paired = a.iter().zip(b).filter(|(x, y)| !eq(x, y)).count();
+    paired + a.len().abs_diff(b.len())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn bytes(&mut self, b: &[u8]) {
+        self.buf.extend_from_slice(b);
+    }
+
+    fn usize(&mut self, v: usize) {
+        self.bytes(&(v as u64).to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn blob(&mut self, b: &[u8]) {
+        self.usize(b.len());
+        self.bytes(b);
+    }
+
+    fn matrix(&mut self, m: &BitMatrix) {
+        self.usize(m.width());
+        self.usize(m.height());
+        let mut packed = vec![0u8; (m.width() * m.height()).div_ceil(8)];
+        for y in 0..m.height() {
+            for x in 0..m.width() {
+                if m.get(x, y) {
+                    let i = y * m.width() + x;
+                    packed[i / 8] |= 1 << (i % 8);
+                }
+            }
+        }
+        self.blob(&packed);
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|&end| end <= self.buf.len())
+            .ok_or_else(|| invalid("truncated bundle"))?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn usize(&mut self) -> io::Result<usize> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        usize::try_from(u64::from_le_bytes(b)).map_err(|_| invalid("length overflow"))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        let mut b = [0u8; 4];
+        b.copy_from_slice(self.take(4)?);
+        Ok(f32::from_le_bytes(b))
+    }
+
+    fn blob(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.usize()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.blob()?.to_vec()).map_err(|_| invalid("invalid UTF-8"))
+    }
+
+    fn matrix(&mut self) -> io::Result<BitMatrix> {
+        let width = self.usize()?;
+        let height = self.usize()?;
+        let packed = self.blob()?;
+        let cells = width
+            .checked_mul(height)
+            .ok_or_else(|| invalid("matrix size overflow"))?;
+        if packed.len() != cells.div_ceil(8) {
+            return Err(invalid("matrix size mismatch"));
+        }
+        let mut m = BitMatrix::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if packed[i / 8] & (1 << (i % 8)) != 0 {
+                    m.set(x, y, true);
+                }
+            }
+        }
+        Ok(m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn synthetic_gray() -> (Vec<u8>, usize, usize) {
+        let (w, h) = (64, 48);
+        let gray = (0..w * h)
+            .map(|i| {
+                if (i % w / 4 + i / w / 4) % 2 == 0 {
+                    20
+                } else {
+                    230
+                }
+            })
+            .collect();
+        (gray, w, h)
+    }
+
+    #[test]
+    fn bundle_roundtrip_preserves_contents() {
+        let (gray, w, h) = synthetic_gray();
+        let snap = Snapshot::capture(&gray, w, h);
+        let decoded = Snapshot::from_bytes(&snap.to_bytes()).expect("roundtrip");
+        assert_eq!(decoded.roi, snap.roi);
+        assert_eq!(decoded.gray, snap.gray);
+        assert_eq!(decoded.binarizations.len(), snap.binarizations.len());
+        for (a, b) in decoded.binarizations.iter().zip(&snap.binarizations) {
+            assert_eq!(a.policy, b.policy);
+            assert!(matrices_equal(&a.matrix, &b.matrix));
+        }
+        assert_eq!(decoded.candidates, snap.candidates);
+        assert_eq!(decoded.results, snap.results);
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let (gray, w, h) = synthetic_gray();
+        let snap = Snapshot::capture(&gray, w, h);
+        assert!(snap.replay().is_deterministic());
+    }
+
+    #[test]
+    fn rejects_garbage_bundle() {
+        assert!(Snapshot::from_bytes(b"not a bundle").is_err());
+        let truncated = miniz_oxide::deflate::compress_to_vec(b"RQZ1\x01", 6);
+        assert!(Snapshot::from_bytes(&truncated).is_err());
+    }
+
+    #[test]
+    fn rejects_bundle_larger_than_its_image() {
+        let mut w = Writer::default();
+        w.bytes(MAGIC);
+        for v in [1, 1, 0, 0, 1, 1] {
+            w.usize(v);
+        }
+        w.blob(&vec![0; bundle_limit(1, 1).unwrap()]);
+        let bomb = miniz_oxide::deflate::compress_to_vec(&w.buf, 6);
+        let err = Snapshot::from_bytes(&bomb).unwrap_err();
+        assert_eq!(err.to_string(), "bundle is larger than its image allows");
+
+        // A header claiming a huge frame is refused before inflating.
+        let mut w = Writer::default();
+        w.bytes(MAGIC);
+        for v in [usize::MAX, 2, 0, 0, 1, 1] {
+            w.usize(v);
+        }
+        w.blob(&[0]);
+        let bundle = miniz_oxide::deflate::compress_to_vec(&w.buf, 6);
+        let err = Snapshot::from_bytes(&bundle).unwrap_err();
+        assert_eq!(err.to_string(), "bundle image is too large");
+    }
+
+    #[test]
+    fn capture_of_short_buffer_is_empty() {
+        let (gray, w, h) = synthetic_gray();
+        let snap = Snapshot::capture(&gray[1..], w, h);
+        assert_eq!((snap.image_width, snap.roi), (0, (0, 0, 0, 0)));
+        assert!(snap.gray.is_empty() && snap.binarizations.is_empty());
+        assert!(Snapshot::capture(&gray, usize::MAX, 2).gray.is_empty());
+        let decoded = Snapshot::from_bytes(&snap.to_bytes()).expect("roundtrip");
+        assert!(decoded.gray.is_empty());
+        assert!(decoded.replay().is_deterministic());
+    }
+}
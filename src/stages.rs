@@ -0,0 +1,383 @@
+//! Composable detection stages
+//!
+//! Detection as four swappable steps: [`Localize`] proposes finder triplets,
+//! [`Rank`] orders them, [`Sample`] reads a candidate into module grids and
+//! [`Decode`] turns a grid into a code. A [`Pipeline`] runs them in that
+//! order, so a caller can put their own localizer (say, a learned one) in
+//! front of this crate's sampler and decoder without forking it.
+//!
+//! With no stage replaced, [`Pipeline::run`] is [`crate::detect_from_grayscale`],
+//! binarization and recovery fallbacks included. Once any stage is replaced
+//! the four run in sequence on one binarization, the default stages filling
+//! the rest.
+
+use crate::decoder::qr_decoder::QrDecoder;
+use crate::frame::Planes;
+use crate::models::{BitMatrix, GrayFrame, Point, QRCode};
+use crate::utils::geometry::PerspectiveTransform;
+
+/// Candidates the default ranker passes on to sampling.
+const DEFAULT_MAX_CANDIDATES: usize = 40;
+
+/// A grayscale frame with the binarization the default stages work on
+#[derive(Debug)]
+pub struct StageImage<'a> {
+    frame: GrayFrame<'a>,
+    binary: BitMatrix,
+}
+
+impl<'a> StageImage<'a> {
+    /// Binarize `frame` the way detection's first pass does.
+    pub fn new(frame: GrayFrame<'a>) -> Self {
+        let (width, height) = (frame.width(), frame.height());
+        let policy = crate::initial_policy(width, height);
+        let binary = crate::binarize_with_policy(frame.data(), width, height, policy);
+        Self { frame, binary }
+    }
+
+    /// Luma bytes, row by row
+    pub fn gray(&self) -> &'a [u8] {
+        self.frame.data()
+    }
+
+    /// Frame width in pixels
+    pub fn width(&self) -> usize {
+        self.frame.width()
+    }
+
+    /// Frame height in pixels
+    pub fn height(&self) -> usize {
+        self.frame.height()
+    }
+
+    /// Binarized frame, dark modules `true`
+    pub fn binary(&self) -> &BitMatrix {
+        &self.binary
+    }
+
+    pub(crate) fn planes(&self) -> Planes<'_> {
+        Planes::new(&self.binary, self.gray())
+    }
+
+    /// Luma along the lines between `candidate`'s finder centres, the
+    /// scans the default localizer scores stripe alternation on, for
+    /// inspecting ringing, blooming or uneven lighting on real captures.
+    pub fn scan_line_profiles(&self, candidate: &Candidate) -> ScanLineProfiles {
+        let profile = |start: Point, end: Point| ScanLineProfile {
+            start,
+            end,
+            samples: crate::pipeline::line_pixels(&start, &end, self.width(), self.height())
+                .map(|pixel| pixel.map(|(x, y)| self.gray()[y * self.width() + x]))
+                .collect(),
+        };
+        ScanLineProfiles {
+            horizontal: profile(candidate.top_left, candidate.top_right),
+            vertical: profile(candidate.top_left, candidate.bottom_left),
+        }
+    }
+}
+
+/// Grayscale samples along one straight line in a frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanLineProfile {
+    pub start: Point,
+    pub end: Point,
+    /// Luma from `start` to `end` inclusive, one sample per pixel of length
+    /// at the nearest pixel; `None` where the line leaves the frame
+    pub samples: Vec<Option<u8>>,
+}
+
+/// Intensity profiles of a candidate's two finder-to-finder lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanLineProfiles {
+    /// Top-left finder centre to top-right
+    pub horizontal: ScanLineProfile,
+    /// Top-left finder centre to bottom-left
+    pub vertical: ScanLineProfile,
+}
+
+/// Finder pattern centres of one possible symbol, in image coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candidate {
+    pub top_left: Point,
+    pub top_right: Point,
+    pub bottom_left: Point,
+    /// Module pitch in pixels
+    pub module_size: f32,
+    /// Localizer's confidence; the default ranker orders by it, higher first
+    pub score: f32,
+}
+
+impl Candidate {
+    /// Parallelogram through the three finder centres, completed at the
+    /// bottom-right and wound clockwise in image space like
+    /// [`QRCode::outline`]: TL, TR, BR, BL, or TL, BL, BR, TR for a
+    /// mirrored triplet. It spans centre to centre, so it is 7 modules
+    /// smaller than the symbol on each axis.
+    pub fn quad(&self) -> [Point; 4] {
+        let bottom_right = Point::new(
+            self.top_right.x + self.bottom_left.x - self.top_left.x,
+            self.top_right.y + self.bottom_left.y - self.top_left.y,
+        );
+        crate::utils::geometry::clockwise([
+            self.top_left,
+            self.top_right,
+            bottom_right,
+            self.bottom_left,
+        ])
+    }
+}
+
+/// Module grid read from the image for one candidate at one version
+#[derive(Debug, Clone)]
+pub struct SampledGrid {
+    /// Symbol version the grid was sampled at
+    pub version: u8,
+    /// Modules as sampled, dark `true`; not yet oriented
+    pub modules: BitMatrix,
+    /// Per-module confidence (0-255), row-major; empty when unknown
+    pub confidence: Vec<u8>,
+    /// Maps grid coordinates (one unit per module) into the image
+    pub transform: PerspectiveTransform,
+}
+
+/// Proposes finder triplets in a frame
+pub trait Localize {
+    fn localize(&self, image: &StageImage<'_>) -> Vec<Candidate>;
+}
+
+/// Orders and trims candidates before sampling, most promising first
+pub trait Rank {
+    fn rank(&self, image: &StageImage<'_>, candidates: Vec<Candidate>) -> Vec<Candidate>;
+}
+
+/// Reads a candidate into module grids, most plausible first
+pub trait Sample {
+    fn sample(&self, image: &StageImage<'_>, candidate: &Candidate) -> Vec<SampledGrid>;
+}
+
+/// Decodes a sampled grid
+pub trait Decode {
+    fn decode(&self, grid: &SampledGrid) -> Option<QRCode>;
+}
+
+impl<F: Fn(&StageImage<'_>) -> Vec<Candidate>> Localize for F {
+    fn localize(&self, image: &StageImage<'_>) -> Vec<Candidate> {
+        self(image)
+    }
+}
+
+impl<F: Fn(&StageImage<'_>, Vec<Candidate>) -> Vec<Candidate>> Rank for F {
+    fn rank(&self, image: &StageImage<'_>, candidates: Vec<Candidate>) -> Vec<Candidate> {
+        self(image, candidates)
+    }
+}
+
+impl<F: Fn(&StageImage<'_>, &Candidate) -> Vec<SampledGrid>> Sample for F {
+    fn sample(&self, image: &StageImage<'_>, candidate: &Candidate) -> Vec<SampledGrid> {
+        self(image, candidate)
+    }
+}
+
+impl<F: Fn(&SampledGrid) -> Option<QRCode>> Decode for F {
+    fn decode(&self, grid: &SampledGrid) -> Option<QRCode> {
+        self(grid)
+    }
+}
+
+/// Finder pattern scan of the binarized frame, each triplet scored by its
+/// geometry and the grid it implies
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultLocalizer;
+
+impl Localize for DefaultLocalizer {
+    fn localize(&self, image: &StageImage<'_>) -> Vec<Candidate> {
+        let (width, height) = (image.width(), image.height());
+        let patterns = crate::detect_finder_patterns(image.binary(), width, height);
+        if patterns.len() < 3 {
+            return Vec::new();
+        }
+        crate::pipeline::ranked_candidate_geometry(image.planes(), &patterns)
+            .into_iter()
+            .map(
+                |(top_left, top_right, bottom_left, module_size, score)| Candidate {
+                    top_left,
+                    top_right,
+                    bottom_left,
+                    module_size,
+                    score,
+                },
+            )
+            .collect()
+    }
+}
+
+/// Best score first, keeping up to `max_candidates`
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultRanker {
+    pub max_candidates: usize,
+}
+
+impl Default for DefaultRanker {
+    fn default() -> Self {
+        Self {
+            max_candidates: DEFAULT_MAX_CANDIDATES,
+        }
+    }
+}
+
+impl Rank for DefaultRanker {
+    fn rank(&self, _image: &StageImage<'_>, mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(self.max_candidates);
+        candidates
+    }
+}
+
+/// Grayscale sampling at each version the finder spacing allows, through
+/// the bottom-right alignment pattern where there is one, best structured
+/// grid first
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultSampler;
+
+impl Sample for DefaultSampler {
+    fn sample(&self, image: &StageImage<'_>, candidate: &Candidate) -> Vec<SampledGrid> {
+        QrDecoder::sample_version_grids(
+            image.planes(),
+            &candidate.top_left,
+            &candidate.top_right,
+            &candidate.bottom_left,
+            candidate.module_size,
+        )
+        .into_iter()
+        .map(|(version, transform, modules, confidence)| SampledGrid {
+            version,
+            modules,
+            confidence,
+            transform,
+        })
+        .collect()
+    }
+}
+
+/// Format, error correction and data decoding of this crate, the grid read
+/// as sampled and then inverted
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultDecoder;
+
+impl Decode for DefaultDecoder {
+    fn decode(&self, grid: &SampledGrid) -> Option<QRCode> {
+        let dimension = 17 + 4 * grid.version as usize;
+        if grid.modules.width() != dimension || grid.modules.height() != dimension {
+            return None;
+        }
+        let uniform;
+        let confidence = if grid.confidence.len() == dimension * dimension {
+            &grid.confidence
+        } else {
+            uniform = vec![u8::MAX; dimension * dimension];
+            &uniform
+        };
+        QrDecoder::decode_sampled_grid(&grid.modules, grid.version, confidence, &grid.transform)
+    }
+}
+
+/// Detection composed of [`Localize`], [`Rank`], [`Sample`] and [`Decode`]
+/// stages; see the [module docs](self) for what the default composition runs.
+#[derive(Default)]
+pub struct Pipeline {
+    localizer: Option<Box<dyn Localize>>,
+    ranker: Option<Box<dyn Rank>>,
+    sampler: Option<Box<dyn Sample>>,
+    decoder: Option<Box<dyn Decode>>,
+}
+
+impl Pipeline {
+    /// The default composition
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the localizer.
+    pub fn with_localizer(mut self, localizer: impl Localize + 'static) -> Self {
+        self.localizer = Some(Box::new(localizer));
+        self
+    }
+
+    /// Replace the ranker.
+    pub fn with_ranker(mut self, ranker: impl Rank + 'static) -> Self {
+        self.ranker = Some(Box::new(ranker));
+        self
+    }
+
+    /// Replace the sampler.
+    pub fn with_sampler(mut self, sampler: impl Sample + 'static) -> Self {
+        self.sampler = Some(Box::new(sampler));
+        self
+    }
+
+    /// Replace the decoder.
+    pub fn with_decoder(mut self, decoder: impl Decode + 'static) -> Self {
+        self.decoder = Some(Box::new(decoder));
+        self
+    }
+
+    /// Whether every stage is the default.
+    pub fn is_default(&self) -> bool {
+        self.localizer.is_none()
+            && self.ranker.is_none()
+            && self.sampler.is_none()
+            && self.decoder.is_none()
+    }
+
+    /// Detect QR codes in `frame`.
+    ///
+    /// Candidates are tried in ranked order and each yields at most one
+    /// code, from the first of its grids that decodes. A code repeating one
+    /// already found at the same place is dropped.
+    pub fn run(&self, frame: GrayFrame<'_>) -> Vec<QRCode> {
+        if self.is_default() {
+            return crate::detect_from_grayscale(frame.data(), frame.width(), frame.height());
+        }
+        let _frame = crate::decoder::qr_decoder::scoped_decode_counters();
+        let image = StageImage::new(frame);
+        let candidates = match &self.localizer {
+            Some(localizer) => localizer.localize(&image),
+            None => DefaultLocalizer.localize(&image),
+        };
+        let candidates = match &self.ranker {
+            Some(ranker) => ranker.rank(&image, candidates),
+            None => DefaultRanker::default().rank(&image, candidates),
+        };
+        let mut codes: Vec<QRCode> = Vec::new();
+        for candidate in &candidates {
+            let grids = match &self.sampler {
+                Some(sampler) => sampler.sample(&image, candidate),
+                None => DefaultSampler.sample(&image, candidate),
+            };
+            let decoded = grids.iter().find_map(|grid| match &self.decoder {
+                Some(decoder) => decoder.decode(grid),
+                None => DefaultDecoder.decode(grid),
+            });
+            if let Some(qr) = decoded
+                && !codes.iter().any(|c| same_code(c, &qr))
+            {
+                codes.push(qr);
+            }
+        }
+        codes
+    }
+}
+
+/// Same payload with centres closer than half the symbol's side.
+fn same_code(a: &QRCode, b: &QRCode) -> bool {
+    let centre = |qr: &QRCode| {
+        let (x, y) = qr
+            .position
+            .iter()
+            .fold((0.0, 0.0), |(x, y), p| (x + p.x / 4.0, y + p.y / 4.0));
+        Point::new(x, y)
+    };
+    let side = a.position[0].distance(&a.position[1]);
+    a.data == b.data && centre(a).distance(&centre(b)) < side / 2.0
+}
@@ -0,0 +1,432 @@
+//! Stage-grouped view of [`DetectionTelemetry`]
+//!
+//! [`DetectionTelemetry`] keeps one flat counter per pipeline hook. For
+//! reporting, [`DetectionTelemetry::stages`] folds them into one
+//! [`StageStats`] per stage (attempts, successes and time, with derived
+//! success rates and costs), [`TelemetryStages::merge`] sums images into a
+//! dataset total, and [`TelemetryStages::summary`] renders the table
+//! `qrtool reading-rate` prints. [`ImageCondition`] names the condition the
+//! router's fast signals label an image with. [`FailedGrid`] keeps the
+//! grids that sampled cleanly but would not decode, for mining hard cases.
+
+use std::fmt::Write;
+
+use crate::DetectionTelemetry;
+use crate::error::DecodeFailure;
+use crate::models::{BitMatrix, ImagePoint};
+
+/// Attempts, successes and time of one pipeline stage
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageStats {
+    /// Times the stage ran
+    pub attempts: usize,
+    /// Runs that produced what the next stage needs
+    pub successes: usize,
+    /// Wall-clock time spent in the stage (ms); 0 when not timed
+    pub time_ms: f64,
+}
+
+impl StageStats {
+    fn new(attempts: usize, successes: usize, time_ms: f64) -> Self {
+        Self {
+            attempts,
+            successes,
+            time_ms,
+        }
+    }
+
+    /// Fraction of attempts that succeeded, `None` before any attempt
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.successes as f64 / self.attempts as f64)
+    }
+
+    /// Mean time per attempt (ms), `None` before any attempt
+    pub fn ms_per_attempt(&self) -> Option<f64> {
+        (self.attempts > 0).then(|| self.time_ms / self.attempts as f64)
+    }
+
+    /// Time spent per success (ms), failures included; `None` before any
+    /// success
+    pub fn ms_per_success(&self) -> Option<f64> {
+        (self.successes > 0).then(|| self.time_ms / self.successes as f64)
+    }
+
+    /// Add another image's or dataset's counts to these
+    pub fn merge(&mut self, other: &Self) {
+        self.attempts += other.attempts;
+        self.successes += other.successes;
+        self.time_ms += other.time_ms;
+    }
+}
+
+/// Recovery paths tried after the ranked candidates fail
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecoveryStages {
+    /// ROI-local contrast normalization and re-decode
+    pub roi_norm: StageStats,
+    /// Guessing the third finder from two
+    pub two_finder: StageStats,
+    /// Re-thresholding a grid that failed only at error correction
+    pub threshold_sweep: StageStats,
+    /// Deskewed re-sampling
+    pub deskew: StageStats,
+    /// Multi-scale retries
+    pub scale_retry: StageStats,
+    /// High-version grid refinement
+    pub high_version_refine: StageStats,
+    /// Iterative grid refinement
+    pub grid_refine: StageStats,
+    /// Reed-Solomon decoding with erasures
+    pub rs_erasure: StageStats,
+    /// Time in all recovery paths together (ms), also part of the decode
+    /// stage's time
+    pub time_ms: f64,
+}
+
+impl RecoveryStages {
+    /// Each recovery path with its name, in pipeline order
+    pub fn paths(&self) -> [(&'static str, &StageStats); 8] {
+        [
+            ("roi_norm", &self.roi_norm),
+            ("two_finder", &self.two_finder),
+            ("threshold_sweep", &self.threshold_sweep),
+            ("deskew", &self.deskew),
+            ("scale_retry", &self.scale_retry),
+            ("hv_refine", &self.high_version_refine),
+            ("grid_refine", &self.grid_refine),
+            ("rs_erasure", &self.rs_erasure),
+        ]
+    }
+
+    /// Add another image's or dataset's counts to these
+    pub fn merge(&mut self, other: &Self) {
+        self.roi_norm.merge(&other.roi_norm);
+        self.two_finder.merge(&other.two_finder);
+        self.threshold_sweep.merge(&other.threshold_sweep);
+        self.deskew.merge(&other.deskew);
+        self.scale_retry.merge(&other.scale_retry);
+        self.high_version_refine.merge(&other.high_version_refine);
+        self.grid_refine.merge(&other.grid_refine);
+        self.rs_erasure.merge(&other.rs_erasure);
+        self.time_ms += other.time_ms;
+    }
+}
+
+/// The condition an image's fast signals point to, named after the
+/// benchmark category it would be filed under, so unlabelled traffic can be
+/// bucketed the same way as the dataset.
+///
+/// Reported in [`DetectionTelemetry::image_condition`] by name, for images
+/// that reached the strategy router.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageCondition {
+    /// One symbol past ~57 modules (version 10)
+    HighVersion,
+    /// Several symbols in the frame
+    Multi,
+    /// A screenshot or render: flat tones on the pixel grid
+    Synthetic,
+    /// The symbol leans well off the image axes
+    Rotated,
+    /// A share of clipped highlights
+    Glare,
+    /// Little high-frequency detail
+    Blurry,
+    /// A narrow gray-level span: under- or over-exposed
+    LowContrast,
+    /// None of the above
+    Nominal,
+}
+
+impl ImageCondition {
+    /// Every condition, in the order the classifier checks them
+    pub const ALL: [ImageCondition; 8] = [
+        ImageCondition::HighVersion,
+        ImageCondition::Multi,
+        ImageCondition::Synthetic,
+        ImageCondition::Rotated,
+        ImageCondition::Glare,
+        ImageCondition::Blurry,
+        ImageCondition::LowContrast,
+        ImageCondition::Nominal,
+    ];
+
+    /// The benchmark category directory this condition matches
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageCondition::HighVersion => "high_version",
+            ImageCondition::Multi => "lots",
+            ImageCondition::Synthetic => "synthetic",
+            ImageCondition::Rotated => "rotations",
+            ImageCondition::Glare => "glare",
+            ImageCondition::Blurry => "blurred",
+            ImageCondition::LowContrast => "brightness",
+            ImageCondition::Nominal => "nominal",
+        }
+    }
+
+    /// The condition [`Self::name`] gives `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// Slot in [`Self::ALL`]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Telemetry of one or more images, grouped by pipeline stage.
+///
+/// The image-level stages (grayscale through transform) count images: an
+/// attempt per image, a success per image that got through the stage.
+/// Decode, rerank and recovery count individual candidate attempts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TelemetryStages {
+    /// Images summarized
+    pub images: usize,
+    /// RGB to grayscale conversion
+    pub grayscale: StageStats,
+    /// Binarization, succeeding when it produced a non-empty matrix
+    pub binarize: StageStats,
+    /// Finder scanning, succeeding with at least three patterns
+    pub finder_scan: StageStats,
+    /// Grouping and ranking finder triplets, succeeding with a group
+    pub grouping: StageStats,
+    /// Building a sampling transform for a group
+    pub transform: StageStats,
+    /// Candidate decode attempts and the codes they produced
+    pub decode: StageStats,
+    /// Decodes of the top-ranked candidate
+    pub rerank_top1: StageStats,
+    /// Recovery paths
+    pub recovery: RecoveryStages,
+    /// Whole-call time (ms)
+    pub total_time_ms: f64,
+}
+
+impl TelemetryStages {
+    /// Add another image's or dataset's counts to these
+    pub fn merge(&mut self, other: &Self) {
+        self.images += other.images;
+        self.grayscale.merge(&other.grayscale);
+        self.binarize.merge(&other.binarize);
+        self.finder_scan.merge(&other.finder_scan);
+        self.grouping.merge(&other.grouping);
+        self.transform.merge(&other.transform);
+        self.decode.merge(&other.decode);
+        self.rerank_top1.merge(&other.rerank_top1);
+        self.recovery.merge(&other.recovery);
+        self.total_time_ms += other.total_time_ms;
+    }
+
+    /// Mean whole-call time per image (ms), `None` before any image
+    pub fn ms_per_image(&self) -> Option<f64> {
+        (self.images > 0).then(|| self.total_time_ms / self.images as f64)
+    }
+
+    /// One row per stage with its attempts, successes, success rate, total
+    /// time and time per attempt; recovery paths are indented under their
+    /// combined time.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<16} {:>9} {:>9} {:>8} {:>10} {:>9}",
+            "Stage", "Attempts", "Successes", "Rate", "Time ms", "ms/try"
+        );
+        let stages = [
+            ("grayscale", &self.grayscale),
+            ("binarize", &self.binarize),
+            ("finder_scan", &self.finder_scan),
+            ("grouping", &self.grouping),
+            ("transform", &self.transform),
+            ("decode", &self.decode),
+            ("rerank_top1", &self.rerank_top1),
+        ];
+        for (name, stage) in stages {
+            stage_row(&mut out, name, stage);
+        }
+        let _ = writeln!(
+            out,
+            "{:<16} {:>9} {:>9} {:>8} {:>10.2} {:>9}",
+            "recovery", "", "", "", self.recovery.time_ms, ""
+        );
+        for (name, stage) in self.recovery.paths() {
+            stage_row(&mut out, &format!("  {name}"), stage);
+        }
+        let _ = writeln!(
+            out,
+            "{} images, {:.2} ms total, {} ms/image",
+            self.images,
+            self.total_time_ms,
+            fmt_opt(self.ms_per_image(), 2)
+        );
+        out
+    }
+}
+
+fn stage_row(out: &mut String, name: &str, stage: &StageStats) {
+    let rate = stage
+        .success_rate()
+        .map_or_else(|| "-".to_string(), |r| format!("{:.1}%", r * 100.0));
+    let time = if stage.time_ms > 0.0 {
+        format!("{:.2}", stage.time_ms)
+    } else {
+        "-".to_string()
+    };
+    let per_try = if stage.time_ms > 0.0 {
+        fmt_opt(stage.ms_per_attempt(), 3)
+    } else {
+        "-".to_string()
+    };
+    let _ = writeln!(
+        out,
+        "{:<16} {:>9} {:>9} {:>8} {:>10} {:>9}",
+        name, stage.attempts, stage.successes, rate, time, per_try
+    );
+}
+
+fn fmt_opt(value: Option<f64>, precision: usize) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{v:.precision$}"))
+}
+
+/// A grid that passed timing validation and read as far as error
+/// correction or payload parsing, then failed; kept in
+/// [`DetectionTelemetry::failed_grids`] when
+/// [`crate::DetectorConfig::export_failed_grids`] is set
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailedGrid {
+    /// Symbol version the grid was sampled at
+    pub version: u8,
+    /// Modules as sampled, dark `true`; not yet oriented or unmasked
+    pub modules: BitMatrix,
+    /// Per-module confidence (0-255), row-major
+    pub confidence: Vec<u8>,
+    /// Grid-to-image homography, row-major, one unit per module (see
+    /// [`crate::utils::geometry::PerspectiveTransform::coefficients`]); a
+    /// configured lens model is not included
+    pub transform: [f32; 9],
+    /// Grid corners `(0, 0)`, `(d, 0)`, `(d, d)`, `(0, d)` in image pixels,
+    /// `d` being the grid dimension
+    pub corners: [ImagePoint; 4],
+    /// Stage the decode stopped at: `ReedSolomon` or `Payload`
+    pub failure: DecodeFailure,
+}
+
+impl DetectionTelemetry {
+    /// This image's counters grouped by stage
+    pub fn stages(&self) -> TelemetryStages {
+        let image = |ok: bool, time_ms: f64| StageStats::new(1, ok as usize, time_ms);
+        TelemetryStages {
+            images: 1,
+            grayscale: image(true, self.time_grayscale_ms),
+            binarize: image(self.binarize_ok, self.time_binarize_ms),
+            finder_scan: image(self.finder_patterns_found >= 3, self.time_finder_scan_ms),
+            grouping: image(self.groups_found >= 1, self.time_group_rank_ms),
+            transform: image(self.transforms_built >= 1, 0.0),
+            decode: StageStats::new(
+                self.decode_attempts,
+                self.qr_codes_found,
+                self.time_decode_ms,
+            ),
+            rerank_top1: StageStats::new(
+                self.rerank_top1_attempts,
+                self.rerank_top1_successes,
+                0.0,
+            ),
+            recovery: RecoveryStages {
+                roi_norm: StageStats::new(self.roi_norm_attempts, self.roi_norm_successes, 0.0),
+                two_finder: StageStats::new(
+                    self.two_finder_attempts,
+                    self.two_finder_successes,
+                    0.0,
+                ),
+                threshold_sweep: StageStats::new(
+                    self.threshold_sweep_attempts,
+                    self.threshold_sweep_successes,
+                    0.0,
+                ),
+                deskew: StageStats::new(self.deskew_attempts, self.deskew_successes, 0.0),
+                scale_retry: StageStats::new(
+                    self.scale_retry_attempts,
+                    self.scale_retry_successes,
+                    0.0,
+                ),
+                high_version_refine: StageStats::new(
+                    self.hv_refine_attempts,
+                    self.hv_refine_successes,
+                    0.0,
+                ),
+                grid_refine: StageStats::new(
+                    self.grid_refine_attempts,
+                    self.grid_refine_successes,
+                    0.0,
+                ),
+                rs_erasure: StageStats::new(
+                    self.rs_erasure_attempts,
+                    self.rs_erasure_successes,
+                    0.0,
+                ),
+                time_ms: self.time_recovery_ms,
+            },
+            total_time_ms: self.time_total_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_merge_and_derive_rates() {
+        let hit = DetectionTelemetry {
+            binarize_ok: true,
+            finder_patterns_found: 3,
+            groups_found: 1,
+            transforms_built: 1,
+            decode_attempts: 2,
+            qr_codes_found: 1,
+            deskew_attempts: 1,
+            time_decode_ms: 6.0,
+            time_total_ms: 10.0,
+            ..Default::default()
+        };
+        let miss = DetectionTelemetry {
+            binarize_ok: true,
+            finder_patterns_found: 1,
+            decode_attempts: 2,
+            deskew_attempts: 1,
+            deskew_successes: 1,
+            time_decode_ms: 2.0,
+            time_total_ms: 20.0,
+            ..Default::default()
+        };
+        let mut stages = hit.stages();
+        stages.merge(&miss.stages());
+
+        assert_eq!(stages.images, 2);
+        assert_eq!(stages.finder_scan.success_rate(), Some(0.5));
+        assert_eq!(stages.decode.attempts, 4);
+        assert_eq!(stages.decode.ms_per_attempt(), Some(2.0));
+        assert_eq!(stages.decode.ms_per_success(), Some(8.0));
+        assert_eq!(stages.recovery.deskew.success_rate(), Some(0.5));
+        assert_eq!(stages.recovery.rs_erasure.success_rate(), None);
+        assert_eq!(stages.ms_per_image(), Some(15.0));
+
+        let summary = stages.summary();
+        let row = |name: &str| -> Vec<&str> {
+            let line = summary.lines().find(|l| l.starts_with(name)).unwrap();
+            line[name.len()..].split_whitespace().collect()
+        };
+        assert_eq!(row("finder_scan"), ["2", "1", "50.0%", "-", "-"]);
+        assert_eq!(row("decode"), ["4", "1", "25.0%", "8.00", "2.000"]);
+        assert_eq!(row("  deskew"), ["2", "1", "50.0%", "-", "-"]);
+        assert!(summary.ends_with("2 images, 30.00 ms total, 15.00 ms/image\n"));
+    }
+}
@@ -0,0 +1,273 @@
+//! ZXing- and BoofCV-shaped JSON for detection results
+//!
+//! `qrtool detect --format zxing-json|boofcv-json` prints decoded codes in the
+//! field layouts of ZXing's `Result` and BoofCV's `QrCode`, so harnesses that
+//! already compare those libraries can read RustQR output as-is. Codeword
+//! fields need the codes to be decoded with
+//! [`DetectorConfig::keep_intermediate_data`](crate::DetectorConfig); without
+//! it they come out empty.
+
+use super::per_image::escape;
+use crate::decoder::format::FormatInfo;
+use crate::decoder::function_mask::FunctionMask;
+use crate::decoder::qr_decoder::{DecodeSession, SegmentMode};
+use crate::decoder::tables::ec_block_info;
+use crate::decoder::unmask::unmask;
+use crate::models::{Point, QRCode};
+use crate::utils::geometry::PerspectiveTransform;
+use std::fmt::Write as _;
+
+/// JSON array of ZXing `Result` objects, one per code.
+///
+/// `resultPoints` follow ZXing's QR order: bottom-left, top-left and
+/// top-right finder centres, then the bottom-right alignment pattern for
+/// version 2 and up. `rawBytes` holds the corrected data codewords in hex.
+pub fn zxing_json(codes: &[QRCode]) -> String {
+    let mut json = String::from("[");
+    for (i, qr) in codes.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let d = qr.version.size() as f32;
+        let mut points = vec![(3.5, d - 3.5), (3.5, 3.5), (d - 3.5, 3.5)];
+        if qr.version.number() >= 2 {
+            points.push((d - 6.5, d - 6.5));
+        }
+        let data = data_codewords(qr);
+        let _ = write!(
+            json,
+            "\n  {{\"text\": \"{}\", \"rawBytes\": \"{}\", \"numBits\": {}, \"format\": \"QR_CODE\", \"resultPoints\": [",
+            escape(&qr.content),
+            hex(&data),
+            data.len() * 8
+        );
+        for (j, &(mx, my)) in points.iter().enumerate() {
+            let p = symbol_point(qr, mx, my);
+            let sep = if j > 0 { ", " } else { "" };
+            let _ = write!(json, "{sep}{{\"x\": {:.2}, \"y\": {:.2}}}", p.x, p.y);
+        }
+        let _ = write!(
+            json,
+            "], \"resultMetadata\": {{\"ERROR_CORRECTION_LEVEL\": \"{:?}\", \"ERRORS_CORRECTED\": {}, \"SYMBOLOGY_IDENTIFIER\": \"]Q1\"}}}}",
+            qr.error_correction,
+            codeword_errors(qr)
+        );
+    }
+    json.push_str(if codes.is_empty() { "]" } else { "\n]" });
+    json
+}
+
+/// JSON array of BoofCV `QrCode` objects, one per code.
+///
+/// `bounds` and the three position-pattern quads (`ppCorner`, `ppRight`,
+/// `ppDown`) list corners top-left, top-right, bottom-right, bottom-left in
+/// the symbol's frame. `rawbits` are the codewords as read and `corrected`
+/// the data codewords after error correction, both in hex.
+pub fn boofcv_json(codes: &[QRCode]) -> String {
+    let mut json = String::from("[");
+    for (i, qr) in codes.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let d = qr.version.size() as f32;
+        let raw = qr.raw_codewords().unwrap_or_default();
+        let _ = write!(
+            json,
+            "\n  {{\"message\": \"{}\", \"version\": {}, \"error\": \"{:?}\", \"mask\": \"M{:03b}\", \"mode\": \"{}\", \"bounds\": {}, \"ppCorner\": {}, \"ppRight\": {}, \"ppDown\": {}, \"rawbits\": \"{}\", \"corrected\": \"{}\", \"totalBitErrors\": {}}}",
+            escape(&qr.content),
+            qr.version.number(),
+            qr.error_correction,
+            qr.mask_pattern as u8,
+            boofcv_mode(qr),
+            quad(qr, 0.0, 0.0, d),
+            quad(qr, 0.0, 0.0, 7.0),
+            quad(qr, d - 7.0, 0.0, 7.0),
+            quad(qr, 0.0, d - 7.0, 7.0),
+            hex(raw),
+            hex(&data_codewords(qr)),
+            bit_errors(qr)
+        );
+    }
+    json.push_str(if codes.is_empty() { "]" } else { "\n]" });
+    json
+}
+
+/// Image position of module coordinate `(mx, my)`, mapped through the
+/// symbol's corner quad.
+fn symbol_point(qr: &QRCode, mx: f32, my: f32) -> Point {
+    let d = qr.version.size() as f32;
+    let src = [
+        Point::new(0.0, 0.0),
+        Point::new(d, 0.0),
+        Point::new(d, d),
+        Point::new(0.0, d),
+    ];
+    PerspectiveTransform::from_points(&src, &qr.position)
+        .map(|t| t.transform(&Point::new(mx, my)))
+        .unwrap_or_default()
+}
+
+/// `[[x, y], ...]` corners of the `size`-module square at `(mx, my)`.
+fn quad(qr: &QRCode, mx: f32, my: f32, size: f32) -> String {
+    let corners = [
+        (mx, my),
+        (mx + size, my),
+        (mx + size, my + size),
+        (mx, my + size),
+    ];
+    let mut out = String::from("[");
+    for (i, &(x, y)) in corners.iter().enumerate() {
+        let p = symbol_point(qr, x, y);
+        let sep = if i > 0 { ", " } else { "" };
+        let _ = write!(out, "{sep}[{:.2}, {:.2}]", p.x, p.y);
+    }
+    out.push(']');
+    out
+}
+
+/// Corrected data codewords in block order, as the payload parser reads them.
+fn data_codewords(qr: &QRCode) -> Vec<u8> {
+    let (Some(corrected), Some(info)) = (
+        qr.corrected_codewords(),
+        ec_block_info(qr.version.number(), qr.error_correction),
+    ) else {
+        return Vec::new();
+    };
+    let mut blocks: Vec<Vec<u8>> = vec![Vec::new(); info.num_blocks];
+    let mut symbols = corrected.iter().take(info.data_codewords);
+    for i in 0..info.data_len(info.num_blocks - 1) {
+        for (b, block) in blocks.iter_mut().enumerate() {
+            if i < info.data_len(b)
+                && let Some(&codeword) = symbols.next()
+            {
+                block.push(codeword);
+            }
+        }
+    }
+    blocks.concat()
+}
+
+fn codeword_errors(qr: &QRCode) -> usize {
+    match (qr.raw_codewords(), qr.corrected_codewords()) {
+        (Some(raw), Some(corrected)) => raw.iter().zip(corrected).filter(|(a, b)| a != b).count(),
+        _ => 0,
+    }
+}
+
+fn bit_errors(qr: &QRCode) -> u32 {
+    match (qr.raw_codewords(), qr.corrected_codewords()) {
+        (Some(raw), Some(corrected)) => raw
+            .iter()
+            .zip(corrected)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// BoofCV `QrCode.Mode` name: the single segment mode, `MIXED` for several,
+/// `UNKNOWN` when segments cannot be re-read from the kept modules.
+fn boofcv_mode(qr: &QRCode) -> &'static str {
+    let Some(unmasked) = qr.unmasked_modules() else {
+        return "UNKNOWN";
+    };
+    let version = qr.version.number();
+    let mut masked = unmasked.clone();
+    unmask(&mut masked, &qr.mask_pattern, &FunctionMask::new(version));
+    let format = FormatInfo {
+        ec_level: qr.error_correction,
+        mask_pattern: qr.mask_pattern,
+    };
+    let mut session = DecodeSession::new(masked, version).with_format(format);
+    let Ok(segments) = session.parse_segments() else {
+        return "UNKNOWN";
+    };
+    let mut modes = segments
+        .iter()
+        .map(|s| s.mode)
+        .filter(|m| !matches!(m, SegmentMode::Eci(_) | SegmentMode::StructuredAppend(_)));
+    let Some(first) = modes.next() else {
+        return if segments.is_empty() {
+            "UNKNOWN"
+        } else {
+            "ECI"
+        };
+    };
+    if modes.any(|m| m != first) {
+        return "MIXED";
+    }
+    match first {
+        SegmentMode::Numeric => "NUMERIC",
+        SegmentMode::Alphanumeric => "ALPHANUMERIC",
+        SegmentMode::Byte => "BYTE",
+        SegmentMode::Kanji => "KANJI",
+        SegmentMode::Eci(_) | SegmentMode::StructuredAppend(_) => "ECI",
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        let _ = write!(out, "{b:02x}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BitMatrix, ECLevel, IntermediateData, MaskPattern, Version};
+
+    fn code() -> QRCode {
+        let mut qr = QRCode::new(
+            b"A\"1".to_vec(),
+            "A\"1".to_string(),
+            Version::Model2(1),
+            ECLevel::L,
+            MaskPattern::Pattern3,
+        );
+        // 21 modules at 10px each, upright at (100, 50).
+        qr.position = [
+            Point::new(100.0, 50.0),
+            Point::new(310.0, 50.0),
+            Point::new(310.0, 260.0),
+            Point::new(100.0, 260.0),
+        ];
+        qr
+    }
+
+    #[test]
+    fn zxing_points_follow_finder_order() {
+        let json = zxing_json(&[code()]);
+        assert!(json.contains("\"text\": \"A\\\"1\""));
+        assert!(json.contains(
+            "\"resultPoints\": [{\"x\": 135.00, \"y\": 225.00}, {\"x\": 135.00, \"y\": 85.00}, {\"x\": 275.00, \"y\": 85.00}]"
+        ));
+        assert!(json.contains("\"rawBytes\": \"\", \"numBits\": 0"));
+        assert!(json.contains("\"ERROR_CORRECTION_LEVEL\": \"L\""));
+        assert_eq!(zxing_json(&[]), "[]");
+    }
+
+    #[test]
+    fn boofcv_reports_quads_and_codeword_errors() {
+        let mut qr = code();
+        let corrected: Vec<u8> = (0..26).collect();
+        let mut raw = corrected.clone();
+        raw[0] ^= 0b101;
+        raw[20] ^= 0x80;
+        qr.intermediate = Some(Box::new(IntermediateData {
+            raw_codewords: raw,
+            corrected_codewords: corrected,
+            unmasked_modules: BitMatrix::new(21, 21),
+        }));
+        let json = boofcv_json(&[qr.clone()]);
+        assert!(json.contains("\"mask\": \"M011\""));
+        assert!(json.contains(
+            "\"ppRight\": [[240.00, 50.00], [310.00, 50.00], [310.00, 120.00], [240.00, 120.00]]"
+        ));
+        assert!(json.contains("\"totalBitErrors\": 3"));
+        // v1-L has 19 data codewords in a single block.
+        assert_eq!(data_codewords(&qr), (0..19).collect::<Vec<u8>>());
+        assert!(zxing_json(&[qr]).contains("\"numBits\": 152"));
+    }
+}
@@ -0,0 +1,276 @@
+//! Minimal QR symbol encoder for generated test images
+//!
+//! Builds a single-segment Model 2 symbol at a caller-chosen version, EC
+//! level and mask, laid out with the decoder's own tables and function mask
+//! so generated corpora track what the reader expects. There is no mode
+//! optimisation or mask scoring: every parameter is the caller's.
+
+use crate::decoder::function_mask::{FunctionMask, alignment_pattern_positions};
+use crate::decoder::qr_decoder::SegmentMode;
+use crate::decoder::reed_solomon::Gf256;
+use crate::decoder::tables::{char_count_bits, ec_block_info};
+use crate::models::{BitMatrix, ECLevel, MaskPattern};
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// Encode `payload` as one `mode` segment in a `version` symbol.
+///
+/// Returns the module grid without quiet zone, dark modules `true`, or
+/// `None` when the version is out of range, `mode` is not numeric,
+/// alphanumeric or byte, `payload` has characters `mode` can't hold, or it
+/// doesn't fit.
+pub fn encode(
+    payload: &str,
+    mode: SegmentMode,
+    version: u8,
+    ec_level: ECLevel,
+    mask: MaskPattern,
+) -> Option<BitMatrix> {
+    let info = ec_block_info(version, ec_level)?;
+    let bits = segment_bits(payload, mode, version)?;
+    if bits.len() > info.data_bits() {
+        return None;
+    }
+    let data = pad_codewords(bits, info.data_codewords);
+
+    let mut blocks = Vec::with_capacity(info.num_blocks);
+    let mut offset = 0;
+    for index in 0..info.num_blocks {
+        let len = info.data_len(index);
+        let block = &data[offset..offset + len];
+        blocks.push((block, ecc_codewords(block, info.ecc_per_block)));
+        offset += len;
+    }
+    let mut codewords = Vec::with_capacity(info.total_codewords);
+    for i in 0..info.short_block_data_len() + 1 {
+        codewords.extend(blocks.iter().filter_map(|(d, _)| d.get(i)));
+    }
+    for j in 0..info.ecc_per_block {
+        codewords.extend(blocks.iter().map(|(_, e)| e[j]));
+    }
+
+    let size = 17 + 4 * version as usize;
+    let mut matrix = BitMatrix::new(size, size);
+    draw_function_patterns(&mut matrix, version);
+    draw_format(&mut matrix, ec_level, mask);
+    draw_codewords(&mut matrix, &FunctionMask::new(version), &codewords, mask);
+    Some(matrix)
+}
+
+fn push_bits(bits: &mut Vec<bool>, value: u32, count: usize) {
+    bits.extend((0..count).rev().map(|i| (value >> i) & 1 == 1));
+}
+
+/// Mode indicator, character count and data bits of a single segment.
+fn segment_bits(payload: &str, mode: SegmentMode, version: u8) -> Option<Vec<bool>> {
+    let mut bits = Vec::new();
+    let (indicator, count) = match mode {
+        SegmentMode::Numeric => (0b0001, payload.len()),
+        SegmentMode::Alphanumeric => (0b0010, payload.len()),
+        SegmentMode::Byte => (0b0100, payload.len()),
+        _ => return None,
+    };
+    let count_bits = char_count_bits(mode, version);
+    if count >= 1 << count_bits {
+        return None;
+    }
+    push_bits(&mut bits, indicator, 4);
+    push_bits(&mut bits, count as u32, count_bits);
+    match mode {
+        SegmentMode::Numeric => {
+            if !payload.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            for chunk in payload.as_bytes().chunks(3) {
+                let value = chunk.iter().fold(0, |v, &b| v * 10 + u32::from(b - b'0'));
+                push_bits(&mut bits, value, chunk.len() * 3 + 1);
+            }
+        }
+        SegmentMode::Alphanumeric => {
+            let values = payload
+                .bytes()
+                .map(|b| ALPHANUMERIC.iter().position(|&c| c == b).map(|i| i as u32))
+                .collect::<Option<Vec<_>>>()?;
+            for pair in values.chunks(2) {
+                match pair {
+                    [a, b] => push_bits(&mut bits, a * 45 + b, 11),
+                    [a] => push_bits(&mut bits, *a, 6),
+                    _ => unreachable!(),
+                }
+            }
+        }
+        _ => {
+            for &b in payload.as_bytes() {
+                push_bits(&mut bits, u32::from(b), 8);
+            }
+        }
+    }
+    Some(bits)
+}
+
+/// Terminator, byte alignment and the alternating 0xEC/0x11 pad codewords.
+fn pad_codewords(mut bits: Vec<bool>, data_codewords: usize) -> Vec<u8> {
+    let capacity = data_codewords * 8;
+    let terminator = (capacity - bits.len()).min(4);
+    bits.resize(bits.len() + terminator, false);
+    bits.resize(bits.len().div_ceil(8) * 8, false);
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0, |v, &b| (v << 1) | u8::from(b)))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= data_codewords {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Reed-Solomon EC codewords: the remainder of `data` divided by the
+/// generator polynomial with roots 2^0..2^(degree-1).
+fn ecc_codewords(data: &[u8], degree: usize) -> Vec<u8> {
+    let mut generator = vec![0u8; degree];
+    generator[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            generator[j] = Gf256::mul(generator[j], root);
+            if j + 1 < degree {
+                generator[j] ^= generator[j + 1];
+            }
+        }
+        root = Gf256::mul(root, 0x02);
+    }
+
+    let mut remainder = vec![0u8; degree];
+    for &b in data {
+        let factor = b ^ remainder[0];
+        remainder.rotate_left(1);
+        remainder[degree - 1] = 0;
+        for (r, &g) in remainder.iter_mut().zip(&generator) {
+            *r ^= Gf256::mul(g, factor);
+        }
+    }
+    remainder
+}
+
+/// Finder, timing and alignment patterns, version information and the dark
+/// module. Separators and the format area stay light.
+fn draw_function_patterns(matrix: &mut BitMatrix, version: u8) {
+    let size = matrix.width();
+    for i in 8..size - 8 {
+        matrix.set(6, i, i % 2 == 0);
+        matrix.set(i, 6, i % 2 == 0);
+    }
+    for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+        for dy in 0..7 {
+            for dx in 0..7 {
+                let ring = (dx as i32 - 3).abs().max((dy as i32 - 3).abs());
+                matrix.set(cx - 3 + dx, cy - 3 + dy, ring != 2);
+            }
+        }
+    }
+
+    let align = alignment_pattern_positions(version);
+    for &cx in &align {
+        for &cy in &align {
+            let in_tl = cx <= 8 && cy <= 8;
+            let in_tr = cx >= size - 9 && cy <= 8;
+            let in_bl = cx <= 8 && cy >= size - 9;
+            if in_tl || in_tr || in_bl {
+                continue;
+            }
+            for dy in 0..5 {
+                for dx in 0..5 {
+                    let ring = (dx as i32 - 2).abs().max((dy as i32 - 2).abs());
+                    matrix.set(cx - 2 + dx, cy - 2 + dy, ring != 1);
+                }
+            }
+        }
+    }
+
+    if version >= 7 {
+        let mut rem = u32::from(version);
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+        }
+        let bits = (u32::from(version) << 12) | rem;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 == 1;
+            let (a, b) = (size - 11 + i % 3, i / 3);
+            matrix.set(a, b, dark);
+            matrix.set(b, a, dark);
+        }
+    }
+
+    matrix.set(8, size - 8, true);
+}
+
+/// Both copies of the 15-bit format information.
+fn draw_format(matrix: &mut BitMatrix, ec_level: ECLevel, mask: MaskPattern) {
+    let ec_bits: u32 = match ec_level {
+        ECLevel::L => 1,
+        ECLevel::M => 0,
+        ECLevel::Q => 3,
+        ECLevel::H => 2,
+    };
+    let data = (ec_bits << 3) | mask as u32;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    let bits = ((data << 10) | rem) ^ 0x5412;
+    let bit = |i: usize| (bits >> i) & 1 == 1;
+
+    let size = matrix.width();
+    for i in 0..6 {
+        matrix.set(8, i, bit(i));
+    }
+    matrix.set(8, 7, bit(6));
+    matrix.set(8, 8, bit(7));
+    matrix.set(7, 8, bit(8));
+    for i in 9..15 {
+        matrix.set(14 - i, 8, bit(i));
+    }
+    for i in 0..8 {
+        matrix.set(size - 1 - i, 8, bit(i));
+    }
+    for i in 8..15 {
+        matrix.set(8, size - 15 + i, bit(i));
+    }
+}
+
+/// Codeword bits in the zigzag order the reader extracts them, masked.
+fn draw_codewords(
+    matrix: &mut BitMatrix,
+    func: &FunctionMask,
+    codewords: &[u8],
+    mask: MaskPattern,
+) {
+    let size = matrix.width();
+    let total_bits = codewords.len() * 8;
+    let mut i = 0;
+    let mut right = size - 1;
+    loop {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vert in 0..size {
+            let y = if upward { size - 1 - vert } else { vert };
+            for x in [right, right - 1] {
+                if func.is_function(x, y) {
+                    continue;
+                }
+                let dark = i < total_bits && (codewords[i / 8] >> (7 - i % 8)) & 1 == 1;
+                matrix.set(x, y, dark ^ mask.is_masked(y, x));
+                i += 1;
+            }
+        }
+        if right < 2 {
+            break;
+        }
+        right -= 2;
+    }
+}
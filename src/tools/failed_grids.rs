@@ -0,0 +1,92 @@
+//! Failed-grid records for mining hard cases
+//!
+//! `qrtool reading-rate --failed-grids-jsonl` runs with
+//! [`DetectorConfig::export_failed_grids`](crate::DetectorConfig) and writes
+//! each [`FailedGrid`] of an image that was not fully read as one line of
+//! JSON, keyed by the image's dataset-relative path. Modules are rows of
+//! `0`/`1` characters, so a record is readable as it stands and needs no
+//! JSON dependency to write.
+
+use std::fmt::Write as _;
+
+use super::per_image::escape;
+use crate::FailedGrid;
+use crate::error::DecodeFailure;
+
+/// Short name of the stage a grid failed at.
+fn stage_name(failure: DecodeFailure) -> &'static str {
+    match failure {
+        DecodeFailure::ReedSolomon(_) => "reed_solomon",
+        DecodeFailure::Payload => "payload",
+        _ => "other",
+    }
+}
+
+/// Serialize `grid`, found in the image at `path`, as a single JSON line
+/// (with trailing newline).
+pub fn to_json_line(path: &str, grid: &FailedGrid) -> String {
+    let mut line = String::new();
+    let _ = write!(
+        &mut line,
+        "{{\"path\":\"{}\",\"version\":{},\"failure\":\"{}\",\"detail\":\"{}\",\"transform\":[",
+        escape(path),
+        grid.version,
+        stage_name(grid.failure),
+        escape(&grid.failure.to_string()),
+    );
+    for (i, c) in grid.transform.iter().enumerate() {
+        let sep = if i > 0 { "," } else { "" };
+        let _ = write!(&mut line, "{sep}{c}");
+    }
+    line.push_str("],\"corners\":[");
+    for (i, p) in grid.corners.iter().enumerate() {
+        let sep = if i > 0 { "," } else { "" };
+        let _ = write!(&mut line, "{sep}[{:.2},{:.2}]", p.x, p.y);
+    }
+    line.push_str("],\"modules\":[");
+    for y in 0..grid.modules.height() {
+        line.push_str(if y > 0 { ",\"" } else { "\"" });
+        for x in 0..grid.modules.width() {
+            line.push(if grid.modules.get(x, y) { '1' } else { '0' });
+        }
+        line.push('"');
+    }
+    line.push_str("],\"confidence\":[");
+    for (i, c) in grid.confidence.iter().enumerate() {
+        let sep = if i > 0 { "," } else { "" };
+        let _ = write!(&mut line, "{sep}{c}");
+    }
+    line.push_str("]}\n");
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_json_line;
+    use crate::FailedGrid;
+    use crate::decoder::reed_solomon::RsError;
+    use crate::error::DecodeFailure;
+    use crate::models::{BitMatrix, ImagePoint};
+
+    #[test]
+    fn json_line_lists_rows_and_stage() {
+        let mut modules = BitMatrix::new(2, 2);
+        modules.set(1, 0, true);
+        let grid = FailedGrid {
+            version: 1,
+            modules,
+            confidence: vec![255, 10, 200, 0],
+            transform: [1.0, 0.0, 4.0, 0.0, 1.0, 4.0, 0.0, 0.0, 1.0],
+            corners: [(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)]
+                .map(|(x, y)| ImagePoint::new(x, y)),
+            failure: DecodeFailure::ReedSolomon(RsError::Uncorrectable),
+        };
+        assert_eq!(
+            to_json_line("damaged/a \"b\".jpg", &grid),
+            "{\"path\":\"damaged/a \\\"b\\\".jpg\",\"version\":1,\"failure\":\"reed_solomon\",\
+             \"detail\":\"reed-solomon: Uncorrectable error\",\"transform\":[1,0,4,0,1,4,0,0,1],\
+             \"corners\":[[4.00,4.00],[6.00,4.00],[6.00,6.00],[4.00,6.00]],\
+             \"modules\":[\"01\",\"00\"],\"confidence\":[255,10,200,0]}\n"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! HEIC/HEIF input through the system libheif (`heic` feature)
+//!
+//! Phones save photos as HEIC, which has no pure-Rust decoder. With the
+//! `heic` feature the tools loader links libheif 1.x (`libheif-dev` on
+//! Debian and Ubuntu, `libheif` in Homebrew) and decodes files with it to
+//! 8-bit RGB: [`open_image`](super::open_image) reads the primary image and
+//! [`load_pages`](super::load_pages) every top-level image, in file order.
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, ImageError, RgbImage};
+use std::ffi::{CStr, c_char, c_int, c_void};
+use std::fs;
+use std::path::Path;
+use std::ptr;
+
+#[repr(C)]
+struct HeifError {
+    code: c_int,
+    subcode: c_int,
+    message: *const c_char,
+}
+
+#[repr(C)]
+struct HeifContext {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct HeifImageHandle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct HeifImage {
+    _private: [u8; 0],
+}
+
+const COLORSPACE_RGB: c_int = 1;
+const CHROMA_INTERLEAVED_RGB: c_int = 10;
+const CHANNEL_INTERLEAVED: c_int = 10;
+
+#[link(name = "heif")]
+unsafe extern "C" {
+    fn heif_context_alloc() -> *mut HeifContext;
+    fn heif_context_free(ctx: *mut HeifContext);
+    fn heif_context_read_from_memory_without_copy(
+        ctx: *mut HeifContext,
+        mem: *const c_void,
+        size: usize,
+        options: *const c_void,
+    ) -> HeifError;
+    fn heif_context_get_number_of_top_level_images(ctx: *mut HeifContext) -> c_int;
+    fn heif_context_get_list_of_top_level_image_IDs(
+        ctx: *mut HeifContext,
+        ids: *mut u32,
+        count: c_int,
+    ) -> c_int;
+    fn heif_context_get_image_handle(
+        ctx: *mut HeifContext,
+        id: u32,
+        handle: *mut *mut HeifImageHandle,
+    ) -> HeifError;
+    fn heif_context_get_primary_image_handle(
+        ctx: *mut HeifContext,
+        handle: *mut *mut HeifImageHandle,
+    ) -> HeifError;
+    fn heif_image_handle_release(handle: *const HeifImageHandle);
+    fn heif_decode_image(
+        handle: *const HeifImageHandle,
+        image: *mut *mut HeifImage,
+        colorspace: c_int,
+        chroma: c_int,
+        options: *const c_void,
+    ) -> HeifError;
+    fn heif_image_release(image: *const HeifImage);
+    fn heif_image_get_width(image: *const HeifImage, channel: c_int) -> c_int;
+    fn heif_image_get_height(image: *const HeifImage, channel: c_int) -> c_int;
+    fn heif_image_get_plane_readonly(
+        image: *const HeifImage,
+        channel: c_int,
+        stride: *mut c_int,
+    ) -> *const u8;
+}
+
+/// Decode the primary image of the HEIF file at `path`.
+pub(crate) fn open(path: &Path) -> Result<DynamicImage, ImageError> {
+    let bytes = fs::read(path)?;
+    let ctx = Context::read(&bytes)?;
+    let mut handle = ptr::null_mut();
+    // SAFETY: `ctx` holds a parsed file and `handle` is a valid out-pointer.
+    check(unsafe { heif_context_get_primary_image_handle(ctx.0, &mut handle) })?;
+    Ok(DynamicImage::ImageRgb8(decode(Handle(handle))?))
+}
+
+/// Decode every top-level image of the HEIF file at `path`, in file order.
+pub(crate) fn open_all(path: &Path) -> Result<Vec<DynamicImage>, ImageError> {
+    let bytes = fs::read(path)?;
+    let ctx = Context::read(&bytes)?;
+    // SAFETY: `ctx` holds a parsed file.
+    let count = unsafe { heif_context_get_number_of_top_level_images(ctx.0) };
+    let mut ids = vec![0u32; count.max(0) as usize];
+    // SAFETY: `ids` has room for `count` IDs.
+    let listed =
+        unsafe { heif_context_get_list_of_top_level_image_IDs(ctx.0, ids.as_mut_ptr(), count) };
+    ids.truncate(listed.max(0) as usize);
+    ids.into_iter()
+        .map(|id| {
+            let mut handle = ptr::null_mut();
+            // SAFETY: `id` was listed by this context; `handle` is a valid
+            // out-pointer.
+            check(unsafe { heif_context_get_image_handle(ctx.0, id, &mut handle) })?;
+            Ok(DynamicImage::ImageRgb8(decode(Handle(handle))?))
+        })
+        .collect()
+}
+
+/// A libheif context over borrowed file bytes, freed on drop.
+struct Context<'a>(*mut HeifContext, std::marker::PhantomData<&'a [u8]>);
+
+impl<'a> Context<'a> {
+    fn read(bytes: &'a [u8]) -> Result<Self, ImageError> {
+        // SAFETY: allocation takes no arguments; a null result is checked.
+        let raw = unsafe { heif_context_alloc() };
+        if raw.is_null() {
+            return Err(heic_error("libheif could not allocate a context"));
+        }
+        let ctx = Context(raw, std::marker::PhantomData);
+        // SAFETY: `bytes` outlives the context, as its lifetime requires.
+        check(unsafe {
+            heif_context_read_from_memory_without_copy(
+                ctx.0,
+                bytes.as_ptr().cast(),
+                bytes.len(),
+                ptr::null(),
+            )
+        })?;
+        Ok(ctx)
+    }
+}
+
+impl Drop for Context<'_> {
+    fn drop(&mut self) {
+        // SAFETY: the context was allocated by `heif_context_alloc`.
+        unsafe { heif_context_free(self.0) };
+    }
+}
+
+/// An image handle, released on drop.
+struct Handle(*mut HeifImageHandle);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        // SAFETY: the handle came from a successful libheif call.
+        unsafe { heif_image_handle_release(self.0) };
+    }
+}
+
+/// A decoded image, released on drop.
+struct Image(*mut HeifImage);
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        // SAFETY: the image came from a successful `heif_decode_image`.
+        unsafe { heif_image_release(self.0) };
+    }
+}
+
+/// Decode `handle` to interleaved 8-bit RGB.
+fn decode(handle: Handle) -> Result<RgbImage, ImageError> {
+    let mut raw = ptr::null_mut();
+    // SAFETY: `handle` is live and `raw` is a valid out-pointer.
+    check(unsafe {
+        heif_decode_image(
+            handle.0,
+            &mut raw,
+            COLORSPACE_RGB,
+            CHROMA_INTERLEAVED_RGB,
+            ptr::null(),
+        )
+    })?;
+    let image = Image(raw);
+    let mut stride = 0;
+    // SAFETY: `image` is live; the plane stays valid until it is released.
+    let (width, height, plane) = unsafe {
+        (
+            heif_image_get_width(image.0, CHANNEL_INTERLEAVED),
+            heif_image_get_height(image.0, CHANNEL_INTERLEAVED),
+            heif_image_get_plane_readonly(image.0, CHANNEL_INTERLEAVED, &mut stride),
+        )
+    };
+    let (Ok(width), Ok(height), Ok(stride)) = (
+        usize::try_from(width),
+        usize::try_from(height),
+        usize::try_from(stride),
+    ) else {
+        return Err(heic_error("libheif returned no RGB plane"));
+    };
+    if plane.is_null() || stride < width * 3 {
+        return Err(heic_error("libheif returned no RGB plane"));
+    }
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        // SAFETY: the plane holds `height` rows of `stride` bytes, each
+        // starting with `width` RGB pixels.
+        let row = unsafe { std::slice::from_raw_parts(plane.add(y * stride), width * 3) };
+        rgb.extend_from_slice(row);
+    }
+    RgbImage::from_raw(width as u32, height as u32, rgb)
+        .ok_or_else(|| heic_error("HEIC image buffer does not match its dimensions"))
+}
+
+fn check(err: HeifError) -> Result<(), ImageError> {
+    if err.code == 0 {
+        return Ok(());
+    }
+    let message = if err.message.is_null() {
+        format!("libheif error {}.{}", err.code, err.subcode)
+    } else {
+        // SAFETY: libheif error messages are static NUL-terminated strings.
+        unsafe { CStr::from_ptr(err.message) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    Err(heic_error(message))
+}
+
+fn heic_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("HEIC".to_string()),
+        err,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{load_pages, load_rgb};
+    use std::path::PathBuf;
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/v2_phone.heic")
+    }
+
+    #[test]
+    fn heic_photo_decodes_and_scans() {
+        let (rgb, width, height) = load_rgb(fixture()).expect("HEIC decodes");
+        assert_eq!((width, height), (132, 132));
+        let codes = crate::detect(&rgb, width, height);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].content, "TEL:555-123-5555");
+
+        let pages = load_pages(fixture()).expect("HEIC decodes");
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].rgb, rgb);
+    }
+
+    #[test]
+    fn corrupt_heic_is_a_decoding_error() {
+        let path = std::env::temp_dir().join(format!("rustqr-corrupt-{}.heic", std::process::id()));
+        std::fs::write(&path, b"\0\0\0\x18ftypheic\0\0\0\0mif1heic").unwrap();
+        let err = load_rgb(&path).unwrap_err();
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(err, image::ImageError::Decoding(_)), "{err}");
+        assert!(err.to_string().contains("HEIC"), "{err}");
+    }
+}
@@ -8,8 +8,23 @@ use image::GenericImageView;
 use std::env;
 use std::fs;
 use std::hash::Hasher;
+use std::io;
 use std::path::{Path, PathBuf};
 
+pub mod compat_json;
+pub mod encode;
+pub mod failed_grids;
+#[cfg(feature = "heic")]
+mod heic;
+pub mod pages;
+pub mod per_image;
+pub mod smoke;
+pub mod svg;
+
+pub use pages::{Page, load_pages};
+pub use per_image::PerImageRow;
+pub use svg::{is_svg, load_svg};
+
 fn max_dim_from_env() -> Option<u32> {
     match env::var("QR_MAX_DIM") {
         Ok(value) => match value.trim().parse::<u32>() {
@@ -23,21 +38,67 @@ fn max_dim_from_env() -> Option<u32> {
 
 /// Load an image as RGB bytes along with its dimensions.
 pub fn load_rgb<P: AsRef<Path>>(path: P) -> Result<(Vec<u8>, usize, usize), image::ImageError> {
-    let img = image::open(path)?;
-    let rgb = if let Some(max_dim) = max_dim_from_env() {
+    let rgb = fit_max_dim(open_image(path.as_ref())?);
+    let (width, height) = rgb.dimensions();
+    Ok((rgb.into_raw(), width as usize, height as usize))
+}
+
+/// Decode `path` by its content rather than its extension, since uploads
+/// often arrive renamed. WebP is read by the `image` crate's pure-Rust
+/// decoder. HEIC/HEIF has no pure-Rust decoder: with the `heic` feature the
+/// system libheif decodes it, and without it the file is recognised and
+/// rejected with a clear unsupported-format error; convert it first.
+pub(crate) fn open_image(path: &Path) -> Result<image::DynamicImage, image::ImageError> {
+    if is_heif(path) {
+        return open_heif(path);
+    }
+    image::io::Reader::open(path)?
+        .with_guessed_format()?
+        .decode()
+}
+
+/// Whether `path` starts with the `ftyp` box of a HEIC/HEIF file.
+fn is_heif(path: &Path) -> bool {
+    let mut header = [0u8; 12];
+    fs::File::open(path)
+        .and_then(|mut file| io::Read::read_exact(&mut file, &mut header))
+        .is_ok()
+        && &header[4..8] == b"ftyp"
+        && HEIF_BRANDS.contains(&&header[8..12])
+}
+
+#[cfg(feature = "heic")]
+fn open_heif(path: &Path) -> Result<image::DynamicImage, image::ImageError> {
+    heic::open(path)
+}
+
+#[cfg(not(feature = "heic"))]
+fn open_heif(_path: &Path) -> Result<image::DynamicImage, image::ImageError> {
+    let format = image::error::ImageFormatHint::Name("HEIC".to_string());
+    Err(image::ImageError::Unsupported(
+        image::error::UnsupportedError::from_format_and_kind(
+            format.clone(),
+            image::error::UnsupportedErrorKind::Format(format),
+        ),
+    ))
+}
+
+/// `ftyp` major brands of HEIC/HEIF still images and sequences.
+const HEIF_BRANDS: [&[u8]; 8] = [
+    b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+];
+
+/// RGB copy of `img`, downscaled to `QR_MAX_DIM` when it is larger.
+fn fit_max_dim(img: image::DynamicImage) -> image::RgbImage {
+    if let Some(max_dim) = max_dim_from_env() {
         let (orig_w, orig_h) = img.dimensions();
         let max_side = orig_w.max(orig_h);
         if max_side > max_dim {
             let resized = img.resize(max_dim, max_dim, image::imageops::FilterType::Triangle);
-            resized.to_rgb8()
-        } else {
-            img.to_rgb8()
+            return resized.to_rgb8();
         }
-    } else {
-        img.to_rgb8()
-    };
-    let (width, height) = rgb.dimensions();
-    Ok((rgb.into_raw(), width as usize, height as usize))
+    }
+    img.to_rgb8()
 }
 
 /// Convert RGB bytes into grayscale.
@@ -136,7 +197,14 @@ pub fn dataset_root_from_env() -> PathBuf {
 
 /// Deterministic fingerprint of dataset contents for benchmark provenance.
 ///
-/// The fingerprint includes every file path and file bytes under `root`.
+/// Every regular file under `root` is visited in order of its relative path
+/// (`/`-separated, byte-wise sorted). For each file the relative path, a `0`
+/// byte, the little-endian `u64` size, the file bytes and a `0xff` byte are
+/// fed into a single 64-bit FNV-1a hash, rendered as 16 lowercase hex digits.
+/// Timestamps and permissions are ignored, so a fresh checkout or copy of the
+/// same dataset yields the same value on any platform. A missing root
+/// fingerprints as `"missing"`.
+///
 /// It is intended for change detection and traceability, not cryptographic use.
 pub fn dataset_fingerprint<P: AsRef<Path>>(root: P) -> String {
     struct Fnv1a64(u64);
@@ -185,7 +253,6 @@ pub fn dataset_fingerprint<P: AsRef<Path>>(root: P) -> String {
             }
         }
 
-        files.sort();
         files
     }
 
@@ -194,13 +261,23 @@ pub fn dataset_fingerprint<P: AsRef<Path>>(root: P) -> String {
         return "missing".to_string();
     }
 
+    let mut files: Vec<(String, PathBuf)> = collect_files(root)
+        .into_iter()
+        .map(|path| {
+            let rel = path
+                .strip_prefix(root)
+                .ok()
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|| path.to_string_lossy().replace('\\', "/"));
+            (rel, path)
+        })
+        .collect();
+    // Sort on the normalised string rather than `Path` so the order does not
+    // depend on the platform's separator or component comparison.
+    files.sort();
+
     let mut hasher = Fnv1a64::default();
-    for path in collect_files(root) {
-        let rel = path
-            .strip_prefix(root)
-            .ok()
-            .map(|p| p.to_string_lossy().replace('\\', "/"))
-            .unwrap_or_else(|| path.to_string_lossy().replace('\\', "/"));
+    for (rel, path) in files {
         hasher.write(rel.as_bytes());
         hasher.write(&[0]);
 
@@ -216,6 +293,78 @@ pub fn dataset_fingerprint<P: AsRef<Path>>(root: P) -> String {
     format!("{:016x}", hasher.finish())
 }
 
+/// Host and build description recorded next to benchmark results.
+///
+/// Reading rates and timings are only comparable between runs on similar
+/// machines with the same cargo features, so artifacts embed this alongside
+/// the commit and [`dataset_fingerprint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentInfo {
+    /// CPU model name, or `"unknown"` when the platform does not expose it
+    pub cpu_model: String,
+    /// Target operating system (`std::env::consts::OS`)
+    pub os: &'static str,
+    /// Target architecture (`std::env::consts::ARCH`)
+    pub arch: &'static str,
+    /// Logical CPUs available to the process
+    pub logical_cpus: usize,
+    /// Threads in the rayon pool used by the parallel scan paths
+    pub worker_threads: usize,
+    /// Cargo features this build was compiled with, in manifest order
+    pub features: Vec<&'static str>,
+}
+
+/// Capture the [`EnvironmentInfo`] of the running process.
+pub fn capture_environment() -> EnvironmentInfo {
+    let features = [
+        ("full-recovery", cfg!(feature = "full-recovery")),
+        ("multi-qr", cfg!(feature = "multi-qr")),
+        ("high-version", cfg!(feature = "high-version")),
+        ("fixed-point", cfg!(feature = "fixed-point")),
+        ("image-interop", cfg!(feature = "image-interop")),
+        ("ndarray-interop", cfg!(feature = "ndarray-interop")),
+        ("serde", cfg!(feature = "serde")),
+        ("tools", cfg!(feature = "tools")),
+        ("python", cfg!(feature = "python")),
+        ("node", cfg!(feature = "node")),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect();
+
+    EnvironmentInfo {
+        cpu_model: cpu_model(),
+        os: env::consts::OS,
+        arch: env::consts::ARCH,
+        logical_cpus: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        worker_threads: rayon::current_num_threads(),
+        features,
+    }
+}
+
+fn cpu_model() -> String {
+    let from_proc = fs::read_to_string("/proc/cpuinfo").ok().and_then(|info| {
+        info.lines()
+            .find(|line| line.starts_with("model name") || line.starts_with("Hardware"))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, value)| value.trim().to_string())
+    });
+    let from_sysctl = || {
+        std::process::Command::new("sysctl")
+            .args(["-n", "machdep.cpu.brand_string"])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8(o.stdout).ok())
+            .map(|s| s.trim().to_string())
+    };
+    from_proc
+        .or_else(from_sysctl)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
 /// Default bench limit from environment variables.
 ///
 /// Returns `None` (full dataset) when `QR_BENCH_LIMIT` is unset or set to `0`.
@@ -296,9 +445,84 @@ pub fn parse_expected_qr_count<P: AsRef<Path>>(txt_path: P) -> usize {
     }
 }
 
+/// Read the expected payload from a payload label: a `.txt` file holding the
+/// encoded text, as in `benches/images/custom/decoding`. Compare it to a
+/// decode with [`payload_matches`].
+///
+/// Corner label files (see [`parse_expected_qr_count`]), empty files and
+/// unreadable ones yield `None`.
+pub fn parse_expected_payload<P: AsRef<Path>>(txt_path: P) -> Option<String> {
+    let txt_path = txt_path.as_ref();
+    let content = fs::read_to_string(txt_path).ok()?;
+    if content.trim().is_empty() || parse_expected_qr_count(txt_path) > 0 {
+        return None;
+    }
+    Some(content)
+}
+
+/// Whether `decoded` is the payload `expected` by a payload label. Labels
+/// are saved with `\n` line endings and without the symbol's trailing line
+/// break, so line endings and trailing breaks are not compared.
+pub fn payload_matches(decoded: &str, expected: &str) -> bool {
+    let normalize = |text: &str| {
+        text.replace("\r\n", "\n")
+            .trim_end_matches('\n')
+            .to_string()
+    };
+    normalize(decoded) == normalize(expected)
+}
+
+/// Parse the labeled corner quads from a BoofCV-style `.txt` label file.
+///
+/// Accepts the same two layouts as [`parse_expected_qr_count`]. Corners are
+/// returned in label order and in original image pixels; an unreadable file
+/// yields no quads.
+pub fn parse_expected_qr_corners<P: AsRef<Path>>(txt_path: P) -> Vec<[(f32, f32); 4]> {
+    let content = match fs::read_to_string(txt_path) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut quads = Vec::new();
+    let mut pending: Vec<(f32, f32)> = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.eq_ignore_ascii_case("SETS") {
+            continue;
+        }
+        let Ok(values) = trimmed
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            continue;
+        };
+        match values.len() {
+            n if n >= 8 => quads.push([
+                (values[0], values[1]),
+                (values[2], values[3]),
+                (values[4], values[5]),
+                (values[6], values[7]),
+            ]),
+            2 => {
+                pending.push((values[0], values[1]));
+                if let [a, b, c, d] = pending[..] {
+                    quads.push([a, b, c, d]);
+                    pending.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+    quads
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{dataset_fingerprint, parse_expected_qr_count};
+    use super::{
+        capture_environment, dataset_fingerprint, load_rgb, parse_expected_payload,
+        parse_expected_qr_corners, parse_expected_qr_count, payload_matches,
+    };
     use std::fs::{self, create_dir_all};
     use std::path::PathBuf;
     use std::sync::atomic::{AtomicU64, Ordering};
@@ -327,6 +551,25 @@ mod tests {
              9.0 10.0 11.0 12.0 13.0 14.0 15.0 16.0\n",
         );
         assert_eq!(parse_expected_qr_count(&path), 2);
+        let quads = parse_expected_qr_corners(&path);
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[1][3], (15.0, 16.0));
+        assert_eq!(parse_expected_payload(&path), None);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn payload_labels_match_decodes_across_line_endings() {
+        let path = write_temp_file("BEGIN:VCARD\nN:Doe\nEND:VCARD");
+        let expected = parse_expected_payload(&path).unwrap();
+        assert!(payload_matches(
+            "BEGIN:VCARD\r\nN:Doe\r\nEND:VCARD\r\n",
+            &expected
+        ));
+        assert!(!payload_matches(
+            "BEGIN:VCARD\r\nN:Roe\r\nEND:VCARD\r\n",
+            &expected
+        ));
         let _ = fs::remove_file(path);
     }
 
@@ -344,6 +587,9 @@ mod tests {
              71.0 81.0\n",
         );
         assert_eq!(parse_expected_qr_count(&path), 2);
+        let quads = parse_expected_qr_corners(&path);
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[1][0], (11.0, 21.0));
         let _ = fs::remove_file(path);
     }
 
@@ -372,6 +618,83 @@ mod tests {
         assert_ne!(before, after);
         let _ = fs::remove_dir_all(root);
     }
+
+    #[test]
+    fn dataset_fingerprint_ignores_location_and_mtime() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock before UNIX epoch")
+            .as_nanos();
+        let mut roots = Vec::new();
+        for copy in ["a", "b"] {
+            let root = std::env::temp_dir().join(format!("rustqr_fp_stable_{nanos}_{copy}"));
+            create_dir_all(root.join("nominal")).expect("failed to create temp dataset");
+            create_dir_all(root.join("blurred")).expect("failed to create temp dataset");
+            fs::write(root.join("nominal").join("a.png"), b"abc").expect("failed to write file");
+            fs::write(root.join("blurred").join("b.png"), b"def").expect("failed to write file");
+            roots.push(root);
+        }
+        // Rewrite one file with identical bytes so only its mtime moves.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(roots[1].join("nominal").join("a.png"), b"abc").expect("failed to write file");
+
+        let a = dataset_fingerprint(&roots[0]);
+        assert_eq!(a.len(), 16);
+        assert_eq!(a, dataset_fingerprint(&roots[1]));
+        for root in roots {
+            let _ = fs::remove_dir_all(root);
+        }
+    }
+
+    #[test]
+    fn load_rgb_reads_misnamed_webp_and_rejects_heic() {
+        // With the `heic` feature the HEIC half is covered in `heic`.
+        // 1x1 lossless and lossy WebP, saved under a JPEG name.
+        let lossless: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, 0x1a, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50,
+            0x38, 0x4c, 0x0d, 0x00, 0x00, 0x00, 0x2f, 0x00, 0x00, 0x00, 0x10, 0x07, 0x10, 0x11,
+            0x11, 0x88, 0x88, 0xfe, 0x07, 0x00,
+        ];
+        let lossy: &[u8] = &[
+            0x52, 0x49, 0x46, 0x46, 0x22, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50,
+            0x38, 0x20, 0x16, 0x00, 0x00, 0x00, 0x30, 0x01, 0x00, 0x9d, 0x01, 0x2a, 0x01, 0x00,
+            0x01, 0x00, 0x0e, 0xc0, 0xfe, 0x25, 0xa4, 0x00, 0x03, 0x70, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let temp_path = |ext: &str| {
+            let txt = write_temp_file("");
+            let path = txt.with_extension(ext);
+            fs::rename(&txt, &path).unwrap();
+            path
+        };
+        for bytes in [lossless, lossy] {
+            let path = temp_path("jpg");
+            fs::write(&path, bytes).unwrap();
+            let loaded = load_rgb(&path);
+            let _ = fs::remove_file(&path);
+            let (rgb, width, height) = loaded.unwrap();
+            assert_eq!((rgb.len(), width, height), (3, 1, 1));
+        }
+
+        if cfg!(feature = "heic") {
+            return;
+        }
+        let path = temp_path("heic");
+        fs::write(&path, b"\0\0\0\x18ftypheic\0\0\0\0mif1heic").unwrap();
+        let err = load_rgb(&path).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert!(matches!(err, image::ImageError::Unsupported(_)), "{err}");
+        assert!(err.to_string().contains("HEIC"), "{err}");
+    }
+
+    #[test]
+    fn capture_environment_reports_build_and_host() {
+        let env = capture_environment();
+        assert!(!env.cpu_model.is_empty());
+        assert_eq!(env.arch, std::env::consts::ARCH);
+        assert!(env.logical_cpus >= 1);
+        assert!(env.worker_threads >= 1);
+        assert!(env.features.contains(&"tools"));
+    }
 }
 
 /// Smoke test flag from environment variables.
@@ -442,7 +765,10 @@ fn collect_images(root: &Path) -> Vec<PathBuf> {
             }
             if let Some(ext) = path.extension() {
                 let ext = ext.to_string_lossy().to_lowercase();
-                if ext == "png" || ext == "jpg" || ext == "jpeg" || ext == "gif" || ext == "bmp" {
+                if matches!(
+                    ext.as_str(),
+                    "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp"
+                ) {
                     images.push(path);
                 }
             }
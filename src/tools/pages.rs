@@ -0,0 +1,155 @@
+//! Page iteration for multi-page inputs
+//!
+//! Scanned documents often arrive as one multi-page TIFF rather than a
+//! folder of single images. [`load_pages`] yields every page of such a file
+//! with its index, so `qrtool detect` can report which page each code came
+//! from without pre-splitting. HEIC/HEIF files read with the `heic` feature
+//! yield each top-level image as a page. Any other format
+//! [`load_rgb`](super::load_rgb) reads comes back as a single page 0. PDF input is not supported; rasterize
+//! the pages to a TIFF first.
+
+use super::{fit_max_dim, open_image};
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, ImageBuffer, ImageError, ImageFormat};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use tiff::ColorType;
+use tiff::decoder::{Decoder, DecodingResult};
+
+/// One page of an input file as RGB bytes.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Zero-based page index within the file.
+    pub index: usize,
+    pub rgb: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Load every page of `path`, in file order.
+///
+/// TIFF files are recognised by their magic bytes and yield one page per
+/// image directory; everything else is loaded as a single page. Pages are
+/// downscaled to `QR_MAX_DIM` like [`load_rgb`](super::load_rgb). A page
+/// whose colour type cannot be converted to RGB (palette, CMYK, YCbCr,
+/// bilevel) fails the whole load.
+pub fn load_pages<P: AsRef<Path>>(path: P) -> Result<Vec<Page>, ImageError> {
+    let path = path.as_ref();
+    #[cfg(feature = "heic")]
+    if super::is_heif(path) {
+        return Ok(super::heic::open_all(path)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, img)| page(index, fit_max_dim(img)))
+            .collect());
+    }
+    let mut magic = [0u8; 4];
+    let is_tiff = File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && (magic == *b"II*\0" || magic == *b"MM\0*");
+    if !is_tiff {
+        let rgb = fit_max_dim(open_image(path)?);
+        return Ok(vec![page(0, rgb)]);
+    }
+
+    let mut decoder = Decoder::new(BufReader::new(File::open(path)?)).map_err(tiff_error)?;
+    let mut pages = Vec::new();
+    loop {
+        let img = tiff_page(&mut decoder)?;
+        pages.push(page(pages.len(), fit_max_dim(img)));
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image().map_err(tiff_error)?;
+    }
+    Ok(pages)
+}
+
+fn page(index: usize, rgb: image::RgbImage) -> Page {
+    let (width, height) = rgb.dimensions();
+    Page {
+        index,
+        rgb: rgb.into_raw(),
+        width: width as usize,
+        height: height as usize,
+    }
+}
+
+/// Decode the decoder's current image directory.
+fn tiff_page<R: Read + std::io::Seek>(
+    decoder: &mut Decoder<R>,
+) -> Result<DynamicImage, ImageError> {
+    let (w, h) = decoder.dimensions().map_err(tiff_error)?;
+    let color = decoder.colortype().map_err(tiff_error)?;
+    let data = decoder.read_image().map_err(tiff_error)?;
+    let img = match (color, data) {
+        (ColorType::Gray(8), DecodingResult::U8(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(d)) => {
+            ImageBuffer::from_raw(w, h, d).map(DynamicImage::ImageRgba16)
+        }
+        (color, _) => {
+            return Err(tiff_error(format!(
+                "unsupported TIFF page colour type {color:?}"
+            )));
+        }
+    };
+    img.ok_or_else(|| tiff_error("TIFF page buffer does not match its dimensions"))
+}
+
+fn tiff_error(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Tiff),
+        err,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tiff::encoder::{TiffEncoder, colortype};
+
+    #[test]
+    fn multi_page_tiff_yields_each_page_in_order() {
+        let path = env::temp_dir().join(format!("rustqr_pages_{}.tif", std::process::id()));
+        {
+            let mut encoder = TiffEncoder::new(File::create(&path).unwrap()).unwrap();
+            encoder
+                .write_image::<colortype::Gray8>(4, 3, &[40u8; 12])
+                .unwrap();
+            encoder
+                .write_image::<colortype::RGB8>(2, 5, &[200u8; 30])
+                .unwrap();
+        }
+        let pages = load_pages(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!((pages[0].index, pages[0].width, pages[0].height), (0, 4, 3));
+        assert_eq!(pages[0].rgb, vec![40u8; 36]);
+        assert_eq!((pages[1].index, pages[1].width, pages[1].height), (1, 2, 5));
+        assert_eq!(pages[1].rgb, vec![200u8; 30]);
+    }
+}
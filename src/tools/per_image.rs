@@ -0,0 +1,312 @@
+//! Per-image benchmark rows
+//!
+//! `qrtool reading-rate --per-image-jsonl` writes one [`PerImageRow`] per
+//! labeled image as a line of JSON; `qrtool telemetry-diff` reads two such
+//! files back and joins them on `path`. The format is flat on purpose so it
+//! stays greppable and needs no JSON dependency.
+
+use std::fmt::Write as _;
+
+/// Outcome of one labeled image in a reading-rate run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerImageRow {
+    /// Path relative to the dataset root.
+    pub path: String,
+    /// Dataset category (first path component), empty for flat datasets.
+    pub category: String,
+    /// QR codes listed in the label file.
+    pub expected: usize,
+    /// QR codes decoded.
+    pub decoded: usize,
+    /// Decoded codes credited against `expected`.
+    pub hits: usize,
+    /// Decoded payloads.
+    pub payloads: Vec<String>,
+    /// `ok`, `partial`, `load-failed`, or a failure signature such as
+    /// `no-finders` or `rs-fail`.
+    pub signature: String,
+    /// Detection wall-clock time; `None` when the image failed to load.
+    pub runtime_ms: Option<f64>,
+    /// Finder patterns found.
+    pub finders: usize,
+    /// Finder groups formed.
+    pub groups: usize,
+    /// Perspective transforms built.
+    pub transforms: usize,
+    /// Decode attempts made.
+    pub decode_attempts: usize,
+}
+
+impl PerImageRow {
+    /// True when every expected code was read.
+    pub fn passed(&self) -> bool {
+        self.expected > 0 && self.hits >= self.expected
+    }
+
+    /// Serialize as a single JSON line (with trailing newline).
+    pub fn to_json_line(&self) -> String {
+        let mut line = String::new();
+        let _ = write!(
+            &mut line,
+            "{{\"path\":\"{}\",\"category\":\"{}\",\"expected\":{},\"decoded\":{},\"hits\":{},\"payloads\":[",
+            escape(&self.path),
+            escape(&self.category),
+            self.expected,
+            self.decoded,
+            self.hits,
+        );
+        for (i, payload) in self.payloads.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            let _ = write!(&mut line, "\"{}\"", escape(payload));
+        }
+        let _ = write!(&mut line, "],\"signature\":\"{}\"", escape(&self.signature));
+        match self.runtime_ms {
+            Some(ms) => {
+                let _ = write!(&mut line, ",\"runtime_ms\":{:.3}", ms);
+            }
+            None => line.push_str(",\"runtime_ms\":null"),
+        }
+        let _ = writeln!(
+            &mut line,
+            ",\"finders\":{},\"groups\":{},\"transforms\":{},\"decode_attempts\":{}}}",
+            self.finders, self.groups, self.transforms, self.decode_attempts,
+        );
+        line
+    }
+
+    /// Parse a line written by [`Self::to_json_line`]. Unknown keys are
+    /// ignored and missing counters default to zero; `path` is required.
+    pub fn parse_json_line(line: &str) -> Result<Self, String> {
+        let mut parser = Parser::new(line);
+        let mut row = PerImageRow::default();
+        let mut has_path = false;
+        parser.expect(b'{')?;
+        if !parser.eat(b'}') {
+            loop {
+                let key = parser.string()?;
+                parser.expect(b':')?;
+                let value = parser.value()?;
+                match (key.as_str(), value) {
+                    ("path", Value::Str(s)) => {
+                        row.path = s;
+                        has_path = true;
+                    }
+                    ("category", Value::Str(s)) => row.category = s,
+                    ("signature", Value::Str(s)) => row.signature = s,
+                    ("payloads", Value::List(list)) => row.payloads = list,
+                    ("runtime_ms", Value::Num(n)) => row.runtime_ms = Some(n),
+                    ("runtime_ms", Value::Null) => row.runtime_ms = None,
+                    ("expected", Value::Num(n)) => row.expected = n as usize,
+                    ("decoded", Value::Num(n)) => row.decoded = n as usize,
+                    ("hits", Value::Num(n)) => row.hits = n as usize,
+                    ("finders", Value::Num(n)) => row.finders = n as usize,
+                    ("groups", Value::Num(n)) => row.groups = n as usize,
+                    ("transforms", Value::Num(n)) => row.transforms = n as usize,
+                    ("decode_attempts", Value::Num(n)) => row.decode_attempts = n as usize,
+                    (
+                        "path" | "category" | "signature" | "payloads" | "runtime_ms" | "expected"
+                        | "decoded" | "hits" | "finders" | "groups" | "transforms"
+                        | "decode_attempts",
+                        _,
+                    ) => return Err(format!("unexpected value type for `{key}`")),
+                    _ => {}
+                }
+                if parser.eat(b'}') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+        if !parser.at_end() {
+            return Err("trailing characters after object".to_string());
+        }
+        if !has_path {
+            return Err("missing `path`".to_string());
+        }
+        Ok(row)
+    }
+}
+
+pub(super) fn escape(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + 8);
+    for ch in input.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(&mut out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+enum Value {
+    Str(String),
+    Num(f64),
+    Null,
+    List(Vec<String>),
+}
+
+/// Just enough JSON for flat objects of strings, numbers, `null` and string
+/// arrays.
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.src.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}` at byte {}", byte as char, self.pos))
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .map_err(|_| format!("bad \\u escape `{hex}`"))?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    _ => return Err("bad escape in string".to_string()),
+                },
+                c => out.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::Str),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut list = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        list.push(self.string()?);
+                        if self.eat(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Value::List(list))
+            }
+            Some(b'n') if self.src[self.pos..].starts_with("null") => {
+                self.pos += 4;
+                Ok(Value::Null)
+            }
+            Some(_) => {
+                let rest = &self.src[self.pos..];
+                let len = rest
+                    .find(|c: char| {
+                        !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                    })
+                    .unwrap_or(rest.len());
+                let num = rest[..len]
+                    .parse::<f64>()
+                    .map_err(|_| format!("expected a value at byte {}", self.pos))?;
+                self.pos += len;
+                Ok(Value::Num(num))
+            }
+            None => Err("unexpected end of line".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PerImageRow;
+
+    #[test]
+    fn json_line_round_trips() {
+        let row = PerImageRow {
+            path: "lots/image001.jpg".to_string(),
+            category: "lots".to_string(),
+            expected: 3,
+            decoded: 2,
+            hits: 2,
+            payloads: vec!["a \"quoted\"\npayload".to_string(), "café".to_string()],
+            signature: "partial".to_string(),
+            runtime_ms: Some(12.5),
+            finders: 9,
+            groups: 3,
+            transforms: 3,
+            decode_attempts: 4,
+        };
+        let line = row.to_json_line();
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+        assert_eq!(PerImageRow::parse_json_line(&line), Ok(row));
+    }
+
+    #[test]
+    fn parse_tolerates_missing_counters_and_rejects_garbage() {
+        let row = PerImageRow::parse_json_line(
+            r#"{"path":"a.jpg","expected":1,"signature":"load-failed","runtime_ms":null,"extra":7}"#,
+        )
+        .unwrap();
+        assert_eq!(row.path, "a.jpg");
+        assert_eq!(row.runtime_ms, None);
+        assert_eq!(row.decode_attempts, 0);
+        assert!(!row.passed());
+
+        assert!(PerImageRow::parse_json_line(r#"{"expected":1}"#).is_err());
+        assert!(PerImageRow::parse_json_line(r#"{"path":"a.jpg""#).is_err());
+        assert!(PerImageRow::parse_json_line(r#"{"path":3}"#).is_err());
+    }
+}
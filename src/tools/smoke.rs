@@ -0,0 +1,270 @@
+//! Synthetic smoke corpus for CI
+//!
+//! Symbols from [`super::encode`] cycled through every version, EC level and
+//! numeric/alphanumeric/byte payloads, rendered at key rotations and module
+//! sizes and labeled in the BoofCV layout `reading-rate` reads, so a
+//! correctness run needs no downloaded dataset.
+
+use super::encode::encode;
+use crate::decoder::qr_decoder::SegmentMode;
+use crate::decoder::tables::capacity;
+use crate::models::{BitMatrix, ECLevel, MaskPattern};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+const EC_LEVELS: [ECLevel; 4] = [ECLevel::L, ECLevel::M, ECLevel::Q, ECLevel::H];
+const MODES: [SegmentMode; 3] = [
+    SegmentMode::Numeric,
+    SegmentMode::Alphanumeric,
+    SegmentMode::Byte,
+];
+const ROTATIONS_DEG: [f32; 6] = [0.0, 90.0, 180.0, 270.0, 15.0, 45.0];
+const MODULE_PX: [usize; 4] = [3, 4, 6, 8];
+/// Longest symbol side rendered, in pixels; high versions get smaller modules.
+const MAX_SYMBOL_PX: usize = 1200;
+const QUIET_ZONE: usize = 4;
+
+/// One generated image: what is encoded and how it is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmokeCase {
+    /// Symbol version (1-40)
+    pub version: u8,
+    /// Error correction level
+    pub ec_level: ECLevel,
+    /// Mode of the single data segment
+    pub mode: SegmentMode,
+    /// Data mask
+    pub mask: MaskPattern,
+    /// Clockwise rotation of the symbol in the image
+    pub rotation_deg: f32,
+    /// Module pitch in pixels
+    pub module_px: usize,
+    /// Encoded text
+    pub payload: String,
+}
+
+/// A rendered case with the symbol's outer corners.
+#[derive(Debug, Clone)]
+pub struct SmokeImage {
+    /// 8-bit grayscale pixels
+    pub gray: Vec<u8>,
+    /// Image width
+    pub width: usize,
+    /// Image height
+    pub height: usize,
+    /// Symbol corners (top-left, top-right, bottom-right, bottom-left),
+    /// quiet zone excluded
+    pub corners: [(f32, f32); 4],
+}
+
+/// The first `count` cases. Versions cycle fastest so any 40 consecutive
+/// cases cover them all; EC level, mode, mask, rotation and module size
+/// step at different rates so each version meets a spread of the others.
+pub fn smoke_cases(count: usize) -> Vec<SmokeCase> {
+    (0..count)
+        .map(|i| {
+            let version = (i % 40) as u8 + 1;
+            let ec_level = EC_LEVELS[(i + i / 40) % EC_LEVELS.len()];
+            let mode = MODES[i % MODES.len()];
+            let dimension = 17 + 4 * version as usize;
+            let max_px = (MAX_SYMBOL_PX / (dimension + 2 * QUIET_ZONE)).max(2);
+            SmokeCase {
+                version,
+                ec_level,
+                mode,
+                mask: MaskPattern::from_bits((i * 3 + i / 40) as u8)
+                    .unwrap_or(MaskPattern::Pattern0),
+                rotation_deg: ROTATIONS_DEG[(i / 2) % ROTATIONS_DEG.len()],
+                module_px: MODULE_PX[(i / 3) % MODULE_PX.len()].min(max_px),
+                payload: payload(i, version, ec_level, mode),
+            }
+        })
+        .collect()
+}
+
+/// Deterministic text filling about three quarters of the symbol.
+fn payload(seed: usize, version: u8, ec_level: ECLevel, mode: SegmentMode) -> String {
+    let alphabet: &[u8] = match mode {
+        SegmentMode::Numeric => b"0123456789",
+        SegmentMode::Alphanumeric => b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:",
+        _ => b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 -_.,:;/?=&",
+    };
+    let len = (capacity(version, ec_level, mode).unwrap_or(1) * 3 / 4).max(1);
+    let mut state = seed as u64 ^ 0x9E37_79B9_7F4A_7C15;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            alphabet[(state >> 33) as usize % alphabet.len()] as char
+        })
+        .collect()
+}
+
+impl SmokeCase {
+    /// Dataset category (subdirectory) of this case: its payload mode.
+    pub fn category(&self) -> &'static str {
+        match self.mode {
+            SegmentMode::Numeric => "numeric",
+            SegmentMode::Alphanumeric => "alphanumeric",
+            _ => "byte",
+        }
+    }
+
+    /// Encode and draw the symbol, dark on light with a four-module quiet
+    /// zone, anti-aliased from 3x3 samples per pixel.
+    pub fn render(&self) -> Option<SmokeImage> {
+        let matrix = encode(
+            &self.payload,
+            self.mode,
+            self.version,
+            self.ec_level,
+            self.mask,
+        )?;
+        Some(render_rotated(
+            &matrix,
+            self.module_px as f32,
+            self.rotation_deg,
+        ))
+    }
+}
+
+fn render_rotated(matrix: &BitMatrix, module_px: f32, rotation_deg: f32) -> SmokeImage {
+    let dimension = matrix.width() as f32;
+    let side = (dimension + 2.0 * QUIET_ZONE as f32) * module_px;
+    let (sin, cos) = rotation_deg.to_radians().sin_cos();
+    let canvas = (side * (cos.abs() + sin.abs())).ceil() as usize;
+    let center = canvas as f32 / 2.0;
+    // Symbol frame: origin at the symbol centre, one unit per module.
+    let to_image = |u: f32, v: f32| {
+        let (x, y) = (u * module_px, v * module_px);
+        (center + x * cos - y * sin, center + x * sin + y * cos)
+    };
+    let half = dimension / 2.0;
+    let dark_at = |px: f32, py: f32| {
+        let (x, y) = (px - center, py - center);
+        let u = (x * cos + y * sin) / module_px + half;
+        let v = (-x * sin + y * cos) / module_px + half;
+        (0.0..dimension).contains(&u)
+            && (0.0..dimension).contains(&v)
+            && matrix.get(u as usize, v as usize)
+    };
+
+    let mut gray = vec![0u8; canvas * canvas];
+    for py in 0..canvas {
+        for px in 0..canvas {
+            let mut dark = 0u32;
+            for sy in 0..3 {
+                for sx in 0..3 {
+                    let x = px as f32 + (sx as f32 + 0.5) / 3.0;
+                    let y = py as f32 + (sy as f32 + 0.5) / 3.0;
+                    dark += u32::from(dark_at(x, y));
+                }
+            }
+            gray[py * canvas + px] = (240 - dark * 215 / 9) as u8;
+        }
+    }
+    SmokeImage {
+        gray,
+        width: canvas,
+        height: canvas,
+        corners: [(-half, -half), (half, -half), (half, half), (-half, half)]
+            .map(|(u, v)| to_image(u, v)),
+    }
+}
+
+/// Label file for `image` in the BoofCV layout, with the case in a comment.
+pub fn label_file(case: &SmokeCase, image: &SmokeImage) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# rust_qr smoke: version {} {:?} {:?} {:?} rotation {} module {}px",
+        case.version, case.ec_level, case.mode, case.mask, case.rotation_deg, case.module_px
+    );
+    out.push_str("SETS\n");
+    let coords: Vec<String> = image
+        .corners
+        .iter()
+        .flat_map(|&(x, y)| [format!("{x:.3}"), format!("{y:.3}")])
+        .collect();
+    out.push_str(&coords.join(" "));
+    out.push('\n');
+    out
+}
+
+/// Write the first `count` cases under `out` as `<category>/imageNNN.png`
+/// with a `.txt` label beside each, plus a `_smoke.txt` listing them all.
+/// Returns the number of images written.
+pub fn write_smoke_set(out: &Path, count: usize) -> Result<usize, image::ImageError> {
+    let mut listing =
+        String::from("# Generated by `qrtool gen-smoke`; paths relative to this file\n");
+    let mut written = 0;
+    for (index, case) in smoke_cases(count).iter().enumerate() {
+        let Some(image) = case.render() else {
+            continue;
+        };
+        let dir = out.join(case.category());
+        fs::create_dir_all(&dir)?;
+        let name = format!("image{:03}", index + 1);
+        image::save_buffer(
+            dir.join(format!("{name}.png")),
+            &image.gray,
+            image.width as u32,
+            image.height as u32,
+            image::ColorType::L8,
+        )?;
+        fs::write(dir.join(format!("{name}.txt")), label_file(case, &image))?;
+        let _ = writeln!(listing, "{}/{name}.png", case.category());
+        written += 1;
+    }
+    fs::create_dir_all(out)?;
+    fs::write(out.join("_smoke.txt"), listing)?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::qr_decoder::QrDecoder;
+
+    #[test]
+    fn encoded_symbols_decode_at_every_version_and_ec_level() {
+        for case in smoke_cases(160) {
+            let matrix = encode(
+                &case.payload,
+                case.mode,
+                case.version,
+                case.ec_level,
+                case.mask,
+            )
+            .expect("payload fits");
+            let qr = QrDecoder::decode_from_matrix(&matrix, case.version)
+                .unwrap_or_else(|| panic!("{case:?}"));
+            assert_eq!(qr.content, case.payload, "{case:?}");
+        }
+    }
+
+    #[test]
+    fn rendered_cases_detect_inside_their_labels() {
+        // Lower EC levels often decode only after the format fallbacks,
+        // which are slow in debug builds.
+        let cases = smoke_cases(160)
+            .into_iter()
+            .filter(|c| c.version <= 2 && c.ec_level == ECLevel::H);
+        for case in cases {
+            let image = case.render().expect("renders");
+            let rgb: Vec<u8> = image.gray.iter().flat_map(|&v| [v, v, v]).collect();
+            let codes = crate::detect(&rgb, image.width, image.height);
+            assert_eq!(codes.len(), 1, "{case:?}");
+            assert_eq!(codes[0].content, case.payload);
+            let tolerance = case.module_px as f32;
+            for (p, &(x, y)) in codes[0].position.iter().zip(&image.corners) {
+                assert!(
+                    (p.x - x).abs() < tolerance && (p.y - y).abs() < tolerance,
+                    "{case:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! SVG input for vector QR exports
+//!
+//! Design tools hand out QR codes as SVG. Rasterizing them with an external
+//! converter adds a resampling step that blurs module edges, so [`load_svg`]
+//! renders the vector at a fixed [`SVG_DPI`] with crisp edges, flattens it
+//! onto white and thresholds it to pure black and white, ready for
+//! [`detect_from_grayscale`](crate::detect_from_grayscale).
+
+use super::max_dim_from_env;
+use image::ImageError;
+use image::error::{DecodingError, ImageFormatHint};
+use resvg::tiny_skia::{Color, Pixmap, Transform};
+use resvg::usvg::{Options, ShapeRendering, Tree};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+/// Rasterization density. Lengths without units are CSS pixels (1/96 in).
+pub const SVG_DPI: f32 = 300.0;
+
+/// Whether `path` holds SVG markup, sniffed from its first 4 KiB like the
+/// other input formats. Gzipped SVG has no marker of its own and is
+/// recognised by the `.svgz` extension.
+pub fn is_svg<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    let mut head = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(4096).read_to_end(&mut head));
+    if read.is_err() {
+        return false;
+    }
+    if head.starts_with(&[0x1f, 0x8b]) {
+        return path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svgz"));
+    }
+    let text = String::from_utf8_lossy(&head);
+    text.trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('<')
+        && text.contains("<svg")
+}
+
+/// Load an SVG file as black-and-white luma along with its dimensions.
+pub fn load_svg<P: AsRef<Path>>(path: P) -> Result<(Vec<u8>, usize, usize), ImageError> {
+    rasterize_svg(&fs::read(path)?)
+}
+
+/// Render SVG (or gzipped SVG) data at [`SVG_DPI`] to luma that is 0 where
+/// the drawing is dark and 255 elsewhere, including transparent areas.
+///
+/// The output is downscaled to `QR_MAX_DIM` like [`load_rgb`](super::load_rgb),
+/// by lowering the density rather than resampling.
+pub fn rasterize_svg(data: &[u8]) -> Result<(Vec<u8>, usize, usize), ImageError> {
+    // usvg resolves absolute units at 96 dpi, leaving CSS pixels; the
+    // transform takes every length to `SVG_DPI` at once.
+    let options = Options {
+        shape_rendering: ShapeRendering::CrispEdges,
+        ..Options::default()
+    };
+    let tree = Tree::from_data(data, &options).map_err(svg_error)?;
+    let size = tree.size();
+    let mut scale = SVG_DPI / 96.0;
+    if let Some(max_dim) = max_dim_from_env() {
+        scale = scale.min(max_dim as f32 / size.width().max(size.height()));
+    }
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| svg_error(format!("cannot rasterize SVG at {width}x{height}")))?;
+    pixmap.fill(Color::WHITE);
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    let gray = pixmap
+        .data()
+        .chunks_exact(4)
+        .map(|px| {
+            let luma = (px[0] as u32 * 77 + px[1] as u32 * 150 + px[2] as u32 * 29) >> 8;
+            if luma < 128 { 0 } else { 255 }
+        })
+        .collect();
+    Ok((gray, width as usize, height as usize))
+}
+
+fn svg_error(err: impl ToString) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("SVG".to_string()),
+        err.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::qr_decoder::SegmentMode;
+    use crate::models::{ECLevel, MaskPattern};
+    use crate::tools::encode::encode;
+    use std::fmt::Write as _;
+
+    #[test]
+    fn vector_symbol_decodes_from_grayscale() {
+        let symbol = encode(
+            "vector export",
+            SegmentMode::Byte,
+            2,
+            ECLevel::H,
+            MaskPattern::Pattern4,
+        )
+        .unwrap();
+        // One path of unit squares on a quiet-zoned viewBox, 33 mm wide, as
+        // common generators write it.
+        let side = symbol.width() + 8;
+        let mut path = String::new();
+        for y in 0..symbol.width() {
+            for x in 0..symbol.width() {
+                if symbol.get(x, y) {
+                    let _ = write!(path, "M{} {}h1v1h-1z", x + 4, y + 4);
+                }
+            }
+        }
+        let svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{side}mm\" height=\"{side}mm\" \
+             viewBox=\"0 0 {side} {side}\"><path fill=\"#000\" d=\"{path}\"/></svg>"
+        );
+
+        // Recognised by content; the extension says nothing.
+        let path = std::env::temp_dir().join(format!("rustqr_svg_{}.img", std::process::id()));
+        fs::write(&path, &svg).unwrap();
+        let sniffed = is_svg(&path);
+        let loaded = load_svg(&path);
+        let _ = fs::remove_file(&path);
+        assert!(sniffed);
+
+        let (gray, width, height) = loaded.unwrap();
+        let expected = (side as f32 / 25.4 * SVG_DPI).round() as usize;
+        assert_eq!((width, height), (expected, expected));
+        assert!(gray.iter().all(|&v| v == 0 || v == 255));
+        let codes = crate::detect_from_grayscale(&gray, width, height);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].content, "vector export");
+    }
+
+    #[test]
+    fn malformed_svg_is_a_decoding_error() {
+        let err = rasterize_svg(b"<svg").unwrap_err();
+        assert!(matches!(err, ImageError::Decoding(_)), "{err:?}");
+    }
+}
@@ -19,11 +19,8 @@ pub fn otsu_binarize_into(
     let threshold = calculate_otsu_threshold(gray);
 
     for y in 0..height {
-        for x in 0..width {
-            let idx = y * width + x;
-            let is_black = gray[idx] < threshold;
-            output.set(x, y, is_black);
-        }
+        let row = &gray[y * width..(y + 1) * width];
+        output.fill_row(y, |x| row[x] < threshold);
     }
 }
 
@@ -57,6 +54,20 @@ pub fn adaptive_binarize_into(
     adaptive_binarize_core(gray, width, height, window_size, output, integral);
 }
 
+/// Adaptive binarization over a prebuilt [`build_integral_image`] of `gray`,
+/// so several matrices derived from one frame share its integral
+pub(crate) fn adaptive_binarize_with_integral(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+    integral: &[u32],
+) -> crate::models::BitMatrix {
+    let mut binary = crate::models::BitMatrix::new(width, height);
+    adaptive_binarize_core(gray, width, height, window_size, &mut binary, integral);
+    binary
+}
+
 /// Core adaptive binarization logic shared by allocating and _into variants
 fn adaptive_binarize_core(
     gray: &[u8],
@@ -69,7 +80,7 @@ fn adaptive_binarize_core(
     let half_window = window_size / 2;
 
     for y in 0..height {
-        for x in 0..width {
+        binary.fill_row(y, |x| {
             let idx = y * width + x;
 
             // Get local mean using integral image
@@ -84,15 +95,14 @@ fn adaptive_binarize_core(
 
             // Use local mean as threshold
             let threshold = local_mean;
-            let is_black = gray[idx] < threshold;
-            binary.set(x, y, is_black);
-        }
+            gray[idx] < threshold
+        });
     }
 }
 
 /// Build integral image for fast box sum queries
 /// integral[y][x] = sum of all pixels from (0,0) to (x,y)
-fn build_integral_image(gray: &[u8], width: usize, height: usize) -> Vec<u32> {
+pub(crate) fn build_integral_image(gray: &[u8], width: usize, height: usize) -> Vec<u32> {
     let mut integral = vec![0u32; width * height];
     build_integral_image_into(gray, width, height, &mut integral);
     integral
@@ -215,11 +225,8 @@ pub fn threshold_binarize(
     let mut binary = BitMatrix::new(width, height);
 
     for y in 0..height {
-        for x in 0..width {
-            let idx = y * width + x;
-            let is_black = gray[idx] < threshold;
-            binary.set(x, y, is_black);
-        }
+        let row = &gray[y * width..(y + 1) * width];
+        binary.fill_row(y, |x| row[x] < threshold);
     }
 
     binary
@@ -280,6 +287,31 @@ pub fn sauvola_binarize_into(
     );
 }
 
+/// Sauvola binarization over prebuilt integral images of `gray` and of its
+/// squares
+pub(crate) fn sauvola_binarize_with_integrals(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+    k: f32,
+    integral: &[u32],
+    integral_sq: &[u64],
+) -> crate::models::BitMatrix {
+    let mut binary = crate::models::BitMatrix::new(width, height);
+    sauvola_binarize_core(
+        gray,
+        width,
+        height,
+        window_size,
+        k,
+        &mut binary,
+        integral,
+        integral_sq,
+    );
+    binary
+}
+
 /// Core Sauvola binarization logic
 #[allow(clippy::too_many_arguments)]
 fn sauvola_binarize_core(
@@ -296,7 +328,7 @@ fn sauvola_binarize_core(
     const R: f64 = 128.0;
 
     for y in 0..height {
-        for x in 0..width {
+        binary.fill_row(y, |x| {
             let idx = y * width + x;
 
             let x1 = x.saturating_sub(half_window);
@@ -314,14 +346,133 @@ fn sauvola_binarize_core(
             let std_dev = variance.sqrt();
 
             let threshold = mean * (1.0 + k as f64 * (std_dev / R - 1.0));
-            let is_black = (gray[idx] as f64) < threshold;
-            binary.set(x, y, is_black);
-        }
+            (gray[idx] as f64) < threshold
+        });
+    }
+}
+
+/// Local standard deviation (gray levels) from which [`hybrid_binarize`]
+/// trusts the local mean over the global threshold.
+const HYBRID_MIN_STDDEV: f64 = 16.0;
+
+/// Binarize with a per-pixel choice between a global and a local threshold.
+/// Where the window around a pixel has texture (standard deviation of at
+/// least [`HYBRID_MIN_STDDEV`]) the pixel is compared to the local mean, as
+/// in [`adaptive_binarize`]; in flat areas, where the local mean sits on the
+/// noise, it is compared to the Otsu threshold instead. Symbols in shadow
+/// keep their modules while the flat background around them stays clean, in
+/// a single pass.
+pub fn hybrid_binarize(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+) -> crate::models::BitMatrix {
+    use crate::models::BitMatrix;
+
+    let mut binary = BitMatrix::new(width, height);
+    let integral = build_integral_image(gray, width, height);
+    let integral_sq = build_integral_sq_image(gray, width, height);
+    hybrid_binarize_core(
+        gray,
+        width,
+        height,
+        window_size,
+        &mut binary,
+        &integral,
+        &integral_sq,
+    );
+    binary
+}
+
+/// Hybrid binarization writing into existing buffers (avoids allocation)
+pub fn hybrid_binarize_into(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+    output: &mut crate::models::BitMatrix,
+    integral: &mut Vec<u32>,
+    integral_sq: &mut Vec<u64>,
+) {
+    output.reset(width, height);
+    build_integral_image_into(gray, width, height, integral);
+    build_integral_sq_image_into(gray, width, height, integral_sq);
+    hybrid_binarize_core(
+        gray,
+        width,
+        height,
+        window_size,
+        output,
+        integral,
+        integral_sq,
+    );
+}
+
+/// Hybrid binarization over prebuilt integral images of `gray` and of its
+/// squares
+pub(crate) fn hybrid_binarize_with_integrals(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+    integral: &[u32],
+    integral_sq: &[u64],
+) -> crate::models::BitMatrix {
+    let mut binary = crate::models::BitMatrix::new(width, height);
+    hybrid_binarize_core(
+        gray,
+        width,
+        height,
+        window_size,
+        &mut binary,
+        integral,
+        integral_sq,
+    );
+    binary
+}
+
+/// Core hybrid binarization logic
+fn hybrid_binarize_core(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    window_size: usize,
+    binary: &mut crate::models::BitMatrix,
+    integral: &[u32],
+    integral_sq: &[u64],
+) {
+    let half_window = window_size / 2;
+    let global = calculate_otsu_threshold(gray) as f64;
+    let min_variance = HYBRID_MIN_STDDEV * HYBRID_MIN_STDDEV;
+
+    for y in 0..height {
+        binary.fill_row(y, |x| {
+            let idx = y * width + x;
+
+            let x1 = x.saturating_sub(half_window);
+            let y1 = y.saturating_sub(half_window);
+            let x2 = (x + half_window).min(width - 1);
+            let y2 = (y + half_window).min(height - 1);
+
+            let pixel_count = (x2 - x1 + 1) * (y2 - y1 + 1);
+            let local_sum = query_integral_sum(integral, width, height, x1, y1, x2, y2);
+            let local_sq_sum = query_integral_sq_sum(integral_sq, width, x1, y1, x2, y2);
+
+            let mean = local_sum as f64 / pixel_count as f64;
+            let variance = local_sq_sum as f64 / pixel_count as f64 - mean * mean;
+            let threshold = if variance >= min_variance {
+                mean
+            } else {
+                global
+            };
+            (gray[idx] as f64) < threshold
+        });
     }
 }
 
 /// Build integral image of squared pixel values for variance computation
-fn build_integral_sq_image(gray: &[u8], width: usize, height: usize) -> Vec<u64> {
+pub(crate) fn build_integral_sq_image(gray: &[u8], width: usize, height: usize) -> Vec<u64> {
     let mut integral_sq = vec![0u64; width * height];
     build_integral_sq_image_into(gray, width, height, &mut integral_sq);
     integral_sq
@@ -493,4 +644,33 @@ mod tests {
         assert_eq!(binary.width(), 20);
         assert_eq!(binary.height(), 20);
     }
+
+    #[test]
+    fn test_hybrid_binarize_keeps_flat_areas_global() {
+        // A lit, slightly noisy left half and a shadowed right half, each
+        // flat apart from a dark bar.
+        let (w, h) = (40, 10);
+        let gray: Vec<u8> = (0..w * h)
+            .map(|i| match i % w {
+                4..=6 => 20,
+                30..=32 => 10,
+                0..=19 => 220 + 3 * (i % 3 == 0) as u8,
+                _ => 120,
+            })
+            .collect();
+        let hybrid = hybrid_binarize(&gray, w, h, 9);
+        // Both bars are black, and so is neither bar's surround.
+        assert!(hybrid.get(5, 5) && hybrid.get(31, 5));
+        assert!(!hybrid.get(2, 5) && !hybrid.get(28, 5) && !hybrid.get(34, 5));
+        // Away from the bar the lit half is flat: the local mean would split
+        // its noise, the global threshold leaves it white.
+        let speckles = |b: &crate::models::BitMatrix| {
+            (11..=15)
+                .flat_map(|x| (0..h).map(move |y| (x, y)))
+                .filter(|&(x, y)| b.get(x, y))
+                .count()
+        };
+        assert!(speckles(&adaptive_binarize(&gray, w, h, 9)) > 0);
+        assert_eq!(speckles(&hybrid), 0);
+    }
 }
@@ -1,3 +1,5 @@
+use crate::utils::geometry::PerspectiveTransform;
+
 /// Fixed-point arithmetic module (16.16 format)
 ///
 /// 16.16 fixed-point representation:
@@ -21,6 +23,16 @@ impl Fixed {
         Fixed(n.wrapping_shl(Self::FRACTIONAL_BITS as u32))
     }
 
+    /// Create from the raw 16.16 representation
+    pub const fn from_bits(bits: i32) -> Self {
+        Fixed(bits)
+    }
+
+    /// Raw 16.16 representation
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
     /// Create from float
     pub fn from_f32(f: f32) -> Self {
         Fixed((f * (Self::SCALE as f32)) as i32)
@@ -75,9 +87,130 @@ impl FixedMatrix3x3 {
     }
 }
 
+/// Fractional bits of the projective (denominator) row
+const DEN_BITS: u32 = 30;
+
+/// Perspective transform evaluated with integer arithmetic only
+///
+/// The numerator rows are 16.16, but the projective row needs far more
+/// fractional precision (its terms are typically around 1e-4), so it is kept
+/// in 2.30. Points are mapped in homogeneous coordinates: along a row of
+/// evenly spaced inputs the three accumulators advance by additions, leaving
+/// one division per output coordinate.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPerspective {
+    /// `[a11, a12, a13]` and `[a21, a22, a23]` in 16.16
+    num: [[i64; 3]; 2],
+    /// `[a31, a32, a33]` in 2.30
+    den: [i64; 3],
+}
+
+impl FixedPerspective {
+    /// Convert a float transform, normalized so `a33 = 1`. Returns `None`
+    /// when an entry does not fit the fixed-point ranges.
+    pub fn from_transform(transform: &PerspectiveTransform) -> Option<Self> {
+        if transform.lens().is_some() {
+            return None;
+        }
+        let c = transform.coefficients();
+        let scale = c[8];
+        if !scale.is_finite() || scale.abs() < 1e-12 {
+            return None;
+        }
+        let to_fixed = |v: f32, bits: u32, limit: f64| {
+            let v = f64::from(v / scale);
+            (v.is_finite() && v.abs() < limit).then(|| (v * f64::from(1u32 << bits)).round() as i64)
+        };
+        let num = |i: usize| to_fixed(c[i], 16, 32768.0);
+        let den = |i: usize| to_fixed(c[i], DEN_BITS, 2.0);
+        Some(Self {
+            num: [[num(0)?, num(1)?, num(2)?], [num(3)?, num(4)?, num(5)?]],
+            den: [den(6)?, den(7)?, den(8)?],
+        })
+    }
+
+    /// Map `(x, y)`; `None` on or behind the horizon or outside the 16.16
+    /// range.
+    pub fn transform(&self, x: Fixed, y: Fixed) -> Option<(Fixed, Fixed)> {
+        let (x, y) = (i64::from(x.0), i64::from(y.0));
+        let row = |r: &[i64; 3]| ((r[0] * x + r[1] * y) >> 16) + r[2];
+        project(row(&self.num[0]), row(&self.num[1]), row(&self.den))
+    }
+
+    /// Map the `count` points `(x0 + i * dx, y)` into `out` (cleared first).
+    pub fn map_row(
+        &self,
+        x0: Fixed,
+        y: Fixed,
+        dx: Fixed,
+        count: usize,
+        out: &mut Vec<Option<(Fixed, Fixed)>>,
+    ) {
+        out.clear();
+        let (x0, y, dx) = (i64::from(x0.0), i64::from(y.0), i64::from(dx.0));
+        let start = |r: &[i64; 3]| ((r[0] * x0 + r[1] * y) >> 16) + r[2];
+        let step = |r: &[i64; 3]| (r[0] * dx) >> 16;
+        let (mut nx, mut ny, mut d) = (start(&self.num[0]), start(&self.num[1]), start(&self.den));
+        let (sx, sy, sd) = (step(&self.num[0]), step(&self.num[1]), step(&self.den));
+        for _ in 0..count {
+            out.push(project(nx, ny, d));
+            nx += sx;
+            ny += sy;
+            d += sd;
+        }
+    }
+}
+
+/// Divide 16.16 numerators by a 2.30 denominator.
+fn project(nx: i64, ny: i64, den: i64) -> Option<(Fixed, Fixed)> {
+    if den <= 0 {
+        return None;
+    }
+    let coord = |n: i64| i32::try_from((n << DEN_BITS) / den).ok().map(Fixed);
+    Some((coord(nx)?, coord(ny)?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::Point;
+
+    #[test]
+    fn perspective_matches_float_transform() {
+        let src = [
+            Point::new(0.0, 0.0),
+            Point::new(25.0, 0.0),
+            Point::new(0.0, 25.0),
+            Point::new(25.0, 25.0),
+        ];
+        let dst = [
+            Point::new(412.0, 96.0),
+            Point::new(1180.0, 140.0),
+            Point::new(350.0, 905.0),
+            Point::new(1290.0, 1010.0),
+        ];
+        let float = PerspectiveTransform::from_points(&src, &dst).unwrap();
+        let fixed = FixedPerspective::from_transform(&float).unwrap();
+
+        let mut row = Vec::new();
+        let mut worst = 0.0f32;
+        for my in 0..25 {
+            let y = Fixed::from_f32(my as f32 + 0.5);
+            fixed.map_row(Fixed::from_f32(0.5), y, Fixed::from_i32(1), 25, &mut row);
+            for (mx, mapped) in row.iter().enumerate() {
+                let expected = float.transform(&Point::new(mx as f32 + 0.5, my as f32 + 0.5));
+                let (fx, fy) = mapped.unwrap();
+                let direct = fixed
+                    .transform(Fixed::from_f32(mx as f32 + 0.5), y)
+                    .unwrap();
+                assert!((direct.0.to_f32() - fx.to_f32()).abs() < 0.01);
+                worst = worst
+                    .max((fx.to_f32() - expected.x).abs())
+                    .max((fy.to_f32() - expected.y).abs());
+            }
+        }
+        assert!(worst < 0.05, "worst deviation {worst} px");
+    }
 
     #[test]
     fn test_fixed_basic() {
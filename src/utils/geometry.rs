@@ -1,7 +1,10 @@
 /// Geometry utilities for perspective transformations and calculations
-use crate::models::Point;
+use crate::config::LensModel;
+use crate::models::{ImagePoint, ModulePoint, Point};
 
-/// Perspective transformation matrix (3x3)
+/// Perspective transformation matrix (3x3), optionally followed by a lens
+/// distortion (see [`PerspectiveTransform::through_lens`])
+#[derive(Debug, Clone, Copy)]
 pub struct PerspectiveTransform {
     a11: f32,
     a12: f32,
@@ -12,6 +15,7 @@ pub struct PerspectiveTransform {
     a31: f32,
     a32: f32,
     a33: f32,
+    lens: Option<LensModel>,
 }
 
 impl PerspectiveTransform {
@@ -60,9 +64,133 @@ impl PerspectiveTransform {
             a31: solution[6],
             a32: solution[7],
             a33: 1.0,
+            lens: None,
         })
     }
 
+    /// Least-squares transform from 4 or more point correspondences
+    ///
+    /// Minimizes the algebraic DLT error via the normal equations, so extra
+    /// correspondences average out measurement noise. Both point sets are
+    /// normalized first to keep the f32 system well conditioned.
+    pub fn fit(src: &[Point], dst: &[Point]) -> Option<Self> {
+        if src.len() < 4 || src.len() != dst.len() {
+            return None;
+        }
+        let (src_center, src_scale) = normalization(src)?;
+        let (dst_center, dst_scale) = normalization(dst)?;
+
+        let mut ata = [[0.0f32; 8]; 8];
+        let mut atb = [0.0f32; 8];
+        for (s, d) in src.iter().zip(dst) {
+            let (sx, sy) = (
+                (s.x - src_center.x) * src_scale,
+                (s.y - src_center.y) * src_scale,
+            );
+            let (dx, dy) = (
+                (d.x - dst_center.x) * dst_scale,
+                (d.y - dst_center.y) * dst_scale,
+            );
+            let rows = [
+                ([sx, sy, 1.0, 0.0, 0.0, 0.0, -dx * sx, -dx * sy], dx),
+                ([0.0, 0.0, 0.0, sx, sy, 1.0, -dy * sx, -dy * sy], dy),
+            ];
+            for (row, rhs) in rows {
+                for i in 0..8 {
+                    atb[i] += row[i] * rhs;
+                    for j in 0..8 {
+                        ata[i][j] += row[i] * row[j];
+                    }
+                }
+            }
+        }
+        let h = solve_linear_system(&ata, &atb)?;
+
+        // Undo normalization: H = T_dst^-1 * Hn * T_src.
+        let (ss, cx, cy) = (src_scale, src_center.x, src_center.y);
+        let hn = [
+            [h[0] * ss, h[1] * ss, h[2] - (h[0] * cx + h[1] * cy) * ss],
+            [h[3] * ss, h[4] * ss, h[5] - (h[3] * cx + h[4] * cy) * ss],
+            [h[6] * ss, h[7] * ss, 1.0 - (h[6] * cx + h[7] * cy) * ss],
+        ];
+        let inv = 1.0 / dst_scale;
+        let row = |i: usize| {
+            let c = if i == 0 { dst_center.x } else { dst_center.y };
+            [
+                hn[i][0] * inv + c * hn[2][0],
+                hn[i][1] * inv + c * hn[2][1],
+                hn[i][2] * inv + c * hn[2][2],
+            ]
+        };
+        let (r0, r1, r2) = (row(0), row(1), hn[2]);
+        if r2[2].abs() < 1e-10 {
+            return None;
+        }
+        let n = 1.0 / r2[2];
+        Some(Self {
+            a11: r0[0] * n,
+            a12: r0[1] * n,
+            a13: r0[2] * n,
+            a21: r1[0] * n,
+            a22: r1[1] * n,
+            a23: r1[2] * n,
+            a31: r2[0] * n,
+            a32: r2[1] * n,
+            a33: 1.0,
+            lens: None,
+        })
+    }
+
+    /// Transform from `src` to image points `dst` seen through `lens`.
+    ///
+    /// The matrix is fitted to the undistorted `dst` by `fit` (which solves
+    /// the 4-point case exactly) and [`Self::transform`] distorts its output
+    /// again, so a planar grid maps onto its curved image. `None` is a plain
+    /// `fit`.
+    pub fn through_lens(src: &[Point], dst: &[Point], lens: Option<LensModel>) -> Option<Self> {
+        let Some(lens) = lens else {
+            return Self::fit(src, dst);
+        };
+        let ideal: Vec<Point> = dst.iter().map(|&p| lens.undistort(p)).collect();
+        Some(Self {
+            lens: Some(lens),
+            ..Self::fit(src, &ideal)?
+        })
+    }
+
+    /// Transform from module-grid points to the image points they were
+    /// found at, seen through `lens`, for sampling: four correspondences
+    /// without a lens are solved exactly by [`Self::from_points`], anything
+    /// else goes through [`Self::through_lens`].
+    pub fn module_to_image(
+        modules: &[ModulePoint],
+        image: &[ImagePoint],
+        lens: Option<LensModel>,
+    ) -> Option<Self> {
+        let src: Vec<Point> = modules.iter().map(|m| Point::new(m.x, m.y)).collect();
+        let dst: Vec<Point> = image.iter().map(|&p| p.into()).collect();
+        match (
+            <&[Point; 4]>::try_from(&src[..]),
+            <&[Point; 4]>::try_from(&dst[..]),
+        ) {
+            (Ok(src), Ok(dst)) if lens.is_none() => Self::from_points(src, dst),
+            _ => Self::through_lens(&src, &dst, lens),
+        }
+    }
+
+    /// Lens distortion applied after the matrix, if any
+    pub fn lens(&self) -> Option<LensModel> {
+        self.lens
+    }
+
+    /// Matrix entries in row-major order, without any lens distortion
+    pub fn coefficients(&self) -> [f32; 9] {
+        [
+            self.a11, self.a12, self.a13, self.a21, self.a22, self.a23, self.a31, self.a32,
+            self.a33,
+        ]
+    }
+
     /// Transform a point using this perspective matrix
     pub fn transform(&self, p: &Point) -> Point {
         let x = p.x;
@@ -76,10 +204,72 @@ impl PerspectiveTransform {
         let x_new = (self.a11 * x + self.a12 * y + self.a13) / denominator;
         let y_new = (self.a21 * x + self.a22 * y + self.a23) / denominator;
 
-        Point::new(x_new, y_new)
+        match &self.lens {
+            Some(lens) => lens.distort(Point::new(x_new, y_new)),
+            None => Point::new(x_new, y_new),
+        }
+    }
+
+    /// Where module-grid point `p` lies in the image, for a transform built
+    /// from module space to image space (as every sampling transform is)
+    pub fn to_image(&self, p: ModulePoint) -> ImagePoint {
+        self.transform(&Point::new(p.x, p.y)).into()
+    }
+
+    /// In-plane rotation and out-of-plane tilt, in degrees, of the source
+    /// plane as seen at source point `at`.
+    ///
+    /// Both come from the local Jacobian: rotation is that of its closest
+    /// rotation matrix (clockwise in image coordinates, source +x at 0), tilt
+    /// is `acos` of the ratio of its singular values, i.e. how much the
+    /// plane is foreshortened along its most compressed direction. `aspect`
+    /// is the width over height of one destination pixel. A lens is
+    /// ignored: the pose is that of the undistorted view.
+    pub fn pose_angles(&self, at: &Point, aspect: f32) -> (f32, f32) {
+        let w = self.a31 * at.x + self.a32 * at.y + self.a33;
+        if w.abs() < 1e-10 {
+            return (0.0, 0.0);
+        }
+        let p = Self {
+            lens: None,
+            ..*self
+        }
+        .transform(at);
+        // Partial derivatives, with x scaled to square pixels
+        let j11 = aspect * (self.a11 - p.x * self.a31) / w;
+        let j12 = aspect * (self.a12 - p.x * self.a32) / w;
+        let j21 = (self.a21 - p.y * self.a31) / w;
+        let j22 = (self.a22 - p.y * self.a32) / w;
+
+        let rotation = (j21 - j12).atan2(j11 + j22).to_degrees();
+
+        // Singular values of the 2x2 Jacobian
+        let e = (j11 + j22) / 2.0;
+        let f = (j11 - j22) / 2.0;
+        let g = (j21 + j12) / 2.0;
+        let h = (j21 - j12) / 2.0;
+        let q = (e * e + h * h).sqrt();
+        let r = (f * f + g * g).sqrt();
+        let (s_max, s_min) = (q + r, (q - r).abs());
+        let tilt = if s_max > 0.0 {
+            (s_min / s_max).clamp(0.0, 1.0).acos().to_degrees()
+        } else {
+            0.0
+        };
+        (rotation, tilt)
     }
 }
 
+/// Centroid and scale that map `points` to mean distance sqrt(2) from the origin.
+fn normalization(points: &[Point]) -> Option<(Point, f32)> {
+    let n = points.len() as f32;
+    let cx = points.iter().map(|p| p.x).sum::<f32>() / n;
+    let cy = points.iter().map(|p| p.y).sum::<f32>() / n;
+    let center = Point::new(cx, cy);
+    let mean_dist = points.iter().map(|p| distance(p, &center)).sum::<f32>() / n;
+    (mean_dist > 1e-6).then(|| (center, std::f32::consts::SQRT_2 / mean_dist))
+}
+
 /// Solve 8x8 linear system using Gaussian elimination
 #[allow(clippy::needless_range_loop)]
 fn solve_linear_system(a: &[[f32; 8]; 8], b: &[f32; 8]) -> Option<[f32; 8]> {
@@ -158,6 +348,110 @@ pub fn angle(p1: &Point, p2: &Point, p3: &Point) -> f32 {
     cross.atan2(dot).abs()
 }
 
+/// Signed area of a simple polygon, positive when its vertices wind
+/// clockwise in image space (x right, y down)
+///
+/// This is the winding every corner polygon this crate exports uses:
+/// [`crate::QRCode::outline`] and [`crate::stages::Candidate::quad`] both
+/// return TL, TR, BR, BL with a positive signed area.
+pub fn signed_area(polygon: &[Point]) -> f32 {
+    let n = polygon.len();
+    let twice: f32 = (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    twice / 2.0
+}
+
+/// Area of a simple polygon in square pixels, whatever its winding
+pub fn polygon_area(polygon: &[Point]) -> f32 {
+    signed_area(polygon).abs()
+}
+
+/// `quad` wound clockwise in image space, keeping its first corner first
+///
+/// A counter-clockwise quad (a mirrored symbol's TL, TR, BR, BL) comes back
+/// as TL, BL, BR, TR.
+pub fn clockwise(quad: [Point; 4]) -> [Point; 4] {
+    if signed_area(&quad) < 0.0 {
+        [quad[0], quad[3], quad[2], quad[1]]
+    } else {
+        quad
+    }
+}
+
+/// Whether `p` lies inside `polygon` (even-odd rule, so concave and
+/// self-touching outlines work; points exactly on an edge may go either way)
+pub fn point_in_polygon(p: Point, polygon: &[Point]) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + n - 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Intersection over union of two convex polygons, either winding
+///
+/// 0 when they do not overlap (or either is degenerate), 1 when they
+/// coincide. Code outlines and candidate quads are convex unless the
+/// geometry has gone badly wrong; a concave input gives an approximate
+/// answer.
+pub fn polygon_iou(a: &[Point], b: &[Point]) -> f32 {
+    let (area_a, area_b) = (polygon_area(a), polygon_area(b));
+    if area_a <= 0.0 || area_b <= 0.0 {
+        return 0.0;
+    }
+    let orient = |poly: &[Point]| -> Vec<Point> {
+        let mut v = poly.to_vec();
+        if signed_area(&v) < 0.0 {
+            v.reverse();
+        }
+        v
+    };
+    let inter = polygon_area(&clip_convex(&orient(a), &orient(b)));
+    let union = area_a + area_b - inter;
+    if union <= 0.0 { 0.0 } else { inter / union }
+}
+
+/// Sutherland-Hodgman: the part of `subject` inside convex `clip`, both
+/// wound clockwise in image space.
+fn clip_convex(subject: &[Point], clip: &[Point]) -> Vec<Point> {
+    let mut output = subject.to_vec();
+    for i in 0..clip.len() {
+        if output.is_empty() {
+            break;
+        }
+        let (c0, c1) = (clip[i], clip[(i + 1) % clip.len()]);
+        // Inside is to the right of c0 -> c1 in a y-down frame.
+        let side = |p: Point| (c1.x - c0.x) * (p.y - c0.y) - (c1.y - c0.y) * (p.x - c0.x);
+        let input = std::mem::take(&mut output);
+        for j in 0..input.len() {
+            let (cur, prev) = (input[j], input[(j + input.len() - 1) % input.len()]);
+            let (s_cur, s_prev) = (side(cur), side(prev));
+            if (s_cur >= 0.0) != (s_prev >= 0.0) {
+                let t = s_prev / (s_prev - s_cur);
+                output.push(Point::new(
+                    prev.x + t * (cur.x - prev.x),
+                    prev.y + t * (cur.y - prev.y),
+                ));
+            }
+            if s_cur >= 0.0 {
+                output.push(cur);
+            }
+        }
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,6 +480,84 @@ mod tests {
         assert!(p.x > 20.0 && p.x < 30.0); // Should be approximately in the middle
     }
 
+    #[test]
+    fn test_fit_matches_exact_solution() {
+        let src = [
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(0.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(5.0, 5.0),
+        ];
+        let dst_exact = [
+            Point::new(3.0, 4.0),
+            Point::new(41.0, 6.0),
+            Point::new(5.0, 38.0),
+            Point::new(47.0, 44.0),
+        ];
+        let exact =
+            PerspectiveTransform::from_points(&[src[0], src[1], src[2], src[3]], &dst_exact)
+                .unwrap();
+        let dst: Vec<Point> = src.iter().map(|p| exact.transform(p)).collect();
+        let fitted = PerspectiveTransform::fit(&src, &dst).unwrap();
+        for p in &src {
+            assert!(distance(&exact.transform(p), &fitted.transform(p)) < 0.01);
+        }
+        assert!(PerspectiveTransform::fit(&src[..3], &dst[..3]).is_none());
+    }
+
+    #[test]
+    fn test_module_to_image_maps_module_centres() {
+        let modules = [
+            ModulePoint::new(0.0, 0.0),
+            ModulePoint::new(21.0, 0.0),
+            ModulePoint::new(21.0, 21.0),
+            ModulePoint::new(0.0, 21.0),
+        ];
+        let image = modules.map(|m| ImagePoint::new(10.0 + 3.0 * m.x, 20.0 + 3.0 * m.y));
+        let t = PerspectiveTransform::module_to_image(&modules, &image, None).unwrap();
+        let p = t.to_image(ModulePoint::center_of(3, 4));
+        assert!(p.distance(&ImagePoint::new(20.5, 33.5)) < 1e-3);
+        assert!(PerspectiveTransform::module_to_image(&modules[..3], &image[..3], None).is_none());
+    }
+
+    #[test]
+    fn test_pose_angles() {
+        let src = [
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+        ];
+        let centre = Point::new(5.0, 5.0);
+        let place = |theta_deg: f32, squash: f32| {
+            let (sin, cos) = theta_deg.to_radians().sin_cos();
+            src.map(|p| {
+                let x = p.x * squash;
+                Point::new(
+                    100.0 + 4.0 * (x * cos - p.y * sin),
+                    80.0 + 4.0 * (x * sin + p.y * cos),
+                )
+            })
+        };
+
+        let rotated = PerspectiveTransform::from_points(&src, &place(30.0, 1.0)).unwrap();
+        let (rotation, tilt) = rotated.pose_angles(&centre, 1.0);
+        assert!((rotation - 30.0).abs() < 0.1, "rotation {rotation}");
+        assert!(tilt < 0.5, "tilt {tilt}");
+
+        let squash = 40f32.to_radians().cos();
+        let tilted = PerspectiveTransform::from_points(&src, &place(-120.0, squash)).unwrap();
+        let (rotation, tilt) = tilted.pose_angles(&centre, 1.0);
+        assert!((tilt - 40.0).abs() < 0.5, "tilt {tilt}");
+        assert!((rotation + 120.0).abs() < 5.0, "rotation {rotation}");
+
+        // The same square captured with pixels twice as tall as wide
+        let stretched = place(0.0, 1.0).map(|p| Point::new(p.x * 2.0, p.y));
+        let stretched = PerspectiveTransform::from_points(&src, &stretched).unwrap();
+        assert!(stretched.pose_angles(&centre, 0.5).1 < 0.5);
+    }
+
     #[test]
     fn test_distance() {
         let p1 = Point::new(0.0, 0.0);
@@ -202,4 +574,40 @@ mod tests {
         let a = angle(&p1, &p2, &p3);
         assert!((a - std::f32::consts::PI / 2.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_polygon_helpers_follow_clockwise_winding() {
+        let square = [
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+        ];
+        assert!((signed_area(&square) - 100.0).abs() < 1e-3);
+        let mirrored = [square[0], square[3], square[2], square[1]];
+        assert!((signed_area(&mirrored) + 100.0).abs() < 1e-3);
+        assert_eq!(clockwise(mirrored), square);
+        assert_eq!(clockwise(square), square);
+
+        assert!(point_in_polygon(Point::new(5.0, 5.0), &square));
+        assert!(point_in_polygon(Point::new(5.0, 5.0), &mirrored));
+        assert!(!point_in_polygon(Point::new(11.0, 5.0), &square));
+        // Concave L: the notch is outside
+        let l_shape = [
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(4.0, 6.0),
+            Point::new(10.0, 6.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+        ];
+        assert!(!point_in_polygon(Point::new(7.0, 3.0), &l_shape));
+        assert!(point_in_polygon(Point::new(7.0, 8.0), &l_shape));
+
+        assert!((polygon_iou(&square, &mirrored) - 1.0).abs() < 1e-4);
+        let shifted = square.map(|p| p.translate(5.0, 0.0));
+        assert!((polygon_iou(&square, &shifted) - 50.0 / 150.0).abs() < 1e-4);
+        let apart = square.map(|p| p.translate(20.0, 0.0));
+        assert_eq!(polygon_iou(&square, &apart), 0.0);
+    }
 }
@@ -125,6 +125,157 @@ pub fn normalize_roi_local_contrast(
     out
 }
 
+/// Histogram of every `step`-th pixel of every `step`-th row, with the number
+/// of pixels counted. Empty when `gray` does not hold `width * height` bytes.
+pub(crate) fn gray_histogram(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    step: usize,
+) -> ([usize; 256], usize) {
+    let mut hist = [0usize; 256];
+    let mut total = 0usize;
+    if width == 0 || gray.len() != width * height {
+        return (hist, total);
+    }
+    let step = step.max(1);
+    for y in (0..height).step_by(step) {
+        for &v in gray[y * width..(y + 1) * width].iter().step_by(step) {
+            hist[v as usize] += 1;
+            total += 1;
+        }
+    }
+    (hist, total)
+}
+
+/// Lowest gray level with more than `q` percent of the `total` samples at or
+/// below it.
+pub(crate) fn histogram_percentile(hist: &[usize; 256], total: usize, q: usize) -> u8 {
+    let target = total * q / 100;
+    let mut seen = 0usize;
+    hist.iter()
+        .position(|&n| {
+            seen += n;
+            seen > target
+        })
+        .unwrap_or(255) as u8
+}
+
+/// Gray levels between the `lo`th and `hi`th percentile of a
+/// [`gray_histogram`] sample. Unlike the min/max range, a few hot or dead
+/// pixels don't widen it.
+pub(crate) fn percentile_contrast_span(
+    gray: &[u8],
+    width: usize,
+    height: usize,
+    step: usize,
+    lo: usize,
+    hi: usize,
+) -> u8 {
+    let (hist, total) = gray_histogram(gray, width, height, step);
+    if total == 0 {
+        return 0;
+    }
+    histogram_percentile(&hist, total, hi).saturating_sub(histogram_percentile(&hist, total, lo))
+}
+
+/// Side, in pixels, of the tiles [`fuse_exposures`] weights frames over.
+const FUSION_TILE: usize = 16;
+
+/// Merge bracketed exposures of one scene into a single frame.
+///
+/// Each frame is weighted per 16x16 tile by how close the tile's mean is to
+/// mid-grey, how much contrast it holds and how few of its pixels are
+/// clipped, so areas lost to glare in one exposure come from the others.
+/// Weights are interpolated between tile centres, and are shared by all the
+/// pixels around a point so neighbouring modules keep the contrast of the
+/// exposure they were taken from. Every frame must hold `width * height`
+/// bytes.
+pub fn fuse_exposures(frames: &[&[u8]], width: usize, height: usize) -> Vec<u8> {
+    let pixel_count = width * height;
+    assert!(
+        frames.iter().all(|f| f.len() >= pixel_count),
+        "Frame buffer too small"
+    );
+    match frames {
+        [] => return Vec::new(),
+        [only] => return only[..pixel_count].to_vec(),
+        _ => {}
+    }
+
+    let tiles_x = width.div_ceil(FUSION_TILE);
+    let tiles_y = height.div_ceil(FUSION_TILE);
+    let weights: Vec<Vec<f32>> = frames
+        .iter()
+        .map(|frame| exposure_weights(frame, width, height, tiles_x, tiles_y))
+        .collect();
+
+    let mut out = vec![0u8; pixel_count];
+    for y in 0..height {
+        let (ty0, ty1, fy) = tile_coord(y, tiles_y);
+        for x in 0..width {
+            let (tx0, tx1, fx) = tile_coord(x, tiles_x);
+            let mut sum = 0.0f32;
+            let mut weight_sum = 0.0f32;
+            for (frame, w) in frames.iter().zip(&weights) {
+                let top = w[ty0 * tiles_x + tx0] * (1.0 - fx) + w[ty0 * tiles_x + tx1] * fx;
+                let bottom = w[ty1 * tiles_x + tx0] * (1.0 - fx) + w[ty1 * tiles_x + tx1] * fx;
+                let weight = top * (1.0 - fy) + bottom * fy;
+                sum += weight * frame[y * width + x] as f32;
+                weight_sum += weight;
+            }
+            out[y * width + x] = (sum / weight_sum).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    out
+}
+
+/// Per-tile weight of one exposure: well-exposedness times contrast, scaled
+/// down by the share of clipped pixels.
+fn exposure_weights(
+    frame: &[u8],
+    width: usize,
+    height: usize,
+    tiles_x: usize,
+    tiles_y: usize,
+) -> Vec<f32> {
+    let mut weights = Vec::with_capacity(tiles_x * tiles_y);
+    for ty in 0..tiles_y {
+        let (y0, y1) = (ty * FUSION_TILE, ((ty + 1) * FUSION_TILE).min(height));
+        for tx in 0..tiles_x {
+            let (x0, x1) = (tx * FUSION_TILE, ((tx + 1) * FUSION_TILE).min(width));
+            let mut sum = 0u32;
+            let mut sum_sq = 0u64;
+            let mut clipped = 0u32;
+            for y in y0..y1 {
+                for &v in &frame[y * width + x0..y * width + x1] {
+                    sum += v as u32;
+                    sum_sq += (v as u64) * (v as u64);
+                    if !(5..=250).contains(&v) {
+                        clipped += 1;
+                    }
+                }
+            }
+            let n = ((y1 - y0) * (x1 - x0)) as f32;
+            let mean = sum as f32 / n;
+            let std = (sum_sq as f32 / n - mean * mean).max(0.0).sqrt();
+            let exposure = (-0.5 * ((mean - 128.0) / 51.0).powi(2)).exp();
+            let unclipped = 1.0 - clipped as f32 / n;
+            weights.push((exposure * (std + 1.0) * unclipped).max(1e-4));
+        }
+    }
+    weights
+}
+
+/// Neighbouring tile indices and blend factor for pixel `p` along an axis
+/// of `tiles` tiles, measured between tile centres.
+fn tile_coord(p: usize, tiles: usize) -> (usize, usize, f32) {
+    let t = ((p as f32 + 0.5) / FUSION_TILE as f32 - 0.5).clamp(0.0, (tiles - 1) as f32);
+    let t0 = t.floor() as usize;
+    let t1 = (t0 + 1).min(tiles - 1);
+    (t0, t1, t - t0 as f32)
+}
+
 // ============== x86_64 SSE2 Implementation ==============
 
 #[cfg(target_arch = "x86_64")]
@@ -361,13 +512,10 @@ fn rgba_to_grayscale_scalar_unrolled(rgba: &[u8], gray: &mut [u8], pixel_count:
 use rayon::prelude::*;
 
 /// Convert RGB to grayscale using parallel processing
-/// Processes rows in parallel for multi-core speedup
+/// Processes rows in parallel for multi-core speedup, on at most
+/// [`crate::DetectorConfig::max_threads`] threads
 pub fn rgb_to_grayscale_parallel(rgb: &[u8], width: usize, height: usize) -> Vec<u8> {
-    let pixel_count = width * height;
-    let mut gray = vec![0u8; pixel_count];
-
-    // Process rows in parallel
-    gray.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+    convert_rows(width, height, |y, row| {
         let row_start = y * width * 3;
         for (x, px) in row.iter_mut().enumerate().take(width) {
             let idx = row_start + x * 3;
@@ -377,18 +525,12 @@ pub fn rgb_to_grayscale_parallel(rgb: &[u8], width: usize, height: usize) -> Vec
             let lum = (COEF_R * r + COEF_G * g + COEF_B * b) >> 8;
             *px = lum.min(255) as u8;
         }
-    });
-
-    gray
+    })
 }
 
 /// Convert RGBA to grayscale using parallel processing
 pub fn rgba_to_grayscale_parallel(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
-    let pixel_count = width * height;
-    let mut gray = vec![0u8; pixel_count];
-
-    // Process rows in parallel
-    gray.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+    convert_rows(width, height, |y, row| {
         let row_start = y * width * 4;
         for (x, px) in row.iter_mut().enumerate().take(width) {
             let idx = row_start + x * 4;
@@ -398,6 +540,32 @@ pub fn rgba_to_grayscale_parallel(rgba: &[u8], width: usize, height: usize) -> V
             let lum = (COEF_R * r + COEF_G * g + COEF_B * b) >> 8;
             *px = lum.min(255) as u8;
         }
+    })
+}
+
+/// Fill a `width` x `height` grayscale buffer row by row, in parallel
+/// where the configured thread limit allows.
+fn convert_rows(
+    width: usize,
+    height: usize,
+    convert: impl Fn(usize, &mut [u8]) + Send + Sync,
+) -> Vec<u8> {
+    let pixel_count = width * height;
+    let mut gray = vec![0u8; pixel_count];
+    if width == 0 {
+        return gray;
+    }
+
+    crate::config::with_worker_pool(|parallel| {
+        if parallel {
+            gray.par_chunks_mut(width)
+                .enumerate()
+                .for_each(|(y, row)| convert(y, row));
+        } else {
+            gray.chunks_mut(width)
+                .enumerate()
+                .for_each(|(y, row)| convert(y, row));
+        }
     });
 
     gray
@@ -442,6 +610,56 @@ mod tests {
         let gray = rgba_to_grayscale(&rgba, 1, 1);
         assert_eq!(gray.len(), 1);
     }
+
+    #[test]
+    fn test_percentile_contrast_span_ignores_hot_pixels() {
+        // Washed-out 60..120 stripes with one hot and one dead pixel.
+        let (width, height) = (40, 10);
+        let mut gray: Vec<u8> = (0..width * height)
+            .map(|i| if (i % width) / 4 % 2 == 0 { 60 } else { 120 })
+            .collect();
+        gray[0] = 255;
+        gray[width * height - 1] = 0;
+        assert_eq!(
+            percentile_contrast_span(&gray, width, height, 1, 0, 100),
+            255
+        );
+        assert_eq!(percentile_contrast_span(&gray, width, height, 1, 5, 95), 60);
+        assert_eq!(percentile_contrast_span(&gray, width, 3, 1, 5, 95), 0);
+    }
+
+    #[test]
+    fn test_fuse_exposures_keeps_contrast_from_unclipped_frame() {
+        // 64x32 checkerboard of 4px cells; each exposure blows out one half.
+        let (width, height) = (64, 32);
+        let checker = |x: usize, y: usize| if (x / 4 + y / 4) % 2 == 0 { 40 } else { 200 };
+        let exposure = |blown: std::ops::Range<usize>| {
+            let mut frame = vec![0u8; width * height];
+            for y in 0..height {
+                for x in 0..width {
+                    frame[y * width + x] = if blown.contains(&x) {
+                        253 + (checker(x, y) > 100) as u8 * 2
+                    } else {
+                        checker(x, y)
+                    };
+                }
+            }
+            frame
+        };
+        let left_glare = exposure(0..32);
+        let right_glare = exposure(32..64);
+        let fused = fuse_exposures(&[&left_glare, &right_glare], width, height);
+
+        // Away from the seam between the glare halves, the checkerboard is
+        // fully restored.
+        for y in 0..height {
+            for x in (0..8).chain(56..64) {
+                let v = fused[y * width + x] as i32;
+                assert!((v - checker(x, y) as i32).abs() <= 3, "({x},{y}) = {v}");
+            }
+        }
+        assert_eq!(fuse_exposures(&[&left_glare], width, height), left_glare);
+    }
 }
 
 /// Convert RGB to grayscale using a pre-allocated buffer (no allocation)
@@ -6,7 +6,9 @@
 //! - Integral image buffer for adaptive binarization
 //! - Temporary vectors for detection pipeline
 //! - Finder pattern candidate storage
+//! - Cropped ROI buffers for retry re-thresholding
 
+use crate::detector::finder::FinderScratch;
 use crate::models::BitMatrix;
 
 /// A simple arena allocator that reuses a fixed-size buffer
@@ -20,6 +22,8 @@ pub struct BufferPool {
     binary_otsu: BitMatrix,
     // Pooled integral image buffer for adaptive binarization
     integral_buffer: Vec<u32>,
+    // Finder scan candidates and merge working buffers for the fast path
+    finder: FinderScratch,
 }
 
 impl BufferPool {
@@ -32,6 +36,7 @@ impl BufferPool {
             binary_adaptive: BitMatrix::default(),
             binary_otsu: BitMatrix::default(),
             integral_buffer: Vec::new(),
+            finder: FinderScratch::new(),
         }
     }
 
@@ -43,6 +48,7 @@ impl BufferPool {
             binary_adaptive: BitMatrix::default(),
             binary_otsu: BitMatrix::default(),
             integral_buffer: Vec::new(),
+            finder: FinderScratch::new(),
         }
     }
 
@@ -95,18 +101,7 @@ impl BufferPool {
         height: usize,
     ) -> (&mut [u8], &mut BitMatrix, &mut BitMatrix, &mut Vec<u32>) {
         let pixel_count = width * height;
-
-        // Prepare grayscale buffer
-        if pixel_count > self.grayscale_capacity {
-            let additional = pixel_count - self.grayscale_buffer.len();
-            self.grayscale_buffer.reserve(additional);
-            self.grayscale_capacity = self.grayscale_buffer.capacity();
-        }
-        if pixel_count <= self.grayscale_buffer.capacity() {
-            unsafe {
-                self.grayscale_buffer.set_len(pixel_count);
-            }
-        }
+        self.size_grayscale(pixel_count);
 
         // Prepare binarization buffers
         self.binary_adaptive.reset(width, height);
@@ -124,6 +119,39 @@ impl BufferPool {
         )
     }
 
+    /// Get the buffers the single-pass Otsu fast path needs: grayscale sized
+    /// to `width * height` bytes, the Otsu output and finder scan scratch.
+    ///
+    /// The grayscale contents are left as they are, so a later
+    /// [`Self::get_all_buffers`] call for the same size still sees the frame.
+    pub(crate) fn fast_path_buffers(
+        &mut self,
+        width: usize,
+        height: usize,
+    ) -> (&mut [u8], &mut BitMatrix, &mut FinderScratch) {
+        let pixel_count = width * height;
+        self.size_grayscale(pixel_count);
+        (
+            &mut self.grayscale_buffer[..pixel_count],
+            &mut self.binary_otsu,
+            &mut self.finder,
+        )
+    }
+
+    /// Grow the grayscale buffer if needed and set its length to `pixel_count`
+    fn size_grayscale(&mut self, pixel_count: usize) {
+        if pixel_count > self.grayscale_capacity {
+            let additional = pixel_count - self.grayscale_buffer.len();
+            self.grayscale_buffer.reserve(additional);
+            self.grayscale_capacity = self.grayscale_buffer.capacity();
+        }
+        if pixel_count <= self.grayscale_buffer.capacity() {
+            unsafe {
+                self.grayscale_buffer.set_len(pixel_count);
+            }
+        }
+    }
+
     /// Clear all buffers (resets lengths but keeps capacity)
     pub fn clear(&mut self) {
         self.grayscale_buffer.clear();
@@ -136,6 +164,45 @@ impl Default for BufferPool {
     }
 }
 
+/// Reusable buffers for re-thresholding a rectangular region of a frame.
+///
+/// Retry paths crop the candidate bounding box into `gray`, binarize it into
+/// `binary` and run detection in ROI coordinates, so fallback passes cost
+/// O(ROI) rather than O(frame).
+#[derive(Default)]
+pub struct RoiScratch {
+    gray: Vec<u8>,
+    binary: BitMatrix,
+    integral: Vec<u32>,
+}
+
+impl RoiScratch {
+    /// Create empty scratch buffers; they grow to the largest ROI seen
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy `roi = (x0, y0, x1, y1)` of `gray` into the pooled crop buffer and
+    /// return it with the pooled binary output (reset to the ROI size) and
+    /// integral buffer.
+    pub fn crop(
+        &mut self,
+        gray: &[u8],
+        width: usize,
+        roi: (usize, usize, usize, usize),
+    ) -> (&mut [u8], &mut BitMatrix, &mut Vec<u32>) {
+        let (x0, y0, x1, y1) = roi;
+        let roi_w = x1 - x0;
+        self.gray.clear();
+        for y in y0..y1 {
+            let row = y * width;
+            self.gray.extend_from_slice(&gray[row + x0..row + x1]);
+        }
+        self.binary.reset(roi_w, y1 - y0);
+        (&mut self.gray, &mut self.binary, &mut self.integral)
+    }
+}
+
 /// Statistics for monitoring allocation patterns
 #[derive(Debug, Default)]
 pub struct AllocationStats {
@@ -188,4 +255,18 @@ mod tests {
         assert_eq!(buf.len(), 500);
         assert!(pool.grayscale_capacity() >= 500);
     }
+
+    #[test]
+    fn test_roi_scratch_crop() {
+        let gray: Vec<u8> = (0..48).collect(); // 8x6 image
+        let mut scratch = RoiScratch::new();
+        let (crop, binary, _) = scratch.crop(&gray, 8, (2, 1, 5, 4));
+        assert_eq!(crop, &[10, 11, 12, 18, 19, 20, 26, 27, 28]);
+        assert_eq!((binary.width(), binary.height()), (3, 3));
+
+        // A smaller ROI reuses the buffers
+        let (crop, binary, _) = scratch.crop(&gray, 8, (0, 0, 2, 1));
+        assert_eq!(crop, &[0, 1]);
+        assert_eq!((binary.width(), binary.height()), (2, 1));
+    }
 }
@@ -0,0 +1,107 @@
+//! Allocation regression tests for the pooled fast path
+//!
+//! A counting global allocator records heap allocations made on the current
+//! thread, so concurrently running tests do not disturb each other's counts.
+
+use rust_qr::detect_fast_with_pool;
+use rust_qr::utils::memory_pool::BufferPool;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 480;
+
+fn rgb_frame(dark: impl Fn(usize, usize) -> bool) -> Vec<u8> {
+    let mut rgb = vec![230u8; WIDTH * HEIGHT * 3];
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if dark(x, y) {
+                rgb[(y * WIDTH + x) * 3..][..3].fill(20);
+            }
+        }
+    }
+    rgb
+}
+
+/// A lone 7x7-module finder pattern with 8px modules at (100, 100)
+fn single_finder(x: usize, y: usize) -> bool {
+    if !(100..156).contains(&x) || !(100..156).contains(&y) {
+        return false;
+    }
+    let (mx, my) = ((x - 100) / 8, (y - 100) / 8);
+    let ring = mx.min(my).min(6 - mx).min(6 - my);
+    ring != 1
+}
+
+#[test]
+fn fast_path_does_not_allocate_on_warm_pool() {
+    let frames = [
+        ("blank", rgb_frame(|_, _| false)),
+        ("stripes", rgb_frame(|x, _| (x / 4) % 2 == 0)),
+        ("checker", rgb_frame(|x, y| (x / 16 + y / 16) % 2 == 0)),
+        ("single finder", rgb_frame(single_finder)),
+    ];
+
+    let mut pool = BufferPool::new();
+    for (_, frame) in &frames {
+        assert!(detect_fast_with_pool(frame, WIDTH, HEIGHT, &mut pool).is_empty());
+    }
+
+    for (name, frame) in &frames {
+        let allocations = allocations_during(|| {
+            let codes = detect_fast_with_pool(frame, WIDTH, HEIGHT, &mut pool);
+            assert!(codes.is_empty());
+        });
+        assert_eq!(allocations, 0, "{name} frame allocated {allocations} times");
+    }
+}
+
+#[test]
+fn cold_pool_allocates_only_while_growing() {
+    let frame = rgb_frame(single_finder);
+    let mut pool = BufferPool::with_capacity(0);
+    let cold = allocations_during(|| {
+        detect_fast_with_pool(&frame, WIDTH, HEIGHT, &mut pool);
+    });
+    assert!(cold > 0);
+    let warm = allocations_during(|| {
+        detect_fast_with_pool(&frame, WIDTH, HEIGHT, &mut pool);
+    });
+    assert_eq!(warm, 0);
+}
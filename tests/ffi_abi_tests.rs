@@ -0,0 +1,171 @@
+//! ABI stability tests for the `ffi` feature
+//!
+//! The v1 types and symbols are a promise to binaries already linked against
+//! the library: their layouts are pinned here, the checked-in header is held
+//! to the values the library exports, and results are read back through the
+//! raw C representation the way a C caller would.
+
+use rust_qr::decoder::qr_decoder::SegmentMode;
+use rust_qr::ffi::*;
+use rust_qr::tools::encode::encode;
+use rust_qr::{ECLevel, MaskPattern};
+use std::ffi::CStr;
+use std::mem::{align_of, offset_of, size_of};
+use std::ptr;
+
+const HEADER: &str = include_str!("../include/rustqr.h");
+
+/// `payload` as a version 2-H symbol, 6 px modules, 4-module quiet zone,
+/// in RGB rows padded by `pad` bytes. Returns the pixels, side and stride.
+fn rgb_symbol(payload: &str, pad: usize) -> (Vec<u8>, usize, usize) {
+    let symbol = encode(
+        payload,
+        SegmentMode::Byte,
+        2,
+        ECLevel::H,
+        MaskPattern::Pattern1,
+    )
+    .unwrap();
+    let (pitch, quiet) = (6, 4);
+    let side = (symbol.width() + 2 * quiet) * pitch;
+    let stride = side * 3 + pad;
+    let mut rgb = vec![255u8; stride * side];
+    for y in 0..side {
+        for x in 0..side {
+            let (mx, my) = (x / pitch, y / pitch);
+            let inside = (quiet..symbol.width() + quiet).contains(&mx)
+                && (quiet..symbol.width() + quiet).contains(&my);
+            if inside && symbol.get(mx - quiet, my - quiet) {
+                rgb[y * stride + x * 3..][..3].fill(0);
+            }
+        }
+    }
+    (rgb, side, stride)
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn v1_layouts_are_pinned() {
+    assert_eq!(
+        (size_of::<RustqrV1Point>(), align_of::<RustqrV1Point>()),
+        (8, 4)
+    );
+
+    assert_eq!(
+        (size_of::<RustqrV1Code>(), align_of::<RustqrV1Code>()),
+        (72, 8)
+    );
+    assert_eq!(offset_of!(RustqrV1Code, data), 0);
+    assert_eq!(offset_of!(RustqrV1Code, data_len), 8);
+    assert_eq!(offset_of!(RustqrV1Code, content), 16);
+    assert_eq!(offset_of!(RustqrV1Code, content_len), 24);
+    assert_eq!(offset_of!(RustqrV1Code, corners), 32);
+    assert_eq!(offset_of!(RustqrV1Code, confidence), 64);
+    assert_eq!(offset_of!(RustqrV1Code, version), 68);
+    assert_eq!(offset_of!(RustqrV1Code, ec_level), 69);
+    assert_eq!(offset_of!(RustqrV1Code, mask), 70);
+
+    assert_eq!(
+        (size_of::<RustqrV1Result>(), align_of::<RustqrV1Result>()),
+        (16, 8)
+    );
+    assert_eq!(offset_of!(RustqrV1Result, codes), 0);
+    assert_eq!(offset_of!(RustqrV1Result, len), 8);
+}
+
+#[test]
+fn header_matches_exported_values() {
+    let defines = [
+        ("RUSTQR_ABI_VERSION", RUSTQR_ABI_VERSION as i64),
+        ("RUSTQR_V1_OK", RUSTQR_V1_OK as i64),
+        ("RUSTQR_V1_NULL_POINTER", RUSTQR_V1_NULL_POINTER as i64),
+        ("RUSTQR_V1_INVALID_IMAGE", RUSTQR_V1_INVALID_IMAGE as i64),
+        ("RUSTQR_V1_INTERNAL_ERROR", RUSTQR_V1_INTERNAL_ERROR as i64),
+        ("RUSTQR_V1_FORMAT_LUMA8", RUSTQR_V1_FORMAT_LUMA8 as i64),
+        ("RUSTQR_V1_FORMAT_RGB8", RUSTQR_V1_FORMAT_RGB8 as i64),
+        ("RUSTQR_V1_FORMAT_RGBA8", RUSTQR_V1_FORMAT_RGBA8 as i64),
+    ];
+    for (name, value) in defines {
+        let line = format!("#define {name} {value}\n");
+        assert!(HEADER.contains(&line), "header lacks `{}`", line.trim());
+    }
+    for declaration in [
+        "uint32_t rustqr_v1_abi_version(void);",
+        "int32_t rustqr_v1_detect(const uint8_t *pixels,",
+        "void rustqr_v1_result_free(struct RustqrV1Result *result);",
+    ] {
+        assert!(HEADER.contains(declaration), "header lacks `{declaration}`");
+    }
+    assert_eq!(rustqr_v1_abi_version(), RUSTQR_ABI_VERSION);
+}
+
+#[test]
+fn detect_round_trips_through_the_c_layer() {
+    let payload = "rustqr v1 abi";
+    // Rows padded past the pixels, as C frame buffers often are.
+    let (rgb, side, stride) = rgb_symbol(payload, 5);
+
+    let mut result: *mut RustqrV1Result = ptr::null_mut();
+    let status = unsafe {
+        rustqr_v1_detect(
+            rgb.as_ptr(),
+            side,
+            side,
+            stride,
+            RUSTQR_V1_FORMAT_RGB8,
+            &mut result,
+        )
+    };
+    assert_eq!(status, RUSTQR_V1_OK);
+    assert!(!result.is_null());
+
+    let (codes, len) = unsafe { ((*result).codes, (*result).len) };
+    assert_eq!(len, 1);
+    let code = unsafe { &*codes };
+    let data = unsafe { std::slice::from_raw_parts(code.data, code.data_len) };
+    assert_eq!(data, payload.as_bytes());
+    let content = unsafe { CStr::from_ptr(code.content) };
+    assert_eq!(content.to_bytes(), payload.as_bytes());
+    assert_eq!(code.content_len, payload.len());
+    assert_eq!((code.version, code.ec_level, code.mask), (2, 3, 1));
+    assert!((0.0..=1.0).contains(&code.confidence));
+    let xs = code.corners.map(|p| p.x);
+    assert!(xs.iter().all(|&x| (0.0..side as f32).contains(&x)));
+
+    unsafe { rustqr_v1_result_free(result) };
+    unsafe { rustqr_v1_result_free(ptr::null_mut()) };
+}
+
+#[test]
+fn detect_reports_bad_arguments_as_status() {
+    let rgb = vec![255u8; 30 * 20 * 3];
+    let mut result: *mut RustqrV1Result = ptr::dangling_mut();
+    let detect = |pixels: *const u8, stride: usize, format: u32, out| unsafe {
+        rustqr_v1_detect(pixels, 30, 20, stride, format, out)
+    };
+
+    assert_eq!(
+        detect(ptr::null(), 90, RUSTQR_V1_FORMAT_RGB8, &mut result),
+        RUSTQR_V1_NULL_POINTER
+    );
+    assert!(result.is_null());
+    assert_eq!(
+        detect(rgb.as_ptr(), 90, RUSTQR_V1_FORMAT_RGB8, ptr::null_mut()),
+        RUSTQR_V1_NULL_POINTER
+    );
+    assert_eq!(
+        detect(rgb.as_ptr(), 60, RUSTQR_V1_FORMAT_RGB8, &mut result),
+        RUSTQR_V1_INVALID_IMAGE
+    );
+    assert_eq!(
+        detect(rgb.as_ptr(), 90, 7, &mut result),
+        RUSTQR_V1_INVALID_IMAGE
+    );
+
+    assert_eq!(
+        detect(rgb.as_ptr(), 90, RUSTQR_V1_FORMAT_RGB8, &mut result),
+        RUSTQR_V1_OK
+    );
+    assert_eq!(unsafe { (*result).len }, 0);
+    unsafe { rustqr_v1_result_free(result) };
+}